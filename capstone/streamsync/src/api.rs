@@ -0,0 +1,212 @@
+//! Optional HTTP control API for a running flow (feature `http-api`).
+//!
+//! - `GET /processors` — name, type, state, properties (sensitive ones redacted)
+//!   and counters of every processor
+//! - `GET /connections` — queue depth, backpressure status and the most recent
+//!   depth samples
+//! - `POST /processors/{name}/stop` and `POST /processors/{name}/start`
+//! - `GET /bulletins` — recent bulletins, newest first
+//! - `GET /status` — the flow graph: each processor with its incoming and
+//!   outgoing connections, and each connection's endpoints and queue depth
+
+use crate::controller::{FlowController, FlowState};
+use crate::logging::LogLevel;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+pub fn router(state: Arc<FlowState>) -> Router {
+    Router::new()
+        .route("/processors", get(list_processors))
+        .route("/processors/{name}/stop", post(stop_processor))
+        .route("/processors/{name}/start", post(start_processor))
+        .route("/connections", get(list_connections))
+        .route("/bulletins", get(list_bulletins))
+        .route("/status", get(flow_status))
+        .with_state(state)
+}
+
+async fn list_processors(State(state): State<Arc<FlowState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!(state.metrics().processors))
+}
+
+async fn list_connections(State(state): State<Arc<FlowState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!(state.metrics().connections))
+}
+
+async fn list_bulletins(State(state): State<Arc<FlowState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!(state.bulletins().recent()))
+}
+
+async fn flow_status(State(state): State<Arc<FlowState>>) -> Json<serde_json::Value> {
+    Json(serde_json::json!(state.status().await))
+}
+
+async fn stop_processor(
+    State(state): State<Arc<FlowState>>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.stop_processor(&name) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn start_processor(
+    State(state): State<Arc<FlowState>>,
+    Path(name): Path<String>,
+) -> StatusCode {
+    if state.start_processor(&name) {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+impl FlowController {
+    /// Serves the control API on `addr` until the controller is stopped, and
+    /// returns the bound address (useful when binding port 0).
+    pub async fn start_api(&mut self, addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+        let (app, logger) = (router(self.state()), self.logger.clone());
+        self.api = Some(tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                logger.log(
+                    LogLevel::Error,
+                    "control-api",
+                    &format!("control API stopped: {}", e),
+                );
+            }
+        }));
+        Ok(local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+    use crate::processor::{FileProcessor, Processor, ProcessorError};
+    use crate::processor_context::ProcessorContext;
+    use crate::relationship::{self, Relationship};
+    use crate::session::ProcessSession;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    struct Generate;
+
+    impl Processor for Generate {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let flowfile = session.create();
+            session.transfer(flowfile, relationship::SUCCESS);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Generate"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    async fn request(addr: SocketAddr, method: &str, path: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            method, path
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = response
+            .split("\r\n\r\n")
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        (status, body)
+    }
+
+    async fn queue_depth(addr: SocketAddr) -> u64 {
+        let (_, body) = request(addr, "GET", "/connections").await;
+        let connections: serde_json::Value = serde_json::from_str(&body).unwrap();
+        connections[0]["queue_depth"].as_u64().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_start_processor_over_http() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("generate", Generate).run_schedule(Duration::from_millis(1)),
+        );
+        flow.add_processor(
+            ProcessorNode::new("sink", FileProcessor::new())
+                .with_property("label", "archive")
+                .with_sensitive_property("password", "hunter2")
+                .auto_terminate("success"),
+        );
+        flow.add_connection(
+            ConnectionDefinition::new("to-sink", "generate", "success", "sink")
+                .with_backpressure(50),
+        );
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let addr = controller.start_api("127.0.0.1:0").await.unwrap();
+
+        let (status, _) = request(addr, "POST", "/processors/sink/stop").await;
+        assert_eq!(status, 204);
+        let (_, body) = request(addr, "GET", "/processors").await;
+        let processors: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(processors[1]["name"], "sink");
+        assert_eq!(processors[1]["state"], "stopped");
+        assert_eq!(processors[1]["properties"]["label"], "archive");
+        assert_eq!(processors[1]["properties"]["password"], "********");
+        assert!(!body.contains("hunter2"));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while queue_depth(addr).await < 50 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("queue never backed up");
+        let (_, body) = request(addr, "GET", "/connections").await;
+        assert!(body.contains("\"backpressured\":true"));
+        let connections: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert!(connections[0]["history"].is_array());
+
+        let (status, _) = request(addr, "POST", "/processors/sink/start").await;
+        assert_eq!(status, 204);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while queue_depth(addr).await >= 50 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("queue never drained");
+
+        let (status, _) = request(addr, "POST", "/processors/missing/stop").await;
+        assert_eq!(status, 404);
+        let (status, body) = request(addr, "GET", "/bulletins").await;
+        assert_eq!((status, body.as_str()), (200, "[]"));
+        let (_, body) = request(addr, "GET", "/status").await;
+        let flow: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(flow["processors"][0]["outgoing"][0], "to-sink");
+        assert_eq!(flow["connections"][0]["destination"], "sink");
+        controller.stop().await;
+    }
+}
@@ -0,0 +1,302 @@
+//! Throughput harness for `Connection` implementations. Each case pushes a
+//! fixed number of generated FlowFiles through a fresh queue from a single
+//! producer while one or more consumers drain it, and reports FlowFiles and
+//! bytes per second. `streamsync bench` runs the default cases; tests run the
+//! same code with tiny counts.
+
+use crate::clock::{Clock, SystemClock};
+use crate::connection::funnel::FunnelConnection;
+use crate::connection::{Connection, MemoryConnection};
+use crate::flowfile::FlowFile;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+// FlowFiles a consumer takes per `receive_batch` call.
+const CONSUMER_BATCH: usize = 64;
+
+pub const DEFAULT_FLOWFILES: usize = 100_000;
+pub const DEFAULT_CONTENT_SIZE: usize = 1024;
+pub const DEFAULT_CONSUMERS: &[usize] = &[1, 4];
+
+/// The end of a queue the producer sends to and the end the consumers
+/// receive from. Most queues are both; a funnel is sent to through an inlet.
+pub type QueueEnds = (Arc<dyn Connection>, Arc<dyn Connection>);
+
+/// A named way of building the queue under test.
+pub struct QueueCase {
+    pub name: &'static str,
+    pub build: fn() -> QueueEnds,
+}
+
+fn both_ends(queue: impl Connection + 'static) -> QueueEnds {
+    let queue: Arc<dyn Connection> = Arc::new(queue);
+    (queue.clone(), queue)
+}
+
+fn through_inlet(funnel: FunnelConnection) -> QueueEnds {
+    (Arc::new(funnel.inlet("bench")), Arc::new(funnel))
+}
+
+/// The queues benchmarked by default.
+pub fn default_cases() -> Vec<QueueCase> {
+    vec![
+        QueueCase {
+            name: "memory",
+            build: || both_ends(MemoryConnection::new()),
+        },
+        QueueCase {
+            name: "memory (backpressure 1000)",
+            build: || both_ends(MemoryConnection::with_backpressure(1000)),
+        },
+        QueueCase {
+            name: "funnel",
+            build: || through_inlet(FunnelConnection::new()),
+        },
+        QueueCase {
+            name: "funnel (backpressure 1000)",
+            build: || through_inlet(FunnelConnection::with_backpressure(1000)),
+        },
+    ]
+}
+
+/// Builds FlowFiles with `content_size` bytes of content and a couple of
+/// attributes, so queues that copy or serialize FlowFiles pay for both.
+#[derive(Debug, Clone, Copy)]
+pub struct FlowFileGenerator {
+    pub content_size: usize,
+}
+
+impl FlowFileGenerator {
+    pub fn new(content_size: usize) -> Self {
+        Self { content_size }
+    }
+
+    pub fn generate(&self, sequence: usize, now: SystemTime) -> FlowFile {
+        let content: Vec<u8> = (0..self.content_size)
+            .map(|i| (sequence + i) as u8)
+            .collect();
+        let mut flowfile = FlowFile::created_by("bench", now);
+        flowfile.set_content(content);
+        flowfile.put_attribute("filename", &format!("bench-{}", sequence));
+        flowfile.set_attribute("sequence", sequence as i64);
+        flowfile
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BenchConfig {
+    pub flowfiles: usize,
+    pub content_size: usize,
+    pub consumers: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    pub flowfiles: usize,
+    pub bytes: usize,
+    pub elapsed: Duration,
+}
+
+impl Measurement {
+    pub fn flowfiles_per_sec(&self) -> f64 {
+        self.flowfiles as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// One line of the results table.
+#[derive(Debug, Clone)]
+pub struct BenchRow {
+    pub queue: &'static str,
+    pub consumers: usize,
+    pub content_size: usize,
+    pub measurement: Measurement,
+}
+
+/// Sends `config.flowfiles` FlowFiles through `queue` and times how long it
+/// takes until the consumers have received all of them. A full queue makes
+/// the producer wait, as the scheduler would. A consumer stops early if the
+/// queue fails to receive, e.g. because it was closed, so the measurement
+/// then counts fewer FlowFiles than were sent.
+pub async fn measure(queue: QueueEnds, config: BenchConfig) -> Measurement {
+    let (producer, consumer) = queue;
+    let generator = FlowFileGenerator::new(config.content_size);
+    let now = SystemClock.now();
+    let flowfiles: Vec<FlowFile> = (0..config.flowfiles)
+        .map(|i| generator.generate(i, now))
+        .collect();
+    let received = Arc::new(AtomicUsize::new(0));
+    let bytes = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+
+    let consumers: Vec<_> = (0..config.consumers.max(1))
+        .map(|_| {
+            let (consumer, received, bytes) = (consumer.clone(), received.clone(), bytes.clone());
+            let total = config.flowfiles;
+            tokio::spawn(async move {
+                while received.load(Ordering::SeqCst) < total {
+                    let Ok(batch) = consumer.receive_batch(CONSUMER_BATCH).await else {
+                        break;
+                    };
+                    if batch.is_empty() {
+                        tokio::task::yield_now().await;
+                        continue;
+                    }
+                    bytes.fetch_add(batch.iter().map(FlowFile::size).sum(), Ordering::SeqCst);
+                    received.fetch_add(batch.len(), Ordering::SeqCst);
+                }
+            })
+        })
+        .collect();
+
+    for flowfile in flowfiles {
+        while producer.is_full() {
+            tokio::task::yield_now().await;
+        }
+        producer
+            .send(flowfile)
+            .await
+            .expect("benchmark queue closed");
+    }
+    for consumer in consumers {
+        consumer.await.expect("benchmark consumer panicked");
+    }
+
+    Measurement {
+        flowfiles: received.load(Ordering::SeqCst),
+        bytes: bytes.load(Ordering::SeqCst),
+        elapsed: started.elapsed(),
+    }
+}
+
+/// Measures every case with every consumer count, each on a fresh queue.
+pub async fn run(
+    cases: &[QueueCase],
+    flowfiles: usize,
+    content_size: usize,
+    consumers: &[usize],
+) -> Vec<BenchRow> {
+    let mut rows = Vec::new();
+    for case in cases {
+        for &count in consumers {
+            let config = BenchConfig {
+                flowfiles,
+                content_size,
+                consumers: count,
+            };
+            rows.push(BenchRow {
+                queue: case.name,
+                consumers: count,
+                content_size,
+                measurement: measure((case.build)(), config).await,
+            });
+        }
+    }
+    rows
+}
+
+pub fn render_table(rows: &[BenchRow]) -> String {
+    let width = rows
+        .iter()
+        .map(|row| row.queue.len())
+        .max()
+        .unwrap_or(0)
+        .max("queue".len());
+    let mut table = format!(
+        "{:<width$}  {:>9}  {:>10}  {:>10}  {:>14}  {:>10}\n",
+        "queue", "consumers", "size", "flowfiles", "flowfiles/s", "MiB/s"
+    );
+    for row in rows {
+        let _ = writeln!(
+            table,
+            "{:<width$}  {:>9}  {:>10}  {:>10}  {:>14.0}  {:>10.1}",
+            row.queue,
+            row.consumers,
+            row.content_size,
+            row.measurement.flowfiles,
+            row.measurement.flowfiles_per_sec(),
+            row.measurement.bytes_per_sec() / (1024.0 * 1024.0)
+        );
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generator_is_deterministic() {
+        let generator = FlowFileGenerator::new(300);
+        let now = SystemClock.now();
+        let flowfile = generator.generate(7, now);
+        assert_eq!(flowfile.size(), 300);
+        assert_eq!(flowfile.content().unwrap()[..3], [7, 8, 9]);
+        assert_eq!(
+            flowfile.content().unwrap(),
+            generator.generate(7, now).content().unwrap()
+        );
+        assert_eq!(
+            flowfile.get_attribute("filename").unwrap().to_string(),
+            "bench-7"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_every_case_delivers_everything() {
+        let rows = run(&default_cases(), 500, 32, DEFAULT_CONSUMERS).await;
+        assert_eq!(rows.len(), default_cases().len() * DEFAULT_CONSUMERS.len());
+        for row in &rows {
+            assert_eq!(row.measurement.flowfiles, 500, "{}", row.queue);
+            assert_eq!(row.measurement.bytes, 500 * 32, "{}", row.queue);
+            assert!(row.measurement.flowfiles_per_sec() > 0.0);
+        }
+
+        let table = render_table(&rows);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), rows.len() + 1);
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            [
+                "queue",
+                "consumers",
+                "size",
+                "flowfiles",
+                "flowfiles/s",
+                "MiB/s"
+            ]
+        );
+        for (line, row) in lines[1..].iter().zip(&rows) {
+            // Queue names may hold spaces; the five numeric columns are last.
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let (name, numbers) = columns.split_at(columns.len() - 5);
+            assert_eq!(name.join(" "), row.queue);
+            assert_eq!(numbers[0].parse(), Ok(row.consumers));
+            assert_eq!(numbers[1].parse(), Ok(32));
+            assert_eq!(numbers[2].parse(), Ok(500));
+            assert!(numbers[3].parse::<f64>().unwrap() > 0.0, "{}", line);
+            assert!(numbers[4].parse::<f64>().is_ok(), "{}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consumers_stop_on_a_closed_queue() {
+        let closed = MemoryConnection::new();
+        closed.close();
+        let queue: QueueEnds = (Arc::new(MemoryConnection::new()), Arc::new(closed));
+        let config = BenchConfig {
+            flowfiles: 10,
+            content_size: 1,
+            consumers: 2,
+        };
+        let measurement = tokio::time::timeout(Duration::from_secs(5), measure(queue, config))
+            .await
+            .expect("consumers kept polling a closed queue");
+        assert_eq!(measurement.flowfiles, 0);
+    }
+}
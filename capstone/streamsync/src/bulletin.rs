@@ -0,0 +1,113 @@
+use crate::logging::LogLevel;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub const DEFAULT_BULLETIN_CAPACITY: usize = 100;
+
+/// A message raised by the framework about a processor, kept so operators
+/// can see recent failures without going through the logs.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bulletin {
+    pub processor: String,
+    pub timestamp: SystemTime,
+    pub severity: LogLevel,
+    pub message: String,
+}
+
+/// Bounded ring buffer of the most recent bulletins. The framework posts
+/// here about failed triggers; processors post through
+/// `ProcessSession::report_bulletin`. A capacity of 0 keeps none.
+pub struct BulletinRepository {
+    capacity: usize,
+    entries: Mutex<VecDeque<Bulletin>>,
+}
+
+impl BulletinRepository {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records a bulletin, evicting the oldest once the buffer is full.
+    pub fn add(&self, bulletin: Bulletin) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        while entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(bulletin);
+    }
+
+    /// All retained bulletins, newest first.
+    pub fn recent(&self) -> Vec<Bulletin> {
+        self.entries.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Retained bulletins raised for `processor`, newest first.
+    pub fn for_processor(&self, processor: &str) -> Vec<Bulletin> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|bulletin| bulletin.processor == processor)
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for BulletinRepository {
+    fn default() -> Self {
+        Self::new(DEFAULT_BULLETIN_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulletin(processor: &str, message: &str) -> Bulletin {
+        Bulletin {
+            processor: processor.to_string(),
+            timestamp: SystemTime::now(),
+            severity: LogLevel::Warn,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_full_buffer_drops_oldest() {
+        let repository = BulletinRepository::new(2);
+        repository.add(bulletin("a", "first"));
+        repository.add(bulletin("b", "second"));
+        repository.add(bulletin("a", "third"));
+
+        let messages: Vec<String> = repository.recent().into_iter().map(|b| b.message).collect();
+        assert_eq!(messages, vec!["third", "second"]);
+        assert_eq!(repository.len(), 2);
+        assert_eq!(repository.for_processor("a").len(), 1);
+    }
+
+    #[test]
+    fn test_zero_capacity_keeps_nothing() {
+        let repository = BulletinRepository::new(0);
+        repository.add(bulletin("a", "first"));
+        repository.add(bulletin("a", "second"));
+        assert_eq!(repository.len(), 0);
+        assert!(repository.recent().is_empty());
+    }
+}
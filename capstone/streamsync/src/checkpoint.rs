@@ -0,0 +1,514 @@
+//! On-disk snapshot of a flow's queues and processor state.
+//!
+//! A checkpoint directory holds `manifest.json`, describing the flow it was
+//! taken from, plus one file of framed FlowFiles (see `flowfile::codec`) per
+//! connection under `queues/` and one JSON state map per processor under
+//! `state/`. The manifest also carries each processor's trigger, failure and
+//! transfer counters. See `FlowController::checkpoint` and
+//! `FlowController::restore`.
+
+use crate::flow::FlowDefinition;
+use crate::flowfile::codec::CodecError;
+use crate::flowfile::FlowFile;
+use crate::metrics::CounterSnapshot;
+use crate::state::StateError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Cursor, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Version written to, and required of, every manifest.
+pub const MANIFEST_VERSION: u32 = 1;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Codec(CodecError),
+    State(StateError),
+    /// The manifest could not be parsed.
+    InvalidManifest(String),
+    UnsupportedVersion(u32),
+    /// The checkpoint was taken from a flow with different processors or
+    /// connections; each entry describes one difference.
+    TopologyMismatch(Vec<String>),
+    /// The flow has never been started, so it has no queues to save.
+    NotStarted,
+    /// Restoring is only possible before the flow starts.
+    Running,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "checkpoint I/O error: {}", e),
+            CheckpointError::Codec(e) => write!(f, "checkpointed queue is corrupt: {}", e),
+            CheckpointError::State(e) => write!(f, "{}", e),
+            CheckpointError::InvalidManifest(reason) => {
+                write!(f, "invalid checkpoint manifest: {}", reason)
+            }
+            CheckpointError::UnsupportedVersion(version) => write!(
+                f,
+                "checkpoint manifest version {} is not supported (expected {})",
+                version, MANIFEST_VERSION
+            ),
+            CheckpointError::TopologyMismatch(differences) => {
+                write!(
+                    f,
+                    "checkpoint was taken from a different flow: {}",
+                    differences.join("; ")
+                )
+            }
+            CheckpointError::NotStarted => {
+                write!(
+                    f,
+                    "the flow has not been started, so there is nothing to checkpoint"
+                )
+            }
+            CheckpointError::Running => write!(
+                f,
+                "a checkpoint can only be restored before the flow starts"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+impl From<CodecError> for CheckpointError {
+    fn from(e: CodecError) -> Self {
+        CheckpointError::Codec(e)
+    }
+}
+
+impl From<StateError> for CheckpointError {
+    fn from(e: StateError) -> Self {
+        CheckpointError::State(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub created_at: SystemTime,
+    pub processors: Vec<ProcessorEntry>,
+    pub connections: Vec<ConnectionEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessorEntry {
+    pub name: String,
+    pub processor_type: String,
+    /// Path of its state map, relative to the checkpoint directory.
+    pub state_file: String,
+    #[serde(default)]
+    pub counters: CounterSnapshot,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionEntry {
+    pub name: String,
+    pub source: String,
+    pub relationship: String,
+    pub destination: String,
+    /// Path of its queued FlowFiles, relative to the checkpoint directory.
+    pub queue_file: String,
+    pub queued: usize,
+}
+
+impl Manifest {
+    /// Describes `flow` as of `now`, naming a queue and state file for each
+    /// of its connections and processors.
+    pub fn for_flow(flow: &FlowDefinition, now: SystemTime) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            created_at: now,
+            processors: flow
+                .processors
+                .iter()
+                .enumerate()
+                .map(|(index, node)| ProcessorEntry {
+                    name: node.name().to_string(),
+                    processor_type: node.processor.get_name().to_string(),
+                    state_file: format!("state/{}.json", index),
+                    counters: CounterSnapshot::default(),
+                })
+                .collect(),
+            connections: flow
+                .connections
+                .iter()
+                .enumerate()
+                .map(|(index, definition)| ConnectionEntry {
+                    name: definition.name.clone(),
+                    source: definition.source.clone(),
+                    relationship: definition.relationship.clone(),
+                    destination: definition.destination.clone(),
+                    queue_file: format!("queues/{}.ssff", index),
+                    queued: 0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Reads the manifest in `dir`, rejecting any other version.
+    pub fn read(dir: &Path) -> Result<Self, CheckpointError> {
+        let bytes = fs::read(dir.join(MANIFEST_FILE))?;
+        let value: serde_json::Value = serde_json::from_slice(&bytes)
+            .map_err(|e| CheckpointError::InvalidManifest(e.to_string()))?;
+        let version = value
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .ok_or_else(|| CheckpointError::InvalidManifest("missing version".to_string()))?;
+        if version != u64::from(MANIFEST_VERSION) {
+            return Err(CheckpointError::UnsupportedVersion(version as u32));
+        }
+        serde_json::from_value(value).map_err(|e| CheckpointError::InvalidManifest(e.to_string()))
+    }
+
+    /// Writes the manifest into `dir`. It goes to a temporary file that is
+    /// then renamed into place, so a checkpoint is only complete once its
+    /// manifest exists.
+    pub fn write(&self, dir: &Path) -> Result<(), CheckpointError> {
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| CheckpointError::InvalidManifest(e.to_string()))?;
+        let temporary = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&temporary, json)?;
+        fs::rename(temporary, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    /// How `flow` differs from the flow this manifest was taken from: the
+    /// processors (by name and type) and connections (by name and ends) must
+    /// be the same.
+    pub fn differences(&self, flow: &FlowDefinition) -> Vec<String> {
+        let mut differences = Vec::new();
+        for node in &flow.processors {
+            match self
+                .processors
+                .iter()
+                .find(|entry| entry.name == node.name())
+            {
+                None => differences.push(format!(
+                    "processor '{}' is not in the checkpoint",
+                    node.name()
+                )),
+                Some(entry) if entry.processor_type != node.processor.get_name() => differences
+                    .push(format!(
+                        "processor '{}' is a {} but was a {}",
+                        node.name(),
+                        node.processor.get_name(),
+                        entry.processor_type
+                    )),
+                Some(_) => {}
+            }
+        }
+        for entry in &self.processors {
+            if flow.processor(&entry.name).is_none() {
+                differences.push(format!(
+                    "checkpointed processor '{}' is not in the flow",
+                    entry.name
+                ));
+            }
+        }
+        for definition in &flow.connections {
+            match self
+                .connections
+                .iter()
+                .find(|entry| entry.name == definition.name)
+            {
+                None => differences.push(format!(
+                    "connection '{}' is not in the checkpoint",
+                    definition.name
+                )),
+                Some(entry)
+                    if (&entry.source, &entry.relationship, &entry.destination)
+                        != (
+                            &definition.source,
+                            &definition.relationship,
+                            &definition.destination,
+                        ) =>
+                {
+                    differences.push(format!(
+                        "connection '{}' runs {}.{} -> {} but ran {}.{} -> {}",
+                        definition.name,
+                        definition.source,
+                        definition.relationship,
+                        definition.destination,
+                        entry.source,
+                        entry.relationship,
+                        entry.destination
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+        for entry in &self.connections {
+            if !flow
+                .connections
+                .iter()
+                .any(|definition| definition.name == entry.name)
+            {
+                differences.push(format!(
+                    "checkpointed connection '{}' is not in the flow",
+                    entry.name
+                ));
+            }
+        }
+        differences
+    }
+}
+
+/// Writes `flowfiles` to `path` as consecutive frames.
+pub fn write_queue(path: &Path, flowfiles: &[FlowFile]) -> Result<(), CheckpointError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+    for flowfile in flowfiles {
+        flowfile.encode_into(&mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads back every frame written by `write_queue`.
+pub fn read_queue(path: &Path) -> Result<Vec<FlowFile>, CheckpointError> {
+    let bytes = fs::read(path)?;
+    let mut reader = Cursor::new(bytes.as_slice());
+    let mut flowfiles = Vec::new();
+    while (reader.position() as usize) < bytes.len() {
+        flowfiles.push(FlowFile::decode_from(&mut reader)?);
+    }
+    Ok(flowfiles)
+}
+
+pub fn write_state(path: &Path, state: &HashMap<String, String>) -> Result<(), CheckpointError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_vec(state).map_err(|e| StateError::Corrupt(e.to_string()))?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+pub fn read_state(path: &Path) -> Result<HashMap<String, String>, CheckpointError> {
+    let bytes = fs::read(path)?;
+    Ok(serde_json::from_slice(&bytes).map_err(|e| StateError::Corrupt(e.to_string()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, ProcessorNode};
+    use crate::processor::{Processor, ProcessorError};
+    use crate::processor_context::ProcessorContext;
+    use crate::processors::stdio::GetStdin;
+    use crate::relationship::{self, Relationship};
+    use crate::session::ProcessSession;
+    use crate::state::{MemoryStateManager, StateManager};
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-checkpoint-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // Takes one FlowFile per trigger, remembers its content and keeps a
+    // running count in processor state.
+    #[derive(Default)]
+    struct Tally {
+        seen: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Processor for Tally {
+        fn on_trigger(
+            &self,
+            context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let Some(flowfile) = session.get()? else {
+                return Ok(());
+            };
+            let name = &context.processor_name;
+            let mut state = context
+                .state_manager
+                .get_state(name)
+                .map_err(|e| ProcessorError::Retryable(e.to_string()))?;
+            let count: u64 = state.get("count").and_then(|c| c.parse().ok()).unwrap_or(0);
+            state.insert("count".to_string(), (count + 1).to_string());
+            context
+                .state_manager
+                .set_state(name, state)
+                .map_err(|e| ProcessorError::Retryable(e.to_string()))?;
+            self.seen
+                .lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&flowfile.content().unwrap()).to_string());
+            session.transfer(flowfile, relationship::SUCCESS);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Tally"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    fn tally_flow(input: &'static str, tally: Tally, connection: &str) -> FlowDefinition {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new(
+            "lines",
+            GetStdin::with_reader(io::Cursor::new(input)),
+        ));
+        flow.add_processor(
+            ProcessorNode::new("tally", tally)
+                .run_schedule(Duration::from_millis(20))
+                .auto_terminate(relationship::SUCCESS),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            connection, "lines", "success", "tally",
+        ));
+        flow
+    }
+
+    async fn wait_until(condition: impl Fn() -> bool) {
+        while !condition() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restored_flowfiles_complete_processing() {
+        let dir = temp_dir("resume");
+        let lines = ["one", "two", "three", "four", "five", "six"];
+        let first = Tally::default();
+        let seen_before = first.seen.clone();
+        let mut controller = FlowController::new(tally_flow(
+            "one\ntwo\nthree\nfour\nfive\nsix\n",
+            first,
+            "queue",
+        ));
+        controller.start().unwrap();
+        controller.stop_processor("tally");
+        let queue = controller.connection("queue").unwrap();
+        wait_until(|| queue.len() == lines.len()).await;
+
+        controller.start_processor("tally");
+        wait_until(|| seen_before.lock().unwrap().len() >= 2).await;
+        controller.stop_processor("tally");
+        let manifest = controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+
+        let done = seen_before.lock().unwrap().clone();
+        assert_eq!(done, lines[..done.len()]);
+        assert_eq!(manifest.connections[0].queued, lines.len() - done.len());
+
+        let second = Tally::default();
+        let seen_after = second.seen.clone();
+        let state_manager = Arc::new(MemoryStateManager::new());
+        let mut controller = FlowController::new(tally_flow("", second, "queue"))
+            .with_state_manager(state_manager.clone());
+        controller.restore(&dir).unwrap();
+        assert_eq!(
+            state_manager.get_state("tally").unwrap()["count"],
+            done.len().to_string()
+        );
+        assert_eq!(manifest.processors[1].counters.processed, done.len() as u64);
+
+        let summary = controller
+            .run_to_completion(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(summary.completed);
+        assert_eq!(*seen_after.lock().unwrap(), lines[done.len()..]);
+        // The counters carry on from the checkpoint.
+        assert_eq!(summary.processed["tally"], lines.len() as u64);
+        assert_eq!(
+            state_manager.get_state("tally").unwrap()["count"],
+            lines.len().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_topology_is_rejected() {
+        let dir = temp_dir("topology");
+        let mut controller = FlowController::new(tally_flow("a\n", Tally::default(), "queue"));
+        controller.start().unwrap();
+        controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+
+        let mut controller = FlowController::new(tally_flow("", Tally::default(), "renamed"));
+        let error = controller.restore(&dir).unwrap_err();
+        let CheckpointError::TopologyMismatch(differences) = &error else {
+            panic!("unexpected error: {}", error);
+        };
+        assert_eq!(
+            *differences,
+            vec![
+                "connection 'renamed' is not in the checkpoint".to_string(),
+                "checkpointed connection 'queue' is not in the flow".to_string(),
+            ]
+        );
+        assert!(error
+            .to_string()
+            .starts_with("checkpoint was taken from a different flow"));
+    }
+
+    #[tokio::test]
+    async fn test_manifest_is_stamped_by_the_flow_clock() {
+        let dir = temp_dir("clock");
+        let clock = Arc::new(MockClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let mut controller = FlowController::new(tally_flow("", Tally::default(), "queue"))
+            .with_clock(clock.clone());
+        controller.start().unwrap();
+        let manifest = controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+        assert_eq!(manifest.created_at, clock.now());
+        assert_eq!(Manifest::read(&dir).unwrap().created_at, clock.now());
+    }
+
+    #[tokio::test]
+    async fn test_other_manifest_versions_are_rejected() {
+        let dir = temp_dir("version");
+        let mut controller = FlowController::new(tally_flow("", Tally::default(), "queue"));
+        assert!(matches!(
+            controller.checkpoint(&dir).await,
+            Err(CheckpointError::NotStarted)
+        ));
+        controller.start().unwrap();
+        let mut manifest = controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+
+        manifest.version = MANIFEST_VERSION + 1;
+        manifest.write(&dir).unwrap();
+        let mut controller = FlowController::new(tally_flow("", Tally::default(), "queue"));
+        assert!(matches!(
+            controller.restore(&dir),
+            Err(CheckpointError::UnsupportedVersion(version)) if version == MANIFEST_VERSION + 1
+        ));
+    }
+}
@@ -0,0 +1,466 @@
+//! Subcommands behind the `streamsync` binary. Each one writes its normal
+//! output to `out`, diagnostics to `err`, and returns the process exit code.
+
+use crate::bench;
+use crate::controller::FlowController;
+use crate::loader::load_flow;
+use crate::registry::ProcessorRegistry;
+use crate::validation::validate;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+/// How long `run --once` waits for a flow to drain before giving up.
+pub const RUN_ONCE_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub const USAGE: &str = "usage: streamsync <command>
+
+commands:
+  run <flow.yaml>          run the flow until interrupted with Ctrl-C
+  run --once <flow.yaml>   run the flow until its sources are exhausted and its queues drained
+  validate <flow.yaml>     check the flow and report every problem found
+  inspect <flow.yaml> [--dot]
+                           draw the flow's processors and connections, as Graphviz DOT with --dot
+  list-processors          list the available processor types and their properties
+  bench [--flowfiles N] [--size BYTES]
+                           measure queue throughput and print a table";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Run(PathBuf),
+    RunOnce(PathBuf),
+    Validate(PathBuf),
+    Inspect {
+        path: PathBuf,
+        dot: bool,
+    },
+    ListProcessors,
+    Bench {
+        flowfiles: usize,
+        content_size: usize,
+    },
+}
+
+/// Parses the arguments following the program name.
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    match args {
+        [command, flag, path] if command == "run" && flag == "--once" => {
+            Ok(Command::RunOnce(PathBuf::from(path)))
+        }
+        [command, path] if command == "run" => Ok(Command::Run(PathBuf::from(path))),
+        [command, path] if command == "validate" => Ok(Command::Validate(PathBuf::from(path))),
+        [command, path] if command == "inspect" => Ok(Command::Inspect {
+            path: PathBuf::from(path),
+            dot: false,
+        }),
+        [command, path, flag] if command == "inspect" && flag == "--dot" => Ok(Command::Inspect {
+            path: PathBuf::from(path),
+            dot: true,
+        }),
+        [command] if command == "list-processors" => Ok(Command::ListProcessors),
+        [command, options @ ..] if command == "bench" => parse_bench_options(options),
+        [command, ..] if command == "inspect" => {
+            Err("usage: streamsync inspect <flow.yaml> [--dot]".to_string())
+        }
+        [command, ..] if command == "run" || command == "validate" => {
+            Err(format!("'{}' expects exactly one flow file", command))
+        }
+        [command, ..] if command == "list-processors" => {
+            Err("'list-processors' takes no arguments".to_string())
+        }
+        [command, ..] => Err(format!("unknown command '{}'", command)),
+        [] => Err("missing command".to_string()),
+    }
+}
+
+fn parse_bench_options(options: &[String]) -> Result<Command, String> {
+    let (mut flowfiles, mut content_size) = (bench::DEFAULT_FLOWFILES, bench::DEFAULT_CONTENT_SIZE);
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let target = match option.as_str() {
+            "--flowfiles" => &mut flowfiles,
+            "--size" => &mut content_size,
+            _ => return Err(format!("unknown bench option '{}'", option)),
+        };
+        *target = options
+            .next()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("'{}' expects a number", option))?;
+    }
+    Ok(Command::Bench {
+        flowfiles,
+        content_size,
+    })
+}
+
+pub fn validate_command(
+    path: &Path,
+    registry: &ProcessorRegistry,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> i32 {
+    let flow = match load_flow(path, registry) {
+        Ok(flow) => flow,
+        Err(e) => {
+            let _ = writeln!(err, "{}: {}", path.display(), e);
+            return EXIT_FAILURE;
+        }
+    };
+    let errors = validate(&flow);
+    if errors.is_empty() {
+        let _ = writeln!(
+            out,
+            "{}: valid ({} processors, {} connections)",
+            path.display(),
+            flow.processors.len(),
+            flow.connections.len()
+        );
+        EXIT_OK
+    } else {
+        for error in &errors {
+            let _ = writeln!(err, "{}: {}", path.display(), error);
+        }
+        EXIT_FAILURE
+    }
+}
+
+/// Draws the flow without running or validating it.
+pub fn inspect_command(
+    path: &Path,
+    dot: bool,
+    registry: &ProcessorRegistry,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> i32 {
+    match load_flow(path, registry) {
+        Ok(flow) => {
+            let rendered = if dot {
+                flow.render_dot()
+            } else {
+                flow.render_ascii()
+            };
+            let _ = write!(out, "{}", rendered);
+            EXIT_OK
+        }
+        Err(e) => {
+            let _ = writeln!(err, "{}: {}", path.display(), e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+pub fn list_processors(registry: &ProcessorRegistry, out: &mut dyn Write) -> i32 {
+    for type_name in registry.types() {
+        let Some(processor) = registry.create(type_name) else {
+            continue;
+        };
+        let _ = writeln!(out, "{}", type_name);
+        for property in processor.properties() {
+            let mut flags = Vec::new();
+            if property.required {
+                flags.push("required".to_string());
+            }
+            if let Some(default) = &property.default_value {
+                flags.push(format!("default: {}", default));
+            }
+            let flags = if flags.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", flags.join(", "))
+            };
+            let _ = writeln!(
+                out,
+                "  {}{} - {}",
+                property.name, flags, property.description
+            );
+        }
+    }
+    EXIT_OK
+}
+
+/// Starts the flow and keeps it running until Ctrl-C, then stops it cleanly.
+pub async fn run_command(
+    path: &Path,
+    registry: &ProcessorRegistry,
+    out: &mut dyn Write,
+    err: &mut dyn Write,
+) -> i32 {
+    let flow = match load_flow(path, registry) {
+        Ok(flow) => flow,
+        Err(e) => {
+            let _ = writeln!(err, "{}: {}", path.display(), e);
+            return EXIT_FAILURE;
+        }
+    };
+    let mut controller = FlowController::new(flow);
+    if let Err(errors) = controller.start() {
+        for error in &errors {
+            let _ = writeln!(err, "{}: {}", path.display(), error);
+        }
+        return EXIT_FAILURE;
+    }
+    let _ = writeln!(out, "running {} (Ctrl-C to stop)", path.display());
+
+    let interrupted = tokio::signal::ctrl_c().await;
+    controller.stop().await;
+    match interrupted {
+        Ok(()) => {
+            let _ = writeln!(out, "stopped");
+            EXIT_OK
+        }
+        Err(e) => {
+            let _ = writeln!(err, "cannot listen for Ctrl-C: {}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Runs the flow to completion and reports how many FlowFiles each processor
+/// handled. Fails if the flow did not drain in time or raised any bulletins.
+/// Everything goes to `err`, leaving stdout to the flow (see `PutStdout`).
+pub async fn run_once_command(
+    path: &Path,
+    registry: &ProcessorRegistry,
+    timeout: Duration,
+    err: &mut dyn Write,
+) -> i32 {
+    let flow = match load_flow(path, registry) {
+        Ok(flow) => flow,
+        Err(e) => {
+            let _ = writeln!(err, "{}: {}", path.display(), e);
+            return EXIT_FAILURE;
+        }
+    };
+    let mut controller = FlowController::new(flow);
+    let summary = match controller.run_to_completion(timeout).await {
+        Ok(summary) => summary,
+        Err(errors) => {
+            for error in &errors {
+                let _ = writeln!(err, "{}: {}", path.display(), error);
+            }
+            return EXIT_FAILURE;
+        }
+    };
+
+    for (processor, count) in &summary.processed {
+        let _ = writeln!(err, "{}: {} FlowFiles", processor, count);
+    }
+    let _ = writeln!(err, "finished in {:.3}s", summary.elapsed.as_secs_f64());
+    for bulletin in &summary.errors {
+        let _ = writeln!(
+            err,
+            "{}: {}: {}",
+            bulletin.processor, bulletin.severity, bulletin.message
+        );
+    }
+    if !summary.completed {
+        let _ = writeln!(
+            err,
+            "{}: did not finish within {:?}",
+            path.display(),
+            timeout
+        );
+        return EXIT_FAILURE;
+    }
+    if summary.errors.is_empty() {
+        EXIT_OK
+    } else {
+        EXIT_FAILURE
+    }
+}
+
+pub async fn bench_command(flowfiles: usize, content_size: usize, out: &mut dyn Write) -> i32 {
+    let rows = bench::run(
+        &bench::default_cases(),
+        flowfiles,
+        content_size,
+        bench::DEFAULT_CONSUMERS,
+    )
+    .await;
+    let _ = write!(out, "{}", bench::render_table(&rows));
+    EXIT_OK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_flow(name: &str, yaml: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "streamsync-cli-{}-{}.yaml",
+            std::process::id(),
+            name
+        ));
+        std::fs::write(&path, yaml).unwrap();
+        path
+    }
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&args(&["validate", "flow.yaml"])),
+            Ok(Command::Validate(PathBuf::from("flow.yaml")))
+        );
+        assert_eq!(
+            parse_args(&args(&["run", "--once", "flow.yaml"])),
+            Ok(Command::RunOnce(PathBuf::from("flow.yaml")))
+        );
+        assert_eq!(
+            parse_args(&args(&["inspect", "flow.yaml", "--dot"])),
+            Ok(Command::Inspect {
+                path: PathBuf::from("flow.yaml"),
+                dot: true
+            })
+        );
+        assert!(parse_args(&args(&["inspect", "flow.yaml", "--svg"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["list-processors"])),
+            Ok(Command::ListProcessors)
+        );
+        assert_eq!(
+            parse_args(&args(&["bench", "--size", "64"])),
+            Ok(Command::Bench {
+                flowfiles: bench::DEFAULT_FLOWFILES,
+                content_size: 64
+            })
+        );
+        assert!(parse_args(&args(&["bench", "--flowfiles"])).is_err());
+        assert!(parse_args(&args(&["run"])).is_err());
+        assert!(parse_args(&args(&["deploy", "flow.yaml"])).is_err());
+        assert!(parse_args(&[]).is_err());
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let registry = ProcessorRegistry::with_builtins();
+        let valid = write_flow(
+            "valid",
+            "processors:\n  - name: log\n    type: LogProcessor\n    auto_terminate: [success]\n",
+        );
+        let invalid = write_flow(
+            "invalid",
+            "processors:\n  - name: log\n    type: LogProcessor\n",
+        );
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+
+        assert_eq!(
+            validate_command(&valid, &registry, &mut out, &mut err),
+            EXIT_OK
+        );
+        assert!(String::from_utf8_lossy(&out).contains("valid (1 processors, 0 connections)"));
+        assert!(err.is_empty());
+
+        assert_eq!(
+            validate_command(&invalid, &registry, &mut out, &mut err),
+            EXIT_FAILURE
+        );
+        assert!(String::from_utf8_lossy(&err).contains("success"));
+
+        std::fs::remove_file(valid).unwrap();
+        std::fs::remove_file(invalid).unwrap();
+    }
+
+    #[test]
+    fn test_inspect_command() {
+        let registry = ProcessorRegistry::with_builtins();
+        let path = write_flow(
+            "inspect",
+            "processors:\n  - name: log\n    type: LogProcessor\n    auto_terminate: [success]\n",
+        );
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        assert_eq!(
+            inspect_command(&path, false, &registry, &mut out, &mut err),
+            EXIT_OK
+        );
+        assert!(String::from_utf8_lossy(&out)
+            .starts_with("+-------------------+\n| log               |\n"));
+
+        out.clear();
+        assert_eq!(
+            inspect_command(&path, true, &registry, &mut out, &mut err),
+            EXIT_OK
+        );
+        assert!(String::from_utf8_lossy(&out)
+            .contains("\"log\" [label=\"log\\nLogProcessor\\nruns continuously\"];"));
+        assert!(err.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_once_command() {
+        let registry = ProcessorRegistry::with_builtins();
+        let input = std::env::temp_dir().join(format!(
+            "streamsync-cli-{}-run-once-input",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&input).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            std::fs::write(input.join(name), "hello").unwrap();
+        }
+        let finite = write_flow(
+            "run-once",
+            &format!(
+                "processors:
+  - name: read
+    type: GetFileProcessor
+    properties:
+      input.directory: {}
+  - name: log
+    type: LogProcessor
+    auto_terminate: [success]
+connections:
+  - name: read-to-log
+    source: read
+    relationship: success
+    destination: log
+",
+                input.display()
+            ),
+        );
+        let mut err = Vec::new();
+        assert_eq!(
+            run_once_command(&finite, &registry, Duration::from_secs(10), &mut err).await,
+            EXIT_OK
+        );
+        let err = String::from_utf8(err).unwrap();
+        assert!(err.contains("read: 2 FlowFiles\n"));
+        assert!(err.contains("log: 2 FlowFiles\n"));
+        assert!(err.contains("finished in "));
+
+        // A LogProcessor source is never exhausted.
+        let endless = write_flow(
+            "run-once-endless",
+            "processors:\n  - name: log\n    type: LogProcessor\n    auto_terminate: [success]\n",
+        );
+        let mut err = Vec::new();
+        assert_eq!(
+            run_once_command(&endless, &registry, Duration::from_millis(100), &mut err).await,
+            EXIT_FAILURE
+        );
+        assert!(String::from_utf8_lossy(&err).contains("did not finish within 100ms"));
+
+        std::fs::remove_file(finite).unwrap();
+        std::fs::remove_file(endless).unwrap();
+        std::fs::remove_dir_all(input).unwrap();
+    }
+
+    #[test]
+    fn test_list_processors() {
+        let mut out = Vec::new();
+        assert_eq!(
+            list_processors(&ProcessorRegistry::with_builtins(), &mut out),
+            EXIT_OK
+        );
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("FileProcessor\n"));
+        assert!(out.contains("LogProcessor\n  log.level (default: info)"));
+    }
+}
@@ -1,5 +1,522 @@
+pub mod funnel;
+
+use crate::clock::{Clock, SystemClock};
+use crate::flowfile::FlowFile;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+// Poll interval used by the default `receive_timeout`.
+const RECEIVE_POLL: Duration = Duration::from_millis(5);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionError {
+    /// The connection was closed and holds nothing more to deliver.
+    Closed,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::Closed => write!(f, "connection is closed"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+#[async_trait::async_trait]
+pub trait Connection: Send + Sync {
+    async fn send(&self, flowfile: FlowFile) -> Result<(), ConnectionError>;
+
+    /// Returns `Ok(None)` when the queue is merely empty, and an error when
+    /// the connection itself can no longer deliver.
+    async fn receive(&self) -> Result<Option<FlowFile>, ConnectionError>;
+
+    /// Receives up to `max` FlowFiles, stopping early once the queue drains.
+    /// A connection that closes partway still hands over what was already
+    /// received; the error comes with the next call.
+    async fn receive_batch(&self, max: usize) -> Result<Vec<FlowFile>, ConnectionError> {
+        let mut batch = Vec::new();
+        while batch.len() < max {
+            match self.receive().await {
+                Ok(Some(flowfile)) => batch.push(flowfile),
+                Ok(None) => break,
+                Err(_) if !batch.is_empty() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Waits up to `timeout` for a FlowFile to arrive.
+    async fn receive_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<Option<FlowFile>, ConnectionError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(flowfile) = self.receive().await? {
+                return Ok(Some(flowfile));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(None);
+            }
+            tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + RECEIVE_POLL))
+                .await;
+        }
+    }
+
+    /// Number of FlowFiles currently queued.
+    fn len(&self) -> usize;
+
+    /// Total content size of the FlowFiles currently queued.
+    fn size_bytes(&self) -> u64;
+
+    /// Copies of everything queued, penalized FlowFiles included, in queue
+    /// order. The queue itself is left untouched.
+    fn snapshot(&self) -> Vec<FlowFile>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True once the queue has reached one of its backpressure limits; the
+    /// scheduler stops triggering the upstream processor until it drains.
+    fn is_full(&self) -> bool {
+        false
+    }
+
+    /// Number of FlowFiles dropped so far for having been queued past the
+    /// connection's expiration; 0 for a queue without one.
+    fn expired(&self) -> u64 {
+        0
+    }
+}
+
+/// Told about the FlowFiles a queue dropped for being past its expiration,
+/// outside the queue's lock.
+pub type ExpiryListener = Box<dyn Fn(Vec<FlowFile>) + Send + Sync>;
+
+/// In-process FIFO queue between two processors.
+///
+/// A queue may be bounded by a FlowFile count, by total content bytes, or by
+/// both; it is full as soon as either limit is reached. `send` still accepts
+/// FlowFiles past the limits, since a session that has committed cannot take
+/// them back: backpressure is applied by the scheduler, which holds off the
+/// upstream processor until the queue drains below them again.
+///
+/// With an expiration set, FlowFiles that have been around longer than it
+/// are dropped instead of delivered; see `with_expiration`.
+pub struct MemoryConnection {
+    queue: Mutex<VecDeque<FlowFile>>,
+    // Kept alongside the queue so they can be read without taking its lock.
+    depth: AtomicUsize,
+    bytes: AtomicU64,
+    closed: AtomicBool,
+    backpressure_threshold: Option<usize>,
+    backpressure_bytes: Option<u64>,
+    expiration: Option<Duration>,
+    expired: AtomicU64,
+    // When the first queued FlowFile reaches the expiration, so `receive`
+    // scans the queue only once one can have. Taken with the queue's lock.
+    next_expiry: Mutex<Option<SystemTime>>,
+    expiry_listener: Option<ExpiryListener>,
+    clock: Arc<dyn Clock>,
+}
+
+impl MemoryConnection {
+    pub fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+            depth: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            backpressure_threshold: None,
+            backpressure_bytes: None,
+            expiration: None,
+            expired: AtomicU64::new(0),
+            next_expiry: Mutex::new(None),
+            expiry_listener: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_backpressure(threshold: usize) -> Self {
+        Self::with_limits(Some(threshold), None)
+    }
+
+    /// A queue full at `max_count` FlowFiles or `max_bytes` bytes of
+    /// content, whichever comes first. `None` leaves that dimension unbounded.
+    pub fn with_limits(max_count: Option<usize>, max_bytes: Option<u64>) -> Self {
+        Self {
+            backpressure_threshold: max_count,
+            backpressure_bytes: max_bytes,
+            ..Self::new()
+        }
+    }
+
+    /// Clock used for penalties and expiration.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Drops FlowFiles once they are `expiration` old, counted from their
+    /// creation (see `FlowFile::age`). Expired FlowFiles are removed when the
+    /// queue is next received from, penalized ones included.
+    pub fn with_expiration(mut self, expiration: Duration) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    /// Hands the FlowFiles dropped for being past the expiration to
+    /// `listener`, e.g. to record their provenance and raise a bulletin.
+    pub fn on_expire(mut self, listener: impl Fn(Vec<FlowFile>) + Send + Sync + 'static) -> Self {
+        self.expiry_listener = Some(Box::new(listener));
+        self
+    }
+
+    /// Number of FlowFiles queued, penalized ones included.
+    pub fn queue_size(&self) -> usize {
+        self.len()
+    }
+
+    /// Total content bytes queued, penalized FlowFiles included.
+    pub fn queue_bytes(&self) -> u64 {
+        self.size_bytes()
+    }
+
+    /// Stops accepting FlowFiles. Anything already queued can still be
+    /// received; after that `receive` reports `ConnectionError::Closed`.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Removes and returns everything queued, penalized FlowFiles included.
+    pub fn drain(&self) -> Vec<FlowFile> {
+        let mut queue = self.queue.lock().unwrap();
+        *self.next_expiry.lock().unwrap() = None;
+        self.depth.store(0, Ordering::SeqCst);
+        self.bytes.store(0, Ordering::SeqCst);
+        queue.drain(..).collect()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    // When `flowfile` reaches `expiration`, as seen from `now`.
+    fn expires_at(&self, flowfile: &FlowFile, expiration: Duration, now: SystemTime) -> SystemTime {
+        now + expiration.saturating_sub(flowfile.age(self.clock.as_ref()))
+    }
+
+    // Takes the FlowFiles past the expiration out of `queue`, if any can be,
+    // and works out when the next of those left expires.
+    fn remove_expired(&self, queue: &mut VecDeque<FlowFile>, now: SystemTime) -> Vec<FlowFile> {
+        let Some(expiration) = self.expiration else {
+            return Vec::new();
+        };
+        let mut next_expiry = self.next_expiry.lock().unwrap();
+        if next_expiry.is_none_or(|next| now < next) {
+            return Vec::new();
+        }
+        let mut expired = Vec::new();
+        let mut next = None;
+        for flowfile in std::mem::take(queue) {
+            let expires = self.expires_at(&flowfile, expiration, now);
+            if expires <= now {
+                self.depth.fetch_sub(1, Ordering::SeqCst);
+                self.bytes
+                    .fetch_sub(flowfile.size() as u64, Ordering::SeqCst);
+                expired.push(flowfile);
+            } else {
+                next = Some(next.map_or(expires, |next: SystemTime| next.min(expires)));
+                queue.push_back(flowfile);
+            }
+        }
+        *next_expiry = next;
+        self.expired
+            .fetch_add(expired.len() as u64, Ordering::SeqCst);
+        expired
+    }
+}
+
+impl Default for MemoryConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[async_trait::async_trait]
-trait Connection {
-    async fn send(&self, flowfile: FlowFile);
-    async fn receive(&self) -> Option<FlowFile>;
+impl Connection for MemoryConnection {
+    async fn send(&self, flowfile: FlowFile) -> Result<(), ConnectionError> {
+        if self.is_closed() {
+            return Err(ConnectionError::Closed);
+        }
+        let mut queue = self.queue.lock().unwrap();
+        if let Some(expiration) = self.expiration {
+            let expires = self.expires_at(&flowfile, expiration, self.clock.now());
+            let mut next_expiry = self.next_expiry.lock().unwrap();
+            *next_expiry = Some(next_expiry.map_or(expires, |next| next.min(expires)));
+        }
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        self.bytes
+            .fetch_add(flowfile.size() as u64, Ordering::SeqCst);
+        queue.push_back(flowfile);
+        Ok(())
+    }
+
+    async fn receive(&self) -> Result<Option<FlowFile>, ConnectionError> {
+        let now = self.clock.now();
+        let mut queue = self.queue.lock().unwrap();
+        let expired = self.remove_expired(&mut queue, now);
+        let next = queue
+            .iter()
+            .position(|flowfile| !flowfile.is_penalized(now));
+        let received = next.and_then(|index| queue.remove(index));
+        drop(queue);
+        if let (false, Some(listener)) = (expired.is_empty(), &self.expiry_listener) {
+            listener(expired);
+        }
+        match received {
+            Some(flowfile) => {
+                self.depth.fetch_sub(1, Ordering::SeqCst);
+                self.bytes
+                    .fetch_sub(flowfile.size() as u64, Ordering::SeqCst);
+                Ok(Some(flowfile))
+            }
+            None if self.is_closed() => Err(ConnectionError::Closed),
+            None => Ok(None),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.bytes.load(Ordering::SeqCst)
+    }
+
+    fn snapshot(&self) -> Vec<FlowFile> {
+        self.queue.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn expired(&self) -> u64 {
+        self.expired.load(Ordering::SeqCst)
+    }
+
+    fn is_full(&self) -> bool {
+        let count_reached = self
+            .backpressure_threshold
+            .is_some_and(|threshold| self.len() >= threshold);
+        let bytes_reached = self
+            .backpressure_bytes
+            .is_some_and(|threshold| self.size_bytes() >= threshold);
+        count_reached || bytes_reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::time::UNIX_EPOCH;
+
+    #[tokio::test]
+    async fn test_closed_connection_reports_closed() {
+        let connection = MemoryConnection::new();
+        connection
+            .send(FlowFile::with_content("last"))
+            .await
+            .unwrap();
+        connection.close();
+
+        assert_eq!(
+            connection.send(FlowFile::new()).await.unwrap_err(),
+            ConnectionError::Closed
+        );
+        assert_eq!(
+            connection
+                .receive()
+                .await
+                .unwrap()
+                .unwrap()
+                .content()
+                .unwrap()
+                .as_ref(),
+            b"last"
+        );
+        assert_eq!(
+            connection.receive().await.unwrap_err(),
+            ConnectionError::Closed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receive_batch_keeps_flowfiles_received_before_close() {
+        let connection = MemoryConnection::new();
+        for i in 0..2 {
+            connection
+                .send(FlowFile::with_content(vec![i]))
+                .await
+                .unwrap();
+        }
+        connection.close();
+
+        let batch = connection.receive_batch(10).await.unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[1].content().unwrap().as_ref(), &[1]);
+        assert_eq!(
+            connection.receive_batch(10).await.unwrap_err(),
+            ConnectionError::Closed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receive_batch_stops_when_drained() {
+        let connection = MemoryConnection::new();
+        for i in 0..3 {
+            connection
+                .send(FlowFile::with_content(vec![i]))
+                .await
+                .unwrap();
+        }
+
+        let batch = connection.receive_batch(10).await.unwrap();
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[2].content().unwrap().as_ref(), &[2]);
+        assert!(connection.receive_batch(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_reached_before_count_limit() {
+        let connection = MemoryConnection::with_limits(Some(10), Some(100));
+        connection
+            .send(FlowFile::with_content(vec![0; 60]))
+            .await
+            .unwrap();
+        assert!(!connection.is_full());
+        connection
+            .send(FlowFile::with_content(vec![0; 40]))
+            .await
+            .unwrap();
+
+        assert_eq!(connection.queue_size(), 2);
+        assert_eq!(connection.queue_bytes(), 100);
+        assert!(connection.is_full());
+
+        connection.receive().await.unwrap();
+        assert_eq!(connection.queue_bytes(), 40);
+        assert!(!connection.is_full());
+    }
+
+    #[tokio::test]
+    async fn test_count_limit_reached_before_byte_limit() {
+        let connection = MemoryConnection::with_limits(Some(3), Some(1024));
+        for _ in 0..2 {
+            connection
+                .send(FlowFile::with_content("tiny"))
+                .await
+                .unwrap();
+        }
+        assert!(!connection.is_full());
+        connection
+            .send(FlowFile::with_content("tiny"))
+            .await
+            .unwrap();
+
+        assert_eq!(connection.queue_size(), 3);
+        assert_eq!(connection.queue_bytes(), 12);
+        assert!(connection.is_full());
+
+        // Still accepted past the limit; the scheduler is what holds back.
+        connection
+            .send(FlowFile::with_content("tiny"))
+            .await
+            .unwrap();
+        assert_eq!(connection.queue_size(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_expired_flowfiles_are_dropped() {
+        let clock = Arc::new(MockClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let connection = {
+            let dropped = dropped.clone();
+            MemoryConnection::new()
+                .with_clock(clock.clone())
+                .with_expiration(Duration::from_secs(60))
+                .on_expire(move |expired| dropped.lock().unwrap().extend(expired))
+        };
+        let mut first = FlowFile::created_by("source", clock.now());
+        first.set_content("first");
+        connection.send(first).await.unwrap();
+        clock.advance(Duration::from_secs(30));
+        let mut second = FlowFile::created_by("source", clock.now());
+        second.set_content("second");
+        connection.send(second).await.unwrap();
+
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(
+            connection
+                .receive()
+                .await
+                .unwrap()
+                .unwrap()
+                .content()
+                .unwrap()
+                .as_ref(),
+            b"second"
+        );
+        assert_eq!(connection.expired(), 1);
+        assert_eq!(
+            dropped.lock().unwrap()[0].content().unwrap().as_ref(),
+            b"first"
+        );
+        // The scan kept the second's expiry time, so the next receive does
+        // not scan again before then, even though it has been received.
+        assert_eq!(
+            *connection.next_expiry.lock().unwrap(),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_090))
+        );
+
+        let mut penalized = FlowFile::created_by("source", clock.now());
+        penalized.penalize_until(clock.now() + Duration::from_secs(90));
+        connection.send(penalized).await.unwrap();
+        clock.advance(Duration::from_secs(60));
+        assert!(connection.receive().await.unwrap().is_none());
+        assert_eq!(connection.expired(), 2);
+        assert_eq!(dropped.lock().unwrap().len(), 2);
+        assert_eq!(connection.queue_size(), 0);
+        assert_eq!(connection.queue_bytes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_penalty_follows_clock() {
+        let clock = Arc::new(MockClock::default());
+        let connection = MemoryConnection::new().with_clock(clock.clone());
+        let mut flowfile = FlowFile::created_by("source", clock.now());
+        flowfile.penalize_until(clock.now() + Duration::from_secs(1));
+        connection.send(flowfile).await.unwrap();
+
+        assert!(connection.receive().await.unwrap().is_none());
+        clock.advance(Duration::from_secs(1));
+        assert!(connection.receive().await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_receive_timeout_on_empty_queue() {
+        let connection = MemoryConnection::new();
+        let received = connection
+            .receive_timeout(Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert!(received.is_none());
+    }
 }
@@ -0,0 +1,394 @@
+//! A connection that merges several upstream processors into one queue, like
+//! a NiFi funnel.
+//!
+//! Each upstream sends through its own [`FunnelInlet`], which keeps a
+//! separate lane per source. The downstream side receives from the
+//! [`FunnelConnection`] and takes the lanes in turn, so a source that floods
+//! the funnel cannot starve the others. Backpressure applies to the funnel
+//! as a whole: once the lanes together reach the threshold, every inlet
+//! reports full.
+//!
+//! In a flow, connections that name the same `funnel` share one; the
+//! controller gives each of them an inlet of its own, keyed by the
+//! connection's name.
+
+use super::{Connection, ConnectionError};
+use crate::clock::{Clock, SystemClock};
+use crate::flowfile::FlowFile;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+struct Lane {
+    source: String,
+    queue: VecDeque<FlowFile>,
+}
+
+#[derive(Default)]
+struct Lanes {
+    lanes: Vec<Lane>,
+    // Lane to look at first on the next receive.
+    next: usize,
+}
+
+impl Lanes {
+    fn index_of(&mut self, source: &str) -> usize {
+        match self.lanes.iter().position(|lane| lane.source == source) {
+            Some(index) => index,
+            None => {
+                self.lanes.push(Lane {
+                    source: source.to_string(),
+                    queue: VecDeque::new(),
+                });
+                self.lanes.len() - 1
+            }
+        }
+    }
+}
+
+struct Shared {
+    lanes: Mutex<Lanes>,
+    depth: AtomicUsize,
+    bytes: AtomicU64,
+    closed: AtomicBool,
+    backpressure_threshold: Option<usize>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            lanes: Mutex::default(),
+            depth: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            backpressure_threshold: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl Shared {
+    fn send(&self, lane: usize, flowfile: FlowFile) -> Result<(), ConnectionError> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ConnectionError::Closed);
+        }
+        let mut lanes = self.lanes.lock().unwrap();
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        self.bytes
+            .fetch_add(flowfile.size() as u64, Ordering::SeqCst);
+        lanes.lanes[lane].queue.push_back(flowfile);
+        Ok(())
+    }
+
+    fn receive(&self) -> Result<Option<FlowFile>, ConnectionError> {
+        let mut lanes = self.lanes.lock().unwrap();
+        let now = self.clock.now();
+        let count = lanes.lanes.len();
+        for offset in 0..count {
+            let index = (lanes.next + offset) % count;
+            let queue = &mut lanes.lanes[index].queue;
+            let Some(position) = queue
+                .iter()
+                .position(|flowfile| !flowfile.is_penalized(now))
+            else {
+                continue;
+            };
+            let flowfile = queue.remove(position).expect("position is in range");
+            lanes.next = (index + 1) % count;
+            self.depth.fetch_sub(1, Ordering::SeqCst);
+            self.bytes
+                .fetch_sub(flowfile.size() as u64, Ordering::SeqCst);
+            return Ok(Some(flowfile));
+        }
+        if self.closed.load(Ordering::SeqCst) {
+            Err(ConnectionError::Closed)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn snapshot(&self) -> Vec<FlowFile> {
+        let lanes = self.lanes.lock().unwrap();
+        lanes
+            .lanes
+            .iter()
+            .flat_map(|lane| lane.queue.iter().cloned())
+            .collect()
+    }
+
+    fn lane_len(&self, lane: usize) -> usize {
+        self.lanes.lock().unwrap().lanes[lane].queue.len()
+    }
+
+    fn lane_bytes(&self, lane: usize) -> u64 {
+        let lanes = self.lanes.lock().unwrap();
+        lanes.lanes[lane]
+            .queue
+            .iter()
+            .map(|flowfile| flowfile.size() as u64)
+            .sum()
+    }
+
+    fn lane_snapshot(&self, lane: usize) -> Vec<FlowFile> {
+        let lanes = self.lanes.lock().unwrap();
+        lanes.lanes[lane].queue.iter().cloned().collect()
+    }
+
+    fn is_full(&self) -> bool {
+        match self.backpressure_threshold {
+            Some(threshold) => self.depth.load(Ordering::SeqCst) >= threshold,
+            None => false,
+        }
+    }
+}
+
+/// The downstream end of a funnel. Sending to it directly goes through an
+/// unnamed lane of its own.
+pub struct FunnelConnection {
+    shared: Arc<Shared>,
+}
+
+impl FunnelConnection {
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Shared::default()),
+        }
+    }
+
+    pub fn with_backpressure(threshold: usize) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                backpressure_threshold: Some(threshold),
+                ..Shared::default()
+            }),
+        }
+    }
+
+    /// Clock used to tell when penalties run out. Only effective before the
+    /// first inlet is taken.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        if let Some(shared) = Arc::get_mut(&mut self.shared) {
+            shared.clock = clock;
+        }
+        self
+    }
+
+    /// The upstream end for `source`. Inlets for the same source share a
+    /// lane.
+    pub fn inlet(&self, source: &str) -> FunnelInlet {
+        let lane = self.shared.lanes.lock().unwrap().index_of(source);
+        FunnelInlet {
+            shared: self.shared.clone(),
+            lane,
+        }
+    }
+
+    /// Stops accepting FlowFiles on every inlet. Anything already queued can
+    /// still be received; after that `receive` reports
+    /// `ConnectionError::Closed`.
+    pub fn close(&self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// FlowFiles queued per source, in the order the sources joined.
+    pub fn lane_depths(&self) -> Vec<(String, usize)> {
+        let lanes = self.shared.lanes.lock().unwrap();
+        lanes
+            .lanes
+            .iter()
+            .map(|lane| (lane.source.clone(), lane.queue.len()))
+            .collect()
+    }
+}
+
+impl Default for FunnelConnection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait::async_trait]
+impl Connection for FunnelConnection {
+    async fn send(&self, flowfile: FlowFile) -> Result<(), ConnectionError> {
+        let lane = self.shared.lanes.lock().unwrap().index_of("");
+        self.shared.send(lane, flowfile)
+    }
+
+    async fn receive(&self) -> Result<Option<FlowFile>, ConnectionError> {
+        self.shared.receive()
+    }
+
+    fn len(&self) -> usize {
+        self.shared.depth.load(Ordering::SeqCst)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.shared.bytes.load(Ordering::SeqCst)
+    }
+
+    fn snapshot(&self) -> Vec<FlowFile> {
+        self.shared.snapshot()
+    }
+
+    fn is_full(&self) -> bool {
+        self.shared.is_full()
+    }
+}
+
+/// One upstream's end of a [`FunnelConnection`]. Receiving from an inlet
+/// receives from the whole funnel, and its fullness is the funnel's, so the
+/// scheduler holds back every source once the funnel is full. Its length,
+/// size and snapshot cover only its own lane, so adding up the inlets of a
+/// funnel, or checkpointing each of them, counts every FlowFile once.
+pub struct FunnelInlet {
+    shared: Arc<Shared>,
+    lane: usize,
+}
+
+#[async_trait::async_trait]
+impl Connection for FunnelInlet {
+    async fn send(&self, flowfile: FlowFile) -> Result<(), ConnectionError> {
+        self.shared.send(self.lane, flowfile)
+    }
+
+    async fn receive(&self) -> Result<Option<FlowFile>, ConnectionError> {
+        self.shared.receive()
+    }
+
+    fn len(&self) -> usize {
+        self.shared.lane_len(self.lane)
+    }
+
+    fn size_bytes(&self) -> u64 {
+        self.shared.lane_bytes(self.lane)
+    }
+
+    fn snapshot(&self) -> Vec<FlowFile> {
+        self.shared.lane_snapshot(self.lane)
+    }
+
+    fn is_full(&self) -> bool {
+        self.shared.is_full()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(source: &str, i: usize) -> FlowFile {
+        FlowFile::with_content(format!("{}-{}", source, i))
+    }
+
+    fn source_of(flowfile: &FlowFile) -> String {
+        String::from_utf8_lossy(&flowfile.content().unwrap())
+            .split('-')
+            .next()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_two_producers_one_consumer() {
+        let funnel = Arc::new(FunnelConnection::new());
+        let producers: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|source| {
+                let inlet = funnel.inlet(source);
+                tokio::spawn(async move {
+                    for i in 0..100 {
+                        inlet.send(tagged(source, i)).await.unwrap();
+                        tokio::task::yield_now().await;
+                    }
+                })
+            })
+            .collect();
+        let consumer = {
+            let funnel = funnel.clone();
+            tokio::spawn(async move {
+                let mut received = Vec::new();
+                while received.len() < 200 {
+                    match funnel.receive().await.unwrap() {
+                        Some(flowfile) => received.push(flowfile),
+                        None => tokio::task::yield_now().await,
+                    }
+                }
+                received
+            })
+        };
+        for producer in producers {
+            producer.await.unwrap();
+        }
+        let received = consumer.await.unwrap();
+
+        // Everything arrived, each source in its own order.
+        for source in ["a", "b"] {
+            let contents: Vec<String> = received
+                .iter()
+                .filter(|f| source_of(f) == source)
+                .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+                .collect();
+            let expected: Vec<String> = (0..100).map(|i| format!("{}-{}", source, i)).collect();
+            assert_eq!(contents, expected);
+        }
+        assert!(funnel.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flooding_source_does_not_starve_others() {
+        let funnel = FunnelConnection::new();
+        let (busy, quiet) = (funnel.inlet("busy"), funnel.inlet("quiet"));
+        for i in 0..100 {
+            busy.send(tagged("busy", i)).await.unwrap();
+        }
+        for i in 0..3 {
+            quiet.send(tagged("quiet", i)).await.unwrap();
+        }
+
+        let first: Vec<String> = funnel
+            .receive_batch(6)
+            .await
+            .unwrap()
+            .iter()
+            .map(source_of)
+            .collect();
+        assert_eq!(
+            first,
+            vec!["busy", "quiet", "busy", "quiet", "busy", "quiet"]
+        );
+        assert_eq!(
+            funnel.lane_depths(),
+            vec![("busy".to_string(), 97), ("quiet".to_string(), 0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backpressure_covers_every_inlet() {
+        let funnel = FunnelConnection::with_backpressure(3);
+        let (a, b) = (funnel.inlet("a"), funnel.inlet("b"));
+        a.send(tagged("a", 0)).await.unwrap();
+        a.send(tagged("a", 1)).await.unwrap();
+        assert!(!b.is_full());
+        b.send(tagged("b", 0)).await.unwrap();
+        assert!(a.is_full() && b.is_full() && funnel.is_full());
+        assert_eq!((a.len(), b.len(), funnel.len()), (2, 1, 3));
+
+        funnel.receive().await.unwrap().unwrap();
+        assert!(!a.is_full());
+    }
+
+    #[tokio::test]
+    async fn test_close_stops_every_inlet() {
+        let funnel = FunnelConnection::new();
+        let inlet = funnel.inlet("a");
+        inlet.send(tagged("a", 0)).await.unwrap();
+        funnel.close();
+        assert_eq!(
+            inlet.send(tagged("a", 1)).await.unwrap_err(),
+            ConnectionError::Closed
+        );
+        assert!(funnel.receive().await.unwrap().is_some());
+        assert_eq!(funnel.receive().await.unwrap_err(), ConnectionError::Closed);
+    }
+}
@@ -0,0 +1,2823 @@
+use crate::bulletin::{Bulletin, BulletinRepository, DEFAULT_BULLETIN_CAPACITY};
+use crate::checkpoint::{self, CheckpointError, Manifest};
+use crate::clock::{Clock, SystemClock};
+use crate::connection::funnel::FunnelConnection;
+use crate::connection::{Connection, MemoryConnection};
+use crate::cron::{CronSchedule, CRON_EXPRESSION};
+use crate::flow::{
+    parse_concurrent_tasks, parse_execution_timeout, ConnectionDefinition, FlowDefinition,
+    ProcessorNode, CONCURRENT_TASKS, EXECUTION_TIMEOUT,
+};
+use crate::flowfile::limits::AttributeLimits;
+use crate::flowfile::FlowFile;
+use crate::logging::{LogLevel, Logger, StdoutLogger};
+use crate::metrics::{
+    ConnectionMetrics, ConnectionStatus, CounterSnapshot, DepthHistory, DepthSample, FlowStatus,
+    MetricsSnapshot, ProcessorCounters, ProcessorMetrics, ProcessorState, ProcessorStatus,
+    SNAPSHOT_HISTORY_SAMPLES,
+};
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::provenance::ProvenanceEventType;
+use crate::service::ControllerServices;
+use crate::session::ProcessSession;
+use crate::state::StateManager;
+use crate::validation::{validate, ValidationError};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{
+    Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard, OwnedRwLockWriteGuard,
+    RwLock as AsyncRwLock,
+};
+use tokio::task::JoinHandle;
+
+// How long a processor with nothing to do waits before checking again.
+const IDLE_YIELD: Duration = Duration::from_millis(10);
+
+// How long a processor waits after a retryable error unless configured otherwise.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How often connection depths are sampled unless configured otherwise.
+pub const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Depth samples kept per connection unless configured otherwise: five
+/// minutes at the default interval.
+pub const DEFAULT_HISTORY_CAPACITY: usize = 60;
+
+// Longest a cron-scheduled processor sleeps before re-reading the clock, so
+// stopping the flow and clock adjustments are noticed promptly.
+const CRON_POLL: Duration = Duration::from_millis(100);
+
+/// Runtime view of a started flow, shared by the controller, its scheduling
+/// tasks and anything observing the flow from outside (such as the control API).
+pub struct FlowState {
+    processors: RwLock<Vec<ProcessorHandle>>,
+    connections: RwLock<Vec<ConnectionHandle>>,
+    bulletins: Arc<BulletinRepository>,
+    // Held by whoever is write-locking several wirings at once, so two of
+    // them never each hold a lock the other is waiting for.
+    quiescing: AsyncMutex<()>,
+}
+
+struct ProcessorHandle {
+    name: String,
+    processor_type: String,
+    properties: BTreeMap<String, String>,
+    counters: Arc<ProcessorCounters>,
+    enabled: Arc<AtomicBool>,
+    wiring: Arc<AsyncRwLock<Wiring>>,
+}
+
+struct ConnectionHandle {
+    name: String,
+    source: String,
+    relationship: String,
+    destination: String,
+    connection: Arc<dyn Connection>,
+    history: Arc<DepthHistory>,
+}
+
+impl FlowState {
+    fn new(bulletin_capacity: usize) -> Self {
+        Self {
+            processors: RwLock::new(Vec::new()),
+            connections: RwLock::new(Vec::new()),
+            bulletins: Arc::new(BulletinRepository::new(bulletin_capacity)),
+            quiescing: AsyncMutex::new(()),
+        }
+    }
+
+    pub fn bulletins(&self) -> &BulletinRepository {
+        &self.bulletins
+    }
+
+    pub fn connection(&self, name: &str) -> Option<Arc<dyn Connection>> {
+        self.connections
+            .read()
+            .unwrap()
+            .iter()
+            .find(|handle| handle.name == name)
+            .map(|handle| handle.connection.clone())
+    }
+
+    // The counters of the running processor named `name`.
+    fn counters(&self, name: &str) -> Option<CounterSnapshot> {
+        self.processors
+            .read()
+            .unwrap()
+            .iter()
+            .find(|handle| handle.name == name)
+            .map(|handle| handle.counters.snapshot())
+    }
+
+    pub fn metrics(&self) -> MetricsSnapshot {
+        let processors = self
+            .processors
+            .read()
+            .unwrap()
+            .iter()
+            .map(|handle| ProcessorMetrics {
+                name: handle.name.clone(),
+                processor_type: handle.processor_type.clone(),
+                state: if handle.enabled.load(Ordering::SeqCst) {
+                    ProcessorState::Running
+                } else {
+                    ProcessorState::Stopped
+                },
+                properties: handle.properties.clone(),
+                triggers: handle.counters.triggers(),
+                failures: handle.counters.failures(),
+                processed: handle.counters.processed(),
+                transferred: handle.counters.transferred(),
+            })
+            .collect();
+        let connections = self
+            .connections
+            .read()
+            .unwrap()
+            .iter()
+            .map(|handle| ConnectionMetrics {
+                name: handle.name.clone(),
+                queue_depth: handle.connection.len(),
+                backpressured: handle.connection.is_full(),
+                history: handle.history.recent(SNAPSHOT_HISTORY_SAMPLES),
+            })
+            .collect();
+        MetricsSnapshot {
+            processors,
+            connections,
+            bulletin_count: self.bulletins.len(),
+        }
+    }
+
+    // Write-locks every processor's wiring, which waits for the triggers in
+    // flight to settle their sessions and holds off new ones until the
+    // guards are dropped.
+    async fn quiesce(&self) -> (AsyncMutexGuard<'_, ()>, Vec<OwnedRwLockWriteGuard<Wiring>>) {
+        let quiescing = self.quiescing.lock().await;
+        let wirings: Vec<Arc<AsyncRwLock<Wiring>>> = self
+            .processors
+            .read()
+            .unwrap()
+            .iter()
+            .map(|handle| handle.wiring.clone())
+            .collect();
+        let mut guards = Vec::new();
+        for wiring in wirings {
+            guards.push(wiring.write_owned().await);
+        }
+        (quiescing, guards)
+    }
+
+    /// Processors, connections and queue depths of the running flow, all as
+    /// of one moment. Triggers are quiesced while it is taken, as for
+    /// `FlowController::checkpoint`, so every FlowFile is counted in exactly
+    /// one queue and each queue's depth and size agree. Waits for a trigger
+    /// that is still running to finish.
+    pub async fn status(&self) -> FlowStatus {
+        let _quiesced = self.quiesce().await;
+        let processors = self.processors.read().unwrap();
+        let connections = self.connections.read().unwrap();
+        FlowStatus {
+            processors: processors
+                .iter()
+                .map(|processor| ProcessorStatus {
+                    name: processor.name.clone(),
+                    processor_type: processor.processor_type.clone(),
+                    state: if processor.enabled.load(Ordering::SeqCst) {
+                        ProcessorState::Running
+                    } else {
+                        ProcessorState::Stopped
+                    },
+                    incoming: connections
+                        .iter()
+                        .filter(|handle| handle.destination == processor.name)
+                        .map(|handle| handle.name.clone())
+                        .collect(),
+                    outgoing: connections
+                        .iter()
+                        .filter(|handle| handle.source == processor.name)
+                        .map(|handle| handle.name.clone())
+                        .collect(),
+                })
+                .collect(),
+            connections: connections
+                .iter()
+                .map(|handle| ConnectionStatus {
+                    name: handle.name.clone(),
+                    source: handle.source.clone(),
+                    relationship: handle.relationship.clone(),
+                    destination: handle.destination.clone(),
+                    queue_depth: handle.connection.len(),
+                    queued_bytes: handle.connection.size_bytes(),
+                    expired: handle.connection.expired(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Retained depth samples of a connection, oldest first.
+    pub fn connection_history(&self, name: &str) -> Option<Vec<DepthSample>> {
+        self.connections
+            .read()
+            .unwrap()
+            .iter()
+            .find(|handle| handle.name == name)
+            .map(|handle| handle.history.samples())
+    }
+
+    // Records the current depth of every connection. Depth and size are
+    // read without locking the queues, so senders and receivers carry on.
+    fn sample(&self, timestamp: SystemTime) {
+        for handle in self.connections.read().unwrap().iter() {
+            handle.history.record(DepthSample {
+                timestamp,
+                depth: handle.connection.len(),
+                bytes: handle.connection.size_bytes(),
+            });
+        }
+    }
+
+    /// Pauses scheduling of one processor; its incoming queues keep filling.
+    /// Returns false if no processor has that name.
+    pub fn stop_processor(&self, name: &str) -> bool {
+        self.set_enabled(name, false)
+    }
+
+    pub fn start_processor(&self, name: &str) -> bool {
+        self.set_enabled(name, true)
+    }
+
+    fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        match self
+            .processors
+            .read()
+            .unwrap()
+            .iter()
+            .find(|handle| handle.name == name)
+        {
+            Some(handle) => {
+                handle.enabled.store(enabled, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Owns a flow and schedules its processors on the tokio runtime.
+pub struct FlowController {
+    flow: FlowDefinition,
+    state: Arc<FlowState>,
+    running: Arc<AtomicBool>,
+    tasks: HashMap<String, ProcessorTask>,
+    state_manager: Option<Arc<dyn StateManager>>,
+    services: Arc<ControllerServices>,
+    dead_letter: Arc<MemoryConnection>,
+    pub(crate) logger: Arc<dyn Logger>,
+    clock: Arc<dyn Clock>,
+    retry_delay: Duration,
+    sample_interval: Duration,
+    history_capacity: usize,
+    sampler: Option<JoinHandle<()>>,
+    // FlowFiles loaded by `restore`, queued again by the next `start`.
+    restored: HashMap<String, Vec<FlowFile>>,
+    // Processor counters loaded by `restore`, carried on by the next `start`.
+    restored_counters: HashMap<String, CounterSnapshot>,
+    // Funnels of the running flow by name, shared by their connections.
+    funnels: Mutex<HashMap<String, Arc<FunnelConnection>>>,
+    pub(crate) api: Option<JoinHandle<()>>,
+}
+
+/// What a `run_to_completion` call did.
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    /// FlowFiles each processor transferred, auto-terminated ones included.
+    pub processed: BTreeMap<String, u64>,
+    pub elapsed: Duration,
+    /// Bulletins raised during the run, oldest first.
+    pub errors: Vec<Bulletin>,
+    /// False if the timeout expired before the flow drained.
+    pub completed: bool,
+}
+
+/// Why a change to a running (or not yet started) flow was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlowChangeError {
+    DuplicateName(String),
+    UnknownProcessor(String),
+    UnknownConnection(String),
+    Invalid(Vec<ValidationError>),
+    ProcessorInUse {
+        processor: String,
+        connections: Vec<String>,
+    },
+    ConnectionNotEmpty {
+        connection: String,
+        queued: usize,
+    },
+}
+
+impl fmt::Display for FlowChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlowChangeError::DuplicateName(name) => {
+                write!(f, "'{}' is already part of the flow", name)
+            }
+            FlowChangeError::UnknownProcessor(name) => write!(f, "no processor named '{}'", name),
+            FlowChangeError::UnknownConnection(name) => write!(f, "no connection named '{}'", name),
+            FlowChangeError::Invalid(errors) => {
+                let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+            FlowChangeError::ProcessorInUse {
+                processor,
+                connections,
+            } => write!(
+                f,
+                "{} is still connected through {}",
+                processor,
+                connections.join(", ")
+            ),
+            FlowChangeError::ConnectionNotEmpty { connection, queued } => {
+                write!(
+                    f,
+                    "connection '{}' still holds {} FlowFiles",
+                    connection, queued
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for FlowChangeError {}
+
+// The queues a processor reads from and writes to, by connection name. A
+// trigger holds a read lock from building its session until the session is
+// settled, so rewiring under the write lock is atomic for sessions and waits
+// for any trigger in flight.
+#[derive(Default)]
+struct Wiring {
+    incoming: Vec<NamedConnection>,
+    outgoing: HashMap<String, Vec<NamedConnection>>,
+}
+
+type NamedConnection = (String, Arc<dyn Connection>);
+
+impl Wiring {
+    fn outgoing(&self) -> HashMap<String, Vec<Arc<dyn Connection>>> {
+        self.outgoing
+            .iter()
+            .map(|(relationship, queues)| {
+                (
+                    relationship.clone(),
+                    queues.iter().map(|(_, q)| q.clone()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn detach(&mut self, connection: &str) {
+        self.incoming.retain(|(name, _)| name != connection);
+        for queues in self.outgoing.values_mut() {
+            queues.retain(|(name, _)| name != connection);
+        }
+        // A relationship left with no queues must fail the commit rather
+        // than silently send FlowFiles nowhere.
+        self.outgoing.retain(|_, queues| !queues.is_empty());
+    }
+}
+
+struct ProcessorTask {
+    // One scheduling loop per concurrent task.
+    handles: Vec<JoinHandle<()>>,
+    alive: Arc<AtomicBool>,
+    wiring: Arc<AsyncRwLock<Wiring>>,
+    downstream: Arc<RwLock<Vec<Arc<dyn Connection>>>>,
+}
+
+struct ScheduledProcessor {
+    processor: Arc<dyn Processor>,
+    context: Arc<ProcessorContext>,
+    wiring: Arc<AsyncRwLock<Wiring>>,
+    // Every queue reachable from a source, see `refresh_downstream`.
+    downstream: Arc<RwLock<Vec<Arc<dyn Connection>>>>,
+    alive: Arc<AtomicBool>,
+    auto_terminated: HashSet<String>,
+    run_schedule: Duration,
+    cron: Option<CronSchedule>,
+    execution_timeout: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    retry_delay: Duration,
+    counters: Arc<ProcessorCounters>,
+    enabled: Arc<AtomicBool>,
+    bulletins: Arc<BulletinRepository>,
+    attribute_limits: Option<AttributeLimits>,
+    dead_letter: Arc<dyn Connection>,
+    logger: Arc<dyn Logger>,
+}
+
+impl FlowController {
+    pub fn new(flow: FlowDefinition) -> Self {
+        Self {
+            flow,
+            state: Arc::new(FlowState::new(DEFAULT_BULLETIN_CAPACITY)),
+            running: Arc::new(AtomicBool::new(false)),
+            tasks: HashMap::new(),
+            state_manager: None,
+            services: Arc::new(ControllerServices::new()),
+            dead_letter: Arc::new(MemoryConnection::new()),
+            logger: Arc::new(StdoutLogger::new()),
+            clock: Arc::new(SystemClock),
+            retry_delay: DEFAULT_RETRY_DELAY,
+            sample_interval: DEFAULT_SAMPLE_INTERVAL,
+            history_capacity: DEFAULT_HISTORY_CAPACITY,
+            sampler: None,
+            restored: HashMap::new(),
+            restored_counters: HashMap::new(),
+            funnels: Mutex::default(),
+            api: None,
+        }
+    }
+
+    /// Gives every processor this state manager instead of its own in-memory
+    /// one, e.g. a `FileStateManager` so state survives restarts.
+    pub fn with_state_manager(mut self, state_manager: Arc<dyn StateManager>) -> Self {
+        self.state_manager = Some(state_manager);
+        self
+    }
+
+    /// The controller services every processor of the flow shares. Services
+    /// added here before `start` are seen by the processors from their first
+    /// trigger.
+    pub fn services(&self) -> Arc<ControllerServices> {
+        self.services.clone()
+    }
+
+    /// FlowFiles routed to failure by a processor whose failure relationship
+    /// is neither connected nor auto-terminated, e.g. one added to the
+    /// running flow before its failure connection. Each carries a
+    /// `dead.letter.reason` attribute. The queue may be drained to reprocess
+    /// them.
+    pub fn dead_letters(&self) -> Arc<dyn Connection> {
+        self.dead_letter.clone()
+    }
+
+    /// Where processors' log messages and bulletins are written; stdout and
+    /// stderr by default. Only effective before `start`.
+    pub fn with_logger(mut self, logger: Arc<dyn Logger>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Clock used to work out cron fire times, timestamp FlowFiles, bulletins
+    /// and provenance, and time penalties and queue expiration. Only
+    /// effective before `start`.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// How long a processor waits before being triggered again after it
+    /// returned `ProcessorError::Retryable`.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Samples every connection's depth each `interval`, keeping the last
+    /// `capacity` samples per connection. A zero interval or capacity turns
+    /// sampling off. Only effective before `start`.
+    pub fn with_history(mut self, interval: Duration, capacity: usize) -> Self {
+        self.sample_interval = interval;
+        self.history_capacity = capacity;
+        self
+    }
+
+    /// Changes how many bulletins are retained, none for 0. Only effective
+    /// before `start`.
+    pub fn with_bulletin_capacity(mut self, capacity: usize) -> Self {
+        self.state = Arc::new(FlowState::new(capacity));
+        self
+    }
+
+    pub fn flow(&self) -> &FlowDefinition {
+        &self.flow
+    }
+
+    pub fn state(&self) -> Arc<FlowState> {
+        self.state.clone()
+    }
+
+    pub fn connection(&self, name: &str) -> Option<Arc<dyn Connection>> {
+        self.state.connection(name)
+    }
+
+    pub fn connection_history(&self, name: &str) -> Option<Vec<DepthSample>> {
+        self.state.connection_history(name)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Recent bulletins across all processors, newest first.
+    pub fn bulletins(&self) -> Vec<Bulletin> {
+        self.state.bulletins.recent()
+    }
+
+    pub fn bulletins_for(&self, processor: &str) -> Vec<Bulletin> {
+        self.state.bulletins.for_processor(processor)
+    }
+
+    pub async fn status(&self) -> FlowStatus {
+        self.state.status().await
+    }
+
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.state.metrics()
+    }
+
+    pub fn stop_processor(&self, name: &str) -> bool {
+        self.state.stop_processor(name)
+    }
+
+    pub fn start_processor(&self, name: &str) -> bool {
+        self.state.start_processor(name)
+    }
+
+    /// Validates the flow and, if it is sound, spawns one task per processor.
+    /// Must be called from within a tokio runtime.
+    pub fn start(&mut self) -> Result<(), Vec<ValidationError>> {
+        let errors = validate(&self.flow);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        if self.is_running() {
+            return Ok(());
+        }
+
+        self.funnels.lock().unwrap().clear();
+        let connections: HashMap<String, Arc<dyn Connection>> = self
+            .flow
+            .connections
+            .iter()
+            .map(|definition| (definition.name.clone(), self.new_connection(definition)))
+            .collect();
+        for (name, flowfiles) in self.restored.drain() {
+            for flowfile in flowfiles {
+                // Nothing else holds the new queues yet, so this cannot fail.
+                let _ = futures::executor::block_on(connections[&name].send(flowfile));
+            }
+        }
+        let connection_handles: Vec<ConnectionHandle> = self
+            .flow
+            .connections
+            .iter()
+            .map(|definition| ConnectionHandle {
+                name: definition.name.clone(),
+                source: definition.source.clone(),
+                relationship: definition.relationship.clone(),
+                destination: definition.destination.clone(),
+                connection: connections[&definition.name].clone(),
+                history: Arc::new(DepthHistory::new(self.history_capacity)),
+            })
+            .collect();
+
+        self.running.store(true, Ordering::SeqCst);
+        let mut handles = Vec::new();
+        for node in &self.flow.processors {
+            let mut wiring = Wiring::default();
+            for definition in &self.flow.connections {
+                attach(
+                    &mut wiring,
+                    node.name(),
+                    definition,
+                    &connections[&definition.name],
+                );
+            }
+            let (handle, task) = self.schedule(node, wiring);
+            handles.push(handle);
+            self.tasks.insert(node.name().to_string(), task);
+        }
+        self.restored_counters.clear();
+        {
+            // Both lists at once, in the order `status` reads them, so no
+            // status sees the connections without their processors.
+            let mut processors = self.state.processors.write().unwrap();
+            let mut connections = self.state.connections.write().unwrap();
+            *processors = handles;
+            *connections = connection_handles;
+        }
+        self.refresh_downstream();
+
+        let (state, clock, interval) =
+            (self.state.clone(), self.clock.clone(), self.sample_interval);
+        if !interval.is_zero() && self.history_capacity > 0 {
+            self.sampler = Some(tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    state.sample(clock.now());
+                }
+            }));
+        }
+        Ok(())
+    }
+
+    /// Runs a finite flow: triggers its sources (processors without incoming
+    /// connections) until each reports `is_exhausted`, waits for every queue
+    /// to drain, then stops the flow. Gives up after `timeout`.
+    pub async fn run_to_completion(
+        &mut self,
+        timeout: Duration,
+    ) -> Result<RunSummary, Vec<ValidationError>> {
+        let started = Instant::now();
+        self.start()?;
+        let completed = tokio::time::timeout(timeout, self.wait_until_drained())
+            .await
+            .is_ok();
+        self.stop().await;
+
+        let mut errors = self.bulletins();
+        errors.reverse();
+        Ok(RunSummary {
+            processed: self
+                .metrics()
+                .processors
+                .into_iter()
+                .map(|processor| (processor.name, processor.processed))
+                .collect(),
+            elapsed: started.elapsed(),
+            errors,
+            completed,
+        })
+    }
+
+    async fn wait_until_drained(&self) {
+        let sources: Vec<&ProcessorNode> = self
+            .flow
+            .processors
+            .iter()
+            .filter(|node| {
+                !self
+                    .flow
+                    .connections
+                    .iter()
+                    .any(|c| c.destination == node.name())
+            })
+            .collect();
+        loop {
+            tokio::time::sleep(IDLE_YIELD).await;
+            let mut exhausted = true;
+            for node in &sources {
+                if node.processor.is_exhausted() {
+                    self.stop_processor(node.name());
+                } else {
+                    exhausted = false;
+                }
+            }
+            if !exhausted {
+                continue;
+            }
+            // With every wiring locked no trigger is in flight, so an empty
+            // queue cannot be refilled by a session about to commit.
+            let _quiesced = self.state.quiesce().await;
+            if self
+                .state
+                .connections
+                .read()
+                .unwrap()
+                .iter()
+                .all(|handle| handle.connection.is_empty())
+            {
+                return;
+            }
+        }
+    }
+
+    /// Saves everything queued on the flow's connections and each
+    /// processor's state into `dir`, along with a manifest describing the
+    /// flow. Triggers are quiesced while the checkpoint is taken, so it
+    /// reflects settled sessions only. The flow keeps running afterwards.
+    pub async fn checkpoint(&self, dir: impl AsRef<Path>) -> Result<Manifest, CheckpointError> {
+        let dir = dir.as_ref();
+        let _quiesced = self.state.quiesce().await;
+
+        fs::create_dir_all(dir)?;
+        let mut manifest = Manifest::for_flow(&self.flow, self.clock.now());
+        for entry in &mut manifest.connections {
+            let connection = self
+                .state
+                .connection(&entry.name)
+                .ok_or(CheckpointError::NotStarted)?;
+            let flowfiles = connection.snapshot();
+            checkpoint::write_queue(&dir.join(&entry.queue_file), &flowfiles)?;
+            entry.queued = flowfiles.len();
+        }
+        for (node, entry) in self.flow.processors.iter().zip(&mut manifest.processors) {
+            entry.counters = self
+                .state
+                .counters(node.name())
+                .ok_or(CheckpointError::NotStarted)?;
+            let context = self.context_for(node);
+            let state = context.state_manager.get_state(&context.processor_name)?;
+            checkpoint::write_state(&dir.join(&entry.state_file), &state)?;
+        }
+        manifest.write(dir)?;
+        Ok(manifest)
+    }
+
+    /// Loads a checkpoint taken by `checkpoint` from a flow with the same
+    /// processors and connections. Processor state is restored right away;
+    /// the saved FlowFiles are queued when the flow next starts.
+    pub fn restore(&mut self, dir: impl AsRef<Path>) -> Result<Manifest, CheckpointError> {
+        if self.is_running() {
+            return Err(CheckpointError::Running);
+        }
+        let dir = dir.as_ref();
+        let manifest = Manifest::read(dir)?;
+        let differences = manifest.differences(&self.flow);
+        if !differences.is_empty() {
+            return Err(CheckpointError::TopologyMismatch(differences));
+        }
+
+        let mut restored = HashMap::new();
+        for entry in &manifest.connections {
+            restored.insert(
+                entry.name.clone(),
+                checkpoint::read_queue(&dir.join(&entry.queue_file))?,
+            );
+        }
+        for entry in &manifest.processors {
+            let state = checkpoint::read_state(&dir.join(&entry.state_file))?;
+            let node = self
+                .flow
+                .processor(&entry.name)
+                .expect("topology already checked");
+            let context = self.context_for(node);
+            context
+                .state_manager
+                .set_state(&context.processor_name, state)?;
+        }
+        self.restored = restored;
+        self.restored_counters = manifest
+            .processors
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.counters.clone()))
+            .collect();
+        Ok(manifest)
+    }
+
+    // The queue for `definition`: an inlet of its funnel if it names one,
+    // otherwise a queue of its own. FlowFiles that expire in it get a DROP
+    // event and a bulletin on the processor it feeds.
+    fn new_connection(&self, definition: &ConnectionDefinition) -> Arc<dyn Connection> {
+        if let Some(name) = &definition.funnel {
+            let mut funnels = self.funnels.lock().unwrap();
+            let funnel = funnels.entry(name.clone()).or_insert_with(|| {
+                let funnel = match definition.backpressure_threshold {
+                    Some(threshold) => FunnelConnection::with_backpressure(threshold),
+                    None => FunnelConnection::new(),
+                };
+                Arc::new(funnel.with_clock(self.clock.clone()))
+            });
+            return Arc::new(funnel.inlet(&definition.name));
+        }
+        let mut connection = MemoryConnection::with_limits(
+            definition.backpressure_threshold,
+            definition.backpressure_bytes,
+        )
+        .with_clock(self.clock.clone());
+        if let Some(expiration) = definition.expiration {
+            let (clock, logger, bulletins) = (
+                self.clock.clone(),
+                self.logger.clone(),
+                self.state.bulletins.clone(),
+            );
+            let (name, destination) = (definition.name.clone(), definition.destination.clone());
+            connection = connection
+                .with_expiration(expiration)
+                .on_expire(move |expired| {
+                    let now = clock.now();
+                    let message = format!(
+                        "{} FlowFile(s) expired in connection '{}' after {} ms",
+                        expired.len(),
+                        name,
+                        expiration.as_millis()
+                    );
+                    for mut flowfile in expired {
+                        flowfile.lineage_mut().record(
+                            ProvenanceEventType::Drop,
+                            &name,
+                            "expired",
+                            now,
+                        );
+                    }
+                    logger.log(LogLevel::Warn, &destination, &message);
+                    bulletins.add(Bulletin {
+                        processor: destination.clone(),
+                        timestamp: now,
+                        severity: LogLevel::Warn,
+                        message,
+                    });
+                });
+        }
+        Arc::new(connection)
+    }
+
+    // Spawns the scheduling tasks for one processor, as many as its
+    // `concurrent.tasks`. They share the processor, its wiring and its
+    // counters; each trigger gets a session of its own.
+    fn schedule(&self, node: &ProcessorNode, wiring: Wiring) -> (ProcessorHandle, ProcessorTask) {
+        let counters = Arc::new(match self.restored_counters.get(node.name()) {
+            Some(snapshot) => ProcessorCounters::restored(snapshot),
+            None => ProcessorCounters::default(),
+        });
+        let enabled = Arc::new(AtomicBool::new(true));
+        let alive = Arc::new(AtomicBool::new(true));
+        let wiring = Arc::new(AsyncRwLock::new(wiring));
+        let downstream = Arc::new(RwLock::new(Vec::new()));
+        let handle = ProcessorHandle {
+            name: node.name().to_string(),
+            processor_type: node.processor.get_name().to_string(),
+            properties: node.context.redacted_config(),
+            counters: counters.clone(),
+            enabled: enabled.clone(),
+            wiring: wiring.clone(),
+        };
+        let scheduled = ScheduledProcessor {
+            processor: node.processor.clone(),
+            context: Arc::new(self.context_for(node)),
+            wiring: wiring.clone(),
+            downstream: downstream.clone(),
+            alive: alive.clone(),
+            auto_terminated: node.auto_terminated.clone(),
+            run_schedule: node.run_schedule,
+            // Already checked by `validate`.
+            cron: node
+                .context
+                .get_property(CRON_EXPRESSION)
+                .and_then(|expression| CronSchedule::parse(expression).ok()),
+            execution_timeout: node
+                .context
+                .get_property(EXECUTION_TIMEOUT)
+                .and_then(|value| parse_execution_timeout(value).ok()),
+            clock: self.clock.clone(),
+            retry_delay: self.retry_delay,
+            counters,
+            enabled,
+            bulletins: self.state.bulletins.clone(),
+            attribute_limits: self
+                .flow
+                .attribute_limits
+                .as_ref()
+                .map(|limits| limits.for_relationships(&node.processor.relationships())),
+            dead_letter: self.dead_letter.clone(),
+            logger: self.logger.clone(),
+        };
+        // Already checked by `validate`.
+        let concurrency = node
+            .context
+            .get_property(CONCURRENT_TASKS)
+            .and_then(|value| parse_concurrent_tasks(value).ok())
+            .unwrap_or(1);
+        let scheduled = Arc::new(scheduled);
+        let task = ProcessorTask {
+            handles: (0..concurrency)
+                .map(|_| tokio::spawn(run_processor(scheduled.clone())))
+                .collect(),
+            alive,
+            wiring,
+            downstream,
+        };
+        (handle, task)
+    }
+
+    /// Adds a processor, scheduling it right away if the flow is running.
+    /// Its relationships need not be connected yet; until they are, FlowFiles
+    /// it routes to them stay in its incoming queues, except those routed to
+    /// failure, which go to the dead-letter queue.
+    pub fn add_processor(&mut self, node: ProcessorNode) -> Result<(), FlowChangeError> {
+        if self.flow.processor(node.name()).is_some() {
+            return Err(FlowChangeError::DuplicateName(node.name().to_string()));
+        }
+        let before = validate(&self.flow);
+        self.flow.add_processor(node);
+        let introduced = introduced_errors(&before, &self.flow);
+        if !introduced.is_empty() {
+            self.flow.processors.pop();
+            return Err(FlowChangeError::Invalid(introduced));
+        }
+        if self.is_running() {
+            let node = self.flow.processors.last().expect("just added");
+            let (handle, task) = self.schedule(node, Wiring::default());
+            self.tasks.insert(handle.name.clone(), task);
+            self.state.processors.write().unwrap().push(handle);
+            self.refresh_downstream();
+        }
+        Ok(())
+    }
+
+    /// Adds a connection. In a running flow both ends pick up the new queue
+    /// atomically, between two of their triggers.
+    pub async fn add_connection(
+        &mut self,
+        definition: ConnectionDefinition,
+    ) -> Result<(), FlowChangeError> {
+        if self
+            .flow
+            .connections
+            .iter()
+            .any(|c| c.name == definition.name)
+        {
+            return Err(FlowChangeError::DuplicateName(definition.name));
+        }
+        let before = validate(&self.flow);
+        self.flow.add_connection(definition.clone());
+        let introduced = introduced_errors(&before, &self.flow);
+        if !introduced.is_empty() {
+            self.flow.connections.pop();
+            return Err(FlowChangeError::Invalid(introduced));
+        }
+        if self.is_running() {
+            let connection = self.new_connection(&definition);
+            self.state
+                .connections
+                .write()
+                .unwrap()
+                .push(ConnectionHandle {
+                    name: definition.name.clone(),
+                    source: definition.source.clone(),
+                    relationship: definition.relationship.clone(),
+                    destination: definition.destination.clone(),
+                    connection: connection.clone(),
+                    history: Arc::new(DepthHistory::new(self.history_capacity)),
+                });
+            let endpoints = self.endpoints(&definition);
+            let _quiescing = self.state.quiescing.lock().await;
+            let mut guards = Vec::new();
+            for (name, wiring) in &endpoints {
+                guards.push((name, wiring.write().await));
+            }
+            for (name, wiring) in &mut guards {
+                attach(wiring, name, &definition, &connection);
+            }
+            self.refresh_downstream();
+        }
+        Ok(())
+    }
+
+    /// Removes a connection once no trigger is using it. A connection with
+    /// FlowFiles queued is refused unless `force` is set, in which case the
+    /// queued FlowFiles are dropped.
+    pub async fn remove_connection(
+        &mut self,
+        name: &str,
+        force: bool,
+    ) -> Result<(), FlowChangeError> {
+        let index = self
+            .flow
+            .connections
+            .iter()
+            .position(|c| c.name == name)
+            .ok_or_else(|| FlowChangeError::UnknownConnection(name.to_string()))?;
+        if self.is_running() {
+            let definition = self.flow.connections[index].clone();
+            let endpoints = self.endpoints(&definition);
+            let _quiescing = self.state.quiescing.lock().await;
+            let mut guards = Vec::new();
+            for (_, wiring) in &endpoints {
+                guards.push(wiring.write().await);
+            }
+            // No trigger can touch the queue while both ends are locked.
+            let queued = self.state.connection(name).map_or(0, |c| c.len());
+            if queued > 0 && !force {
+                return Err(FlowChangeError::ConnectionNotEmpty {
+                    connection: name.to_string(),
+                    queued,
+                });
+            }
+            for wiring in &mut guards {
+                wiring.detach(name);
+            }
+            self.state
+                .connections
+                .write()
+                .unwrap()
+                .retain(|handle| handle.name != name);
+        }
+        self.flow.connections.remove(index);
+        self.refresh_downstream();
+        Ok(())
+    }
+
+    /// Removes a processor after its current trigger, if any, has finished.
+    /// Its connections have to be removed first.
+    pub async fn remove_processor(&mut self, name: &str) -> Result<(), FlowChangeError> {
+        let index = self
+            .flow
+            .processors
+            .iter()
+            .position(|node| node.name() == name)
+            .ok_or_else(|| FlowChangeError::UnknownProcessor(name.to_string()))?;
+        let connections: Vec<String> = self
+            .flow
+            .connections
+            .iter()
+            .filter(|c| c.source == name || c.destination == name)
+            .map(|c| c.name.clone())
+            .collect();
+        if !connections.is_empty() {
+            return Err(FlowChangeError::ProcessorInUse {
+                processor: name.to_string(),
+                connections,
+            });
+        }
+        if let Some(task) = self.tasks.remove(name) {
+            task.alive.store(false, Ordering::SeqCst);
+            for handle in task.handles {
+                let _ = handle.await;
+            }
+        }
+        self.state
+            .processors
+            .write()
+            .unwrap()
+            .retain(|handle| handle.name != name);
+        self.flow.processors.remove(index);
+        Ok(())
+    }
+
+    // The wiring of each distinct processor at either end of `definition`.
+    fn endpoints(
+        &self,
+        definition: &ConnectionDefinition,
+    ) -> Vec<(String, Arc<AsyncRwLock<Wiring>>)> {
+        let mut names = vec![definition.source.clone()];
+        if definition.destination != definition.source {
+            names.push(definition.destination.clone());
+        }
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let wiring = self.tasks.get(&name)?.wiring.clone();
+                Some((name, wiring))
+            })
+            .collect()
+    }
+
+    // Points each source at every queue reachable from it, so it is not
+    // triggered while any of them is full. Other processors only look at
+    // their own outgoing queues.
+    fn refresh_downstream(&self) {
+        for (name, task) in &self.tasks {
+            let is_source = !self.flow.connections.iter().any(|c| &c.destination == name);
+            let downstream = if is_source {
+                reachable_connections(&self.flow, name)
+                    .iter()
+                    .filter_map(|connection| self.state.connection(connection))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            *task.downstream.write().unwrap() = downstream;
+        }
+    }
+
+    fn context_for(&self, node: &ProcessorNode) -> ProcessorContext {
+        let mut context = node.context.clone();
+        if let Some(state_manager) = &self.state_manager {
+            context.state_manager = state_manager.clone();
+        }
+        context.services = self.services.clone();
+        context
+    }
+
+    pub async fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(api) = self.api.take() {
+            api.abort();
+        }
+        if let Some(sampler) = self.sampler.take() {
+            sampler.abort();
+        }
+        for task in self.tasks.values() {
+            task.alive.store(false, Ordering::SeqCst);
+        }
+        for (_, task) in self.tasks.drain() {
+            for handle in task.handles {
+                let _ = handle.await;
+            }
+        }
+    }
+}
+
+// Adds `connection` to `processor`'s wiring at whichever ends it belongs.
+fn attach(
+    wiring: &mut Wiring,
+    processor: &str,
+    definition: &ConnectionDefinition,
+    connection: &Arc<dyn Connection>,
+) {
+    if definition.destination == processor {
+        wiring
+            .incoming
+            .push((definition.name.clone(), connection.clone()));
+    }
+    if definition.source == processor {
+        wiring
+            .outgoing
+            .entry(definition.relationship.clone())
+            .or_default()
+            .push((definition.name.clone(), connection.clone()));
+    }
+}
+
+// Names of the connections FlowFiles from `processor` can pass through.
+fn reachable_connections(flow: &FlowDefinition, processor: &str) -> Vec<String> {
+    let mut visited = HashSet::from([processor.to_string()]);
+    let mut pending = vec![processor.to_string()];
+    let mut reachable = Vec::new();
+    while let Some(current) = pending.pop() {
+        for connection in flow.connections.iter().filter(|c| c.source == current) {
+            reachable.push(connection.name.clone());
+            if visited.insert(connection.destination.clone()) {
+                pending.push(connection.destination.clone());
+            }
+        }
+    }
+    reachable
+}
+
+// Validation errors present in `flow` but not in `before`. Unconnected
+// relationships are allowed, since a running flow is rewired one
+// connection at a time.
+fn introduced_errors(before: &[ValidationError], flow: &FlowDefinition) -> Vec<ValidationError> {
+    validate(flow)
+        .into_iter()
+        .filter(|error| !matches!(error, ValidationError::UnconnectedRelationship { .. }))
+        .filter(|error| !before.contains(error))
+        .collect()
+}
+
+async fn run_processor(scheduled: Arc<ScheduledProcessor>) {
+    // A trigger that overran `execution.timeout` and is still running; this
+    // task starts no other until it returns and its session is rolled back.
+    let mut overrunning: Option<JoinHandle<()>> = None;
+    while scheduled.alive.load(Ordering::SeqCst) {
+        if !scheduled.enabled.load(Ordering::SeqCst)
+            || overrunning.as_ref().is_some_and(|t| !t.is_finished())
+        {
+            tokio::time::sleep(IDLE_YIELD).await;
+            continue;
+        }
+        overrunning = None;
+        if let Some(cron) = &scheduled.cron {
+            if !wait_for_fire_time(cron, scheduled.clock.as_ref(), &scheduled.alive).await {
+                break;
+            }
+        }
+        let wiring = scheduled.wiring.clone().read_owned().await;
+        let has_input =
+            wiring.incoming.is_empty() || wiring.incoming.iter().any(|(_, c)| !c.is_empty());
+        let saturated = scheduled
+            .downstream
+            .read()
+            .unwrap()
+            .iter()
+            .any(|c| c.is_full());
+        scheduled.context.set_backpressured(saturated);
+        let backpressured =
+            saturated || wiring.outgoing.values().flatten().any(|(_, c)| c.is_full());
+        if !has_input || backpressured {
+            drop(wiring);
+            tokio::time::sleep(IDLE_YIELD).await;
+            continue;
+        }
+
+        let mut session = ProcessSession::new(
+            &scheduled.context.processor_name,
+            wiring.incoming.iter().map(|(_, c)| c.clone()).collect(),
+            wiring.outgoing(),
+            scheduled.auto_terminated.clone(),
+        )
+        .with_clock(scheduled.clock.clone())
+        .with_dead_letter(scheduled.dead_letter.clone())
+        .with_logger(scheduled.logger.clone())
+        .with_bulletins(scheduled.bulletins.clone());
+        if let Some(limits) = &scheduled.attribute_limits {
+            session = session.with_attribute_limits(limits.clone());
+        }
+        session
+            .prefetch(scheduled.processor.batch_size(&scheduled.context))
+            .await;
+        let processor = scheduled.processor.clone();
+        let context = scheduled.context.clone();
+        let mut trigger = tokio::task::spawn_blocking(move || {
+            let outcome = catch_unwind(AssertUnwindSafe(|| {
+                processor.on_trigger(&context, &mut session)
+            }));
+            (session, outcome)
+        });
+        let joined = match scheduled.execution_timeout {
+            Some(limit) => match tokio::time::timeout(limit, &mut trigger).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    scheduled.counters.record_trigger();
+                    scheduled.report(
+                        LogLevel::Error,
+                        format!("on_trigger timed out after {:?}", limit),
+                    );
+                    // A blocking trigger cannot be cancelled, so its session
+                    // is rolled back whenever it does return. The wiring
+                    // stays locked until then, so anything that quiesces the
+                    // flow waits for the FlowFiles it is holding.
+                    overrunning = Some(tokio::spawn(async move {
+                        if let Ok((mut session, _)) = trigger.await {
+                            session.rollback().await;
+                        }
+                        drop(wiring);
+                    }));
+                    tokio::time::sleep(IDLE_YIELD).await;
+                    continue;
+                }
+            },
+            None => trigger.await,
+        };
+        let (mut session, outcome) = joined.expect("trigger task was cancelled");
+
+        scheduled.counters.record_trigger();
+        if matches!(outcome, Ok(Ok(()))) {
+            session.enforce_attribute_limits();
+            let transferred = session.transfer_counts();
+            match session.commit().await {
+                Ok(()) => {
+                    scheduled.counters.record_transfers(&transferred);
+                    scheduled.report_limit_violations(session.limit_violations());
+                }
+                Err(e) => {
+                    scheduled.report(LogLevel::Error, format!("session commit failed: {}", e));
+                    session.rollback().await;
+                }
+            }
+        } else {
+            session.rollback().await;
+        }
+        drop(wiring);
+
+        match outcome {
+            Ok(Ok(())) => {}
+            Ok(Err(ProcessorError::Yield(duration))) => {
+                tokio::time::sleep(duration).await;
+                continue;
+            }
+            Ok(Err(ProcessorError::Retryable(reason))) => {
+                scheduled.report(
+                    LogLevel::Warn,
+                    format!("retrying in {:?}: {}", scheduled.retry_delay, reason),
+                );
+                tokio::time::sleep(scheduled.retry_delay).await;
+                continue;
+            }
+            Ok(Err(ProcessorError::Fatal(reason))) => {
+                scheduled.report(LogLevel::Error, format!("stopped: {}", reason));
+                scheduled.enabled.store(false, Ordering::SeqCst);
+                continue;
+            }
+            Err(panic) => {
+                scheduled.report(
+                    LogLevel::Error,
+                    format!("on_trigger panicked: {}", panic_message(&*panic)),
+                );
+                tokio::time::sleep(IDLE_YIELD).await;
+            }
+        }
+
+        // A cron schedule decides for itself when to run next.
+        if scheduled.cron.is_some() {
+            continue;
+        }
+        if let Some(duration) = session.yield_duration() {
+            tokio::time::sleep(duration.max(scheduled.run_schedule)).await;
+        } else if !scheduled.run_schedule.is_zero() {
+            tokio::time::sleep(scheduled.run_schedule).await;
+        } else {
+            tokio::task::yield_now().await;
+        }
+    }
+}
+
+/// Sleeps until the next time matching `schedule`, returning false if the
+/// processor is stopped for good first.
+async fn wait_for_fire_time(
+    schedule: &CronSchedule,
+    clock: &dyn Clock,
+    alive: &AtomicBool,
+) -> bool {
+    let fire_at = schedule.next_after(clock.now());
+    while alive.load(Ordering::SeqCst) {
+        match fire_at.map(|fire_at| fire_at.duration_since(clock.now())) {
+            Some(Ok(remaining)) if !remaining.is_zero() => {
+                tokio::time::sleep(remaining.min(CRON_POLL)).await
+            }
+            Some(_) => return true,
+            None => tokio::time::sleep(CRON_POLL).await,
+        }
+    }
+    false
+}
+
+impl ScheduledProcessor {
+    fn report(&self, severity: LogLevel, message: String) {
+        self.counters.record_failure();
+        self.bulletin(severity, message);
+    }
+
+    // One warning per commit, however many FlowFiles it had to fix.
+    fn report_limit_violations(&self, violations: &[String]) {
+        let (Some(first), Some(limits)) = (violations.first(), &self.attribute_limits) else {
+            return;
+        };
+        self.bulletin(
+            LogLevel::Warn,
+            format!(
+                "attribute limits enforced ({}) on {} FlowFile(s): {}",
+                limits.policy,
+                violations.len(),
+                first
+            ),
+        );
+    }
+
+    fn bulletin(&self, severity: LogLevel, message: String) {
+        self.logger
+            .log(severity, &self.context.processor_name, &message);
+        self.bulletins.add(Bulletin {
+            processor: self.context.processor_name.clone(),
+            timestamp: self.clock.now(),
+            severity,
+            message,
+        });
+    }
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::flow::{ConnectionDefinition, ProcessorNode};
+    use crate::flowfile::limits::LimitPolicy;
+    use crate::flowfile::FlowFile;
+    use crate::logging::MemoryLogger;
+    use crate::processor::{FileProcessor, DEFAULT_BATCH_SIZE};
+    use crate::processor_context::ProcessorContext;
+    use crate::relationship::{self, Relationship};
+    use crate::session::DEAD_LETTER_REASON;
+    use std::sync::atomic::AtomicUsize;
+
+    struct AlwaysFails {
+        attempts: AtomicUsize,
+    }
+
+    impl Processor for AlwaysFails {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            panic!("attempt {} failed", attempt);
+        }
+
+        fn get_name(&self) -> &'static str {
+            "AlwaysFails"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    #[derive(Default)]
+    struct CountsTriggers {
+        triggers: Arc<AtomicUsize>,
+    }
+
+    impl Processor for CountsTriggers {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            self.triggers.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "CountsTriggers"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            Vec::new()
+        }
+    }
+
+    // Declares a success relationship but never produces anything, so tests
+    // can feed a connection by hand.
+    struct Idle;
+
+    impl Processor for Idle {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Idle"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    // Takes one FlowFile per trigger and either passes it on or fails with
+    // whatever `outcome` returns for that attempt.
+    struct Scripted {
+        attempts: Arc<AtomicUsize>,
+        outcome: fn(usize) -> Result<(), ProcessorError>,
+    }
+
+    impl Processor for Scripted {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let Some(flowfile) = session.get()? else {
+                return Ok(());
+            };
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            (self.outcome)(attempt)?;
+            session.transfer(flowfile, relationship::SUCCESS);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Scripted"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    // Starts `idle -> in -> scripted` with one FlowFile queued on `in`.
+    async fn start_scripted(
+        outcome: fn(usize) -> Result<(), ProcessorError>,
+        retry_delay: Duration,
+    ) -> (FlowController, Arc<AtomicUsize>) {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let scripted = Scripted {
+            attempts: attempts.clone(),
+            outcome,
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("scripted", scripted).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new(
+            "in", "idle", "success", "scripted",
+        ));
+        let mut controller = FlowController::new(flow).with_retry_delay(retry_delay);
+        controller.start().unwrap();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("payload"))
+            .await
+            .unwrap();
+        (controller, attempts)
+    }
+
+    async fn wait_for_attempts(attempts: &AtomicUsize, expected: usize) {
+        while attempts.load(Ordering::SeqCst) < expected {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_yield_rolls_back_and_retries_quietly() {
+        let (mut controller, attempts) = start_scripted(
+            |attempt| match attempt {
+                1 => Err(ProcessorError::Yield(Duration::from_millis(200))),
+                _ => Ok(()),
+            },
+            Duration::ZERO,
+        )
+        .await;
+
+        wait_for_attempts(&attempts, 1).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(controller.connection("in").unwrap().len(), 1);
+
+        wait_for_attempts(&attempts, 2).await;
+        controller.stop().await;
+        assert_eq!(controller.connection("in").unwrap().len(), 0);
+        assert!(controller.bulletins().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_retryable_error_warns_and_retries_after_delay() {
+        let (mut controller, attempts) = start_scripted(
+            |attempt| match attempt {
+                1 => Err(ProcessorError::Retryable("database locked".to_string())),
+                _ => Ok(()),
+            },
+            Duration::from_millis(200),
+        )
+        .await;
+
+        wait_for_attempts(&attempts, 1).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(controller.connection("in").unwrap().len(), 1);
+
+        wait_for_attempts(&attempts, 2).await;
+        controller.stop().await;
+        assert_eq!(controller.connection("in").unwrap().len(), 0);
+        let bulletins = controller.bulletins_for("scripted");
+        assert_eq!(bulletins.len(), 1);
+        assert_eq!(bulletins[0].severity, LogLevel::Warn);
+        assert_eq!(bulletins[0].message, "retrying in 200ms: database locked");
+    }
+
+    #[tokio::test]
+    async fn test_execution_timeout_rolls_back_and_continues() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let scripted = Scripted {
+            attempts: attempts.clone(),
+            outcome: |attempt| {
+                if attempt == 1 {
+                    std::thread::sleep(Duration::from_millis(300));
+                }
+                Ok(())
+            },
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(
+            ProcessorNode::new("scripted", scripted)
+                .auto_terminate("success")
+                .execution_timeout(Duration::from_millis(50)),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "in", "idle", "success", "scripted",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("payload"))
+            .await
+            .unwrap();
+
+        wait_for_attempts(&attempts, 1).await;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let bulletins = controller.bulletins_for("scripted");
+        assert_eq!(bulletins.len(), 1);
+        assert_eq!(bulletins[0].severity, LogLevel::Error);
+        assert_eq!(bulletins[0].message, "on_trigger timed out after 50ms");
+        // Still held by the overrunning trigger until it returns.
+        assert_eq!(controller.connection("in").unwrap().len(), 0);
+
+        // Rolled back when the first trigger returns, then passed on by the next.
+        wait_for_attempts(&attempts, 2).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        controller.stop().await;
+        assert_eq!(controller.connection("in").unwrap().len(), 0);
+        let scripted = controller
+            .metrics()
+            .processors
+            .into_iter()
+            .find(|p| p.name == "scripted")
+            .unwrap();
+        assert_eq!(scripted.failures, 1);
+        assert_eq!(scripted.transferred.get("success"), Some(&1));
+        assert_eq!(controller.bulletins_for("scripted").len(), 1);
+    }
+
+    // A flow whose "scripted" processor overruns its 50ms timeout on the
+    // first trigger, holding the FlowFile it took for 300ms.
+    fn overrunning_flow(attempts: &Arc<AtomicUsize>) -> FlowDefinition {
+        let scripted = Scripted {
+            attempts: attempts.clone(),
+            outcome: |attempt| {
+                if attempt == 1 {
+                    std::thread::sleep(Duration::from_millis(300));
+                }
+                Ok(())
+            },
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(
+            ProcessorNode::new("scripted", scripted)
+                .auto_terminate("success")
+                .execution_timeout(Duration::from_millis(50)),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "in", "idle", "success", "scripted",
+        ));
+        flow
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_waits_for_an_overrunning_trigger() {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-controller-{}-overrun",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mut controller = FlowController::new(overrunning_flow(&attempts));
+        controller.start().unwrap();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("payload"))
+            .await
+            .unwrap();
+        wait_for_attempts(&attempts, 1).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(controller.bulletins_for("scripted").len(), 1);
+
+        // Taken while the timed-out trigger still holds the FlowFile, so it
+        // waits for the rollback to put it back.
+        let manifest = controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+        assert_eq!(manifest.connections[0].queued, 1);
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mut controller = FlowController::new(overrunning_flow(&attempts));
+        controller.restore(&dir).unwrap();
+        controller.start().unwrap();
+        // Overruns again, then passes the FlowFile on.
+        wait_for_attempts(&attempts, 2).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        controller.stop().await;
+        let scripted = controller
+            .metrics()
+            .processors
+            .into_iter()
+            .find(|p| p.name == "scripted")
+            .unwrap();
+        assert_eq!(scripted.transferred.get("success"), Some(&1));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    // A source whose every trigger runs for 100ms, recording the most
+    // triggers ever running at once.
+    #[derive(Default)]
+    struct Overruns {
+        active: AtomicUsize,
+        most_active: Arc<AtomicUsize>,
+        triggers: Arc<AtomicUsize>,
+    }
+
+    impl Processor for Overruns {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            self.triggers.fetch_add(1, Ordering::SeqCst);
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Overruns"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_timed_out_trigger_finishes_before_the_next_starts() {
+        let overruns = Overruns::default();
+        let most_active = overruns.most_active.clone();
+        let triggers = overruns.triggers.clone();
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("overruns", overruns).execution_timeout(Duration::from_millis(10)),
+        );
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        wait_for_attempts(&triggers, 3).await;
+        controller.stop().await;
+
+        assert_eq!(most_active.load(Ordering::SeqCst), 1);
+        assert!(controller.bulletins_for("overruns").len() >= 2);
+    }
+
+    // Takes one FlowFile per trigger and holds it for a while, recording the
+    // most triggers ever running at once.
+    #[derive(Default)]
+    struct Slow {
+        active: AtomicUsize,
+        most_active: Arc<AtomicUsize>,
+    }
+
+    impl Processor for Slow {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let Some(flowfile) = session.get()? else {
+                return Ok(());
+            };
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.most_active.fetch_max(active, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            session.transfer(flowfile, relationship::SUCCESS);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Slow"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+
+        fn supports_concurrent_tasks(&self) -> bool {
+            true
+        }
+    }
+
+    // Runs 40 FlowFiles through `Slow` configured with `tasks` concurrent
+    // tasks, returning the most triggers seen running at once.
+    async fn most_concurrent_triggers(tasks: Option<usize>) -> usize {
+        let slow = Slow::default();
+        let most_active = slow.most_active.clone();
+        let mut node = ProcessorNode::new("slow", slow).auto_terminate("success");
+        if let Some(tasks) = tasks {
+            node = node.concurrent_tasks(tasks);
+        }
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(node);
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "slow"));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let queue = controller.connection("in").unwrap();
+        for i in 0..40 {
+            queue
+                .send(FlowFile::with_content(format!("file {}", i)))
+                .await
+                .unwrap();
+        }
+
+        let transferred = |controller: &FlowController| {
+            let slow = controller
+                .metrics()
+                .processors
+                .into_iter()
+                .find(|p| p.name == "slow")
+                .unwrap();
+            slow.transferred.get("success").copied().unwrap_or(0)
+        };
+        while transferred(&controller) < 40 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        controller.stop().await;
+        assert_eq!(transferred(&controller), 40);
+        assert!(queue.is_empty());
+        most_active.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_tasks_reach_configured_level() {
+        assert_eq!(most_concurrent_triggers(Some(4)).await, 4);
+        assert_eq!(most_concurrent_triggers(None).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fatal_error_stops_processor() {
+        let (mut controller, attempts) = start_scripted(
+            |attempt| match attempt {
+                1 => Err(ProcessorError::Fatal("bad configuration".to_string())),
+                _ => Ok(()),
+            },
+            Duration::ZERO,
+        )
+        .await;
+
+        wait_for_attempts(&attempts, 1).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let scripted = |controller: &FlowController| {
+            controller
+                .metrics()
+                .processors
+                .into_iter()
+                .find(|p| p.name == "scripted")
+                .unwrap()
+        };
+        assert_eq!(scripted(&controller).state, ProcessorState::Stopped);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(controller.connection("in").unwrap().len(), 1);
+        let bulletins = controller.bulletins_for("scripted");
+        assert_eq!(bulletins.len(), 1);
+        assert_eq!(bulletins[0].severity, LogLevel::Error);
+        assert_eq!(bulletins[0].message, "stopped: bad configuration");
+
+        assert!(controller.start_processor("scripted"));
+        wait_for_attempts(&attempts, 2).await;
+        controller.stop().await;
+        assert_eq!(scripted(&controller).state, ProcessorState::Running);
+        assert_eq!(controller.connection("in").unwrap().len(), 0);
+    }
+
+    // Emits FlowFiles numbered 0..limit, one per trigger.
+    struct Numbers {
+        next: AtomicUsize,
+        limit: usize,
+    }
+
+    impl Processor for Numbers {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let n = self.next.load(Ordering::SeqCst);
+            if n < self.limit {
+                let mut flowfile = session.create();
+                flowfile.set_content(n.to_string());
+                session.transfer(flowfile, relationship::SUCCESS);
+                self.next.store(n + 1, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Numbers"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    // Records the content of everything it receives, taking `delay` per trigger.
+    #[derive(Clone, Default)]
+    struct Collect {
+        seen: Arc<Mutex<Vec<String>>>,
+        delay: Duration,
+    }
+
+    impl Collect {
+        fn seen(&self) -> Vec<String> {
+            self.seen.lock().unwrap().clone()
+        }
+    }
+
+    impl Processor for Collect {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            std::thread::sleep(self.delay);
+            for flowfile in session.get_batch(DEFAULT_BATCH_SIZE)? {
+                self.seen
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8_lossy(&flowfile.content().unwrap()).into_owned());
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Collect"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+
+        fn batch_size(&self, _context: &ProcessorContext) -> usize {
+            DEFAULT_BATCH_SIZE
+        }
+    }
+
+    async fn eventually(condition: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !condition() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("condition never became true");
+    }
+
+    #[tokio::test]
+    async fn test_source_stalls_while_downstream_queue_is_full() {
+        let sink = Collect {
+            delay: Duration::from_millis(20),
+            ..Collect::default()
+        };
+        let numbers = Numbers {
+            next: AtomicUsize::new(0),
+            limit: usize::MAX,
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("numbers", numbers).run_schedule(Duration::from_millis(1)),
+        );
+        flow.add_processor(ProcessorNode::new("relay", Collect::default()));
+        flow.add_processor(ProcessorNode::new("sink", sink.clone()).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new(
+            "raw", "numbers", "success", "relay",
+        ));
+        flow.add_connection(
+            ConnectionDefinition::new("relayed", "relay", "success", "sink").with_backpressure(5),
+        );
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        controller.stop_processor("sink");
+
+        let relayed = controller.connection("relayed").unwrap();
+        eventually(|| relayed.is_full()).await;
+        let generated = |controller: &FlowController| {
+            controller
+                .metrics()
+                .processors
+                .into_iter()
+                .find(|p| p.name == "numbers")
+                .unwrap()
+                .processed
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let stalled = generated(&controller);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(generated(&controller), stalled);
+        assert!(controller
+            .flow()
+            .processor("numbers")
+            .unwrap()
+            .context
+            .is_backpressured());
+
+        controller.start_processor("sink");
+        eventually(|| generated(&controller) > stalled + 20).await;
+        controller.stop().await;
+        assert!(!sink.seen().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_graph_and_queue_depths() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("source", Idle));
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new(
+            "to-sink", "source", "success", "sink",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        controller.stop_processor("source");
+        let queue = controller.connection("to-sink").unwrap();
+        for content in ["a", "bb", "ccc"] {
+            queue.send(FlowFile::with_content(content)).await.unwrap();
+        }
+
+        let status = controller.status().await;
+        let names: Vec<&str> = status.processors.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["source", "sink"]);
+        assert_eq!(status.processors[0].outgoing, ["to-sink"]);
+        assert!(status.processors[0].incoming.is_empty());
+        assert_eq!(status.processors[1].incoming, ["to-sink"]);
+        assert_eq!(status.processors[0].state, ProcessorState::Stopped);
+        assert_eq!(status.processors[1].state, ProcessorState::Running);
+        assert_eq!(
+            status.connections,
+            vec![ConnectionStatus {
+                name: "to-sink".to_string(),
+                source: "source".to_string(),
+                relationship: "success".to_string(),
+                destination: "sink".to_string(),
+                queue_depth: 3,
+                queued_bytes: 6,
+                expired: 0,
+            }]
+        );
+        controller.stop().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_status_counts_each_flowfile_once_while_running() {
+        // Two processors pass the same FlowFiles back and forth, so however
+        // busy they are, the queues together always hold all of them.
+        let passes = |attempts: &Arc<AtomicUsize>| Scripted {
+            attempts: attempts.clone(),
+            outcome: |_| Ok(()),
+        };
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("left", passes(&attempts)));
+        flow.add_processor(ProcessorNode::new("right", passes(&attempts)));
+        flow.add_connection(
+            ConnectionDefinition::new("rightward", "left", "success", "right")
+                .with_backpressure(100),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "leftward", "right", "success", "left",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let queue = controller.connection("rightward").unwrap();
+        for _ in 0..20 {
+            queue.send(FlowFile::with_content("abc")).await.unwrap();
+        }
+
+        wait_for_attempts(&attempts, 20).await;
+        let before = attempts.load(Ordering::SeqCst);
+        for _ in 0..100 {
+            let status = controller.status().await;
+            let depth: usize = status.connections.iter().map(|c| c.queue_depth).sum();
+            let bytes: u64 = status.connections.iter().map(|c| c.queued_bytes).sum();
+            assert_eq!((depth, bytes), (20, 60));
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        controller.stop().await;
+        // The FlowFiles kept moving between the snapshots.
+        assert!(attempts.load(Ordering::SeqCst) > before);
+    }
+
+    #[tokio::test]
+    async fn test_depth_history_is_bounded_and_shows_backlog() {
+        let numbers = Numbers {
+            next: AtomicUsize::new(0),
+            limit: usize::MAX,
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("numbers", numbers).run_schedule(Duration::from_millis(1)),
+        );
+        flow.add_processor(
+            ProcessorNode::new("sink", Collect::default()).auto_terminate("success"),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "backlog", "numbers", "success", "sink",
+        ));
+        let mut controller = FlowController::new(flow).with_history(Duration::from_millis(10), 5);
+        controller.start().unwrap();
+        controller.stop_processor("sink");
+
+        let samples =
+            |controller: &FlowController| controller.connection_history("backlog").unwrap();
+        eventually(|| samples(&controller).len() == 5).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let history = samples(&controller);
+        controller.stop().await;
+
+        assert_eq!(history.len(), 5);
+        assert!(history
+            .windows(2)
+            .all(|pair| pair[0].timestamp < pair[1].timestamp));
+        assert!(history
+            .windows(2)
+            .all(|pair| pair[0].depth <= pair[1].depth));
+        assert!(history[4].depth > history[0].depth);
+        assert!(history[4].bytes > history[0].bytes);
+        let metrics = controller.metrics();
+        assert_eq!(metrics.connections[0].history.len(), 5);
+        assert!(controller.connection_history("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval_or_capacity_turns_sampling_off() {
+        for (interval, capacity) in [(Duration::ZERO, 5), (Duration::from_millis(1), 0)] {
+            let mut flow = FlowDefinition::new();
+            flow.add_processor(ProcessorNode::new("idle", Idle).auto_terminate("success"));
+            let mut controller = FlowController::new(flow).with_history(interval, capacity);
+            controller.start().unwrap();
+            assert!(controller.sampler.is_none());
+            controller.stop().await;
+        }
+
+        let history = DepthHistory::new(0);
+        history.record(DepthSample {
+            timestamp: SystemTime::UNIX_EPOCH,
+            depth: 1,
+            bytes: 1,
+        });
+        assert!(history.samples().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tap_added_and_removed_while_running() {
+        const TOTAL: usize = 300;
+        let sink = Collect::default();
+        let tap = Collect::default();
+        let mut flow = FlowDefinition::new();
+        let numbers = Numbers {
+            next: AtomicUsize::new(0),
+            limit: TOTAL,
+        };
+        flow.add_processor(
+            ProcessorNode::new("numbers", numbers).run_schedule(Duration::from_millis(1)),
+        );
+        flow.add_processor(ProcessorNode::new("sink", sink.clone()).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new(
+            "main", "numbers", "success", "sink",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+
+        eventually(|| sink.seen().len() >= 20).await;
+        controller
+            .add_processor(ProcessorNode::new("tap", tap.clone()).auto_terminate("success"))
+            .unwrap();
+        controller
+            .add_connection(ConnectionDefinition::new(
+                "to-tap", "numbers", "success", "tap",
+            ))
+            .await
+            .unwrap();
+        eventually(|| tap.seen().len() >= 20).await;
+
+        // Let the tap's queue fill up, then check it cannot be dropped by accident.
+        assert!(controller.stop_processor("tap"));
+        let queue = controller.connection("to-tap").unwrap();
+        eventually(|| queue.len() >= 5).await;
+        match controller.remove_connection("to-tap", false).await {
+            Err(FlowChangeError::ConnectionNotEmpty { connection, queued }) => {
+                assert_eq!(connection, "to-tap");
+                assert!(queued >= 5);
+            }
+            other => panic!("expected ConnectionNotEmpty, got {:?}", other),
+        }
+        assert!(matches!(
+            controller.remove_processor("tap").await,
+            Err(FlowChangeError::ProcessorInUse { connections, .. }) if connections == ["to-tap"]
+        ));
+
+        // Drain it, then detach; whatever the tap saw is a gap-free slice.
+        assert!(controller.start_processor("tap"));
+        loop {
+            match controller.remove_connection("to-tap", false).await {
+                Ok(()) => break,
+                Err(FlowChangeError::ConnectionNotEmpty { .. }) => {
+                    tokio::time::sleep(Duration::from_millis(1)).await
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+        controller.remove_processor("tap").await.unwrap();
+        assert!(controller.connection("to-tap").is_none());
+        assert_eq!(controller.metrics().processors.len(), 2);
+
+        eventually(|| sink.seen().len() == TOTAL).await;
+        controller.stop().await;
+        let expected: Vec<String> = (0..TOTAL).map(|n| n.to_string()).collect();
+        assert_eq!(sink.seen(), expected);
+        let tapped = tap.seen();
+        let first: usize = tapped[0].parse().unwrap();
+        let contiguous: Vec<String> = (first..first + tapped.len())
+            .map(|n| n.to_string())
+            .collect();
+        assert_eq!(tapped, contiguous);
+    }
+
+    #[tokio::test]
+    async fn test_force_removal_and_in_flight_triggers() {
+        let slow = Collect {
+            delay: Duration::from_millis(200),
+            ..Collect::default()
+        };
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("slow", slow.clone()).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "slow"));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+
+        let queue = controller.connection("in").unwrap();
+        queue.send(FlowFile::with_content("first")).await.unwrap();
+        // Wait until the slow trigger has started and taken "first".
+        eventually(|| queue.is_empty()).await;
+        queue.send(FlowFile::with_content("second")).await.unwrap();
+
+        // Removal waits for the trigger holding "first" to commit, then drops
+        // "second" because it is forced.
+        controller.remove_connection("in", true).await.unwrap();
+        assert_eq!(slow.seen(), ["first"]);
+        assert_eq!(queue.len(), 1);
+        controller.remove_processor("slow").await.unwrap();
+
+        assert_eq!(
+            controller.remove_connection("in", true).await,
+            Err(FlowChangeError::UnknownConnection("in".to_string()))
+        );
+        assert!(matches!(
+            controller.add_processor(ProcessorNode::new("idle", Idle)),
+            Err(FlowChangeError::DuplicateName(name)) if name == "idle"
+        ));
+        assert!(matches!(
+            controller.add_connection(ConnectionDefinition::new("bad", "idle", "retry", "idle")).await,
+            Err(FlowChangeError::Invalid(errors)) if errors.len() == 2
+        ));
+        controller.stop().await;
+    }
+
+    // Tokio's clock is paused, so settling waits no real time; `clock`
+    // decides when the schedule fires.
+    #[tokio::test(start_paused = true)]
+    async fn test_cron_schedule_follows_clock() {
+        // 2024-01-01 01:59:30 UTC.
+        let clock = Arc::new(MockClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_074_370),
+        ));
+        let processor = CountsTriggers::default();
+        let triggers = processor.triggers.clone();
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("nightly", processor).cron_schedule("0 2 * * *"));
+        let mut controller = FlowController::new(flow).with_clock(clock.clone());
+        controller.start().unwrap();
+
+        let settle = || tokio::time::sleep(CRON_POLL * 3);
+        settle().await;
+        assert_eq!(triggers.load(Ordering::SeqCst), 0);
+        clock.advance(Duration::from_secs(30));
+        settle().await;
+        assert_eq!(triggers.load(Ordering::SeqCst), 1);
+        clock.advance(Duration::from_secs(23 * 3600));
+        settle().await;
+        assert_eq!(triggers.load(Ordering::SeqCst), 1);
+        clock.advance(Duration::from_secs(3600));
+        settle().await;
+        assert_eq!(triggers.load(Ordering::SeqCst), 2);
+        controller.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_expired_flowfiles_raise_a_bulletin_and_are_counted() {
+        let clock = Arc::new(MockClock::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("source", Idle));
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate("success"));
+        flow.add_connection(
+            ConnectionDefinition::new("stale", "source", "success", "sink")
+                .with_expiration(Duration::from_secs(60)),
+        );
+        let mut controller = FlowController::new(flow).with_clock(clock.clone());
+        controller.start().unwrap();
+        let stale = controller.connection("stale").unwrap();
+        for _ in 0..2 {
+            stale
+                .send(FlowFile::created_by("source", clock.now()))
+                .await
+                .unwrap();
+        }
+        clock.advance(Duration::from_secs(60));
+        assert!(stale.receive().await.unwrap().is_none());
+
+        assert_eq!(controller.status().await.connections[0].expired, 2);
+        let bulletins = controller.bulletins_for("sink");
+        controller.stop().await;
+        assert_eq!(bulletins.len(), 1);
+        assert_eq!(bulletins[0].severity, LogLevel::Warn);
+        assert_eq!(
+            bulletins[0].message,
+            "2 FlowFile(s) expired in connection 'stale' after 60000 ms"
+        );
+        assert_eq!(bulletins[0].timestamp, clock.now());
+    }
+
+    #[tokio::test]
+    async fn test_connections_through_a_funnel_share_one_queue() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("a", Idle));
+        flow.add_processor(ProcessorNode::new("b", Idle));
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate("success"));
+        for source in ["a", "b"] {
+            flow.add_connection(
+                ConnectionDefinition::new(
+                    &format!("{}-to-sink", source),
+                    source,
+                    "success",
+                    "sink",
+                )
+                .with_backpressure(3)
+                .through_funnel("merge"),
+            );
+        }
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let (a, b) = (
+            controller.connection("a-to-sink").unwrap(),
+            controller.connection("b-to-sink").unwrap(),
+        );
+        for source in ["a", "a", "b"] {
+            let inlet = if source == "a" { &a } else { &b };
+            inlet
+                .send(FlowFile::with_content(source.as_bytes().to_vec()))
+                .await
+                .unwrap();
+        }
+        assert_eq!((a.len(), b.len()), (2, 1));
+        assert!(a.is_full() && b.is_full());
+
+        let mut received = Vec::new();
+        while let Some(flowfile) = b.receive().await.unwrap() {
+            received.push(flowfile.content().unwrap().into_owned());
+        }
+        controller.stop().await;
+        assert_eq!(received, [b"a".to_vec(), b"b".to_vec(), b"a".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_bulletins_are_bounded_and_newest_first() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new(
+                "flaky",
+                AlwaysFails {
+                    attempts: AtomicUsize::new(0),
+                },
+            )
+            .auto_terminate("success"),
+        );
+        let mut controller = FlowController::new(flow).with_bulletin_capacity(3);
+        controller.start().unwrap();
+        while controller.metrics().processors[0].failures < 5 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        controller.stop().await;
+
+        let bulletins = controller.bulletins();
+        let failures = controller.metrics().processors[0].failures as usize;
+        assert_eq!(bulletins.len(), 3);
+        assert_eq!(controller.metrics().bulletin_count, 3);
+        for (i, bulletin) in bulletins.iter().enumerate() {
+            assert_eq!(bulletin.processor, "flaky");
+            assert_eq!(bulletin.severity, LogLevel::Error);
+            assert_eq!(
+                bulletin.message,
+                format!("on_trigger panicked: attempt {} failed", failures - i)
+            );
+        }
+        assert_eq!(controller.bulletins_for("flaky").len(), 3);
+        assert!(controller.bulletins_for("other").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_to_completion_counts_every_file() {
+        use crate::processors::get_file::{GetFileProcessor, BATCH_SIZE, INPUT_DIRECTORY};
+        use crate::processors::put_file::{PutFileProcessor, OUTPUT_DIRECTORY};
+
+        let root = std::env::temp_dir().join(format!(
+            "streamsync-controller-{}-run-once",
+            std::process::id()
+        ));
+        let (input, output) = (root.join("in"), root.join("out"));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&input).unwrap();
+        std::fs::create_dir_all(&output).unwrap();
+        for i in 0..7 {
+            std::fs::write(input.join(format!("file-{}.txt", i)), format!("line {}", i)).unwrap();
+        }
+
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("read", GetFileProcessor::new())
+                .with_property(INPUT_DIRECTORY, &input.to_string_lossy())
+                .with_property(BATCH_SIZE, "3"),
+        );
+        flow.add_processor(
+            ProcessorNode::new("write", PutFileProcessor::new())
+                .with_property(OUTPUT_DIRECTORY, &output.to_string_lossy())
+                .auto_terminate("success")
+                .auto_terminate("failure"),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "read-to-write",
+            "read",
+            "success",
+            "write",
+        ));
+        let mut controller = FlowController::new(flow);
+
+        let summary = controller
+            .run_to_completion(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(summary.completed);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(summary.processed["read"], 7);
+        assert_eq!(summary.processed["write"], 7);
+        assert!(summary.elapsed < Duration::from_secs(10));
+        assert_eq!(std::fs::read_dir(&output).unwrap().count(), 7);
+        assert_eq!(
+            std::fs::read_to_string(output.join("file-4.txt")).unwrap(),
+            "line 4"
+        );
+        assert!(!controller.is_running());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_get_file_picks_each_file_up_once() {
+        use crate::processors::get_file::{GetFileProcessor, BATCH_SIZE, INPUT_DIRECTORY};
+
+        let input = std::env::temp_dir().join(format!(
+            "streamsync-controller-{}-concurrent-get-file",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&input);
+        std::fs::create_dir_all(&input).unwrap();
+        for i in 0..20 {
+            std::fs::write(input.join(format!("file-{:02}.txt", i)), "x").unwrap();
+        }
+
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("read", GetFileProcessor::new())
+                .with_property(INPUT_DIRECTORY, &input.to_string_lossy())
+                .with_property(BATCH_SIZE, "1")
+                .concurrent_tasks(4),
+        );
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new(
+            "files", "read", "success", "sink",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let queue = controller.connection("files").unwrap();
+        while queue.len() < 20 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // Give any duplicate time to show up.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        controller.stop().await;
+
+        let mut names: Vec<String> = queue
+            .snapshot()
+            .iter()
+            .map(|f| f.get_attribute("filename").unwrap().to_string())
+            .collect();
+        assert_eq!(names.len(), 20);
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 20);
+        std::fs::remove_dir_all(input).unwrap();
+    }
+
+    // Passes each FlowFile on with one 1 KiB attribute and eight small ones
+    // added. Without `failure`, it only declares success.
+    struct Bloats {
+        failure: bool,
+    }
+
+    impl Processor for Bloats {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let Some(mut flowfile) = session.get()? else {
+                return Ok(());
+            };
+            flowfile.put_attribute("blob", &"x".repeat(1024));
+            for i in 0..8 {
+                flowfile.put_attribute(&format!("tag.{}", i), "t");
+            }
+            session.transfer(flowfile, relationship::SUCCESS);
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Bloats"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            let mut relationships = vec![Relationship::success()];
+            if self.failure {
+                relationships.push(Relationship::failure());
+            }
+            relationships
+        }
+    }
+
+    // Runs one FlowFile through `Bloats` under `limits`, checkpoints the flow
+    // and returns what was persisted for the success and failure queues,
+    // along with the bulletins raised.
+    async fn bloat_and_checkpoint(
+        limits: AttributeLimits,
+    ) -> (Vec<FlowFile>, Vec<FlowFile>, Vec<Bulletin>) {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("bloat", Bloats { failure: true }));
+        flow.add_processor(ProcessorNode::new("kept", Idle).auto_terminate("success"));
+        flow.add_processor(ProcessorNode::new("failed", Idle).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "bloat"));
+        flow.add_connection(ConnectionDefinition::new("out", "bloat", "success", "kept"));
+        flow.add_connection(ConnectionDefinition::new(
+            "errors", "bloat", "failure", "failed",
+        ));
+        let policy = limits.policy;
+        flow.set_attribute_limits(limits);
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("payload"))
+            .await
+            .unwrap();
+        let (out, errors) = (
+            controller.connection("out").unwrap(),
+            controller.connection("errors").unwrap(),
+        );
+        while out.is_empty() && errors.is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-controller-{}-limits-{}",
+            std::process::id(),
+            policy
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let manifest = controller.checkpoint(&dir).await.unwrap();
+        controller.stop().await;
+        let persisted = |name: &str| {
+            let entry = manifest
+                .connections
+                .iter()
+                .find(|entry| entry.name == name)
+                .unwrap();
+            checkpoint::read_queue(&dir.join(&entry.queue_file)).unwrap()
+        };
+        let (kept, failed) = (persisted("out"), persisted("errors"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        (kept, failed, controller.bulletins_for("bloat"))
+    }
+
+    #[tokio::test]
+    async fn test_attribute_limits_truncate() {
+        let limits = AttributeLimits::new(LimitPolicy::Truncate).max_value_length(16);
+        let (kept, failed, bulletins) = bloat_and_checkpoint(limits).await;
+        assert!(failed.is_empty());
+        assert_eq!(
+            kept[0].get_attribute("blob").unwrap().to_string(),
+            "x".repeat(16)
+        );
+        assert_eq!(bulletins.len(), 1);
+        assert_eq!(bulletins[0].severity, LogLevel::Warn);
+        assert!(bulletins[0]
+            .message
+            .starts_with("attribute limits enforced (truncate) on 1 FlowFile(s)"));
+        assert!(bulletins[0]
+            .message
+            .contains("'blob' is 1024 bytes, over the limit of 16"));
+    }
+
+    #[tokio::test]
+    async fn test_attribute_limits_drop_attribute() {
+        let limits = AttributeLimits::new(LimitPolicy::DropAttribute)
+            .max_value_length(16)
+            .max_attributes(4);
+        let (kept, failed, bulletins) = bloat_and_checkpoint(limits).await;
+        assert!(failed.is_empty());
+        assert!(kept[0].get_attribute("blob").is_none());
+        assert_eq!(kept[0].attributes().len(), 4);
+        assert!(bulletins[0].message.contains("drop-attribute"));
+    }
+
+    #[tokio::test]
+    async fn test_attribute_limits_route_to_failure() {
+        let limits = AttributeLimits::new(LimitPolicy::RouteToFailure).max_value_length(16);
+        let (kept, failed, bulletins) = bloat_and_checkpoint(limits).await;
+        assert!(kept.is_empty());
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].get_attribute("blob").is_none());
+        assert_eq!(failed[0].get_attribute("tag.0").unwrap().to_string(), "t");
+        assert!(bulletins[0].message.contains("route-to-failure"));
+    }
+
+    #[tokio::test]
+    async fn test_route_to_failure_without_failure_drops_attribute() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("bloat", Bloats { failure: false }));
+        flow.add_processor(ProcessorNode::new("kept", Idle).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "bloat"));
+        flow.add_connection(ConnectionDefinition::new("out", "bloat", "success", "kept"));
+        flow.set_attribute_limits(
+            AttributeLimits::new(LimitPolicy::RouteToFailure).max_value_length(16),
+        );
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("payload"))
+            .await
+            .unwrap();
+        // The bulletin is raised once the session has committed.
+        while controller.bulletins_for("bloat").is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let bulletins = controller.bulletins_for("bloat");
+        let kept = controller.connection("out").unwrap().receive().await;
+        assert!(kept.unwrap().unwrap().get_attribute("blob").is_none());
+        assert!(controller.dead_letters().is_empty());
+        controller.stop().await;
+        assert!(bulletins[0].message.contains("(drop-attribute)"));
+    }
+
+    // Routes everything to failure.
+    struct Rejects;
+
+    impl Processor for Rejects {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            if let Some(flowfile) = session.get()? {
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Rejects"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success(), Relationship::failure()]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unconnected_failure_goes_to_dead_letters() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate("success"));
+        flow.add_connection(ConnectionDefinition::new("out", "idle", "success", "sink"));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+
+        // Added while running, so its failure relationship may stay unconnected.
+        controller
+            .add_processor(ProcessorNode::new("rejects", Rejects).auto_terminate("success"))
+            .unwrap();
+        controller
+            .add_connection(ConnectionDefinition::new(
+                "in", "idle", "success", "rejects",
+            ))
+            .await
+            .unwrap();
+        let queue = controller.connection("in").unwrap();
+        queue
+            .send(FlowFile::with_content("unwanted"))
+            .await
+            .unwrap();
+
+        let dead_letters = controller.dead_letters();
+        eventually(|| !dead_letters.is_empty()).await;
+        let flowfile = dead_letters.receive().await.unwrap().unwrap();
+        assert_eq!(flowfile.content().unwrap().as_ref(), b"unwanted");
+        assert_eq!(
+            flowfile
+                .get_attribute(DEAD_LETTER_REASON)
+                .unwrap()
+                .to_string(),
+            "rejects routed it to failure, which is not connected"
+        );
+        assert!(queue.is_empty());
+        assert!(controller.bulletins_for("rejects").is_empty());
+        controller.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_processors_log_through_the_controller_logger() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(
+            ProcessorNode::new("files", FileProcessor::new()).auto_terminate("success"),
+        );
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "files"));
+        let logger = Arc::new(MemoryLogger::new());
+        let mut controller = FlowController::new(flow).with_logger(logger.clone());
+        controller.start().unwrap();
+
+        controller
+            .connection("in")
+            .unwrap()
+            .send(FlowFile::with_content("hi"))
+            .await
+            .unwrap();
+        eventually(|| !logger.records().is_empty()).await;
+        let record = &logger.records()[0];
+        assert_eq!(
+            (record.component.as_str(), record.message.as_str()),
+            ("files", "MyProcessor is executing!")
+        );
+        controller.stop().await;
+    }
+
+    // Reports a bulletin for every FlowFile it drops.
+    struct Complains;
+
+    impl Processor for Complains {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            if let Some(flowfile) = session.get()? {
+                session
+                    .report_bulletin(LogLevel::Error, &format!("cannot parse {}", flowfile.id()));
+                session.remove(flowfile);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Complains"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_processor_bulletins_reach_the_controller() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("idle", Idle));
+        flow.add_processor(ProcessorNode::new("parser", Complains));
+        flow.add_connection(ConnectionDefinition::new("in", "idle", "success", "parser"));
+        let mut controller = FlowController::new(flow).with_logger(Arc::new(MemoryLogger::new()));
+        controller.start().unwrap();
+
+        let flowfile = FlowFile::with_content("garbage");
+        let id = flowfile.id();
+        controller
+            .connection("in")
+            .unwrap()
+            .send(flowfile)
+            .await
+            .unwrap();
+        eventually(|| !controller.bulletins_for("parser").is_empty()).await;
+        controller.stop().await;
+
+        let bulletins = controller.bulletins_for("parser");
+        assert_eq!(bulletins[0].severity, LogLevel::Error);
+        assert_eq!(bulletins[0].message, format!("cannot parse {}", id));
+        let parser = controller
+            .metrics()
+            .processors
+            .into_iter()
+            .find(|p| p.name == "parser")
+            .unwrap();
+        assert_eq!(parser.failures, 0);
+    }
+}
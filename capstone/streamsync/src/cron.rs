@@ -0,0 +1,266 @@
+//! Five-field cron expressions ("minute hour day-of-month month day-of-week")
+//! for scheduling processors at fixed times. Each field accepts `*`, single
+//! values, ranges (`1-5`), lists (`1,15`) and steps (`*/10`, `8-18/2`). Day of
+//! week runs 0-7 with both 0 and 7 meaning Sunday. Times are in UTC.
+//!
+//! As in classic cron, when both day fields are restricted a day matches if
+//! either does: `0 9 1 * 1` fires on the 1st *and* on every Monday.
+
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Processor property that switches a processor from interval to cron
+/// scheduling.
+pub const CRON_EXPRESSION: &str = "cron.expression";
+
+// How far ahead to search before deciding an expression never fires
+// (e.g. "0 0 30 2 *"). Eight years always includes a 29th of February.
+const SEARCH_DAYS: i64 = 8 * 366;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronError {
+    FieldCount(usize),
+    InvalidField {
+        field: &'static str,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CronError::FieldCount(count) => write!(f, "expected 5 fields, found {}", count),
+            CronError::InvalidField {
+                field,
+                value,
+                reason,
+            } => {
+                write!(f, "invalid {} field '{}': {}", field, value, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CronError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    // Bit n is set when value n matches.
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, CronError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(CronError::FieldCount(fields.len()));
+        };
+        let mut days_of_week = parse_field(day_of_week, "day-of-week", 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, "minute", 0, 59)?,
+            hours: parse_field(hour, "hour", 0, 23)?,
+            days_of_month: parse_field(day_of_month, "day-of-month", 1, 31)?,
+            months: parse_field(month, "month", 1, 12)?,
+            days_of_week,
+            day_of_month_restricted: !day_of_month.starts_with('*'),
+            day_of_week_restricted: !day_of_week.starts_with('*'),
+        })
+    }
+
+    /// The first minute boundary strictly after `after` that matches, or
+    /// `None` if the expression can never fire.
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let seconds = after
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let start = seconds.div_euclid(60) + 1;
+        let first_day = start.div_euclid(1440);
+        let first_minute = start.rem_euclid(1440);
+
+        for day in first_day..first_day + SEARCH_DAYS {
+            let (_, month, day_of_month) = civil_from_days(day);
+            let day_of_week = (day + 4).rem_euclid(7) as u32; // 1970-01-01 was a Thursday
+            if !matches(self.months, month) || !self.day_matches(day_of_month, day_of_week) {
+                continue;
+            }
+            let from = if day == first_day { first_minute } else { 0 };
+            for minute_of_day in from..1440 {
+                let (hour, minute) = ((minute_of_day / 60) as u32, (minute_of_day % 60) as u32);
+                if matches(self.hours, hour) && matches(self.minutes, minute) {
+                    let seconds = (day * 1440 + minute_of_day) * 60;
+                    return Some(UNIX_EPOCH + Duration::from_secs(seconds as u64));
+                }
+            }
+        }
+        None
+    }
+
+    fn day_matches(&self, day_of_month: u32, day_of_week: u32) -> bool {
+        let by_month = matches(self.days_of_month, day_of_month);
+        let by_week = matches(self.days_of_week, day_of_week);
+        if self.day_of_month_restricted && self.day_of_week_restricted {
+            by_month || by_week
+        } else {
+            by_month && by_week
+        }
+    }
+}
+
+fn matches(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn parse_field(text: &str, field: &'static str, min: u32, max: u32) -> Result<u64, CronError> {
+    let invalid = |reason: String| CronError::InvalidField {
+        field,
+        value: text.to_string(),
+        reason,
+    };
+    let number = |s: &str| -> Result<u32, CronError> {
+        let value: u32 = s
+            .parse()
+            .map_err(|_| invalid(format!("'{}' is not a number", s)))?;
+        if value < min || value > max {
+            return Err(invalid(format!("{} is outside {}-{}", value, min, max)));
+        }
+        Ok(value)
+    };
+
+    let mut bits = 0;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => {
+                    return Err(invalid(format!(
+                        "step '{}' must be a positive number",
+                        step
+                    )))
+                }
+            },
+            None => (part, 1),
+        };
+        let (low, high) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((low, high)) => (number(low)?, number(high)?),
+                // "5/15" means every 15 starting at 5.
+                None if part.contains('/') => (number(range)?, max),
+                None => {
+                    let value = number(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if low > high {
+            return Err(invalid(format!("range {}-{} is backwards", low, high)));
+        }
+        for value in (low..=high).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+// Proleptic Gregorian date for a day count since 1970-01-01
+// (Howard Hinnant's civil_from_days).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01 00:00:00 UTC, a Monday.
+    const JAN_1_2024: u64 = 1_704_067_200;
+
+    fn at(days: u64, hours: u64, minutes: u64, seconds: u64) -> SystemTime {
+        UNIX_EPOCH
+            + Duration::from_secs(
+                JAN_1_2024 + days * 86_400 + hours * 3600 + minutes * 60 + seconds,
+            )
+    }
+
+    fn next(expression: &str, after: SystemTime) -> SystemTime {
+        CronSchedule::parse(expression)
+            .unwrap()
+            .next_after(after)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_minute_and_hour_fields() {
+        assert_eq!(next("* * * * *", at(0, 10, 15, 30)), at(0, 10, 16, 0));
+        assert_eq!(next("*/15 * * * *", at(0, 10, 15, 0)), at(0, 10, 30, 0));
+        assert_eq!(next("5,50 9-17/4 * * *", at(0, 9, 51, 0)), at(0, 13, 5, 0));
+        assert_eq!(next("0 2 * * *", at(0, 1, 59, 59)), at(0, 2, 0, 0));
+        assert_eq!(next("0 2 * * *", at(0, 2, 0, 0)), at(1, 2, 0, 0));
+        assert_eq!(next("30 23 * * *", at(30, 23, 45, 0)), at(31, 23, 30, 0));
+    }
+
+    #[test]
+    fn test_day_of_week_field() {
+        // Monday 2024-01-01 -> the following Saturday and Sunday.
+        assert_eq!(next("0 0 * * 6", at(0, 12, 0, 0)), at(5, 0, 0, 0));
+        assert_eq!(next("0 0 * * 0", at(0, 12, 0, 0)), at(6, 0, 0, 0));
+        assert_eq!(next("0 0 * * 7", at(0, 12, 0, 0)), at(6, 0, 0, 0));
+        assert_eq!(next("0 9 * * 1-5", at(4, 10, 0, 0)), at(7, 9, 0, 0));
+        // Both day fields restricted: the 15th or any Wednesday, whichever is first.
+        assert_eq!(next("0 0 15 * 3", at(0, 0, 0, 0)), at(2, 0, 0, 0));
+        assert_eq!(next("0 0 15 * 3", at(9, 0, 0, 0)), at(14, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_month_and_leap_day() {
+        assert_eq!(next("0 0 1 3 *", at(0, 0, 0, 0)), at(60, 0, 0, 0));
+        assert_eq!(next("0 12 29 2 *", at(0, 0, 0, 0)), at(59, 12, 0, 0));
+        assert_eq!(
+            next("0 12 29 2 *", at(60, 0, 0, 0)),
+            UNIX_EPOCH + Duration::from_secs(1_835_438_400)
+        );
+        assert_eq!(
+            CronSchedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(at(0, 0, 0, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            CronSchedule::parse("* * * *"),
+            Err(CronError::FieldCount(4))
+        );
+        assert_eq!(
+            CronSchedule::parse("60 * * * *").unwrap_err().to_string(),
+            "invalid minute field '60': 60 is outside 0-59"
+        );
+        assert!(CronSchedule::parse("* 5-2 * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("* * 0 * *").is_err());
+        assert!(CronSchedule::parse("* * * * MON").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Attribute expressions in property values: `${name}` is replaced by the
+//! FlowFile's attribute of that name (empty when missing), and `$${` writes a
+//! literal `${`. Everything else is copied as is.
+//!
+//! A reference ending in `()` calls a function instead. The only one is
+//! `${now()}`, the time on the given clock in milliseconds since the Unix
+//! epoch.
+
+pub mod predicate;
+
+use crate::clock::Clock;
+use crate::flowfile::FlowFile;
+use std::fmt;
+use std::time::UNIX_EPOCH;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpressionError {
+    Unterminated { position: usize },
+    EmptyReference { position: usize },
+    UnknownFunction { name: String, position: usize },
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::Unterminated { position } => {
+                write!(f, "'${{' at {} is never closed", position)
+            }
+            ExpressionError::EmptyReference { position } => {
+                write!(f, "empty '${{}}' at {}", position)
+            }
+            ExpressionError::UnknownFunction { name, position } => {
+                write!(f, "unknown function '{}()' at {}", name, position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+pub fn evaluate(
+    expression: &str,
+    flowfile: &FlowFile,
+    clock: &dyn Clock,
+) -> Result<String, ExpressionError> {
+    let mut result = String::with_capacity(expression.len());
+    let mut rest = expression;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let position = expression.len() - rest.len() + start;
+        let tail = &rest[start..];
+        if let Some(escaped) = tail.strip_prefix("$${") {
+            result.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = tail.strip_prefix("${") {
+            let end = reference
+                .find('}')
+                .ok_or(ExpressionError::Unterminated { position })?;
+            let name = reference[..end].trim();
+            if name.is_empty() {
+                return Err(ExpressionError::EmptyReference { position });
+            }
+            if let Some(function) = name.strip_suffix("()") {
+                result.push_str(&call(function.trim(), position, clock)?);
+            } else if let Some(value) = flowfile.get_attribute(name) {
+                result.push_str(&value.to_string());
+            }
+            rest = &reference[end + 1..];
+        } else {
+            result.push('$');
+            rest = &tail[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn call(function: &str, position: usize, clock: &dyn Clock) -> Result<String, ExpressionError> {
+    match function {
+        "now" => Ok(clock
+            .now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            .to_string()),
+        _ => Err(ExpressionError::UnknownFunction {
+            name: function.to_string(),
+            position,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{MockClock, SystemClock};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_evaluate() {
+        let mut flowfile = FlowFile::new();
+        flowfile.put_attribute("filename", "data.csv");
+        flowfile.put_attribute("user.id", "42");
+
+        assert_eq!(
+            evaluate("plain $5", &flowfile, &SystemClock).unwrap(),
+            "plain $5"
+        );
+        assert_eq!(
+            evaluate("${filename}", &flowfile, &SystemClock).unwrap(),
+            "data.csv"
+        );
+        assert_eq!(
+            evaluate("${ user.id }-${filename}", &flowfile, &SystemClock).unwrap(),
+            "42-data.csv"
+        );
+        assert_eq!(
+            evaluate("[${missing}]", &flowfile, &SystemClock).unwrap(),
+            "[]"
+        );
+        assert_eq!(
+            evaluate("$${filename}", &flowfile, &SystemClock).unwrap(),
+            "${filename}"
+        );
+        assert_eq!(
+            evaluate("id ${user.id", &flowfile, &SystemClock),
+            Err(ExpressionError::Unterminated { position: 3 })
+        );
+        assert_eq!(
+            evaluate("${}", &flowfile, &SystemClock),
+            Err(ExpressionError::EmptyReference { position: 0 })
+        );
+    }
+
+    #[test]
+    fn test_now() {
+        let clock = MockClock::new(UNIX_EPOCH + Duration::from_millis(1_700_000_000_123));
+        assert_eq!(
+            evaluate("at ${now()}", &FlowFile::new(), &clock).unwrap(),
+            "at 1700000000123"
+        );
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let now: u128 = evaluate("${now()}", &FlowFile::new(), &SystemClock)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(now >= before && now < before + 60_000);
+        assert_eq!(
+            evaluate("at ${upper()}", &FlowFile::new(), &clock),
+            Err(ExpressionError::UnknownFunction {
+                name: "upper".to_string(),
+                position: 3
+            })
+        );
+    }
+}
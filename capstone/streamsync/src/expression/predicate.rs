@@ -0,0 +1,431 @@
+//! Boolean expressions over a FlowFile's attributes and size, such as
+//! `contentLength > 1024 && filename endsWith '.log'`.
+//!
+//! Operands are attribute names, `contentLength`, numbers, quoted strings
+//! (single or double quotes) and `true`/`false`. Comparisons are `==`, `!=`,
+//! `<`, `<=`, `>`, `>=`, `contains`, `startsWith` and `endsWith`; they combine
+//! with `&&`, `||`, `!` and parentheses. Two values that are both numeric
+//! (typed `Int`, `Float` or `Timestamp` attributes, or strings that parse as
+//! numbers) compare numerically, anything else compares as text. A comparison
+//! against a missing attribute is false, except `!=` which is true.
+
+use crate::flowfile::{AttributeValue, FlowFile};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Name that refers to the FlowFile's content size rather than an attribute.
+pub const CONTENT_LENGTH: &str = "contentLength";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PredicateError {
+    pub position: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: {}", self.position, self.reason)
+    }
+}
+
+impl std::error::Error for PredicateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Attribute(String),
+    ContentLength,
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Operand, CompareOp, Operand),
+    Operand(Operand),
+}
+
+/// A parsed expression, ready to be evaluated against any number of FlowFiles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    root: Node,
+}
+
+impl Predicate {
+    pub fn parse(expression: &str) -> Result<Self, PredicateError> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens,
+            next: 0,
+            end: expression.len(),
+        };
+        let root = parser.or()?;
+        match parser.peek() {
+            None => Ok(Self { root }),
+            Some((position, token)) => Err(PredicateError {
+                position,
+                reason: format!("unexpected {}", token),
+            }),
+        }
+    }
+
+    pub fn matches(&self, flowfile: &FlowFile) -> bool {
+        evaluate(&self.root, flowfile)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Text(String),
+    Word(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Text(s) => write!(f, "string '{}'", s),
+            Token::Word(w) => write!(f, "'{}'", w),
+            Token::Symbol(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+// Longest symbols first so "<=" is not read as "<" followed by "=".
+const SYMBOLS: [&str; 11] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "(", ")"];
+
+fn tokenize(expression: &str) -> Result<Vec<(usize, Token)>, PredicateError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        let rest = &expression[position..];
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' || c == '"' {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped)) => text.push(escaped),
+                        None => break,
+                    },
+                    Some((_, end)) if end == c => {
+                        tokens.push((position, Token::Text(text)));
+                        break;
+                    }
+                    Some((_, other)) => text.push(other),
+                    None => {
+                        return Err(PredicateError {
+                            position,
+                            reason: "string is never closed".to_string(),
+                        })
+                    }
+                }
+            }
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|d: char| d.is_ascii_digit()))
+        {
+            let length = rest[1..]
+                .find(|d: char| !(d.is_ascii_digit() || d == '.'))
+                .map_or(rest.len(), |i| i + 1);
+            let number = rest[..length].parse().map_err(|_| PredicateError {
+                position,
+                reason: format!("'{}' is not a number", &rest[..length]),
+            })?;
+            tokens.push((position, Token::Number(number)));
+            for _ in 0..length {
+                chars.next();
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let length = rest
+                .find(|w: char| !(w.is_alphanumeric() || matches!(w, '_' | '.' | '-')))
+                .unwrap_or(rest.len());
+            tokens.push((position, Token::Word(rest[..length].to_string())));
+            for _ in rest[..length].chars() {
+                chars.next();
+            }
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+            tokens.push((position, Token::Symbol(symbol)));
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+        } else {
+            return Err(PredicateError {
+                position,
+                reason: format!("unexpected character '{}'", c),
+            });
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, &Token)> {
+        self.tokens
+            .get(self.next)
+            .map(|(position, token)| (*position, token))
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some((_, Token::Symbol(s))) if *s == symbol);
+        if found {
+            self.next += 1;
+        }
+        found
+    }
+
+    fn error(&self, reason: &str) -> PredicateError {
+        PredicateError {
+            position: self.peek().map_or(self.end, |(position, _)| position),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, PredicateError> {
+        let mut node = self.and()?;
+        while self.eat("||") {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, PredicateError> {
+        let mut node = self.unary()?;
+        while self.eat("&&") {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, PredicateError> {
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let node = self.or()?;
+            if !self.eat(")") {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(node);
+        }
+        let left = self.operand()?;
+        match self.compare_op() {
+            Some(op) => Ok(Node::Compare(left, op, self.operand()?)),
+            None => Ok(Node::Operand(left)),
+        }
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        let op = match self.peek()?.1 {
+            Token::Symbol("==") => CompareOp::Eq,
+            Token::Symbol("!=") => CompareOp::Ne,
+            Token::Symbol("<") => CompareOp::Lt,
+            Token::Symbol("<=") => CompareOp::Le,
+            Token::Symbol(">") => CompareOp::Gt,
+            Token::Symbol(">=") => CompareOp::Ge,
+            Token::Word(w) if w == "contains" => CompareOp::Contains,
+            Token::Word(w) if w == "startsWith" => CompareOp::StartsWith,
+            Token::Word(w) if w == "endsWith" => CompareOp::EndsWith,
+            _ => return None,
+        };
+        self.next += 1;
+        Some(op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, PredicateError> {
+        let operand = match self.peek() {
+            Some((_, Token::Number(n))) => Operand::Number(*n),
+            Some((_, Token::Text(s))) => Operand::Text(s.clone()),
+            Some((_, Token::Word(w))) => match w.as_str() {
+                "true" => Operand::Bool(true),
+                "false" => Operand::Bool(false),
+                CONTENT_LENGTH => Operand::ContentLength,
+                name => Operand::Attribute(name.to_string()),
+            },
+            _ => return Err(self.error("expected a value")),
+        };
+        self.next += 1;
+        Ok(operand)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+}
+
+impl Value {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Text(s) => s.trim().parse().ok(),
+            Value::Bool(_) => None,
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => s.clone(),
+            Value::Bool(b) => b.to_string(),
+        }
+    }
+
+    fn is_true(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0,
+            Value::Text(s) => s == "true",
+            Value::Bool(b) => *b,
+        }
+    }
+}
+
+impl From<&AttributeValue> for Value {
+    fn from(value: &AttributeValue) -> Self {
+        match value {
+            AttributeValue::Str(s) => Value::Text(s.clone()),
+            AttributeValue::Bool(b) => Value::Bool(*b),
+            numeric => Value::Number(numeric.as_f64().unwrap_or(f64::NAN)),
+        }
+    }
+}
+
+fn resolve(operand: &Operand, flowfile: &FlowFile) -> Option<Value> {
+    match operand {
+        Operand::Attribute(name) => flowfile.get_attribute(name).map(Value::from),
+        Operand::ContentLength => Some(Value::Number(flowfile.size() as f64)),
+        Operand::Number(n) => Some(Value::Number(*n)),
+        Operand::Text(s) => Some(Value::Text(s.clone())),
+        Operand::Bool(b) => Some(Value::Bool(*b)),
+    }
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    let ordering = match (left.as_number(), right.as_number()) {
+        (Some(l), Some(r)) => l.partial_cmp(&r),
+        _ => Some(left.as_text().cmp(&right.as_text())),
+    };
+    match op {
+        CompareOp::Eq => ordering == Some(Ordering::Equal),
+        CompareOp::Ne => ordering != Some(Ordering::Equal),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        CompareOp::Contains => left.as_text().contains(&right.as_text()),
+        CompareOp::StartsWith => left.as_text().starts_with(&right.as_text()),
+        CompareOp::EndsWith => left.as_text().ends_with(&right.as_text()),
+    }
+}
+
+fn evaluate(node: &Node, flowfile: &FlowFile) -> bool {
+    match node {
+        Node::Or(left, right) => evaluate(left, flowfile) || evaluate(right, flowfile),
+        Node::And(left, right) => evaluate(left, flowfile) && evaluate(right, flowfile),
+        Node::Not(inner) => !evaluate(inner, flowfile),
+        Node::Compare(left, op, right) => match (resolve(left, flowfile), resolve(right, flowfile))
+        {
+            (Some(left), Some(right)) => compare(&left, *op, &right),
+            _ => *op == CompareOp::Ne,
+        },
+        Node::Operand(operand) => resolve(operand, flowfile).is_some_and(|value| value.is_true()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn flowfile(size: usize, attributes: &[(&str, &str)]) -> FlowFile {
+        let mut flowfile = FlowFile::with_content(vec![b'x'; size]);
+        for (key, value) in attributes {
+            flowfile.put_attribute(key, value);
+        }
+        flowfile
+    }
+
+    fn matches(expression: &str, flowfile: &FlowFile) -> bool {
+        Predicate::parse(expression).unwrap().matches(flowfile)
+    }
+
+    #[test]
+    fn test_numeric_and_text_coercion() {
+        let ff = flowfile(10, &[("retries", "10"), ("version", "9"), ("code", "abc")]);
+        assert!(matches("contentLength >= 10", &ff));
+        assert!(!matches("contentLength > 10", &ff));
+        // Both sides read as numbers, so 9 < 10 rather than "9" > "10".
+        assert!(matches("version < retries", &ff));
+        assert!(matches("retries == '10.0'", &ff));
+        assert!(matches("code > 'abb'", &ff));
+        assert!(matches("missing != 1 && !(missing == 1)", &ff));
+    }
+
+    #[test]
+    fn test_typed_attributes() {
+        let mut ff = flowfile(0, &[("limit", "10")]);
+        ff.set_attribute("count", 9);
+        ff.set_attribute("ratio", 0.5);
+        ff.set_attribute("enabled", true);
+        ff.set_attribute("seen", UNIX_EPOCH + Duration::from_secs(2));
+
+        assert!(matches("count < limit", &ff));
+        assert!(matches("count == 9.0 && count != '9.5'", &ff));
+        assert!(matches("ratio < count", &ff));
+        assert!(matches(
+            "enabled && enabled == true && enabled == 'true'",
+            &ff
+        ));
+        assert!(!matches("enabled == 1", &ff));
+        assert!(matches("seen == 2000 && seen > count", &ff));
+        assert!(matches("count startsWith '9'", &ff));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Predicate::parse("a == 'x").unwrap_err(),
+            PredicateError {
+                position: 5,
+                reason: "string is never closed".to_string()
+            }
+        );
+        assert_eq!(
+            Predicate::parse("(a == 1").unwrap_err().reason,
+            "expected ')'"
+        );
+        assert_eq!(Predicate::parse("a == ").unwrap_err().position, 5);
+        assert_eq!(
+            Predicate::parse("a b").unwrap_err().reason,
+            "unexpected 'b'"
+        );
+        assert!(Predicate::parse("a = 1").is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::cron::CRON_EXPRESSION;
+use crate::flowfile::limits::AttributeLimits;
+use crate::processor::Processor;
+use crate::processor_context::ProcessorContext;
+use crate::relationship::Relationship;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Processor property bounding how long one `on_trigger` call may run, in
+/// milliseconds. The scheduler records an overrunning trigger as a failure
+/// and rolls back its session once it returns; until then that task starts
+/// no new trigger.
+pub const EXECUTION_TIMEOUT: &str = "execution.timeout";
+
+/// Processor property setting how many triggers of the processor may run at
+/// once; 1 unless set. Each has its own session, so they share only what
+/// the processor itself holds, and only processors whose
+/// `supports_concurrent_tasks` says so may have more than one.
+pub const CONCURRENT_TASKS: &str = "concurrent.tasks";
+
+/// A processor instance placed in a flow, together with its configuration.
+pub struct ProcessorNode {
+    pub context: ProcessorContext,
+    pub processor: Arc<dyn Processor>,
+    pub auto_terminated: HashSet<String>,
+    pub run_schedule: Duration,
+}
+
+impl ProcessorNode {
+    pub fn new(name: &str, processor: impl Processor + 'static) -> Self {
+        Self::from_arc(name, Arc::new(processor))
+    }
+
+    /// Builds a node around an already shared processor, as handed out by the
+    /// `ProcessorRegistry`.
+    pub fn from_arc(name: &str, processor: Arc<dyn Processor>) -> Self {
+        Self {
+            context: ProcessorContext::new(name),
+            processor,
+            auto_terminated: HashSet::new(),
+            run_schedule: Duration::ZERO,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.context.processor_name
+    }
+
+    /// The processor's fixed relationships followed by those its
+    /// configuration adds.
+    pub fn relationships(&self) -> Vec<Relationship> {
+        let mut relationships = self.processor.relationships();
+        relationships.extend(self.processor.dynamic_relationships(&self.context));
+        relationships
+    }
+
+    pub fn with_property(mut self, key: &str, value: &str) -> Self {
+        self.context.set_property(key, value);
+        self
+    }
+
+    pub fn with_sensitive_property(mut self, key: &str, value: &str) -> Self {
+        self.context.set_sensitive_property(key, value);
+        self
+    }
+
+    pub fn auto_terminate(mut self, relationship: &str) -> Self {
+        self.auto_terminated.insert(relationship.to_string());
+        self
+    }
+
+    pub fn run_schedule(mut self, interval: Duration) -> Self {
+        self.run_schedule = interval;
+        self
+    }
+
+    /// Triggers the processor at the times matching a five-field cron
+    /// expression instead of on an interval.
+    pub fn cron_schedule(self, expression: &str) -> Self {
+        self.with_property(CRON_EXPRESSION, expression)
+    }
+
+    /// Gives up on any trigger still running after `limit`.
+    pub fn execution_timeout(self, limit: Duration) -> Self {
+        self.with_property(EXECUTION_TIMEOUT, &limit.as_millis().to_string())
+    }
+
+    /// Lets up to `tasks` triggers run in parallel.
+    pub fn concurrent_tasks(self, tasks: usize) -> Self {
+        self.with_property(CONCURRENT_TASKS, &tasks.to_string())
+    }
+}
+
+/// Parses an `execution.timeout` value: a positive number of milliseconds.
+pub fn parse_execution_timeout(value: &str) -> Result<Duration, String> {
+    match value.trim().parse::<u64>() {
+        Ok(millis) if millis > 0 => Ok(Duration::from_millis(millis)),
+        _ => Err(format!(
+            "'{}' is not a positive number of milliseconds",
+            value
+        )),
+    }
+}
+
+/// Parses a `concurrent.tasks` value: a positive number of tasks.
+pub fn parse_concurrent_tasks(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(tasks) if tasks > 0 => Ok(tasks),
+        _ => Err(format!("'{}' is not a positive number of tasks", value)),
+    }
+}
+
+/// Routes one relationship of `source` into the queue feeding `destination`.
+#[derive(Debug, Clone)]
+pub struct ConnectionDefinition {
+    pub name: String,
+    pub source: String,
+    pub relationship: String,
+    pub destination: String,
+    pub backpressure_threshold: Option<usize>,
+    /// Content bytes at which the queue counts as full, alongside the count.
+    pub backpressure_bytes: Option<u64>,
+    /// Age past which queued FlowFiles are dropped; see
+    /// `MemoryConnection::with_expiration`. Zero does not validate.
+    pub expiration: Option<Duration>,
+    /// Funnel the connection feeds into. Connections naming the same funnel
+    /// share one queue, which takes their sources in turn; see
+    /// `FunnelConnection`.
+    pub funnel: Option<String>,
+}
+
+impl ConnectionDefinition {
+    pub fn new(name: &str, source: &str, relationship: &str, destination: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            source: source.to_string(),
+            relationship: relationship.to_string(),
+            destination: destination.to_string(),
+            backpressure_threshold: None,
+            backpressure_bytes: None,
+            expiration: None,
+            funnel: None,
+        }
+    }
+
+    pub fn with_backpressure(mut self, threshold: usize) -> Self {
+        self.backpressure_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_backpressure_bytes(mut self, bytes: u64) -> Self {
+        self.backpressure_bytes = Some(bytes);
+        self
+    }
+
+    pub fn with_expiration(mut self, expiration: Duration) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub fn through_funnel(mut self, funnel: &str) -> Self {
+        self.funnel = Some(funnel.to_string());
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct FlowDefinition {
+    pub processors: Vec<ProcessorNode>,
+    pub connections: Vec<ConnectionDefinition>,
+    /// Bounds every session of the flow enforces on the FlowFiles it routes.
+    pub attribute_limits: Option<AttributeLimits>,
+}
+
+impl FlowDefinition {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_processor(&mut self, node: ProcessorNode) {
+        self.processors.push(node);
+    }
+
+    pub fn add_connection(&mut self, connection: ConnectionDefinition) {
+        self.connections.push(connection);
+    }
+
+    pub fn set_attribute_limits(&mut self, limits: AttributeLimits) {
+        self.attribute_limits = Some(limits);
+    }
+
+    pub fn processor(&self, name: &str) -> Option<&ProcessorNode> {
+        self.processors.iter().find(|node| node.name() == name)
+    }
+}
@@ -0,0 +1,198 @@
+pub mod attribute;
+pub mod codec;
+pub mod content;
+pub mod fragment;
+pub mod limits;
+
+pub use attribute::AttributeValue;
+pub use content::{Content, ContentReader, ContentWriter};
+
+use crate::clock::{Clock, SystemClock};
+use crate::provenance::{elapsed, Lineage};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+use std::time::{Duration, SystemTime};
+use uuid::Uuid;
+
+/// A unit of data moving through the flow: binary content plus key/value attributes.
+/// Content is immutable once set and shared, not copied, between a FlowFile
+/// and its clones; `set_content` gives a FlowFile content of its own. Large
+/// content can be streamed in and out, see [`content`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowFile {
+    id: Uuid,
+    attributes: HashMap<String, AttributeValue>,
+    #[serde(with = "shared_bytes")]
+    content: Content,
+    created_at: SystemTime,
+    lineage: Lineage,
+    penalized_until: Option<SystemTime>,
+}
+
+impl FlowFile {
+    /// An empty FlowFile created now by the system clock. Code running on a
+    /// flow's clock creates FlowFiles with `created_by` instead.
+    pub fn new() -> Self {
+        Self::created_by("", SystemClock.now())
+    }
+
+    /// Creates an empty FlowFile whose lineage starts with a Create event
+    /// attributed to `component`.
+    pub fn created_by(component: &str, now: SystemTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            attributes: HashMap::new(),
+            content: Content::from(Vec::new()),
+            created_at: now,
+            lineage: Lineage::created(component, now),
+            penalized_until: None,
+        }
+    }
+
+    pub fn with_content(content: impl Into<Vec<u8>>) -> Self {
+        let mut flowfile = Self::new();
+        flowfile.content = content.into().into();
+        flowfile
+    }
+
+    /// A copy with a new id and creation time that shares this FlowFile's
+    /// content. The lineage is carried over; see `ProcessSession::transfer_clone`.
+    pub fn duplicate(&self, now: SystemTime) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            created_at: now,
+            penalized_until: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn attributes(&self) -> &HashMap<String, AttributeValue> {
+        &self.attributes
+    }
+
+    pub fn get_attribute(&self, key: &str) -> Option<&AttributeValue> {
+        self.attributes.get(key)
+    }
+
+    /// Sets a string attribute.
+    pub fn put_attribute(&mut self, key: &str, value: &str) {
+        self.attributes
+            .insert(key.to_string(), AttributeValue::Str(value.to_string()));
+    }
+
+    /// Sets an attribute keeping its type, e.g. `set_attribute("file.size", 42)`.
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<AttributeValue>) {
+        self.attributes.insert(key.to_string(), value.into());
+    }
+
+    pub fn remove_attribute(&mut self, key: &str) -> Option<AttributeValue> {
+        self.attributes.remove(key)
+    }
+
+    /// The whole content. Spilled content is read into memory on every
+    /// call, and that read can fail; prefer `content_reader` for content
+    /// that may be large.
+    pub fn content(&self) -> io::Result<Cow<'_, [u8]>> {
+        self.content.bytes()
+    }
+
+    /// Streams the content without loading all of it into memory.
+    pub fn content_reader(&self) -> ContentReader {
+        self.content.reader()
+    }
+
+    pub fn set_content(&mut self, content: impl Into<Vec<u8>>) {
+        self.content = content.into().into();
+    }
+
+    /// Replaces the content with what was written to `writer`, e.g.
+    ///
+    /// ```
+    /// use std::io::Write;
+    /// use streamsync::flowfile::{ContentWriter, FlowFile};
+    ///
+    /// let mut flowfile = FlowFile::new();
+    /// let mut writer = ContentWriter::new();
+    /// for chunk in ["a", "b", "c"] {
+    ///     writer.write_all(chunk.as_bytes())?;
+    /// }
+    /// flowfile.set_content_from(writer)?;
+    /// assert_eq!(flowfile.content()?.as_ref(), b"abc");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn set_content_from(&mut self, writer: ContentWriter) -> io::Result<()> {
+        self.content = writer.finish()?;
+        Ok(())
+    }
+
+    /// True if the content was spilled to a temporary file.
+    pub fn is_content_spilled(&self) -> bool {
+        self.content.is_spilled()
+    }
+
+    /// True if both FlowFiles point at the same content bytes, as a FlowFile
+    /// and its clones do until one of them is given new content.
+    pub fn shares_content_with(&self, other: &FlowFile) -> bool {
+        self.content.ptr_eq(&other.content)
+    }
+
+    /// Content length in bytes.
+    pub fn size(&self) -> usize {
+        self.content.len()
+    }
+
+    /// A penalized FlowFile is skipped by its queue until the penalty expires.
+    pub fn is_penalized(&self, now: SystemTime) -> bool {
+        self.penalized_until.is_some_and(|until| until > now)
+    }
+
+    pub(crate) fn penalize_until(&mut self, until: SystemTime) {
+        self.penalized_until = Some(until);
+    }
+
+    pub fn lineage(&self) -> &Lineage {
+        &self.lineage
+    }
+
+    pub(crate) fn lineage_mut(&mut self) -> &mut Lineage {
+        &mut self.lineage
+    }
+
+    /// Time since this particular FlowFile was created.
+    pub fn age(&self, clock: &dyn Clock) -> Duration {
+        elapsed(self.created_at, clock.now())
+    }
+
+    /// Time since the first Create event of the lineage this FlowFile belongs to.
+    pub fn lineage_duration(&self, clock: &dyn Clock) -> Duration {
+        elapsed(self.lineage.start(), clock.now())
+    }
+}
+
+impl Default for FlowFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Serializes shared content as plain bytes.
+mod shared_bytes {
+    use super::Content;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(content: &Content, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes = content.bytes().map_err(serde::ser::Error::custom)?;
+        serde_bytes::serialize(bytes.as_ref(), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Content, D::Error> {
+        let content: Vec<u8> = serde_bytes::deserialize(deserializer)?;
+        Ok(content.into())
+    }
+}
@@ -0,0 +1,241 @@
+//! Typed attribute values. Attributes set through `put_attribute` are plain
+//! strings; processors that know a value's type can store it as such with
+//! `set_attribute`, so later comparisons need not parse it again.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Serialized untagged, so a string attribute encodes exactly as it did when
+/// attributes were plain strings. A float that JSON cannot hold as a number
+/// (NaN or an infinity) encodes as `{"float": "NaN"}`, `"inf"` or `"-inf"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    Str(String),
+    Int(i64),
+    Float(#[serde(with = "float")] f64),
+    Bool(bool),
+    Timestamp(SystemTime),
+}
+
+impl AttributeValue {
+    /// The value as an integer: an `Int`, or a `Str` that parses as one.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            AttributeValue::Int(i) => Some(*i),
+            AttributeValue::Str(s) => s.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value as a number: an `Int`, a `Float`, a `Timestamp` (in
+    /// milliseconds since the Unix epoch) or a `Str` that parses as one.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Int(i) => Some(*i as f64),
+            AttributeValue::Float(f) => Some(*f),
+            AttributeValue::Timestamp(t) => Some(epoch_millis(*t) as f64),
+            AttributeValue::Str(s) => s.trim().parse().ok(),
+            AttributeValue::Bool(_) => None,
+        }
+    }
+
+    /// The value as a boolean: a `Bool`, or the string "true" or "false".
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AttributeValue::Bool(b) => Some(*b),
+            AttributeValue::Str(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is typed as, or reads as, a number.
+    pub fn is_numeric(&self) -> bool {
+        self.as_f64().is_some()
+    }
+}
+
+// JSON numbers cannot be NaN or infinite, so those floats are written as
+// an object holding their name, and read back from it.
+mod float {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Encoded {
+        Number(f64),
+        NonFinite { float: String },
+    }
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else {
+            Encoded::NonFinite {
+                float: value.to_string(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Number(value) => Ok(value),
+            Encoded::NonFinite { float } => match float.parse::<f64>() {
+                Ok(value) if !value.is_finite() => Ok(value),
+                _ => Err(D::Error::custom(format!(
+                    "'{}' is not NaN or an infinity",
+                    float
+                ))),
+            },
+        }
+    }
+}
+
+fn epoch_millis(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_millis() as i128,
+        Err(before) => -(before.duration().as_millis() as i128),
+    }
+}
+
+/// Strings print as is and timestamps as milliseconds since the Unix epoch.
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeValue::Str(s) => f.write_str(s),
+            AttributeValue::Int(i) => write!(f, "{}", i),
+            AttributeValue::Float(x) => write!(f, "{}", x),
+            AttributeValue::Bool(b) => write!(f, "{}", b),
+            AttributeValue::Timestamp(t) => write!(f, "{}", epoch_millis(*t)),
+        }
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::Str(value.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::Str(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Int(value)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Float(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+impl From<SystemTime> for AttributeValue {
+    fn from(value: SystemTime) -> Self {
+        AttributeValue::Timestamp(value)
+    }
+}
+
+/// Compares the displayed form, so `Int(5) == "5"`.
+impl PartialEq<str> for AttributeValue {
+    fn eq(&self, other: &str) -> bool {
+        match self {
+            AttributeValue::Str(s) => s == other,
+            typed => {
+                let displayed = typed.to_string();
+                displayed == other
+            }
+        }
+    }
+}
+
+impl PartialEq<&str> for AttributeValue {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+impl PartialEq<String> for AttributeValue {
+    fn eq(&self, other: &String) -> bool {
+        self == other.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(AttributeValue::from(42).as_i64(), Some(42));
+        assert_eq!(AttributeValue::from(" 42 ").as_i64(), Some(42));
+        assert_eq!(AttributeValue::from(4.5).as_i64(), None);
+        assert_eq!(AttributeValue::from(42).as_f64(), Some(42.0));
+        assert_eq!(AttributeValue::from("4.5").as_f64(), Some(4.5));
+        assert_eq!(AttributeValue::from(true).as_f64(), None);
+        assert_eq!(AttributeValue::from("true").as_bool(), Some(true));
+        assert_eq!(AttributeValue::from(1).as_bool(), None);
+        let time = UNIX_EPOCH + Duration::from_millis(1500);
+        assert_eq!(AttributeValue::from(time).as_f64(), Some(1500.0));
+        assert_eq!(AttributeValue::from(time).to_string(), "1500");
+        assert_eq!(AttributeValue::from(7), "7");
+    }
+
+    #[test]
+    fn test_json_keeps_types() {
+        let values = vec![
+            AttributeValue::from("5"),
+            AttributeValue::from(5),
+            AttributeValue::from(5.0),
+            AttributeValue::from(false),
+            AttributeValue::from(UNIX_EPOCH + Duration::from_secs(10)),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        assert!(json.starts_with("[\"5\",5,5.0,false,"), "{}", json);
+        assert_eq!(
+            serde_json::from_str::<Vec<AttributeValue>>(&json).unwrap(),
+            values
+        );
+    }
+
+    #[test]
+    fn test_non_finite_floats_round_trip() {
+        let values = vec![
+            AttributeValue::from(f64::INFINITY),
+            AttributeValue::from(f64::NEG_INFINITY),
+            AttributeValue::from(f64::NAN),
+        ];
+        let json = serde_json::to_string(&values).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"float":"inf"},{"float":"-inf"},{"float":"NaN"}]"#
+        );
+        let decoded = serde_json::from_str::<Vec<AttributeValue>>(&json).unwrap();
+        assert_eq!(decoded[..2], values[..2]);
+        assert!(matches!(decoded[2], AttributeValue::Float(x) if x.is_nan()));
+
+        assert!(serde_json::from_str::<AttributeValue>(r#"{"float":"1.5"}"#).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+//! Framed binary encoding of a FlowFile for moving it between processes.
+//!
+//! A frame is laid out as:
+//!
+//! | field          | size           |                                      |
+//! |----------------|----------------|--------------------------------------|
+//! | magic          | 4              | `SSFF`                               |
+//! | version        | 1              | [`FRAME_VERSION`]                    |
+//! | header length  | 4, big endian  |                                      |
+//! | header         | header length  | JSON: id, attributes, lineage, times |
+//! | content length | 8, big endian  |                                      |
+//! | content        | content length | raw bytes                            |
+
+use super::{AttributeValue, Content, ContentWriter, FlowFile};
+use crate::provenance::Lineage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::SystemTime;
+use uuid::Uuid;
+
+pub const FRAME_MAGIC: [u8; 4] = *b"SSFF";
+pub const FRAME_VERSION: u8 = 1;
+
+// Upper bound on the header so a corrupt length cannot trigger a huge allocation.
+const MAX_HEADER_LEN: u32 = 16 * 1024 * 1024;
+
+#[derive(Debug)]
+pub enum CodecError {
+    Io(io::Error),
+    BadMagic([u8; 4]),
+    UnsupportedVersion(u8),
+    Truncated,
+    InvalidHeader(String),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Io(e) => write!(f, "I/O error: {}", e),
+            CodecError::BadMagic(magic) => write!(f, "not a FlowFile frame (magic {:?})", magic),
+            CodecError::UnsupportedVersion(version) => {
+                write!(f, "unsupported frame version {}", version)
+            }
+            CodecError::Truncated => write!(f, "frame is truncated"),
+            CodecError::InvalidHeader(reason) => write!(f, "invalid frame header: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<io::Error> for CodecError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            CodecError::Truncated
+        } else {
+            CodecError::Io(e)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HeaderRef<'a> {
+    id: Uuid,
+    attributes: &'a HashMap<String, AttributeValue>,
+    created_at: SystemTime,
+    lineage: &'a Lineage,
+    penalized_until: Option<SystemTime>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    id: Uuid,
+    attributes: HashMap<String, AttributeValue>,
+    created_at: SystemTime,
+    lineage: Lineage,
+    penalized_until: Option<SystemTime>,
+}
+
+impl FlowFile {
+    /// Writes this FlowFile as a single frame.
+    pub fn encode_into(&self, writer: &mut impl Write) -> Result<(), CodecError> {
+        let header = serde_json::to_vec(&HeaderRef {
+            id: self.id,
+            attributes: &self.attributes,
+            created_at: self.created_at,
+            lineage: &self.lineage,
+            penalized_until: self.penalized_until,
+        })
+        .map_err(|e| CodecError::InvalidHeader(e.to_string()))?;
+        let header_len = u32::try_from(header.len())
+            .ok()
+            .filter(|len| *len <= MAX_HEADER_LEN)
+            .ok_or_else(|| {
+                CodecError::InvalidHeader(format!("header is {} bytes", header.len()))
+            })?;
+
+        writer.write_all(&FRAME_MAGIC)?;
+        writer.write_all(&[FRAME_VERSION])?;
+        writer.write_all(&header_len.to_be_bytes())?;
+        writer.write_all(&header)?;
+        writer.write_all(&(self.content.len() as u64).to_be_bytes())?;
+        io::copy(&mut self.content.reader(), writer)?;
+        Ok(())
+    }
+
+    /// Reads one frame written by [`FlowFile::encode_into`].
+    pub fn decode_from(reader: &mut impl Read) -> Result<FlowFile, CodecError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != FRAME_MAGIC {
+            return Err(CodecError::BadMagic(magic));
+        }
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version)?;
+        if version[0] != FRAME_VERSION {
+            return Err(CodecError::UnsupportedVersion(version[0]));
+        }
+
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let header_len = u32::from_be_bytes(len);
+        if header_len > MAX_HEADER_LEN {
+            return Err(CodecError::InvalidHeader(format!(
+                "header is {} bytes",
+                header_len
+            )));
+        }
+        let header = read_exactly(reader, header_len as u64)?;
+        let header: Header = serde_json::from_slice(&header)
+            .map_err(|e| CodecError::InvalidHeader(e.to_string()))?;
+
+        let mut len = [0u8; 8];
+        reader.read_exact(&mut len)?;
+        let content = read_content(reader, u64::from_be_bytes(len))?;
+
+        Ok(FlowFile {
+            id: header.id,
+            attributes: header.attributes,
+            content,
+            created_at: header.created_at,
+            lineage: header.lineage,
+            penalized_until: header.penalized_until,
+        })
+    }
+}
+
+// Reads `len` bytes without trusting `len` for the initial allocation.
+fn read_exactly(reader: &mut impl Read, len: u64) -> Result<Vec<u8>, CodecError> {
+    let mut buffer = Vec::new();
+    reader.take(len).read_to_end(&mut buffer)?;
+    if (buffer.len() as u64) < len {
+        return Err(CodecError::Truncated);
+    }
+    Ok(buffer)
+}
+
+// Streams `len` bytes of content, spilling large content to disk rather than
+// holding it all in memory.
+fn read_content(reader: &mut impl Read, len: u64) -> Result<Content, CodecError> {
+    let mut writer = ContentWriter::new();
+    io::copy(&mut reader.take(len), &mut writer)?;
+    if writer.len() < len {
+        return Err(CodecError::Truncated);
+    }
+    Ok(writer.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn round_trip(flowfile: &FlowFile) -> FlowFile {
+        let mut frame = Vec::new();
+        flowfile.encode_into(&mut frame).unwrap();
+        FlowFile::decode_from(&mut frame.as_slice()).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip(
+            content in prop::collection::vec(any::<u8>(), 0..4096),
+            attributes in prop::collection::hash_map("\\PC{0,16}", "\\PC{0,32}", 0..8),
+        ) {
+            let mut flowfile = FlowFile::with_content(content);
+            for (key, value) in &attributes {
+                flowfile.put_attribute(key, value);
+            }
+            prop_assert_eq!(round_trip(&flowfile), flowfile);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_edge_cases() {
+        let empty = FlowFile::new();
+        assert_eq!(round_trip(&empty), empty);
+
+        let large = FlowFile::with_content(vec![0xAB; 8 * 1024 * 1024]);
+        assert_eq!(round_trip(&large), large);
+
+        let mut unicode = FlowFile::with_content("naïve");
+        unicode.put_attribute("日本語", "värde ✓");
+        unicode.put_attribute("", "");
+        assert_eq!(round_trip(&unicode), unicode);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut flowfile = FlowFile::with_content(vec![0, 1, 2, 255]);
+        flowfile.put_attribute("filename", "data.bin");
+        let json = serde_json::to_string(&flowfile).unwrap();
+        assert_eq!(serde_json::from_str::<FlowFile>(&json).unwrap(), flowfile);
+    }
+
+    #[test]
+    fn test_rejects_bad_frames() {
+        let mut frame = Vec::new();
+        FlowFile::with_content("hello")
+            .encode_into(&mut frame)
+            .unwrap();
+
+        let mut wrong_version = frame.clone();
+        wrong_version[4] = 9;
+        assert!(matches!(
+            FlowFile::decode_from(&mut wrong_version.as_slice()),
+            Err(CodecError::UnsupportedVersion(9))
+        ));
+
+        let mut wrong_magic = frame.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(
+            FlowFile::decode_from(&mut wrong_magic.as_slice()),
+            Err(CodecError::BadMagic(_))
+        ));
+
+        for len in [0, 3, 7, frame.len() - 1] {
+            assert!(matches!(
+                FlowFile::decode_from(&mut &frame[..len]),
+                Err(CodecError::Truncated)
+            ));
+        }
+    }
+}
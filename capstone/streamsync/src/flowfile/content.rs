@@ -0,0 +1,350 @@
+//! FlowFile content that may live in memory or, once it grows past a
+//! threshold, in a temporary file.
+//!
+//! Processors that handle large payloads read content through
+//! [`FlowFile::content_reader`](super::FlowFile::content_reader) and write it
+//! through a [`ContentWriter`], so only one chunk is held in memory at a
+//! time. `FlowFile::content` still works on spilled content, but reads the
+//! whole file into memory each time it is called.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Content larger than this is written to a temporary file by a
+/// `ContentWriter` made with `new`.
+pub const DEFAULT_SPILL_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// Where spilled content goes, under the system temporary directory.
+pub const SPILL_DIRECTORY: &str = "streamsync-content";
+
+/// Immutable content, cheap to clone: clones share the same bytes or file.
+#[derive(Clone)]
+pub enum Content {
+    Memory(Arc<[u8]>),
+    Spilled(Arc<SpillFile>),
+}
+
+/// A temporary file holding content, removed when the last FlowFile
+/// referring to it is dropped.
+pub struct SpillFile {
+    path: PathBuf,
+    len: u64,
+}
+
+// Names the spill file in an error reading it, which otherwise says only
+// that some file is missing.
+fn spill_error(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(
+        e.kind(),
+        format!("cannot read spilled content {}: {}", path.display(), e),
+    )
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl Content {
+    pub fn len(&self) -> usize {
+        match self {
+            Content::Memory(bytes) => bytes.len(),
+            Content::Spilled(file) => file.len as usize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_spilled(&self) -> bool {
+        matches!(self, Content::Spilled(_))
+    }
+
+    /// All of the content in memory. A spilled file is read anew on every
+    /// call, which fails if it can no longer be read, e.g. because it was
+    /// deleted from under the process.
+    pub fn bytes(&self) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            Content::Memory(bytes) => Ok(Cow::Borrowed(bytes)),
+            Content::Spilled(file) => fs::read(&file.path)
+                .map(Cow::Owned)
+                .map_err(|e| spill_error(&file.path, e)),
+        }
+    }
+
+    /// Streams the content without loading all of it.
+    pub fn reader(&self) -> ContentReader {
+        match self {
+            Content::Memory(bytes) => ContentReader::Memory(Cursor::new(bytes.clone())),
+            Content::Spilled(file) => ContentReader::Spilled {
+                file: file.clone(),
+                reader: None,
+            },
+        }
+    }
+
+    /// True if both refer to the same bytes or the same file.
+    pub fn ptr_eq(&self, other: &Content) -> bool {
+        match (self, other) {
+            (Content::Memory(a), Content::Memory(b)) => Arc::ptr_eq(a, b),
+            (Content::Spilled(a), Content::Spilled(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Content {
+    fn from(bytes: Vec<u8>) -> Self {
+        Content::Memory(bytes.into())
+    }
+}
+
+// Content that cannot be read equals no other content.
+impl PartialEq for Content {
+    fn eq(&self, other: &Self) -> bool {
+        if self.ptr_eq(other) {
+            return true;
+        }
+        if self.len() != other.len() {
+            return false;
+        }
+        match (self.bytes(), other.bytes()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for Content {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Content::Memory(bytes) => f.debug_tuple("Memory").field(&bytes.len()).finish(),
+            Content::Spilled(file) => f
+                .debug_tuple("Spilled")
+                .field(&file.path)
+                .field(&file.len)
+                .finish(),
+        }
+    }
+}
+
+/// Reads content chunk by chunk. A spilled file is opened on the first read,
+/// so any error opening it surfaces there.
+pub enum ContentReader {
+    Memory(Cursor<Arc<[u8]>>),
+    Spilled {
+        file: Arc<SpillFile>,
+        reader: Option<BufReader<File>>,
+    },
+}
+
+impl Read for ContentReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ContentReader::Memory(cursor) => cursor.read(buf),
+            ContentReader::Spilled { file, reader } => {
+                if reader.is_none() {
+                    let opened = File::open(&file.path).map_err(|e| spill_error(&file.path, e))?;
+                    *reader = Some(BufReader::new(opened));
+                }
+                reader.as_mut().expect("opened above").read(buf)
+            }
+        }
+    }
+}
+
+/// Builds new content from chunks written to it, in memory until it passes
+/// the threshold and in a temporary file from then on.
+pub struct ContentWriter {
+    threshold: usize,
+    buffer: Vec<u8>,
+    spill: Option<(PathBuf, File)>,
+    len: u64,
+}
+
+impl ContentWriter {
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_SPILL_THRESHOLD)
+    }
+
+    /// Spills to disk once more than `threshold` bytes have been written.
+    pub fn with_threshold(threshold: usize) -> Self {
+        Self {
+            threshold,
+            buffer: Vec::new(),
+            spill: None,
+            len: 0,
+        }
+    }
+
+    /// Bytes written so far.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Flushes what was written and hands it over as content.
+    pub fn finish(mut self) -> io::Result<Content> {
+        match self.spill.take() {
+            None => Ok(Content::Memory(std::mem::take(&mut self.buffer).into())),
+            Some((path, mut file)) => {
+                file.flush()?;
+                Ok(Content::Spilled(Arc::new(SpillFile {
+                    path,
+                    len: self.len,
+                })))
+            }
+        }
+    }
+
+    // Moves the buffered bytes into a new temporary file.
+    fn spill(&mut self) -> io::Result<()> {
+        let directory = std::env::temp_dir().join(SPILL_DIRECTORY);
+        fs::create_dir_all(&directory)?;
+        let path = directory.join(Uuid::new_v4().to_string());
+        let mut file = File::create(&path)?;
+        file.write_all(&self.buffer)?;
+        self.buffer = Vec::new();
+        self.spill = Some((path, file));
+        Ok(())
+    }
+}
+
+impl Default for ContentWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Write for ContentWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.spill.is_none() && self.buffer.len() + buf.len() > self.threshold {
+            self.spill()?;
+        }
+        let written = match &mut self.spill {
+            Some((_, file)) => file.write(buf)?,
+            None => {
+                self.buffer.extend_from_slice(buf);
+                buf.len()
+            }
+        };
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.spill {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ContentWriter {
+    // A writer dropped without `finish` leaves no file behind.
+    fn drop(&mut self) {
+        if let Some((path, _)) = &self.spill {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn write_in_chunks(writer: &mut ContentWriter, data: &[u8]) {
+        for chunk in data.chunks(1000) {
+            writer.write_all(chunk).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_small_content_stays_in_memory() {
+        let mut writer = ContentWriter::with_threshold(4096);
+        write_in_chunks(&mut writer, &payload(4096));
+        let content = writer.finish().unwrap();
+        assert!(!content.is_spilled());
+        assert_eq!(content.bytes().unwrap().as_ref(), &payload(4096)[..]);
+    }
+
+    #[test]
+    fn test_streamed_read_of_spilled_content_matches_original() {
+        let original = payload(100_000);
+        let mut writer = ContentWriter::with_threshold(16 * 1024);
+        write_in_chunks(&mut writer, &original);
+        let content = writer.finish().unwrap();
+        assert!(content.is_spilled());
+        assert_eq!(content.len(), original.len());
+
+        let mut streamed = Vec::new();
+        let mut reader = content.reader();
+        let mut chunk = [0u8; 777];
+        loop {
+            let n = reader.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&chunk[..n]);
+        }
+        assert_eq!(streamed, original);
+        assert_eq!(content.bytes().unwrap().as_ref(), &original[..]);
+    }
+
+    #[test]
+    fn test_deleted_spill_file_is_an_error() {
+        let mut writer = ContentWriter::with_threshold(10);
+        writer.write_all(&payload(100)).unwrap();
+        let content = writer.finish().unwrap();
+        let Content::Spilled(file) = &content else {
+            panic!("expected spilled content");
+        };
+        fs::remove_file(&file.path).unwrap();
+
+        let error = content.bytes().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(error.to_string().contains(&file.path.display().to_string()));
+        assert!(content.reader().read(&mut [0u8; 8]).is_err());
+        assert_ne!(content, Content::from(payload(100)));
+    }
+
+    #[test]
+    fn test_spill_file_removed_with_last_clone() {
+        let mut writer = ContentWriter::with_threshold(10);
+        writer.write_all(&payload(100)).unwrap();
+        let content = writer.finish().unwrap();
+        let Content::Spilled(file) = &content else {
+            panic!("expected spilled content");
+        };
+        let path = file.path.clone();
+
+        let clone = content.clone();
+        drop(content);
+        assert!(path.exists());
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unfinished_writer_leaves_no_file() {
+        let mut writer = ContentWriter::with_threshold(10);
+        writer.write_all(&payload(100)).unwrap();
+        let path = writer.spill.as_ref().unwrap().0.clone();
+        drop(writer);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,186 @@
+//! Attributes that tie the fragments of a split FlowFile back to it, so a
+//! processor merging them can restore what the split lost.
+//!
+//! A splitting processor calls `mark_fragment` on each piece it emits; a
+//! merging processor collects the pieces of one identifier and calls
+//! `restore_filename` on the FlowFile it builds from them. The original
+//! `filename` survives however the fragments are renamed in between.
+//! `SplitText` and `MergeContent` are the processors that do so.
+
+use super::FlowFile;
+
+pub const FILENAME: &str = "filename";
+/// Shared by every fragment of one split: the id of the FlowFile split.
+pub const FRAGMENT_IDENTIFIER: &str = "fragment.identifier";
+/// Position of the fragment among its siblings, from 0.
+pub const FRAGMENT_INDEX: &str = "fragment.index";
+pub const FRAGMENT_COUNT: &str = "fragment.count";
+/// `filename` of the FlowFile split, as it was before the split.
+pub const FRAGMENT_ORIGINAL_FILENAME: &str = "fragment.original.filename";
+
+/// Marks `fragment` as piece `index` of `count` split from `original`. A
+/// fragment split again keeps the first original filename, not its own.
+pub fn mark_fragment(original: &FlowFile, fragment: &mut FlowFile, index: usize, count: usize) {
+    fragment.put_attribute(FRAGMENT_IDENTIFIER, &original.id().to_string());
+    fragment.set_attribute(FRAGMENT_INDEX, index as i64);
+    fragment.set_attribute(FRAGMENT_COUNT, count as i64);
+    let filename = original
+        .get_attribute(FRAGMENT_ORIGINAL_FILENAME)
+        .or_else(|| original.get_attribute(FILENAME))
+        .map(ToString::to_string);
+    if let Some(filename) = filename {
+        fragment.put_attribute(FRAGMENT_ORIGINAL_FILENAME, &filename);
+    }
+}
+
+/// The name for a FlowFile merged from `fragments`: their original filename
+/// when they all agree on one, otherwise a name derived from their common
+/// `fragment.identifier`, or failing that from the smallest fragment id, so
+/// merging the same fragments always gives the same name.
+pub fn merged_filename(fragments: &[FlowFile]) -> String {
+    if let Some(filename) = common_attribute(fragments, FRAGMENT_ORIGINAL_FILENAME) {
+        return filename;
+    }
+    match common_attribute(fragments, FRAGMENT_IDENTIFIER) {
+        Some(identifier) => format!("merged-{}", identifier),
+        None => match fragments.iter().map(FlowFile::id).min() {
+            Some(id) => format!("merged-{}", id),
+            None => "merged".to_string(),
+        },
+    }
+}
+
+/// Names `merged` after the fragments it was built from (see
+/// `merged_filename`) and drops the fragment attributes it inherited.
+pub fn restore_filename(merged: &mut FlowFile, fragments: &[FlowFile]) {
+    merged.put_attribute(FILENAME, &merged_filename(fragments));
+    for key in [
+        FRAGMENT_IDENTIFIER,
+        FRAGMENT_INDEX,
+        FRAGMENT_COUNT,
+        FRAGMENT_ORIGINAL_FILENAME,
+    ] {
+        merged.remove_attribute(key);
+    }
+}
+
+// The value of `key` when every fragment has the same one.
+fn common_attribute(fragments: &[FlowFile], key: &str) -> Option<String> {
+    let first = fragments.first()?.get_attribute(key)?;
+    fragments
+        .iter()
+        .all(|fragment| fragment.get_attribute(key) == Some(first))
+        .then(|| first.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Splits `original` into one fragment per line, each renamed the way a
+    // downstream processor might.
+    fn split_lines(original: &FlowFile) -> Vec<FlowFile> {
+        let text = String::from_utf8(original.content().unwrap().to_vec()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let mut fragment = FlowFile::with_content(line.as_bytes());
+                mark_fragment(original, &mut fragment, index, lines.len());
+                fragment.put_attribute(FILENAME, &format!("part-{}.txt", index));
+                fragment
+            })
+            .collect()
+    }
+
+    // Joins fragments back in index order, as a merging processor would.
+    fn merge(fragments: &mut [FlowFile]) -> FlowFile {
+        fragments.sort_by_key(|fragment| {
+            fragment
+                .get_attribute(FRAGMENT_INDEX)
+                .and_then(|i| i.as_i64())
+        });
+        let lines: Vec<Vec<u8>> = fragments
+            .iter()
+            .map(|f| f.content().unwrap().into_owned())
+            .collect();
+        let mut merged = FlowFile::with_content(lines.join(&b'\n'));
+        for (key, value) in fragments[0].attributes() {
+            merged.set_attribute(key, value.clone());
+        }
+        restore_filename(&mut merged, fragments);
+        merged
+    }
+
+    #[test]
+    fn test_filename_survives_split_then_merge() {
+        let mut original = FlowFile::with_content("alpha\nbeta\ngamma");
+        original.put_attribute(FILENAME, "report.csv");
+        let mut fragments = split_lines(&original);
+        assert_eq!(fragments.len(), 3);
+        assert_eq!(
+            fragments[1].get_attribute(FRAGMENT_INDEX).unwrap().as_i64(),
+            Some(1)
+        );
+        assert_eq!(
+            fragments[1].get_attribute(FRAGMENT_COUNT).unwrap().as_i64(),
+            Some(3)
+        );
+        assert_eq!(
+            fragments[1].get_attribute(FILENAME).unwrap().to_string(),
+            "part-1.txt"
+        );
+
+        fragments.reverse();
+        let merged = merge(&mut fragments);
+        assert_eq!(merged.content().unwrap().as_ref(), b"alpha\nbeta\ngamma");
+        assert_eq!(
+            merged.get_attribute(FILENAME).unwrap().to_string(),
+            "report.csv"
+        );
+        assert!(merged.get_attribute(FRAGMENT_ORIGINAL_FILENAME).is_none());
+        assert!(merged.get_attribute(FRAGMENT_IDENTIFIER).is_none());
+    }
+
+    #[test]
+    fn test_fragment_split_again_keeps_first_filename() {
+        let mut original = FlowFile::with_content("a\nb");
+        original.put_attribute(FILENAME, "report.csv");
+        let fragment = split_lines(&original).remove(0);
+        let refragmented = split_lines(&fragment);
+        assert_eq!(
+            refragmented[0]
+                .get_attribute(FRAGMENT_ORIGINAL_FILENAME)
+                .unwrap()
+                .to_string(),
+            "report.csv"
+        );
+        assert_eq!(
+            refragmented[0]
+                .get_attribute(FRAGMENT_IDENTIFIER)
+                .unwrap()
+                .to_string(),
+            fragment.id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_merged_name_is_deterministic_without_a_filename() {
+        let original = FlowFile::with_content("a\nb");
+        let fragments = split_lines(&original);
+        assert_eq!(
+            merged_filename(&fragments),
+            format!("merged-{}", original.id())
+        );
+
+        let mut unrelated = vec![FlowFile::with_content("x"), FlowFile::with_content("y")];
+        unrelated[0].put_attribute(FRAGMENT_ORIGINAL_FILENAME, "one.csv");
+        unrelated[1].put_attribute(FRAGMENT_ORIGINAL_FILENAME, "two.csv");
+        let smallest = unrelated.iter().map(FlowFile::id).min().unwrap();
+        assert_eq!(merged_filename(&unrelated), format!("merged-{}", smallest));
+        unrelated.reverse();
+        assert_eq!(merged_filename(&unrelated), format!("merged-{}", smallest));
+        assert_eq!(merged_filename(&[]), "merged");
+    }
+}
@@ -0,0 +1,223 @@
+//! Framework-level bounds on FlowFile attributes, so a misbehaving processor
+//! cannot bloat every queue (and every checkpoint) downstream of it.
+//!
+//! Limits are set on the `FlowDefinition` and enforced by the session when
+//! it commits, on every FlowFile it routes.
+
+use super::{AttributeValue, FlowFile};
+use crate::relationship::{Relationship, FAILURE};
+use serde::Deserialize;
+use std::fmt;
+
+/// What happens to a FlowFile that breaks the limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LimitPolicy {
+    /// Cut long values down to the maximum length and drop excess attributes.
+    Truncate,
+    /// Drop long values and excess attributes.
+    DropAttribute,
+    /// Drop as `DropAttribute` does, then route the FlowFile to the
+    /// processor's `failure` relationship instead of where it was going. A
+    /// processor without one falls back to `DropAttribute`.
+    RouteToFailure,
+}
+
+impl fmt::Display for LimitPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LimitPolicy::Truncate => "truncate",
+            LimitPolicy::DropAttribute => "drop-attribute",
+            LimitPolicy::RouteToFailure => "route-to-failure",
+        })
+    }
+}
+
+/// Unset limits are not enforced. Value lengths are in bytes and only apply
+/// to string attributes; typed values are small by construction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeLimits {
+    pub max_value_length: Option<usize>,
+    pub max_attributes: Option<usize>,
+    pub policy: LimitPolicy,
+}
+
+impl AttributeLimits {
+    pub fn new(policy: LimitPolicy) -> Self {
+        Self {
+            max_value_length: None,
+            max_attributes: None,
+            policy,
+        }
+    }
+
+    pub fn max_value_length(mut self, bytes: usize) -> Self {
+        self.max_value_length = Some(bytes);
+        self
+    }
+
+    pub fn max_attributes(mut self, count: usize) -> Self {
+        self.max_attributes = Some(count);
+        self
+    }
+
+    /// The limits as enforced on a processor with `relationships`: one that
+    /// has no `failure` relationship drops attributes instead.
+    pub fn for_relationships(&self, relationships: &[Relationship]) -> Self {
+        let mut limits = self.clone();
+        if limits.policy == LimitPolicy::RouteToFailure
+            && !relationships.iter().any(|r| r.name == FAILURE)
+        {
+            limits.policy = LimitPolicy::DropAttribute;
+        }
+        limits
+    }
+
+    /// Brings `flowfile` within the limits according to the policy and
+    /// describes what was wrong with it; nothing is changed, and nothing
+    /// returned, for a FlowFile that already complies.
+    pub fn enforce(&self, flowfile: &mut FlowFile) -> Vec<String> {
+        let mut violations = Vec::new();
+        if let Some(max) = self.max_value_length {
+            let mut long: Vec<(String, usize)> = flowfile
+                .attributes()
+                .iter()
+                .filter_map(|(key, value)| match value {
+                    AttributeValue::Str(s) if s.len() > max => Some((key.clone(), s.len())),
+                    _ => None,
+                })
+                .collect();
+            long.sort();
+            for (key, len) in long {
+                violations.push(format!(
+                    "'{}' is {} bytes, over the limit of {}",
+                    key, len, max
+                ));
+                if self.policy == LimitPolicy::Truncate {
+                    let value = flowfile
+                        .get_attribute(&key)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default();
+                    flowfile.put_attribute(&key, truncated(&value, max));
+                } else {
+                    flowfile.remove_attribute(&key);
+                }
+            }
+        }
+        if let Some(max) = self.max_attributes {
+            let count = flowfile.attributes().len();
+            if count > max {
+                violations.push(format!("{} attributes, over the limit of {}", count, max));
+                // The bulkiest attributes go first, as the likeliest bloat.
+                let mut by_size: Vec<(usize, String)> = flowfile
+                    .attributes()
+                    .iter()
+                    .map(|(key, value)| (value.to_string().len(), key.clone()))
+                    .collect();
+                by_size.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+                for (_, key) in by_size.into_iter().take(count - max) {
+                    flowfile.remove_attribute(&key);
+                }
+            }
+        }
+        violations
+    }
+}
+
+// The longest prefix of `value` within `max` bytes that ends on a character
+// boundary.
+fn truncated(value: &str, max: usize) -> &str {
+    let mut end = max.min(value.len());
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bloated() -> FlowFile {
+        let mut flowfile = FlowFile::with_content("payload");
+        flowfile.put_attribute("filename", "a.txt");
+        flowfile.put_attribute("blob", &"x".repeat(100));
+        flowfile.set_attribute("size", 7);
+        flowfile
+    }
+
+    #[test]
+    fn test_truncate_cuts_long_values() {
+        let mut flowfile = bloated();
+        let violations = AttributeLimits::new(LimitPolicy::Truncate)
+            .max_value_length(10)
+            .enforce(&mut flowfile);
+        assert_eq!(
+            violations,
+            vec!["'blob' is 100 bytes, over the limit of 10"]
+        );
+        assert_eq!(
+            flowfile.get_attribute("blob").unwrap().to_string(),
+            "x".repeat(10)
+        );
+        assert_eq!(flowfile.attributes().len(), 3);
+    }
+
+    #[test]
+    fn test_truncate_keeps_whole_characters() {
+        assert_eq!(truncated("ééé", 3), "é");
+        assert_eq!(truncated("abc", 10), "abc");
+    }
+
+    #[test]
+    fn test_drop_attribute_removes_long_values() {
+        let mut flowfile = bloated();
+        let violations = AttributeLimits::new(LimitPolicy::DropAttribute)
+            .max_value_length(10)
+            .enforce(&mut flowfile);
+        assert_eq!(violations.len(), 1);
+        assert!(flowfile.get_attribute("blob").is_none());
+        assert!(flowfile.get_attribute("filename").is_some());
+    }
+
+    #[test]
+    fn test_excess_attributes_drop_bulkiest_first() {
+        let mut flowfile = bloated();
+        let violations = AttributeLimits::new(LimitPolicy::Truncate)
+            .max_attributes(2)
+            .enforce(&mut flowfile);
+        assert_eq!(violations, vec!["3 attributes, over the limit of 2"]);
+        assert!(flowfile.get_attribute("blob").is_none());
+        assert_eq!(flowfile.attributes().len(), 2);
+    }
+
+    #[test]
+    fn test_compliant_flowfile_is_untouched() {
+        let mut flowfile = bloated();
+        let before = flowfile.clone();
+        let violations = AttributeLimits::new(LimitPolicy::RouteToFailure)
+            .max_value_length(100)
+            .max_attributes(3)
+            .enforce(&mut flowfile);
+        assert!(violations.is_empty());
+        assert_eq!(flowfile, before);
+    }
+
+    #[test]
+    fn test_route_to_failure_needs_a_failure_relationship() {
+        let limits = AttributeLimits::new(LimitPolicy::RouteToFailure).max_value_length(16);
+        let both = [Relationship::success(), Relationship::failure()];
+        assert_eq!(
+            limits.for_relationships(&both).policy,
+            LimitPolicy::RouteToFailure
+        );
+        let only_success = limits.for_relationships(&[Relationship::success()]);
+        assert_eq!(only_success.policy, LimitPolicy::DropAttribute);
+        assert_eq!(only_success.max_value_length, Some(16));
+        let truncate = AttributeLimits::new(LimitPolicy::Truncate);
+        assert_eq!(
+            truncate.for_relationships(&[]).policy,
+            LimitPolicy::Truncate
+        );
+    }
+}
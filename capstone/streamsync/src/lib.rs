@@ -0,0 +1,31 @@
+#[cfg(feature = "http-api")]
+pub mod api;
+pub mod bench;
+pub mod bulletin;
+pub mod checkpoint;
+pub mod cli;
+pub mod clock;
+pub mod connection;
+pub mod controller;
+pub mod cron;
+pub mod expression;
+pub mod flow;
+pub mod flowfile;
+pub mod loader;
+pub mod logging;
+pub mod metrics;
+pub mod parameter;
+pub mod processor;
+pub mod processor_context;
+pub mod processors;
+pub mod properties_file;
+pub mod property;
+pub mod provenance;
+pub mod registry;
+pub mod relationship;
+pub mod render;
+pub mod service;
+pub mod session;
+pub mod state;
+pub mod testing;
+pub mod validation;
@@ -0,0 +1,415 @@
+//! Loads a `FlowDefinition` from YAML:
+//!
+//! ```yaml
+//! parameters:
+//!   log.level: debug
+//!   db.key:
+//!     sensitive: true
+//! processors:
+//!   - name: log
+//!     type: LogProcessor
+//!     properties:
+//!       log.level: "#{log.level}"
+//!     auto_terminate: [success]
+//!     run_schedule_ms: 100
+//!   - name: store
+//!     type: PutDatabase
+//!     properties:
+//!       database.path: "/secure/#{db.key}/flow.db"
+//!       table.name: events
+//! connections:
+//!   - name: source-to-log
+//!     source: source
+//!     relationship: success
+//!     destination: log
+//!     backpressure: 1000
+//!     backpressure_bytes: 1048576
+//!     expiration_ms: 60000
+//!   - name: store-to-log
+//!     source: store
+//!     relationship: success
+//!     destination: log
+//!     funnel: to-log
+//! attribute_limits:
+//!   max_value_length: 4096
+//!   max_attributes: 64
+//!   policy: truncate
+//! ```
+//!
+//! Setting a processor's `cron.expression` property (see `crate::cron`) runs it
+//! at fixed times instead of every `run_schedule_ms`, its
+//! `execution.timeout` property bounds each trigger to that many
+//! milliseconds (see `crate::flow::EXECUTION_TIMEOUT`), and its
+//! `concurrent.tasks` property lets that many triggers run at once (see
+//! `crate::flow::CONCURRENT_TASKS`). A connection's `expiration_ms`, if given
+//! at all, must be positive; it drops FlowFiles older than that instead of
+//! delivering them, with a bulletin. Connections naming the same `funnel`
+//! share one queue that takes their sources in turn (see
+//! `crate::connection::funnel`); they must lead to the same processor with
+//! the same `backpressure`, and set no byte limit or expiration. Property
+//! values may use
+//! `#{name}` to refer to a parameter (see `crate::parameter`); a parameter
+//! marked `sensitive` may leave out its value and take it from the
+//! environment. `attribute_limits` bounds the attributes of every FlowFile
+//! routed in the flow, with a `policy` of `truncate`, `drop-attribute` or
+//! `route-to-failure` (see `crate::flowfile::limits`).
+
+use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+use crate::flowfile::limits::{AttributeLimits, LimitPolicy};
+use crate::parameter::{Parameter, ParameterContext};
+use crate::registry::ProcessorRegistry;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Parse(String),
+    UnknownProcessorType {
+        processor: String,
+        type_name: String,
+    },
+    UnresolvedParameters(Vec<String>),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "cannot read flow file: {}", e),
+            LoadError::Parse(message) => write!(f, "invalid flow file: {}", message),
+            LoadError::UnknownProcessorType {
+                processor,
+                type_name,
+            } => {
+                write!(f, "{}: unknown processor type '{}'", processor, type_name)
+            }
+            LoadError::UnresolvedParameters(names) => {
+                write!(f, "unresolved parameters: {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FlowFileConfig {
+    #[serde(default)]
+    parameters: BTreeMap<String, ParameterConfig>,
+    #[serde(default)]
+    processors: Vec<ProcessorConfig>,
+    #[serde(default)]
+    connections: Vec<ConnectionConfig>,
+    attribute_limits: Option<AttributeLimitsConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ParameterConfig {
+    Value(String),
+    Detailed {
+        value: Option<String>,
+        #[serde(default)]
+        sensitive: bool,
+    },
+}
+
+impl From<ParameterConfig> for Parameter {
+    fn from(config: ParameterConfig) -> Self {
+        match config {
+            ParameterConfig::Value(value) => Parameter {
+                value: Some(value),
+                sensitive: false,
+            },
+            ParameterConfig::Detailed { value, sensitive } => Parameter { value, sensitive },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProcessorConfig {
+    name: String,
+    #[serde(rename = "type")]
+    type_name: String,
+    #[serde(default)]
+    properties: BTreeMap<String, String>,
+    #[serde(default)]
+    auto_terminate: Vec<String>,
+    #[serde(default)]
+    run_schedule_ms: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConnectionConfig {
+    name: String,
+    source: String,
+    relationship: String,
+    destination: String,
+    backpressure: Option<usize>,
+    backpressure_bytes: Option<u64>,
+    expiration_ms: Option<u64>,
+    funnel: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AttributeLimitsConfig {
+    max_value_length: Option<usize>,
+    max_attributes: Option<usize>,
+    policy: LimitPolicy,
+}
+
+impl From<AttributeLimitsConfig> for AttributeLimits {
+    fn from(config: AttributeLimitsConfig) -> Self {
+        Self {
+            max_value_length: config.max_value_length,
+            max_attributes: config.max_attributes,
+            policy: config.policy,
+        }
+    }
+}
+
+pub fn load_flow(path: &Path, registry: &ProcessorRegistry) -> Result<FlowDefinition, LoadError> {
+    let yaml = std::fs::read_to_string(path).map_err(LoadError::Io)?;
+    parse_flow(&yaml, registry)
+}
+
+pub fn parse_flow(yaml: &str, registry: &ProcessorRegistry) -> Result<FlowDefinition, LoadError> {
+    parse_flow_with_parameters(yaml, registry, ParameterContext::new())
+}
+
+/// Like `parse_flow`, resolving parameters against `parameters` (normally
+/// just the process environment) plus the file's own `parameters:` section.
+pub fn parse_flow_with_parameters(
+    yaml: &str,
+    registry: &ProcessorRegistry,
+    mut parameters: ParameterContext,
+) -> Result<FlowDefinition, LoadError> {
+    let config: FlowFileConfig =
+        serde_yaml::from_str(yaml).map_err(|e| LoadError::Parse(e.to_string()))?;
+    for (name, parameter) in config.parameters {
+        parameters.insert(&name, parameter.into());
+    }
+
+    let mut flow = FlowDefinition::new();
+    let mut unresolved = BTreeSet::new();
+    for processor in config.processors {
+        let instance = registry.create(&processor.type_name).ok_or_else(|| {
+            LoadError::UnknownProcessorType {
+                processor: processor.name.clone(),
+                type_name: processor.type_name.clone(),
+            }
+        })?;
+        let mut node = ProcessorNode::from_arc(&processor.name, instance)
+            .run_schedule(Duration::from_millis(processor.run_schedule_ms));
+        for (key, value) in &processor.properties {
+            match parameters.substitute(value) {
+                Ok(resolved) if resolved.sensitive => {
+                    node = node.with_sensitive_property(key, &resolved.value)
+                }
+                Ok(resolved) => node = node.with_property(key, &resolved.value),
+                Err(names) => unresolved.extend(names),
+            }
+        }
+        for relationship in &processor.auto_terminate {
+            node = node.auto_terminate(relationship);
+        }
+        flow.add_processor(node);
+    }
+    for connection in config.connections {
+        let mut definition = ConnectionDefinition::new(
+            &connection.name,
+            &connection.source,
+            &connection.relationship,
+            &connection.destination,
+        );
+        definition.backpressure_threshold = connection.backpressure;
+        definition.backpressure_bytes = connection.backpressure_bytes;
+        definition.expiration = connection.expiration_ms.map(Duration::from_millis);
+        definition.funnel = connection.funnel;
+        flow.add_connection(definition);
+    }
+    if let Some(limits) = config.attribute_limits {
+        flow.set_attribute_limits(limits.into());
+    }
+    if !unresolved.is_empty() {
+        return Err(LoadError::UnresolvedParameters(
+            unresolved.into_iter().collect(),
+        ));
+    }
+    Ok(flow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flow() {
+        let yaml = r#"
+processors:
+  - name: pass
+    type: FileProcessor
+  - name: log
+    type: LogProcessor
+    properties:
+      log.level: debug
+    auto_terminate: [success]
+    run_schedule_ms: 250
+connections:
+  - name: pass-to-log
+    source: pass
+    relationship: success
+    destination: log
+    backpressure: 10
+    backpressure_bytes: 4096
+    expiration_ms: 60000
+  - name: pass-failure-to-log
+    source: pass
+    relationship: failure
+    destination: log
+    funnel: to-log
+"#;
+        let flow = parse_flow(yaml, &ProcessorRegistry::with_builtins()).unwrap();
+        let log = flow.processor("log").unwrap();
+        assert_eq!(log.processor.get_name(), "LogProcessor");
+        assert_eq!(log.context.get_property("log.level").unwrap(), "debug");
+        assert_eq!(log.run_schedule, Duration::from_millis(250));
+        assert!(log.auto_terminated.contains("success"));
+        assert_eq!(flow.connections[0].backpressure_threshold, Some(10));
+        assert_eq!(flow.connections[0].backpressure_bytes, Some(4096));
+        assert_eq!(
+            flow.connections[0].expiration,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(flow.connections[0].funnel, None);
+        assert_eq!(flow.connections[1].funnel.as_deref(), Some("to-log"));
+        assert_eq!(flow.attribute_limits, None);
+    }
+
+    #[test]
+    fn test_parse_attribute_limits() {
+        let yaml = r#"
+attribute_limits:
+  max_value_length: 256
+  policy: route-to-failure
+"#;
+        let flow = parse_flow(yaml, &ProcessorRegistry::with_builtins()).unwrap();
+        assert_eq!(
+            flow.attribute_limits,
+            Some(AttributeLimits::new(LimitPolicy::RouteToFailure).max_value_length(256))
+        );
+
+        let unknown = "attribute_limits:\n  policy: shrink\n";
+        assert!(matches!(
+            parse_flow(unknown, &ProcessorRegistry::with_builtins()),
+            Err(LoadError::Parse(_))
+        ));
+    }
+
+    const PARAMETERIZED: &str = r##"
+parameters:
+  log.level: info
+  input.dir: /data/in
+  db.key:
+    sensitive: true
+processors:
+  - name: read
+    type: GetFileProcessor
+    properties:
+      input.directory: "#{input.dir}"
+  - name: log
+    type: LogProcessor
+    properties:
+      log.level: "#{log.level}"
+  - name: store
+    type: PutDatabase
+    properties:
+      database.path: "/secure/#{db.key}/flow.db"
+      table.name: events
+    auto_terminate: [success, failure]
+connections:
+  - name: read-to-log
+    source: read
+    relationship: success
+    destination: log
+  - name: log-to-store
+    source: log
+    relationship: success
+    destination: store
+"##;
+
+    fn parse_with_env(
+        yaml: &str,
+        env: &'static [(&'static str, &'static str)],
+    ) -> Result<FlowDefinition, LoadError> {
+        let parameters = ParameterContext::new().with_env(|name| {
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        });
+        parse_flow_with_parameters(yaml, &ProcessorRegistry::with_builtins(), parameters)
+    }
+
+    #[test]
+    fn test_parameters_resolve_with_environment_first() {
+        let flow = parse_with_env(
+            PARAMETERIZED,
+            &[
+                ("STREAMSYNC_PARAM_LOG_LEVEL", "debug"),
+                ("STREAMSYNC_PARAM_DB_KEY", "k3y"),
+            ],
+        )
+        .unwrap();
+        let read = flow.processor("read").unwrap();
+        assert_eq!(
+            read.context.get_property("input.directory").unwrap(),
+            "/data/in"
+        );
+        let log = flow.processor("log").unwrap();
+        assert_eq!(log.context.get_property("log.level").unwrap(), "debug");
+        let store = flow.processor("store").unwrap();
+        assert_eq!(
+            store.context.get_property("database.path").unwrap(),
+            "/secure/k3y/flow.db"
+        );
+    }
+
+    #[test]
+    fn test_unresolved_parameters_are_listed() {
+        let yaml = PARAMETERIZED.replace("#{input.dir}", "#{input.root}/#{input.sub}");
+        match parse_with_env(&yaml, &[]) {
+            Err(error) => assert_eq!(
+                error.to_string(),
+                "unresolved parameters: db.key, input.root, input.sub"
+            ),
+            Ok(_) => panic!("expected unresolved parameters"),
+        }
+    }
+
+    #[test]
+    fn test_sensitive_properties_are_redacted() {
+        let flow = parse_with_env(PARAMETERIZED, &[("STREAMSYNC_PARAM_DB_KEY", "k3y")]).unwrap();
+        let store = flow.processor("store").unwrap();
+        assert!(store.context.is_sensitive("database.path"));
+        assert!(!store.context.is_sensitive("table.name"));
+        let shown = store.context.redacted_config();
+        assert_eq!(shown["database.path"], "********");
+        assert_eq!(shown["table.name"], "events");
+    }
+
+    #[test]
+    fn test_unknown_processor_type() {
+        let yaml = "processors:\n  - name: x\n    type: Teleport\n";
+        match parse_flow(yaml, &ProcessorRegistry::with_builtins()) {
+            Err(error) => assert_eq!(error.to_string(), "x: unknown processor type 'Teleport'"),
+            Ok(_) => panic!("expected an unknown processor type error"),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Log levels and the `Logger` processors write through, so their output
+//! can be redirected or captured instead of going straight to stdout.
+
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            other => Err(format!("unknown log level '{}'", other)),
+        }
+    }
+}
+
+/// Destination for messages logged by processors and the framework around
+/// them. `component` is normally the name of the processor.
+pub trait Logger: Send + Sync {
+    fn log(&self, level: LogLevel, component: &str, message: &str);
+}
+
+/// Prints `[LEVEL] component: message`, warnings and errors to stderr and
+/// everything else to stdout. Messages below `min_level` are dropped.
+pub struct StdoutLogger {
+    min_level: LogLevel,
+}
+
+impl StdoutLogger {
+    pub fn new() -> Self {
+        Self::with_min_level(LogLevel::Trace)
+    }
+
+    pub fn with_min_level(min_level: LogLevel) -> Self {
+        Self { min_level }
+    }
+}
+
+impl Default for StdoutLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Logger for StdoutLogger {
+    fn log(&self, level: LogLevel, component: &str, message: &str) {
+        if level < self.min_level {
+            return;
+        }
+        if level >= LogLevel::Warn {
+            eprintln!("[{}] {}: {}", level, component, message);
+        } else {
+            println!("[{}] {}: {}", level, component, message);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub level: LogLevel,
+    pub component: String,
+    pub message: String,
+}
+
+/// Keeps every message in memory, e.g. for tests to assert on.
+#[derive(Default)]
+pub struct MemoryLogger {
+    records: Mutex<Vec<LogRecord>>,
+}
+
+impl MemoryLogger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything logged so far, oldest first.
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.records.lock().unwrap().clone()
+    }
+}
+
+impl Logger for MemoryLogger {
+    fn log(&self, level: LogLevel, component: &str, message: &str) {
+        self.records.lock().unwrap().push(LogRecord {
+            level,
+            component: component.to_string(),
+            message: message.to_string(),
+        });
+    }
+}
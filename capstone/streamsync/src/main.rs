@@ -1,3 +1,35 @@
-fn main() {
-    println!("Hello, world!");
+use std::io;
+use std::process::ExitCode;
+use streamsync::cli::{self, Command};
+use streamsync::registry::ProcessorRegistry;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse_args(&args) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("streamsync: {}\n\n{}", message, cli::USAGE);
+            return ExitCode::from(cli::EXIT_USAGE as u8);
+        }
+    };
+
+    let registry = ProcessorRegistry::with_builtins();
+    let (mut out, mut err) = (io::stdout(), io::stderr());
+    let code = match command {
+        Command::Run(path) => cli::run_command(&path, &registry, &mut out, &mut err).await,
+        Command::RunOnce(path) => {
+            cli::run_once_command(&path, &registry, cli::RUN_ONCE_TIMEOUT, &mut err).await
+        }
+        Command::Validate(path) => cli::validate_command(&path, &registry, &mut out, &mut err),
+        Command::Inspect { path, dot } => {
+            cli::inspect_command(&path, dot, &registry, &mut out, &mut err)
+        }
+        Command::ListProcessors => cli::list_processors(&registry, &mut out),
+        Command::Bench {
+            flowfiles,
+            content_size,
+        } => cli::bench_command(flowfiles, content_size, &mut out).await,
+    };
+    ExitCode::from(code as u8)
 }
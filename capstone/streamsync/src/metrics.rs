@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Depth samples included with each connection in a `MetricsSnapshot`.
+pub const SNAPSHOT_HISTORY_SAMPLES: usize = 12;
+
+/// Live counters updated by a processor's scheduling task.
+#[derive(Default)]
+pub struct ProcessorCounters {
+    triggers: AtomicU64,
+    failures: AtomicU64,
+    processed: AtomicU64,
+    transferred: Mutex<BTreeMap<String, u64>>,
+}
+
+impl ProcessorCounters {
+    pub fn record_trigger(&self) {
+        self.triggers.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records what a committed session routed, as counted by
+    /// `ProcessSession::transfer_counts`.
+    pub fn record_transfers(&self, counts: &BTreeMap<String, usize>) {
+        let mut transferred = self.transferred.lock().unwrap();
+        for (relationship, count) in counts {
+            *transferred.entry(relationship.clone()).or_default() += *count as u64;
+            self.processed.fetch_add(*count as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub fn triggers(&self) -> u64 {
+        self.triggers.load(Ordering::Relaxed)
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// FlowFiles transferred by committed sessions, auto-terminated ones included.
+    pub fn processed(&self) -> u64 {
+        self.processed.load(Ordering::Relaxed)
+    }
+
+    /// FlowFiles routed to each relationship by committed sessions.
+    pub fn transferred(&self) -> BTreeMap<String, u64> {
+        self.transferred.lock().unwrap().clone()
+    }
+
+    /// The counts so far, as saved in a checkpoint.
+    pub fn snapshot(&self) -> CounterSnapshot {
+        CounterSnapshot {
+            triggers: self.triggers(),
+            failures: self.failures(),
+            processed: self.processed(),
+            transferred: self.transferred(),
+        }
+    }
+
+    /// Counters carrying on from `snapshot`.
+    pub fn restored(snapshot: &CounterSnapshot) -> Self {
+        Self {
+            triggers: AtomicU64::new(snapshot.triggers),
+            failures: AtomicU64::new(snapshot.failures),
+            processed: AtomicU64::new(snapshot.processed),
+            transferred: Mutex::new(snapshot.transferred.clone()),
+        }
+    }
+}
+
+/// A processor's counters at one moment; see `ProcessorCounters`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CounterSnapshot {
+    pub triggers: u64,
+    /// Failed triggers, each retried after the controller's retry delay.
+    pub failures: u64,
+    pub processed: u64,
+    pub transferred: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessorState {
+    Running,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorMetrics {
+    pub name: String,
+    pub processor_type: String,
+    pub state: ProcessorState,
+    /// Configured properties, with sensitive values redacted.
+    pub properties: BTreeMap<String, String>,
+    pub triggers: u64,
+    pub failures: u64,
+    pub processed: u64,
+    pub transferred: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionMetrics {
+    pub name: String,
+    pub queue_depth: usize,
+    pub backpressured: bool,
+    /// The most recent depth samples, oldest first.
+    pub history: Vec<DepthSample>,
+}
+
+/// A connection's depth and queued content size at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct DepthSample {
+    pub timestamp: SystemTime,
+    pub depth: usize,
+    pub bytes: u64,
+}
+
+/// Bounded ring buffer of a connection's most recent depth samples. A
+/// capacity of 0 keeps none.
+pub struct DepthHistory {
+    capacity: usize,
+    samples: Mutex<VecDeque<DepthSample>>,
+}
+
+impl DepthHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            samples: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Records a sample, evicting the oldest once the buffer is full.
+    pub fn record(&self, sample: DepthSample) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut samples = self.samples.lock().unwrap();
+        while samples.len() >= self.capacity {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+
+    /// The last `count` samples, oldest first.
+    pub fn recent(&self, count: usize) -> Vec<DepthSample> {
+        let samples = self.samples.lock().unwrap();
+        samples
+            .iter()
+            .skip(samples.len().saturating_sub(count))
+            .copied()
+            .collect()
+    }
+
+    /// Every retained sample, oldest first.
+    pub fn samples(&self) -> Vec<DepthSample> {
+        self.samples.lock().unwrap().iter().copied().collect()
+    }
+}
+
+/// Point-in-time view of a running flow.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsSnapshot {
+    pub processors: Vec<ProcessorMetrics>,
+    pub connections: Vec<ConnectionMetrics>,
+    pub bulletin_count: usize,
+}
+
+/// A processor's place in the running flow graph.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ProcessorStatus {
+    pub name: String,
+    pub processor_type: String,
+    pub state: ProcessorState,
+    /// Connections feeding the processor, in flow order.
+    pub incoming: Vec<String>,
+    /// Connections the processor routes to, in flow order.
+    pub outgoing: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConnectionStatus {
+    pub name: String,
+    pub source: String,
+    pub relationship: String,
+    pub destination: String,
+    pub queue_depth: usize,
+    pub queued_bytes: u64,
+    /// FlowFiles dropped so far for being past the connection's expiration.
+    pub expired: u64,
+}
+
+/// The running flow's graph and queue depths at one moment; see
+/// `FlowState::status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlowStatus {
+    pub processors: Vec<ProcessorStatus>,
+    pub connections: Vec<ConnectionStatus>,
+}
@@ -0,0 +1,189 @@
+//! Parameters in property values: `#{name}` is replaced when the flow is
+//! loaded, and `##{` writes a literal `#{`. A parameter's value comes from the
+//! environment variable `STREAMSYNC_PARAM_<NAME>` if set (the name upper-cased
+//! with every other character turned into `_`, so `db.host` is read from
+//! `STREAMSYNC_PARAM_DB_HOST`), otherwise from the flow file's `parameters:`
+//! section.
+//!
+//! A property that uses a sensitive parameter is itself sensitive and is shown
+//! as `********` by the API and in validation errors.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+pub const ENV_PREFIX: &str = "STREAMSYNC_PARAM_";
+pub const REDACTED: &str = "********";
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Parameter {
+    /// May be left out when the value is always supplied by the environment.
+    pub value: Option<String>,
+    pub sensitive: bool,
+}
+
+/// A property value with its parameters filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substituted {
+    pub value: String,
+    pub sensitive: bool,
+}
+
+pub fn env_var_name(parameter: &str) -> String {
+    let suffix: String = parameter
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", ENV_PREFIX, suffix)
+}
+
+type EnvLookup = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct ParameterContext {
+    parameters: BTreeMap<String, Parameter>,
+    env: EnvLookup,
+}
+
+impl ParameterContext {
+    pub fn new() -> Self {
+        Self {
+            parameters: BTreeMap::new(),
+            env: Arc::new(|name| std::env::var(name).ok()),
+        }
+    }
+
+    /// Replaces the process environment, e.g. with a fixed map in tests.
+    pub fn with_env(
+        mut self,
+        lookup: impl Fn(&str) -> Option<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.env = Arc::new(lookup);
+        self
+    }
+
+    pub fn insert(&mut self, name: &str, parameter: Parameter) {
+        self.parameters.insert(name.to_string(), parameter);
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        (self.env)(&env_var_name(name)).or_else(|| self.parameters.get(name)?.value.clone())
+    }
+
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        self.parameters.get(name).is_some_and(|p| p.sensitive)
+    }
+
+    /// Fills in every `#{name}` in `text`, or returns the names that have no
+    /// value. An unterminated `#{` is copied as is.
+    pub fn substitute(&self, text: &str) -> Result<Substituted, BTreeSet<String>> {
+        let mut value = String::with_capacity(text.len());
+        let mut sensitive = false;
+        let mut unresolved = BTreeSet::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('#') {
+            value.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            if let Some(escaped) = tail.strip_prefix("##{") {
+                value.push_str("#{");
+                rest = escaped;
+            } else if let Some((name, after)) =
+                tail.strip_prefix("#{").and_then(|r| r.split_once('}'))
+            {
+                let name = name.trim();
+                match self.get(name) {
+                    Some(resolved) => value.push_str(&resolved),
+                    None => {
+                        unresolved.insert(name.to_string());
+                    }
+                }
+                sensitive |= self.is_sensitive(name);
+                rest = after;
+            } else {
+                value.push('#');
+                rest = &tail[1..];
+            }
+        }
+        value.push_str(rest);
+        if unresolved.is_empty() {
+            Ok(Substituted { value, sensitive })
+        } else {
+            Err(unresolved)
+        }
+    }
+}
+
+impl Default for ParameterContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn context(env: &[(&str, &str)]) -> ParameterContext {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let mut context = ParameterContext::new().with_env(move |name| env.get(name).cloned());
+        context.insert(
+            "db.host",
+            Parameter {
+                value: Some("localhost".to_string()),
+                sensitive: false,
+            },
+        );
+        context.insert(
+            "db.password",
+            Parameter {
+                value: None,
+                sensitive: true,
+            },
+        );
+        context
+    }
+
+    #[test]
+    fn test_environment_overrides_file() {
+        assert_eq!(env_var_name("db.host"), "STREAMSYNC_PARAM_DB_HOST");
+        let from_file = context(&[]).substitute("tcp://#{db.host}:5432").unwrap();
+        assert_eq!(from_file.value, "tcp://localhost:5432");
+        assert!(!from_file.sensitive);
+
+        let overridden = context(&[("STREAMSYNC_PARAM_DB_HOST", "db.internal")]);
+        assert_eq!(
+            overridden.substitute("#{ db.host }").unwrap().value,
+            "db.internal"
+        );
+        assert_eq!(
+            overridden.substitute("##{db.host} #5").unwrap().value,
+            "#{db.host} #5"
+        );
+    }
+
+    #[test]
+    fn test_unresolved_and_sensitive() {
+        let unresolved = context(&[])
+            .substitute("#{db.password}@#{db.host}/#{db.name}")
+            .unwrap_err();
+        assert_eq!(
+            unresolved.into_iter().collect::<Vec<_>>(),
+            vec!["db.name", "db.password"]
+        );
+
+        let secret = context(&[("STREAMSYNC_PARAM_DB_PASSWORD", "hunter2")])
+            .substitute("#{db.password}")
+            .unwrap();
+        assert_eq!(secret.value, "hunter2");
+        assert!(secret.sensitive);
+    }
+}
@@ -1,19 +1,164 @@
-pub trait Processor {
-    fn on_trigger(&self); 
+use crate::connection::ConnectionError;
+use crate::logging::LogLevel;
+use crate::processor_context::ProcessorContext;
+use crate::property::PropertyDescriptor;
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// What `batch_size` returns for processors that handle FlowFiles in
+/// batches and have no setting of their own for it.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Why a trigger did not complete. Whatever the variant, the controller rolls
+/// the session back, so FlowFiles taken from the queue are returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessorError {
+    /// Nothing can be done right now (e.g. a remote system is busy); try
+    /// again after the given duration. No bulletin is raised.
+    Yield(Duration),
+    /// A transient failure; it is reported and the trigger is retried after
+    /// the controller's retry delay.
+    Retryable(String),
+    /// The processor cannot continue; it is reported and stopped until it is
+    /// started again.
+    Fatal(String),
+}
+
+impl fmt::Display for ProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProcessorError::Yield(duration) => write!(f, "yielding for {:?}", duration),
+            ProcessorError::Retryable(reason) => write!(f, "{} (will retry)", reason),
+            ProcessorError::Fatal(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ProcessorError {}
+
+// Reading or writing content, spilled content in particular, fails for
+// reasons the next trigger may not run into.
+impl From<io::Error> for ProcessorError {
+    fn from(e: io::Error) -> Self {
+        ProcessorError::Retryable(e.to_string())
+    }
+}
+
+// An incoming queue that cannot be read may be readable again later, e.g.
+// once a closed connection has been replaced.
+impl From<ConnectionError> for ProcessorError {
+    fn from(e: ConnectionError) -> Self {
+        ProcessorError::Retryable(e.to_string())
+    }
+}
+
+pub trait Processor: Send + Sync {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError>;
     fn get_name(&self) -> &'static str;
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        Vec::new()
+    }
+
+    fn relationships(&self) -> Vec<Relationship>;
+
+    /// Relationships that depend on the configuration, such as one per
+    /// dynamic property of `RouteOnAttribute`. They must be connected or
+    /// auto-terminated just like the fixed ones.
+    fn dynamic_relationships(&self, _context: &ProcessorContext) -> Vec<Relationship> {
+        Vec::new()
+    }
+
+    /// Most FlowFiles one trigger takes from the incoming queues. That many
+    /// are pre-fetched before `on_trigger` runs, for `ProcessSession::get`
+    /// and `get_batch` to hand out; whatever is left over goes back to its
+    /// queue when the session ends.
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        1
+    }
+
+    /// For sources: true once everything there is to emit has been emitted,
+    /// which lets `FlowController::run_to_completion` stop triggering them.
+    /// Unbounded sources never are.
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+
+    /// Whether triggers may run in parallel, as `concurrent.tasks` asks for.
+    /// Only processors that keep nothing between triggers, or that guard
+    /// what they keep so no two triggers act on the same thing, say so;
+    /// validation rejects more than one task for the rest.
+    fn supports_concurrent_tasks(&self) -> bool {
+        false
+    }
 }
-pub struct FileProcessor {
-    processor_context: ProcessorContext,
+
+pub struct FileProcessor;
+
+impl FileProcessor {
+    pub fn new() -> Self {
+        Self
+    }
 }
-impl Processor for FileProcessor {
-    pub fn new(context: ProcessorContext) -> Self {
-        Self { context }
+
+impl Default for FileProcessor {
+    fn default() -> Self {
+        Self::new()
     }
-    fn on_trigger(&self) {
-        println!("MyProcessor is executing!");
+}
+
+impl Processor for FileProcessor {
+    fn on_trigger(
+        &self,
+        _context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        session.log(LogLevel::Info, "MyProcessor is executing!");
+        if let Some(flowfile) = session.get()? {
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
     }
 
     fn get_name(&self) -> &'static str {
         "FileProcessor"
     }
-}
\ No newline at end of file
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::MemoryLogger;
+    use crate::testing::TestRunner;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_file_processor_logs_through_the_logger() {
+        let logger = Arc::new(MemoryLogger::new());
+        let mut runner = TestRunner::new(FileProcessor::new());
+        runner.set_logger(logger.clone());
+        runner.enqueue("hello", &[]);
+        runner.run(1);
+
+        let records = logger.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, LogLevel::Info);
+        assert_eq!(records[0].component, "FileProcessor");
+        assert_eq!(records[0].message, "MyProcessor is executing!");
+    }
+}
@@ -1,6 +1,27 @@
+use crate::cron::CRON_EXPRESSION;
+use crate::flow::{CONCURRENT_TASKS, EXECUTION_TIMEOUT};
+use crate::parameter::REDACTED;
+use crate::properties_file::{self, PropertiesFileError};
+use crate::property::{PropertyDescriptor, PropertyError};
+use crate::service::ControllerServices;
+use crate::state::{MemoryStateManager, StateManager};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
 pub struct ProcessorContext {
     pub processor_name: String,
     pub config: std::collections::HashMap<String, String>,
+    /// Properties whose values must not be shown, see `redacted_config`.
+    pub sensitive: HashSet<String>,
+    pub state_manager: Arc<dyn StateManager>,
+    /// Controller services, shared by every processor of the flow.
+    pub services: Arc<ControllerServices>,
+    // Set by the controller while a source's downstream queues are full.
+    backpressured: Arc<AtomicBool>,
 }
 
 impl ProcessorContext {
@@ -8,16 +29,293 @@ impl ProcessorContext {
         Self {
             processor_name: processor_name.to_string(),
             config: std::collections::HashMap::new(),
+            sensitive: HashSet::new(),
+            state_manager: Arc::new(MemoryStateManager::new()),
+            services: Arc::new(ControllerServices::new()),
+            backpressured: Arc::new(AtomicBool::new(false)),
         }
     }
 
+    /// Loads a configuration from a properties file (see `properties_file`),
+    /// resolving `${ENV:NAME}` against the process environment and `${key}`
+    /// against the file's other properties. Properties `descriptors` marks
+    /// required may not refer to anything undefined. The context is named
+    /// after the file, without its extension.
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        descriptors: &[PropertyDescriptor],
+    ) -> Result<Self, PropertiesFileError> {
+        let path = path.as_ref();
+        let raw = properties_file::parse(&fs::read_to_string(path)?)?;
+        let is_required = |key: &str| descriptors.iter().any(|d| d.required && d.name == key);
+        let config =
+            properties_file::interpolate(&raw, &is_required, &|name| std::env::var(name).ok())?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut context = Self::new(&name);
+        context.config = config;
+        Ok(context)
+    }
+
+    /// True while a queue somewhere downstream of this processor is at its
+    /// backpressure threshold. Only maintained for sources (processors
+    /// without incoming connections), which the controller stops triggering
+    /// meanwhile; one that also takes in data in the background, such as a
+    /// listener, should stop accepting until this clears.
+    pub fn is_backpressured(&self) -> bool {
+        self.backpressured.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_backpressured(&self, backpressured: bool) {
+        self.backpressured.store(backpressured, Ordering::SeqCst);
+    }
+
     // Add a method to set configuration properties
     pub fn set_property(&mut self, key: &str, value: &str) {
         self.config.insert(key.to_string(), value.to_string());
     }
 
+    pub fn set_sensitive_property(&mut self, key: &str, value: &str) {
+        self.set_property(key, value);
+        self.sensitive.insert(key.to_string());
+    }
+
+    pub fn is_sensitive(&self, key: &str) -> bool {
+        self.sensitive.contains(key)
+    }
+
+    /// The configuration as it may be displayed, with sensitive values masked.
+    pub fn redacted_config(&self) -> BTreeMap<String, String> {
+        self.config
+            .iter()
+            .map(|(key, value)| {
+                let shown = if self.is_sensitive(key) {
+                    REDACTED
+                } else {
+                    value
+                };
+                (key.clone(), shown.to_string())
+            })
+            .collect()
+    }
+
     // Get a property from the configuration
     pub fn get_property(&self, key: &str) -> Option<&String> {
         self.config.get(key)
     }
+
+    // Get a property, falling back to the descriptor's default
+    pub fn get_property_or_default<'a>(
+        &'a self,
+        descriptor: &'a PropertyDescriptor,
+    ) -> Option<&'a str> {
+        self.get_property(&descriptor.name)
+            .map(String::as_str)
+            .or(descriptor.default_value.as_deref())
+    }
+
+    /// Properties the processor has no descriptor for, sorted by name. Some
+    /// processors (e.g. `ExtractText`) take their rules from these. The
+    /// scheduling properties read by the controller are left out.
+    pub fn dynamic_properties(&self, descriptors: &[PropertyDescriptor]) -> Vec<(&str, &str)> {
+        let mut dynamic: Vec<(&str, &str)> = self
+            .config
+            .iter()
+            .filter(|(key, _)| {
+                ![CRON_EXPRESSION, EXECUTION_TIMEOUT, CONCURRENT_TASKS].contains(&key.as_str())
+            })
+            .filter(|(key, _)| !descriptors.iter().any(|d| &d.name == *key))
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        dynamic.sort();
+        dynamic
+    }
+
+    /// Checks the configuration against a processor's descriptors, reporting
+    /// required properties with neither a value nor a default, and values
+    /// rejected by their validator.
+    pub fn validate_against(&self, descriptors: &[PropertyDescriptor]) -> Vec<PropertyError> {
+        let mut errors = Vec::new();
+        for descriptor in descriptors {
+            match self.get_property_or_default(descriptor) {
+                None if descriptor.required => errors.push(PropertyError::Missing {
+                    property: descriptor.name.clone(),
+                }),
+                None => {}
+                Some(value) => {
+                    let Some(validator) = &descriptor.validator else {
+                        continue;
+                    };
+                    let Err(reason) = validator.validate(value) else {
+                        continue;
+                    };
+                    // Validators quote the offending value, which must not
+                    // leak from a sensitive property.
+                    let error = if self.is_sensitive(&descriptor.name) {
+                        PropertyError::Invalid {
+                            property: descriptor.name.clone(),
+                            value: REDACTED.to_string(),
+                            reason: validator.requirement(),
+                        }
+                    } else {
+                        PropertyError::Invalid {
+                            property: descriptor.name.clone(),
+                            value: value.to_string(),
+                            reason,
+                        }
+                    };
+                    errors.push(error);
+                }
+            }
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::property::PropertyValidator;
+
+    fn descriptors() -> Vec<PropertyDescriptor> {
+        vec![
+            PropertyDescriptor::new("input.directory", "Directory to read")
+                .required()
+                .validator(PropertyValidator::NonEmpty),
+            PropertyDescriptor::new("batch.size", "FlowFiles per trigger")
+                .default_value("10")
+                .validator(PropertyValidator::IntRange { min: 1, max: 1000 }),
+            PropertyDescriptor::new("mode", "Read mode")
+                .default_value("text")
+                .validator(PropertyValidator::allowed_values(&["text", "binary"])),
+        ]
+    }
+
+    #[test]
+    fn test_required_property_missing() {
+        let context = ProcessorContext::new("reader");
+        assert_eq!(
+            context.validate_against(&descriptors()),
+            vec![PropertyError::Missing {
+                property: "input.directory".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_int_out_of_range() {
+        let mut context = ProcessorContext::new("reader");
+        context.set_property("input.directory", "/data");
+        context.set_property("batch.size", "5000");
+        assert_eq!(
+            context.validate_against(&descriptors()),
+            vec![PropertyError::Invalid {
+                property: "batch.size".to_string(),
+                value: "5000".to_string(),
+                reason: "5000 is outside the range 1..=1000".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_sensitive_value_is_left_out() {
+        let mut context = ProcessorContext::new("reader");
+        context.set_property("input.directory", "/data");
+        // Values short enough to turn up inside any message about them.
+        context.set_sensitive_property("batch.size", "0");
+        context.set_sensitive_property("mode", "e");
+        assert_eq!(
+            context.validate_against(&descriptors()),
+            vec![
+                PropertyError::Invalid {
+                    property: "batch.size".to_string(),
+                    value: REDACTED.to_string(),
+                    reason: "must be an integer in the range 1..=1000".to_string(),
+                },
+                PropertyError::Invalid {
+                    property: "mode".to_string(),
+                    value: REDACTED.to_string(),
+                    reason: "must be one of: text, binary".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_value_not_allowed() {
+        let mut context = ProcessorContext::new("reader");
+        context.set_property("input.directory", "/data");
+        context.set_property("mode", "hex");
+        let errors = context.validate_against(&descriptors());
+        assert_eq!(errors.len(), 1);
+        assert!(
+            matches!(&errors[0], PropertyError::Invalid { property, .. } if property == "mode")
+        );
+    }
+
+    #[test]
+    fn test_defaults_fill_in() {
+        let mut context = ProcessorContext::new("reader");
+        context.set_property("input.directory", "/data");
+        assert!(context.validate_against(&descriptors()).is_empty());
+        assert_eq!(
+            context.get_property_or_default(&descriptors()[1]),
+            Some("10")
+        );
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = std::env::temp_dir().join(format!("streamsync-context-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("reader.properties");
+        fs::write(
+            &path,
+            "base.dir = /srv\ninput.directory = ${base.dir}/in\nsearch = ${ENV:PATH}\n",
+        )
+        .unwrap();
+        let context = ProcessorContext::from_file(&path, &descriptors()).unwrap();
+        assert_eq!(context.processor_name, "reader");
+        assert_eq!(
+            context.get_property("input.directory").map(String::as_str),
+            Some("/srv/in")
+        );
+        assert_eq!(
+            context.get_property("search"),
+            std::env::var("PATH").ok().as_ref()
+        );
+        assert!(context.validate_against(&descriptors()).is_empty());
+
+        fs::write(
+            &path,
+            "input.directory = ${ENV:STREAMSYNC_UNSET_FOR_TEST}/in\n",
+        )
+        .unwrap();
+        let error = ProcessorContext::from_file(&path, &descriptors()).unwrap_err();
+        assert!(
+            matches!(error, PropertiesFileError::Undefined { property, .. } if property == "input.directory")
+        );
+
+        assert!(matches!(
+            ProcessorContext::from_file(dir.join("missing.properties"), &[]),
+            Err(PropertiesFileError::Io(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dynamic_properties() {
+        let mut context = ProcessorContext::new("extract");
+        context.set_property("input.directory", "/data");
+        context.set_property("zip", "\\d{5}");
+        context.set_property("email", "\\S+@\\S+");
+        context.set_property(CRON_EXPRESSION, "0 * * * *");
+        context.set_property(CONCURRENT_TASKS, "4");
+        assert_eq!(
+            context.dynamic_properties(&descriptors()),
+            vec![("email", "\\S+@\\S+"), ("zip", "\\d{5}")]
+        );
+    }
 }
@@ -0,0 +1,233 @@
+use crate::flowfile::ContentWriter;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::io::{self, Read};
+
+pub const COMPRESSION_FORMAT: &str = "compression.format";
+pub const COMPRESSION_LEVEL: &str = "compression.level";
+
+pub const COMPRESSION: &str = "compression";
+pub const COMPRESSION_ERROR: &str = "compression.error";
+
+pub const GZIP: &str = "gzip";
+pub const DEFLATE: &str = "deflate";
+
+fn compression_format() -> PropertyDescriptor {
+    PropertyDescriptor::new(COMPRESSION_FORMAT, "Compression format: gzip or deflate")
+        .default_value(GZIP)
+        .validator(PropertyValidator::allowed_values(&[GZIP, DEFLATE]))
+}
+
+fn compression_level() -> PropertyDescriptor {
+    PropertyDescriptor::new(COMPRESSION_LEVEL, "0 (fastest) to 9 (smallest)")
+        .default_value("6")
+        .validator(PropertyValidator::IntRange { min: 0, max: 9 })
+}
+
+/// Compresses everything read from `content`, a chunk at a time.
+pub fn compress(mut content: impl Read, format: &str, level: u32) -> io::Result<ContentWriter> {
+    let level = Compression::new(level);
+    match format {
+        DEFLATE => {
+            let mut encoder = DeflateEncoder::new(ContentWriter::new(), level);
+            io::copy(&mut content, &mut encoder)?;
+            encoder.finish()
+        }
+        _ => {
+            let mut encoder = GzEncoder::new(ContentWriter::new(), level);
+            io::copy(&mut content, &mut encoder)?;
+            encoder.finish()
+        }
+    }
+}
+
+/// Decompresses everything read from `content`, a chunk at a time.
+pub fn decompress(content: impl Read, format: &str) -> io::Result<ContentWriter> {
+    let mut decompressed = ContentWriter::new();
+    match format {
+        DEFLATE => io::copy(&mut DeflateDecoder::new(content), &mut decompressed)?,
+        _ => io::copy(&mut GzDecoder::new(content), &mut decompressed)?,
+    };
+    Ok(decompressed)
+}
+
+/// Replaces each FlowFile's content with its compressed form and records the
+/// format in the `compression` attribute.
+pub struct CompressContentProcessor;
+
+impl CompressContentProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for CompressContentProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for CompressContentProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(mut flowfile) = session.get()? else {
+            return Ok(());
+        };
+        let format = context
+            .get_property_or_default(&compression_format())
+            .unwrap_or(GZIP)
+            .to_string();
+        let level = context
+            .get_property_or_default(&compression_level())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(6);
+        match compress(flowfile.content_reader(), &format, level) {
+            Ok(compressed) => {
+                flowfile.set_content_from(compressed)?;
+                flowfile.put_attribute(COMPRESSION, &format);
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Err(e) => {
+                flowfile.put_attribute(COMPRESSION_ERROR, &e.to_string());
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "CompressContentProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![compression_format(), compression_level()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+/// Replaces each FlowFile's content with its decompressed form and sets the
+/// `compression` attribute to "none". Content that is not valid in the
+/// configured format is left untouched and routed to failure.
+pub struct DecompressContentProcessor;
+
+impl DecompressContentProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for DecompressContentProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for DecompressContentProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(mut flowfile) = session.get()? else {
+            return Ok(());
+        };
+        let format = context
+            .get_property_or_default(&compression_format())
+            .unwrap_or(GZIP)
+            .to_string();
+        match decompress(flowfile.content_reader(), &format) {
+            Ok(decompressed) => {
+                flowfile.set_content_from(decompressed)?;
+                flowfile.put_attribute(COMPRESSION, "none");
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Err(e) => {
+                flowfile.put_attribute(COMPRESSION_ERROR, &e.to_string());
+                let flowfile = session.penalize(flowfile);
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "DecompressContentProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![compression_format()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn round_trip(format: &str) {
+        let original = "the quick brown fox jumps over the lazy dog\n".repeat(100);
+        let mut compressor = TestRunner::new(CompressContentProcessor::new());
+        compressor.set_property(COMPRESSION_FORMAT, format);
+        compressor.enqueue(original.clone(), &[]);
+        compressor.run(1);
+        compressor.assert_transferred("success", 1);
+        let compressed = compressor.get_output("success").remove(0);
+        assert_eq!(compressed.get_attribute(COMPRESSION).unwrap(), format);
+        assert!(compressed.size() < original.len());
+
+        let mut decompressor = TestRunner::new(DecompressContentProcessor::new());
+        decompressor.set_property(COMPRESSION_FORMAT, format);
+        decompressor.enqueue_flowfile(compressed);
+        decompressor.run(1);
+        decompressor.assert_transferred("success", 1);
+        let restored = &decompressor.get_output("success")[0];
+        assert_eq!(restored.content().unwrap().as_ref(), original.as_bytes());
+        assert_eq!(restored.get_attribute(COMPRESSION).unwrap(), "none");
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        round_trip(GZIP);
+    }
+
+    #[test]
+    fn test_deflate_round_trip() {
+        round_trip(DEFLATE);
+    }
+
+    #[test]
+    fn test_garbage_routes_to_failure() {
+        let mut runner = TestRunner::new(DecompressContentProcessor::new());
+        runner.enqueue("definitely not gzip", &[]);
+        runner.run(1);
+
+        runner.assert_transferred("failure", 1);
+        let failed = &runner.get_output("failure")[0];
+        assert_eq!(failed.content().unwrap().as_ref(), b"definitely not gzip");
+        assert!(failed.get_attribute(COMPRESSION_ERROR).is_some());
+    }
+}
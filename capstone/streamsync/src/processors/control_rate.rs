@@ -0,0 +1,239 @@
+use crate::clock::{Clock, SystemClock};
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+pub const RATE_CONTROL_CRITERIA: &str = "rate.control.criteria";
+pub const MAXIMUM_RATE: &str = "maximum.rate";
+pub const TIME_DURATION: &str = "time.duration.ms";
+
+pub const FLOWFILE_COUNT: &str = "flowfile.count";
+pub const DATA_SIZE: &str = "data.size";
+
+fn rate_control_criteria() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        RATE_CONTROL_CRITERIA,
+        "Whether the rate counts FlowFiles or content bytes",
+    )
+    .default_value(FLOWFILE_COUNT)
+    .validator(PropertyValidator::allowed_values(&[
+        FLOWFILE_COUNT,
+        DATA_SIZE,
+    ]))
+}
+
+fn maximum_rate() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAXIMUM_RATE,
+        "FlowFiles or bytes allowed through per time duration",
+    )
+    .required()
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn time_duration() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        TIME_DURATION,
+        "Length of the window the maximum rate applies to",
+    )
+    .default_value("1000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Option<SystemTime>,
+}
+
+/// Passes FlowFiles through at no more than `maximum.rate` per
+/// `time.duration.ms`, leaving the rest queued upstream. The limit is a token
+/// bucket holding one window's worth of budget, so a burst up to that size
+/// passes at once and the budget then refills continuously. In data size
+/// mode a FlowFile may take the bucket below zero; the debt is paid back
+/// before anything else is let through.
+pub struct ControlRate {
+    bucket: Mutex<TokenBucket>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ControlRate {
+    pub fn new() -> Self {
+        Self {
+            bucket: Mutex::new(TokenBucket {
+                tokens: 0.0,
+                refilled_at: None,
+            }),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl Default for ControlRate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for ControlRate {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let by_size = context.get_property_or_default(&rate_control_criteria()) == Some(DATA_SIZE);
+        let capacity: f64 = context
+            .get_property_or_default(&maximum_rate())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(1) as f64;
+        let window_ms: u64 = context
+            .get_property_or_default(&time_duration())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000);
+        let per_ms = capacity / window_ms as f64;
+
+        let now = self.clock.now();
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.tokens = match bucket.refilled_at {
+            None => capacity,
+            Some(then) => {
+                let elapsed = now.duration_since(then).unwrap_or(Duration::ZERO);
+                (bucket.tokens + elapsed.as_secs_f64() * 1000.0 * per_ms).min(capacity)
+            }
+        };
+        bucket.refilled_at = Some(now);
+
+        // A FlowFile needs one token, or in data size mode any positive balance.
+        let needed = if by_size { f64::MIN_POSITIVE } else { 1.0 };
+        while bucket.tokens >= needed {
+            let Some(flowfile) = session.get()? else {
+                return Ok(());
+            };
+            bucket.tokens -= if by_size { flowfile.size() as f64 } else { 1.0 };
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        let wait_ms = ((needed - bucket.tokens) / per_ms).ceil().max(1.0);
+        session.yield_for(Duration::from_millis(wait_ms as u64));
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ControlRate"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![rate_control_criteria(), maximum_rate(), time_duration()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::testing::TestRunner;
+
+    // Triggers once per simulated `step` until `expected` FlowFiles are out,
+    // returning the simulated time that took.
+    fn drain(
+        runner: &mut TestRunner,
+        clock: &MockClock,
+        step: Duration,
+        expected: usize,
+    ) -> Duration {
+        let start = clock.now();
+        runner.run(1);
+        while runner.get_output("success").len() < expected {
+            clock.advance(step);
+            runner.run(1);
+        }
+        clock.now().duration_since(start).unwrap()
+    }
+
+    #[test]
+    fn test_hundred_flowfiles_at_ten_per_second() {
+        let clock = Arc::new(MockClock::default());
+        let mut runner = TestRunner::new(ControlRate::new().with_clock(clock.clone()));
+        runner.set_property(MAXIMUM_RATE, "10");
+        for i in 0..100 {
+            runner.enqueue(i.to_string(), &[]);
+        }
+
+        runner.run(1);
+        runner.assert_transferred("success", 10);
+        assert_eq!(runner.queue_size(), 90);
+
+        // The first ten were a burst within budget; the other 90 trickle out
+        // at one per 100ms.
+        let elapsed = drain(&mut runner, &clock, Duration::from_millis(100), 100);
+        assert_eq!(elapsed, Duration::from_secs(9));
+        let contents: Vec<String> = runner
+            .get_output("success")
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+            .collect();
+        let expected: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn test_data_size_rate_carries_debt() {
+        let clock = Arc::new(MockClock::default());
+        let mut runner = TestRunner::new(ControlRate::new().with_clock(clock.clone()));
+        runner.set_property(RATE_CONTROL_CRITERIA, DATA_SIZE);
+        runner.set_property(MAXIMUM_RATE, "100");
+        runner.enqueue(vec![0u8; 250], &[]);
+        runner.enqueue(vec![0u8; 10], &[]);
+
+        runner.run(1);
+        runner.assert_transferred("success", 1);
+        // 150 bytes of debt take 1.5s to pay off at 100 bytes per second.
+        clock.advance(Duration::from_millis(1500));
+        runner.run(1);
+        runner.assert_transferred("success", 1);
+        clock.advance(Duration::from_millis(10));
+        runner.run(1);
+        runner.assert_transferred("success", 2);
+    }
+
+    #[test]
+    fn test_excess_deferred_within_window() {
+        let clock = Arc::new(MockClock::default());
+        let mut runner = TestRunner::new(ControlRate::new().with_clock(clock.clone()));
+        runner.set_property(MAXIMUM_RATE, "5");
+        for i in 0..12 {
+            runner.enqueue(i.to_string(), &[]);
+        }
+
+        // Triggering again without time passing lets nothing more through.
+        runner.run(3);
+        runner.assert_transferred("success", 5);
+        assert_eq!(runner.queue_size(), 7);
+
+        clock.advance(Duration::from_millis(1000));
+        runner.run(3);
+        runner.assert_transferred("success", 10);
+        assert_eq!(runner.queue_size(), 2);
+    }
+}
@@ -0,0 +1,349 @@
+//! `ConvertCsvToJson`: turns CSV with a header row into JSON lines, one
+//! object per record keyed by the header's column names.
+
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::processors::get_http::MIME_TYPE;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use serde_json::Value;
+
+pub const CSV_DELIMITER: &str = "csv.delimiter";
+pub const CSV_QUOTE: &str = "csv.quote";
+
+pub const CSV_ERROR: &str = "csv.error";
+pub const RECORD_COUNT: &str = "record.count";
+
+fn csv_delimiter() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CSV_DELIMITER,
+        "Single character separating fields; \\t for a tab",
+    )
+    .default_value(",")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn csv_quote() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CSV_QUOTE,
+        "Single character enclosing fields that hold delimiters, quotes or newlines",
+    )
+    .default_value("\"")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+// The one character a delimiter or quote property holds.
+fn single_char(name: &str, value: &str) -> Result<char, ProcessorError> {
+    if value == "\\t" {
+        return Ok('\t');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c != '\n' && c != '\r' => Ok(c),
+        _ => Err(ProcessorError::Fatal(format!(
+            "{} must be a single character, got '{}'",
+            name, value
+        ))),
+    }
+}
+
+/// One CSV record and the line it starts on, for error messages.
+#[derive(Debug)]
+struct Record {
+    line: usize,
+    fields: Vec<String>,
+}
+
+// Splits `text` into records. A quoted field may contain the delimiter, line
+// breaks and doubled quote characters standing for one quote. Blank lines
+// are skipped.
+fn parse_csv(text: &str, delimiter: char, quote: char) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut start = 1;
+    // Whether the current field began with a quote, and whether that quote
+    // is still open.
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    chars.next();
+                    field.push(quote);
+                } else {
+                    in_quotes = false;
+                }
+            } else {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if !(fields.len() == 1 && fields[0].is_empty() && !quoted) {
+                    records.push(Record {
+                        line: start,
+                        fields: std::mem::take(&mut fields),
+                    });
+                }
+                fields.clear();
+                line += 1;
+                start = line;
+            }
+            c if c == quote && field.is_empty() && !quoted => {
+                quoted = true;
+                in_quotes = true;
+                continue;
+            }
+            c if quoted => {
+                return Err(format!(
+                    "line {}: unexpected '{}' after closing quote",
+                    line, c
+                ));
+            }
+            c => field.push(c),
+        }
+        if c == delimiter || c == '\n' {
+            quoted = false;
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted field", start));
+    }
+    if !field.is_empty() || !fields.is_empty() || quoted {
+        fields.push(field);
+        records.push(Record {
+            line: start,
+            fields,
+        });
+    }
+    Ok(records)
+}
+
+// One JSON object per record, keys in header order.
+fn convert(content: &[u8], delimiter: char, quote: char) -> Result<(String, usize), String> {
+    let text = std::str::from_utf8(content).map_err(|e| format!("content is not UTF-8: {}", e))?;
+    let mut records = parse_csv(text, delimiter, quote)?.into_iter();
+    let Some(header) = records.next() else {
+        return Err("no header row".to_string());
+    };
+    let mut output = String::new();
+    let mut count = 0;
+    for record in records {
+        if record.fields.len() != header.fields.len() {
+            return Err(format!(
+                "line {}: expected {} fields, found {}",
+                record.line,
+                header.fields.len(),
+                record.fields.len()
+            ));
+        }
+        let pairs: Vec<String> = header
+            .fields
+            .iter()
+            .zip(record.fields)
+            .map(|(key, value)| format!("{}:{}", Value::from(key.as_str()), Value::from(value)))
+            .collect();
+        output.push('{');
+        output.push_str(&pairs.join(","));
+        output.push_str("}\n");
+        count += 1;
+    }
+    Ok((output, count))
+}
+
+/// Parses each FlowFile's content as CSV whose first record names the
+/// columns, and replaces it with JSON lines: one object per remaining record,
+/// every value a string. Converted FlowFiles get `record.count` and
+/// `mime.type` and go to "success". A record with the wrong number of fields,
+/// an unterminated quote or content that is not UTF-8 sends the FlowFile,
+/// unchanged, to "failure" with the problem and its line in `csv.error`.
+pub struct ConvertCsvToJson;
+
+impl ConvertCsvToJson {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ConvertCsvToJson {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for ConvertCsvToJson {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let delimiter_property = csv_delimiter();
+        let delimiter = single_char(
+            CSV_DELIMITER,
+            context
+                .get_property_or_default(&delimiter_property)
+                .unwrap_or(","),
+        )?;
+        let quote_property = csv_quote();
+        let quote = single_char(
+            CSV_QUOTE,
+            context
+                .get_property_or_default(&quote_property)
+                .unwrap_or("\""),
+        )?;
+        if delimiter == quote {
+            return Err(ProcessorError::Fatal(format!(
+                "{} and {} must differ",
+                CSV_DELIMITER, CSV_QUOTE
+            )));
+        }
+
+        for mut flowfile in batch {
+            let converted = convert(&flowfile.content()?, delimiter, quote);
+            match converted {
+                Ok((json, count)) => {
+                    flowfile.set_content(json);
+                    flowfile.set_attribute(RECORD_COUNT, count as i64);
+                    flowfile.put_attribute(MIME_TYPE, "application/x-ndjson");
+                    session.transfer(flowfile, relationship::SUCCESS);
+                }
+                Err(message) => {
+                    flowfile.put_attribute(CSV_ERROR, &message);
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ConvertCsvToJson"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![csv_delimiter(), csv_quote()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn convert_one(content: &str, properties: &[(&str, &str)]) -> TestRunner {
+        let mut runner = TestRunner::new(ConvertCsvToJson::new());
+        for (name, value) in properties {
+            runner.set_property(name, value);
+        }
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        runner
+    }
+
+    fn output(runner: &TestRunner) -> String {
+        String::from_utf8_lossy(&runner.get_output("success")[0].content().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_simple_csv() {
+        let runner = convert_one("name,age\r\nada,36\r\ngrace,85\r\n", &[]);
+        runner.assert_transferred("success", 1);
+        assert_eq!(
+            output(&runner),
+            "{\"name\":\"ada\",\"age\":\"36\"}\n{\"name\":\"grace\",\"age\":\"85\"}\n"
+        );
+        let flowfile = &runner.get_output("success")[0];
+        assert_eq!(
+            flowfile.get_attribute(RECORD_COUNT).unwrap().to_string(),
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_quoted_field_containing_delimiter() {
+        let csv = "city;note\n\"Paris; France\";\"said \"\"bonjour\"\"\"\nOslo;\"two\nlines\"";
+        let runner = convert_one(csv, &[(CSV_DELIMITER, ";")]);
+        assert_eq!(
+            output(&runner),
+            "{\"city\":\"Paris; France\",\"note\":\"said \\\"bonjour\\\"\"}\n{\"city\":\"Oslo\",\"note\":\"two\\nlines\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_quote_and_tab_delimiter() {
+        let runner = convert_one(
+            "a\tb\n'x\ty'\t'it''s'\n",
+            &[(CSV_DELIMITER, "\\t"), (CSV_QUOTE, "'")],
+        );
+        assert_eq!(output(&runner), "{\"a\":\"x\\ty\",\"b\":\"it's\"}\n");
+    }
+
+    #[test]
+    fn test_wrong_column_count_routes_to_failure() {
+        let csv = "id,name\n1,ada\n2,grace,hopper\n";
+        let runner = convert_one(csv, &[]);
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+        let failed = &runner.get_output("failure")[0];
+        assert_eq!(failed.content().unwrap().as_ref(), csv.as_bytes());
+        assert_eq!(
+            failed.get_attribute(CSV_ERROR).unwrap().to_string(),
+            "line 3: expected 2 fields, found 3"
+        );
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        let runner = convert_one("id,name\n1,\"ada\n", &[]);
+        let failed = &runner.get_output("failure")[0];
+        assert_eq!(
+            failed.get_attribute(CSV_ERROR).unwrap().to_string(),
+            "line 2: unterminated quoted field"
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_edge_cases() {
+        let records = parse_csv("a,b\n\n\"\",x\n,\n", ',', '"').unwrap();
+        let fields: Vec<Vec<String>> = records.into_iter().map(|r| r.fields).collect();
+        assert_eq!(fields, vec![vec!["a", "b"], vec!["", "x"], vec!["", ""]]);
+        assert!(parse_csv("\"a\"b,c", ',', '"')
+            .unwrap_err()
+            .contains("after closing quote"));
+    }
+
+    #[test]
+    fn test_multi_character_delimiter_is_fatal() {
+        let runner = convert_one("a,b\n", &[(CSV_DELIMITER, "::")]);
+        assert!(matches!(runner.errors(), [ProcessorError::Fatal(m)] if m.contains(CSV_DELIMITER)));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::clock::{Clock, SystemClock};
+use crate::expression;
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const CACHE_ENTRY_IDENTIFIER: &str = "cache.entry.identifier";
+pub const CACHE_CAPACITY: &str = "cache.capacity";
+pub const AGE_OFF_DURATION: &str = "age.off.duration.ms";
+pub const PERSIST_CACHE: &str = "persist.cache";
+
+pub const NON_DUPLICATE: &str = "non-duplicate";
+pub const DUPLICATE: &str = "duplicate";
+pub const DUPLICATE_ORIGINAL_ID: &str = "duplicate.original.id";
+
+fn cache_entry_identifier() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CACHE_ENTRY_IDENTIFIER,
+        "Expression evaluated per FlowFile to get the key to deduplicate on",
+    )
+    .default_value("${hash.value}")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn cache_capacity() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CACHE_CAPACITY,
+        "Number of keys remembered; the least recently seen is evicted first",
+    )
+    .default_value("10000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 10_000_000,
+    })
+}
+
+fn age_off_duration() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        AGE_OFF_DURATION,
+        "How long a key is remembered after it was first seen; 0 keeps it until evicted",
+    )
+    .default_value("0")
+    .validator(PropertyValidator::IntRange {
+        min: 0,
+        max: i64::MAX,
+    })
+}
+
+fn persist_cache() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        PERSIST_CACHE,
+        "Store the cache through the state manager so it survives restarts",
+    )
+    .default_value("false")
+    .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+struct Entry {
+    original_id: String,
+    cached_at: SystemTime,
+    recency: u64,
+}
+
+// Keys by recency of use; `order` maps a use counter back to its key so the
+// least recently used key is the first entry.
+#[derive(Default)]
+struct LruCache {
+    entries: HashMap<String, Entry>,
+    order: BTreeMap<u64, String>,
+    next_recency: u64,
+}
+
+impl LruCache {
+    // The original FlowFile id for `key`, unless it is absent or aged off.
+    fn peek(&self, key: &str, now: SystemTime, age_off: Option<Duration>) -> Option<&str> {
+        let entry = self.entries.get(key)?;
+        let expired = age_off.is_some_and(|age_off| {
+            now.duration_since(entry.cached_at)
+                .unwrap_or(Duration::ZERO)
+                >= age_off
+        });
+        (!expired).then_some(entry.original_id.as_str())
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.recency);
+            entry.recency = self.next_recency;
+            self.order.insert(self.next_recency, key.to_string());
+            self.next_recency += 1;
+        }
+    }
+
+    fn insert(&mut self, key: &str, original_id: &str, cached_at: SystemTime, capacity: usize) {
+        if let Some(old) = self.entries.remove(key) {
+            self.order.remove(&old.recency);
+        }
+        while self.entries.len() >= capacity {
+            let Some((_, oldest)) = self.order.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(
+            key.to_string(),
+            Entry {
+                original_id: original_id.to_string(),
+                cached_at,
+                recency: self.next_recency,
+            },
+        );
+        self.order.insert(self.next_recency, key.to_string());
+        self.next_recency += 1;
+    }
+
+    // State values are "<original id> <cached at, ms since epoch> <recency>".
+    fn to_state(&self) -> HashMap<String, String> {
+        self.entries
+            .iter()
+            .map(|(key, entry)| {
+                let millis = entry
+                    .cached_at
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                (
+                    key.clone(),
+                    format!("{} {} {}", entry.original_id, millis, entry.recency),
+                )
+            })
+            .collect()
+    }
+
+    fn restore(&mut self, state: &HashMap<String, String>, capacity: usize) {
+        let mut restored: Vec<(u64, &str, &str, SystemTime)> = state
+            .iter()
+            .filter_map(|(key, value)| {
+                let mut parts = value.split(' ');
+                let original_id = parts.next()?;
+                let millis: u64 = parts.next()?.parse().ok()?;
+                let recency: u64 = parts.next()?.parse().ok()?;
+                Some((
+                    recency,
+                    key.as_str(),
+                    original_id,
+                    UNIX_EPOCH + Duration::from_millis(millis),
+                ))
+            })
+            .collect();
+        restored.sort_by_key(|(recency, ..)| *recency);
+        for (_, key, original_id, cached_at) in restored {
+            self.insert(key, original_id, cached_at, capacity);
+        }
+    }
+}
+
+enum CacheChange {
+    Insert { key: String, original_id: String },
+    Touch(String),
+}
+
+/// Routes the first FlowFile seen for a key to "non-duplicate" and later ones
+/// to "duplicate", tagged with the id of the first. Keys that cannot be
+/// evaluated, or evaluate to nothing, go to "failure".
+pub struct DetectDuplicate {
+    cache: Arc<Mutex<LruCache>>,
+    restored: AtomicBool,
+    clock: Arc<dyn Clock>,
+}
+
+impl DetectDuplicate {
+    pub fn new() -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(LruCache::default())),
+            restored: AtomicBool::new(false),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl Default for DetectDuplicate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for DetectDuplicate {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let identifier = cache_entry_identifier();
+        let identifier = context
+            .get_property_or_default(&identifier)
+            .unwrap_or_default();
+        let capacity = context
+            .get_property_or_default(&cache_capacity())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000);
+        let age_off = context
+            .get_property_or_default(&age_off_duration())
+            .and_then(|v| v.parse().ok())
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis);
+        let persist = context.get_property_or_default(&persist_cache()) == Some("true");
+
+        if persist && !self.restored.swap(true, Ordering::SeqCst) {
+            match context.state_manager.get_state(&context.processor_name) {
+                Ok(state) => self.cache.lock().unwrap().restore(&state, capacity),
+                Err(e) => {
+                    self.restored.store(false, Ordering::SeqCst);
+                    return Err(ProcessorError::Retryable(format!(
+                        "cannot restore duplicate cache: {}",
+                        e
+                    )));
+                }
+            }
+        }
+
+        // Decide against the committed cache plus this batch's own keys; the
+        // cache itself only changes once the session commits.
+        let now = self.clock.now();
+        let clock = session.clock();
+        let mut changes = Vec::new();
+        let mut admitted: HashMap<String, String> = HashMap::new();
+        {
+            let cache = self.cache.lock().unwrap();
+            for mut flowfile in batch {
+                let key = match expression::evaluate(identifier, &flowfile, clock.as_ref()) {
+                    Ok(key) if !key.is_empty() => key,
+                    _ => {
+                        session.transfer(flowfile, relationship::FAILURE);
+                        continue;
+                    }
+                };
+                let original = admitted
+                    .get(&key)
+                    .map(String::as_str)
+                    .or_else(|| cache.peek(&key, now, age_off))
+                    .map(str::to_string);
+                match original {
+                    Some(original_id) => {
+                        flowfile.put_attribute(DUPLICATE_ORIGINAL_ID, &original_id);
+                        session.transfer(flowfile, DUPLICATE);
+                        changes.push(CacheChange::Touch(key));
+                    }
+                    None => {
+                        let original_id = flowfile.id().to_string();
+                        admitted.insert(key.clone(), original_id.clone());
+                        changes.push(CacheChange::Insert { key, original_id });
+                        session.transfer(flowfile, NON_DUPLICATE);
+                    }
+                }
+            }
+        }
+
+        let cache = self.cache.clone();
+        let state_manager = context.state_manager.clone();
+        let name = context.processor_name.clone();
+        let logger = session.logger();
+        session.on_commit(move || {
+            let mut cache = cache.lock().unwrap();
+            for change in changes {
+                match change {
+                    CacheChange::Insert { key, original_id } => {
+                        cache.insert(&key, &original_id, now, capacity)
+                    }
+                    CacheChange::Touch(key) => cache.touch(&key),
+                }
+            }
+            if persist {
+                if let Err(e) = state_manager.set_state(&name, cache.to_state()) {
+                    logger.log(
+                        LogLevel::Error,
+                        &name,
+                        &format!("cannot persist duplicate cache: {}", e),
+                    );
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "DetectDuplicate"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            cache_entry_identifier(),
+            cache_capacity(),
+            age_off_duration(),
+            persist_cache(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(NON_DUPLICATE, "First FlowFile seen for its key"),
+            Relationship::new(DUPLICATE, "FlowFile whose key was already seen"),
+            Relationship::failure(),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::state::MemoryStateManager;
+    use crate::testing::TestRunner;
+
+    fn runner(processor: DetectDuplicate) -> TestRunner {
+        let mut runner = TestRunner::new(processor);
+        runner.set_property(CACHE_ENTRY_IDENTIFIER, "${order.id}");
+        runner
+    }
+
+    #[test]
+    fn test_repeats_route_to_duplicate() {
+        let mut runner = runner(DetectDuplicate::new());
+        runner.enqueue("a", &[("order.id", "1")]);
+        runner.enqueue("b", &[("order.id", "2")]);
+        runner.enqueue("a again", &[("order.id", "1")]);
+        runner.enqueue("no key", &[]);
+        runner.run(1);
+
+        runner.assert_transferred(NON_DUPLICATE, 2);
+        runner.assert_transferred(DUPLICATE, 1);
+        runner.assert_transferred("failure", 1);
+        let original = runner.get_output(NON_DUPLICATE)[0].id().to_string();
+        assert_eq!(
+            runner.get_output(DUPLICATE)[0]
+                .get_attribute(DUPLICATE_ORIGINAL_ID)
+                .unwrap(),
+            &original
+        );
+    }
+
+    #[test]
+    fn test_age_off_readmits_key() {
+        let clock = Arc::new(MockClock::default());
+        let mut runner = runner(DetectDuplicate::new().with_clock(clock.clone()));
+        runner.set_property(AGE_OFF_DURATION, "60000");
+        runner.enqueue("first", &[("order.id", "1")]);
+        runner.run(1);
+        clock.advance(Duration::from_secs(59));
+        runner.enqueue("within ttl", &[("order.id", "1")]);
+        runner.run(1);
+        runner.assert_transferred(DUPLICATE, 1);
+
+        clock.advance(Duration::from_secs(1));
+        runner.enqueue("after ttl", &[("order.id", "1")]);
+        runner.run(1);
+        runner.assert_transferred(NON_DUPLICATE, 2);
+        assert_eq!(
+            runner.get_output(NON_DUPLICATE)[1]
+                .content()
+                .unwrap()
+                .as_ref(),
+            b"after ttl"
+        );
+    }
+
+    #[test]
+    fn test_least_recently_seen_key_is_evicted() {
+        let mut runner = runner(DetectDuplicate::new());
+        runner.set_property(CACHE_CAPACITY, "2");
+        for (content, key) in [("1", "a"), ("2", "b"), ("3", "a"), ("4", "c")] {
+            runner.enqueue(content, &[("order.id", key)]);
+            runner.run(1);
+        }
+        // "a" was seen again after "b", so adding "c" evicted "b".
+        runner.enqueue("5", &[("order.id", "b")]);
+        runner.enqueue("6", &[("order.id", "a")]);
+        runner.run(1);
+
+        let duplicates: Vec<Vec<u8>> = runner
+            .get_output(DUPLICATE)
+            .iter()
+            .map(|f| f.content().unwrap().to_vec())
+            .collect();
+        assert_eq!(duplicates, vec![b"3".to_vec(), b"6".to_vec()]);
+        let unique: Vec<Vec<u8>> = runner
+            .get_output(NON_DUPLICATE)
+            .iter()
+            .map(|f| f.content().unwrap().to_vec())
+            .collect();
+        assert_eq!(
+            unique,
+            vec![b"1".to_vec(), b"2".to_vec(), b"4".to_vec(), b"5".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_persisted_cache_survives_restart() {
+        let state_manager: Arc<MemoryStateManager> = Arc::new(MemoryStateManager::new());
+        let mut first = runner(DetectDuplicate::new());
+        first.set_property(PERSIST_CACHE, "true");
+        first.set_state_manager(state_manager.clone());
+        first.enqueue("x", &[("order.id", "7")]);
+        first.run(1);
+
+        let mut restarted = runner(DetectDuplicate::new());
+        restarted.set_property(PERSIST_CACHE, "true");
+        restarted.set_state_manager(state_manager);
+        restarted.enqueue("x again", &[("order.id", "7")]);
+        restarted.run(1);
+        restarted.assert_transferred(DUPLICATE, 1);
+    }
+
+    #[test]
+    fn test_lru_order_survives_restore() {
+        let mut cache = LruCache::default();
+        for key in ["a", "b", "c"] {
+            cache.insert(key, key, UNIX_EPOCH, 3);
+        }
+        cache.touch("a");
+        let mut restored = LruCache::default();
+        restored.restore(&cache.to_state(), 3);
+        let order: Vec<&str> = restored.order.values().map(String::as_str).collect();
+        assert_eq!(order, vec!["b", "c", "a"]);
+    }
+}
@@ -0,0 +1,423 @@
+use crate::expression;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use dictclient::connection::{self, TcpDictConnection};
+use dictclient::error::DictError;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+pub const DICT_HOST: &str = "dict.host";
+pub const DICT_PORT: &str = "dict.port";
+pub const DICT_DATABASE: &str = "dict.database";
+pub const DICT_TIMEOUT: &str = "dict.timeout.ms";
+pub const LOOKUP_WORD: &str = "lookup.word";
+pub const DESTINATION: &str = "destination";
+
+pub const UNMATCHED: &str = "unmatched";
+pub const DEFINITION: &str = "definition";
+pub const DICT_LOOKUP_ERROR: &str = "dict.lookup.error";
+
+const ATTRIBUTE: &str = "attribute";
+const CONTENT: &str = "content";
+// DICT status for a DEFINE that found nothing.
+const NO_MATCH: u16 = 552;
+
+fn dict_host() -> PropertyDescriptor {
+    PropertyDescriptor::new(DICT_HOST, "Host name of the DICT server").required()
+}
+
+fn dict_port() -> PropertyDescriptor {
+    PropertyDescriptor::new(DICT_PORT, "TCP port of the DICT server")
+        .default_value("2628")
+        .validator(PropertyValidator::IntRange { min: 1, max: 65535 })
+}
+
+fn dict_database() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DICT_DATABASE,
+        "Database to search; \"*\" searches every database, \"!\" stops at the first match",
+    )
+    .default_value("*")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn dict_timeout() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DICT_TIMEOUT,
+        "Bound on connecting and on every read and write",
+    )
+    .default_value("5000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn lookup_word() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        LOOKUP_WORD,
+        "Expression evaluated per FlowFile to get the word to define",
+    )
+    .default_value("${word}")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn destination() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DESTINATION,
+        "Where the definition goes: the definition attribute or the content",
+    )
+    .default_value(ATTRIBUTE)
+    .validator(PropertyValidator::allowed_values(&[ATTRIBUTE, CONTENT]))
+}
+
+// The open session, along with the server it was opened against so a
+// changed host or port is noticed.
+struct Client {
+    host: String,
+    port: u16,
+    connection: TcpDictConnection,
+}
+
+/// Looks up a word per FlowFile on a DICT server (RFC 2229) and writes the
+/// first definition returned into the `definition` attribute or over the
+/// content. Words without a definition go to "unmatched"; FlowFiles whose
+/// lookup failed on the network are penalized and routed to failure.
+///
+/// One connection is kept open across triggers and only replaced after an
+/// error, so a busy flow does not reconnect for every FlowFile.
+pub struct DictLookup {
+    // The DICT client is async; the processor drives it on its own
+    // single-threaded runtime, which also owns the cached connection's socket.
+    runtime: Option<Runtime>,
+    client: Mutex<Option<Client>>,
+}
+
+impl DictLookup {
+    pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to build the DictLookup runtime");
+        Self {
+            runtime: Some(runtime),
+            client: Mutex::new(None),
+        }
+    }
+
+    // Runs DEFINE for `word`, reusing the cached connection when it points at
+    // `host:port`. Any error drops the connection so the next lookup starts
+    // over on a fresh one.
+    fn define(
+        &self,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+        database: &str,
+        word: &str,
+    ) -> Result<Option<String>, DictError> {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("runtime is only taken on drop");
+        let mut cached = self.client.lock().unwrap();
+        if cached
+            .as_ref()
+            .is_some_and(|client| client.host != host || client.port != port)
+        {
+            *cached = None;
+        }
+        let result = runtime.block_on(async {
+            if cached.is_none() {
+                let connection = connection::connect_with_timeout(host, port, timeout).await?;
+                *cached = Some(Client {
+                    host: host.to_string(),
+                    port,
+                    connection,
+                });
+            }
+            let client = cached.as_mut().expect("connected above");
+            client.connection.define_in(database, word).await
+        });
+        match result {
+            Ok(reply) if reply.code == NO_MATCH => Ok(None),
+            Ok(reply) if reply.is_success() => {
+                Ok(reply.first_block().map(|block| block.join("\n")))
+            }
+            Ok(reply) => Err(DictError::UnexpectedResponse(format!(
+                "{} {}",
+                reply.code, reply.message
+            ))),
+            Err(e) => {
+                *cached = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Default for DictLookup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DictLookup {
+    // Dropping a runtime blocks until its tasks finish, which panics when the
+    // processor is dropped from async code; there is nothing left to wait for.
+    fn drop(&mut self) {
+        self.client.get_mut().unwrap().take();
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl Processor for DictLookup {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let Some(host) = context
+            .get_property_or_default(&dict_host())
+            .map(str::to_string)
+        else {
+            return Err(ProcessorError::Fatal(format!("{} is not set", DICT_HOST)));
+        };
+        let port = context
+            .get_property_or_default(&dict_port())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(2628);
+        let timeout = context
+            .get_property_or_default(&dict_timeout())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(5));
+        let database = context
+            .get_property_or_default(&dict_database())
+            .unwrap_or("*")
+            .to_string();
+        let word = context
+            .get_property_or_default(&lookup_word())
+            .unwrap_or("${word}")
+            .to_string();
+        let to_content = context.get_property_or_default(&destination()) == Some(CONTENT);
+        let clock = session.clock();
+
+        for mut flowfile in batch {
+            let looked_up = match expression::evaluate(&word, &flowfile, clock.as_ref()) {
+                Ok(word) if !word.trim().is_empty() => self
+                    .define(&host, port, timeout, &database, word.trim())
+                    .map_err(|e| e.to_string()),
+                Ok(_) => Err("word to look up is empty".to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match looked_up {
+                Ok(Some(definition)) => {
+                    if to_content {
+                        flowfile.set_content(definition);
+                    } else {
+                        flowfile.put_attribute(DEFINITION, &definition);
+                    }
+                    session.transfer(flowfile, relationship::SUCCESS);
+                }
+                Ok(None) => session.transfer(flowfile, UNMATCHED),
+                Err(message) => {
+                    flowfile.put_attribute(DICT_LOOKUP_ERROR, &message);
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "DictLookup"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            dict_host(),
+            dict_port(),
+            dict_database(),
+            dict_timeout(),
+            lookup_word(),
+            destination(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::success(),
+            Relationship::new(UNMATCHED, "Words the server has no definition for"),
+            Relationship::failure(),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{SocketAddr, TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    // A DICT server knowing one word, "gold", in two databases. Counts the
+    // connections it accepts so tests can check the client is reused.
+    struct MockServer {
+        addr: SocketAddr,
+        connections: Arc<AtomicUsize>,
+    }
+
+    impl MockServer {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let connections = Arc::new(AtomicUsize::new(0));
+            let accepted = connections.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else { break };
+                    accepted.fetch_add(1, Ordering::SeqCst);
+                    thread::spawn(move || serve(stream));
+                }
+            });
+            Self { addr, connections }
+        }
+
+        fn connections(&self) -> usize {
+            self.connections.load(Ordering::SeqCst)
+        }
+    }
+
+    fn serve(stream: TcpStream) {
+        let mut writer = stream.try_clone().unwrap();
+        let _ =
+            writer.write_all(b"220 mock.example.org dictd <auth.mime> <1.1@mock.example.org>\r\n");
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { return };
+            let reply = match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["DEFINE", _, "gold"] => {
+                    "150 2 definitions retrieved\r\n\
+151 \"gold\" wn \"WordNet\"\r\ngold\r\n  n 1: a soft yellow metal\r\n.\r\n\
+151 \"gold\" gcide \"GCIDE\"\r\nGold \\Gold\\\r\n.\r\n\
+250 ok\r\n"
+                }
+                ["DEFINE", ..] => "552 no match\r\n",
+                ["QUIT"] => "221 bye\r\n",
+                _ => "500 unknown command\r\n",
+            };
+            if writer.write_all(reply.as_bytes()).is_err() {
+                return;
+            }
+        }
+    }
+
+    fn runner(server: &MockServer) -> TestRunner {
+        let mut runner = TestRunner::new(DictLookup::new());
+        runner.set_property(DICT_HOST, &server.addr.ip().to_string());
+        runner.set_property(DICT_PORT, &server.addr.port().to_string());
+        runner
+    }
+
+    #[test]
+    fn test_first_definition_into_attribute() {
+        let server = MockServer::start();
+        let mut runner = runner(&server);
+        runner.enqueue("ore", &[("word", "gold")]);
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 1);
+        let flowfile = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(
+            flowfile.get_attribute(DEFINITION).unwrap().to_string(),
+            "gold\n  n 1: a soft yellow metal"
+        );
+        assert_eq!(flowfile.content().unwrap().as_ref(), b"ore");
+    }
+
+    #[test]
+    fn test_definition_replaces_content() {
+        let server = MockServer::start();
+        let mut runner = runner(&server);
+        runner.set_property(DESTINATION, CONTENT);
+        runner.set_property(LOOKUP_WORD, "${metal}");
+        runner.enqueue("ore", &[("metal", "gold")]);
+        runner.run(1);
+        let flowfile = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(
+            flowfile.content().unwrap().as_ref(),
+            b"gold\n  n 1: a soft yellow metal"
+        );
+        assert!(flowfile.get_attribute(DEFINITION).is_none());
+    }
+
+    #[test]
+    fn test_no_definition_is_unmatched() {
+        let server = MockServer::start();
+        let mut runner = runner(&server);
+        runner.enqueue("", &[("word", "glod")]);
+        runner.run(1);
+        runner.assert_transferred(UNMATCHED, 1);
+        runner.assert_transferred(relationship::SUCCESS, 0);
+    }
+
+    #[test]
+    fn test_connection_reused_across_triggers() {
+        let server = MockServer::start();
+        let mut runner = runner(&server);
+        for word in ["gold", "glod", "gold"] {
+            runner.enqueue("", &[("word", word)]);
+            runner.run(1);
+        }
+        runner.assert_transferred(relationship::SUCCESS, 2);
+        runner.assert_transferred(UNMATCHED, 1);
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[test]
+    fn test_unreachable_server_routes_to_failure() {
+        // Bind then drop a listener so nothing is listening on the port.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut runner = TestRunner::new(DictLookup::new());
+        runner.set_property(DICT_HOST, "127.0.0.1");
+        runner.set_property(DICT_PORT, &port.to_string());
+        runner.enqueue("", &[("word", "gold")]);
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        runner.assert_penalized();
+        assert!(runner.get_output(relationship::FAILURE)[0]
+            .get_attribute(DICT_LOOKUP_ERROR)
+            .is_some());
+    }
+
+    #[test]
+    fn test_missing_word_routes_to_failure() {
+        let server = MockServer::start();
+        let mut runner = runner(&server);
+        runner.enqueue("", &[]);
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        assert_eq!(server.connections(), 0);
+    }
+}
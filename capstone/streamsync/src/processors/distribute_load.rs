@@ -0,0 +1,262 @@
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+use std::sync::Mutex;
+
+pub const NUMBER_OF_RELATIONSHIPS: &str = "number.of.relationships";
+pub const DISTRIBUTION_STRATEGY: &str = "distribution.strategy";
+
+pub const ROUND_ROBIN: &str = "round-robin";
+pub const WEIGHTED: &str = "weighted";
+
+fn number_of_relationships() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        NUMBER_OF_RELATIONSHIPS,
+        "How many relationships, named 1 to N, to spread FlowFiles over",
+    )
+    .default_value("1")
+    .validator(PropertyValidator::IntRange { min: 1, max: 1000 })
+}
+
+fn distribution_strategy() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DISTRIBUTION_STRATEGY,
+        "Take the relationships in turn, or in proportion to their weights",
+    )
+    .default_value(ROUND_ROBIN)
+    .validator(PropertyValidator::allowed_values(&[ROUND_ROBIN, WEIGHTED]))
+}
+
+// Smooth weighted round-robin: each pick raises every relationship's score
+// by its weight, takes the highest, and lowers that one by the total. Equal
+// weights reduce to plain round-robin.
+#[derive(Default)]
+struct Rotation {
+    weights: Vec<i64>,
+    scores: Vec<i64>,
+}
+
+impl Rotation {
+    fn reset(&mut self, weights: Vec<i64>) {
+        if self.weights != weights {
+            self.scores = vec![0; weights.len()];
+            self.weights = weights;
+        }
+    }
+
+    fn next(&mut self) -> usize {
+        let total: i64 = self.weights.iter().sum();
+        for (score, weight) in self.scores.iter_mut().zip(&self.weights) {
+            *score += weight;
+        }
+        // The first of the highest scores, so ties go to the lower number.
+        let (index, _) = self
+            .scores
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, score)| **score)
+            .unwrap_or((0, &0));
+        self.scores[index] -= total;
+        index
+    }
+}
+
+/// Spreads FlowFiles over relationships `1` to `number.of.relationships`,
+/// e.g. to fan out to several copies of a slow downstream processor. In
+/// `round-robin` mode they take turns; in `weighted` mode a dynamic property
+/// named after a relationship sets its weight (default 1), and each
+/// relationship gets its share of every `sum of weights` FlowFiles,
+/// interleaved rather than in runs. Weight 0 leaves a relationship out.
+pub struct DistributeLoad {
+    rotation: Mutex<Rotation>,
+}
+
+impl DistributeLoad {
+    pub fn new() -> Self {
+        Self {
+            rotation: Mutex::new(Rotation::default()),
+        }
+    }
+}
+
+impl Default for DistributeLoad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn relationship_count(context: &ProcessorContext) -> usize {
+    context
+        .get_property_or_default(&number_of_relationships())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+fn weights(context: &ProcessorContext, count: usize) -> Result<Vec<i64>, ProcessorError> {
+    let mut weights = vec![1; count];
+    if context.get_property_or_default(&distribution_strategy()) != Some(WEIGHTED) {
+        return Ok(weights);
+    }
+    for (name, value) in
+        context.dynamic_properties(&[number_of_relationships(), distribution_strategy()])
+    {
+        let index = match name.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => n - 1,
+            _ => {
+                return Err(ProcessorError::Fatal(format!(
+                    "'{}' is not a relationship between 1 and {}",
+                    name, count
+                )))
+            }
+        };
+        weights[index] = match value.trim().parse::<i64>() {
+            Ok(weight) if weight >= 0 => weight,
+            _ => {
+                return Err(ProcessorError::Fatal(format!(
+                    "weight of '{}' must be a whole number, got '{}'",
+                    name, value
+                )))
+            }
+        };
+    }
+    if weights.iter().all(|weight| *weight == 0) {
+        return Err(ProcessorError::Fatal(
+            "every relationship has weight 0".to_string(),
+        ));
+    }
+    Ok(weights)
+}
+
+impl Processor for DistributeLoad {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let weights = weights(context, relationship_count(context))?;
+        let mut rotation = self.rotation.lock().unwrap();
+        rotation.reset(weights);
+        for flowfile in batch {
+            let relationship = (rotation.next() + 1).to_string();
+            session.transfer(flowfile, &relationship);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "DistributeLoad"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![number_of_relationships(), distribution_strategy()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        Vec::new()
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+
+    fn dynamic_relationships(&self, context: &ProcessorContext) -> Vec<Relationship> {
+        (1..=relationship_count(context))
+            .map(|n| Relationship::new(&n.to_string(), "One share of the distributed FlowFiles"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn distribute(properties: &[(&str, &str)], flowfiles: usize, triggers: usize) -> TestRunner {
+        let mut runner = TestRunner::new(DistributeLoad::new());
+        for (name, value) in properties {
+            runner.set_property(name, value);
+        }
+        for i in 0..flowfiles {
+            runner.enqueue(i.to_string(), &[]);
+        }
+        runner.run(triggers);
+        runner
+    }
+
+    #[test]
+    fn test_round_robin_is_even() {
+        let runner = distribute(&[(NUMBER_OF_RELATIONSHIPS, "3")], 9, 1);
+        assert_eq!(runner.get_output_text("1"), vec!["0", "3", "6"]);
+        assert_eq!(runner.get_output_text("2"), vec!["1", "4", "7"]);
+        assert_eq!(runner.get_output_text("3"), vec!["2", "5", "8"]);
+    }
+
+    #[test]
+    fn test_round_robin_continues_across_triggers() {
+        // 250 FlowFiles take three triggers of at most 100.
+        let runner = distribute(&[(NUMBER_OF_RELATIONSHIPS, "4")], 250, 3);
+        let counts: Vec<usize> = (1..=4)
+            .map(|n| runner.get_output(&n.to_string()).len())
+            .collect();
+        assert_eq!(counts, vec![63, 63, 62, 62]);
+    }
+
+    #[test]
+    fn test_weighted_is_proportional() {
+        let runner = distribute(
+            &[
+                (NUMBER_OF_RELATIONSHIPS, "3"),
+                (DISTRIBUTION_STRATEGY, WEIGHTED),
+                ("2", "2"),
+                ("3", "3"),
+            ],
+            60,
+            1,
+        );
+        let counts: Vec<usize> = (1..=3)
+            .map(|n| runner.get_output(&n.to_string()).len())
+            .collect();
+        assert_eq!(counts, vec![10, 20, 30]);
+        // Interleaved rather than ten, then twenty, then thirty in a row.
+        assert_eq!(runner.get_output_text("3")[..3], ["0", "3", "5"]);
+    }
+
+    #[test]
+    fn test_zero_weight_is_skipped() {
+        let runner = distribute(
+            &[
+                (NUMBER_OF_RELATIONSHIPS, "2"),
+                (DISTRIBUTION_STRATEGY, WEIGHTED),
+                ("1", "0"),
+            ],
+            5,
+            1,
+        );
+        runner.assert_transferred("1", 0);
+        runner.assert_transferred("2", 5);
+    }
+
+    #[test]
+    fn test_weight_for_unknown_relationship_is_fatal() {
+        let runner = distribute(
+            &[
+                (NUMBER_OF_RELATIONSHIPS, "2"),
+                (DISTRIBUTION_STRATEGY, WEIGHTED),
+                ("5", "1"),
+            ],
+            1,
+            1,
+        );
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("'5'"))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+}
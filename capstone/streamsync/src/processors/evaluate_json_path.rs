@@ -0,0 +1,405 @@
+//! `EvaluateJsonPath`: pulls values out of JSON content with JSONPath-like
+//! expressions and stores them in attributes or as the new content.
+
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use serde_json::Value;
+use std::fmt;
+
+pub const DESTINATION: &str = "destination";
+
+pub const TO_ATTRIBUTE: &str = "flowfile-attribute";
+pub const TO_CONTENT: &str = "flowfile-content";
+
+pub const MATCHED: &str = "matched";
+pub const UNMATCHED: &str = "unmatched";
+pub const JSON_PATH_ERROR: &str = "json.path.error";
+
+fn destination() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DESTINATION,
+        "Where results go: flowfile-attribute or flowfile-content",
+    )
+    .default_value(TO_ATTRIBUTE)
+    .validator(PropertyValidator::allowed_values(&[
+        TO_ATTRIBUTE,
+        TO_CONTENT,
+    ]))
+}
+
+/// Evaluates JSONPath-like expressions against each FlowFile's content.
+/// Every property other than `destination` is an expression: with
+/// `flowfile-attribute` its name is the attribute to write, and with
+/// `flowfile-content` there must be exactly one, whose result replaces the
+/// content.
+///
+/// Expressions start at `$` and step through `.key`, `['key']`, `[index]`
+/// and `*` / `[*]` for every member. A string result is stored as is, any
+/// other value as JSON; a path with a wildcard yields a JSON array of
+/// everything it reached. If any expression reaches nothing the FlowFile
+/// goes unchanged to "unmatched"; content that is not JSON goes to failure.
+pub struct EvaluateJsonPath;
+
+impl EvaluateJsonPath {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for EvaluateJsonPath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// A parsed expression such as `$.items[0].sku` or `$['user name'].*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+impl JsonPath {
+    pub fn parse(expression: &str) -> Result<Self, JsonPathError> {
+        let error = |position: usize, message: &str| JsonPathError {
+            position,
+            message: message.to_string(),
+        };
+        let expression = expression.trim();
+        let Some(mut rest) = expression.strip_prefix('$') else {
+            return Err(error(0, "expected '$'"));
+        };
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let position = expression.len() - rest.len();
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let step = match &after[..end] {
+                    "" => return Err(error(position + 1, "expected a key after '.'")),
+                    "*" => Step::Wildcard,
+                    key => Step::Key(key.to_string()),
+                };
+                steps.push(step);
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after
+                    .find(']')
+                    .ok_or_else(|| error(position, "unclosed '['"))?;
+                let inside = after[..end].trim();
+                let step = if inside == "*" {
+                    Step::Wildcard
+                } else if let Some(key) = quoted(inside) {
+                    Step::Key(key.to_string())
+                } else {
+                    Step::Index(inside.parse().map_err(|_| {
+                        error(position + 1, "expected an index, '*' or a quoted key")
+                    })?)
+                };
+                steps.push(step);
+                rest = &after[end + 1..];
+            } else {
+                return Err(error(position, "expected '.' or '['"));
+            }
+        }
+        Ok(Self { steps })
+    }
+
+    /// True if the expression can reach more than one value.
+    pub fn is_multiple(&self) -> bool {
+        self.steps.contains(&Step::Wildcard)
+    }
+
+    /// Every value the expression reaches in `document`, in document order.
+    pub fn evaluate<'a>(&self, document: &'a Value) -> Vec<&'a Value> {
+        let mut reached = vec![document];
+        for step in &self.steps {
+            reached = reached
+                .into_iter()
+                .flat_map(|value| -> Vec<&Value> {
+                    match (step, value) {
+                        (Step::Key(key), Value::Object(fields)) => {
+                            fields.get(key).into_iter().collect()
+                        }
+                        (Step::Index(index), Value::Array(items)) => {
+                            items.get(*index).into_iter().collect()
+                        }
+                        (Step::Wildcard, Value::Object(fields)) => fields.values().collect(),
+                        (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        reached
+    }
+
+    /// The text to store for `document`, or None when nothing was reached.
+    pub fn extract(&self, document: &Value) -> Option<String> {
+        let reached = self.evaluate(document);
+        if reached.is_empty() {
+            return None;
+        }
+        if self.is_multiple() {
+            return Some(Value::Array(reached.into_iter().cloned().collect()).to_string());
+        }
+        Some(match reached[0] {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+}
+
+// The key inside 'single' or "double" quotes.
+fn quoted(text: &str) -> Option<&str> {
+    ['\'', '"']
+        .into_iter()
+        .find_map(|quote| text.strip_prefix(quote)?.strip_suffix(quote))
+}
+
+impl Processor for EvaluateJsonPath {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let to_content = context.get_property_or_default(&destination()) == Some(TO_CONTENT);
+        let mut paths = Vec::new();
+        for (name, expression) in context.dynamic_properties(&self.properties()) {
+            match JsonPath::parse(expression) {
+                Ok(path) => paths.push((name, path)),
+                Err(e) => {
+                    return Err(ProcessorError::Fatal(format!(
+                        "invalid path for '{}': {}",
+                        name, e
+                    )))
+                }
+            }
+        }
+        if to_content && paths.len() != 1 {
+            return Err(ProcessorError::Fatal(format!(
+                "{} needs exactly one expression, found {}",
+                TO_CONTENT,
+                paths.len()
+            )));
+        }
+
+        for mut flowfile in batch {
+            let parsed = serde_json::from_slice(&flowfile.content()?);
+            let document: Value = match parsed {
+                Ok(document) => document,
+                Err(e) => {
+                    flowfile.put_attribute(JSON_PATH_ERROR, &format!("content is not JSON: {}", e));
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                    continue;
+                }
+            };
+            let results: Option<Vec<String>> = paths
+                .iter()
+                .map(|(_, path)| path.extract(&document))
+                .collect();
+            let Some(results) = results else {
+                session.transfer(flowfile, UNMATCHED);
+                continue;
+            };
+            if to_content {
+                flowfile.set_content(results.into_iter().next().expect("exactly one expression"));
+            } else {
+                for ((name, _), result) in paths.iter().zip(results) {
+                    flowfile.put_attribute(name, &result);
+                }
+            }
+            session.transfer(flowfile, MATCHED);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "EvaluateJsonPath"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![destination()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(MATCHED, "FlowFiles every expression found a value in"),
+            Relationship::new(UNMATCHED, "FlowFiles some expression found nothing in"),
+            Relationship::failure(),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowfile::FlowFile;
+    use crate::testing::TestRunner;
+
+    const ORDER: &str = r#"{"id": 1042, "customer": {"name": "Ana", "address": {"city": "Lisbon"}},
+        "items": [{"sku": "A-1", "qty": 2}, {"sku": "B-7", "qty": 1}], "note": null}"#;
+
+    fn attribute(flowfile: &FlowFile, name: &str) -> Option<String> {
+        flowfile.get_attribute(name).map(ToString::to_string)
+    }
+
+    fn evaluate(content: &str, rules: &[(&str, &str)]) -> TestRunner {
+        let mut runner = TestRunner::new(EvaluateJsonPath::new());
+        for (name, expression) in rules {
+            runner.set_property(name, expression);
+        }
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        runner
+    }
+
+    #[test]
+    fn test_extracts_scalar() {
+        let runner = evaluate(ORDER, &[("order.id", "$.id"), ("note", "$.note")]);
+        runner.assert_transferred(MATCHED, 1);
+        let flowfile = runner.get_output(MATCHED).remove(0);
+        assert_eq!(attribute(&flowfile, "order.id").as_deref(), Some("1042"));
+        assert_eq!(attribute(&flowfile, "note").as_deref(), Some("null"));
+        assert_eq!(flowfile.content().unwrap().as_ref(), ORDER.as_bytes());
+    }
+
+    #[test]
+    fn test_extracts_nested_values() {
+        let runner = evaluate(
+            ORDER,
+            &[
+                ("city", "$.customer.address.city"),
+                ("first.sku", "$.items[0]['sku']"),
+                ("customer", "$[\"customer\"].address"),
+                ("skus", "$.items[*].sku"),
+            ],
+        );
+        let flowfile = runner.get_output(MATCHED).remove(0);
+        assert_eq!(attribute(&flowfile, "city").as_deref(), Some("Lisbon"));
+        assert_eq!(attribute(&flowfile, "first.sku").as_deref(), Some("A-1"));
+        assert_eq!(
+            attribute(&flowfile, "customer").as_deref(),
+            Some(r#"{"city":"Lisbon"}"#)
+        );
+        assert_eq!(
+            attribute(&flowfile, "skus").as_deref(),
+            Some(r#"["A-1","B-7"]"#)
+        );
+    }
+
+    #[test]
+    fn test_missing_path_routes_to_unmatched() {
+        let runner = evaluate(
+            ORDER,
+            &[
+                ("city", "$.customer.address.city"),
+                ("zip", "$.customer.address.zip"),
+            ],
+        );
+        runner.assert_transferred(UNMATCHED, 1);
+        let flowfile = runner.get_output(UNMATCHED).remove(0);
+        assert_eq!(attribute(&flowfile, "city"), None);
+
+        let runner = evaluate(ORDER, &[("third", "$.items[2].sku")]);
+        runner.assert_transferred(UNMATCHED, 1);
+    }
+
+    #[test]
+    fn test_result_replaces_content() {
+        let mut runner = TestRunner::new(EvaluateJsonPath::new());
+        runner.set_property(DESTINATION, TO_CONTENT);
+        runner.set_property("items", "$.items[1]");
+        runner.enqueue(ORDER, &[]);
+        runner.run(1);
+        let flowfile = runner.get_output(MATCHED).remove(0);
+        assert_eq!(
+            flowfile.content().unwrap().as_ref(),
+            br#"{"qty":1,"sku":"B-7"}"#
+        );
+        assert_eq!(attribute(&flowfile, "items"), None);
+    }
+
+    #[test]
+    fn test_invalid_json_fails() {
+        let runner = evaluate("{\"id\": ", &[("id", "$.id")]);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        runner.assert_penalized();
+        let failed = &runner.get_output(relationship::FAILURE)[0];
+        assert!(attribute(failed, JSON_PATH_ERROR)
+            .unwrap()
+            .starts_with("content is not JSON"));
+    }
+
+    #[test]
+    fn test_invalid_configuration_is_fatal() {
+        let runner = evaluate(ORDER, &[("broken", "items[0]")]);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("'broken'"))
+        );
+
+        let mut runner = TestRunner::new(EvaluateJsonPath::new());
+        runner.set_property(DESTINATION, TO_CONTENT);
+        runner.set_property("a", "$.id");
+        runner.set_property("b", "$.note");
+        runner.enqueue(ORDER, &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("exactly one"))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            JsonPath::parse("$.a[0"),
+            Err(JsonPathError {
+                position: 3,
+                message: "unclosed '['".to_string()
+            })
+        );
+        assert!(JsonPath::parse("$.").is_err());
+        assert!(JsonPath::parse("$[x]").is_err());
+        assert!(JsonPath::parse("$a").is_err());
+        assert_eq!(
+            JsonPath::parse("$").unwrap().evaluate(&Value::Null),
+            vec![&Value::Null]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use regex::Regex;
+
+pub const MAX_CAPTURE_LENGTH: &str = "max.capture.length";
+
+pub const EXTRACT_TEXT_ERROR: &str = "extract.text.error";
+
+fn max_capture_length() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAX_CAPTURE_LENGTH,
+        "Longest capture, in characters, kept in an attribute",
+    )
+    .default_value("1024")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 1_048_576,
+    })
+}
+
+/// Runs regular expressions over each FlowFile's content and stores what they
+/// capture in attributes. Every property other than `max.capture.length` is a
+/// rule: its name is the attribute to write and its value the expression.
+///
+/// The first capture group (the whole match if there is none) goes into the
+/// attribute itself, and group N > 1 into `<name>.N`. A pattern that does not
+/// match leaves its attributes unset. Content that is not UTF-8 goes to
+/// failure.
+pub struct ExtractText;
+
+impl ExtractText {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ExtractText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cuts `capture` to at most `limit` characters.
+fn truncate(capture: &str, limit: usize) -> &str {
+    match capture.char_indices().nth(limit) {
+        Some((end, _)) => &capture[..end],
+        None => capture,
+    }
+}
+
+fn extract(flowfile: &mut FlowFile, text: &str, name: &str, pattern: &Regex, limit: usize) {
+    let Some(captures) = pattern.captures(text) else {
+        return;
+    };
+    if captures.len() == 1 {
+        flowfile.put_attribute(name, truncate(&captures[0], limit));
+        return;
+    }
+    for (group, capture) in captures.iter().enumerate().skip(1) {
+        let Some(capture) = capture else {
+            continue;
+        };
+        let attribute = if group == 1 {
+            name.to_string()
+        } else {
+            format!("{}.{}", name, group)
+        };
+        flowfile.put_attribute(&attribute, truncate(capture.as_str(), limit));
+    }
+}
+
+impl Processor for ExtractText {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let limit: usize = context
+            .get_property_or_default(&max_capture_length())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1024);
+        let mut patterns = Vec::new();
+        for (name, pattern) in context.dynamic_properties(&self.properties()) {
+            match Regex::new(pattern) {
+                Ok(regex) => patterns.push((name, regex)),
+                Err(e) => {
+                    return Err(ProcessorError::Fatal(format!(
+                        "invalid pattern for '{}': {}",
+                        name, e
+                    )))
+                }
+            }
+        }
+
+        for mut flowfile in batch {
+            let text = match String::from_utf8(flowfile.content()?.into_owned()) {
+                Ok(text) => text,
+                Err(e) => {
+                    flowfile
+                        .put_attribute(EXTRACT_TEXT_ERROR, &format!("content is not UTF-8: {}", e));
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                    continue;
+                }
+            };
+            for (name, pattern) in &patterns {
+                extract(&mut flowfile, &text, name, pattern, limit);
+            }
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ExtractText"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![max_capture_length()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn attribute(flowfile: &FlowFile, name: &str) -> Option<String> {
+        flowfile.get_attribute(name).map(ToString::to_string)
+    }
+
+    fn extract_from(content: &str, rules: &[(&str, &str)]) -> FlowFile {
+        let mut runner = TestRunner::new(ExtractText::new());
+        for (name, pattern) in rules {
+            runner.set_property(name, pattern);
+        }
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 1);
+        runner.get_output(relationship::SUCCESS).remove(0)
+    }
+
+    #[test]
+    fn test_multiple_patterns() {
+        let flowfile = extract_from(
+            "order 1042 shipped to ana@example.org on 2024-05-01",
+            &[
+                ("order.id", r"order (\d+)"),
+                ("email", r"\S+@\S+"),
+                ("date", r"\d{4}-\d{2}-\d{2}"),
+            ],
+        );
+        assert_eq!(attribute(&flowfile, "order.id").as_deref(), Some("1042"));
+        assert_eq!(
+            attribute(&flowfile, "email").as_deref(),
+            Some("ana@example.org")
+        );
+        assert_eq!(attribute(&flowfile, "date").as_deref(), Some("2024-05-01"));
+    }
+
+    #[test]
+    fn test_multiple_groups() {
+        let flowfile = extract_from(
+            "GET /index.html HTTP/1.1",
+            &[("request", r"^(\w+) (\S+)(?: (\S+))?( x)?")],
+        );
+        assert_eq!(attribute(&flowfile, "request").as_deref(), Some("GET"));
+        assert_eq!(
+            attribute(&flowfile, "request.2").as_deref(),
+            Some("/index.html")
+        );
+        assert_eq!(
+            attribute(&flowfile, "request.3").as_deref(),
+            Some("HTTP/1.1")
+        );
+        assert_eq!(attribute(&flowfile, "request.4"), None);
+    }
+
+    #[test]
+    fn test_no_match_leaves_attribute_absent() {
+        let flowfile = extract_from("nothing to see", &[("id", r"id=(\d+)"), ("word", r"see")]);
+        assert_eq!(attribute(&flowfile, "id"), None);
+        assert_eq!(attribute(&flowfile, "word").as_deref(), Some("see"));
+    }
+
+    #[test]
+    fn test_long_captures_are_truncated() {
+        let content = format!("token: {}", "é".repeat(50));
+        let mut runner = TestRunner::new(ExtractText::new());
+        runner.set_property("token", r"token: (\S+)");
+        runner.set_property(MAX_CAPTURE_LENGTH, "8");
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        let flowfile = &runner.get_output(relationship::SUCCESS)[0];
+        assert_eq!(attribute(flowfile, "token"), Some("é".repeat(8)));
+    }
+
+    #[test]
+    fn test_non_utf8_content_fails() {
+        let mut runner = TestRunner::new(ExtractText::new());
+        runner.set_property("any", ".+");
+        runner.enqueue(vec![0x66, 0xff, 0xfe], &[]);
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        runner.assert_penalized();
+        let failed = &runner.get_output(relationship::FAILURE)[0];
+        assert!(attribute(failed, EXTRACT_TEXT_ERROR)
+            .unwrap()
+            .starts_with("content is not UTF-8"));
+        assert_eq!(attribute(failed, "any"), None);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_fatal() {
+        let mut runner = TestRunner::new(ExtractText::new());
+        runner.set_property("broken", "(unclosed");
+        runner.enqueue("text", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("'broken'"))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::processors::sample_flowfile::Rng;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const CONTENT_MODE: &str = "content.mode";
+pub const CUSTOM_TEXT: &str = "custom.text";
+pub const FILE_SIZE: &str = "file.size";
+pub const BATCH_SIZE: &str = "batch.size";
+
+pub const TEXT: &str = "text";
+pub const RANDOM: &str = "random";
+pub const COUNTER: &str = "counter";
+
+/// Largest `file.size` accepted: random content is built in memory.
+pub const MAX_FILE_SIZE: usize = 1 << 30;
+
+/// Attribute holding an identifier no other generated FlowFile shares.
+pub const UNIQUE_ID: &str = "unique.id";
+
+fn content_mode() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CONTENT_MODE,
+        "Fixed text, random bytes, or a counter that increases with every FlowFile",
+    )
+    .default_value(TEXT)
+    .validator(PropertyValidator::allowed_values(&[TEXT, RANDOM, COUNTER]))
+}
+
+fn custom_text() -> PropertyDescriptor {
+    PropertyDescriptor::new(CUSTOM_TEXT, "Content of every FlowFile in text mode").default_value("")
+}
+
+fn file_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(FILE_SIZE, "Content size in bytes in random mode")
+        .default_value("0")
+        .validator(PropertyValidator::IntRange {
+            min: 0,
+            max: MAX_FILE_SIZE as i64,
+        })
+}
+
+fn batch_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(BATCH_SIZE, "FlowFiles created per trigger")
+        .default_value("1")
+        .validator(PropertyValidator::IntRange {
+            min: 1,
+            max: i64::MAX,
+        })
+}
+
+struct Generator {
+    rng: Option<Rng>,
+    // The next value in counter mode; it carries across triggers.
+    counter: u64,
+}
+
+/// Creates `batch.size` FlowFiles every time it is triggered, for load
+/// testing a flow without any external input. Content is `custom.text`,
+/// `file.size` random bytes, or in counter mode the decimal count of FlowFiles
+/// generated so far, starting at 0. Every FlowFile gets a `unique.id`
+/// attribute and goes to "success".
+pub struct GenerateFlowFile {
+    generator: Mutex<Generator>,
+}
+
+impl GenerateFlowFile {
+    pub fn new() -> Self {
+        Self {
+            generator: Mutex::new(Generator {
+                rng: None,
+                counter: 0,
+            }),
+        }
+    }
+}
+
+impl Default for GenerateFlowFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for GenerateFlowFile {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let mode = context
+            .get_property_or_default(&content_mode())
+            .unwrap_or(TEXT)
+            .to_string();
+        let text_property = custom_text();
+        let text = context
+            .get_property_or_default(&text_property)
+            .unwrap_or("")
+            .to_string();
+        let size: usize = context
+            .get_property_or_default(&file_size())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(0)
+            .min(MAX_FILE_SIZE);
+        let batch: usize = context
+            .get_property_or_default(&batch_size())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(1);
+
+        let mut generator = self.generator.lock().unwrap();
+        for _ in 0..batch {
+            let content = match mode.as_str() {
+                RANDOM => {
+                    let rng = generator.rng.get_or_insert_with(|| {
+                        Rng(SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap_or_default()
+                            .as_nanos() as u64)
+                    });
+                    random_bytes(rng, size)
+                }
+                COUNTER => {
+                    generator.counter += 1;
+                    (generator.counter - 1).to_string().into_bytes()
+                }
+                _ => text.clone().into_bytes(),
+            };
+            let mut flowfile = session.create();
+            flowfile.set_content(content);
+            flowfile.put_attribute(UNIQUE_ID, &flowfile.id().to_string());
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "GenerateFlowFile"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![content_mode(), custom_text(), file_size(), batch_size()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+}
+
+fn random_bytes(rng: &mut Rng, size: usize) -> Vec<u8> {
+    let mut bytes = vec![0; size];
+    for chunk in bytes.chunks_mut(8) {
+        chunk.copy_from_slice(&rng.next_u64().to_le_bytes()[..chunk.len()]);
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_batch_of_fixed_text() {
+        let mut runner = TestRunner::new(GenerateFlowFile::new());
+        runner.set_property(CUSTOM_TEXT, "hello");
+        runner.set_property(BATCH_SIZE, "5");
+        runner.run(2);
+
+        runner.assert_transferred("success", 10);
+        assert!(runner
+            .get_output("success")
+            .iter()
+            .all(|f| f.content().unwrap().as_ref() == b"hello"));
+    }
+
+    #[test]
+    fn test_random_content_has_configured_size() {
+        let mut runner = TestRunner::new(GenerateFlowFile::new());
+        runner.set_property(CONTENT_MODE, RANDOM);
+        runner.set_property(FILE_SIZE, "1021");
+        runner.set_property(BATCH_SIZE, "3");
+        runner.run(1);
+
+        let output = runner.get_output("success");
+        assert_eq!(output.len(), 3);
+        assert!(output.iter().all(|f| f.size() == 1021));
+        assert_ne!(output[0].content().unwrap(), output[1].content().unwrap());
+    }
+
+    #[test]
+    fn test_file_size_is_capped() {
+        let mut runner = TestRunner::new(GenerateFlowFile::new());
+        runner.set_property(FILE_SIZE, &MAX_FILE_SIZE.to_string());
+        let properties = GenerateFlowFile::new().properties();
+        assert!(runner.context().validate_against(&properties).is_empty());
+
+        runner.set_property(FILE_SIZE, &(MAX_FILE_SIZE + 1).to_string());
+        assert_eq!(runner.context().validate_against(&properties).len(), 1);
+    }
+
+    #[test]
+    fn test_counter_carries_across_triggers() {
+        let mut runner = TestRunner::new(GenerateFlowFile::new());
+        runner.set_property(CONTENT_MODE, COUNTER);
+        runner.set_property(BATCH_SIZE, "2");
+        runner.run(2);
+
+        let counts: Vec<String> = runner
+            .get_output("success")
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+            .collect();
+        assert_eq!(counts, vec!["0", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_unique_id_differs_per_file() {
+        let mut runner = TestRunner::new(GenerateFlowFile::new());
+        runner.set_property(BATCH_SIZE, "50");
+        runner.run(2);
+
+        let ids: HashSet<String> = runner
+            .get_output("success")
+            .iter()
+            .map(|f| f.get_attribute(UNIQUE_ID).unwrap().to_string())
+            .collect();
+        assert_eq!(ids.len(), 100);
+    }
+}
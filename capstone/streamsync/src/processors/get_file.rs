@@ -0,0 +1,328 @@
+use crate::flowfile::ContentWriter;
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+pub const INPUT_DIRECTORY: &str = "input.directory";
+pub const KEEP_SOURCE: &str = "keep.source";
+pub const BATCH_SIZE: &str = "batch.size";
+
+fn input_directory() -> PropertyDescriptor {
+    PropertyDescriptor::new(INPUT_DIRECTORY, "Directory to pick up files from")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn keep_source() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        KEEP_SOURCE,
+        "Leave the source file in place; when false it is deleted once ingested",
+    )
+    .default_value("true")
+    .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+fn batch_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(BATCH_SIZE, "Maximum number of files ingested per trigger")
+        .default_value("10")
+        .validator(PropertyValidator::IntRange {
+            min: 1,
+            max: 10_000,
+        })
+}
+
+/// Emits each regular file in `input.directory` once. Files are remembered by
+/// name and modification time, so a file is picked up again only if it is
+/// replaced or modified. A file whose size or mtime changes while it is being
+/// read is still being written and is left for a later trigger.
+///
+/// Several tasks may run at once: each file a trigger picks up is claimed
+/// until its session commits or rolls back, and the others pass it over.
+pub struct GetFileProcessor {
+    listing: Arc<Mutex<Listing>>,
+    // Set by a trigger that found nothing new and nothing still being written.
+    exhausted: AtomicBool,
+}
+
+#[derive(Default)]
+struct Listing {
+    // File name -> modification time of the version already ingested.
+    seen: HashMap<PathBuf, SystemTime>,
+    // Files picked up by a trigger whose session is still open.
+    claimed: HashSet<PathBuf>,
+}
+
+impl GetFileProcessor {
+    pub fn new() -> Self {
+        Self {
+            listing: Arc::new(Mutex::new(Listing::default())),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Default for GetFileProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Streams `path` into new content, returning `None` if the file no longer
+/// matches `expected` (size or modification time) once the read completes.
+pub fn read_if_unchanged(path: &Path, expected: &Metadata) -> Option<ContentWriter> {
+    let mut content = ContentWriter::new();
+    io::copy(&mut File::open(path).ok()?, &mut content).ok()?;
+    let after = fs::metadata(path).ok()?;
+    let unchanged = content.len() == expected.len()
+        && after.len() == expected.len()
+        && after.modified().ok() == expected.modified().ok();
+    unchanged.then_some(content)
+}
+
+impl Processor for GetFileProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let directory = PathBuf::from(
+            context
+                .get_property_or_default(&input_directory())
+                .unwrap_or_default(),
+        );
+        let keep_source = context.get_property_or_default(&keep_source()) != Some("false");
+        let batch_size: usize = context
+            .get_property_or_default(&batch_size())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) => {
+                return Err(ProcessorError::Retryable(format!(
+                    "cannot list {}: {}",
+                    directory.display(),
+                    e
+                )));
+            }
+        };
+        let mut candidates: Vec<(PathBuf, Metadata)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| Some((entry.path(), entry.metadata().ok()?)))
+            .filter(|(_, metadata)| metadata.is_file())
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut ingested = Vec::new();
+        let mut unsettled = false;
+        {
+            let mut listing = self.listing.lock().unwrap();
+            for (path, metadata) in candidates {
+                if ingested.len() >= batch_size {
+                    break;
+                }
+                let Ok(modified) = metadata.modified() else {
+                    continue;
+                };
+                let Some(name) = path.file_name().map(PathBuf::from) else {
+                    continue;
+                };
+                if listing.claimed.contains(&name) || listing.seen.get(&name) == Some(&modified) {
+                    continue;
+                }
+                let Some(content) = read_if_unchanged(&path, &metadata) else {
+                    unsettled = true;
+                    continue;
+                };
+
+                let mut flowfile = session.create();
+                if flowfile.set_content_from(content).is_err() {
+                    unsettled = true;
+                    continue;
+                }
+                flowfile.put_attribute("filename", &name.to_string_lossy());
+                flowfile.put_attribute("path", &directory.to_string_lossy());
+                let absolute = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                flowfile.put_attribute("absolute.path", &absolute.to_string_lossy());
+                flowfile.set_attribute("file.size", metadata.len() as i64);
+                session.transfer(flowfile, relationship::SUCCESS);
+                listing.claimed.insert(name.clone());
+                ingested.push((path, name, modified));
+            }
+        }
+        self.exhausted
+            .store(ingested.is_empty() && !unsettled, Ordering::SeqCst);
+        if ingested.is_empty() {
+            return Ok(());
+        }
+
+        // Only remember (and delete) files once their FlowFiles are safely
+        // queued; after a rollback they are picked up again.
+        let claimed: Vec<PathBuf> = ingested.iter().map(|(_, name, _)| name.clone()).collect();
+        let listing = self.listing.clone();
+        session.on_rollback(move || {
+            let mut listing = listing.lock().unwrap();
+            for name in claimed {
+                listing.claimed.remove(&name);
+            }
+        });
+        let listing = self.listing.clone();
+        let name = context.processor_name.clone();
+        let logger = session.logger();
+        session.on_commit(move || {
+            let mut listing = listing.lock().unwrap();
+            for (path, file_name, modified) in ingested {
+                listing.claimed.remove(&file_name);
+                if keep_source {
+                    listing.seen.insert(file_name, modified);
+                } else if let Err(e) = fs::remove_file(&path) {
+                    logger.log(
+                        LogLevel::Warn,
+                        &name,
+                        &format!("cannot delete {}: {}", path.display(), e),
+                    );
+                    listing.seen.insert(file_name, modified);
+                } else {
+                    listing.seen.remove(&file_name);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "GetFileProcessor"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![input_directory(), keep_source(), batch_size()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.exhausted.load(Ordering::SeqCst)
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-get-file-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn runner_for(dir: &Path) -> TestRunner {
+        let mut runner = TestRunner::new(GetFileProcessor::new());
+        runner.set_property(INPUT_DIRECTORY, &dir.to_string_lossy());
+        runner
+    }
+
+    #[test]
+    fn test_second_trigger_without_new_files_yields_nothing() {
+        let dir = temp_dir("rerun");
+        fs::write(dir.join("a.txt"), "alpha").unwrap();
+        fs::write(dir.join("b.txt"), "beta").unwrap();
+        let mut runner = runner_for(&dir);
+
+        runner.run(1);
+        runner.assert_transferred("success", 2);
+        let first = &runner.get_output("success")[0];
+        assert_eq!(first.get_attribute("filename").unwrap(), "a.txt");
+        assert_eq!(first.get_attribute("file.size").unwrap(), "5");
+        assert_eq!(first.content().unwrap().as_ref(), b"alpha");
+
+        runner.run(1);
+        runner.assert_transferred("success", 2);
+
+        fs::write(dir.join("c.txt"), "gamma").unwrap();
+        let modified = fs::File::options()
+            .write(true)
+            .open(dir.join("a.txt"))
+            .unwrap();
+        modified
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        runner.run(1);
+        runner.assert_transferred("success", 4);
+        assert!(dir.join("a.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_sources_deleted_when_not_kept() {
+        let dir = temp_dir("delete");
+        fs::write(dir.join("a.txt"), "alpha").unwrap();
+        let mut runner = runner_for(&dir);
+        runner.set_property(KEEP_SOURCE, "false");
+
+        runner.run(1);
+        runner.assert_transferred("success", 1);
+        assert!(!dir.join("a.txt").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_directory_is_retryable() {
+        let dir = temp_dir("missing");
+        fs::remove_dir_all(&dir).unwrap();
+        let mut runner = runner_for(&dir);
+
+        runner.run(1);
+        runner.assert_transferred("success", 0);
+        assert!(
+            matches!(runner.errors(), [ProcessorError::Retryable(reason)] if reason.starts_with("cannot list"))
+        );
+    }
+
+    #[test]
+    fn test_file_growing_during_read_is_skipped() {
+        let dir = temp_dir("growing");
+        let path = dir.join("log.txt");
+        fs::write(&path, "first line\n").unwrap();
+        let before = fs::metadata(&path).unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"second line\n")
+            .unwrap();
+
+        assert!(read_if_unchanged(&path, &before).is_none());
+        let now = fs::metadata(&path).unwrap();
+        let content = read_if_unchanged(&path, &now).unwrap().finish().unwrap();
+        assert_eq!(
+            content.bytes().unwrap().as_ref(),
+            b"first line\nsecond line\n"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,566 @@
+//! `GetHTTP`: polls a URL and emits its body when it has changed.
+//!
+//! Only plain `http://` is supported. Requests are HTTP/1.1 with
+//! `Connection: close`, so each poll opens a fresh connection and the body
+//! ends at `Content-Length`, after the last chunk, or when the server closes.
+
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+pub const URL: &str = "url";
+pub const CONNECTION_TIMEOUT: &str = "connection.timeout.ms";
+pub const MAX_BODY_SIZE: &str = "max.body.size";
+
+pub const HTTP_STATUS_CODE: &str = "http.status.code";
+pub const HTTP_LAST_MODIFIED: &str = "http.last.modified";
+pub const MIME_TYPE: &str = "mime.type";
+pub const GET_HTTP_ERROR: &str = "get.http.error";
+
+// Keys in the processor's state.
+const ETAG: &str = "etag";
+const LAST_MODIFIED: &str = "last.modified";
+
+fn url() -> PropertyDescriptor {
+    PropertyDescriptor::new(URL, "http:// URL fetched on every trigger")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn connection_timeout() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CONNECTION_TIMEOUT,
+        "Bound on connecting and on every read and write",
+    )
+    .default_value("5000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn max_body_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAX_BODY_SIZE,
+        "Largest response body accepted, in bytes; a larger one fails the request",
+    )
+    .default_value("10485760")
+    .validator(PropertyValidator::IntRange {
+        min: 0,
+        max: i64::MAX,
+    })
+}
+
+struct Response {
+    status: u16,
+    // Header names lowercased.
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+// Splits `http://host[:port][/path]` into its parts.
+fn parse_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("only http:// URLs are supported: {}", url))?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (
+            host,
+            port.parse()
+                .map_err(|_| format!("invalid port in {}", url))?,
+        ),
+        None => (authority, 80),
+    };
+    if host.is_empty() {
+        return Err(format!("no host in {}", url));
+    }
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+fn connect(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error =
+        io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {}", host));
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed mid-response",
+        ));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn too_large(max_body: u64) -> io::Error {
+    invalid(format!("body larger than {} bytes", max_body))
+}
+
+// Appends exactly `length` more bytes to `body`, growing it only as they
+// arrive rather than trusting `length` up front.
+fn read_more(reader: &mut impl Read, length: u64, body: &mut Vec<u8>) -> io::Result<()> {
+    let read = reader.take(length).read_to_end(body)?;
+    if (read as u64) < length {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed mid-body",
+        ));
+    }
+    Ok(())
+}
+
+fn read_chunked(reader: &mut impl BufRead, max_body: u64) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = u64::from_str_radix(size, 16)
+            .map_err(|_| invalid(format!("bad chunk size '{}'", line)))?;
+        if size == 0 {
+            // Trailers, up to the blank line.
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+        match (body.len() as u64).checked_add(size) {
+            Some(total) if total <= max_body => read_more(reader, size, &mut body)?,
+            _ => return Err(too_large(max_body)),
+        }
+        read_line(reader)?;
+    }
+}
+
+fn get(
+    url: &str,
+    conditions: &[(&str, &str)],
+    timeout: Duration,
+    max_body: u64,
+) -> io::Result<Response> {
+    let (host, port, path) = parse_url(url).map_err(invalid)?;
+    let stream = connect(&host, port, timeout)?;
+    let mut request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}:{}\r\nConnection: close\r\n",
+        path, host, port
+    );
+    for (name, value) in conditions {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    (&stream).write_all(request.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let status_line = read_line(&mut reader)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| invalid(format!("bad status line '{}'", status_line)))?;
+    let mut headers = HashMap::new();
+    loop {
+        let line = read_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let body = if status == 304 || status == 204 {
+        Vec::new()
+    } else if headers
+        .get("transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+    {
+        read_chunked(&mut reader, max_body)?
+    } else if let Some(length) = headers.get("content-length") {
+        let length: u64 = length
+            .parse()
+            .map_err(|_| invalid(format!("bad Content-Length '{}'", length)))?;
+        if length > max_body {
+            return Err(too_large(max_body));
+        }
+        let mut body = Vec::new();
+        read_more(&mut reader, length, &mut body)?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader
+            .take(max_body.saturating_add(1))
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > max_body {
+            return Err(too_large(max_body));
+        }
+        body
+    };
+    Ok(Response {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// Fetches `url` on every trigger and emits the body as a FlowFile carrying
+/// `http.status.code`, `mime.type` and, when the server sends one,
+/// `http.last.modified`.
+///
+/// The ETag and Last-Modified of the last emitted response are kept through
+/// the state manager and sent back as `If-None-Match` / `If-Modified-Since`,
+/// so an unchanged resource answers 304 and nothing is emitted. Non-2xx
+/// responses are emitted to failure with their body; a request that could not
+/// be completed (refused, timed out, malformed, or with a body larger than
+/// `max.body.size`) emits an empty FlowFile to failure with the reason in
+/// `get.http.error`.
+pub struct GetHTTP;
+
+impl GetHTTP {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for GetHTTP {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for GetHTTP {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(url) = context.get_property_or_default(&url()).map(str::to_string) else {
+            return Err(ProcessorError::Fatal(format!("{} is not set", URL)));
+        };
+        let timeout = context
+            .get_property_or_default(&connection_timeout())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(5));
+        let max_body = context
+            .get_property_or_default(&max_body_size())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(10 * 1024 * 1024);
+        let state = context
+            .state_manager
+            .get_state(&context.processor_name)
+            .map_err(|e| ProcessorError::Retryable(format!("cannot read state: {}", e)))?;
+        let mut conditions = Vec::new();
+        if let Some(etag) = state.get(ETAG) {
+            conditions.push(("If-None-Match", etag.as_str()));
+        }
+        if let Some(last_modified) = state.get(LAST_MODIFIED) {
+            conditions.push(("If-Modified-Since", last_modified.as_str()));
+        }
+
+        let response = match get(&url, &conditions, timeout, max_body) {
+            Ok(response) => response,
+            Err(e) => {
+                let mut flowfile = session.create();
+                flowfile.put_attribute(GET_HTTP_ERROR, &format!("GET {}: {}", url, e));
+                let flowfile = session.penalize(flowfile);
+                session.transfer(flowfile, relationship::FAILURE);
+                return Ok(());
+            }
+        };
+        if response.status == 304 {
+            return Ok(());
+        }
+
+        let mut flowfile = session.create();
+        flowfile.set_content(response.body);
+        flowfile.set_attribute(HTTP_STATUS_CODE, response.status as i64);
+        let mime_type = response.headers.get("content-type").map(String::as_str);
+        flowfile.put_attribute(MIME_TYPE, mime_type.unwrap_or("application/octet-stream"));
+        if let Some(last_modified) = response.headers.get("last-modified") {
+            flowfile.put_attribute(HTTP_LAST_MODIFIED, last_modified);
+        }
+        if !(200..300).contains(&response.status) {
+            session.transfer(flowfile, relationship::FAILURE);
+            return Ok(());
+        }
+        session.transfer(flowfile, relationship::SUCCESS);
+
+        // Only validators of content that made it into the flow are kept, so
+        // a rolled back session fetches the resource again next time.
+        let mut validators = HashMap::new();
+        for (header, key) in [("etag", ETAG), ("last-modified", LAST_MODIFIED)] {
+            if let Some(value) = response.headers.get(header) {
+                validators.insert(key.to_string(), value.clone());
+            }
+        }
+        let state_manager = context.state_manager.clone();
+        let name = context.processor_name.clone();
+        let logger = session.logger();
+        session.on_commit(move || {
+            if let Err(e) = state_manager.set_state(&name, validators) {
+                logger.log(
+                    LogLevel::Error,
+                    &name,
+                    &format!("cannot store ETag and Last-Modified: {}", e),
+                );
+            }
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "GetHTTP"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![url(), connection_timeout(), max_body_size()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    const LAST_MODIFIED_AT: &str = "Tue, 01 Oct 2024 10:00:00 GMT";
+
+    // Serves one resource whose version the test can bump. A request
+    // carrying the current ETag gets 304; anything else gets the body.
+    // Every request's headers are recorded.
+    struct MockServer {
+        addr: SocketAddr,
+        version: Arc<Mutex<u32>>,
+        requests: Arc<Mutex<Vec<Vec<String>>>>,
+    }
+
+    impl MockServer {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let addr = listener.local_addr().unwrap();
+            let version = Arc::new(Mutex::new(1));
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let (current, seen) = (version.clone(), requests.clone());
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(stream) = stream else { break };
+                    let mut reader = BufReader::new(&stream);
+                    let mut headers = Vec::new();
+                    while let Ok(line) = read_line(&mut reader) {
+                        if line.is_empty() {
+                            break;
+                        }
+                        headers.push(line);
+                    }
+                    let version = *current.lock().unwrap();
+                    let etag = format!("\"v{}\"", version);
+                    let response = if headers
+                        .iter()
+                        .any(|h| h == &format!("If-None-Match: {}", etag))
+                    {
+                        format!("HTTP/1.1 304 Not Modified\r\nETag: {}\r\n\r\n", etag)
+                    } else if headers[0].starts_with("GET /missing ") {
+                        "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: 9\r\n\r\nnot found"
+                            .to_string()
+                    } else {
+                        let body = format!("version {}", version);
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nETag: {}\r\nLast-Modified: {}\r\n\
+                             Content-Length: {}\r\n\r\n{}",
+                            etag,
+                            LAST_MODIFIED_AT,
+                            body.len(),
+                            body
+                        )
+                    };
+                    seen.lock().unwrap().push(headers);
+                    let _ = (&stream).write_all(response.as_bytes());
+                }
+            });
+            Self {
+                addr,
+                version,
+                requests,
+            }
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("http://{}{}", self.addr, path)
+        }
+    }
+
+    fn bodies(runner: &TestRunner) -> Vec<String> {
+        runner
+            .get_output(relationship::SUCCESS)
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_unchanged_resource_is_not_emitted_again() {
+        let server = MockServer::start();
+        let mut runner = TestRunner::new(GetHTTP::new());
+        runner.set_property(URL, &server.url("/feed"));
+
+        runner.run(1);
+        let flowfile = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(
+            flowfile
+                .get_attribute(HTTP_STATUS_CODE)
+                .unwrap()
+                .to_string(),
+            "200"
+        );
+        assert_eq!(
+            flowfile.get_attribute(MIME_TYPE).unwrap().to_string(),
+            "text/plain"
+        );
+        assert_eq!(
+            flowfile
+                .get_attribute(HTTP_LAST_MODIFIED)
+                .unwrap()
+                .to_string(),
+            LAST_MODIFIED_AT
+        );
+
+        // 304 twice, then a new version, then 304 again.
+        runner.run(2);
+        *server.version.lock().unwrap() = 2;
+        runner.run(2);
+        assert_eq!(bodies(&runner), vec!["version 1", "version 2"]);
+        runner.assert_transferred(relationship::FAILURE, 0);
+
+        let requests = server.requests.lock().unwrap();
+        assert_eq!(requests.len(), 5);
+        assert!(!requests[0].iter().any(|h| h.starts_with("If-None-Match")));
+        assert!(requests[1].contains(&"If-None-Match: \"v1\"".to_string()));
+        assert!(requests[1].contains(&format!("If-Modified-Since: {}", LAST_MODIFIED_AT)));
+        assert!(requests[4].contains(&"If-None-Match: \"v2\"".to_string()));
+    }
+
+    #[test]
+    fn test_non_2xx_goes_to_failure() {
+        let server = MockServer::start();
+        let mut runner = TestRunner::new(GetHTTP::new());
+        runner.set_property(URL, &server.url("/missing"));
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        let flowfile = runner.get_output(relationship::FAILURE).remove(0);
+        assert_eq!(
+            flowfile
+                .get_attribute(HTTP_STATUS_CODE)
+                .unwrap()
+                .to_string(),
+            "404"
+        );
+        assert_eq!(flowfile.content().unwrap().as_ref(), b"not found");
+    }
+
+    #[test]
+    fn test_timeout_goes_to_failure() {
+        // Accepts the connection but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut runner = TestRunner::new(GetHTTP::new());
+        runner.set_property(
+            URL,
+            &format!("http://{}/slow", listener.local_addr().unwrap()),
+        );
+        runner.set_property(CONNECTION_TIMEOUT, "100");
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        runner.assert_penalized();
+        let flowfile = runner.get_output(relationship::FAILURE).remove(0);
+        assert!(flowfile.get_attribute(GET_HTTP_ERROR).is_some());
+        drop(listener);
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let chunked = &b"5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n"[..];
+        let body = read_chunked(&mut BufReader::new(chunked), 12).unwrap();
+        assert_eq!(body, b"hello, world");
+        let error = read_chunked(&mut BufReader::new(chunked), 11).unwrap_err();
+        assert_eq!(error.to_string(), "body larger than 11 bytes");
+        // A chunk size near u64::MAX is refused before anything is allocated.
+        let huge = &b"ffffffffffffffff\r\nhello\r\n0\r\n\r\n"[..];
+        assert!(read_chunked(&mut BufReader::new(huge), u64::MAX).is_err());
+        let truncated = &b"ff\r\nhello"[..];
+        assert_eq!(
+            read_chunked(&mut BufReader::new(truncated), 1024)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_body_over_the_limit_goes_to_failure() {
+        // Announces far more than it sends, and more than allowed.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let mut reader = BufReader::new(&stream);
+                while !read_line(&mut reader).unwrap_or_default().is_empty() {}
+                let _ = (&stream)
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 99999999999999\r\n\r\nhi");
+            }
+        });
+        let mut runner = TestRunner::new(GetHTTP::new());
+        runner.set_property(URL, &format!("http://{}/big", addr));
+        runner.set_property(MAX_BODY_SIZE, "1024");
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        let flowfile = runner.get_output(relationship::FAILURE).remove(0);
+        assert!(flowfile
+            .get_attribute(GET_HTTP_ERROR)
+            .unwrap()
+            .to_string()
+            .ends_with("body larger than 1024 bytes"));
+    }
+
+    #[test]
+    fn test_parse_url() {
+        assert_eq!(
+            parse_url("http://example.org").unwrap(),
+            ("example.org".to_string(), 80, "/".to_string())
+        );
+        assert_eq!(
+            parse_url("http://127.0.0.1:8080/a/b?c=d").unwrap(),
+            ("127.0.0.1".to_string(), 8080, "/a/b?c=d".to_string())
+        );
+        assert!(parse_url("https://example.org").is_err());
+    }
+}
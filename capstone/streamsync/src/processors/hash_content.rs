@@ -0,0 +1,240 @@
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use ring::digest;
+use std::io::{self, Read};
+
+pub const HASH_ALGORITHM: &str = "hash.algorithm";
+pub const HASH_ATTRIBUTE: &str = "hash.attribute";
+
+pub const MD5: &str = "MD5";
+pub const SHA_1: &str = "SHA-1";
+pub const SHA_256: &str = "SHA-256";
+
+fn hash_algorithm() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        HASH_ALGORITHM,
+        "Digest computed over the content: MD5, SHA-1 or SHA-256",
+    )
+    .default_value(SHA_256)
+    .validator(PropertyValidator::allowed_values(&[MD5, SHA_1, SHA_256]))
+}
+
+fn hash_attribute() -> PropertyDescriptor {
+    PropertyDescriptor::new(HASH_ATTRIBUTE, "Attribute the hex digest is written to")
+        .default_value("hash.value")
+        .validator(PropertyValidator::NonEmpty)
+}
+
+// A digest being computed a chunk at a time.
+enum Hasher {
+    Md5(md5::Context),
+    Ring(digest::Context),
+}
+
+impl Hasher {
+    fn new(algorithm: &str) -> Option<Self> {
+        match algorithm {
+            MD5 => Some(Hasher::Md5(md5::Context::new())),
+            SHA_1 => Some(Hasher::Ring(digest::Context::new(
+                &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            ))),
+            SHA_256 => Some(Hasher::Ring(digest::Context::new(&digest::SHA256))),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        match self {
+            Hasher::Md5(context) => context.consume(chunk),
+            Hasher::Ring(context) => context.update(chunk),
+        }
+    }
+
+    fn finish(self) -> String {
+        let bytes = match self {
+            Hasher::Md5(context) => context.compute().0.to_vec(),
+            Hasher::Ring(context) => context.finish().as_ref().to_vec(),
+        };
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+/// Lowercase hex digest of `content` under `algorithm`, one of `MD5`,
+/// `SHA-1` and `SHA-256`.
+pub fn hash(content: &[u8], algorithm: &str) -> Option<String> {
+    let mut hasher = Hasher::new(algorithm)?;
+    hasher.update(content);
+    Some(hasher.finish())
+}
+
+/// Like `hash`, but reads the content from `reader` a chunk at a time, so
+/// content of any size is hashed in constant memory.
+pub fn hash_reader(mut reader: impl Read, algorithm: &str) -> io::Result<Option<String>> {
+    let Some(mut hasher) = Hasher::new(algorithm) else {
+        return Ok(None);
+    };
+    let mut chunk = [0; 8192];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => return Ok(Some(hasher.finish())),
+            Ok(n) => hasher.update(&chunk[..n]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Writes a digest of each FlowFile's content into an attribute, by default
+/// `hash.value`, which is what `DetectDuplicate` keys on unless told
+/// otherwise. MD5 and SHA-1 are there for matching digests made elsewhere;
+/// they are no protection against deliberate collisions.
+pub struct HashContentProcessor;
+
+impl HashContentProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for HashContentProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for HashContentProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let algorithm = context
+            .get_property_or_default(&hash_algorithm())
+            .unwrap_or(SHA_256)
+            .to_string();
+        let attribute = context
+            .get_property_or_default(&hash_attribute())
+            .unwrap_or("hash.value")
+            .to_string();
+        if Hasher::new(&algorithm).is_none() {
+            return Err(ProcessorError::Fatal(format!(
+                "unknown {}: {}",
+                HASH_ALGORITHM, algorithm
+            )));
+        }
+
+        for mut flowfile in batch {
+            let digest = hash_reader(flowfile.content_reader(), &algorithm)?
+                .expect("algorithm checked above");
+            flowfile.put_attribute(&attribute, &digest);
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "HashContentProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![hash_algorithm(), hash_attribute()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowfile::{ContentWriter, FlowFile};
+    use crate::testing::TestRunner;
+    use std::io::Write;
+
+    fn hashed(algorithm: Option<&str>, content: &str) -> String {
+        let mut runner = TestRunner::new(HashContentProcessor::new());
+        if let Some(algorithm) = algorithm {
+            runner.set_property(HASH_ALGORITHM, algorithm);
+        }
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 1);
+        let flowfile = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(flowfile.content().unwrap().as_ref(), content.as_bytes());
+        flowfile.get_attribute("hash.value").unwrap().to_string()
+    }
+
+    #[test]
+    fn test_known_digests() {
+        assert_eq!(hashed(Some(MD5), "abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(
+            hashed(Some(SHA_1), "abc"),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hashed(Some(SHA_256), "abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // SHA-256 is the default.
+        assert_eq!(
+            hashed(None, ""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_configured_attribute() {
+        let mut runner = TestRunner::new(HashContentProcessor::new());
+        runner.set_property(HASH_ALGORITHM, MD5);
+        runner.set_property(HASH_ATTRIBUTE, "content.md5");
+        runner.enqueue("", &[]);
+        runner.run(1);
+        let flowfile = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(
+            flowfile.get_attribute("content.md5").unwrap().to_string(),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+        assert!(flowfile.get_attribute("hash.value").is_none());
+    }
+
+    #[test]
+    fn test_spilled_content_is_hashed_in_chunks() {
+        let payload: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+        let mut writer = ContentWriter::with_threshold(1024);
+        writer.write_all(&payload).unwrap();
+        let mut flowfile = FlowFile::new();
+        flowfile.set_content_from(writer).unwrap();
+
+        let mut runner = TestRunner::new(HashContentProcessor::new());
+        runner.enqueue_flowfile(flowfile);
+        runner.run(1);
+        let hashed = runner.get_output(relationship::SUCCESS).remove(0);
+        assert_eq!(
+            hashed.get_attribute("hash.value").unwrap().to_string(),
+            hash(&payload, SHA_256).unwrap()
+        );
+        for algorithm in [MD5, SHA_1, SHA_256] {
+            assert_eq!(
+                hash_reader(payload.as_slice(), algorithm).unwrap(),
+                hash(&payload, algorithm)
+            );
+        }
+        assert_eq!(hash_reader(payload.as_slice(), "CRC32").unwrap(), None);
+    }
+}
@@ -0,0 +1,554 @@
+//! `PublishKafka` and `ConsumeKafka`.
+//!
+//! Both talk to Kafka through the [`KafkaProducer`] and [`KafkaConsumer`]
+//! traits so they can be exercised without a broker. The rdkafka-backed
+//! clients, and the `new()` constructors that use them, are only built with
+//! the `kafka` feature, which keeps librdkafka out of the default build.
+
+use crate::flowfile::FlowFile;
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[cfg(feature = "kafka")]
+pub mod client;
+
+pub const KAFKA_BROKERS: &str = "kafka.brokers";
+pub const TOPIC: &str = "topic";
+pub const GROUP_ID: &str = "group.id";
+pub const MAX_POLL_RECORDS: &str = "max.poll.records";
+pub const DELIVERY_TIMEOUT: &str = "delivery.timeout.ms";
+
+pub const KAFKA_TOPIC: &str = "kafka.topic";
+pub const KAFKA_PARTITION: &str = "kafka.partition";
+pub const KAFKA_OFFSET: &str = "kafka.offset";
+pub const KAFKA_KEY: &str = "kafka.key";
+pub const KAFKA_ERROR: &str = "kafka.error";
+
+// How long an idle ConsumeKafka trigger waits for messages.
+const POLL_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaError(pub String);
+
+impl fmt::Display for KafkaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for KafkaError {}
+
+pub trait KafkaProducer: Send + Sync {
+    /// Sends one record and blocks until its delivery report arrives.
+    fn send(
+        &self,
+        topic: &str,
+        key: Option<&str>,
+        payload: &[u8],
+        headers: &[(String, String)],
+    ) -> Result<(), KafkaError>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaMessage {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Option<Vec<u8>>,
+    pub payload: Vec<u8>,
+    pub headers: Vec<(String, String)>,
+}
+
+pub trait KafkaConsumer: Send + Sync {
+    /// Returns up to `max` messages, waiting at most `timeout` for the first.
+    fn poll(&self, max: usize, timeout: Duration) -> Result<Vec<KafkaMessage>, KafkaError>;
+
+    /// Commits `(topic, partition, offset)` of the last message processed in
+    /// each partition.
+    fn commit(&self, processed: &[(String, i32, i64)]) -> Result<(), KafkaError>;
+
+    /// Moves back to the committed offsets, so messages from a rolled back
+    /// session are delivered again.
+    fn rewind(&self) -> Result<(), KafkaError>;
+}
+
+type ClientFactory<T> = Box<dyn Fn(&ProcessorContext) -> Result<Arc<T>, KafkaError> + Send + Sync>;
+
+// Builds the client from the processor properties on first use and keeps it.
+struct LazyClient<T: ?Sized> {
+    factory: ClientFactory<T>,
+    client: Mutex<Option<Arc<T>>>,
+}
+
+impl<T: ?Sized> LazyClient<T> {
+    fn new(factory: ClientFactory<T>) -> Self {
+        Self {
+            factory,
+            client: Mutex::new(None),
+        }
+    }
+
+    fn get(&self, context: &ProcessorContext) -> Result<Arc<T>, KafkaError> {
+        let mut client = self.client.lock().unwrap();
+        if let Some(client) = client.as_ref() {
+            return Ok(client.clone());
+        }
+        let created = (self.factory)(context)?;
+        *client = Some(created.clone());
+        Ok(created)
+    }
+}
+
+fn brokers() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        KAFKA_BROKERS,
+        "Comma-separated host:port list of Kafka bootstrap servers",
+    )
+    .required()
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn topic() -> PropertyDescriptor {
+    PropertyDescriptor::new(TOPIC, "Kafka topic to publish to or consume from")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn group_id() -> PropertyDescriptor {
+    PropertyDescriptor::new(GROUP_ID, "Consumer group whose offsets are committed")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn max_poll_records() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAX_POLL_RECORDS,
+        "Maximum number of messages turned into FlowFiles per trigger",
+    )
+    .default_value("100")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 100_000,
+    })
+}
+
+fn delivery_timeout() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DELIVERY_TIMEOUT,
+        "How long to wait for a delivery report before failing",
+    )
+    .default_value("30000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 3_600_000,
+    })
+}
+
+/// Publishes each FlowFile's content as one record, with its attributes as
+/// record headers and `kafka.key` as the key, routing on the delivery report.
+pub struct PublishKafka {
+    producer: LazyClient<dyn KafkaProducer>,
+}
+
+impl PublishKafka {
+    #[cfg(feature = "kafka")]
+    pub fn new() -> Self {
+        Self {
+            producer: LazyClient::new(Box::new(|context: &ProcessorContext| {
+                let producer = client::RdKafkaProducer::from_context(context)?;
+                Ok(Arc::new(producer) as Arc<dyn KafkaProducer>)
+            })),
+        }
+    }
+
+    pub fn with_producer(producer: Arc<dyn KafkaProducer>) -> Self {
+        Self {
+            producer: LazyClient::new(Box::new(move |_: &ProcessorContext| Ok(producer.clone()))),
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl Default for PublishKafka {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for PublishKafka {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(mut flowfile) = session.get()? else {
+            return Ok(());
+        };
+        let content = flowfile.content()?;
+        let result = self.producer.get(context).and_then(|producer| {
+            let descriptor = topic();
+            let topic = context
+                .get_property_or_default(&descriptor)
+                .unwrap_or_default();
+            let mut headers: Vec<(String, String)> = flowfile
+                .attributes()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_string()))
+                .collect();
+            headers.sort();
+            let key = flowfile.get_attribute(KAFKA_KEY).map(|key| key.to_string());
+            producer.send(topic, key.as_deref(), &content, &headers)
+        });
+        match result {
+            Ok(()) => session.transfer(flowfile, relationship::SUCCESS),
+            Err(e) => {
+                flowfile.put_attribute(KAFKA_ERROR, &e.to_string());
+                let flowfile = session.penalize(flowfile);
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "PublishKafka"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![brokers(), topic(), delivery_timeout()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+/// Turns each polled message into a FlowFile carrying `kafka.topic`,
+/// `kafka.partition` and `kafka.offset` (plus `kafka.key` and any headers),
+/// and commits the offsets only once the session that emitted them commits.
+pub struct ConsumeKafka {
+    consumer: LazyClient<dyn KafkaConsumer>,
+    // Set while emitted messages wait for their session to commit; still set
+    // on the next trigger means the session rolled back.
+    uncommitted: Arc<AtomicBool>,
+}
+
+impl ConsumeKafka {
+    #[cfg(feature = "kafka")]
+    pub fn new() -> Self {
+        Self::from_factory(Box::new(|context: &ProcessorContext| {
+            let consumer = client::RdKafkaConsumer::from_context(context)?;
+            Ok(Arc::new(consumer) as Arc<dyn KafkaConsumer>)
+        }))
+    }
+
+    pub fn with_consumer(consumer: Arc<dyn KafkaConsumer>) -> Self {
+        Self::from_factory(Box::new(move |_: &ProcessorContext| Ok(consumer.clone())))
+    }
+
+    fn from_factory(factory: ClientFactory<dyn KafkaConsumer>) -> Self {
+        Self {
+            consumer: LazyClient::new(factory),
+            uncommitted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+impl Default for ConsumeKafka {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_flowfile(message: &KafkaMessage, session: &mut ProcessSession) -> FlowFile {
+    let mut flowfile = session.create();
+    flowfile.set_content(message.payload.clone());
+    for (key, value) in &message.headers {
+        flowfile.put_attribute(key, value);
+    }
+    if let Some(key) = &message.key {
+        flowfile.put_attribute(KAFKA_KEY, &String::from_utf8_lossy(key));
+    }
+    flowfile.put_attribute(KAFKA_TOPIC, &message.topic);
+    flowfile.set_attribute(KAFKA_PARTITION, i64::from(message.partition));
+    flowfile.set_attribute(KAFKA_OFFSET, message.offset);
+    flowfile
+}
+
+impl Processor for ConsumeKafka {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let consumer = match self.consumer.get(context) {
+            Ok(consumer) => consumer,
+            Err(e) => {
+                return Err(ProcessorError::Retryable(format!(
+                    "cannot create Kafka consumer: {}",
+                    e
+                )))
+            }
+        };
+        if self.uncommitted.load(Ordering::SeqCst) {
+            if let Err(e) = consumer.rewind() {
+                return Err(ProcessorError::Retryable(format!(
+                    "cannot rewind to committed offsets: {}",
+                    e
+                )));
+            }
+            self.uncommitted.store(false, Ordering::SeqCst);
+        }
+
+        let max = context
+            .get_property_or_default(&max_poll_records())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100);
+        let messages = match consumer.poll(max, POLL_TIMEOUT) {
+            Ok(messages) if !messages.is_empty() => messages,
+            Ok(_) => return Ok(()),
+            Err(e) => return Err(ProcessorError::Retryable(format!("poll failed: {}", e))),
+        };
+
+        let mut processed: Vec<(String, i32, i64)> = Vec::new();
+        for message in &messages {
+            let flowfile = to_flowfile(message, session);
+            session.transfer(flowfile, relationship::SUCCESS);
+            match processed.iter_mut().find(|(topic, partition, _)| {
+                *topic == message.topic && *partition == message.partition
+            }) {
+                Some(last) => last.2 = last.2.max(message.offset),
+                None => processed.push((message.topic.clone(), message.partition, message.offset)),
+            }
+        }
+
+        self.uncommitted.store(true, Ordering::SeqCst);
+        let uncommitted = self.uncommitted.clone();
+        let name = context.processor_name.clone();
+        let logger = session.logger();
+        session.on_commit(move || match consumer.commit(&processed) {
+            Ok(()) => uncommitted.store(false, Ordering::SeqCst),
+            Err(e) => logger.log(
+                LogLevel::Error,
+                &name,
+                &format!("offset commit failed: {}", e),
+            ),
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ConsumeKafka"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![brokers(), topic(), group_id(), max_poll_records()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::{Connection, MemoryConnection};
+    use crate::testing::TestRunner;
+    use futures::executor::block_on;
+    use std::collections::{HashMap, HashSet};
+
+    struct SentRecord {
+        topic: String,
+        key: Option<String>,
+        payload: Vec<u8>,
+        headers: Vec<(String, String)>,
+    }
+
+    #[derive(Default)]
+    struct MockProducer {
+        sent: Mutex<Vec<SentRecord>>,
+        fail: bool,
+    }
+
+    impl KafkaProducer for MockProducer {
+        fn send(
+            &self,
+            topic: &str,
+            key: Option<&str>,
+            payload: &[u8],
+            headers: &[(String, String)],
+        ) -> Result<(), KafkaError> {
+            if self.fail {
+                return Err(KafkaError("Broker: Message size too large".to_string()));
+            }
+            self.sent.lock().unwrap().push(SentRecord {
+                topic: topic.to_string(),
+                key: key.map(str::to_string),
+                payload: payload.to_vec(),
+                headers: headers.to_vec(),
+            });
+            Ok(())
+        }
+    }
+
+    // A single-partition log with a committed position, mimicking a broker.
+    #[derive(Default)]
+    struct MockConsumer {
+        log: Vec<KafkaMessage>,
+        position: Mutex<usize>,
+        committed: Mutex<Vec<(String, i32, i64)>>,
+    }
+
+    impl MockConsumer {
+        fn with_messages(payloads: &[&str]) -> Self {
+            let log = payloads
+                .iter()
+                .enumerate()
+                .map(|(offset, payload)| KafkaMessage {
+                    topic: "events".to_string(),
+                    partition: 0,
+                    offset: offset as i64,
+                    key: Some(b"user-1".to_vec()),
+                    payload: payload.as_bytes().to_vec(),
+                    headers: vec![("source".to_string(), "mock".to_string())],
+                })
+                .collect();
+            Self {
+                log,
+                ..Self::default()
+            }
+        }
+    }
+
+    impl KafkaConsumer for MockConsumer {
+        fn poll(&self, max: usize, _timeout: Duration) -> Result<Vec<KafkaMessage>, KafkaError> {
+            let mut position = self.position.lock().unwrap();
+            let batch: Vec<KafkaMessage> =
+                self.log.iter().skip(*position).take(max).cloned().collect();
+            *position += batch.len();
+            Ok(batch)
+        }
+
+        fn commit(&self, processed: &[(String, i32, i64)]) -> Result<(), KafkaError> {
+            self.committed.lock().unwrap().extend_from_slice(processed);
+            Ok(())
+        }
+
+        fn rewind(&self) -> Result<(), KafkaError> {
+            let committed = self.committed.lock().unwrap();
+            *self.position.lock().unwrap() = committed
+                .last()
+                .map_or(0, |(_, _, offset)| *offset as usize + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_publish_maps_attributes_to_headers() {
+        let producer = Arc::new(MockProducer::default());
+        let mut runner = TestRunner::new(PublishKafka::with_producer(producer.clone()));
+        runner.set_property(KAFKA_BROKERS, "localhost:9092");
+        runner.set_property(TOPIC, "events");
+        runner.enqueue(
+            "{\"id\":1}",
+            &[("kafka.key", "user-1"), ("mime.type", "application/json")],
+        );
+        runner.run(1);
+
+        runner.assert_transferred("success", 1);
+        let sent = producer.sent.lock().unwrap();
+        assert_eq!(sent[0].topic, "events");
+        assert_eq!(sent[0].key.as_deref(), Some("user-1"));
+        assert_eq!(sent[0].payload, b"{\"id\":1}");
+        assert!(sent[0]
+            .headers
+            .contains(&("mime.type".to_string(), "application/json".to_string())));
+    }
+
+    #[test]
+    fn test_failed_delivery_routes_to_failure() {
+        let producer = Arc::new(MockProducer {
+            fail: true,
+            ..MockProducer::default()
+        });
+        let mut runner = TestRunner::new(PublishKafka::with_producer(producer));
+        runner.set_property(TOPIC, "events");
+        runner.enqueue("too big", &[]);
+        runner.run(1);
+
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+        let failed = &runner.get_output("failure")[0];
+        assert_eq!(
+            failed.get_attribute(KAFKA_ERROR).unwrap(),
+            "Broker: Message size too large"
+        );
+    }
+
+    #[test]
+    fn test_consume_commits_after_session_commit() {
+        let consumer = Arc::new(MockConsumer::with_messages(&["a", "b", "c"]));
+        let mut runner = TestRunner::new(ConsumeKafka::with_consumer(consumer.clone()));
+        runner.set_property(MAX_POLL_RECORDS, "2");
+        runner.run(2);
+
+        runner.assert_transferred("success", 3);
+        let first = &runner.get_output("success")[0];
+        assert_eq!(first.content().unwrap().as_ref(), b"a");
+        assert_eq!(first.get_attribute(KAFKA_TOPIC).unwrap(), "events");
+        assert_eq!(first.get_attribute(KAFKA_PARTITION).unwrap(), "0");
+        assert_eq!(first.get_attribute(KAFKA_OFFSET).unwrap(), "0");
+        assert_eq!(first.get_attribute(KAFKA_KEY).unwrap(), "user-1");
+        assert_eq!(first.get_attribute("source").unwrap(), "mock");
+        assert_eq!(
+            *consumer.committed.lock().unwrap(),
+            vec![("events".to_string(), 0, 1), ("events".to_string(), 0, 2)]
+        );
+    }
+
+    #[test]
+    fn test_rolled_back_messages_are_redelivered() {
+        let consumer = Arc::new(MockConsumer::with_messages(&["a", "b"]));
+        let processor = ConsumeKafka::with_consumer(consumer.clone());
+        let context = ProcessorContext::new("consume");
+        let output = Arc::new(MemoryConnection::new());
+        let session = || {
+            let outgoing: Arc<dyn Connection> = output.clone();
+            ProcessSession::new(
+                "consume",
+                Vec::new(),
+                HashMap::from([("success".to_string(), vec![outgoing])]),
+                HashSet::new(),
+            )
+        };
+
+        let mut first = session();
+        processor.on_trigger(&context, &mut first).unwrap();
+        block_on(first.rollback());
+        assert!(consumer.committed.lock().unwrap().is_empty());
+
+        let mut second = session();
+        processor.on_trigger(&context, &mut second).unwrap();
+        block_on(second.commit()).unwrap();
+        let contents: Vec<Vec<u8>> = output
+            .drain()
+            .iter()
+            .map(|f| f.content().unwrap().to_vec())
+            .collect();
+        assert_eq!(contents, vec![b"a".to_vec(), b"b".to_vec()]);
+        assert_eq!(
+            *consumer.committed.lock().unwrap(),
+            vec![("events".to_string(), 0, 1)]
+        );
+    }
+}
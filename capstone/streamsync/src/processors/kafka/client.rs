@@ -0,0 +1,216 @@
+//! rdkafka implementations of [`KafkaProducer`] and [`KafkaConsumer`].
+
+use super::{
+    brokers, delivery_timeout, group_id, topic, KafkaConsumer, KafkaError, KafkaMessage,
+    KafkaProducer,
+};
+use crate::processor_context::ProcessorContext;
+use futures::executor::block_on;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer};
+use rdkafka::message::{Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{Offset, TopicPartitionList};
+use std::time::{Duration, Instant};
+
+// Bound on broker round trips made while committing or rewinding offsets.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn required(
+    context: &ProcessorContext,
+    descriptor: &crate::property::PropertyDescriptor,
+) -> Result<String, KafkaError> {
+    context
+        .get_property_or_default(descriptor)
+        .map(str::to_string)
+        .ok_or_else(|| KafkaError(format!("property '{}' is not set", descriptor.name)))
+}
+
+fn kafka_error(e: rdkafka::error::KafkaError) -> KafkaError {
+    KafkaError(e.to_string())
+}
+
+pub struct RdKafkaProducer {
+    producer: FutureProducer,
+    timeout: Duration,
+}
+
+impl RdKafkaProducer {
+    pub fn from_context(context: &ProcessorContext) -> Result<Self, KafkaError> {
+        let timeout = required(context, &delivery_timeout())?
+            .parse()
+            .map(Duration::from_millis)
+            .map_err(|_| KafkaError("delivery.timeout.ms must be a number".to_string()))?;
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", required(context, &brokers())?)
+            .set("message.timeout.ms", timeout.as_millis().to_string())
+            .create()
+            .map_err(kafka_error)?;
+        Ok(Self { producer, timeout })
+    }
+}
+
+impl KafkaProducer for RdKafkaProducer {
+    fn send(
+        &self,
+        topic: &str,
+        key: Option<&str>,
+        payload: &[u8],
+        headers: &[(String, String)],
+    ) -> Result<(), KafkaError> {
+        let mut owned_headers = OwnedHeaders::new();
+        for (name, value) in headers {
+            owned_headers = owned_headers.insert(Header {
+                key: name,
+                value: Some(value.as_str()),
+            });
+        }
+        let mut record = FutureRecord::to(topic)
+            .payload(payload)
+            .headers(owned_headers);
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+        block_on(self.producer.send(record, self.timeout))
+            .map(|_| ())
+            .map_err(|(e, _)| kafka_error(e))
+    }
+}
+
+pub struct RdKafkaConsumer {
+    consumer: BaseConsumer,
+}
+
+impl RdKafkaConsumer {
+    pub fn from_context(context: &ProcessorContext) -> Result<Self, KafkaError> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", required(context, &brokers())?)
+            .set("group.id", required(context, &group_id())?)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .map_err(kafka_error)?;
+        consumer
+            .subscribe(&[required(context, &topic())?.as_str()])
+            .map_err(kafka_error)?;
+        Ok(Self { consumer })
+    }
+}
+
+impl KafkaConsumer for RdKafkaConsumer {
+    fn poll(&self, max: usize, timeout: Duration) -> Result<Vec<KafkaMessage>, KafkaError> {
+        let deadline = Instant::now() + timeout;
+        let mut messages = Vec::new();
+        while messages.len() < max {
+            // Only the first message is worth waiting for; after that, take
+            // whatever is already buffered.
+            let wait = if messages.is_empty() {
+                deadline.saturating_duration_since(Instant::now())
+            } else {
+                Duration::ZERO
+            };
+            let Some(message) = self.consumer.poll(wait) else {
+                break;
+            };
+            let message = message.map_err(kafka_error)?;
+            let headers = message
+                .headers()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .map(|header| {
+                            let value = header
+                                .value
+                                .map(String::from_utf8_lossy)
+                                .unwrap_or_default();
+                            (header.key.to_string(), value.into_owned())
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            messages.push(KafkaMessage {
+                topic: message.topic().to_string(),
+                partition: message.partition(),
+                offset: message.offset(),
+                key: message.key().map(<[u8]>::to_vec),
+                payload: message.payload().map(<[u8]>::to_vec).unwrap_or_default(),
+                headers,
+            });
+        }
+        Ok(messages)
+    }
+
+    fn commit(&self, processed: &[(String, i32, i64)]) -> Result<(), KafkaError> {
+        let mut offsets = TopicPartitionList::new();
+        for (topic, partition, offset) in processed {
+            // Kafka commits the next offset to read, not the last one read.
+            offsets
+                .add_partition_offset(topic, *partition, Offset::Offset(offset + 1))
+                .map_err(kafka_error)?;
+        }
+        self.consumer
+            .commit(&offsets, CommitMode::Sync)
+            .map_err(kafka_error)
+    }
+
+    fn rewind(&self) -> Result<(), KafkaError> {
+        let committed = self
+            .consumer
+            .committed(REQUEST_TIMEOUT)
+            .map_err(kafka_error)?;
+        let mut positions = TopicPartitionList::new();
+        for element in committed.elements() {
+            // A partition without a committed offset restarts from the beginning,
+            // matching auto.offset.reset=earliest.
+            let offset = match element.offset() {
+                Offset::Invalid => Offset::Beginning,
+                offset => offset,
+            };
+            positions
+                .add_partition_offset(element.topic(), element.partition(), offset)
+                .map_err(kafka_error)?;
+        }
+        self.consumer
+            .seek_partitions(positions, REQUEST_TIMEOUT)
+            .map_err(kafka_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::kafka::{ConsumeKafka, PublishKafka, GROUP_ID, KAFKA_BROKERS, TOPIC};
+    use crate::testing::TestRunner;
+
+    // Needs a broker, e.g. `KAFKA_BROKERS=localhost:9092 cargo test --features kafka -- --ignored`.
+    #[test]
+    #[ignore = "requires a Kafka broker at KAFKA_BROKERS"]
+    fn test_round_trip_through_broker() {
+        let brokers =
+            std::env::var("KAFKA_BROKERS").unwrap_or_else(|_| "localhost:9092".to_string());
+        let topic = format!("streamsync-test-{}", uuid::Uuid::new_v4());
+
+        let mut publish = TestRunner::new(PublishKafka::new());
+        publish.set_property(KAFKA_BROKERS, &brokers);
+        publish.set_property(TOPIC, &topic);
+        for i in 0..10 {
+            publish.enqueue(format!("message {}", i), &[("index", &i.to_string())]);
+        }
+        publish.run(10);
+        publish.assert_transferred("success", 10);
+
+        let mut consume = TestRunner::new(ConsumeKafka::new());
+        consume.set_property(KAFKA_BROKERS, &brokers);
+        consume.set_property(TOPIC, &topic);
+        consume.set_property(GROUP_ID, &topic);
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while consume.get_output("success").len() < 10 && Instant::now() < deadline {
+            consume.run(1);
+        }
+        let received = consume.get_output("success");
+        assert_eq!(received.len(), 10);
+        assert_eq!(received[3].get_attribute("index").unwrap(), "3");
+        assert_eq!(received[3].get_attribute("kafka.offset").unwrap(), "3");
+    }
+}
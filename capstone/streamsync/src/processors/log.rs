@@ -0,0 +1,203 @@
+use crate::flowfile::FlowFile;
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::io::Read;
+
+pub const LOG_LEVEL: &str = "log.level";
+pub const ATTRIBUTES_TO_LOG: &str = "attributes.to.log";
+pub const CONTENT_SNIPPET_LENGTH: &str = "content.snippet.length";
+
+/// Logs a one-line summary of every FlowFile and passes it through unchanged.
+pub struct LogProcessor;
+
+impl LogProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for LogProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds the summary line: id, size, the selected attributes (all of them,
+/// sorted, when no selection is configured) and an optional content snippet.
+pub fn format_summary(flowfile: &FlowFile, attributes: &[String], snippet_length: usize) -> String {
+    let mut names: Vec<&String> = if attributes.is_empty() {
+        flowfile.attributes().keys().collect()
+    } else {
+        attributes.iter().collect()
+    };
+    names.sort();
+
+    let attributes: Vec<String> = names
+        .iter()
+        .filter_map(|name| {
+            flowfile
+                .get_attribute(name)
+                .map(|value| format!("{}={}", name, value))
+        })
+        .collect();
+
+    let mut summary = format!(
+        "FlowFile {} | {} bytes | attributes: {{{}}}",
+        flowfile.id(),
+        flowfile.size(),
+        attributes.join(", ")
+    );
+    if snippet_length > 0 && flowfile.size() > 0 {
+        let end = snippet_length.min(flowfile.size());
+        let mut head = Vec::with_capacity(end);
+        match flowfile
+            .content_reader()
+            .take(end as u64)
+            .read_to_end(&mut head)
+        {
+            Ok(_) => {
+                let mut snippet = String::from_utf8_lossy(&head).into_owned();
+                if end < flowfile.size() {
+                    snippet.push_str("...");
+                }
+                summary.push_str(&format!(" | content: {:?}", snippet));
+            }
+            Err(e) => summary.push_str(&format!(" | content unreadable: {}", e)),
+        }
+    }
+    summary
+}
+
+fn log_level() -> PropertyDescriptor {
+    PropertyDescriptor::new(LOG_LEVEL, "Level to log summaries at")
+        .default_value("info")
+        .validator(PropertyValidator::allowed_values(&[
+            "trace", "debug", "info", "warn", "error",
+        ]))
+}
+
+fn content_snippet_length() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CONTENT_SNIPPET_LENGTH,
+        "Number of content bytes to include; 0 disables the snippet",
+    )
+    .default_value("0")
+    .validator(PropertyValidator::IntRange { min: 0, max: 65536 })
+}
+
+impl Processor for LogProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let level: LogLevel = context
+            .get_property_or_default(&log_level())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(LogLevel::Info);
+        let attributes: Vec<String> = context
+            .get_property(ATTRIBUTES_TO_LOG)
+            .map(|list| {
+                list.split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let snippet_length = context
+            .get_property_or_default(&content_snippet_length())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+
+        while let Some(flowfile) = session.get()? {
+            let summary = format_summary(&flowfile, &attributes, snippet_length);
+            session.log(level, &summary);
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "LogProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            log_level(),
+            PropertyDescriptor::new(
+                ATTRIBUTES_TO_LOG,
+                "Comma-separated attribute names to include; all when unset",
+            ),
+            content_snippet_length(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    #[test]
+    fn test_summary_with_content() {
+        let mut flowfile = FlowFile::with_content("hello streamsync");
+        flowfile.put_attribute("filename", "greeting.txt");
+        flowfile.put_attribute("path", "/tmp");
+        flowfile.put_attribute("owner", "nobody");
+
+        let summary = format_summary(&flowfile, &["path".to_string(), "filename".to_string()], 5);
+        assert_eq!(
+            summary,
+            format!(
+                "FlowFile {} | 16 bytes | attributes: {{filename=greeting.txt, path=/tmp}} | content: \"hello...\"",
+                flowfile.id()
+            )
+        );
+    }
+
+    #[test]
+    fn test_summary_without_content() {
+        let mut flowfile = FlowFile::new();
+        flowfile.put_attribute("filename", "empty.txt");
+
+        let summary = format_summary(&flowfile, &[], 32);
+        assert_eq!(
+            summary,
+            format!(
+                "FlowFile {} | 0 bytes | attributes: {{filename=empty.txt}}",
+                flowfile.id()
+            )
+        );
+    }
+
+    #[test]
+    fn test_passes_flowfiles_through() {
+        let mut runner = TestRunner::new(LogProcessor::new());
+        runner.set_property(LOG_LEVEL, "debug");
+        runner.set_property(CONTENT_SNIPPET_LENGTH, "4");
+        runner.enqueue("payload", &[("filename", "a.txt")]);
+        runner.run(1);
+
+        runner.assert_transferred("success", 1);
+        assert_eq!(
+            runner.get_output("success")[0].content().unwrap().as_ref(),
+            b"payload"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use crate::flowfile::fragment::{
+    restore_filename, FRAGMENT_COUNT, FRAGMENT_IDENTIFIER, FRAGMENT_INDEX,
+};
+use crate::flowfile::{ContentWriter, FlowFile};
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::BTreeMap;
+use std::io;
+use std::time::Duration;
+
+pub const MAX_FRAGMENTS: &str = "max.fragments";
+
+pub const MERGED: &str = "merged";
+pub const ORIGINAL: &str = "original";
+
+pub const MERGE_ERROR: &str = "merge.error";
+
+// How long to back off while only some fragments of a split have arrived.
+const MERGE_YIELD: Duration = Duration::from_millis(100);
+
+fn max_fragments() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAX_FRAGMENTS,
+        "FlowFiles taken per trigger; a split merges only once all its fragments are taken together",
+    )
+    .default_value("1000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 1_000_000,
+    })
+}
+
+fn max_fragments_of(context: &ProcessorContext) -> usize {
+    context
+        .get_property_or_default(&max_fragments())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1000)
+}
+
+/// Joins the fragments of a split (see `crate::flowfile::fragment`) back into
+/// one FlowFile once all `fragment.count` of them have arrived, in
+/// `fragment.index` order, and routes it to "merged" under the original
+/// filename; the fragments go to "original". Each trigger takes up to
+/// `max.fragments` FlowFiles from the queue, and a split is merged only when
+/// all of its fragments are among them; fragments whose siblings were not
+/// taken go back to the queue for a later trigger. A FlowFile that is not a
+/// fragment, a split with duplicate or out of range indices, or one with
+/// more fragments than `max.fragments`, which could never all be taken at
+/// once, goes to failure with a `merge.error` attribute.
+pub struct MergeContent;
+
+impl MergeContent {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for MergeContent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Fragment {
+    identifier: String,
+    index: i64,
+    count: i64,
+}
+
+fn fragment_of(flowfile: &FlowFile) -> Option<Fragment> {
+    Some(Fragment {
+        identifier: flowfile.get_attribute(FRAGMENT_IDENTIFIER)?.to_string(),
+        index: flowfile.get_attribute(FRAGMENT_INDEX)?.as_i64()?,
+        count: flowfile.get_attribute(FRAGMENT_COUNT)?.as_i64()?,
+    })
+}
+
+fn fail(session: &mut ProcessSession, mut flowfile: FlowFile, message: &str) {
+    flowfile.put_attribute(MERGE_ERROR, message);
+    session.transfer(flowfile, relationship::FAILURE);
+}
+
+fn merge(session: &mut ProcessSession, fragments: &[FlowFile]) -> io::Result<FlowFile> {
+    let mut merged = session.create_from(&fragments[0]);
+    let mut content = ContentWriter::new();
+    for fragment in fragments {
+        io::copy(&mut fragment.content_reader(), &mut content)?;
+    }
+    merged.set_content_from(content)?;
+    restore_filename(&mut merged, fragments);
+    Ok(merged)
+}
+
+impl Processor for MergeContent {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let max = max_fragments_of(context);
+        let batch = session.get_batch(max)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let mut splits: BTreeMap<String, (i64, Vec<(i64, FlowFile)>)> = BTreeMap::new();
+        for flowfile in batch {
+            match fragment_of(&flowfile) {
+                Some(fragment) => splits
+                    .entry(fragment.identifier)
+                    .or_insert_with(|| (fragment.count, Vec::new()))
+                    .1
+                    .push((fragment.index, flowfile)),
+                None => fail(
+                    session,
+                    flowfile,
+                    "not a fragment: fragment attributes are missing",
+                ),
+            }
+        }
+
+        let mut waiting = false;
+        for (count, mut fragments) in splits.into_values() {
+            let mergeable = count >= 1 && count as usize <= max;
+            if mergeable && (fragments.len() as i64) < count {
+                waiting = true;
+                for (_, flowfile) in fragments {
+                    session.requeue(flowfile);
+                }
+                continue;
+            }
+            fragments.sort_by_key(|(index, _)| *index);
+            let error = if !mergeable {
+                Some(format!(
+                    "a split of {} cannot be merged with {} of {}",
+                    count, MAX_FRAGMENTS, max
+                ))
+            } else if fragments.len() as i64 != count
+                || fragments
+                    .iter()
+                    .enumerate()
+                    .any(|(position, (index, _))| *index != position as i64)
+            {
+                Some(format!(
+                    "fragment indices of a split of {} are not 0 to {}",
+                    count,
+                    count - 1
+                ))
+            } else {
+                None
+            };
+            let fragments: Vec<FlowFile> = fragments.into_iter().map(|(_, f)| f).collect();
+            match error {
+                Some(message) => {
+                    for flowfile in fragments {
+                        fail(session, flowfile, &message);
+                    }
+                }
+                None => {
+                    let merged = merge(session, &fragments)?;
+                    session.transfer(merged, MERGED);
+                    for fragment in fragments {
+                        session.transfer(fragment, ORIGINAL);
+                    }
+                }
+            }
+        }
+        if waiting {
+            session.yield_for(MERGE_YIELD);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "MergeContent"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![max_fragments()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(MERGED, "FlowFiles joined from all fragments of a split"),
+            Relationship::new(ORIGINAL, "The fragments that were merged"),
+            Relationship::failure(),
+        ]
+    }
+
+    fn batch_size(&self, context: &ProcessorContext) -> usize {
+        max_fragments_of(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowfile::fragment::{FILENAME, FRAGMENT_ORIGINAL_FILENAME};
+    use crate::processors::split_text::{self, SplitText};
+    use crate::testing::TestRunner;
+
+    // The fragments SplitText makes of `content`, renamed the way a
+    // downstream processor might.
+    fn split(content: &str, filename: &str) -> Vec<FlowFile> {
+        let mut runner = TestRunner::new(SplitText::new());
+        runner.enqueue(content, &[(FILENAME, filename)]);
+        runner.run(1);
+        runner
+            .get_output(split_text::SPLITS)
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut fragment)| {
+                fragment.put_attribute(FILENAME, &format!("part-{}.txt", index));
+                fragment
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filename_survives_split_then_merge() {
+        let mut fragments = split("alpha\nbeta\ngamma\n", "report.csv");
+        assert_eq!(fragments.len(), 3);
+        fragments.reverse();
+
+        let mut runner = TestRunner::new(MergeContent::new());
+        for fragment in fragments {
+            runner.enqueue_flowfile(fragment);
+        }
+        runner.run(1);
+
+        runner.assert_transferred(ORIGINAL, 3);
+        let merged = runner.get_output(MERGED);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            merged[0].content().unwrap().as_ref(),
+            b"alpha\nbeta\ngamma\n"
+        );
+        assert_eq!(
+            merged[0].get_attribute(FILENAME).unwrap().to_string(),
+            "report.csv"
+        );
+        assert!(merged[0]
+            .get_attribute(FRAGMENT_ORIGINAL_FILENAME)
+            .is_none());
+    }
+
+    #[test]
+    fn test_waits_for_every_fragment() {
+        let mut fragments = split("a\nb\n", "pair.txt");
+        let last = fragments.pop().unwrap();
+
+        let mut runner = TestRunner::new(MergeContent::new());
+        runner.enqueue_flowfile(fragments.remove(0));
+        runner.run(1);
+        runner.assert_transferred(MERGED, 0);
+        assert_eq!(runner.queue_size(), 1);
+
+        runner.enqueue_flowfile(last);
+        runner.run(1);
+        runner.assert_transferred(MERGED, 1);
+        assert_eq!(runner.queue_size(), 0);
+    }
+
+    #[test]
+    fn test_unmergeable_flowfiles_fail() {
+        let mut runner = TestRunner::new(MergeContent::new());
+        runner.set_property(MAX_FRAGMENTS, "2");
+        runner.enqueue("plain", &[]);
+        for fragment in split("a\nb\nc\n", "three.txt").into_iter().take(2) {
+            runner.enqueue_flowfile(fragment);
+        }
+        // Two FlowFiles per trigger.
+        runner.run(2);
+
+        let failed = runner.get_output(relationship::FAILURE);
+        let errors: Vec<String> = failed
+            .iter()
+            .map(|f| f.get_attribute(MERGE_ERROR).unwrap().to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "not a fragment: fragment attributes are missing",
+                "a split of 3 cannot be merged with max.fragments of 2",
+                "a split of 3 cannot be merged with max.fragments of 2",
+            ]
+        );
+    }
+}
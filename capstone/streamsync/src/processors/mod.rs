@@ -0,0 +1,29 @@
+pub mod compress_content;
+pub mod control_rate;
+pub mod convert_csv_to_json;
+pub mod detect_duplicate;
+pub mod dict_lookup;
+pub mod distribute_load;
+pub mod evaluate_json_path;
+pub mod extract_text;
+pub mod generate_flowfile;
+pub mod get_file;
+pub mod get_http;
+pub mod hash_content;
+pub mod kafka;
+pub mod log;
+pub mod merge_content;
+pub mod put_database;
+pub mod put_file;
+pub mod query;
+pub mod remote_port;
+pub mod route_on_attribute;
+pub mod route_on_size;
+pub mod sample_flowfile;
+pub mod scan_content;
+pub mod split_text;
+pub mod stdio;
+pub mod tail_file;
+pub mod update_attribute;
+pub mod validate_json;
+pub mod wait_notify;
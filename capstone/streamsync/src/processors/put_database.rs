@@ -0,0 +1,294 @@
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use serde_json::{Map, Value};
+use std::sync::Mutex;
+
+pub const DATABASE_PATH: &str = "database.path";
+pub const TABLE_NAME: &str = "table.name";
+pub const CREATE_TABLE: &str = "create.table";
+
+pub const DATABASE_ERROR: &str = "database.error";
+pub const DATABASE_ROWS_INSERTED: &str = "database.rows.inserted";
+
+fn database_path() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DATABASE_PATH,
+        "SQLite database file (or file: URI) to write to",
+    )
+    .required()
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn table_name() -> PropertyDescriptor {
+    PropertyDescriptor::new(TABLE_NAME, "Table that receives one row per JSON record")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn create_table() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CREATE_TABLE,
+        "Create the table from the first record's keys, as TEXT columns, if missing",
+    )
+    .default_value("false")
+    .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+/// Inserts newline-delimited JSON objects into a SQLite table, mapping keys
+/// to columns. Each FlowFile is written in a single transaction, so a bad
+/// record leaves the table untouched and routes the FlowFile to failure.
+pub struct PutDatabase {
+    connection: Mutex<Option<Connection>>,
+}
+
+impl PutDatabase {
+    pub fn new() -> Self {
+        Self {
+            connection: Mutex::new(None),
+        }
+    }
+}
+
+impl Default for PutDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// SQLite identifiers are quoted with double quotes, doubling any inside.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        nested => SqlValue::Text(nested.to_string()),
+    }
+}
+
+fn parse_records(content: &[u8]) -> Result<Vec<Map<String, Value>>, String> {
+    let text = std::str::from_utf8(content).map_err(|e| format!("content is not UTF-8: {}", e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| match serde_json::from_str(line) {
+            Ok(Value::Object(record)) => Ok(record),
+            Ok(_) => Err(format!("line {} is not a JSON object", index + 1)),
+            Err(e) => Err(format!("line {}: {}", index + 1, e)),
+        })
+        .collect()
+}
+
+fn insert_records(
+    connection: &mut Connection,
+    table: &str,
+    create: bool,
+    records: &[Map<String, Value>],
+) -> rusqlite::Result<usize> {
+    let transaction = connection.transaction()?;
+    let table = quote_identifier(table);
+    if let (true, Some(first)) = (create, records.first()) {
+        let columns: Vec<String> = first
+            .keys()
+            .map(|key| format!("{} TEXT", quote_identifier(key)))
+            .collect();
+        transaction.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} ({})",
+                table,
+                columns.join(", ")
+            ),
+            [],
+        )?;
+    }
+    for record in records {
+        let columns: Vec<String> = record.keys().map(|key| quote_identifier(key)).collect();
+        let placeholders: Vec<String> = (1..=record.len()).map(|i| format!("?{}", i)).collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table,
+            columns.join(", "),
+            placeholders.join(", ")
+        );
+        transaction
+            .prepare_cached(&sql)?
+            .execute(params_from_iter(record.values().map(to_sql)))?;
+    }
+    transaction.commit()?;
+    Ok(records.len())
+}
+
+impl PutDatabase {
+    fn write(&self, context: &ProcessorContext, flowfile: &FlowFile) -> Result<usize, String> {
+        let content = flowfile.content().map_err(|e| e.to_string())?;
+        let records = parse_records(&content)?;
+        let table = context
+            .get_property_or_default(&table_name())
+            .unwrap_or_default()
+            .to_string();
+        let create = context.get_property_or_default(&create_table()) == Some("true");
+
+        let mut connection = self.connection.lock().unwrap();
+        if connection.is_none() {
+            let path = context
+                .get_property_or_default(&database_path())
+                .unwrap_or_default()
+                .to_string();
+            *connection = Some(Connection::open(path).map_err(|e| e.to_string())?);
+        }
+        let connection = connection.as_mut().unwrap();
+        insert_records(connection, &table, create, &records).map_err(|e| e.to_string())
+    }
+}
+
+impl Processor for PutDatabase {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(mut flowfile) = session.get()? else {
+            return Ok(());
+        };
+        match self.write(context, &flowfile) {
+            Ok(rows) => {
+                flowfile.set_attribute(DATABASE_ROWS_INSERTED, rows as i64);
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Err(e) => {
+                flowfile.put_attribute(DATABASE_ERROR, &e);
+                let flowfile = session.penalize(flowfile);
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "PutDatabase"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![database_path(), table_name(), create_table()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    // A named in-memory database shared between the processor and the test;
+    // it lives as long as the returned connection stays open.
+    fn shared_memory_database(name: &str) -> (String, Connection) {
+        let uri = format!(
+            "file:{}-{}?mode=memory&cache=shared",
+            name,
+            std::process::id()
+        );
+        let connection = Connection::open(&uri).unwrap();
+        (uri, connection)
+    }
+
+    fn count(connection: &Connection, table: &str) -> i64 {
+        connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_creates_table_and_inserts_rows() {
+        let (uri, db) = shared_memory_database("create");
+        let mut runner = TestRunner::new(PutDatabase::new());
+        runner.set_property(DATABASE_PATH, &uri);
+        runner.set_property(TABLE_NAME, "events");
+        runner.set_property(CREATE_TABLE, "true");
+        runner.enqueue(
+            "{\"id\": 1, \"name\": \"a\"}\n\n{\"id\": 2, \"name\": \"b\"}\n",
+            &[],
+        );
+        runner.enqueue("{\"id\": 3, \"name\": \"c\"}", &[]);
+        runner.run(2);
+
+        runner.assert_transferred("success", 2);
+        assert_eq!(
+            runner.get_output("success")[0]
+                .get_attribute(DATABASE_ROWS_INSERTED)
+                .unwrap(),
+            "2"
+        );
+        assert_eq!(count(&db, "events"), 3);
+        let name: String = db
+            .query_row("SELECT name FROM events WHERE id = '2'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(name, "b");
+    }
+
+    #[test]
+    fn test_constraint_violation_rolls_back_whole_flowfile() {
+        let (uri, db) = shared_memory_database("violation");
+        db.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, email TEXT UNIQUE NOT NULL)",
+            [],
+        )
+        .unwrap();
+        let mut runner = TestRunner::new(PutDatabase::new());
+        runner.set_property(DATABASE_PATH, &uri);
+        runner.set_property(TABLE_NAME, "users");
+        runner.enqueue(
+            "{\"id\": 1, \"email\": \"a@example.com\"}\n\
+             {\"id\": 2, \"email\": \"b@example.com\"}\n\
+             {\"id\": 3, \"email\": \"a@example.com\"}\n",
+            &[],
+        );
+        runner.run(1);
+
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+        let error = runner.get_output("failure")[0]
+            .get_attribute(DATABASE_ERROR)
+            .unwrap()
+            .to_string();
+        assert!(error.contains("UNIQUE constraint failed"), "{}", error);
+        assert_eq!(count(&db, "users"), 0);
+    }
+
+    #[test]
+    fn test_invalid_json_routes_to_failure() {
+        let (uri, _db) = shared_memory_database("invalid");
+        let mut runner = TestRunner::new(PutDatabase::new());
+        runner.set_property(DATABASE_PATH, &uri);
+        runner.set_property(TABLE_NAME, "events");
+        runner.enqueue("{\"id\": 1}\n[1, 2]\n", &[]);
+        runner.run(1);
+
+        runner.assert_transferred("failure", 1);
+        assert_eq!(
+            runner.get_output("failure")[0]
+                .get_attribute(DATABASE_ERROR)
+                .unwrap(),
+            "line 2 is not a JSON object"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const OUTPUT_DIRECTORY: &str = "output.directory";
+pub const CONFLICT_RESOLUTION: &str = "conflict.resolution";
+pub const CREATE_DIRS: &str = "create.dirs";
+
+pub const PUT_FILE_ERROR: &str = "put.file.error";
+
+// Give up renaming after this many taken names rather than probing forever.
+const MAX_RENAME_ATTEMPTS: u32 = 10_000;
+
+fn output_directory() -> PropertyDescriptor {
+    PropertyDescriptor::new(OUTPUT_DIRECTORY, "Directory the files are written to")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn conflict_resolution() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CONFLICT_RESOLUTION,
+        "What to do when the file already exists: fail, replace it, or rename the new one",
+    )
+    .default_value("fail")
+    .validator(PropertyValidator::allowed_values(&[
+        "fail", "replace", "rename",
+    ]))
+}
+
+fn create_dirs() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        CREATE_DIRS,
+        "Create the output directory (and its parents) if missing",
+    )
+    .default_value("false")
+    .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+/// Writes each FlowFile's content to `output.directory`, named after its
+/// `filename` attribute (or its id when that is missing). Only the final path
+/// component of `filename` is used, so a FlowFile cannot escape the directory.
+pub struct PutFileProcessor;
+
+impl PutFileProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for PutFileProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Streams the content into `file` a chunk at a time.
+fn write_to(mut file: File, flowfile: &FlowFile) -> io::Result<()> {
+    io::copy(&mut flowfile.content_reader(), &mut file).map(|_| ())
+}
+
+fn create_new(path: &Path, flowfile: &FlowFile) -> io::Result<()> {
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    write_to(file, flowfile)
+}
+
+// "report.csv" becomes "report-1.csv", "report-2.csv", ... until one is free.
+fn write_renamed(directory: &Path, name: &Path, flowfile: &FlowFile) -> io::Result<PathBuf> {
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    for attempt in 1..=MAX_RENAME_ATTEMPTS {
+        let path = directory.join(format!("{}-{}{}", stem, attempt, extension));
+        match create_new(&path, flowfile) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "no free name left to rename to",
+    ))
+}
+
+impl PutFileProcessor {
+    fn write(&self, context: &ProcessorContext, flowfile: &FlowFile) -> io::Result<PathBuf> {
+        let directory = PathBuf::from(
+            context
+                .get_property_or_default(&output_directory())
+                .unwrap_or_default(),
+        );
+        if context.get_property_or_default(&create_dirs()) == Some("true") {
+            fs::create_dir_all(&directory)?;
+        }
+        let name = flowfile
+            .get_attribute("filename")
+            .map(|filename| PathBuf::from(filename.to_string()))
+            .and_then(|filename| filename.file_name().map(PathBuf::from))
+            .unwrap_or_else(|| PathBuf::from(flowfile.id().to_string()));
+        let path = directory.join(&name);
+
+        match context.get_property_or_default(&conflict_resolution()) {
+            Some("replace") => write_to(File::create(&path)?, flowfile).map(|()| path),
+            Some("rename") => match create_new(&path, flowfile) {
+                Ok(()) => Ok(path),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    write_renamed(&directory, &name, flowfile)
+                }
+                Err(e) => Err(e),
+            },
+            _ => create_new(&path, flowfile).map(|()| path),
+        }
+    }
+}
+
+impl Processor for PutFileProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(mut flowfile) = session.get()? else {
+            return Ok(());
+        };
+        match self.write(context, &flowfile) {
+            Ok(path) => {
+                if let Some(name) = path.file_name() {
+                    flowfile.put_attribute("filename", &name.to_string_lossy());
+                }
+                flowfile.put_attribute("absolute.path", &path.to_string_lossy());
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Err(e) => {
+                flowfile.put_attribute(PUT_FILE_ERROR, &e.to_string());
+                let flowfile = session.penalize(flowfile);
+                session.transfer(flowfile, relationship::FAILURE);
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "PutFileProcessor"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![output_directory(), conflict_resolution(), create_dirs()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-put-file-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn runner_for(dir: &Path, conflict_resolution: &str) -> TestRunner {
+        let mut runner = TestRunner::new(PutFileProcessor::new());
+        runner.set_property(OUTPUT_DIRECTORY, &dir.to_string_lossy());
+        runner.set_property(CONFLICT_RESOLUTION, conflict_resolution);
+        runner
+    }
+
+    #[test]
+    fn test_replace_overwrites_existing_file() {
+        let dir = temp_dir("replace");
+        fs::write(dir.join("out.txt"), "old").unwrap();
+        let mut runner = runner_for(&dir, "replace");
+        runner.enqueue("new", &[("filename", "../out.txt")]);
+        runner.run(1);
+
+        runner.assert_transferred("success", 1);
+        assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "new");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rename_keeps_both_files() {
+        let dir = temp_dir("rename");
+        fs::write(dir.join("out.txt"), "old").unwrap();
+        let mut runner = runner_for(&dir, "rename");
+        runner.enqueue("second", &[("filename", "out.txt")]);
+        runner.enqueue("third", &[("filename", "out.txt")]);
+        runner.run(2);
+
+        runner.assert_transferred("success", 2);
+        assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(dir.join("out-1.txt")).unwrap(), "second");
+        assert_eq!(fs::read_to_string(dir.join("out-2.txt")).unwrap(), "third");
+        assert_eq!(
+            runner.get_output("success")[1]
+                .get_attribute("filename")
+                .unwrap(),
+            "out-2.txt"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_and_io_errors_route_to_failure() {
+        let dir = temp_dir("failure");
+        fs::write(dir.join("out.txt"), "old").unwrap();
+        let mut runner = runner_for(&dir, "fail");
+        runner.enqueue("new", &[("filename", "out.txt")]);
+        runner.run(1);
+        runner.assert_transferred("failure", 1);
+        assert_eq!(fs::read_to_string(dir.join("out.txt")).unwrap(), "old");
+
+        // A regular file where the directory should be cannot be created.
+        let mut runner = runner_for(&dir.join("out.txt").join("nested"), "replace");
+        runner.set_property(CREATE_DIRS, "true");
+        runner.enqueue("new", &[("filename", "x.txt")]);
+        runner.run(1);
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+        assert!(runner.get_output("failure")[0]
+            .get_attribute(PUT_FILE_ERROR)
+            .is_some());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
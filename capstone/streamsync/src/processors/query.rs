@@ -0,0 +1,161 @@
+use crate::expression::predicate::Predicate;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+
+pub const QUERY: &str = "query";
+
+pub const MATCHED: &str = "matched";
+pub const UNMATCHED: &str = "unmatched";
+
+fn query() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        QUERY,
+        "Boolean expression over attributes and contentLength, e.g. contentLength > 1024 && filename endsWith '.log'",
+    )
+    .required()
+    .validator(PropertyValidator::Predicate)
+}
+
+/// Routes each FlowFile to "matched" or "unmatched" depending on whether the
+/// `query` expression holds for it.
+pub struct QueryProcessor;
+
+impl QueryProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for QueryProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for QueryProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        // The flow only starts once the query has validated.
+        let descriptor = query();
+        let Some(Ok(predicate)) = context
+            .get_property_or_default(&descriptor)
+            .map(Predicate::parse)
+        else {
+            return Err(ProcessorError::Fatal(
+                "query is missing or invalid".to_string(),
+            ));
+        };
+        for flowfile in batch {
+            let relationship = if predicate.matches(&flowfile) {
+                MATCHED
+            } else {
+                UNMATCHED
+            };
+            session.transfer(flowfile, relationship);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "QueryProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![query()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(MATCHED, "FlowFiles the query holds for"),
+            Relationship::new(UNMATCHED, "FlowFiles the query does not hold for"),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn run_query(query: &str, inputs: &[(&str, &[(&str, &str)])]) -> TestRunner {
+        let mut runner = TestRunner::new(QueryProcessor::new());
+        runner.set_property(QUERY, query);
+        for (content, attributes) in inputs {
+            runner.enqueue(*content, attributes);
+        }
+        runner.run(1);
+        runner
+    }
+
+    #[test]
+    fn test_numeric_comparison() {
+        let runner = run_query(
+            "priority >= 5",
+            &[
+                ("low", &[("priority", "3")]),
+                ("high", &[("priority", "12")]),
+                ("none", &[]),
+            ],
+        );
+        assert_eq!(runner.get_output_text(MATCHED), vec!["high"]);
+        assert_eq!(runner.get_output_text(UNMATCHED), vec!["low", "none"]);
+    }
+
+    #[test]
+    fn test_string_operation() {
+        let runner = run_query(
+            "filename startsWith \"report-\"",
+            &[
+                ("a", &[("filename", "report-2024.csv")]),
+                ("b", &[("filename", "summary.csv")]),
+            ],
+        );
+        assert_eq!(runner.get_output_text(MATCHED), vec!["a"]);
+        assert_eq!(runner.get_output_text(UNMATCHED), vec!["b"]);
+    }
+
+    #[test]
+    fn test_compound_expression() {
+        let big = "x".repeat(2048);
+        let runner = run_query(
+            "contentLength > 1024 && filename endsWith '.log' || (mime.type == 'text/plain' && !archived)",
+            &[
+                (big.as_str(), &[("filename", "app.log")]),
+                (big.as_str(), &[("filename", "app.txt")]),
+                ("small", &[("filename", "app.log")]),
+                ("plain", &[("mime.type", "text/plain")]),
+                ("old", &[("mime.type", "text/plain"), ("archived", "true")]),
+            ],
+        );
+        assert_eq!(runner.get_output(MATCHED).len(), 2);
+        assert_eq!(runner.get_output_text(MATCHED)[1], "plain");
+        assert_eq!(runner.get_output_text(UNMATCHED)[1..], ["small", "old"]);
+    }
+
+    #[test]
+    fn test_invalid_query_fails_validation() {
+        let runner = run_query("contentLength >", &[]);
+        let errors = runner
+            .context()
+            .validate_against(&QueryProcessor::new().properties());
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,721 @@
+//! Site-to-site transfer of FlowFiles between streamsync instances over TCP.
+//!
+//! The sender writes a batch as a big-endian `u32` count followed by that many
+//! frames from [`crate::flowfile::codec`], then waits for a single status byte:
+//! [`ACK`] once the receiver has committed the batch into its flow, or [`NACK`]
+//! when the receiving session rolled back. Anything else (a timeout, a dropped
+//! connection, a truncated batch) leaves the sender to retry the whole batch.
+//! The receiver remembers the ids of recently committed FlowFiles, so a batch
+//! that is resent because its ack was lost is acknowledged again but not
+//! emitted twice.
+
+use crate::flowfile::codec::CodecError;
+use crate::flowfile::FlowFile;
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::provenance::ProvenanceEventType;
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::{HashSet, VecDeque};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+pub const REMOTE_ADDRESS: &str = "remote.address";
+pub const BATCH_SIZE: &str = "batch.size";
+pub const COMMUNICATIONS_TIMEOUT: &str = "communications.timeout.ms";
+pub const LISTEN_ADDRESS: &str = "listen.address";
+
+pub const ACK: u8 = 0x06;
+pub const NACK: u8 = 0x15;
+
+// How many committed FlowFile ids the receiver keeps for detecting resends.
+const DEDUPLICATION_WINDOW: usize = 100_000;
+// How long an idle RemoteInputPort trigger waits for a batch to arrive.
+const RECEIVE_WAIT: Duration = Duration::from_millis(10);
+// Listener errors kept for the next trigger to report; later ones are dropped.
+const PROBLEM_BACKLOG: usize = 100;
+
+fn write_batch(writer: &mut impl Write, flowfiles: &[FlowFile]) -> Result<(), CodecError> {
+    writer.write_all(&(flowfiles.len() as u32).to_be_bytes())?;
+    for flowfile in flowfiles {
+        flowfile.encode_into(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_batch(reader: &mut impl Read) -> Result<Vec<FlowFile>, CodecError> {
+    let mut count = [0u8; 4];
+    reader.read_exact(&mut count)?;
+    (0..u32::from_be_bytes(count))
+        .map(|_| FlowFile::decode_from(reader))
+        .collect()
+}
+
+fn remote_address() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        REMOTE_ADDRESS,
+        "host:port of the RemoteInputPort to send to",
+    )
+    .required()
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn batch_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        BATCH_SIZE,
+        "Maximum number of FlowFiles sent per acknowledged batch",
+    )
+    .default_value("100")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 100_000,
+    })
+}
+
+fn batch_size_of(context: &ProcessorContext) -> usize {
+    context
+        .get_property_or_default(&batch_size())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(100)
+}
+
+fn communications_timeout() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        COMMUNICATIONS_TIMEOUT,
+        "How long to wait to connect and for each acknowledgement",
+    )
+    .default_value("5000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: 3_600_000,
+    })
+}
+
+fn listen_address() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        LISTEN_ADDRESS,
+        "host:port to accept site-to-site connections on",
+    )
+    .required()
+    .validator(PropertyValidator::NonEmpty)
+}
+
+/// Sends FlowFiles to a `RemoteInputPort` in another instance. A batch only
+/// leaves this flow once the remote side acknowledges it; otherwise it is
+/// penalized and routed to failure, which is normally looped back for retry.
+pub struct RemoteOutputPort {
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl RemoteOutputPort {
+    pub fn new() -> Self {
+        Self {
+            stream: Mutex::new(None),
+        }
+    }
+
+    fn connect(address: &str, timeout: Duration) -> io::Result<TcpStream> {
+        let mut last_error = io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("cannot resolve {}", address),
+        );
+        for addr in address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(timeout))?;
+                    stream.set_write_timeout(Some(timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn send_batch(
+        &self,
+        address: &str,
+        timeout: Duration,
+        flowfiles: &[FlowFile],
+    ) -> Result<(), CodecError> {
+        let mut cached = self.stream.lock().unwrap();
+        // A cached connection may have been closed by the peer since the last
+        // batch; retry once on a fresh one before giving up.
+        let attempts = if cached.is_some() { 2 } else { 1 };
+        let mut result = Ok(());
+        for _ in 0..attempts {
+            let stream = match cached.take() {
+                Some(stream) => stream,
+                None => Self::connect(address, timeout)?,
+            };
+            result = Self::exchange(&stream, flowfiles);
+            if result.is_ok() {
+                *cached = Some(stream);
+                break;
+            }
+        }
+        result
+    }
+
+    fn exchange(stream: &TcpStream, flowfiles: &[FlowFile]) -> Result<(), CodecError> {
+        write_batch(&mut BufWriter::new(stream), flowfiles)?;
+        let mut status = [0u8; 1];
+        (&*stream).read_exact(&mut status)?;
+        match status[0] {
+            ACK => Ok(()),
+            NACK => Err(CodecError::Io(io::Error::other(
+                "remote side rolled back the batch",
+            ))),
+            other => Err(CodecError::InvalidHeader(format!(
+                "unexpected acknowledgement byte {:#04x}",
+                other
+            ))),
+        }
+    }
+}
+
+impl Default for RemoteOutputPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for RemoteOutputPort {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let address = context
+            .get_property_or_default(&remote_address())
+            .unwrap_or_default()
+            .to_string();
+        let timeout = context
+            .get_property_or_default(&communications_timeout())
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(Duration::from_secs(5));
+
+        let batch = session.get_batch(batch_size_of(context))?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        // The remote side receives copies carrying the Send event; the
+        // originals stay untouched in case they have to be retried.
+        let now = session.clock().now();
+        let outgoing: Vec<FlowFile> = batch
+            .iter()
+            .map(|flowfile| {
+                let mut flowfile = flowfile.clone();
+                let lineage = flowfile.lineage_mut();
+                lineage.record(
+                    ProvenanceEventType::Send,
+                    &context.processor_name,
+                    &address,
+                    now,
+                );
+                lineage.enqueued(now);
+                flowfile
+            })
+            .collect();
+
+        match self.send_batch(&address, timeout, &outgoing) {
+            Ok(()) => {
+                for flowfile in batch {
+                    session.remove(flowfile);
+                }
+            }
+            Err(e) => {
+                session.log(
+                    LogLevel::Warn,
+                    &format!(
+                        "could not send {} FlowFiles to {}: {}",
+                        batch.len(),
+                        address,
+                        e
+                    ),
+                );
+                for flowfile in batch {
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "RemoteOutputPort"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![remote_address(), batch_size(), communications_timeout()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::failure()]
+    }
+
+    fn batch_size(&self, context: &ProcessorContext) -> usize {
+        batch_size_of(context)
+    }
+}
+
+struct IncomingBatch {
+    peer: SocketAddr,
+    flowfiles: Vec<FlowFile>,
+    // Dropped without sending when the session rolls back, which NACKs the batch.
+    ack: Sender<()>,
+}
+
+struct Listener {
+    local_addr: SocketAddr,
+    batches: Mutex<Receiver<IncomingBatch>>,
+    // Errors from the accept and connection threads, which have no session
+    // to report them through.
+    problems: Mutex<Receiver<String>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Listener {
+    fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let (sender, receiver) = mpsc::channel();
+        let (problem_sender, problems) = mpsc::sync_channel(PROBLEM_BACKLOG);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let accept_stopped = stopped.clone();
+        thread::spawn(move || {
+            while !accept_stopped.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, peer)) => {
+                        let (sender, problems) = (sender.clone(), problem_sender.clone());
+                        thread::spawn(move || {
+                            if let Err(e) = serve_connection(stream, peer, sender) {
+                                report(
+                                    &problems,
+                                    format!("site-to-site connection from {} closed: {}", peer, e),
+                                );
+                            }
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(RECEIVE_WAIT),
+                    Err(e) => {
+                        report(
+                            &problem_sender,
+                            format!("site-to-site accept failed: {}", e),
+                        );
+                        thread::sleep(RECEIVE_WAIT);
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            local_addr,
+            batches: Mutex::new(receiver),
+            problems: Mutex::new(problems),
+            stopped,
+        })
+    }
+}
+
+fn report(problems: &SyncSender<String>, problem: String) {
+    let _ = problems.try_send(problem);
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+// Reads batches off one connection, hands each to the processor and answers
+// with its outcome. Returns once the sender disconnects.
+fn serve_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    batches: Sender<IncomingBatch>,
+) -> Result<(), CodecError> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(&stream);
+    loop {
+        if reader.fill_buf()?.is_empty() {
+            return Ok(());
+        }
+        let flowfiles = read_batch(&mut reader)?;
+        let (ack, committed) = mpsc::channel();
+        if batches
+            .send(IncomingBatch {
+                peer,
+                flowfiles,
+                ack,
+            })
+            .is_err()
+        {
+            return Ok(());
+        }
+        let status = if committed.recv().is_ok() { ACK } else { NACK };
+        (&stream).write_all(&[status])?;
+    }
+}
+
+/// Accepts batches from `RemoteOutputPort`s and emits them to success,
+/// acknowledging each batch only after the session that emitted it commits.
+pub struct RemoteInputPort {
+    listener: Mutex<Option<Arc<Listener>>>,
+    committed: Arc<Mutex<RecentIds>>,
+}
+
+impl RemoteInputPort {
+    /// Creates a port that binds `listen.address` on its first trigger.
+    pub fn new() -> Self {
+        Self {
+            listener: Mutex::new(None),
+            committed: Arc::new(Mutex::new(RecentIds::default())),
+        }
+    }
+
+    /// Creates a port that is already listening on `addr`; `listen.address`
+    /// is then not consulted. Bind to port 0 and read `local_addr` to get a
+    /// free port.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let port = Self::new();
+        *port.listener.lock().unwrap() = Some(Arc::new(Listener::bind(addr)?));
+        Ok(port)
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|listener| listener.local_addr)
+    }
+
+    fn listener(&self, context: &ProcessorContext) -> io::Result<Arc<Listener>> {
+        let mut listener = self.listener.lock().unwrap();
+        if let Some(listener) = listener.as_ref() {
+            return Ok(listener.clone());
+        }
+        let descriptor = listen_address();
+        let address = context
+            .get_property_or_default(&descriptor)
+            .unwrap_or_default();
+        let bound = Arc::new(Listener::bind(address)?);
+        *listener = Some(bound.clone());
+        Ok(bound)
+    }
+}
+
+impl Default for RemoteInputPort {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for RemoteInputPort {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let listener = match self.listener(context) {
+            Ok(listener) => listener,
+            Err(e) => return Err(ProcessorError::Retryable(format!("cannot listen: {}", e))),
+        };
+        for problem in listener.problems.lock().unwrap().try_iter() {
+            session.report_bulletin(LogLevel::Warn, &problem);
+        }
+        let Ok(batch) = listener.batches.lock().unwrap().recv_timeout(RECEIVE_WAIT) else {
+            return Ok(());
+        };
+
+        let source = batch.peer.to_string();
+        let mut ids = Vec::new();
+        {
+            let committed = self.committed.lock().unwrap();
+            for flowfile in batch.flowfiles {
+                if committed.contains(&flowfile.id()) {
+                    continue;
+                }
+                ids.push(flowfile.id());
+                let flowfile = session.import(flowfile, &source);
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+        }
+        let committed = self.committed.clone();
+        let ack = batch.ack;
+        session.on_commit(move || {
+            committed.lock().unwrap().extend(ids);
+            let _ = ack.send(());
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "RemoteInputPort"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![listen_address()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+}
+
+#[derive(Default)]
+struct RecentIds {
+    order: VecDeque<Uuid>,
+    ids: HashSet<Uuid>,
+}
+
+impl RecentIds {
+    fn contains(&self, id: &Uuid) -> bool {
+        self.ids.contains(id)
+    }
+
+    fn extend(&mut self, ids: Vec<Uuid>) {
+        for id in ids {
+            if self.ids.insert(id) {
+                self.order.push_back(id);
+            }
+        }
+        while self.order.len() > DEDUPLICATION_WINDOW {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+    use crate::processor::DEFAULT_BATCH_SIZE;
+    use crate::testing::TestRunner;
+    use std::sync::atomic::AtomicUsize;
+
+    struct Generate {
+        remaining: AtomicUsize,
+    }
+
+    impl Processor for Generate {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            for _ in 0..10 {
+                let left = self.remaining.load(Ordering::SeqCst);
+                if left == 0 {
+                    return Ok(());
+                }
+                self.remaining.store(left - 1, Ordering::SeqCst);
+                let mut flowfile = session.create();
+                flowfile.set_content(format!("record {}", left));
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Generate"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    struct Collect {
+        received: Arc<Mutex<Vec<FlowFile>>>,
+    }
+
+    impl Processor for Collect {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            for flowfile in session.get_batch(DEFAULT_BATCH_SIZE)? {
+                self.received.lock().unwrap().push(flowfile.clone());
+                session.remove(flowfile);
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Collect"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            Vec::new()
+        }
+
+        fn batch_size(&self, _context: &ProcessorContext) -> usize {
+            DEFAULT_BATCH_SIZE
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_exactly_once_delivery_between_flows() {
+        let input = Arc::new(RemoteInputPort::bind("127.0.0.1:0").unwrap());
+        let address = input.local_addr().unwrap().to_string();
+        let received = Arc::new(Mutex::new(Vec::new()));
+
+        let mut receiving = FlowDefinition::new();
+        receiving.add_processor(
+            ProcessorNode::from_arc("input", input).with_property(LISTEN_ADDRESS, &address),
+        );
+        receiving.add_processor(ProcessorNode::new(
+            "collect",
+            Collect {
+                received: received.clone(),
+            },
+        ));
+        receiving.add_connection(ConnectionDefinition::new(
+            "to-collect",
+            "input",
+            "success",
+            "collect",
+        ));
+
+        let mut sending = FlowDefinition::new();
+        let generate = Generate {
+            remaining: AtomicUsize::new(1000),
+        };
+        sending.add_processor(ProcessorNode::new("generate", generate));
+        sending.add_processor(
+            ProcessorNode::new("output", RemoteOutputPort::new())
+                .with_property(REMOTE_ADDRESS, &address)
+                .with_property(BATCH_SIZE, "64"),
+        );
+        sending.add_connection(ConnectionDefinition::new(
+            "to-output",
+            "generate",
+            "success",
+            "output",
+        ));
+        sending.add_connection(
+            ConnectionDefinition::new("retry", "output", "failure", "output")
+                .with_backpressure(1000),
+        );
+
+        let mut receiver = FlowController::new(receiving);
+        receiver.start().unwrap();
+        let mut sender = FlowController::new(sending);
+        sender.start().unwrap();
+
+        tokio::time::timeout(Duration::from_secs(20), async {
+            while received.lock().unwrap().len() < 1000 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("not every FlowFile arrived");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        sender.stop().await;
+        receiver.stop().await;
+
+        let received = received.lock().unwrap();
+        let ids: HashSet<Uuid> = received.iter().map(|flowfile| flowfile.id()).collect();
+        assert_eq!(received.len(), 1000);
+        assert_eq!(ids.len(), 1000);
+        let events: Vec<ProvenanceEventType> = received[0]
+            .lineage()
+            .events()
+            .iter()
+            .map(|event| event.event_type)
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                ProvenanceEventType::Create,
+                ProvenanceEventType::Route,
+                ProvenanceEventType::Send,
+                ProvenanceEventType::Receive,
+                ProvenanceEventType::Route
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resent_batch_is_acknowledged_but_not_emitted_twice() {
+        let port = RemoteInputPort::bind("127.0.0.1:0").unwrap();
+        let address = port.local_addr().unwrap();
+        let mut runner = TestRunner::new(port);
+        let batch = vec![
+            FlowFile::with_content("once"),
+            FlowFile::with_content("twice?"),
+        ];
+
+        for _ in 0..2 {
+            let sender = thread::spawn({
+                let batch = batch.clone();
+                move || {
+                    let mut stream = TcpStream::connect(address).unwrap();
+                    write_batch(&mut stream, &batch).unwrap();
+                    let mut status = [0u8; 1];
+                    stream.read_exact(&mut status).unwrap();
+                    status[0]
+                }
+            });
+            while !sender.is_finished() {
+                runner.run(1);
+            }
+            assert_eq!(sender.join().unwrap(), ACK);
+        }
+        runner.assert_transferred("success", 2);
+    }
+
+    #[test]
+    fn test_broken_connection_raises_a_bulletin() {
+        let port = RemoteInputPort::bind("127.0.0.1:0").unwrap();
+        let address = port.local_addr().unwrap();
+        let mut runner = TestRunner::new(port);
+        let mut stream = TcpStream::connect(address).unwrap();
+        // Announces one FlowFile, then hangs up before sending it.
+        stream.write_all(&1u32.to_be_bytes()).unwrap();
+        drop(stream);
+
+        while runner.bulletins().is_empty() {
+            runner.run(1);
+        }
+        let bulletins = runner.bulletins();
+        assert_eq!(bulletins[0].severity, LogLevel::Warn);
+        assert!(bulletins[0]
+            .message
+            .starts_with("site-to-site connection from "));
+        runner.assert_transferred("success", 0);
+    }
+
+    #[test]
+    fn test_unreachable_remote_routes_to_failure() {
+        let unused = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut runner = TestRunner::new(RemoteOutputPort::new());
+        runner.set_property(REMOTE_ADDRESS, &unused.to_string());
+        runner.enqueue("payload", &[]);
+        runner.run(1);
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+    }
+}
@@ -0,0 +1,256 @@
+use crate::expression::predicate::Predicate;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+
+pub const ROUTING_STRATEGY: &str = "routing.strategy";
+
+pub const FIRST_MATCHING: &str = "first.matching";
+pub const ALL_MATCHING: &str = "all.matching";
+
+pub const UNMATCHED: &str = "unmatched";
+
+fn routing_strategy() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        ROUTING_STRATEGY,
+        "Route each FlowFile to the first matching route only, or a clone of it to every matching route",
+    )
+    .default_value(FIRST_MATCHING)
+    .validator(PropertyValidator::allowed_values(&[FIRST_MATCHING, ALL_MATCHING]))
+}
+
+/// Routes FlowFiles by their attributes. Every property other than
+/// `routing.strategy` defines a route: a relationship named after the
+/// property, taken by FlowFiles its expression (see `expression::predicate`)
+/// holds for. Routes are tried in name order; FlowFiles matching none go to
+/// "unmatched". With `all.matching`, a FlowFile matching several routes is
+/// cloned into each of them (see `ProcessSession::transfer_clone`).
+pub struct RouteOnAttribute;
+
+impl RouteOnAttribute {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RouteOnAttribute {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for RouteOnAttribute {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let all_matching =
+            context.get_property_or_default(&routing_strategy()) == Some(ALL_MATCHING);
+        let mut routes = Vec::new();
+        for (name, expression) in context.dynamic_properties(&self.properties()) {
+            match Predicate::parse(expression) {
+                Ok(predicate) => routes.push((Relationship::new(name, expression), predicate)),
+                Err(e) => {
+                    return Err(ProcessorError::Fatal(format!(
+                        "invalid route '{}': {}",
+                        name, e
+                    )))
+                }
+            }
+        }
+
+        for flowfile in batch {
+            let mut matching: Vec<Relationship> = Vec::new();
+            for (relationship, predicate) in &routes {
+                if predicate.matches(&flowfile) {
+                    matching.push(relationship.clone());
+                    if !all_matching {
+                        break;
+                    }
+                }
+            }
+            match matching.as_slice() {
+                [] => session.transfer(flowfile, UNMATCHED),
+                [only] => session.transfer(flowfile, &only.name),
+                several => session.transfer_clone(flowfile, several),
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "RouteOnAttribute"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![routing_strategy()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::new(UNMATCHED, "FlowFiles no route matches")]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+
+    fn dynamic_relationships(&self, context: &ProcessorContext) -> Vec<Relationship> {
+        context
+            .dynamic_properties(&self.properties())
+            .into_iter()
+            .map(|(name, expression)| Relationship::new(name, expression))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+    use crate::processors::stdio::GetStdin;
+    use crate::testing::TestRunner;
+    use crate::validation::validate;
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    fn route(strategy: &str, inputs: &[(&str, &str)]) -> TestRunner {
+        let mut runner = TestRunner::new(RouteOnAttribute::new());
+        runner.set_property(ROUTING_STRATEGY, strategy);
+        runner.set_property("large", "size > 100");
+        runner.set_property("logs", "filename endsWith '.log'");
+        for (content, filename) in inputs {
+            runner.enqueue(
+                *content,
+                &[("filename", filename), ("size", &content.len().to_string())],
+            );
+        }
+        runner.run(1);
+        runner
+    }
+
+    fn names(runner: &TestRunner, relationship: &str) -> Vec<String> {
+        let flowfiles = runner.get_output(relationship);
+        flowfiles
+            .iter()
+            .map(|f| f.get_attribute("filename").unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_first_matching_route() {
+        let big = "x".repeat(200);
+        let runner = route(
+            FIRST_MATCHING,
+            &[(&big, "big.log"), ("hi", "app.log"), ("hi", "notes.txt")],
+        );
+        assert_eq!(names(&runner, "large"), vec!["big.log"]);
+        assert_eq!(names(&runner, "logs"), vec!["app.log"]);
+        assert_eq!(names(&runner, UNMATCHED), vec!["notes.txt"]);
+    }
+
+    #[test]
+    fn test_all_matching_clones() {
+        let big = "x".repeat(200);
+        let runner = route(ALL_MATCHING, &[(&big, "big.log"), ("hi", "app.log")]);
+        assert_eq!(names(&runner, "large"), vec!["big.log"]);
+        assert_eq!(names(&runner, "logs"), vec!["big.log", "app.log"]);
+
+        let large = runner.get_output("large").remove(0);
+        let logs = runner.get_output("logs").remove(0);
+        assert_ne!(large.id(), logs.id());
+        assert!(large.shares_content_with(&logs));
+    }
+
+    #[test]
+    fn test_routes_are_relationships_to_validate() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("route", RouteOnAttribute::new())
+                .with_property("urgent", "priority > 5")
+                .auto_terminate(UNMATCHED),
+        );
+        let errors: Vec<String> = validate(&flow).iter().map(ToString::to_string).collect();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("urgent"), "{}", errors[0]);
+
+        flow.processors[0]
+            .auto_terminated
+            .insert("urgent".to_string());
+        flow.add_processor(
+            ProcessorNode::new("sink", RouteOnAttribute::new()).auto_terminate(UNMATCHED),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "urgent-to-sink",
+            "route",
+            "urgent",
+            "sink",
+        ));
+        assert!(validate(&flow).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_route_is_fatal() {
+        let mut runner = TestRunner::new(RouteOnAttribute::new());
+        runner.set_property("broken", "size >");
+        runner.enqueue("x", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("'broken'"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_per_relationship_metrics() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new(
+            "lines",
+            GetStdin::with_reader(Cursor::new("a\nb\nc\nd\ne\n")),
+        ));
+        flow.add_processor(
+            ProcessorNode::new("route", RouteOnAttribute::new())
+                .with_property(ROUTING_STRATEGY, ALL_MATCHING)
+                .with_property("late", "line.number > 2")
+                .with_property("early", "line.number <= 4")
+                .auto_terminate("late")
+                .auto_terminate("early")
+                .auto_terminate(UNMATCHED),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "lines-to-route",
+            "lines",
+            "success",
+            "route",
+        ));
+        let mut controller = FlowController::new(flow);
+        let summary = controller
+            .run_to_completion(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(summary.completed);
+
+        let route = controller
+            .metrics()
+            .processors
+            .into_iter()
+            .find(|p| p.name == "route")
+            .unwrap();
+        assert_eq!(
+            route.transferred,
+            BTreeMap::from([("early".to_string(), 4), ("late".to_string(), 3)])
+        );
+        assert_eq!(route.processed, 7);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+
+pub const MEDIUM_THRESHOLD: &str = "medium.threshold";
+pub const LARGE_THRESHOLD: &str = "large.threshold";
+
+pub const EMPTY: &str = "empty";
+pub const SMALL: &str = "small";
+pub const MEDIUM: &str = "medium";
+pub const LARGE: &str = "large";
+
+fn medium_threshold() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MEDIUM_THRESHOLD,
+        "Smallest content size, in bytes, routed to medium",
+    )
+    .default_value("1024")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn large_threshold() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        LARGE_THRESHOLD,
+        "Smallest content size, in bytes, routed to large",
+    )
+    .default_value("1048576")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+/// Routes FlowFiles by content size: no content to "empty", less than
+/// `medium.threshold` bytes to "small", less than `large.threshold` to
+/// "medium" and anything else to "large". A FlowFile exactly at a threshold
+/// therefore goes to the larger of the two relationships.
+pub struct RouteOnSize;
+
+impl RouteOnSize {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for RouteOnSize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn threshold(context: &ProcessorContext, descriptor: &PropertyDescriptor) -> u64 {
+    context
+        .get_property_or_default(descriptor)
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+impl Processor for RouteOnSize {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let medium = threshold(context, &medium_threshold());
+        let large = threshold(context, &large_threshold());
+        if medium > large {
+            return Err(ProcessorError::Fatal(format!(
+                "{} ({}) is above {} ({})",
+                MEDIUM_THRESHOLD, medium, LARGE_THRESHOLD, large
+            )));
+        }
+
+        for flowfile in batch {
+            let size = flowfile.size() as u64;
+            let relationship = match size {
+                0 => EMPTY,
+                size if size < medium => SMALL,
+                size if size < large => MEDIUM,
+                _ => LARGE,
+            };
+            session.transfer(flowfile, relationship);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "RouteOnSize"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![medium_threshold(), large_threshold()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(EMPTY, "FlowFiles without content"),
+            Relationship::new(SMALL, "FlowFiles smaller than the medium threshold"),
+            Relationship::new(
+                MEDIUM,
+                "FlowFiles from the medium threshold up to the large one",
+            ),
+            Relationship::new(LARGE, "FlowFiles at or above the large threshold"),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn route(sizes: &[usize]) -> TestRunner {
+        let mut runner = TestRunner::new(RouteOnSize::new());
+        runner.set_property(MEDIUM_THRESHOLD, "10");
+        runner.set_property(LARGE_THRESHOLD, "100");
+        for size in sizes {
+            runner.enqueue(vec![b'x'; *size], &[]);
+        }
+        runner.run(1);
+        runner
+    }
+
+    fn sizes(runner: &TestRunner, relationship: &str) -> Vec<usize> {
+        runner
+            .get_output(relationship)
+            .iter()
+            .map(|f| f.size())
+            .collect()
+    }
+
+    #[test]
+    fn test_threshold_boundaries() {
+        let runner = route(&[1, 9, 10, 11, 99, 100, 5000]);
+        assert_eq!(sizes(&runner, SMALL), vec![1, 9]);
+        assert_eq!(sizes(&runner, MEDIUM), vec![10, 11, 99]);
+        assert_eq!(sizes(&runner, LARGE), vec![100, 5000]);
+        runner.assert_transferred(EMPTY, 0);
+    }
+
+    #[test]
+    fn test_empty_content() {
+        let runner = route(&[0, 0, 3]);
+        assert_eq!(sizes(&runner, EMPTY), vec![0, 0]);
+        assert_eq!(sizes(&runner, SMALL), vec![3]);
+    }
+
+    #[test]
+    fn test_default_thresholds() {
+        let mut runner = TestRunner::new(RouteOnSize::new());
+        runner.enqueue(vec![0u8; 1023], &[]);
+        runner.enqueue(vec![0u8; 1024], &[]);
+        runner.enqueue(vec![0u8; 1 << 20], &[]);
+        runner.run(1);
+        assert_eq!(sizes(&runner, SMALL), vec![1023]);
+        assert_eq!(sizes(&runner, MEDIUM), vec![1024]);
+        assert_eq!(sizes(&runner, LARGE), vec![1 << 20]);
+    }
+
+    #[test]
+    fn test_inverted_thresholds_are_fatal() {
+        let mut runner = TestRunner::new(RouteOnSize::new());
+        runner.set_property(MEDIUM_THRESHOLD, "500");
+        runner.set_property(LARGE_THRESHOLD, "50");
+        runner.enqueue("x", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains(LARGE_THRESHOLD))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+}
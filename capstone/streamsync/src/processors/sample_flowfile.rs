@@ -0,0 +1,259 @@
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const SAMPLING_STRATEGY: &str = "sampling.strategy";
+pub const SAMPLING_PROBABILITY: &str = "sampling.probability";
+pub const SAMPLING_INTERVAL: &str = "sampling.interval";
+pub const RANDOM_SEED: &str = "random.seed";
+
+pub const PROBABILISTIC: &str = "probabilistic";
+pub const INTERVAL: &str = "interval";
+
+pub const SAMPLED: &str = "sampled";
+pub const ORIGINAL: &str = "original";
+
+fn sampling_strategy() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        SAMPLING_STRATEGY,
+        "Pick FlowFiles at random or every Nth one",
+    )
+    .default_value(PROBABILISTIC)
+    .validator(PropertyValidator::allowed_values(&[
+        PROBABILISTIC,
+        INTERVAL,
+    ]))
+}
+
+fn sampling_probability() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        SAMPLING_PROBABILITY,
+        "Chance, from 0.0 to 1.0, that a FlowFile is sampled",
+    )
+    .default_value("0.1")
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn sampling_interval() -> PropertyDescriptor {
+    PropertyDescriptor::new(SAMPLING_INTERVAL, "Sample every Nth FlowFile")
+        .default_value("10")
+        .validator(PropertyValidator::IntRange {
+            min: 1,
+            max: i64::MAX,
+        })
+}
+
+fn random_seed() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        RANDOM_SEED,
+        "Seed for probabilistic sampling; unset seeds from the clock",
+    )
+    .validator(PropertyValidator::IntRange {
+        min: 0,
+        max: i64::MAX,
+    })
+}
+
+// SplitMix64: small, fast and good enough to decide which FlowFiles to tap.
+pub(crate) struct Rng(pub(crate) u64);
+
+impl Rng {
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1), from the top 53 bits.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+struct Sampler {
+    rng: Option<Rng>,
+    // FlowFiles seen in interval mode; every one whose position is a
+    // multiple of the interval is sampled.
+    seen: u64,
+}
+
+/// Routes a subset of FlowFiles to "sampled" and the rest to "original",
+/// e.g. to feed a debugging tap from a busy flow. `probabilistic` samples
+/// each FlowFile independently with `sampling.probability`, reproducibly
+/// when `random.seed` is set; `interval` samples the Nth, 2Nth, ... FlowFile,
+/// counting across triggers.
+pub struct SampleFlowFile {
+    sampler: Mutex<Sampler>,
+}
+
+impl SampleFlowFile {
+    pub fn new() -> Self {
+        Self {
+            sampler: Mutex::new(Sampler { rng: None, seen: 0 }),
+        }
+    }
+}
+
+impl Default for SampleFlowFile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for SampleFlowFile {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let by_interval = context.get_property_or_default(&sampling_strategy()) == Some(INTERVAL);
+        let interval: u64 = context
+            .get_property_or_default(&sampling_interval())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(10);
+        let probability_property = sampling_probability();
+        let probability = context
+            .get_property_or_default(&probability_property)
+            .unwrap_or("0.1");
+        let probability = match probability.trim().parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => p,
+            _ => {
+                return Err(ProcessorError::Fatal(format!(
+                    "{} must be a number from 0.0 to 1.0, got '{}'",
+                    SAMPLING_PROBABILITY, probability
+                )))
+            }
+        };
+
+        let seed = context
+            .get_property_or_default(&random_seed())
+            .and_then(|v| v.trim().parse().ok());
+        let mut sampler = self.sampler.lock().unwrap();
+        let Sampler { rng, seen } = &mut *sampler;
+        let rng = rng.get_or_insert_with(|| {
+            Rng(seed.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos() as u64
+            }))
+        });
+        for flowfile in batch {
+            let sampled = if by_interval {
+                *seen += 1;
+                *seen % interval == 0
+            } else {
+                rng.next_f64() < probability
+            };
+            session.transfer(flowfile, if sampled { SAMPLED } else { ORIGINAL });
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "SampleFlowFile"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            sampling_strategy(),
+            sampling_probability(),
+            sampling_interval(),
+            random_seed(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(SAMPLED, "FlowFiles picked by the sampling strategy"),
+            Relationship::new(ORIGINAL, "Every FlowFile that was not sampled"),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    #[test]
+    fn test_interval_counts_across_triggers() {
+        let mut runner = TestRunner::new(SampleFlowFile::new());
+        runner.set_property(SAMPLING_STRATEGY, INTERVAL);
+        runner.set_property(SAMPLING_INTERVAL, "4");
+        // Three triggers of seven, so the count has to carry over.
+        for trigger in 0..3 {
+            for i in 0..7 {
+                runner.enqueue((trigger * 7 + i + 1).to_string(), &[]);
+            }
+            runner.run(1);
+        }
+        assert_eq!(
+            runner.get_output_text(SAMPLED),
+            vec!["4", "8", "12", "16", "20"]
+        );
+        runner.assert_transferred(ORIGINAL, 16);
+    }
+
+    fn sample_seeded(seed: &str) -> Vec<String> {
+        let mut runner = TestRunner::new(SampleFlowFile::new());
+        runner.set_property(SAMPLING_PROBABILITY, "0.25");
+        runner.set_property(RANDOM_SEED, seed);
+        for i in 0..400 {
+            runner.enqueue(i.to_string(), &[]);
+        }
+        runner.run(4);
+        assert_eq!(
+            runner.get_output(SAMPLED).len() + runner.get_output(ORIGINAL).len(),
+            400
+        );
+        runner.get_output_text(SAMPLED)
+    }
+
+    #[test]
+    fn test_probabilistic_is_deterministic_with_seed() {
+        let first = sample_seeded("42");
+        assert_eq!(first, sample_seeded("42"));
+        assert_ne!(first, sample_seeded("43"));
+        assert!(
+            (70..130).contains(&first.len()),
+            "sampled {} of 400",
+            first.len()
+        );
+    }
+
+    #[test]
+    fn test_probability_bounds() {
+        for (probability, sampled) in [("0", 0), ("1.0", 20)] {
+            let mut runner = TestRunner::new(SampleFlowFile::new());
+            runner.set_property(SAMPLING_PROBABILITY, probability);
+            for _ in 0..20 {
+                runner.enqueue("x", &[]);
+            }
+            runner.run(1);
+            runner.assert_transferred(SAMPLED, sampled);
+        }
+
+        let mut runner = TestRunner::new(SampleFlowFile::new());
+        runner.set_property(SAMPLING_PROBABILITY, "1.5");
+        runner.enqueue("x", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains(SAMPLING_PROBABILITY))
+        );
+    }
+}
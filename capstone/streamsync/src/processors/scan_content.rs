@@ -0,0 +1,299 @@
+//! `ScanContent`: routes FlowFiles on whether their content mentions any
+//! term of a dictionary file.
+
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+pub const DICTIONARY_FILE: &str = "dictionary.file";
+pub const CASE_INSENSITIVE: &str = "case.insensitive";
+
+pub const MATCHED: &str = "matched";
+pub const UNMATCHED: &str = "unmatched";
+pub const MATCHING_TERM: &str = "matching.term";
+
+fn dictionary_file() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DICTIONARY_FILE,
+        "Path of the file listing the terms to look for, one per line",
+    )
+    .required()
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn case_insensitive() -> PropertyDescriptor {
+    PropertyDescriptor::new(CASE_INSENSITIVE, "Match terms regardless of case")
+        .default_value("false")
+        .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+// The terms of one dictionary file, along with how it was loaded so a
+// changed configuration is noticed.
+struct Dictionary {
+    path: String,
+    case_insensitive: bool,
+    // As written in the file, and as compared: lowercased when matching
+    // regardless of case.
+    terms: Vec<(String, Vec<u8>)>,
+}
+
+impl Dictionary {
+    fn load(path: &str, case_insensitive: bool) -> Result<Self, ProcessorError> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            ProcessorError::Retryable(format!("cannot read dictionary {}: {}", path, e))
+        })?;
+        let terms: Vec<(String, Vec<u8>)> = text
+            .lines()
+            .map(str::trim)
+            .filter(|term| !term.is_empty())
+            .map(|term| {
+                let compared = if case_insensitive {
+                    term.to_lowercase()
+                } else {
+                    term.to_string()
+                };
+                (term.to_string(), compared.into_bytes())
+            })
+            .collect();
+        if terms.is_empty() {
+            return Err(ProcessorError::Fatal(format!(
+                "dictionary {} has no terms",
+                path
+            )));
+        }
+        Ok(Self {
+            path: path.to_string(),
+            case_insensitive,
+            terms,
+        })
+    }
+
+    // The term occurring first in `content`; of terms starting at the same
+    // place, the longest.
+    fn find(&self, content: &[u8]) -> Option<&str> {
+        let lowered;
+        let content = if self.case_insensitive {
+            lowered = String::from_utf8_lossy(content).to_lowercase().into_bytes();
+            &lowered[..]
+        } else {
+            content
+        };
+        self.terms
+            .iter()
+            .filter_map(|(term, compared)| {
+                let at = content
+                    .windows(compared.len())
+                    .position(|window| window == compared.as_slice())?;
+                Some((at, std::cmp::Reverse(compared.len()), term.as_str()))
+            })
+            .min()
+            .map(|(_, _, term)| term)
+    }
+}
+
+/// Scans each FlowFile's content for the terms listed in `dictionary.file`,
+/// one per line. FlowFiles mentioning any of them go to "matched" with the
+/// term found in `matching.term`; the rest go to "unmatched". With
+/// `case.insensitive` set, "Gold" in the dictionary also matches "GOLD".
+///
+/// The dictionary is read on the first trigger and kept, so editing the file
+/// has no effect until the processor is reconfigured or restarted.
+pub struct ScanContent {
+    dictionary: Mutex<Option<Arc<Dictionary>>>,
+}
+
+impl ScanContent {
+    pub fn new() -> Self {
+        Self {
+            dictionary: Mutex::new(None),
+        }
+    }
+
+    // The loaded dictionary, reading it first if nothing has been loaded yet
+    // or it was loaded under other properties.
+    fn dictionary(
+        &self,
+        path: &str,
+        case_insensitive: bool,
+    ) -> Result<Arc<Dictionary>, ProcessorError> {
+        let mut loaded = self.dictionary.lock().unwrap();
+        match loaded.as_ref() {
+            Some(dictionary)
+                if dictionary.path == path && dictionary.case_insensitive == case_insensitive =>
+            {
+                Ok(dictionary.clone())
+            }
+            _ => {
+                let dictionary = Arc::new(Dictionary::load(path, case_insensitive)?);
+                *loaded = Some(dictionary.clone());
+                Ok(dictionary)
+            }
+        }
+    }
+}
+
+impl Default for ScanContent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for ScanContent {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let Some(path) = context
+            .get_property_or_default(&dictionary_file())
+            .map(str::to_string)
+        else {
+            return Err(ProcessorError::Fatal(format!(
+                "{} is not set",
+                DICTIONARY_FILE
+            )));
+        };
+        let case_insensitive = context.get_property_or_default(&case_insensitive()) == Some("true");
+        let dictionary = self.dictionary(&path, case_insensitive)?;
+
+        for mut flowfile in batch {
+            let term = dictionary.find(&flowfile.content()?);
+            match term {
+                Some(term) => {
+                    flowfile.put_attribute(MATCHING_TERM, term);
+                    session.transfer(flowfile, MATCHED);
+                }
+                None => session.transfer(flowfile, UNMATCHED),
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ScanContent"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![dictionary_file(), case_insensitive()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(
+                MATCHED,
+                "FlowFiles whose content contains a dictionary term",
+            ),
+            Relationship::new(
+                UNMATCHED,
+                "FlowFiles whose content contains none of the terms",
+            ),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::path::{Path, PathBuf};
+
+    fn dictionary_in(name: &str, terms: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-scan-content-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("terms.txt");
+        fs::write(&path, terms).unwrap();
+        path
+    }
+
+    fn runner_for(path: &Path) -> TestRunner {
+        let mut runner = TestRunner::new(ScanContent::new());
+        runner.set_property(DICTIONARY_FILE, &path.to_string_lossy());
+        runner
+    }
+
+    fn terms(runner: &TestRunner) -> Vec<String> {
+        runner
+            .get_output(MATCHED)
+            .iter()
+            .map(|f| f.get_attribute(MATCHING_TERM).unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_terms_hit_and_miss() {
+        let path = dictionary_in("hit", "gold\n\n  silver  \nfool's gold\nlead\n");
+        let mut runner = runner_for(&path);
+        runner.enqueue("a vein of silver ore", &[]);
+        runner.enqueue("nothing but fool's gold here", &[]);
+        runner.enqueue("lead, then gold", &[]);
+        runner.enqueue("copper and tin", &[]);
+        runner.enqueue("GOLD in capitals", &[]);
+        runner.run(1);
+
+        // The earliest term wins, and the longest of those starting together.
+        assert_eq!(terms(&runner), vec!["silver", "fool's gold", "lead"]);
+        runner.assert_transferred(UNMATCHED, 2);
+        assert_eq!(
+            runner.get_output(UNMATCHED)[0].content().unwrap().as_ref(),
+            b"copper and tin"
+        );
+        assert!(runner.get_output(UNMATCHED)[0]
+            .get_attribute(MATCHING_TERM)
+            .is_none());
+
+        // Loaded once: the file is no longer needed after the first trigger.
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        runner.enqueue("more gold", &[]);
+        runner.run(1);
+        assert_eq!(terms(&runner).last().unwrap(), "gold");
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let path = dictionary_in("case", "Gold\nSILVER\n");
+        let mut runner = runner_for(&path);
+        runner.set_property(CASE_INSENSITIVE, "true");
+        runner.enqueue("GOLD in capitals", &[]);
+        runner.enqueue("sterling silver", &[]);
+        runner.enqueue("bronze", &[]);
+        runner.run(1);
+        // The attribute holds the term as the dictionary spells it.
+        assert_eq!(terms(&runner), vec!["Gold", "SILVER"]);
+        runner.assert_transferred(UNMATCHED, 1);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_missing_dictionary_keeps_flowfiles_queued() {
+        let path = dictionary_in("missing", "gold\n");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let mut runner = runner_for(&path);
+        runner.enqueue("gold", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Retryable(message) if message.contains("terms.txt"))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::flowfile::fragment::mark_fragment;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+
+pub const LINE_SPLIT_COUNT: &str = "line.split.count";
+
+pub const SPLITS: &str = "splits";
+pub const ORIGINAL: &str = "original";
+
+fn line_split_count() -> PropertyDescriptor {
+    PropertyDescriptor::new(LINE_SPLIT_COUNT, "Lines per split")
+        .default_value("1")
+        .validator(PropertyValidator::IntRange {
+            min: 1,
+            max: i64::MAX,
+        })
+}
+
+/// Splits each FlowFile's content into pieces of `line.split.count` lines,
+/// each keeping its line endings, and routes them to "splits" and the
+/// FlowFile itself to "original". Every piece is marked as a fragment (see
+/// `crate::flowfile::fragment`), so `MergeContent` can put them back
+/// together under the original filename. Content without lines produces no
+/// splits.
+pub struct SplitText;
+
+impl SplitText {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for SplitText {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for SplitText {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let Some(flowfile) = session.get()? else {
+            return Ok(());
+        };
+        let lines_per_split = context
+            .get_property_or_default(&line_split_count())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(1);
+
+        let content = flowfile.content()?;
+        let lines: Vec<&[u8]> = content.split_inclusive(|&b| b == b'\n').collect();
+        let chunks: Vec<Vec<u8>> = lines
+            .chunks(lines_per_split)
+            .map(|chunk| chunk.concat())
+            .collect();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut split = session.create_from(&flowfile);
+            split.set_content(chunk.clone());
+            mark_fragment(&flowfile, &mut split, index, chunks.len());
+            session.transfer(split, SPLITS);
+        }
+        drop(content);
+        session.transfer(flowfile, ORIGINAL);
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "SplitText"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![line_split_count()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(SPLITS, "The pieces each FlowFile was split into"),
+            Relationship::new(ORIGINAL, "The FlowFiles that were split"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flowfile::fragment::{
+        FILENAME, FRAGMENT_COUNT, FRAGMENT_INDEX, FRAGMENT_ORIGINAL_FILENAME,
+    };
+    use crate::testing::TestRunner;
+
+    #[test]
+    fn test_splits_by_line_count() {
+        let mut runner = TestRunner::new(SplitText::new());
+        runner.set_property(LINE_SPLIT_COUNT, "2");
+        runner.enqueue("a\nb\nc\nd\ne", &[(FILENAME, "letters.txt")]);
+        runner.run(1);
+
+        runner.assert_transferred(ORIGINAL, 1);
+        let splits = runner.get_output(SPLITS);
+        let contents: Vec<Vec<u8>> = splits
+            .iter()
+            .map(|f| f.content().unwrap().into_owned())
+            .collect();
+        assert_eq!(contents, [&b"a\nb\n"[..], b"c\nd\n", b"e"]);
+        for (index, split) in splits.iter().enumerate() {
+            assert_eq!(
+                split.get_attribute(FRAGMENT_INDEX).unwrap().as_i64(),
+                Some(index as i64)
+            );
+            assert_eq!(
+                split.get_attribute(FRAGMENT_COUNT).unwrap().as_i64(),
+                Some(3)
+            );
+            assert_eq!(
+                split
+                    .get_attribute(FRAGMENT_ORIGINAL_FILENAME)
+                    .unwrap()
+                    .to_string(),
+                "letters.txt"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_content_has_no_splits() {
+        let mut runner = TestRunner::new(SplitText::new());
+        runner.enqueue("", &[]);
+        runner.run(1);
+        runner.assert_transferred(SPLITS, 0);
+        runner.assert_transferred(ORIGINAL, 1);
+    }
+}
@@ -0,0 +1,414 @@
+//! `GetStdin` and `PutStdout` let a flow run as a Unix filter:
+//!
+//! ```text
+//! cat data | streamsync run --once pipeline.yaml > out
+//! ```
+//!
+//! Both read or write the process streams by default; `with_reader` and
+//! `with_writer` swap in any other stream, which is how the tests drive them.
+
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const BATCH_SIZE: &str = "batch.size";
+pub const ATTRIBUTES_AS_JSON: &str = "attributes.as.json";
+
+pub const LINE_NUMBER: &str = "line.number";
+pub const PUT_STDOUT_ERROR: &str = "put.stdout.error";
+
+// How long an idle GetStdin trigger waits for the next line.
+const RECEIVE_WAIT: Duration = Duration::from_millis(10);
+
+fn batch_size() -> PropertyDescriptor {
+    PropertyDescriptor::new(BATCH_SIZE, "Maximum number of lines to emit per trigger")
+        .default_value("100")
+        .validator(PropertyValidator::IntRange {
+            min: 1,
+            max: 100_000,
+        })
+}
+
+fn attributes_as_json() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        ATTRIBUTES_AS_JSON,
+        "Prefix each line with the FlowFile's attributes as a JSON object",
+    )
+    .default_value("false")
+    .validator(PropertyValidator::allowed_values(&["true", "false"]))
+}
+
+// A line read by the reader thread, or the error that ended the input.
+type Line = Result<String, String>;
+
+/// Emits one FlowFile per line of stdin, numbered from 1 in `line.number`,
+/// and reports itself exhausted once the input has ended and every line has
+/// been committed. Lines are read on a background thread started by the
+/// first trigger, so triggers never block on a quiet stream.
+pub struct GetStdin {
+    input: Mutex<Option<Box<dyn BufRead + Send>>>,
+    lines: Mutex<Option<Receiver<Line>>>,
+    // Lines received but not yet committed; a rollback emits them again.
+    pending: Arc<Mutex<VecDeque<(i64, String)>>>,
+    received: Mutex<i64>,
+    ended: AtomicBool,
+}
+
+impl GetStdin {
+    pub fn new() -> Self {
+        Self {
+            input: Mutex::new(None),
+            lines: Mutex::new(None),
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            received: Mutex::new(0),
+            ended: AtomicBool::new(false),
+        }
+    }
+
+    /// Reads `input` instead of the process's stdin.
+    pub fn with_reader(input: impl BufRead + Send + 'static) -> Self {
+        let processor = Self::new();
+        *processor.input.lock().unwrap() = Some(Box::new(input));
+        processor
+    }
+
+    fn spawn_reader(&self) -> Receiver<Line> {
+        let input = self
+            .input
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_else(|| Box::new(BufReader::new(io::stdin())));
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in input.lines() {
+                let line = line.map_err(|e| e.to_string());
+                let failed = line.is_err();
+                if sender.send(line).is_err() || failed {
+                    return;
+                }
+            }
+        });
+        receiver
+    }
+
+    // Moves lines from the reader thread into `pending` until it holds
+    // `wanted`, waiting briefly for the first one if nothing is pending.
+    fn receive(
+        &self,
+        pending: &mut VecDeque<(i64, String)>,
+        wanted: usize,
+    ) -> Result<(), ProcessorError> {
+        let mut lines = self.lines.lock().unwrap();
+        let lines = lines.get_or_insert_with(|| self.spawn_reader());
+        let mut received = self.received.lock().unwrap();
+        while pending.len() < wanted && !self.ended.load(Ordering::SeqCst) {
+            let next = if pending.is_empty() {
+                lines
+                    .recv_timeout(RECEIVE_WAIT)
+                    .map_err(|e| e == RecvTimeoutError::Disconnected)
+            } else {
+                lines
+                    .try_recv()
+                    .map_err(|e| e == TryRecvError::Disconnected)
+            };
+            match next {
+                Ok(Ok(line)) => {
+                    *received += 1;
+                    pending.push_back((*received, line));
+                }
+                Ok(Err(e)) => {
+                    self.ended.store(true, Ordering::SeqCst);
+                    return Err(ProcessorError::Fatal(format!("cannot read stdin: {}", e)));
+                }
+                Err(true) => self.ended.store(true, Ordering::SeqCst),
+                Err(false) => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for GetStdin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for GetStdin {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch_size: usize = context
+            .get_property_or_default(&batch_size())
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100);
+
+        let emitted = {
+            let mut pending = self.pending.lock().unwrap();
+            self.receive(&mut pending, batch_size)?;
+            for (number, line) in pending.iter().take(batch_size) {
+                let mut flowfile = session.create();
+                flowfile.set_content(line.as_bytes());
+                flowfile.set_attribute(LINE_NUMBER, *number);
+                session.transfer(flowfile, relationship::SUCCESS);
+            }
+            pending.len().min(batch_size)
+        };
+        if emitted > 0 {
+            let pending = self.pending.clone();
+            session.on_commit(move || {
+                pending.lock().unwrap().drain(..emitted);
+            });
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "GetStdin"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![batch_size()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.ended.load(Ordering::SeqCst) && self.pending.lock().unwrap().is_empty()
+    }
+}
+
+/// Writes each FlowFile's content followed by a newline to stdout, optionally
+/// prefixed by its attributes as a JSON object and a space.
+pub struct PutStdout {
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl PutStdout {
+    pub fn new() -> Self {
+        Self::with_writer(io::stdout())
+    }
+
+    /// Writes to `output` instead of the process's stdout.
+    pub fn with_writer(output: impl Write + Send + 'static) -> Self {
+        Self {
+            output: Mutex::new(Box::new(output)),
+        }
+    }
+
+    fn write(&self, flowfile: &FlowFile, with_attributes: bool) -> io::Result<()> {
+        let mut line = Vec::with_capacity(flowfile.size() + 1);
+        if with_attributes {
+            let attributes: BTreeMap<_, _> = flowfile.attributes().iter().collect();
+            serde_json::to_writer(&mut line, &attributes)?;
+            line.push(b' ');
+        }
+        line.extend_from_slice(&flowfile.content()?);
+        line.push(b'\n');
+
+        let mut output = self.output.lock().unwrap();
+        output.write_all(&line)?;
+        output.flush()
+    }
+}
+
+impl Default for PutStdout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for PutStdout {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let with_attributes =
+            context.get_property_or_default(&attributes_as_json()) == Some("true");
+        for mut flowfile in session.get_batch(DEFAULT_BATCH_SIZE)? {
+            match self.write(&flowfile, with_attributes) {
+                Ok(()) => session.transfer(flowfile, relationship::SUCCESS),
+                Err(e) => {
+                    flowfile.put_attribute(PUT_STDOUT_ERROR, &e.to_string());
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "PutStdout"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![attributes_as_json()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+    use crate::testing::TestRunner;
+    use std::io::Cursor;
+
+    // A writer whose contents the test can still read after handing it over.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    struct BrokenPipe;
+
+    impl Write for BrokenPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "reader went away",
+            ))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_until_exhausted(runner: &mut TestRunner, processor: &GetStdin) {
+        for _ in 0..100 {
+            if processor.is_exhausted() {
+                return;
+            }
+            runner.run(1);
+        }
+        panic!("input never ran out");
+    }
+
+    #[test]
+    fn test_get_stdin_emits_numbered_lines_then_exhausts() {
+        let processor = Arc::new(GetStdin::with_reader(Cursor::new("alpha\nbeta\r\n\ngamma")));
+        let mut runner = TestRunner::from_arc(processor.clone());
+        runner.set_property(BATCH_SIZE, "3");
+        run_until_exhausted(&mut runner, &processor);
+
+        let lines = runner.get_output(relationship::SUCCESS);
+        let contents: Vec<Vec<u8>> = lines
+            .iter()
+            .map(|f| f.content().unwrap().into_owned())
+            .collect();
+        assert_eq!(contents, [&b"alpha"[..], b"beta", b"", b"gamma"]);
+        let numbers: Vec<i64> = lines
+            .iter()
+            .map(|f| f.get_attribute(LINE_NUMBER).unwrap().as_i64().unwrap())
+            .collect();
+        assert_eq!(numbers, vec![1, 2, 3, 4]);
+        assert!(runner.errors().is_empty());
+    }
+
+    #[test]
+    fn test_put_stdout_writes_lines_with_optional_attributes() {
+        let buffer = SharedBuffer::default();
+        let mut runner = TestRunner::new(PutStdout::with_writer(buffer.clone()));
+        runner.enqueue("plain", &[]);
+        runner.run(1);
+        runner.set_property(ATTRIBUTES_AS_JSON, "true");
+        runner.enqueue("tagged", &[("source", "test"), ("kind", "x")]);
+        runner.run(1);
+
+        runner.assert_transferred(relationship::SUCCESS, 2);
+        let written = buffer.contents();
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines[0], "plain");
+        let (json, content) = lines[1].rsplit_once(' ').unwrap();
+        assert_eq!(content, "tagged");
+        let attributes: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(attributes["source"], "test");
+        assert_eq!(attributes["kind"], "x");
+    }
+
+    #[test]
+    fn test_put_stdout_write_failure() {
+        let mut runner = TestRunner::new(PutStdout::with_writer(BrokenPipe));
+        runner.enqueue("lost", &[]);
+        runner.run(1);
+        runner.assert_transferred(relationship::FAILURE, 1);
+        runner.assert_penalized();
+        let failed = &runner.get_output(relationship::FAILURE)[0];
+        assert!(failed
+            .get_attribute(PUT_STDOUT_ERROR)
+            .unwrap()
+            .to_string()
+            .contains("reader went away"));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_runs_to_completion() {
+        let input: String = (1..=250).map(|i| format!("record {}\n", i)).collect();
+        let buffer = SharedBuffer::default();
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new(
+            "stdin",
+            GetStdin::with_reader(Cursor::new(input.clone())),
+        ));
+        flow.add_processor(
+            ProcessorNode::new("stdout", PutStdout::with_writer(buffer.clone()))
+                .auto_terminate("success")
+                .auto_terminate("failure"),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "stdin-to-stdout",
+            "stdin",
+            "success",
+            "stdout",
+        ));
+
+        let summary = FlowController::new(flow)
+            .run_to_completion(Duration::from_secs(10))
+            .await
+            .unwrap();
+        assert!(summary.completed);
+        assert_eq!(summary.processed["stdin"], 250);
+        assert_eq!(summary.processed["stdout"], 250);
+        assert_eq!(buffer.contents(), input);
+    }
+}
@@ -0,0 +1,375 @@
+//! `TailFileProcessor`: follows a file that keeps growing, such as a log, and
+//! emits the lines appended to it since the last trigger.
+
+use crate::logging::LogLevel;
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub const FILE_TO_TAIL: &str = "file.to.tail";
+pub const MAX_BYTES_PER_TRIGGER: &str = "max.bytes.per.trigger";
+pub const START_POSITION: &str = "start.position";
+
+pub const BEGINNING_OF_FILE: &str = "beginning-of-file";
+pub const END_OF_FILE: &str = "end-of-file";
+
+// Key in the processor's state: bytes of the file already emitted.
+const POSITION: &str = "position";
+
+fn file_to_tail() -> PropertyDescriptor {
+    PropertyDescriptor::new(FILE_TO_TAIL, "Path of the file to follow")
+        .required()
+        .validator(PropertyValidator::NonEmpty)
+}
+
+fn max_bytes_per_trigger() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        MAX_BYTES_PER_TRIGGER,
+        "Most bytes of lines emitted per trigger; a longer line is emitted alone",
+    )
+    .default_value("1048576")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn start_position() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        START_POSITION,
+        "Where to start in a file not tailed before: its beginning, or its current end",
+    )
+    .default_value(BEGINNING_OF_FILE)
+    .validator(PropertyValidator::allowed_values(&[
+        BEGINNING_OF_FILE,
+        END_OF_FILE,
+    ]))
+}
+
+/// Emits the complete lines appended to `file.to.tail` since the last
+/// trigger as one FlowFile, at most `max.bytes.per.trigger` of them; the
+/// rest wait for the next trigger. A line still missing its newline is left
+/// for a later trigger. How far the file has been emitted is kept in the
+/// processor's state, so a restarted flow carries on where it stopped. With
+/// `start.position` at `end-of-file`, a file not tailed before is skipped to
+/// its current end and only what is appended afterwards is emitted. A file
+/// that is now shorter than the position has been truncated or rotated, and
+/// is read again from the start; a missing file is waited for.
+pub struct TailFileProcessor;
+
+impl TailFileProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for TailFileProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Reads the complete lines that follow `position` in `path`, as many as fit
+// in `max` bytes but always at least one, so a line longer than `max` does
+// not hold the file up.
+fn read_lines_after(path: &Path, position: u64, max: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let length = file.metadata()?.len();
+    let mut lines = Vec::new();
+    if length <= position {
+        return Ok(lines);
+    }
+    file.seek(SeekFrom::Start(position))?;
+    let mut reader = BufReader::new(file.take(length - position));
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        reader.read_until(b'\n', &mut line)?;
+        if line.last() != Some(&b'\n') {
+            return Ok(lines);
+        }
+        if !lines.is_empty() && (lines.len() + line.len()) as u64 > max {
+            return Ok(lines);
+        }
+        lines.extend_from_slice(&line);
+    }
+}
+
+impl Processor for TailFileProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let path = PathBuf::from(
+            context
+                .get_property_or_default(&file_to_tail())
+                .unwrap_or_default(),
+        );
+        let state = context
+            .state_manager
+            .get_state(&context.processor_name)
+            .map_err(|e| ProcessorError::Retryable(format!("cannot read state: {}", e)))?;
+        let stored: Option<u64> = state.get(POSITION).and_then(|v| v.parse().ok());
+        let mut position = stored.unwrap_or(0);
+        let max: u64 = context
+            .get_property_or_default(&max_bytes_per_trigger())
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(1_048_576);
+        let from_end = context.get_property_or_default(&start_position()) == Some(END_OF_FILE);
+
+        let length = match fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                // A file that only appears later is new, and read in full.
+                if from_end && stored.is_none() {
+                    store_position(context, session, 0);
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(ProcessorError::Retryable(format!(
+                    "cannot stat {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+        if from_end && stored.is_none() {
+            store_position(context, session, length);
+            return Ok(());
+        }
+        if length < position {
+            session.logger().log(
+                LogLevel::Info,
+                &context.processor_name,
+                &format!(
+                    "{} shrank to {} bytes; reading it from the start",
+                    path.display(),
+                    length
+                ),
+            );
+            position = 0;
+        }
+        let lines = read_lines_after(&path, position, max).map_err(|e| {
+            ProcessorError::Retryable(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        if lines.is_empty() {
+            if position == 0 && state.contains_key(POSITION) {
+                // Nothing new since the truncation; still forget the old offset.
+                store_position(context, session, 0);
+            }
+            return Ok(());
+        }
+
+        let next = position + lines.len() as u64;
+        let mut flowfile = session.create();
+        flowfile.set_content(lines);
+        if let Some(name) = path.file_name() {
+            flowfile.put_attribute("filename", &name.to_string_lossy());
+        }
+        let absolute = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        flowfile.put_attribute("absolute.path", &absolute.to_string_lossy());
+        session.transfer(flowfile, relationship::SUCCESS);
+        // Only move past lines that made it into the flow, so a rolled back
+        // session emits them again.
+        store_position(context, session, next);
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "TailFileProcessor"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![file_to_tail(), max_bytes_per_trigger(), start_position()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+}
+
+fn store_position(context: &ProcessorContext, session: &mut ProcessSession, position: u64) {
+    let state_manager = context.state_manager.clone();
+    let name = context.processor_name.clone();
+    let logger = session.logger();
+    session.on_commit(move || {
+        let state = HashMap::from([(POSITION.to_string(), position.to_string())]);
+        if let Err(e) = state_manager.set_state(&name, state) {
+            logger.log(
+                LogLevel::Error,
+                &name,
+                &format!("cannot store tail position: {}", e),
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{MemoryStateManager, StateManager};
+    use crate::testing::TestRunner;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "streamsync-tail-file-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("app.log")
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    fn runner_for(path: &Path, state_manager: &Arc<MemoryStateManager>) -> TestRunner {
+        let mut runner = TestRunner::new(TailFileProcessor::new());
+        runner.set_property(FILE_TO_TAIL, &path.to_string_lossy());
+        runner.set_state_manager(state_manager.clone());
+        runner
+    }
+
+    fn emitted(runner: &TestRunner) -> Vec<String> {
+        runner
+            .get_output(relationship::SUCCESS)
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_appended_lines_across_triggers() {
+        let path = temp_file("append");
+        let state_manager = Arc::new(MemoryStateManager::new());
+        let mut runner = runner_for(&path, &state_manager);
+
+        // No file yet: nothing to do.
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 0);
+
+        append(&path, "one\ntwo\n");
+        runner.run(2);
+        append(&path, "three\npart");
+        runner.run(1);
+        append(&path, "ial\n");
+        runner.run(1);
+        assert_eq!(emitted(&runner), vec!["one\ntwo\n", "three\n", "partial\n"]);
+        let flowfile = &runner.get_output(relationship::SUCCESS)[0];
+        assert_eq!(
+            flowfile.get_attribute("filename").unwrap().to_string(),
+            "app.log"
+        );
+
+        let state = state_manager
+            .get_state(runner.context().processor_name.as_str())
+            .unwrap();
+        assert_eq!(state[POSITION], "22");
+
+        // A restarted processor picks up where the last one stopped.
+        append(&path, "four\n");
+        let mut restarted = runner_for(&path, &state_manager);
+        restarted.run(1);
+        assert_eq!(emitted(&restarted), vec!["four\n"]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_truncated_file_is_read_from_the_start() {
+        let path = temp_file("truncate");
+        let state_manager = Arc::new(MemoryStateManager::new());
+        let mut runner = runner_for(&path, &state_manager);
+        append(&path, "a fairly long first line\nand a second\n");
+        runner.run(1);
+
+        // Rotated: replaced by a shorter file.
+        fs::write(&path, "fresh\n").unwrap();
+        runner.run(1);
+        append(&path, "more\n");
+        runner.run(1);
+        assert_eq!(
+            emitted(&runner),
+            vec![
+                "a fairly long first line\nand a second\n",
+                "fresh\n",
+                "more\n"
+            ]
+        );
+
+        // Truncated to nothing, then written again.
+        fs::write(&path, "").unwrap();
+        runner.run(1);
+        append(&path, "after\n");
+        runner.run(1);
+        assert_eq!(emitted(&runner).last().unwrap(), "after\n");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_lines_beyond_the_cap_wait_for_the_next_trigger() {
+        let path = temp_file("cap");
+        let state_manager = Arc::new(MemoryStateManager::new());
+        let mut runner = runner_for(&path, &state_manager);
+        runner.set_property(MAX_BYTES_PER_TRIGGER, "6");
+        append(&path, "aa\nbb\ncc\na much longer line\ndd\npartial");
+        runner.run(4);
+        assert_eq!(
+            emitted(&runner),
+            vec!["aa\nbb\n", "cc\n", "a much longer line\n", "dd\n"]
+        );
+        let state = state_manager
+            .get_state(runner.context().processor_name.as_str())
+            .unwrap();
+        assert_eq!(state[POSITION], "31");
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_start_at_end_of_file_skips_existing_lines() {
+        let path = temp_file("end");
+        let state_manager = Arc::new(MemoryStateManager::new());
+        let mut runner = runner_for(&path, &state_manager);
+        runner.set_property(START_POSITION, END_OF_FILE);
+        append(&path, "old\nolder\n");
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 0);
+
+        append(&path, "new\n");
+        runner.run(1);
+        let mut restarted = runner_for(&path, &state_manager);
+        restarted.set_property(START_POSITION, END_OF_FILE);
+        append(&path, "newer\n");
+        restarted.run(1);
+        assert_eq!(emitted(&runner), vec!["new\n"]);
+        assert_eq!(emitted(&restarted), vec!["newer\n"]);
+
+        // A file that did not exist yet is read from its beginning.
+        let missing = temp_file("end-missing");
+        let mut runner = runner_for(&missing, &Arc::new(MemoryStateManager::new()));
+        runner.set_property(START_POSITION, END_OF_FILE);
+        runner.run(1);
+        append(&missing, "first\n");
+        runner.run(1);
+        assert_eq!(emitted(&runner), vec!["first\n"]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        fs::remove_dir_all(missing.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,254 @@
+use crate::clock::Clock;
+use crate::expression;
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::session::ProcessSession;
+use regex::Regex;
+
+pub const DELETE_ATTRIBUTES: &str = "delete.attributes";
+
+/// Prefix of the dynamic properties that rename an attribute rather than
+/// set one: `rename.<old>` holds the new name.
+pub const RENAME_PREFIX: &str = "rename.";
+
+fn delete_attributes() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        DELETE_ATTRIBUTES,
+        "Regular expression; attributes whose whole name matches are removed",
+    )
+    .validator(PropertyValidator::NonEmpty)
+}
+
+/// Sets, renames and deletes FlowFile attributes. Every property other than
+/// `delete.attributes` is dynamic: `rename.<old>` renames the attribute `old`
+/// to the property's value, and any other name is an attribute to set to the
+/// property's value, evaluated as an expression (e.g. `${now()}` or
+/// `${filename}.bak`).
+///
+/// Expressions see the attributes the FlowFile arrived with. Deletes are
+/// applied first, then renames, then sets, so `delete.attributes` can clear
+/// out a namespace that the same processor fills back in. Content is never
+/// touched and every FlowFile goes to success.
+pub struct UpdateAttributeProcessor;
+
+impl UpdateAttributeProcessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for UpdateAttributeProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn update(
+    flowfile: &mut FlowFile,
+    clock: &dyn Clock,
+    delete: Option<&Regex>,
+    renames: &[(&str, &str)],
+    sets: &[(&str, &str)],
+) -> Result<(), ProcessorError> {
+    let mut values = Vec::with_capacity(sets.len());
+    for (name, value) in sets {
+        let value = expression::evaluate(value, flowfile, clock).map_err(|e| {
+            ProcessorError::Fatal(format!("invalid expression for '{}': {}", name, e))
+        })?;
+        values.push((*name, value));
+    }
+    if let Some(delete) = delete {
+        let doomed: Vec<String> = flowfile
+            .attributes()
+            .keys()
+            .filter(|key| delete.is_match(key))
+            .cloned()
+            .collect();
+        for key in doomed {
+            flowfile.remove_attribute(&key);
+        }
+    }
+    for (old, new) in renames {
+        if let Some(value) = flowfile.remove_attribute(old) {
+            flowfile.set_attribute(new, value);
+        }
+    }
+    for (name, value) in values {
+        flowfile.put_attribute(name, &value);
+    }
+    Ok(())
+}
+
+impl Processor for UpdateAttributeProcessor {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        // Anchored so that "tmp" does not also remove "tmpdir".
+        let delete = match context.get_property_or_default(&delete_attributes()) {
+            Some(pattern) => Some(Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+                ProcessorError::Fatal(format!(
+                    "invalid pattern for '{}': {}",
+                    DELETE_ATTRIBUTES, e
+                ))
+            })?),
+            None => None,
+        };
+        let mut renames = Vec::new();
+        let mut sets = Vec::new();
+        for (name, value) in context.dynamic_properties(&self.properties()) {
+            match name.strip_prefix(RENAME_PREFIX) {
+                Some(old) => renames.push((old, value)),
+                None => sets.push((name, value)),
+            }
+        }
+
+        let clock = session.clock();
+        for mut flowfile in batch {
+            update(
+                &mut flowfile,
+                clock.as_ref(),
+                delete.as_ref(),
+                &renames,
+                &sets,
+            )?;
+            session.transfer(flowfile, relationship::SUCCESS);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "UpdateAttributeProcessor"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![delete_attributes()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn attribute(flowfile: &FlowFile, name: &str) -> Option<String> {
+        flowfile.get_attribute(name).map(ToString::to_string)
+    }
+
+    fn update_with(properties: &[(&str, &str)], attributes: &[(&str, &str)]) -> FlowFile {
+        let mut runner = TestRunner::new(UpdateAttributeProcessor::new());
+        for (name, value) in properties {
+            runner.set_property(name, value);
+        }
+        runner.enqueue("body", attributes);
+        runner.run(1);
+        runner.assert_transferred(relationship::SUCCESS, 1);
+        runner.get_output(relationship::SUCCESS).remove(0)
+    }
+
+    #[test]
+    fn test_adds_computed_attributes() {
+        let before = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let flowfile = update_with(
+            &[
+                ("backup.name", "${filename}.bak"),
+                ("updated.at", "${now()}"),
+            ],
+            &[("filename", "data.csv")],
+        );
+        assert_eq!(
+            attribute(&flowfile, "backup.name").as_deref(),
+            Some("data.csv.bak")
+        );
+        let updated_at: u128 = attribute(&flowfile, "updated.at").unwrap().parse().unwrap();
+        assert!(updated_at >= before);
+        assert_eq!(
+            attribute(&flowfile, "filename").as_deref(),
+            Some("data.csv")
+        );
+        assert_eq!(flowfile.content().unwrap().as_ref(), b"body");
+    }
+
+    #[test]
+    fn test_deletes_by_regex() {
+        let flowfile = update_with(
+            &[(DELETE_ATTRIBUTES, r"tmp\..*|scratch")],
+            &[
+                ("tmp.a", "1"),
+                ("tmp.b", "2"),
+                ("scratch", "3"),
+                ("scratchpad", "4"),
+                ("filename", "f"),
+            ],
+        );
+        assert_eq!(attribute(&flowfile, "tmp.a"), None);
+        assert_eq!(attribute(&flowfile, "tmp.b"), None);
+        assert_eq!(attribute(&flowfile, "scratch"), None);
+        assert_eq!(attribute(&flowfile, "scratchpad").as_deref(), Some("4"));
+        assert_eq!(attribute(&flowfile, "filename").as_deref(), Some("f"));
+    }
+
+    #[test]
+    fn test_renames_and_preserves_unrelated() {
+        let flowfile = update_with(
+            &[("rename.user", "user.name"), ("rename.absent", "never")],
+            &[("user", "ana"), ("level", "INFO")],
+        );
+        assert_eq!(attribute(&flowfile, "user"), None);
+        assert_eq!(attribute(&flowfile, "user.name").as_deref(), Some("ana"));
+        assert_eq!(attribute(&flowfile, "never"), None);
+        assert_eq!(attribute(&flowfile, "level").as_deref(), Some("INFO"));
+    }
+
+    #[test]
+    fn test_sets_survive_deletes() {
+        let flowfile = update_with(
+            &[
+                (DELETE_ATTRIBUTES, r"http\..*"),
+                ("http.status", "${http.status}-seen"),
+            ],
+            &[("http.status", "200"), ("http.method", "GET")],
+        );
+        assert_eq!(
+            attribute(&flowfile, "http.status").as_deref(),
+            Some("200-seen")
+        );
+        assert_eq!(attribute(&flowfile, "http.method"), None);
+    }
+
+    #[test]
+    fn test_invalid_pattern_is_fatal() {
+        let mut runner = TestRunner::new(UpdateAttributeProcessor::new());
+        runner.set_property(DELETE_ATTRIBUTES, "(unclosed");
+        runner.enqueue("", &[]);
+        runner.run(1);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains(DELETE_ATTRIBUTES))
+        );
+        assert_eq!(runner.queue_size(), 1);
+    }
+}
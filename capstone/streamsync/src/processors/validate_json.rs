@@ -0,0 +1,272 @@
+//! `ValidateJson`: checks that content is JSON, optionally that it carries a
+//! set of fields, and, with the `json-schema` feature, that it matches a JSON
+//! Schema.
+
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::Relationship;
+use crate::session::ProcessSession;
+use serde_json::Value;
+
+pub const REQUIRED_FIELDS: &str = "required.fields";
+pub const SCHEMA: &str = "schema";
+
+pub const VALID: &str = "valid";
+pub const INVALID: &str = "invalid";
+pub const VALIDATION_ERROR: &str = "validation.error";
+
+fn required_fields() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        REQUIRED_FIELDS,
+        "Comma-separated dot-paths that must exist, e.g. user.id,items.0.sku",
+    )
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn schema() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        SCHEMA,
+        "JSON Schema the content must match; needs the json-schema feature",
+    )
+    .validator(PropertyValidator::NonEmpty)
+}
+
+/// Parses each FlowFile's content as JSON and routes it to "valid" or
+/// "invalid". Invalid FlowFiles carry the reason in `validation.error`: the
+/// parse error with its byte offset, the first missing required path, or the
+/// first schema violation. Checks run in that order and stop at the first
+/// failure.
+pub struct ValidateJson;
+
+impl ValidateJson {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for ValidateJson {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// serde_json reports 1-based line and column; turn that back into the byte
+// offset into the content.
+fn byte_offset(content: &[u8], line: usize, column: usize) -> usize {
+    let line_start: usize = content
+        .split(|b| *b == b'\n')
+        .take(line.saturating_sub(1))
+        .map(|l| l.len() + 1)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(content.len())
+}
+
+// Follows `path` through objects by key and arrays by index.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(fields) => fields.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+}
+
+#[cfg(feature = "json-schema")]
+type Schema = jsonschema::Validator;
+
+#[cfg(feature = "json-schema")]
+fn compile_schema(text: &str) -> Result<Schema, ProcessorError> {
+    let schema: Value = serde_json::from_str(text)
+        .map_err(|e| ProcessorError::Fatal(format!("{} is not JSON: {}", SCHEMA, e)))?;
+    jsonschema::validator_for(&schema)
+        .map_err(|e| ProcessorError::Fatal(format!("invalid {}: {}", SCHEMA, e)))
+}
+
+#[cfg(not(feature = "json-schema"))]
+enum Schema {}
+
+#[cfg(not(feature = "json-schema"))]
+fn compile_schema(_text: &str) -> Result<Schema, ProcessorError> {
+    Err(ProcessorError::Fatal(format!(
+        "{} needs streamsync built with the json-schema feature",
+        SCHEMA
+    )))
+}
+
+#[cfg(feature = "json-schema")]
+fn check_schema(schema: &Schema, document: &Value) -> Result<(), String> {
+    schema.validate(document).map_err(|e| {
+        let path = e.instance_path.as_str();
+        format!("{} (at {})", e, if path.is_empty() { "/" } else { path })
+    })
+}
+
+#[cfg(not(feature = "json-schema"))]
+fn check_schema(schema: &Schema, _document: &Value) -> Result<(), String> {
+    match *schema {}
+}
+
+fn validate(content: &[u8], required: &[&str], schema: Option<&Schema>) -> Result<(), String> {
+    let document: Value = serde_json::from_slice(content).map_err(|e| {
+        format!(
+            "{} (byte {})",
+            e,
+            byte_offset(content, e.line(), e.column())
+        )
+    })?;
+    if let Some(path) = required
+        .iter()
+        .find(|path| lookup(&document, path).is_none())
+    {
+        return Err(format!("missing required field '{}'", path));
+    }
+    match schema {
+        Some(schema) => check_schema(schema, &document),
+        None => Ok(()),
+    }
+}
+
+impl Processor for ValidateJson {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let required_fields = required_fields();
+        let required: Vec<&str> = context
+            .get_property_or_default(&required_fields)
+            .map(|fields| {
+                fields
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|f| !f.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let schema = context
+            .get_property_or_default(&schema())
+            .map(compile_schema)
+            .transpose()?;
+
+        for mut flowfile in batch {
+            let outcome = validate(&flowfile.content()?, &required, schema.as_ref());
+            match outcome {
+                Ok(()) => session.transfer(flowfile, VALID),
+                Err(message) => {
+                    flowfile.put_attribute(VALIDATION_ERROR, &message);
+                    session.transfer(flowfile, INVALID);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "ValidateJson"
+    }
+
+    fn supports_concurrent_tasks(&self) -> bool {
+        true
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![required_fields(), schema()]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::new(VALID, "JSON that passed every configured check"),
+            Relationship::new(INVALID, "Content that is not JSON or failed a check"),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestRunner;
+
+    fn validate_one(content: &str, properties: &[(&str, &str)]) -> TestRunner {
+        let mut runner = TestRunner::new(ValidateJson::new());
+        for (name, value) in properties {
+            runner.set_property(name, value);
+        }
+        runner.enqueue(content, &[]);
+        runner.run(1);
+        runner
+    }
+
+    fn error(runner: &TestRunner) -> String {
+        runner.get_output(INVALID)[0]
+            .get_attribute(VALIDATION_ERROR)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_malformed_json() {
+        let runner = validate_one("{\"id\": 1,\n \"name\": }", &[]);
+        runner.assert_transferred(INVALID, 1);
+        let message = error(&runner);
+        assert!(message.starts_with("expected value"), "{}", message);
+        assert!(message.ends_with("(byte 19)"), "{}", message);
+    }
+
+    #[test]
+    fn test_missing_required_path() {
+        let runner = validate_one(
+            r#"{"user": {"id": 7}, "items": [{"sku": "a"}]}"#,
+            &[(REQUIRED_FIELDS, "user.id, items.0.sku, items.1.sku")],
+        );
+        runner.assert_transferred(INVALID, 1);
+        assert_eq!(error(&runner), "missing required field 'items.1.sku'");
+    }
+
+    #[test]
+    fn test_passing_document() {
+        let runner = validate_one(
+            r#"{"user": {"id": 7}, "items": [{"sku": "a"}]}"#,
+            &[(REQUIRED_FIELDS, "user.id,items.0.sku")],
+        );
+        runner.assert_transferred(VALID, 1);
+        assert!(runner.get_output(VALID)[0]
+            .get_attribute(VALIDATION_ERROR)
+            .is_none());
+    }
+
+    #[test]
+    fn test_byte_offset() {
+        assert_eq!(byte_offset(b"ab\ncd\nef", 1, 2), 1);
+        assert_eq!(byte_offset(b"ab\ncd\nef", 3, 1), 6);
+        assert_eq!(byte_offset(b"ab", 1, 9), 2);
+    }
+
+    #[cfg(feature = "json-schema")]
+    #[test]
+    fn test_schema_validation() {
+        let schema =
+            r#"{"type": "object", "properties": {"age": {"type": "integer", "minimum": 0}}}"#;
+        let runner = validate_one(r#"{"age": -3}"#, &[(SCHEMA, schema)]);
+        runner.assert_transferred(INVALID, 1);
+        assert!(error(&runner).ends_with("(at /age)"), "{}", error(&runner));
+        validate_one(r#"{"age": 30}"#, &[(SCHEMA, schema)]).assert_transferred(VALID, 1);
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    #[test]
+    fn test_schema_needs_feature() {
+        let runner = validate_one("{}", &[(SCHEMA, "{}")]);
+        assert!(
+            matches!(&runner.errors()[0], ProcessorError::Fatal(message) if message.contains("json-schema"))
+        );
+    }
+}
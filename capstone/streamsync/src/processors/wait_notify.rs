@@ -0,0 +1,666 @@
+//! `Notify` and `Wait`: hold FlowFiles back until another branch of the flow
+//! says they may go, e.g. to release a file only once all its parts have
+//! been written elsewhere. The two meet in a [`SignalStore`], a controller
+//! service found by name, so any number of `Notify` and `Wait` processors
+//! configured with the same `signal.store` see the same counts. Signals are
+//! keyed by the `correlation.id` attribute unless `release.signal.identifier`
+//! says otherwise, so fan-in is a matter of giving the FlowFile that waits and
+//! the ones that notify the same correlation id.
+
+use crate::clock::{Clock, SystemClock};
+use crate::expression;
+use crate::flowfile::FlowFile;
+use crate::processor::{Processor, ProcessorError, DEFAULT_BATCH_SIZE};
+use crate::processor_context::ProcessorContext;
+use crate::property::{PropertyDescriptor, PropertyValidator};
+use crate::relationship::{self, Relationship};
+use crate::service::ControllerService;
+use crate::session::ProcessSession;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const RELEASE_SIGNAL_IDENTIFIER: &str = "release.signal.identifier";
+pub const SIGNAL_STORE: &str = "signal.store";
+pub const SIGNAL_COUNTER_DELTA: &str = "signal.counter.delta";
+pub const TARGET_SIGNAL_COUNT: &str = "target.signal.count";
+pub const EXPIRATION_DURATION: &str = "expiration.duration.ms";
+
+pub const EXPIRED: &str = "expired";
+
+/// Set by `Wait` when it first holds a FlowFile back, in milliseconds since
+/// the epoch.
+pub const WAIT_START_TIMESTAMP: &str = "wait.start.timestamp";
+pub const SIGNAL_ERROR: &str = "signal.error";
+
+pub const DEFAULT_SIGNAL_STORE: &str = "signal-store";
+pub const DEFAULT_RELEASE_SIGNAL_IDENTIFIER: &str = "${correlation.id}";
+
+// How long `Wait` yields after holding FlowFiles back, so it does not spin
+// on a queue of FlowFiles that are all still waiting.
+const WAIT_YIELD: Duration = Duration::from_millis(100);
+
+fn release_signal_identifier() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        RELEASE_SIGNAL_IDENTIFIER,
+        "Expression evaluated against each FlowFile giving the key of its signal",
+    )
+    .default_value(DEFAULT_RELEASE_SIGNAL_IDENTIFIER)
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn signal_store() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        SIGNAL_STORE,
+        "Name of the signal store controller service, created on first use",
+    )
+    .default_value(DEFAULT_SIGNAL_STORE)
+    .validator(PropertyValidator::NonEmpty)
+}
+
+fn signal_counter_delta() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        SIGNAL_COUNTER_DELTA,
+        "How much each FlowFile adds to its signal's count",
+    )
+    .default_value("1")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn target_signal_count() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        TARGET_SIGNAL_COUNT,
+        "Signal count a FlowFile waits for; releasing it takes that many off the count",
+    )
+    .default_value("1")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+fn expiration_duration() -> PropertyDescriptor {
+    PropertyDescriptor::new(
+        EXPIRATION_DURATION,
+        "How long a FlowFile may wait before it is routed to expired",
+    )
+    .default_value("600000")
+    .validator(PropertyValidator::IntRange {
+        min: 1,
+        max: i64::MAX,
+    })
+}
+
+/// Signal counts by key, shared by `Notify` and `Wait`.
+#[derive(Debug, Default)]
+pub struct SignalStore {
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl SignalStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `delta` to the count for `key`.
+    pub fn notify(&self, key: &str, delta: u64) {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(key.to_string()).or_default();
+        *count = count.saturating_add(delta);
+    }
+
+    pub fn count(&self, key: &str) -> u64 {
+        self.counts.lock().unwrap().get(key).copied().unwrap_or(0)
+    }
+
+    /// Takes `n` off the count for `key` if it has at least that many,
+    /// forgetting the key once it reaches zero. The check and the decrement
+    /// happen under one lock, so of two `Wait`s racing for the same signals
+    /// only one gets them.
+    pub fn try_consume(&self, key: &str, n: u64) -> bool {
+        let mut counts = self.counts.lock().unwrap();
+        match counts.get_mut(key) {
+            Some(count) if *count >= n => {
+                *count -= n;
+                if *count == 0 {
+                    counts.remove(key);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl ControllerService for SignalStore {
+    fn get_name(&self) -> &'static str {
+        "SignalStore"
+    }
+}
+
+fn store(context: &ProcessorContext) -> Result<Arc<SignalStore>, ProcessorError> {
+    let descriptor = signal_store();
+    let name = context
+        .get_property_or_default(&descriptor)
+        .unwrap_or(DEFAULT_SIGNAL_STORE);
+    context
+        .services
+        .get_or_insert_with(name, SignalStore::new)
+        .ok_or_else(|| {
+            ProcessorError::Fatal(format!(
+                "controller service '{}' is not a SignalStore",
+                name
+            ))
+        })
+}
+
+fn identifier(context: &ProcessorContext) -> String {
+    let descriptor = release_signal_identifier();
+    context
+        .get_property_or_default(&descriptor)
+        .unwrap_or(DEFAULT_RELEASE_SIGNAL_IDENTIFIER)
+        .to_string()
+}
+
+fn positive(
+    context: &ProcessorContext,
+    descriptor: PropertyDescriptor,
+) -> Result<u64, ProcessorError> {
+    let value = context.get_property_or_default(&descriptor).unwrap_or("1");
+    match value.trim().parse::<u64>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(ProcessorError::Fatal(format!(
+            "'{}' must be a positive whole number, got '{}'",
+            descriptor.name, value
+        ))),
+    }
+}
+
+fn signal_key(identifier: &str, flowfile: &FlowFile, clock: &dyn Clock) -> Result<String, String> {
+    match expression::evaluate(identifier, flowfile, clock) {
+        Ok(key) if !key.trim().is_empty() => Ok(key.trim().to_string()),
+        Ok(_) => Err("release signal identifier is empty".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn fail(session: &mut ProcessSession, mut flowfile: FlowFile, message: &str) {
+    flowfile.put_attribute(SIGNAL_ERROR, message);
+    let flowfile = session.penalize(flowfile);
+    session.transfer(flowfile, relationship::FAILURE);
+}
+
+/// Adds `signal.counter.delta` to the signal keyed by each FlowFile's
+/// `release.signal.identifier`, then passes the FlowFile on to success. The
+/// counts change only once the session commits, so a rolled back trigger
+/// releases nothing.
+pub struct Notify;
+
+impl Notify {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for Notify {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Processor for Notify {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let identifier = identifier(context);
+        let delta = positive(context, signal_counter_delta())?;
+        let store = store(context)?;
+        let clock = session.clock();
+
+        let mut signals: Vec<String> = Vec::new();
+        for flowfile in batch {
+            match signal_key(&identifier, &flowfile, clock.as_ref()) {
+                Ok(key) => {
+                    signals.push(key);
+                    session.transfer(flowfile, relationship::SUCCESS);
+                }
+                Err(message) => fail(session, flowfile, &message),
+            }
+        }
+        session.on_commit(move || {
+            for key in signals {
+                store.notify(&key, delta);
+            }
+        });
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Notify"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            release_signal_identifier(),
+            signal_store(),
+            signal_counter_delta(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![Relationship::success(), Relationship::failure()]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+/// Holds each FlowFile back until the signal keyed by its
+/// `release.signal.identifier` has reached `target.signal.count`, then
+/// routes it to success and takes the target off the count, so every
+/// FlowFile needs a full set of signals of its own. The count is taken as the
+/// FlowFile is released and given back if the session rolls back. FlowFiles
+/// still waiting
+/// after `expiration.duration.ms` go to expired instead. Waiting FlowFiles
+/// are put back on the incoming queue with `wait.start.timestamp` set.
+pub struct Wait {
+    clock: Arc<dyn Clock>,
+}
+
+impl Wait {
+    pub fn new() -> Self {
+        Self {
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl Default for Wait {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn epoch_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+impl Processor for Wait {
+    fn on_trigger(
+        &self,
+        context: &ProcessorContext,
+        session: &mut ProcessSession,
+    ) -> Result<(), ProcessorError> {
+        let batch = session.get_batch(DEFAULT_BATCH_SIZE)?;
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let identifier = identifier(context);
+        let target = positive(context, target_signal_count())?;
+        let expiration = positive(context, expiration_duration())? as i64;
+        let store = store(context)?;
+        let now = epoch_millis(self.clock.now());
+        let clock = session.clock();
+
+        // Counts taken off the store in this trigger, refunded on rollback.
+        let mut released: HashMap<String, u64> = HashMap::new();
+        let mut waiting = false;
+        for mut flowfile in batch {
+            let key = match signal_key(&identifier, &flowfile, clock.as_ref()) {
+                Ok(key) => key,
+                Err(message) => {
+                    fail(session, flowfile, &message);
+                    continue;
+                }
+            };
+            if store.try_consume(&key, target) {
+                *released.entry(key).or_default() += target;
+                flowfile.remove_attribute(WAIT_START_TIMESTAMP);
+                session.transfer(flowfile, relationship::SUCCESS);
+                continue;
+            }
+            let started = flowfile
+                .get_attribute(WAIT_START_TIMESTAMP)
+                .and_then(|value| value.as_i64());
+            match started {
+                Some(started) if now - started >= expiration => session.transfer(flowfile, EXPIRED),
+                Some(_) => {
+                    waiting = true;
+                    session.requeue(flowfile);
+                }
+                None => {
+                    flowfile.set_attribute(WAIT_START_TIMESTAMP, now);
+                    waiting = true;
+                    session.requeue(flowfile);
+                }
+            }
+        }
+        if !released.is_empty() {
+            session.on_rollback(move || {
+                for (key, n) in released {
+                    store.notify(&key, n);
+                }
+            });
+        }
+        if waiting {
+            session.yield_for(WAIT_YIELD);
+        }
+        Ok(())
+    }
+
+    fn get_name(&self) -> &'static str {
+        "Wait"
+    }
+
+    fn properties(&self) -> Vec<PropertyDescriptor> {
+        vec![
+            release_signal_identifier(),
+            signal_store(),
+            target_signal_count(),
+            expiration_duration(),
+        ]
+    }
+
+    fn relationships(&self) -> Vec<Relationship> {
+        vec![
+            Relationship::success(),
+            Relationship::new(EXPIRED, "FlowFiles whose signal did not arrive in time"),
+            Relationship::failure(),
+        ]
+    }
+
+    fn batch_size(&self, _context: &ProcessorContext) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::connection::{Connection, MemoryConnection};
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+    use crate::service::ControllerServices;
+    use crate::testing::TestRunner;
+    use futures::executor::block_on;
+    use std::collections::HashSet;
+
+    fn notifier(services: &Arc<ControllerServices>) -> TestRunner {
+        let mut runner = TestRunner::new(Notify::new());
+        runner.set_property(RELEASE_SIGNAL_IDENTIFIER, "${batch}");
+        runner.set_services(services.clone());
+        runner
+    }
+
+    fn waiter(
+        services: &Arc<ControllerServices>,
+        clock: &Arc<MockClock>,
+        target: &str,
+    ) -> TestRunner {
+        let mut runner = TestRunner::new(Wait::new().with_clock(clock.clone()));
+        runner.set_property(RELEASE_SIGNAL_IDENTIFIER, "${batch}");
+        runner.set_property(TARGET_SIGNAL_COUNT, target);
+        runner.set_property(EXPIRATION_DURATION, "1000");
+        runner.set_services(services.clone());
+        runner
+    }
+
+    fn signal_count(services: &ControllerServices, key: &str) -> u64 {
+        services
+            .get::<SignalStore>(DEFAULT_SIGNAL_STORE)
+            .map_or(0, |store| store.count(key))
+    }
+
+    #[test]
+    fn test_release_on_exact_count() {
+        let services = Arc::new(ControllerServices::new());
+        let clock = Arc::new(MockClock::default());
+        let mut notify = notifier(&services);
+        let mut wait = waiter(&services, &clock, "3");
+        wait.enqueue("merged", &[("batch", "b-1")]);
+
+        for part in 0..2 {
+            notify.enqueue(format!("part {}", part), &[("batch", "b-1")]);
+        }
+        notify.run(1);
+        notify.assert_transferred(relationship::SUCCESS, 2);
+        wait.run(1);
+        wait.assert_transferred(relationship::SUCCESS, 0);
+        assert_eq!(wait.queue_size(), 1);
+
+        notify.enqueue("part 2", &[("batch", "b-1")]);
+        notify.run(1);
+        assert_eq!(signal_count(&services, "b-1"), 3);
+        wait.run(1);
+        wait.assert_transferred(relationship::SUCCESS, 1);
+        assert_eq!(wait.queue_size(), 0);
+        assert!(wait.get_output(relationship::SUCCESS)[0]
+            .get_attribute(WAIT_START_TIMESTAMP)
+            .is_none());
+        assert_eq!(signal_count(&services, "b-1"), 0);
+    }
+
+    #[test]
+    fn test_each_release_takes_a_full_count() {
+        let services = Arc::new(ControllerServices::new());
+        let clock = Arc::new(MockClock::default());
+        let mut notify = notifier(&services);
+        notify.set_property(SIGNAL_COUNTER_DELTA, "3");
+        let mut wait = waiter(&services, &clock, "2");
+        wait.enqueue("first", &[("batch", "b-1")]);
+        wait.enqueue("second", &[("batch", "b-1")]);
+        wait.enqueue("other", &[("batch", "b-2")]);
+
+        notify.enqueue("done", &[("batch", "b-1")]);
+        notify.run(1);
+        wait.run(1);
+        wait.assert_transferred(relationship::SUCCESS, 1);
+        assert_eq!(
+            wait.get_output(relationship::SUCCESS)[0]
+                .content()
+                .unwrap()
+                .as_ref(),
+            b"first"
+        );
+        assert_eq!(wait.queue_size(), 2);
+        assert_eq!(signal_count(&services, "b-1"), 1);
+    }
+
+    #[test]
+    fn test_try_consume_is_all_or_nothing() {
+        let store = Arc::new(SignalStore::new());
+        store.notify("b-1", 5);
+        assert!(!store.try_consume("b-1", 6));
+        assert_eq!(store.count("b-1"), 5);
+        assert!(!store.try_consume("unknown", 1));
+
+        // Eight racers for five signals: exactly five win.
+        let racers: Vec<_> = (0..8)
+            .map(|_| {
+                let store = store.clone();
+                std::thread::spawn(move || store.try_consume("b-1", 1))
+            })
+            .collect();
+        let won = racers
+            .into_iter()
+            .map(|racer| racer.join().unwrap())
+            .filter(|&won| won)
+            .count();
+        assert_eq!(won, 5);
+        assert!(store.counts.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rollback_gives_the_count_back() {
+        let services = Arc::new(ControllerServices::new());
+        let clock = Arc::new(MockClock::default());
+        let wait = waiter(&services, &clock, "2");
+        let store = services.get_or_insert_with(DEFAULT_SIGNAL_STORE, SignalStore::new);
+        store.unwrap().notify("b-1", 3);
+
+        let input = Arc::new(MemoryConnection::new());
+        let mut flowfile = FlowFile::with_content("held");
+        flowfile.put_attribute("batch", "b-1");
+        block_on(input.send(flowfile)).unwrap();
+        let mut session = ProcessSession::new("wait", vec![input], HashMap::new(), HashSet::new());
+        block_on(session.prefetch(1));
+        Wait::new()
+            .with_clock(clock)
+            .on_trigger(wait.context(), &mut session)
+            .unwrap();
+        assert_eq!(signal_count(&services, "b-1"), 1);
+        block_on(session.rollback());
+        assert_eq!(signal_count(&services, "b-1"), 3);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let services = Arc::new(ControllerServices::new());
+        let clock = Arc::new(MockClock::new(
+            UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        ));
+        let mut wait = waiter(&services, &clock, "1");
+        wait.enqueue("stranded", &[("batch", "b-9")]);
+
+        wait.run(1);
+        clock.advance(Duration::from_millis(999));
+        wait.run(1);
+        wait.assert_transferred(EXPIRED, 0);
+        assert_eq!(wait.queue_size(), 1);
+
+        clock.advance(Duration::from_millis(1));
+        wait.run(1);
+        wait.assert_transferred(EXPIRED, 1);
+        wait.assert_transferred(relationship::SUCCESS, 0);
+        let expired = &wait.get_output(EXPIRED)[0];
+        assert_eq!(
+            expired
+                .get_attribute(WAIT_START_TIMESTAMP)
+                .and_then(|v| v.as_i64()),
+            Some(1_700_000_000_000)
+        );
+    }
+
+    #[test]
+    fn test_empty_identifier_fails() {
+        let services = Arc::new(ControllerServices::new());
+        let mut notify = notifier(&services);
+        notify.enqueue("no batch", &[]);
+        notify.run(1);
+        notify.assert_transferred(relationship::FAILURE, 1);
+        notify.assert_penalized();
+        assert!(services
+            .get::<SignalStore>(DEFAULT_SIGNAL_STORE)
+            .unwrap()
+            .counts
+            .lock()
+            .unwrap()
+            .is_empty());
+    }
+
+    // Never triggers anything of its own; the flow test feeds and drains its
+    // connections by hand.
+    struct Idle;
+
+    impl Processor for Idle {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "Idle"
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    fn correlated(content: &str, id: &str) -> FlowFile {
+        let mut flowfile = FlowFile::with_content(content);
+        flowfile.put_attribute("correlation.id", id);
+        flowfile
+    }
+
+    #[tokio::test]
+    async fn test_flow_releases_held_file_after_notify() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("source", Idle));
+        flow.add_processor(ProcessorNode::new("sink", Idle).auto_terminate(relationship::SUCCESS));
+        flow.add_processor(
+            ProcessorNode::new("wait", Wait::new())
+                .auto_terminate(EXPIRED)
+                .auto_terminate(relationship::FAILURE),
+        );
+        flow.add_processor(
+            ProcessorNode::new("notify", Notify::new())
+                .auto_terminate(relationship::SUCCESS)
+                .auto_terminate(relationship::FAILURE),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "held", "source", "success", "wait",
+        ));
+        flow.add_connection(ConnectionDefinition::new(
+            "parts", "source", "success", "notify",
+        ));
+        flow.add_connection(ConnectionDefinition::new(
+            "released", "wait", "success", "sink",
+        ));
+        let mut controller = FlowController::new(flow);
+        controller.start().unwrap();
+        let (held, parts, released) = (
+            controller.connection("held").unwrap(),
+            controller.connection("parts").unwrap(),
+            controller.connection("released").unwrap(),
+        );
+
+        held.send(correlated("order", "order-7")).await.unwrap();
+        parts
+            .send(correlated("unrelated", "order-8"))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(released.is_empty());
+
+        parts.send(correlated("invoice", "order-7")).await.unwrap();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while released.is_empty() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let flowfile = released
+            .receive()
+            .await
+            .unwrap()
+            .expect("held file was released");
+        assert_eq!(flowfile.content().unwrap().as_ref(), b"order");
+        let store = controller
+            .services()
+            .get::<SignalStore>(DEFAULT_SIGNAL_STORE)
+            .unwrap();
+        assert_eq!(store.count("order-7"), 0);
+        assert_eq!(store.count("order-8"), 1);
+        controller.stop().await;
+    }
+}
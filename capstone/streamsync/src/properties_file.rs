@@ -0,0 +1,285 @@
+//! Processor configuration kept in a properties file, for running a processor
+//! outside a flow definition; see `ProcessorContext::from_file`.
+//!
+//! Each line is `key = value` or `key: value`; blank lines and lines starting
+//! with `#` or `!` are skipped, and a key given twice keeps its last value.
+//! Values may refer to environment variables as `${ENV:NAME}` and to other
+//! properties of the same file as `${key}`. Any other `${...}`, such as
+//! `${filename}` or `${now()}`, is an expression for when the processor runs
+//! and is left as written, as is the `$${` escape the expression language
+//! turns into a literal `${`. An unset environment variable is an error in a
+//! required property and an empty string anywhere else; properties referring
+//! to each other in a cycle are always an error.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+
+/// Prefix of a reference to an environment variable.
+pub const ENV_REFERENCE: &str = "ENV:";
+
+#[derive(Debug)]
+pub enum PropertiesFileError {
+    Io(io::Error),
+    /// A line that is neither a property, a comment nor blank.
+    Syntax {
+        line: usize,
+        text: String,
+    },
+    /// A required property refers to an unset environment variable.
+    Undefined {
+        property: String,
+        reference: String,
+    },
+    /// Properties that refer to each other, the first repeated at the end.
+    Cycle(Vec<String>),
+}
+
+impl fmt::Display for PropertiesFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertiesFileError::Io(e) => write!(f, "cannot read properties file: {}", e),
+            PropertiesFileError::Syntax { line, text } => {
+                write!(f, "line {}: expected 'key = value', found '{}'", line, text)
+            }
+            PropertiesFileError::Undefined {
+                property,
+                reference,
+            } => {
+                write!(
+                    f,
+                    "required property '{}' refers to undefined '${{{}}}'",
+                    property, reference
+                )
+            }
+            PropertiesFileError::Cycle(keys) => {
+                write!(f, "properties refer to each other: {}", keys.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PropertiesFileError {}
+
+impl From<io::Error> for PropertiesFileError {
+    fn from(e: io::Error) -> Self {
+        PropertiesFileError::Io(e)
+    }
+}
+
+/// The raw properties of a file, references not yet resolved.
+pub fn parse(text: &str) -> Result<BTreeMap<String, String>, PropertiesFileError> {
+    let mut properties = BTreeMap::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        match line
+            .find(['=', ':'])
+            .map(|at| (line[..at].trim(), line[at + 1..].trim()))
+        {
+            Some((key, value)) if !key.is_empty() => {
+                properties.insert(key.to_string(), value.to_string());
+            }
+            _ => {
+                return Err(PropertiesFileError::Syntax {
+                    line: index + 1,
+                    text: line.to_string(),
+                })
+            }
+        }
+    }
+    Ok(properties)
+}
+
+/// Resolves every reference in `raw`. `is_required` says which properties
+/// must not refer to anything undefined, and `env` looks up environment
+/// variables.
+pub fn interpolate(
+    raw: &BTreeMap<String, String>,
+    is_required: &dyn Fn(&str) -> bool,
+    env: &dyn Fn(&str) -> Option<String>,
+) -> Result<HashMap<String, String>, PropertiesFileError> {
+    let mut resolver = Resolver {
+        raw,
+        is_required,
+        env,
+        resolved: HashMap::new(),
+        resolving: Vec::new(),
+    };
+    for key in raw.keys() {
+        resolver.resolve(key)?;
+    }
+    Ok(resolver.resolved)
+}
+
+struct Resolver<'a> {
+    raw: &'a BTreeMap<String, String>,
+    is_required: &'a dyn Fn(&str) -> bool,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    resolved: HashMap<String, String>,
+    // Properties whose references are being followed, outermost first.
+    resolving: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn resolve(&mut self, key: &str) -> Result<String, PropertiesFileError> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(value.clone());
+        }
+        if let Some(start) = self.resolving.iter().position(|k| k == key) {
+            let mut cycle = self.resolving[start..].to_vec();
+            cycle.push(key.to_string());
+            return Err(PropertiesFileError::Cycle(cycle));
+        }
+        self.resolving.push(key.to_string());
+        let raw = self.raw;
+        let value = self.expand(key, &raw[key])?;
+        self.resolving.pop();
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    // The value of `key` with its references filled in.
+    fn expand(&mut self, key: &str, text: &str) -> Result<String, PropertiesFileError> {
+        let mut value = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find('$') {
+            value.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            if let Some(escaped) = tail.strip_prefix("$${") {
+                value.push_str("$${");
+                rest = escaped;
+            } else if let Some((reference, after)) =
+                tail.strip_prefix("${").and_then(|r| r.split_once('}'))
+            {
+                let trimmed = reference.trim();
+                match trimmed.strip_prefix(ENV_REFERENCE) {
+                    Some(variable) => match (self.env)(variable) {
+                        Some(resolved) => value.push_str(&resolved),
+                        None if (self.is_required)(key) => {
+                            return Err(PropertiesFileError::Undefined {
+                                property: key.to_string(),
+                                reference: trimmed.to_string(),
+                            })
+                        }
+                        None => {}
+                    },
+                    None if self.raw.contains_key(trimmed) => {
+                        value.push_str(&self.resolve(trimmed)?)
+                    }
+                    // Not ours: an expression evaluated per FlowFile.
+                    None => {
+                        value.push_str("${");
+                        value.push_str(reference);
+                        value.push('}');
+                    }
+                }
+                rest = after;
+            } else {
+                value.push('$');
+                rest = &tail[1..];
+            }
+        }
+        value.push_str(rest);
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(
+        text: &str,
+        required: &[&str],
+        env: &[(&str, &str)],
+    ) -> Result<HashMap<String, String>, PropertiesFileError> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        interpolate(&parse(text)?, &|key| required.contains(&key), &|name| {
+            env.get(name).cloned()
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        let properties =
+            parse("# comment\n! also a comment\n\nbatch.size = 10\nmode: text\nurl=http://x/?a=b\nmode = binary\n")
+                .unwrap();
+        assert_eq!(properties.len(), 3);
+        assert_eq!(properties["batch.size"], "10");
+        assert_eq!(properties["mode"], "binary");
+        assert_eq!(properties["url"], "http://x/?a=b");
+        assert!(matches!(
+            parse("ok = 1\njust words"),
+            Err(PropertiesFileError::Syntax { line: 2, .. })
+        ));
+        assert!(matches!(
+            parse("= 1"),
+            Err(PropertiesFileError::Syntax { line: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        let resolved = resolve(
+            "home = ${ENV:HOME}/data\nliteral = $${ENV:HOME} costs $5",
+            &[],
+            &[("HOME", "/home/ana")],
+        )
+        .unwrap();
+        assert_eq!(resolved["home"], "/home/ana/data");
+        assert_eq!(resolved["literal"], "$${ENV:HOME} costs $5");
+    }
+
+    #[test]
+    fn test_expressions_left_for_run_time() {
+        let text = "output.name = ${base.name}-${filename}.${now():format('yyyy')}
+base.name = out
+escaped = $${filename}";
+        let resolved = resolve(text, &["output.name"], &[]).unwrap();
+        assert_eq!(
+            resolved["output.name"],
+            "out-${filename}.${now():format('yyyy')}"
+        );
+        // Left for the expression language, which makes it a literal `${`.
+        assert_eq!(resolved["escaped"], "$${filename}");
+    }
+
+    #[test]
+    fn test_property_cross_reference() {
+        let text = "input.directory = ${base.dir}/in\nbase.dir = ${root}/streamsync\nroot = /srv";
+        let resolved = resolve(text, &["input.directory"], &[]).unwrap();
+        assert_eq!(resolved["input.directory"], "/srv/streamsync/in");
+        assert_eq!(resolved["base.dir"], "/srv/streamsync");
+    }
+
+    #[test]
+    fn test_undefined_reference() {
+        let text = "input.directory = ${ENV:BASE_DIR}/in\nlabel = ${ENV:MISSING}";
+        let resolved = resolve(text, &[], &[]).unwrap();
+        assert_eq!(resolved["input.directory"], "/in");
+        assert_eq!(resolved["label"], "");
+
+        let error = resolve(text, &["input.directory"], &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "required property 'input.directory' refers to undefined '${ENV:BASE_DIR}'"
+        );
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let error = resolve("a = ${b}\nb = x${c}\nc = ${a}\nd = ${d}", &[], &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "properties refer to each other: a -> b -> c -> a"
+        );
+        let error = resolve("d = ${d}", &[], &[]).unwrap_err();
+        assert!(matches!(error, PropertiesFileError::Cycle(keys) if keys == ["d", "d"]));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::expression::predicate::Predicate;
+use std::fmt;
+
+/// Rule a property value must satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyValidator {
+    NonEmpty,
+    IntRange {
+        min: i64,
+        max: i64,
+    },
+    AllowedValues(Vec<String>),
+    /// A boolean expression as understood by `expression::predicate`.
+    Predicate,
+}
+
+impl PropertyValidator {
+    pub fn allowed_values(values: &[&str]) -> Self {
+        PropertyValidator::AllowedValues(values.iter().map(|v| v.to_string()).collect())
+    }
+
+    /// Returns a human readable reason when `value` is rejected.
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        match self {
+            PropertyValidator::NonEmpty => {
+                if value.trim().is_empty() {
+                    Err("must not be empty".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+            PropertyValidator::IntRange { min, max } => match value.trim().parse::<i64>() {
+                Ok(n) if n >= *min && n <= *max => Ok(()),
+                Ok(n) => Err(format!("{} is outside the range {}..={}", n, min, max)),
+                Err(_) => Err(format!("'{}' is not an integer", value)),
+            },
+            PropertyValidator::AllowedValues(allowed) => {
+                if allowed.iter().any(|a| a == value) {
+                    Ok(())
+                } else {
+                    Err(format!("'{}' is not one of: {}", value, allowed.join(", ")))
+                }
+            }
+            PropertyValidator::Predicate => Predicate::parse(value)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    /// What a value has to be, without naming the value rejected; the reason
+    /// given for a sensitive property, whose value must not be repeated.
+    pub fn requirement(&self) -> String {
+        match self {
+            PropertyValidator::NonEmpty => "must not be empty".to_string(),
+            PropertyValidator::IntRange { min, max } => {
+                format!("must be an integer in the range {}..={}", min, max)
+            }
+            PropertyValidator::AllowedValues(allowed) => {
+                format!("must be one of: {}", allowed.join(", "))
+            }
+            PropertyValidator::Predicate => "must be a boolean expression".to_string(),
+        }
+    }
+}
+
+/// Describes a configuration property a processor understands.
+#[derive(Debug, Clone)]
+pub struct PropertyDescriptor {
+    pub name: String,
+    pub description: String,
+    pub required: bool,
+    pub default_value: Option<String>,
+    pub validator: Option<PropertyValidator>,
+}
+
+impl PropertyDescriptor {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            required: false,
+            default_value: None,
+            validator: None,
+        }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn default_value(mut self, value: &str) -> Self {
+        self.default_value = Some(value.to_string());
+        self
+    }
+
+    pub fn validator(mut self, validator: PropertyValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyError {
+    Missing {
+        property: String,
+    },
+    Invalid {
+        property: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for PropertyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyError::Missing { property } => {
+                write!(f, "required property '{}' is not set", property)
+            }
+            PropertyError::Invalid {
+                property, reason, ..
+            } => {
+                write!(f, "property '{}' is invalid: {}", property, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PropertyError {}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvenanceEventType {
+    Create,
+    Route,
+    Drop,
+    Send,
+    Receive,
+    /// Created as a copy of another FlowFile, whose id is in the details.
+    Clone,
+    /// Created from another FlowFile to hold new content, with the parent's
+    /// id in the details.
+    Derive,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceEvent {
+    pub event_type: ProvenanceEventType,
+    pub timestamp: SystemTime,
+    pub component: String,
+    pub details: String,
+}
+
+/// Provenance carried by a FlowFile: the events it went through and how its
+/// time in the flow splits between sitting in queues and being processed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lineage {
+    events: Vec<ProvenanceEvent>,
+    queued: Duration,
+    processing: Duration,
+    queued_since: Option<SystemTime>,
+    processing_since: Option<SystemTime>,
+}
+
+impl Lineage {
+    /// Starts a lineage with a Create event; the creating processor is
+    /// considered to be processing the FlowFile until it is routed.
+    pub fn created(component: &str, now: SystemTime) -> Self {
+        Self {
+            events: vec![ProvenanceEvent {
+                event_type: ProvenanceEventType::Create,
+                timestamp: now,
+                component: component.to_string(),
+                details: String::new(),
+            }],
+            queued: Duration::ZERO,
+            processing: Duration::ZERO,
+            queued_since: None,
+            processing_since: Some(now),
+        }
+    }
+
+    pub fn events(&self) -> &[ProvenanceEvent] {
+        &self.events
+    }
+
+    pub fn record(
+        &mut self,
+        event_type: ProvenanceEventType,
+        component: &str,
+        details: &str,
+        now: SystemTime,
+    ) {
+        self.events.push(ProvenanceEvent {
+            event_type,
+            timestamp: now,
+            component: component.to_string(),
+            details: details.to_string(),
+        });
+    }
+
+    /// Timestamp of the first Create event.
+    pub fn start(&self) -> SystemTime {
+        self.events
+            .iter()
+            .find(|e| e.event_type == ProvenanceEventType::Create)
+            .map(|e| e.timestamp)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+
+    /// Called when a processor pulls the FlowFile off a queue.
+    pub fn dequeued(&mut self, now: SystemTime) {
+        if let Some(since) = self.queued_since.take() {
+            self.queued += elapsed(since, now);
+        }
+        self.processing_since = Some(now);
+    }
+
+    /// Called when a committed session places the FlowFile on a queue.
+    pub fn enqueued(&mut self, now: SystemTime) {
+        if let Some(since) = self.processing_since.take() {
+            self.processing += elapsed(since, now);
+        }
+        self.queued_since = Some(now);
+    }
+
+    /// Total time spent waiting in queues, up to the last transition.
+    pub fn time_queued(&self) -> Duration {
+        self.queued
+    }
+
+    /// Total time spent inside processors, up to the last transition.
+    pub fn time_processing(&self) -> Duration {
+        self.processing
+    }
+}
+
+pub(crate) fn elapsed(since: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(since).unwrap_or(Duration::ZERO)
+}
@@ -0,0 +1,118 @@
+use crate::processor::{FileProcessor, Processor};
+use crate::processors::compress_content::{CompressContentProcessor, DecompressContentProcessor};
+use crate::processors::control_rate::ControlRate;
+use crate::processors::convert_csv_to_json::ConvertCsvToJson;
+use crate::processors::detect_duplicate::DetectDuplicate;
+use crate::processors::dict_lookup::DictLookup;
+use crate::processors::distribute_load::DistributeLoad;
+use crate::processors::evaluate_json_path::EvaluateJsonPath;
+use crate::processors::extract_text::ExtractText;
+use crate::processors::generate_flowfile::GenerateFlowFile;
+use crate::processors::get_file::GetFileProcessor;
+use crate::processors::get_http::GetHTTP;
+use crate::processors::hash_content::HashContentProcessor;
+use crate::processors::log::LogProcessor;
+use crate::processors::merge_content::MergeContent;
+use crate::processors::put_database::PutDatabase;
+use crate::processors::put_file::PutFileProcessor;
+use crate::processors::query::QueryProcessor;
+use crate::processors::remote_port::{RemoteInputPort, RemoteOutputPort};
+use crate::processors::route_on_attribute::RouteOnAttribute;
+use crate::processors::route_on_size::RouteOnSize;
+use crate::processors::sample_flowfile::SampleFlowFile;
+use crate::processors::scan_content::ScanContent;
+use crate::processors::split_text::SplitText;
+use crate::processors::stdio::{GetStdin, PutStdout};
+use crate::processors::tail_file::TailFileProcessor;
+use crate::processors::update_attribute::UpdateAttributeProcessor;
+use crate::processors::validate_json::ValidateJson;
+use crate::processors::wait_notify::{Notify, Wait};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub type ProcessorFactory = fn() -> Arc<dyn Processor>;
+
+/// Maps processor type names, as written in flow files, to constructors.
+pub struct ProcessorRegistry {
+    factories: BTreeMap<String, ProcessorFactory>,
+}
+
+impl ProcessorRegistry {
+    pub fn new() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// A registry holding every processor shipped with streamsync.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("CompressContentProcessor", || {
+            Arc::new(CompressContentProcessor::new())
+        });
+        registry.register("ControlRate", || Arc::new(ControlRate::new()));
+        registry.register("ConvertCsvToJson", || Arc::new(ConvertCsvToJson::new()));
+        registry.register("DecompressContentProcessor", || {
+            Arc::new(DecompressContentProcessor::new())
+        });
+        registry.register("DetectDuplicate", || Arc::new(DetectDuplicate::new()));
+        registry.register("DictLookup", || Arc::new(DictLookup::new()));
+        registry.register("DistributeLoad", || Arc::new(DistributeLoad::new()));
+        registry.register("EvaluateJsonPath", || Arc::new(EvaluateJsonPath::new()));
+        registry.register("ExtractText", || Arc::new(ExtractText::new()));
+        registry.register("FileProcessor", || Arc::new(FileProcessor::new()));
+        registry.register("GenerateFlowFile", || Arc::new(GenerateFlowFile::new()));
+        registry.register("GetFileProcessor", || Arc::new(GetFileProcessor::new()));
+        registry.register("GetHTTP", || Arc::new(GetHTTP::new()));
+        registry.register("GetStdin", || Arc::new(GetStdin::new()));
+        registry.register("HashContentProcessor", || {
+            Arc::new(HashContentProcessor::new())
+        });
+        registry.register("LogProcessor", || Arc::new(LogProcessor::new()));
+        registry.register("MergeContent", || Arc::new(MergeContent::new()));
+        registry.register("Notify", || Arc::new(Notify::new()));
+        registry.register("PutDatabase", || Arc::new(PutDatabase::new()));
+        registry.register("PutFileProcessor", || Arc::new(PutFileProcessor::new()));
+        registry.register("PutStdout", || Arc::new(PutStdout::new()));
+        registry.register("QueryProcessor", || Arc::new(QueryProcessor::new()));
+        registry.register("RemoteInputPort", || Arc::new(RemoteInputPort::new()));
+        registry.register("RemoteOutputPort", || Arc::new(RemoteOutputPort::new()));
+        registry.register("RouteOnAttribute", || Arc::new(RouteOnAttribute::new()));
+        registry.register("RouteOnSize", || Arc::new(RouteOnSize::new()));
+        registry.register("SampleFlowFile", || Arc::new(SampleFlowFile::new()));
+        registry.register("ScanContent", || Arc::new(ScanContent::new()));
+        registry.register("SplitText", || Arc::new(SplitText::new()));
+        registry.register("TailFileProcessor", || Arc::new(TailFileProcessor::new()));
+        registry.register("UpdateAttributeProcessor", || {
+            Arc::new(UpdateAttributeProcessor::new())
+        });
+        registry.register("ValidateJson", || Arc::new(ValidateJson::new()));
+        registry.register("Wait", || Arc::new(Wait::new()));
+        #[cfg(feature = "kafka")]
+        {
+            use crate::processors::kafka::{ConsumeKafka, PublishKafka};
+            registry.register("ConsumeKafka", || Arc::new(ConsumeKafka::new()));
+            registry.register("PublishKafka", || Arc::new(PublishKafka::new()));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, type_name: &str, factory: ProcessorFactory) {
+        self.factories.insert(type_name.to_string(), factory);
+    }
+
+    pub fn create(&self, type_name: &str) -> Option<Arc<dyn Processor>> {
+        self.factories.get(type_name).map(|factory| factory())
+    }
+
+    /// Registered type names in alphabetical order.
+    pub fn types(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+}
+
+impl Default for ProcessorRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
@@ -0,0 +1,27 @@
+pub const SUCCESS: &str = "success";
+pub const FAILURE: &str = "failure";
+
+/// A named outlet of a processor. Every relationship must be connected
+/// downstream or auto-terminated before the flow can start.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Relationship {
+    pub name: String,
+    pub description: String,
+}
+
+impl Relationship {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+        }
+    }
+
+    pub fn success() -> Self {
+        Self::new(SUCCESS, "FlowFiles that were processed successfully")
+    }
+
+    pub fn failure() -> Self {
+        Self::new(FAILURE, "FlowFiles that could not be processed")
+    }
+}
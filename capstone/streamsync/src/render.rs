@@ -0,0 +1,256 @@
+//! Text renderings of a flow's topology, for checking a flow file wires up
+//! what it was meant to (see `streamsync inspect`).
+
+use crate::cron::CRON_EXPRESSION;
+use crate::flow::{ConnectionDefinition, FlowDefinition, ProcessorNode};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+impl FlowDefinition {
+    /// Draws every processor as a box holding its name, type and schedule,
+    /// followed by an arrow per outgoing connection labelled with the
+    /// relationship, connection name and backpressure limit. Processors are
+    /// listed upstream first where the flow allows it.
+    pub fn render_ascii(&self) -> String {
+        let mut rendered = String::new();
+        for (index, node) in self.topological_order().into_iter().enumerate() {
+            if index > 0 {
+                rendered.push('\n');
+            }
+            let lines = [
+                node.name().to_string(),
+                node.processor.get_name().to_string(),
+                schedule(node),
+            ];
+            let width = lines
+                .iter()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0);
+            let border = format!("+{}+", "-".repeat(width + 2));
+            let _ = writeln!(rendered, "{}", border);
+            for line in &lines {
+                let _ = writeln!(rendered, "| {:<width$} |", line, width = width);
+            }
+            let _ = writeln!(rendered, "{}", border);
+            for connection in self.connections.iter().filter(|c| c.source == node.name()) {
+                let _ = writeln!(
+                    rendered,
+                    "  {} --> {}  ({}, {})",
+                    connection.relationship,
+                    connection.destination,
+                    connection.name,
+                    limit(connection)
+                );
+            }
+            let mut auto_terminated: Vec<&String> = node.auto_terminated.iter().collect();
+            auto_terminated.sort();
+            for relationship in auto_terminated {
+                let _ = writeln!(rendered, "  {} --> (auto-terminated)", relationship);
+            }
+        }
+        rendered
+    }
+
+    /// The same graph in Graphviz DOT, e.g. for `dot -Tsvg`.
+    pub fn render_dot(&self) -> String {
+        let mut rendered = String::from("digraph flow {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in self.topological_order() {
+            let label = format!(
+                "{}\n{}\n{}",
+                node.name(),
+                node.processor.get_name(),
+                schedule(node)
+            );
+            let _ = writeln!(
+                rendered,
+                "    {} [label={}];",
+                quote(node.name()),
+                quote(&label)
+            );
+        }
+        for connection in &self.connections {
+            let label = format!(
+                "{}\n{}, {}",
+                connection.relationship,
+                connection.name,
+                limit(connection)
+            );
+            let _ = writeln!(
+                rendered,
+                "    {} -> {} [label={}];",
+                quote(&connection.source),
+                quote(&connection.destination),
+                quote(&label)
+            );
+        }
+        rendered.push_str("}\n");
+        rendered
+    }
+
+    // Processors with every upstream processor before them, ties kept in
+    // declaration order. Those on a cycle follow in declaration order.
+    fn topological_order(&self) -> Vec<&ProcessorNode> {
+        let mut incoming: HashMap<&str, usize> = self
+            .processors
+            .iter()
+            .map(|node| (node.name(), 0))
+            .collect();
+        for connection in &self.connections {
+            if let Some(count) = incoming.get_mut(connection.destination.as_str()) {
+                *count += 1;
+            }
+        }
+        let mut ordered: Vec<&ProcessorNode> = Vec::new();
+        while let Some(node) = self.processors.iter().find(|node| {
+            incoming[node.name()] == 0 && !ordered.iter().any(|o| o.name() == node.name())
+        }) {
+            ordered.push(node);
+            for connection in self.connections.iter().filter(|c| c.source == node.name()) {
+                if let Some(count) = incoming.get_mut(connection.destination.as_str()) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+        for node in &self.processors {
+            if !ordered.iter().any(|o| o.name() == node.name()) {
+                ordered.push(node);
+            }
+        }
+        ordered
+    }
+}
+
+fn schedule(node: &ProcessorNode) -> String {
+    if let Some(expression) = node.context.get_property(CRON_EXPRESSION) {
+        format!("cron {}", expression)
+    } else if node.run_schedule.is_zero() {
+        "runs continuously".to_string()
+    } else {
+        format!("every {:?}", node.run_schedule)
+    }
+}
+
+fn limit(connection: &ConnectionDefinition) -> String {
+    match (
+        connection.backpressure_threshold,
+        connection.backpressure_bytes,
+    ) {
+        (Some(threshold), Some(bytes)) => format!("backpressure {} / {} bytes", threshold, bytes),
+        (Some(threshold), None) => format!("backpressure {}", threshold),
+        (None, Some(bytes)) => format!("backpressure {} bytes", bytes),
+        (None, None) => "unbounded".to_string(),
+    }
+}
+
+// A DOT string literal.
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::route_on_attribute::{RouteOnAttribute, UNMATCHED};
+    use crate::processors::stdio::{GetStdin, PutStdout};
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    // Declared downstream first, so the rendering has to reorder it.
+    fn three_node_flow() -> FlowDefinition {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("print", PutStdout::with_writer(Vec::new()))
+                .cron_schedule("0 * * * *")
+                .auto_terminate("success")
+                .auto_terminate("failure"),
+        );
+        flow.add_processor(
+            ProcessorNode::new("route", RouteOnAttribute::new())
+                .with_property("errors", "level == 'ERROR'")
+                .auto_terminate(UNMATCHED),
+        );
+        flow.add_processor(
+            ProcessorNode::new("lines", GetStdin::with_reader(Cursor::new("")))
+                .run_schedule(Duration::from_secs(1)),
+        );
+        flow.add_connection(ConnectionDefinition::new(
+            "errors-to-print",
+            "route",
+            "errors",
+            "print",
+        ));
+        flow.add_connection(
+            ConnectionDefinition::new("lines-to-route", "lines", "success", "route")
+                .with_backpressure(100),
+        );
+        flow
+    }
+
+    #[test]
+    fn test_render_ascii() {
+        assert_eq!(
+            three_node_flow().render_ascii(),
+            "\
++----------+
+| lines    |
+| GetStdin |
+| every 1s |
++----------+
+  success --> route  (lines-to-route, backpressure 100)
+
++-------------------+
+| route             |
+| RouteOnAttribute  |
+| runs continuously |
++-------------------+
+  errors --> print  (errors-to-print, unbounded)
+  unmatched --> (auto-terminated)
+
++----------------+
+| print          |
+| PutStdout      |
+| cron 0 * * * * |
++----------------+
+  failure --> (auto-terminated)
+  success --> (auto-terminated)
+"
+        );
+    }
+
+    #[test]
+    fn test_render_dot() {
+        assert_eq!(
+            three_node_flow().render_dot(),
+            r#"digraph flow {
+    rankdir=LR;
+    node [shape=box];
+    "lines" [label="lines\nGetStdin\nevery 1s"];
+    "route" [label="route\nRouteOnAttribute\nruns continuously"];
+    "print" [label="print\nPutStdout\ncron 0 * * * *"];
+    "route" -> "print" [label="errors\nerrors-to-print, unbounded"];
+    "lines" -> "route" [label="success\nlines-to-route, backpressure 100"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_cycle_members_keep_declaration_order() {
+        let mut flow = three_node_flow();
+        flow.add_connection(
+            ConnectionDefinition::new("retry", "print", "failure", "route").with_backpressure(10),
+        );
+        let order: Vec<&str> = flow
+            .topological_order()
+            .iter()
+            .map(|node| node.name())
+            .collect();
+        assert_eq!(order, vec!["lines", "print", "route"]);
+    }
+}
@@ -0,0 +1,137 @@
+//! Controller services: objects shared by name between the processors of a
+//! flow, such as the signal store `Wait` and `Notify` meet in.
+//!
+//! Every processor context of a flow holds the same [`ControllerServices`].
+//! A service is either added up front by whoever builds the flow or created
+//! on first use by the processors themselves through `get_or_insert_with`,
+//! so processors that only need to agree on a name work without extra
+//! configuration.
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+pub trait ControllerService: Send + Sync {
+    /// Type name of the service, as shown in listings.
+    fn get_name(&self) -> &'static str;
+}
+
+struct Entry {
+    service: Arc<dyn ControllerService>,
+    // The same object, kept as `Any` for typed lookups.
+    any: Arc<dyn Any + Send + Sync>,
+}
+
+/// The services of one flow, by name.
+#[derive(Default)]
+pub struct ControllerServices {
+    services: RwLock<BTreeMap<String, Entry>>,
+}
+
+impl ControllerServices {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `service` under `name`, replacing any service already there.
+    pub fn add<S: ControllerService + 'static>(&self, name: &str, service: Arc<S>) {
+        let entry = Entry {
+            service: service.clone(),
+            any: service,
+        };
+        self.services
+            .write()
+            .unwrap()
+            .insert(name.to_string(), entry);
+    }
+
+    /// The service named `name`, if there is one and it is an `S`.
+    pub fn get<S: ControllerService + 'static>(&self, name: &str) -> Option<Arc<S>> {
+        let services = self.services.read().unwrap();
+        services.get(name)?.any.clone().downcast::<S>().ok()
+    }
+
+    /// The service named `name`, adding the one `create` makes if there is
+    /// none yet. Returns `None` when the name is taken by a service of
+    /// another type.
+    pub fn get_or_insert_with<S, F>(&self, name: &str, create: F) -> Option<Arc<S>>
+    where
+        S: ControllerService + 'static,
+        F: FnOnce() -> S,
+    {
+        let mut services = self.services.write().unwrap();
+        let entry = services.entry(name.to_string()).or_insert_with(|| {
+            let service = Arc::new(create());
+            Entry {
+                service: service.clone(),
+                any: service,
+            }
+        });
+        entry.any.clone().downcast::<S>().ok()
+    }
+
+    /// Names and type names of every service, sorted by name.
+    pub fn list(&self) -> Vec<(String, &'static str)> {
+        let services = self.services.read().unwrap();
+        services
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.service.get_name()))
+            .collect()
+    }
+}
+
+impl fmt::Debug for ControllerServices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.list()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counter(AtomicUsize);
+
+    impl ControllerService for Counter {
+        fn get_name(&self) -> &'static str {
+            "Counter"
+        }
+    }
+
+    struct Other;
+
+    impl ControllerService for Other {
+        fn get_name(&self) -> &'static str {
+            "Other"
+        }
+    }
+
+    #[test]
+    fn test_lookups_share_one_instance() {
+        let services = ControllerServices::new();
+        let first = services
+            .get_or_insert_with("hits", Counter::default)
+            .unwrap();
+        first.0.fetch_add(1, Ordering::SeqCst);
+        let second = services
+            .get_or_insert_with("hits", Counter::default)
+            .unwrap();
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+        assert!(services.get::<Counter>("hits").is_some());
+        assert!(services.get::<Counter>("misses").is_none());
+    }
+
+    #[test]
+    fn test_wrong_type_is_not_returned() {
+        let services = ControllerServices::new();
+        services.add("shared", Arc::new(Other));
+        assert!(services.get::<Counter>("shared").is_none());
+        assert!(services
+            .get_or_insert_with("shared", Counter::default)
+            .is_none());
+        assert_eq!(services.list(), vec![("shared".to_string(), "Other")]);
+    }
+}
@@ -0,0 +1,768 @@
+use crate::bulletin::{Bulletin, BulletinRepository};
+use crate::clock::{Clock, SystemClock};
+use crate::connection::{Connection, ConnectionError};
+use crate::flowfile::limits::{AttributeLimits, LimitPolicy};
+use crate::flowfile::FlowFile;
+use crate::logging::{LogLevel, Logger, StdoutLogger};
+use crate::provenance::ProvenanceEventType;
+use crate::relationship::{Relationship, FAILURE};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a penalized FlowFile is held back by its queue.
+pub const DEFAULT_PENALTY: Duration = Duration::from_secs(30);
+
+/// Stamped on every FlowFile a session transfers: the name of the processor
+/// that transferred it, when, and how many transfers it has been through.
+pub const LAST_PROCESSOR: &str = "streamsync.last.processor";
+pub const PROCESSED_AT: &str = "streamsync.processed.at";
+pub const HOP_COUNT: &str = "streamsync.hop.count";
+
+/// Set on a FlowFile put in the dead-letter queue, saying why it ended up
+/// there.
+pub const DEAD_LETTER_REASON: &str = "dead.letter.reason";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionError {
+    UnknownRelationship(String),
+    Connection(ConnectionError),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::UnknownRelationship(name) => {
+                write!(
+                    f,
+                    "relationship '{}' is neither connected nor auto-terminated",
+                    name
+                )
+            }
+            SessionError::Connection(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl From<ConnectionError> for SessionError {
+    fn from(e: ConnectionError) -> Self {
+        SessionError::Connection(e)
+    }
+}
+
+/// Unit of work for a single trigger. Its input is pre-fetched from the
+/// incoming queues with `prefetch` before the trigger runs. FlowFiles taken
+/// with `get` and routed with `transfer` only reach downstream queues on
+/// `commit`; `rollback` returns everything pulled to the queue it came from.
+pub struct ProcessSession {
+    processor_name: String,
+    clock: Arc<dyn Clock>,
+    incoming: Vec<Arc<dyn Connection>>,
+    outgoing: HashMap<String, Vec<Arc<dyn Connection>>>,
+    auto_terminated: HashSet<String>,
+    // Pre-fetched FlowFiles not handed out yet, by incoming index.
+    pending: VecDeque<(usize, FlowFile)>,
+    // Why nothing could be pre-fetched, for `get` and `get_batch` to report.
+    input_error: Option<ConnectionError>,
+    // Originals of every FlowFile pulled, so rollback can restore them unmodified.
+    consumed: Vec<(usize, FlowFile)>,
+    transfers: Vec<(String, FlowFile)>,
+    // FlowFiles to put back on the queue they came from, by incoming index.
+    requeued: Vec<(usize, FlowFile)>,
+    on_commit: Vec<Box<dyn FnOnce() + Send>>,
+    on_rollback: Vec<Box<dyn FnOnce() + Send>>,
+    yield_duration: Option<Duration>,
+    attribute_limits: Option<AttributeLimits>,
+    // Transfers already held to the limits, and what was wrong with them.
+    limits_checked: usize,
+    limit_violations: Vec<String>,
+    dead_letter: Option<Arc<dyn Connection>>,
+    logger: Arc<dyn Logger>,
+    bulletins: Option<Arc<BulletinRepository>>,
+}
+
+impl ProcessSession {
+    pub fn new(
+        processor_name: &str,
+        incoming: Vec<Arc<dyn Connection>>,
+        outgoing: HashMap<String, Vec<Arc<dyn Connection>>>,
+        auto_terminated: HashSet<String>,
+    ) -> Self {
+        Self {
+            processor_name: processor_name.to_string(),
+            clock: Arc::new(SystemClock),
+            incoming,
+            outgoing,
+            auto_terminated,
+            pending: VecDeque::new(),
+            input_error: None,
+            consumed: Vec::new(),
+            transfers: Vec::new(),
+            requeued: Vec::new(),
+            on_commit: Vec::new(),
+            on_rollback: Vec::new(),
+            yield_duration: None,
+            attribute_limits: None,
+            limits_checked: 0,
+            limit_violations: Vec::new(),
+            dead_letter: None,
+            logger: Arc::new(StdoutLogger::new()),
+            bulletins: None,
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_logger(mut self, logger: Arc<dyn Logger>) -> Self {
+        self.logger = logger;
+        self
+    }
+
+    /// Logs `message` under this session's processor name.
+    pub fn log(&self, level: LogLevel, message: &str) {
+        self.logger.log(level, &self.processor_name, message);
+    }
+
+    /// Where `report_bulletin` posts, normally the flow's repository.
+    pub fn with_bulletins(mut self, bulletins: Arc<BulletinRepository>) -> Self {
+        self.bulletins = Some(bulletins);
+        self
+    }
+
+    /// Logs `message` and posts it as a bulletin under this session's
+    /// processor name, for problems operators should see without reading
+    /// the logs. Bulletins are posted at once and survive a rollback.
+    pub fn report_bulletin(&self, severity: LogLevel, message: &str) {
+        self.log(severity, message);
+        if let Some(bulletins) = &self.bulletins {
+            bulletins.add(Bulletin {
+                processor: self.processor_name.clone(),
+                timestamp: self.clock.now(),
+                severity,
+                message: message.to_string(),
+            });
+        }
+    }
+
+    /// The clock the session stamps provenance and penalties with, which is
+    /// the controller's.
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// The logger behind `log`, for callbacks that outlive the session such
+    /// as those given to `on_commit`.
+    pub fn logger(&self) -> Arc<dyn Logger> {
+        self.logger.clone()
+    }
+
+    /// Enforces `limits` on every FlowFile transferred, at commit.
+    pub fn with_attribute_limits(mut self, limits: AttributeLimits) -> Self {
+        self.attribute_limits = Some(limits);
+        self
+    }
+
+    /// Pulls up to `max` FlowFiles for `get` and `get_batch` to hand out,
+    /// draining each incoming connection in turn. The scheduler calls this
+    /// with the processor's `batch_size` before triggering it. A connection
+    /// that cannot be read is skipped; if nothing could be fetched at all,
+    /// its error is kept for `get` and `get_batch` to return.
+    pub async fn prefetch(&mut self, max: usize) {
+        for index in 0..self.incoming.len() {
+            let wanted = max.saturating_sub(self.pending.len());
+            if wanted == 0 {
+                break;
+            }
+            match self.incoming[index].receive_batch(wanted).await {
+                Ok(flowfiles) => self
+                    .pending
+                    .extend(flowfiles.into_iter().map(|flowfile| (index, flowfile))),
+                Err(e) => {
+                    self.input_error.get_or_insert(e);
+                }
+            }
+        }
+        if !self.pending.is_empty() {
+            self.input_error = None;
+        }
+    }
+
+    /// The next pre-fetched FlowFile, or `None` once they have all been
+    /// handed out. Fails if nothing was fetched because an incoming
+    /// connection could not be read.
+    pub fn get(&mut self) -> Result<Option<FlowFile>, ConnectionError> {
+        if let Some(e) = self.input_error.take() {
+            return Err(e);
+        }
+        Ok(self
+            .pending
+            .pop_front()
+            .map(|(index, flowfile)| self.take(index, flowfile)))
+    }
+
+    /// Up to `max` of the pre-fetched FlowFiles. Fails like `get`.
+    pub fn get_batch(&mut self, max: usize) -> Result<Vec<FlowFile>, ConnectionError> {
+        if let Some(e) = self.input_error.take() {
+            return Err(e);
+        }
+        let mut batch = Vec::new();
+        while batch.len() < max {
+            let Some((index, flowfile)) = self.pending.pop_front() else {
+                break;
+            };
+            batch.push(self.take(index, flowfile));
+        }
+        Ok(batch)
+    }
+
+    fn take(&mut self, index: usize, mut flowfile: FlowFile) -> FlowFile {
+        self.consumed.push((index, flowfile.clone()));
+        flowfile.lineage_mut().dequeued(self.clock.now());
+        flowfile
+    }
+
+    pub fn create(&mut self) -> FlowFile {
+        FlowFile::created_by(&self.processor_name, self.clock.now())
+    }
+
+    /// Starts a FlowFile derived from `parent`: a new id, a copy of its
+    /// attributes and lineage, and empty content for the caller to fill. A
+    /// Derive event names `parent` as its source; `parent` itself still has
+    /// to be routed or removed.
+    pub fn create_from(&mut self, parent: &FlowFile) -> FlowFile {
+        let now = self.clock.now();
+        let mut child = parent.duplicate(now);
+        child.set_content(Vec::new());
+        child.lineage_mut().record(
+            ProvenanceEventType::Derive,
+            &self.processor_name,
+            &parent.id().to_string(),
+            now,
+        );
+        child
+    }
+
+    /// Adopts a FlowFile that arrived from outside the flow (for example from
+    /// another streamsync instance), recording a Receive event from `source`.
+    /// Nothing is consumed locally, so a rollback simply discards it.
+    pub fn import(&mut self, mut flowfile: FlowFile, source: &str) -> FlowFile {
+        let now = self.clock.now();
+        let lineage = flowfile.lineage_mut();
+        lineage.record(
+            ProvenanceEventType::Receive,
+            &self.processor_name,
+            source,
+            now,
+        );
+        lineage.dequeued(now);
+        flowfile
+    }
+
+    /// Runs `callback` once this session has committed successfully; it is
+    /// discarded on rollback. Used to acknowledge data to an external source
+    /// only once it is safely queued.
+    pub fn on_commit(&mut self, callback: impl FnOnce() + Send + 'static) {
+        self.on_commit.push(Box::new(callback));
+    }
+
+    /// Runs `callback` if this session is rolled back; it is discarded on
+    /// commit. Used to give back what a trigger reserved, such as a file
+    /// claimed so that concurrent tasks leave it alone.
+    pub fn on_rollback(&mut self, callback: impl FnOnce() + Send + 'static) {
+        self.on_rollback.push(Box::new(callback));
+    }
+
+    /// Routes `flowfile` to `relationship` on commit, stamping it with the
+    /// `streamsync.*` traceability attributes.
+    pub fn transfer(&mut self, mut flowfile: FlowFile, relationship: &str) {
+        let hops = flowfile
+            .get_attribute(HOP_COUNT)
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
+        flowfile.put_attribute(LAST_PROCESSOR, &self.processor_name);
+        flowfile.set_attribute(PROCESSED_AT, self.clock.now());
+        flowfile.set_attribute(HOP_COUNT, hops + 1);
+        self.transfers.push((relationship.to_string(), flowfile));
+    }
+
+    /// Puts a FlowFile pulled by this session back on the queue it came
+    /// from, keeping any changes made to it, once the session commits. Used
+    /// to hold on to a FlowFile that cannot be routed yet. A FlowFile this
+    /// session did not pull is dropped.
+    pub fn requeue(&mut self, flowfile: FlowFile) {
+        let id = flowfile.id();
+        match self
+            .consumed
+            .iter()
+            .find(|(_, original)| original.id() == id)
+        {
+            Some((index, _)) => self.requeued.push((*index, flowfile)),
+            None => self.log(
+                LogLevel::Warn,
+                &format!("cannot requeue FlowFile {}: not pulled by this session", id),
+            ),
+        }
+    }
+
+    /// Routes a clone of `flowfile` to every one of `relationships`, e.g. to
+    /// both archive and process it. Each clone has a new id and a Clone event
+    /// naming `flowfile` as its parent, and shares its content bytes; the
+    /// original itself is not transferred.
+    pub fn transfer_clone(&mut self, flowfile: FlowFile, relationships: &[Relationship]) {
+        let now = self.clock.now();
+        let parent = flowfile.id().to_string();
+        for relationship in relationships {
+            let mut clone = flowfile.duplicate(now);
+            clone.lineage_mut().record(
+                ProvenanceEventType::Clone,
+                &self.processor_name,
+                &parent,
+                now,
+            );
+            self.transfer(clone, &relationship.name);
+        }
+    }
+
+    /// Marks a FlowFile so downstream queues hold it back for `DEFAULT_PENALTY`,
+    /// typically before routing it to failure or rolling it back.
+    pub fn penalize(&mut self, mut flowfile: FlowFile) -> FlowFile {
+        flowfile.penalize_until(self.clock.now() + DEFAULT_PENALTY);
+        flowfile
+    }
+
+    /// Asks the scheduler not to trigger this processor again for `duration`,
+    /// for example because it has nothing it may do until then.
+    pub fn yield_for(&mut self, duration: Duration) {
+        self.yield_duration = Some(duration);
+    }
+
+    pub fn yield_duration(&self) -> Option<Duration> {
+        self.yield_duration
+    }
+
+    /// Drops a FlowFile from the flow.
+    pub fn remove(&mut self, flowfile: FlowFile) {
+        drop(flowfile);
+    }
+
+    /// FlowFiles transferred so far that the next commit will route, counted
+    /// per relationship.
+    pub fn transfer_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for (relationship, _) in &self.transfers {
+            *counts.entry(relationship.clone()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Sends FlowFiles routed to an unconnected `failure` relationship to
+    /// `queue` on commit, instead of refusing the commit.
+    pub fn with_dead_letter(mut self, queue: Arc<dyn Connection>) -> Self {
+        self.dead_letter = Some(queue);
+        self
+    }
+
+    /// One line per FlowFile this session had to bring within the attribute
+    /// limits, for the caller to raise a bulletin about.
+    pub fn limit_violations(&self) -> &[String] {
+        &self.limit_violations
+    }
+
+    /// Holds the FlowFiles transferred so far to the attribute limits,
+    /// rerouting them to failure under `route-to-failure`. `commit` does this
+    /// itself; calling it first makes `transfer_counts` reflect the rerouting.
+    pub fn enforce_attribute_limits(&mut self) {
+        let Some(limits) = &self.attribute_limits else {
+            return;
+        };
+        for (relationship, flowfile) in &mut self.transfers[self.limits_checked..] {
+            let violations = limits.enforce(flowfile);
+            if violations.is_empty() {
+                continue;
+            }
+            if limits.policy == LimitPolicy::RouteToFailure {
+                *relationship = FAILURE.to_string();
+            }
+            self.limit_violations.push(format!(
+                "FlowFile {}: {}",
+                flowfile.id(),
+                violations.join("; ")
+            ));
+        }
+        self.limits_checked = self.transfers.len();
+    }
+
+    fn is_routable(&self, relationship: &str) -> bool {
+        self.outgoing.contains_key(relationship) || self.auto_terminated.contains(relationship)
+    }
+
+    pub async fn commit(&mut self) -> Result<(), SessionError> {
+        self.enforce_attribute_limits();
+        for (relationship, _) in &self.transfers {
+            let dead_letter = relationship == FAILURE && self.dead_letter.is_some();
+            if !self.is_routable(relationship) && !dead_letter {
+                return Err(SessionError::UnknownRelationship(relationship.clone()));
+            }
+        }
+        let now = self.clock.now();
+        self.limits_checked = 0;
+        for (relationship, mut flowfile) in std::mem::take(&mut self.transfers) {
+            if !self.is_routable(&relationship) {
+                let reason = format!(
+                    "{} routed it to failure, which is not connected",
+                    self.processor_name
+                );
+                flowfile.put_attribute(DEAD_LETTER_REASON, &reason);
+                let lineage = flowfile.lineage_mut();
+                lineage.record(
+                    ProvenanceEventType::Route,
+                    &self.processor_name,
+                    "dead-letter",
+                    now,
+                );
+                lineage.enqueued(now);
+                let queue = self.dead_letter.as_ref().expect("checked above");
+                queue.send(flowfile).await?;
+                continue;
+            }
+            match self.outgoing.get(&relationship) {
+                Some(connections) => {
+                    let lineage = flowfile.lineage_mut();
+                    lineage.record(
+                        ProvenanceEventType::Route,
+                        &self.processor_name,
+                        &relationship,
+                        now,
+                    );
+                    lineage.enqueued(now);
+                    for connection in connections {
+                        connection.send(flowfile.clone()).await?;
+                    }
+                }
+                None => {
+                    let lineage = flowfile.lineage_mut();
+                    lineage.record(
+                        ProvenanceEventType::Drop,
+                        &self.processor_name,
+                        &relationship,
+                        now,
+                    );
+                }
+            }
+        }
+        for (index, mut flowfile) in self.requeued.drain(..) {
+            flowfile.lineage_mut().enqueued(now);
+            self.incoming[index].send(flowfile).await?;
+        }
+        for (index, flowfile) in std::mem::take(&mut self.pending) {
+            self.incoming[index].send(flowfile).await?;
+        }
+        self.input_error = None;
+        self.consumed.clear();
+        self.on_rollback.clear();
+        for callback in self.on_commit.drain(..) {
+            callback();
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(&mut self) {
+        self.transfers.clear();
+        self.limits_checked = 0;
+        self.limit_violations.clear();
+        self.requeued.clear();
+        self.on_commit.clear();
+        for callback in self.on_rollback.drain(..) {
+            callback();
+        }
+        self.input_error = None;
+        let pending = std::mem::take(&mut self.pending);
+        let consumed = std::mem::take(&mut self.consumed);
+        for (index, flowfile) in consumed.into_iter().chain(pending) {
+            let id = flowfile.id();
+            if let Err(e) = self.incoming[index].send(flowfile).await {
+                self.log(
+                    LogLevel::Error,
+                    &format!("rollback could not requeue FlowFile {}: {}", id, e),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::connection::MemoryConnection;
+    use crate::logging::MemoryLogger;
+
+    #[tokio::test]
+    async fn test_lineage_durations_follow_mock_clock() {
+        let clock = Arc::new(MockClock::default());
+        let queue: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        let sink: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+
+        let mut source = ProcessSession::new(
+            "source",
+            Vec::new(),
+            HashMap::from([("success".to_string(), vec![queue.clone()])]),
+            HashSet::new(),
+        )
+        .with_clock(clock.clone());
+        let flowfile = source.create();
+        clock.advance(Duration::from_secs(1));
+        source.transfer(flowfile, "success");
+        source.commit().await.unwrap();
+
+        clock.advance(Duration::from_secs(3));
+        let mut worker = ProcessSession::new(
+            "worker",
+            vec![queue.clone()],
+            HashMap::from([("success".to_string(), vec![sink.clone()])]),
+            HashSet::new(),
+        )
+        .with_clock(clock.clone());
+        worker.prefetch(1).await;
+        let flowfile = worker.get().unwrap().unwrap();
+        clock.advance(Duration::from_secs(2));
+        worker.transfer(flowfile, "success");
+        worker.commit().await.unwrap();
+
+        clock.advance(Duration::from_secs(4));
+        let flowfile = sink.receive().await.unwrap().unwrap();
+        assert_eq!(flowfile.lineage().time_processing(), Duration::from_secs(3));
+        assert_eq!(flowfile.lineage().time_queued(), Duration::from_secs(3));
+        assert_eq!(flowfile.age(clock.as_ref()), Duration::from_secs(10));
+        assert_eq!(
+            flowfile.lineage_duration(clock.as_ref()),
+            Duration::from_secs(10)
+        );
+        assert_eq!(flowfile.lineage().events().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_clone_shares_content() {
+        let archive: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        let process: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        let mut session = ProcessSession::new(
+            "router",
+            Vec::new(),
+            HashMap::from([
+                ("archive".to_string(), vec![archive.clone()]),
+                ("process".to_string(), vec![process.clone()]),
+            ]),
+            HashSet::new(),
+        );
+        let mut original = session.create();
+        original.set_content(vec![7u8; 4096]);
+        original.put_attribute("filename", "data.bin");
+        let relationships = [
+            Relationship::new("archive", ""),
+            Relationship::new("process", ""),
+        ];
+        session.transfer_clone(original.clone(), &relationships);
+        assert_eq!(
+            session.transfer_counts(),
+            BTreeMap::from([("archive".to_string(), 1), ("process".to_string(), 1)])
+        );
+        session.commit().await.unwrap();
+
+        let mut archived = archive.receive().await.unwrap().unwrap();
+        let processed = process.receive().await.unwrap().unwrap();
+        assert_ne!(archived.id(), processed.id());
+        assert_ne!(archived.id(), original.id());
+        assert!(archived.shares_content_with(&original));
+        assert!(archived.shares_content_with(&processed));
+        for clone in [&archived, &processed] {
+            let clone_event = &clone.lineage().events()[1];
+            assert_eq!(clone_event.event_type, ProvenanceEventType::Clone);
+            assert_eq!(clone_event.details, original.id().to_string());
+        }
+
+        archived.put_attribute("filename", "archived.bin");
+        assert_eq!(
+            processed.get_attribute("filename").unwrap().to_string(),
+            "data.bin"
+        );
+        archived.set_content("replaced");
+        assert!(!archived.shares_content_with(&processed));
+        assert_eq!(processed.content().unwrap().as_ref(), &[7u8; 4096][..]);
+    }
+
+    #[tokio::test]
+    async fn test_create_from_links_child_to_parent() {
+        let clock = Arc::new(MockClock::default());
+        let mut session =
+            ProcessSession::new("splitter", Vec::new(), HashMap::new(), HashSet::new())
+                .with_clock(clock.clone());
+        let mut parent = session.create();
+        parent.set_content("a,b\n1,2\n");
+        parent.put_attribute("filename", "data.csv");
+        parent.set_attribute("record.count", 1);
+
+        clock.advance(Duration::from_secs(5));
+        let mut child = session.create_from(&parent);
+        assert_ne!(child.id(), parent.id());
+        assert_eq!(child.attributes(), parent.attributes());
+        assert!(child.content().unwrap().is_empty());
+        assert_eq!(child.age(clock.as_ref()), Duration::ZERO);
+
+        let events = child.lineage().events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, ProvenanceEventType::Create);
+        assert_eq!(events[1].event_type, ProvenanceEventType::Derive);
+        assert_eq!(events[1].component, "splitter");
+        assert_eq!(events[1].details, parent.id().to_string());
+
+        child.set_content("{\"a\":1,\"b\":2}");
+        child.put_attribute("filename", "data.json");
+        assert_eq!(parent.content().unwrap().as_ref(), b"a,b\n1,2\n");
+        assert_eq!(
+            parent.get_attribute("filename").unwrap().to_string(),
+            "data.csv"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reported_bulletins_survive_rollback() {
+        let bulletins = Arc::new(BulletinRepository::new(10));
+        let logger = Arc::new(MemoryLogger::new());
+        let mut session = ProcessSession::new("fetch", Vec::new(), HashMap::new(), HashSet::new())
+            .with_bulletins(bulletins.clone())
+            .with_logger(logger.clone());
+        session.report_bulletin(LogLevel::Error, "remote host unreachable");
+        session.rollback().await;
+
+        let posted = bulletins.for_processor("fetch");
+        assert_eq!(posted.len(), 1);
+        assert_eq!(posted[0].severity, LogLevel::Error);
+        assert_eq!(posted[0].message, "remote host unreachable");
+        assert_eq!(logger.records().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_requeue_returns_changed_flowfile_on_commit() {
+        let queue: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        queue.send(FlowFile::with_content("held")).await.unwrap();
+        let mut session =
+            ProcessSession::new("wait", vec![queue.clone()], HashMap::new(), HashSet::new());
+        session.prefetch(1).await;
+        let mut flowfile = session.get().unwrap().unwrap();
+        flowfile.put_attribute("visits", "1");
+        session.requeue(flowfile);
+        assert!(queue.is_empty());
+        session.commit().await.unwrap();
+
+        let requeued = queue.receive().await.unwrap().unwrap();
+        assert_eq!(requeued.get_attribute("visits").unwrap().to_string(), "1");
+    }
+
+    #[tokio::test]
+    async fn test_unclaimed_prefetched_flowfiles_go_back_on_commit() {
+        let queue: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        for content in ["a", "b", "c"] {
+            queue.send(FlowFile::with_content(content)).await.unwrap();
+        }
+        let mut session = ProcessSession::new(
+            "sink",
+            vec![queue.clone()],
+            HashMap::new(),
+            HashSet::from(["success".to_string()]),
+        );
+        session.prefetch(3).await;
+        assert!(queue.is_empty());
+        let flowfile = session.get().unwrap().unwrap();
+        session.transfer(flowfile, "success");
+        session.commit().await.unwrap();
+
+        let left: Vec<Vec<u8>> = queue
+            .receive_batch(3)
+            .await
+            .unwrap()
+            .iter()
+            .map(|f| f.content().unwrap().into_owned())
+            .collect();
+        assert_eq!(left, [b"b".to_vec(), b"c".to_vec()]);
+    }
+
+    #[tokio::test]
+    async fn test_closed_input_fails_get_only_when_nothing_was_fetched() {
+        let closed = Arc::new(MemoryConnection::new());
+        closed.close();
+        let open: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        open.send(FlowFile::with_content("ready")).await.unwrap();
+        let incoming: Vec<Arc<dyn Connection>> = vec![closed.clone(), open.clone()];
+
+        let mut session =
+            ProcessSession::new("sink", incoming.clone(), HashMap::new(), HashSet::new());
+        session.prefetch(10).await;
+        assert!(session.get().unwrap().is_some());
+        assert!(session.get().unwrap().is_none());
+        session.rollback().await;
+
+        let mut session = ProcessSession::new("sink", vec![closed], HashMap::new(), HashSet::new());
+        session.prefetch(10).await;
+        assert_eq!(session.get_batch(10).unwrap_err(), ConnectionError::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_stamps_traceability_attributes() {
+        let clock = Arc::new(MockClock::default());
+        let between: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+        let out: Arc<dyn Connection> = Arc::new(MemoryConnection::new());
+
+        let mut first = ProcessSession::new(
+            "first",
+            Vec::new(),
+            HashMap::from([("success".to_string(), vec![between.clone()])]),
+            HashSet::new(),
+        )
+        .with_clock(clock.clone());
+        let flowfile = first.create();
+        first.transfer(flowfile, "success");
+        first.commit().await.unwrap();
+        let stamped = between.snapshot().remove(0);
+        assert_eq!(
+            stamped.get_attribute(LAST_PROCESSOR).unwrap().to_string(),
+            "first"
+        );
+        assert_eq!(
+            stamped.get_attribute(HOP_COUNT).and_then(|v| v.as_i64()),
+            Some(1)
+        );
+
+        clock.advance(Duration::from_secs(2));
+        let mut second = ProcessSession::new(
+            "second",
+            vec![between.clone()],
+            HashMap::from([("success".to_string(), vec![out.clone()])]),
+            HashSet::new(),
+        )
+        .with_clock(clock.clone());
+        second.prefetch(1).await;
+        let flowfile = second.get().unwrap().unwrap();
+        second.transfer(flowfile, "success");
+        second.commit().await.unwrap();
+
+        let flowfile = out.receive().await.unwrap().unwrap();
+        assert_eq!(
+            flowfile.get_attribute(LAST_PROCESSOR).unwrap().to_string(),
+            "second"
+        );
+        assert_eq!(
+            flowfile.get_attribute(HOP_COUNT).and_then(|v| v.as_i64()),
+            Some(2)
+        );
+        assert_eq!(
+            flowfile.get_attribute(PROCESSED_AT).unwrap().to_string(),
+            "2000"
+        );
+    }
+}
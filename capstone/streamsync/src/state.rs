@@ -0,0 +1,153 @@
+//! Per-processor key/value state that can outlive a single run of the flow.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug)]
+pub enum StateError {
+    Io(io::Error),
+    Corrupt(String),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Io(e) => write!(f, "state I/O error: {}", e),
+            StateError::Corrupt(reason) => write!(f, "stored state is corrupt: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<io::Error> for StateError {
+    fn from(e: io::Error) -> Self {
+        StateError::Io(e)
+    }
+}
+
+/// Stores a map of strings per component (processor name). `set_state`
+/// replaces the whole map, so a component always sees a consistent snapshot.
+pub trait StateManager: Send + Sync {
+    fn get_state(&self, component: &str) -> Result<HashMap<String, String>, StateError>;
+    fn set_state(&self, component: &str, state: HashMap<String, String>) -> Result<(), StateError>;
+
+    fn clear(&self, component: &str) -> Result<(), StateError> {
+        self.set_state(component, HashMap::new())
+    }
+}
+
+impl fmt::Debug for dyn StateManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StateManager")
+    }
+}
+
+/// State that lives only as long as the process.
+#[derive(Default)]
+pub struct MemoryStateManager {
+    states: Mutex<HashMap<String, HashMap<String, String>>>,
+}
+
+impl MemoryStateManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateManager for MemoryStateManager {
+    fn get_state(&self, component: &str) -> Result<HashMap<String, String>, StateError> {
+        Ok(self
+            .states
+            .lock()
+            .unwrap()
+            .get(component)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn set_state(&self, component: &str, state: HashMap<String, String>) -> Result<(), StateError> {
+        self.states
+            .lock()
+            .unwrap()
+            .insert(component.to_string(), state);
+        Ok(())
+    }
+}
+
+/// State kept as one JSON file per component in a directory, so it survives
+/// restarts. Writes go to a temporary file that is then renamed into place.
+pub struct FileStateManager {
+    directory: PathBuf,
+}
+
+impl FileStateManager {
+    pub fn new(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    fn path(&self, component: &str) -> PathBuf {
+        let file_name: String = component
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.directory.join(format!("{}.json", file_name))
+    }
+}
+
+impl StateManager for FileStateManager {
+    fn get_state(&self, component: &str) -> Result<HashMap<String, String>, StateError> {
+        match std::fs::read(self.path(component)) {
+            Ok(bytes) => {
+                serde_json::from_slice(&bytes).map_err(|e| StateError::Corrupt(e.to_string()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_state(&self, component: &str, state: HashMap<String, String>) -> Result<(), StateError> {
+        let path = self.path(component);
+        let temporary = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec(&state).map_err(|e| StateError::Corrupt(e.to_string()))?;
+        std::fs::write(&temporary, bytes)?;
+        std::fs::rename(temporary, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_state_survives_new_manager() {
+        let directory =
+            std::env::temp_dir().join(format!("streamsync-state-{}", std::process::id()));
+        let manager = FileStateManager::new(&directory).unwrap();
+        assert!(manager.get_state("dedupe").unwrap().is_empty());
+        manager
+            .set_state(
+                "dedupe",
+                HashMap::from([("key".to_string(), "value".to_string())]),
+            )
+            .unwrap();
+
+        let reopened = FileStateManager::new(&directory).unwrap();
+        assert_eq!(reopened.get_state("dedupe").unwrap()["key"], "value");
+        reopened.clear("dedupe").unwrap();
+        assert!(manager.get_state("dedupe").unwrap().is_empty());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+//! Harness for unit-testing a single processor without building a flow.
+//!
+//! ```
+//! use streamsync::processor::FileProcessor;
+//! use streamsync::testing::TestRunner;
+//!
+//! let mut runner = TestRunner::new(FileProcessor::new());
+//! runner.enqueue("hello", &[("filename", "hello.txt")]);
+//! runner.run(1);
+//! runner.assert_transferred("success", 1);
+//! assert_eq!(runner.get_output("success")[0].content().unwrap().as_ref(), b"hello");
+//! ```
+
+use crate::bulletin::{Bulletin, BulletinRepository};
+use crate::clock::{Clock, SystemClock};
+use crate::connection::{Connection, MemoryConnection};
+use crate::flowfile::FlowFile;
+use crate::logging::{Logger, StdoutLogger};
+use crate::processor::{Processor, ProcessorError};
+use crate::processor_context::ProcessorContext;
+use crate::service::ControllerServices;
+use crate::session::ProcessSession;
+use crate::state::StateManager;
+use futures::executor::block_on;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Drives one processor through the real `ProcessSession` commit path, with
+/// an in-memory queue in front of it and one captured queue per relationship.
+pub struct TestRunner {
+    processor: Arc<dyn Processor>,
+    context: ProcessorContext,
+    input: Arc<MemoryConnection>,
+    outputs: HashMap<String, Arc<MemoryConnection>>,
+    transferred: HashMap<String, Vec<FlowFile>>,
+    errors: Vec<ProcessorError>,
+    logger: Arc<dyn Logger>,
+    bulletins: Arc<BulletinRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TestRunner {
+    pub fn new(processor: impl Processor + 'static) -> Self {
+        Self::from_arc(Arc::new(processor))
+    }
+
+    /// Drives a processor the test keeps its own handle to, e.g. to check
+    /// `is_exhausted` between runs.
+    pub fn from_arc(processor: Arc<dyn Processor>) -> Self {
+        let context = ProcessorContext::new(processor.get_name());
+        Self {
+            processor,
+            context,
+            input: Arc::new(MemoryConnection::new()),
+            outputs: HashMap::new(),
+            transferred: HashMap::new(),
+            errors: Vec::new(),
+            logger: Arc::new(StdoutLogger::new()),
+            bulletins: Arc::new(BulletinRepository::default()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn set_property(&mut self, key: &str, value: &str) {
+        self.context.set_property(key, value);
+    }
+
+    pub fn context(&self) -> &ProcessorContext {
+        &self.context
+    }
+
+    /// Shares `state_manager` with the processor, e.g. to hand a second runner
+    /// the state left behind by a first one.
+    pub fn set_state_manager(&mut self, state_manager: Arc<dyn StateManager>) {
+        self.context.state_manager = state_manager;
+    }
+
+    /// Shares `services` with the processor, e.g. so a `Notify` and a `Wait`
+    /// under test meet in the same signal store.
+    pub fn set_services(&mut self, services: Arc<ControllerServices>) {
+        self.context.services = services;
+    }
+
+    /// Sends what the processor logs to `logger`, e.g. a `MemoryLogger` to
+    /// assert on.
+    pub fn set_logger(&mut self, logger: Arc<dyn Logger>) {
+        self.logger = logger;
+    }
+
+    /// Runs the processor, its sessions and queues on `clock`, e.g. a
+    /// `MockClock` to step through penalties without sleeping.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        let queued = self.input.drain();
+        self.input = Arc::new(MemoryConnection::new().with_clock(clock.clone()));
+        self.outputs.clear();
+        self.clock = clock;
+        for flowfile in queued {
+            self.enqueue_flowfile(flowfile);
+        }
+    }
+
+    /// Queues a FlowFile for the processor's next trigger.
+    ///
+    /// ```
+    /// # use streamsync::processor::FileProcessor;
+    /// # use streamsync::testing::TestRunner;
+    /// let mut runner = TestRunner::new(FileProcessor::new());
+    /// runner.enqueue("a,b,c", &[("mime.type", "text/csv")]);
+    /// assert_eq!(runner.queue_size(), 1);
+    /// ```
+    pub fn enqueue(&mut self, content: impl Into<Vec<u8>>, attributes: &[(&str, &str)]) {
+        let mut flowfile = FlowFile::created_by("", self.clock.now());
+        flowfile.set_content(content);
+        for (key, value) in attributes {
+            flowfile.put_attribute(key, value);
+        }
+        self.enqueue_flowfile(flowfile);
+    }
+
+    pub fn enqueue_flowfile(&mut self, flowfile: FlowFile) {
+        block_on(self.input.send(flowfile)).expect("test input queue is never closed");
+    }
+
+    /// FlowFiles still waiting in the input queue.
+    pub fn queue_size(&self) -> usize {
+        self.input.len()
+    }
+
+    /// Triggers the processor `triggers` times, committing each session.
+    /// Panics if a commit fails, since that means the processor routed to a
+    /// relationship it does not declare. A trigger that returns an error is
+    /// rolled back, as the controller would, and the error is kept for
+    /// `errors`.
+    pub fn run(&mut self, triggers: usize) {
+        let mut relationships = self.processor.relationships();
+        relationships.extend(self.processor.dynamic_relationships(&self.context));
+        for relationship in relationships {
+            self.outputs.entry(relationship.name).or_insert_with(|| {
+                Arc::new(MemoryConnection::new().with_clock(self.clock.clone()))
+            });
+        }
+        for _ in 0..triggers {
+            let incoming: Vec<Arc<dyn Connection>> = vec![self.input.clone()];
+            let outgoing: HashMap<String, Vec<Arc<dyn Connection>>> = self
+                .outputs
+                .iter()
+                .map(|(name, queue)| (name.clone(), vec![queue.clone() as Arc<dyn Connection>]))
+                .collect();
+            let mut session = ProcessSession::new(
+                &self.context.processor_name,
+                incoming,
+                outgoing,
+                HashSet::new(),
+            )
+            .with_clock(self.clock.clone())
+            .with_logger(self.logger.clone())
+            .with_bulletins(self.bulletins.clone());
+            block_on(session.prefetch(self.processor.batch_size(&self.context)));
+            match self.processor.on_trigger(&self.context, &mut session) {
+                Ok(()) => {
+                    if let Err(e) = block_on(session.commit()) {
+                        panic!("{} failed to commit: {}", self.processor.get_name(), e);
+                    }
+                }
+                Err(e) => {
+                    block_on(session.rollback());
+                    self.errors.push(e);
+                }
+            }
+        }
+        for (name, queue) in &self.outputs {
+            self.transferred
+                .entry(name.clone())
+                .or_default()
+                .extend(queue.drain());
+        }
+    }
+
+    /// Errors returned by `on_trigger` across all runs so far, oldest first.
+    pub fn errors(&self) -> &[ProcessorError] {
+        &self.errors
+    }
+
+    /// Bulletins the processor reported across all runs so far, newest first.
+    pub fn bulletins(&self) -> Vec<Bulletin> {
+        self.bulletins.recent()
+    }
+
+    /// FlowFiles transferred to `relationship` across all runs so far.
+    ///
+    /// ```
+    /// # use streamsync::processor::FileProcessor;
+    /// # use streamsync::testing::TestRunner;
+    /// let mut runner = TestRunner::new(FileProcessor::new());
+    /// runner.enqueue("one", &[]);
+    /// runner.enqueue("two", &[]);
+    /// runner.run(2);
+    /// let contents: Vec<_> = runner.get_output("success").iter().map(|f| f.content().unwrap().to_vec()).collect();
+    /// assert_eq!(contents, vec![b"one".to_vec(), b"two".to_vec()]);
+    /// ```
+    pub fn get_output(&self, relationship: &str) -> Vec<FlowFile> {
+        self.transferred
+            .get(relationship)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The content of each FlowFile transferred to `relationship`, as text.
+    pub fn get_output_text(&self, relationship: &str) -> Vec<String> {
+        self.get_output(relationship)
+            .iter()
+            .map(|f| String::from_utf8_lossy(&f.content().unwrap()).into_owned())
+            .collect()
+    }
+
+    pub fn assert_transferred(&self, relationship: &str, count: usize) {
+        let actual = self.transferred.get(relationship).map_or(0, Vec::len);
+        assert_eq!(
+            actual, count,
+            "expected {} FlowFile(s) on '{}', found {}",
+            count, relationship, actual
+        );
+    }
+
+    /// Asserts that at least one transferred FlowFile carries a penalty.
+    pub fn assert_penalized(&self) {
+        let now = self.clock.now();
+        let penalized = self
+            .transferred
+            .values()
+            .flatten()
+            .any(|flowfile| flowfile.is_penalized(now));
+        assert!(
+            penalized,
+            "expected a penalized FlowFile among the transferred output"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::processor::DEFAULT_BATCH_SIZE;
+    use crate::property::PropertyDescriptor;
+    use crate::relationship::{self, Relationship};
+    use crate::session::DEFAULT_PENALTY;
+    use std::time::Duration;
+
+    // Routes FlowFiles whose "status" attribute matches the configured value
+    // to success and penalizes the rest on their way to failure.
+    struct StatusFilter;
+
+    impl Processor for StatusFilter {
+        fn on_trigger(
+            &self,
+            context: &ProcessorContext,
+            session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            let expected = context.get_property("status").cloned().unwrap_or_default();
+            while let Some(flowfile) = session.get()? {
+                if flowfile
+                    .get_attribute("status")
+                    .is_some_and(|status| *status == expected)
+                {
+                    session.transfer(flowfile, relationship::SUCCESS);
+                } else {
+                    let flowfile = session.penalize(flowfile);
+                    session.transfer(flowfile, relationship::FAILURE);
+                }
+            }
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "StatusFilter"
+        }
+
+        fn properties(&self) -> Vec<PropertyDescriptor> {
+            vec![PropertyDescriptor::new("status", "Status value to accept").required()]
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success(), Relationship::failure()]
+        }
+
+        fn batch_size(&self, _context: &ProcessorContext) -> usize {
+            DEFAULT_BATCH_SIZE
+        }
+    }
+
+    #[test]
+    fn test_runner_routes_and_penalizes() {
+        let mut runner = TestRunner::new(StatusFilter);
+        runner.set_property("status", "ok");
+        runner.enqueue("first", &[("status", "ok")]);
+        runner.enqueue("second", &[("status", "broken")]);
+        runner.run(1);
+
+        runner.assert_transferred("success", 1);
+        runner.assert_transferred("failure", 1);
+        runner.assert_penalized();
+        assert_eq!(
+            runner.get_output("failure")[0].content().unwrap().as_ref(),
+            b"second"
+        );
+        assert_eq!(runner.queue_size(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a penalized FlowFile")]
+    fn test_assert_penalized_fails_without_penalty() {
+        let mut runner = TestRunner::new(StatusFilter);
+        runner.set_property("status", "ok");
+        runner.enqueue("first", &[("status", "ok")]);
+        runner.run(1);
+        runner.assert_penalized();
+    }
+
+    #[test]
+    fn test_penalty_runs_out_on_the_runner_clock() {
+        let clock = Arc::new(MockClock::default());
+        let mut runner = TestRunner::new(StatusFilter);
+        runner.set_clock(clock.clone());
+        runner.set_property("status", "ok");
+        runner.enqueue("second", &[("status", "broken")]);
+        runner.run(1);
+        runner.assert_penalized();
+
+        let failed = &runner.get_output("failure")[0];
+        assert_eq!(failed.age(clock.as_ref()), Duration::ZERO);
+        clock.advance(DEFAULT_PENALTY);
+        assert!(!failed.is_penalized(clock.now()));
+    }
+}
@@ -0,0 +1,578 @@
+use crate::cron::{CronSchedule, CRON_EXPRESSION};
+use crate::flow::{
+    parse_concurrent_tasks, parse_execution_timeout, ConnectionDefinition, FlowDefinition,
+    CONCURRENT_TASKS, EXECUTION_TIMEOUT,
+};
+use crate::property::PropertyError;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    MissingProperty {
+        processor: String,
+        property: String,
+    },
+    InvalidProperty {
+        processor: String,
+        property: String,
+        reason: String,
+    },
+    UnconnectedRelationship {
+        processor: String,
+        relationship: String,
+    },
+    UnknownProcessor {
+        connection: String,
+        processor: String,
+    },
+    UnknownRelationship {
+        connection: String,
+        processor: String,
+        relationship: String,
+    },
+    UnboundedCycle {
+        processors: Vec<String>,
+    },
+    /// An expiration of zero would drop every FlowFile sent to the queue.
+    ZeroExpiration {
+        connection: String,
+    },
+    /// The connections of one funnel have to agree on their destination and
+    /// backpressure threshold, and a funnel has no byte limit or expiration.
+    InvalidFunnel {
+        funnel: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingProperty {
+                processor,
+                property,
+            } => {
+                write!(
+                    f,
+                    "{}: required property '{}' is not set",
+                    processor, property
+                )
+            }
+            ValidationError::InvalidProperty {
+                processor,
+                property,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "{}: property '{}' is invalid: {}",
+                    processor, property, reason
+                )
+            }
+            ValidationError::UnconnectedRelationship {
+                processor,
+                relationship,
+            } => write!(
+                f,
+                "{}: relationship '{}' is not connected and not auto-terminated",
+                processor, relationship
+            ),
+            ValidationError::UnknownProcessor {
+                connection,
+                processor,
+            } => {
+                write!(
+                    f,
+                    "connection '{}' refers to unknown processor '{}'",
+                    connection, processor
+                )
+            }
+            ValidationError::UnknownRelationship {
+                connection,
+                processor,
+                relationship,
+            } => write!(
+                f,
+                "connection '{}' uses relationship '{}' which {} does not define",
+                connection, relationship, processor
+            ),
+            ValidationError::UnboundedCycle { processors } => write!(
+                f,
+                "cycle without a backpressure bound: {}",
+                processors.join(" -> ")
+            ),
+            ValidationError::ZeroExpiration { connection } => write!(
+                f,
+                "connection '{}' has an expiration of zero, which would drop everything",
+                connection
+            ),
+            ValidationError::InvalidFunnel { funnel, reason } => {
+                write!(f, "funnel '{}': {}", funnel, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Checks a flow for configuration mistakes that would make it misbehave at
+/// runtime. An empty result means the flow may be started.
+pub fn validate(flow: &FlowDefinition) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for node in &flow.processors {
+        for error in node.context.validate_against(&node.processor.properties()) {
+            let processor = node.name().to_string();
+            errors.push(match error {
+                PropertyError::Missing { property } => ValidationError::MissingProperty {
+                    processor,
+                    property,
+                },
+                PropertyError::Invalid {
+                    property, reason, ..
+                } => ValidationError::InvalidProperty {
+                    processor,
+                    property,
+                    reason,
+                },
+            });
+        }
+        if let Some(expression) = node.context.get_property(CRON_EXPRESSION) {
+            if let Err(e) = CronSchedule::parse(expression) {
+                errors.push(ValidationError::InvalidProperty {
+                    processor: node.name().to_string(),
+                    property: CRON_EXPRESSION.to_string(),
+                    reason: e.to_string(),
+                });
+            }
+        }
+        if let Some(value) = node.context.get_property(EXECUTION_TIMEOUT) {
+            if let Err(reason) = parse_execution_timeout(value) {
+                errors.push(ValidationError::InvalidProperty {
+                    processor: node.name().to_string(),
+                    property: EXECUTION_TIMEOUT.to_string(),
+                    reason,
+                });
+            }
+        }
+        if let Some(value) = node.context.get_property(CONCURRENT_TASKS) {
+            let reason = match parse_concurrent_tasks(value) {
+                Ok(tasks) if tasks > 1 && !node.processor.supports_concurrent_tasks() => Some(
+                    format!("{} runs one task at a time", node.processor.get_name()),
+                ),
+                Ok(_) => None,
+                Err(reason) => Some(reason),
+            };
+            if let Some(reason) = reason {
+                errors.push(ValidationError::InvalidProperty {
+                    processor: node.name().to_string(),
+                    property: CONCURRENT_TASKS.to_string(),
+                    reason,
+                });
+            }
+        }
+        for relationship in node.relationships() {
+            let connected = flow
+                .connections
+                .iter()
+                .any(|c| c.source == node.name() && c.relationship == relationship.name);
+            if !connected && !node.auto_terminated.contains(&relationship.name) {
+                errors.push(ValidationError::UnconnectedRelationship {
+                    processor: node.name().to_string(),
+                    relationship: relationship.name.clone(),
+                });
+            }
+        }
+    }
+
+    for connection in &flow.connections {
+        for endpoint in [&connection.source, &connection.destination] {
+            if flow.processor(endpoint).is_none() {
+                errors.push(ValidationError::UnknownProcessor {
+                    connection: connection.name.clone(),
+                    processor: endpoint.clone(),
+                });
+            }
+        }
+        if let Some(source) = flow.processor(&connection.source) {
+            let defined = source
+                .relationships()
+                .iter()
+                .any(|r| r.name == connection.relationship);
+            if !defined {
+                errors.push(ValidationError::UnknownRelationship {
+                    connection: connection.name.clone(),
+                    processor: connection.source.clone(),
+                    relationship: connection.relationship.clone(),
+                });
+            }
+        }
+        if connection.expiration == Some(Duration::ZERO) {
+            errors.push(ValidationError::ZeroExpiration {
+                connection: connection.name.clone(),
+            });
+        }
+    }
+
+    errors.extend(invalid_funnels(flow));
+    errors.extend(unbounded_cycles(flow));
+    errors
+}
+
+// Each funnel is one queue, so its connections must describe the same one.
+fn invalid_funnels(flow: &FlowDefinition) -> Vec<ValidationError> {
+    let mut first: HashMap<&str, &ConnectionDefinition> = HashMap::new();
+    let mut errors = Vec::new();
+    for connection in &flow.connections {
+        let Some(funnel) = connection.funnel.as_deref() else {
+            continue;
+        };
+        let mut invalid = |reason: String| {
+            errors.push(ValidationError::InvalidFunnel {
+                funnel: funnel.to_string(),
+                reason,
+            })
+        };
+        if connection.backpressure_bytes.is_some() || connection.expiration.is_some() {
+            invalid(format!(
+                "connection '{}' sets a byte limit or expiration, which funnels do not support",
+                connection.name
+            ));
+        }
+        let leader = *first.entry(funnel).or_insert(connection);
+        if connection.destination != leader.destination {
+            invalid(format!(
+                "connection '{}' leads to '{}' but '{}' leads to '{}'",
+                connection.name, connection.destination, leader.name, leader.destination
+            ));
+        }
+        if connection.backpressure_threshold != leader.backpressure_threshold {
+            invalid(format!(
+                "connections '{}' and '{}' disagree on backpressure",
+                leader.name, connection.name
+            ));
+        }
+    }
+    errors
+}
+
+// A cycle is safe as long as at least one of its queues has a backpressure
+// threshold, so only the graph of unbounded connections is searched.
+fn unbounded_cycles(flow: &FlowDefinition) -> Vec<ValidationError> {
+    let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+    for connection in &flow.connections {
+        if connection.backpressure_threshold.is_none() && connection.backpressure_bytes.is_none() {
+            edges
+                .entry(connection.source.as_str())
+                .or_default()
+                .push(connection.destination.as_str());
+        }
+    }
+
+    let mut finished: Vec<&str> = Vec::new();
+    let mut errors = Vec::new();
+    for node in &flow.processors {
+        let mut path = Vec::new();
+        visit(node.name(), &edges, &mut path, &mut finished, &mut errors);
+    }
+    errors
+}
+
+fn visit<'a>(
+    name: &'a str,
+    edges: &HashMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    finished: &mut Vec<&'a str>,
+    errors: &mut Vec<ValidationError>,
+) {
+    if finished.contains(&name) {
+        return;
+    }
+    if let Some(start) = path.iter().position(|n| *n == name) {
+        let mut processors: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+        processors.push(name.to_string());
+        errors.push(ValidationError::UnboundedCycle { processors });
+        return;
+    }
+    path.push(name);
+    for next in edges.get(name).into_iter().flatten() {
+        visit(next, edges, path, finished, errors);
+    }
+    path.pop();
+    finished.push(name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::FlowController;
+    use crate::flow::{ConnectionDefinition, ProcessorNode};
+    use crate::processor::{FileProcessor, Processor, ProcessorError};
+    use crate::processor_context::ProcessorContext;
+    use crate::processors::detect_duplicate::{DetectDuplicate, CACHE_ENTRY_IDENTIFIER};
+    use crate::processors::get_file::{GetFileProcessor, BATCH_SIZE, INPUT_DIRECTORY};
+    use crate::property::PropertyDescriptor;
+    use crate::relationship::Relationship;
+    use crate::session::ProcessSession;
+
+    struct NeedsDirectory;
+
+    impl Processor for NeedsDirectory {
+        fn on_trigger(
+            &self,
+            _context: &ProcessorContext,
+            _session: &mut ProcessSession,
+        ) -> Result<(), ProcessorError> {
+            Ok(())
+        }
+
+        fn get_name(&self) -> &'static str {
+            "NeedsDirectory"
+        }
+
+        fn properties(&self) -> Vec<PropertyDescriptor> {
+            vec![PropertyDescriptor::new("input.directory", "Directory to read").required()]
+        }
+
+        fn relationships(&self) -> Vec<Relationship> {
+            vec![Relationship::success()]
+        }
+    }
+
+    #[test]
+    fn test_missing_required_property() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("reader", NeedsDirectory).auto_terminate("success"));
+
+        let errors = validate(&flow);
+        assert_eq!(
+            errors,
+            vec![ValidationError::MissingProperty {
+                processor: "reader".to_string(),
+                property: "input.directory".to_string(),
+            }]
+        );
+
+        let mut controller = FlowController::new(flow);
+        assert_eq!(controller.start().unwrap_err(), errors);
+    }
+
+    #[test]
+    fn test_unconnected_relationship() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("first", FileProcessor::new()));
+        flow.add_processor(
+            ProcessorNode::new("second", FileProcessor::new()).auto_terminate("success"),
+        );
+
+        let errors = validate(&flow);
+        assert_eq!(
+            errors,
+            vec![ValidationError::UnconnectedRelationship {
+                processor: "first".to_string(),
+                relationship: "success".to_string(),
+            }]
+        );
+
+        flow.add_connection(ConnectionDefinition::new(
+            "first-to-second",
+            "first",
+            "success",
+            "second",
+        ));
+        assert!(validate(&flow).is_empty());
+    }
+
+    #[test]
+    fn test_cycle_needs_backpressure() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("a", FileProcessor::new()));
+        flow.add_processor(ProcessorNode::new("b", FileProcessor::new()));
+        flow.add_connection(ConnectionDefinition::new("a-to-b", "a", "success", "b"));
+        flow.add_connection(ConnectionDefinition::new("b-to-a", "b", "success", "a"));
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::UnboundedCycle {
+                processors: vec!["a".to_string(), "b".to_string(), "a".to_string()],
+            }]
+        );
+
+        flow.connections[1] =
+            ConnectionDefinition::new("b-to-a", "b", "success", "a").with_backpressure(100);
+        assert!(validate(&flow).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_funnel() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("a", FileProcessor::new()));
+        flow.add_processor(ProcessorNode::new("b", FileProcessor::new()).auto_terminate("success"));
+        flow.add_processor(ProcessorNode::new("c", FileProcessor::new()).auto_terminate("success"));
+        flow.add_connection(
+            ConnectionDefinition::new("a-to-b", "a", "success", "b").through_funnel("merge"),
+        );
+        flow.add_connection(
+            ConnectionDefinition::new("b-to-c", "b", "success", "c")
+                .with_backpressure(10)
+                .through_funnel("merge"),
+        );
+        assert_eq!(
+            validate(&flow),
+            vec![
+                ValidationError::InvalidFunnel {
+                    funnel: "merge".to_string(),
+                    reason: "connection 'b-to-c' leads to 'c' but 'a-to-b' leads to 'b'"
+                        .to_string(),
+                },
+                ValidationError::InvalidFunnel {
+                    funnel: "merge".to_string(),
+                    reason: "connections 'a-to-b' and 'b-to-c' disagree on backpressure"
+                        .to_string(),
+                },
+            ]
+        );
+
+        flow.connections[1] = ConnectionDefinition::new("b-to-c", "b", "success", "c")
+            .with_expiration(Duration::from_secs(1))
+            .through_funnel("other");
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidFunnel {
+                funnel: "other".to_string(),
+                reason: "connection 'b-to-c' sets a byte limit or expiration, which funnels do not support"
+                    .to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_zero_expiration() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(ProcessorNode::new("a", FileProcessor::new()));
+        flow.add_processor(ProcessorNode::new("b", FileProcessor::new()).auto_terminate("success"));
+        flow.add_connection(
+            ConnectionDefinition::new("a-to-b", "a", "success", "b")
+                .with_expiration(Duration::ZERO),
+        );
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::ZeroExpiration {
+                connection: "a-to-b".to_string(),
+            }]
+        );
+
+        flow.connections[0] = ConnectionDefinition::new("a-to-b", "a", "success", "b")
+            .with_expiration(Duration::from_millis(1));
+        assert!(validate(&flow).is_empty());
+    }
+
+    #[test]
+    fn test_sensitive_value_is_redacted_from_errors() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("read", GetFileProcessor::new())
+                .with_property(INPUT_DIRECTORY, "/data/in")
+                .with_sensitive_property(BATCH_SIZE, "hunter2")
+                .auto_terminate("success"),
+        );
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidProperty {
+                processor: "read".to_string(),
+                property: BATCH_SIZE.to_string(),
+                reason: "must be an integer in the range 1..=10000".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_cron_expression() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("nightly", FileProcessor::new())
+                .cron_schedule("0 25 * * *")
+                .auto_terminate("success"),
+        );
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidProperty {
+                processor: "nightly".to_string(),
+                property: CRON_EXPRESSION.to_string(),
+                reason: "invalid hour field '25': 25 is outside 0-23".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_execution_timeout() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("slow", FileProcessor::new())
+                .with_property(EXECUTION_TIMEOUT, "5s")
+                .auto_terminate("success"),
+        );
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidProperty {
+                processor: "slow".to_string(),
+                property: EXECUTION_TIMEOUT.to_string(),
+                reason: "'5s' is not a positive number of milliseconds".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_concurrent_tasks() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("parallel", FileProcessor::new())
+                .with_property(CONCURRENT_TASKS, "0")
+                .auto_terminate("success"),
+        );
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidProperty {
+                processor: "parallel".to_string(),
+                property: CONCURRENT_TASKS.to_string(),
+                reason: "'0' is not a positive number of tasks".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_concurrent_tasks_need_support() {
+        let mut flow = FlowDefinition::new();
+        flow.add_processor(
+            ProcessorNode::new("dedupe", DetectDuplicate::new())
+                .concurrent_tasks(4)
+                .with_property(CACHE_ENTRY_IDENTIFIER, "${hash.value}")
+                .auto_terminate("non-duplicate")
+                .auto_terminate("duplicate")
+                .auto_terminate("failure"),
+        );
+        flow.add_processor(
+            ProcessorNode::new("single", DetectDuplicate::new())
+                .concurrent_tasks(1)
+                .with_property(CACHE_ENTRY_IDENTIFIER, "${hash.value}")
+                .auto_terminate("non-duplicate")
+                .auto_terminate("duplicate")
+                .auto_terminate("failure"),
+        );
+
+        assert_eq!(
+            validate(&flow),
+            vec![ValidationError::InvalidProperty {
+                processor: "dedupe".to_string(),
+                property: CONCURRENT_TASKS.to_string(),
+                reason: "DetectDuplicate runs one task at a time".to_string(),
+            }]
+        );
+    }
+}
@@ -1,36 +1,133 @@
-use std::io;
+mod unit;
 
-fn main() {
-    println!("Please input temperature in Celsius.");
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+use unit::{convert, Unit};
 
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+const PROMPT: &str = "Please input temperature in Celsius.";
+const QUIT: &str = "q";
+const DEFAULT_PRECISION: usize = 2;
 
-    // Trim the input to remove whitespace and newlines
-    let input = input.trim();
-
-    // Parse the input string into a f32
-    let temperature: f32 = match input.parse() {
-        Ok(num) => num,
-        Err(e) => {
-            println!("Failed to convert: {}", e);
-            return;
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let repeat = args.iter().any(|arg| arg == "--repeat");
+    let output = match output_from_args(&args) {
+        Ok(output) => output,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
         }
     };
 
-    let fh = celsius_to_fahrenheit(temperature);
-    println!("Celsius {}°C is {}°F", temperature, fh);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let result = if repeat {
+        run_loop(stdin.lock(), &mut stdout, output)
+    } else {
+        run_once(stdin.lock(), &mut stdout, output)
+    };
+    result.expect("Failed to read line");
 }
 
-fn celsius_to_fahrenheit(temperature: f32) -> f32 {
-    (1.8 * temperature) + 32.0
+// How converted temperatures are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Output {
+    unit: Unit,
+    precision: usize,
+}
+
+// `--to UNIT` picks the target scale (Fahrenheit by default) and
+// `--precision N` the decimal places of the converted value.
+fn output_from_args(args: &[String]) -> Result<Output, String> {
+    Ok(Output {
+        unit: option_from_args(args, "--to", Unit::Fahrenheit, "a unit (C, F, K, Ra or Ré)")?,
+        precision: option_from_args(
+            args,
+            "--precision",
+            DEFAULT_PRECISION,
+            "a number of decimal places",
+        )?,
+    })
+}
+
+fn option_from_args<T: FromStr>(
+    args: &[String],
+    name: &str,
+    default: T,
+    expected: &str,
+) -> Result<T, String> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(default),
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| format!("{} expects {}", name, expected)),
+    }
+}
+
+// Reads a single temperature and converts it. Nothing is printed on EOF.
+fn run_once(mut input: impl BufRead, out: &mut impl Write, output: Output) -> io::Result<()> {
+    writeln!(out, "{}", PROMPT)?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? > 0 {
+        convert_line(&line, out, output)?;
+    }
+    Ok(())
+}
+
+// Keeps prompting and converting until the user enters `q` or input ends.
+fn run_loop(mut input: impl BufRead, out: &mut impl Write, output: Output) -> io::Result<()> {
+    loop {
+        writeln!(out, "{} (or '{}' to quit)", PROMPT, QUIT)?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim() == QUIT {
+            return Ok(());
+        }
+        convert_line(&line, out, output)?;
+    }
+}
+
+// The shared read-convert-print step: parses one line and prints either the
+// conversion or why it failed.
+fn convert_line(line: &str, out: &mut impl Write, output: Output) -> io::Result<()> {
+    // Trim the input to remove whitespace and newlines
+    let input = line.trim();
+
+    // Parse the input string into a f64
+    match input.parse::<f64>() {
+        Ok(temperature) => {
+            let converted = convert(temperature, Unit::Celsius, output.unit);
+            writeln!(
+                out,
+                "Celsius {}°C is {}{}",
+                temperature,
+                format_temperature(converted, output.precision),
+                output.unit
+            )
+        }
+        Err(e) => writeln!(out, "Failed to convert: {}", e),
+    }
+}
+
+// Rounds to `precision` decimal places. A value that rounds to zero is shown
+// without a minus sign.
+fn format_temperature(value: f64, precision: usize) -> String {
+    let formatted = format!("{:.*}", precision, value);
+    match formatted.strip_prefix('-') {
+        Some(unsigned) if unsigned.chars().all(|c| c == '0' || c == '.') => unsigned.to_string(),
+        _ => formatted,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use unit::celsius_to_fahrenheit;
+
+    const FAHRENHEIT_2: Output = Output {
+        unit: Unit::Fahrenheit,
+        precision: 2,
+    };
 
     #[test]
     fn test_celsius_to_fahrenheit() {
@@ -40,4 +137,105 @@ mod tests {
         assert_eq!(celsius_to_fahrenheit(37.0), 98.6);
         assert_eq!(celsius_to_fahrenheit(25.0), 77.0);
     }
+
+    #[test]
+    fn test_format_temperature_rounds() {
+        assert_eq!(format_temperature(98.60000000001, 2), "98.60");
+        assert_eq!(format_temperature(98.456, 2), "98.46");
+        assert_eq!(format_temperature(98.454, 2), "98.45");
+        assert_eq!(format_temperature(77.5, 0), "78");
+        assert_eq!(format_temperature(32.0, 3), "32.000");
+    }
+
+    #[test]
+    fn test_format_temperature_negative() {
+        assert_eq!(format_temperature(-40.126, 2), "-40.13");
+        assert_eq!(format_temperature(-17.7777, 1), "-17.8");
+        assert_eq!(format_temperature(-459.664, 2), "-459.66");
+        assert_eq!(format_temperature(-0.001, 2), "0.00");
+    }
+
+    #[test]
+    fn test_output_from_args() {
+        let args = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(output_from_args(&args(&["--repeat"])), Ok(FAHRENHEIT_2));
+        assert_eq!(
+            output_from_args(&args(&["--precision", "0", "--repeat", "--to", "Ra"])),
+            Ok(Output {
+                unit: Unit::Rankine,
+                precision: 0
+            })
+        );
+        assert!(output_from_args(&args(&["--precision"])).is_err());
+        assert!(output_from_args(&args(&["--precision", "-1"])).is_err());
+        assert!(output_from_args(&args(&["--to", "X"])).is_err());
+    }
+
+    fn results(output: Vec<u8>) -> Vec<String> {
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with(PROMPT))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_loop_stops_at_quit() {
+        let mut output = Vec::new();
+        run_loop(
+            "0\nwarm\n 100 \nq\n37\n".as_bytes(),
+            &mut output,
+            FAHRENHEIT_2,
+        )
+        .unwrap();
+        assert_eq!(
+            results(output),
+            vec![
+                "Celsius 0°C is 32.00°F",
+                "Failed to convert: invalid float literal",
+                "Celsius 100°C is 212.00°F",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loop_and_single_shot_stop_at_eof() {
+        let mut output = Vec::new();
+        let fahrenheit_1 = Output {
+            precision: 1,
+            ..FAHRENHEIT_2
+        };
+        run_loop("-40\n37".as_bytes(), &mut output, fahrenheit_1).unwrap();
+        assert_eq!(
+            results(output),
+            vec!["Celsius -40°C is -40.0°F", "Celsius 37°C is 98.6°F"]
+        );
+
+        let mut output = Vec::new();
+        run_once("".as_bytes(), &mut output, FAHRENHEIT_2).unwrap();
+        assert!(results(output).is_empty());
+    }
+
+    #[test]
+    fn test_other_target_units() {
+        let mut output = Vec::new();
+        let rankine = Output {
+            unit: Unit::Rankine,
+            precision: 2,
+        };
+        run_once("0\n".as_bytes(), &mut output, rankine).unwrap();
+        assert_eq!(results(output), vec!["Celsius 0°C is 491.67°Ra"]);
+
+        let mut output = Vec::new();
+        let reaumur = Output {
+            unit: Unit::Reaumur,
+            precision: 1,
+        };
+        run_loop("100\n-40\n".as_bytes(), &mut output, reaumur).unwrap();
+        assert_eq!(
+            results(output),
+            vec!["Celsius 100°C is 80.0°Ré", "Celsius -40°C is -32.0°Ré"]
+        );
+    }
 }
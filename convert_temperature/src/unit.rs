@@ -0,0 +1,167 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Every conversion goes through Celsius: `convert` turns the value into
+// Celsius with the `*_to_celsius` function of the source scale, then into the
+// target scale with the matching `celsius_to_*`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+    Rankine,
+    Reaumur,
+}
+
+impl Unit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Kelvin => "K",
+            Unit::Rankine => "°Ra",
+            Unit::Reaumur => "°Ré",
+        }
+    }
+
+    fn celsius_from(self, value: f64) -> f64 {
+        match self {
+            Unit::Celsius => value,
+            Unit::Fahrenheit => fahrenheit_to_celsius(value),
+            Unit::Kelvin => kelvin_to_celsius(value),
+            Unit::Rankine => rankine_to_celsius(value),
+            Unit::Reaumur => reaumur_to_celsius(value),
+        }
+    }
+
+    fn celsius_to(self, celsius: f64) -> f64 {
+        match self {
+            Unit::Celsius => celsius,
+            Unit::Fahrenheit => celsius_to_fahrenheit(celsius),
+            Unit::Kelvin => celsius_to_kelvin(celsius),
+            Unit::Rankine => celsius_to_rankine(celsius),
+            Unit::Reaumur => celsius_to_reaumur(celsius),
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+// Accepts the symbol with or without the degree sign, or the full name.
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim_start_matches('°').to_lowercase().as_str() {
+            "c" | "celsius" => Ok(Unit::Celsius),
+            "f" | "fahrenheit" => Ok(Unit::Fahrenheit),
+            "k" | "kelvin" => Ok(Unit::Kelvin),
+            "ra" | "rankine" => Ok(Unit::Rankine),
+            "ré" | "re" | "réaumur" | "reaumur" => Ok(Unit::Reaumur),
+            _ => Err(format!("unknown temperature unit '{}'", s)),
+        }
+    }
+}
+
+pub fn convert(value: f64, from: Unit, to: Unit) -> f64 {
+    to.celsius_to(from.celsius_from(value))
+}
+
+pub fn celsius_to_fahrenheit(celsius: f64) -> f64 {
+    celsius * 9.0 / 5.0 + 32.0
+}
+
+pub fn fahrenheit_to_celsius(fahrenheit: f64) -> f64 {
+    (fahrenheit - 32.0) * 5.0 / 9.0
+}
+
+pub fn celsius_to_kelvin(celsius: f64) -> f64 {
+    celsius + 273.15
+}
+
+pub fn kelvin_to_celsius(kelvin: f64) -> f64 {
+    kelvin - 273.15
+}
+
+// Rankine is Fahrenheit-sized degrees counted from absolute zero.
+pub fn celsius_to_rankine(celsius: f64) -> f64 {
+    (celsius + 273.15) * 9.0 / 5.0
+}
+
+pub fn rankine_to_celsius(rankine: f64) -> f64 {
+    rankine * 5.0 / 9.0 - 273.15
+}
+
+// Réaumur puts water's boiling point at 80°Ré.
+pub fn celsius_to_reaumur(celsius: f64) -> f64 {
+    celsius * 4.0 / 5.0
+}
+
+pub fn reaumur_to_celsius(reaumur: f64) -> f64 {
+    reaumur * 5.0 / 4.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNITS: [Unit; 5] = [
+        Unit::Celsius,
+        Unit::Fahrenheit,
+        Unit::Kelvin,
+        Unit::Rankine,
+        Unit::Reaumur,
+    ];
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_rankine_reference_values() {
+        assert_close(convert(0.0, Unit::Celsius, Unit::Rankine), 491.67);
+        assert_close(convert(100.0, Unit::Celsius, Unit::Rankine), 671.67);
+        assert_close(convert(0.0, Unit::Kelvin, Unit::Rankine), 0.0);
+        assert_close(convert(32.0, Unit::Fahrenheit, Unit::Rankine), 491.67);
+        assert_close(convert(459.67, Unit::Rankine, Unit::Fahrenheit), 0.0);
+        assert_close(rankine_to_celsius(491.67), 0.0);
+    }
+
+    #[test]
+    fn test_reaumur_reference_values() {
+        assert_close(convert(0.0, Unit::Celsius, Unit::Reaumur), 0.0);
+        assert_close(convert(100.0, Unit::Celsius, Unit::Reaumur), 80.0);
+        assert_close(convert(212.0, Unit::Fahrenheit, Unit::Reaumur), 80.0);
+        assert_close(convert(0.0, Unit::Kelvin, Unit::Reaumur), -218.52);
+        assert_close(reaumur_to_celsius(-32.0), -40.0);
+    }
+
+    #[test]
+    fn test_every_pair_round_trips() {
+        for from in UNITS {
+            for to in UNITS {
+                assert_close(convert(convert(36.6, from, to), to, from), 36.6);
+            }
+            assert_close(convert(-12.5, from, from), -12.5);
+        }
+    }
+
+    #[test]
+    fn test_parse_unit() {
+        for unit in UNITS {
+            assert_eq!(unit.symbol().parse::<Unit>(), Ok(unit));
+        }
+        assert_eq!("rankine".parse::<Unit>(), Ok(Unit::Rankine));
+        assert_eq!("Re".parse::<Unit>(), Ok(Unit::Reaumur));
+        assert!("X".parse::<Unit>().is_err());
+    }
+}
@@ -1,66 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 // each duck has this two traits
 // a duck can display itself on screen
 // also can swim
+// behaviors return what they would say and main prints it, so they can be tested
 trait DuckInterface {
-    fn display(&self);
-    fn swim(&self);
+    fn display(&self) -> String;
+    fn swim(&self) -> String;
 }
 // there are ducks that they cannot fly
+// name() is what a saved duck remembers the behavior by, see BehaviorRegistry
 trait FlyBehavior {
-    fn fly(&self);
+    fn name(&self) -> &'static str;
+    fn fly(&self) -> String;
 }
 // there ducks with different kind of quack
 trait QuackBehavior {
-    fn quack(&self);
+    fn name(&self) -> &'static str;
+    fn quack(&self) -> String;
 }
 
 struct FlyWithWings;
 
 impl FlyBehavior for FlyWithWings {
-    fn fly(&self) {
-        println!("I'm flying with wings!");
+    fn name(&self) -> &'static str {
+        "FlyWithWings"
+    }
+    fn fly(&self) -> String {
+        "I'm flying with wings!".to_string()
     }
 }
  
 struct FlyNoWay;
 
 impl FlyBehavior for FlyNoWay {
-    fn fly(&self) {
-        println!("I can't fly.");
+    fn name(&self) -> &'static str {
+        "FlyNoWay"
+    }
+    fn fly(&self) -> String {
+        "I can't fly.".to_string()
     }
 }
  
 struct FlyRocketPowered;
 
 impl FlyBehavior for FlyRocketPowered {
-    fn fly(&self) {
-        println!("I'm flying with a rocket!");
+    fn name(&self) -> &'static str {
+        "FlyRocketPowered"
+    }
+    fn fly(&self) -> String {
+        "I'm flying with a rocket!".to_string()
     }
 }
  
 struct Quack;
 
 impl QuackBehavior for Quack {
-    fn quack(&self) {
-        println!("Quack!");
+    fn name(&self) -> &'static str {
+        "Quack"
+    }
+    fn quack(&self) -> String {
+        "Quack!".to_string()
     }
 }
  
 struct MuteQuack;
 impl QuackBehavior for MuteQuack {
-    fn quack(&self) {
-        println!("...");
+    fn name(&self) -> &'static str {
+        "MuteQuack"
+    }
+    fn quack(&self) -> String {
+        "...".to_string()
     }
 }
  
 struct Squeak;
 impl QuackBehavior for Squeak {
-    fn quack(&self) {
-        println!("Squeak!");
+    fn name(&self) -> &'static str {
+        "Squeak"
+    }
+    fn quack(&self) -> String {
+        "Squeak!".to_string()
     }
 }
 
+// a squeak that wears out: it uses the wrapped behavior for a fixed
+// number of quacks and then swaps itself over to MuteQuack for good
+struct FadingQuack {
+    current: RefCell<Rc<dyn QuackBehavior>>,
+    remaining: RefCell<u32>,
+}
+
+impl FadingQuack {
+    fn new(inner: Rc<dyn QuackBehavior>, quacks: u32) -> Self {
+        FadingQuack {
+            current: RefCell::new(inner),
+            remaining: RefCell::new(quacks),
+        }
+    }
+
+    fn remaining(&self) -> u32 {
+        *self.remaining.borrow()
+    }
+}
+
+// a saved FadingQuack comes back as a fresh squeak; how worn it was is not kept
+impl QuackBehavior for FadingQuack {
+    fn name(&self) -> &'static str {
+        "FadingQuack"
+    }
+    fn quack(&self) -> String {
+        let mut remaining = self.remaining.borrow_mut();
+        if *remaining == 0 {
+            *self.current.borrow_mut() = Rc::new(MuteQuack);
+        } else {
+            *remaining -= 1;
+        }
+        self.current.borrow().quack()
+    }
+}
+
+// how many squeaks a FadingQuack built by the registry has in it
+const FADING_SQUEAKS: u32 = 3;
+
+// --- Behavior Registry ---
+// maps behavior names back to a way of building the behavior,
+// so a duck can be rebuilt from the names in its saved config
+struct BehaviorRegistry {
+    fly: HashMap<&'static str, fn() -> Rc<dyn FlyBehavior>>,
+    quack: HashMap<&'static str, fn() -> Rc<dyn QuackBehavior>>,
+}
+
+impl BehaviorRegistry {
+    fn new() -> Self {
+        BehaviorRegistry {
+            fly: HashMap::new(),
+            quack: HashMap::new(),
+        }
+    }
+
+    // every behavior in this file
+    fn with_defaults() -> Self {
+        let mut registry = BehaviorRegistry::new();
+        registry.register_fly("FlyWithWings", || Rc::new(FlyWithWings));
+        registry.register_fly("FlyNoWay", || Rc::new(FlyNoWay));
+        registry.register_fly("FlyRocketPowered", || Rc::new(FlyRocketPowered));
+        registry.register_quack("Quack", || Rc::new(Quack));
+        registry.register_quack("MuteQuack", || Rc::new(MuteQuack));
+        registry.register_quack("Squeak", || Rc::new(Squeak));
+        registry.register_quack("FadingQuack", || Rc::new(FadingQuack::new(Rc::new(Squeak), FADING_SQUEAKS)));
+        registry
+    }
+
+    fn register_fly(&mut self, name: &'static str, factory: fn() -> Rc<dyn FlyBehavior>) {
+        self.fly.insert(name, factory);
+    }
+
+    fn register_quack(&mut self, name: &'static str, factory: fn() -> Rc<dyn QuackBehavior>) {
+        self.quack.insert(name, factory);
+    }
+
+    fn fly(&self, name: &str) -> Option<Rc<dyn FlyBehavior>> {
+        self.fly.get(name).map(|factory| factory())
+    }
+
+    fn quack(&self, name: &str) -> Option<Rc<dyn QuackBehavior>> {
+        self.quack.get(name).map(|factory| factory())
+    }
+}
+
+// what gets saved of a duck: its name and the names of its behaviors
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct DuckConfig {
+    name: String,
+    fly: String,
+    quack: String,
+}
+
 struct Duck {
     fly_behavior: Rc<dyn FlyBehavior>,
     quack_behavior: Rc<dyn QuackBehavior>,
@@ -68,11 +186,11 @@ struct Duck {
 }
 
 impl DuckInterface for Duck {
-    fn display(&self) {
-        println!("Hello, I am {}!", self.name);
+    fn display(&self) -> String {
+        format!("Hello, I am {}!", self.name)
     }
-    fn swim(&self){
-        println!("I can swim!");
+    fn swim(&self) -> String {
+        "I can swim!".to_string()
     }
 }
  
@@ -85,12 +203,12 @@ impl Duck {
         }
     }
  
-    fn perform_fly(&self) {
-        self.fly_behavior.fly();
+    fn perform_fly(&self) -> String {
+        self.fly_behavior.fly()
     }
  
-    fn perform_quack(&self) {
-        self.quack_behavior.quack();
+    fn perform_quack(&self) -> String {
+        self.quack_behavior.quack()
     }
  
     fn set_flybehavior(&mut self, fb: Rc<dyn FlyBehavior>) {
@@ -100,6 +218,33 @@ impl Duck {
     fn set_quackbehavior(&mut self, qb: Rc<dyn QuackBehavior>) {
         self.quack_behavior = qb;
     }
+
+    fn config(&self) -> DuckConfig {
+        DuckConfig {
+            name: self.name.clone(),
+            fly: self.fly_behavior.name().to_string(),
+            quack: self.quack_behavior.name().to_string(),
+        }
+    }
+
+    fn from_config(config: &DuckConfig, registry: &BehaviorRegistry) -> Result<Self, String> {
+        let fly = registry
+            .fly(&config.fly)
+            .ok_or_else(|| format!("unknown fly behavior '{}'", config.fly))?;
+        let quack = registry
+            .quack(&config.quack)
+            .ok_or_else(|| format!("unknown quack behavior '{}'", config.quack))?;
+        Ok(Duck::new(&config.name, fly, quack))
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(&self.config()).expect("a duck config is always valid JSON")
+    }
+
+    fn from_json(json: &str, registry: &BehaviorRegistry) -> Result<Self, String> {
+        let config: DuckConfig = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Duck::from_config(&config, registry)
+    }
 }
  
 // --- Duck Types ---
@@ -110,6 +255,10 @@ fn create_mallardduck() -> Duck {
 fn create_rubberduck() -> Duck {
     Duck::new("Rubber Duck", Rc::new(FlyNoWay), Rc::new(Squeak))
 }
+
+fn create_old_rubberduck(squeaks: Rc<FadingQuack>) -> Duck {
+    Duck::new("Old Rubber Duck", Rc::new(FlyNoWay), squeaks)
+}
  
 fn create_modelduck() -> Duck {
     Duck::new("Model Duck", Rc::new(FlyNoWay), Rc::new(MuteQuack))
@@ -118,25 +267,137 @@ fn create_modelduck() -> Duck {
 // --- Main Example ---
 fn main() {
     let mallard = create_mallardduck();
-    mallard.display();
-    mallard.perform_fly();
-    mallard.perform_quack();
+    println!("{}", mallard.display());
+    println!("{}", mallard.perform_fly());
+    println!("{}", mallard.perform_quack());
  
     println!("\n--- Rubber Duck ---");
     let rubberduck = create_rubberduck();
-    rubberduck.display();
-    rubberduck.perform_fly();
-    rubberduck.perform_quack();
+    println!("{}", rubberduck.display());
+    println!("{}", rubberduck.perform_fly());
+    println!("{}", rubberduck.perform_quack());
+
+    println!("\n--- Old Rubber Duck ---");
+    let squeaks = Rc::new(FadingQuack::new(Rc::new(Squeak), 2));
+    let old_rubberduck = create_old_rubberduck(squeaks.clone());
+    println!("{}", old_rubberduck.display());
+    for _ in 0..3 {
+        println!("{} squeaks left", squeaks.remaining());
+        println!("{}", old_rubberduck.perform_quack());
+    }
  
     println!("\n--- Model Duck ---");
     let mut modelduck = create_modelduck();
     
-    modelduck.display();
-    modelduck.perform_fly();
+    println!("{}", modelduck.display());
+    println!("{}", modelduck.perform_fly());
     println!("Upgrading model duck with rocket power and mute him");
     modelduck.set_flybehavior(Rc::new(FlyRocketPowered));
     modelduck.set_quackbehavior(Rc::new(MuteQuack));
-    modelduck.perform_fly();
-    modelduck.perform_quack();
-    modelduck.swim();
-}
\ No newline at end of file
+    println!("{}", modelduck.perform_fly());
+    println!("{}", modelduck.perform_quack());
+    println!("{}", modelduck.swim());
+
+    println!("\n--- Saved Mallard ---");
+    let saved = mallard.to_json();
+    println!("{}", saved);
+    let restored = Duck::from_json(&saved, &BehaviorRegistry::with_defaults()).expect("mallard config is valid");
+    println!("{}", restored.display());
+    println!("{}", restored.perform_fly());
+    println!("{}", restored.perform_quack());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // counts how often it was asked to quack
+    struct CountingQuack {
+        quacks: Rc<Cell<u32>>,
+    }
+
+    impl QuackBehavior for CountingQuack {
+        fn name(&self) -> &'static str {
+            "CountingQuack"
+        }
+        fn quack(&self) -> String {
+            self.quacks.set(self.quacks.get() + 1);
+            "Quack!".to_string()
+        }
+    }
+
+    #[test]
+    fn test_fly_behaviors() {
+        assert_eq!(FlyWithWings.fly(), "I'm flying with wings!");
+        assert_eq!(FlyNoWay.fly(), "I can't fly.");
+        assert_eq!(FlyRocketPowered.fly(), "I'm flying with a rocket!");
+    }
+
+    #[test]
+    fn test_quack_behaviors() {
+        assert_eq!(Quack.quack(), "Quack!");
+        assert_eq!(MuteQuack.quack(), "...");
+        assert_eq!(Squeak.quack(), "Squeak!");
+    }
+
+    #[test]
+    fn test_duck_interface() {
+        let mut modelduck = create_modelduck();
+        assert_eq!(modelduck.display(), "Hello, I am Model Duck!");
+        assert_eq!(modelduck.swim(), "I can swim!");
+        assert_eq!(modelduck.perform_fly(), "I can't fly.");
+        modelduck.set_flybehavior(Rc::new(FlyRocketPowered));
+        assert_eq!(modelduck.perform_fly(), "I'm flying with a rocket!");
+    }
+
+    #[test]
+    fn test_fading_quack_goes_silent_after_threshold() {
+        let quacks = Rc::new(Cell::new(0));
+        let fading = FadingQuack::new(Rc::new(CountingQuack { quacks: quacks.clone() }), 3);
+        assert_eq!(fading.remaining(), 3);
+
+        for expected in (0..3).rev() {
+            assert_eq!(fading.quack(), "Quack!");
+            assert_eq!(fading.remaining(), expected);
+        }
+        assert_eq!(quacks.get(), 3);
+
+        assert_eq!(fading.quack(), "...");
+        assert_eq!(fading.quack(), "...");
+        assert_eq!(fading.remaining(), 0);
+        assert_eq!(quacks.get(), 3);
+    }
+
+    fn round_trip(duck: &Duck) -> Duck {
+        Duck::from_json(&duck.to_json(), &BehaviorRegistry::with_defaults()).unwrap()
+    }
+
+    #[test]
+    fn test_mallard_round_trip() {
+        let mallard = create_mallardduck();
+        assert_eq!(
+            mallard.to_json(),
+            r#"{"name":"Mallard Duck","fly":"FlyWithWings","quack":"Quack"}"#
+        );
+        let restored = round_trip(&mallard);
+        assert_eq!(restored.config(), mallard.config());
+        assert_eq!(restored.fly_behavior.fly(), "I'm flying with wings!");
+        assert_eq!(restored.quack_behavior.quack(), "Quack!");
+    }
+
+    #[test]
+    fn test_rubberduck_round_trip() {
+        let restored = round_trip(&create_rubberduck());
+        assert_eq!(restored.name, "Rubber Duck");
+        assert_eq!(restored.fly_behavior.fly(), "I can't fly.");
+        assert_eq!(restored.quack_behavior.quack(), "Squeak!");
+    }
+
+    #[test]
+    fn test_unknown_behavior_is_rejected() {
+        let json = r#"{"name":"Robo Duck","fly":"FlyWithJets","quack":"Quack"}"#;
+        let error = Duck::from_json(json, &BehaviorRegistry::with_defaults()).err().unwrap();
+        assert_eq!(error, "unknown fly behavior 'FlyWithJets'");
+    }
+}
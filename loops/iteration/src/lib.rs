@@ -0,0 +1,86 @@
+use std::ops::Range;
+
+/// Collects the items in front of the first one matching `pred`, like the
+/// "print until I find l" loop in `main`. Everything is collected when no
+/// item matches.
+pub fn take_until<T, F>(items: &[T], mut pred: F) -> Vec<&T>
+where
+    F: FnMut(&T) -> bool,
+{
+    let mut taken = Vec::new();
+    for item in items {
+        if pred(item) {
+            break;
+        }
+        taken.push(item);
+    }
+    taken
+}
+
+/// The numbers in `range` that `keep` accepts, in order.
+pub fn filter_range<F>(range: Range<i32>, mut keep: F) -> Vec<i32>
+where
+    F: FnMut(i32) -> bool,
+{
+    let mut kept = Vec::new();
+    for n in range {
+        if keep(n) {
+            kept.push(n);
+        }
+    }
+    kept
+}
+
+pub fn evens(range: Range<i32>) -> Vec<i32> {
+    filter_range(range, |n| n % 2 == 0)
+}
+
+// `n % 2` is -1 for negative odd numbers, so test for "not even".
+pub fn odds(range: Range<i32>) -> Vec<i32> {
+    filter_range(range, |n| n % 2 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stops_before_first_match() {
+        let message = ['H', 'e', 'l', 'l', 'o'];
+        assert_eq!(take_until(&message, |&c| c == 'l'), vec![&'H', &'e']);
+        assert!(take_until(&message, |&c| c == 'H').is_empty());
+    }
+
+    #[test]
+    fn test_takes_everything_without_match() {
+        let numbers = [1, 3, 5, 7];
+        assert_eq!(take_until(&numbers, |n| n % 2 == 0), vec![&1, &3, &5, &7]);
+        let empty: [String; 0] = [];
+        assert!(take_until(&empty, |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_evens_and_odds() {
+        assert_eq!(evens(1..20), vec![2, 4, 6, 8, 10, 12, 14, 16, 18]);
+        assert_eq!(odds(-3..4), vec![-3, -1, 1, 3]);
+        assert_eq!(evens(-4..1), vec![-4, -2, 0]);
+    }
+
+    #[test]
+    fn test_all_even_range() {
+        assert_eq!(evens(4..5), vec![4]);
+        assert!(odds(4..5).is_empty());
+    }
+
+    #[test]
+    fn test_empty_ranges() {
+        assert!(evens(0..0).is_empty());
+        assert!(filter_range(5..5, |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_custom_predicate() {
+        assert_eq!(filter_range(1..31, |n| n % 3 == 0 && n % 5 == 0), vec![15, 30]);
+        assert_eq!(filter_range(1..50, |n| n * n > 2000), vec![45, 46, 47, 48, 49]);
+    }
+}
@@ -1,3 +1,5 @@
+use for_loop::{evens, take_until};
+
 fn main() {
     let message = ['H','e','l','l','o'];
     for item in message {
@@ -8,17 +10,12 @@ fn main() {
         println!("{} {}", index, item);
     }
     println!("I wanna print until i don't find l");
-    for (index, &item) in message.iter().enumerate() {
-        if item == 'l' {
-            break;
-        }
+    for (index, item) in take_until(&message, |&item| item == 'l').into_iter().enumerate() {
         println!("{} {}", index, item);
     }
     println!("i want to print the even number till 20");
-    for n in 1..20 {
-        if n % 2 == 0 {
-            println!("{}", n);
-        }
+    for n in evens(1..20) {
+        println!("{}", n);
     }
 
 }
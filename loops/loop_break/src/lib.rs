@@ -0,0 +1,47 @@
+/// Finds the first cell holding `target`, scanning row by row, and returns
+/// its `(row, column)`. The labeled `'search` loop lets the inner loop break
+/// out of both loops, with the coordinates as the loop's value, once found.
+pub fn find_in_grid(grid: &[Vec<i32>], target: i32) -> Option<(usize, usize)> {
+    let mut row = 0;
+    'search: loop {
+        if row >= grid.len() {
+            break None;
+        }
+        for (column, &value) in grid[row].iter().enumerate() {
+            if value == target {
+                break 'search Some((row, column));
+            }
+        }
+        row += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Vec<Vec<i32>> {
+        vec![vec![1, 2, 3], vec![4, 5], vec![], vec![7, 8, 9, 5]]
+    }
+
+    #[test]
+    fn test_found() {
+        let grid = grid();
+        assert_eq!(find_in_grid(&grid, 1), Some((0, 0)));
+        assert_eq!(find_in_grid(&grid, 3), Some((0, 2)));
+        assert_eq!(find_in_grid(&grid, 4), Some((1, 0)));
+        assert_eq!(find_in_grid(&grid, 9), Some((3, 2)));
+    }
+
+    #[test]
+    fn test_first_occurrence_wins() {
+        assert_eq!(find_in_grid(&grid(), 5), Some((1, 1)));
+    }
+
+    #[test]
+    fn test_not_found() {
+        assert_eq!(find_in_grid(&grid(), 6), None);
+        assert_eq!(find_in_grid(&[], 1), None);
+        assert_eq!(find_in_grid(&[vec![], vec![]], 1), None);
+    }
+}
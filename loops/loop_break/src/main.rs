@@ -1,3 +1,5 @@
+use loop_break::find_in_grid;
+
 fn main() {
     println!("Loop construct test!");
     let mut counter = 0;
@@ -17,5 +19,11 @@ fn main() {
         }
         cool_counter+=1;
     };
-    println!("The cool counter multipled for 10 is {}", result)
+    println!("The cool counter multipled for 10 is {}", result);
+    // a labeled loop lets us break out of nested loops at once
+    let grid = vec![vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]];
+    match find_in_grid(&grid, 6) {
+        Some((row, column)) => println!("Found 6 at row {} column {}", row, column),
+        None => println!("6 is not in the grid"),
+    }
 }
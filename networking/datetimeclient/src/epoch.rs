@@ -0,0 +1,73 @@
+// Seconds between 1900-01-01 (the TIME and NTP epoch) and 1970-01-01.
+pub const UNIX_OFFSET: i64 = 2_208_988_800;
+
+// Converts a 32-bit seconds-since-1900 value into Unix seconds.
+//
+// The counter wraps on 2036-02-07 06:28:16 UTC and the protocols carry no
+// era number. Values with the top bit clear would mean 1900..1968, which no
+// live server can send, so they are read as the era after the 2036 rollover.
+// This covers 1968-01-20 through 2104-02-26.
+pub fn from_1900_seconds(value: u32) -> i64 {
+    let seconds = if value & 0x8000_0000 == 0 {
+        value as i64 + (1 << 32)
+    } else {
+        value as i64
+    };
+    seconds - UNIX_OFFSET
+}
+
+// Formats Unix seconds as "YYYY-MM-DD HH:MM:SS UTC".
+pub fn format_utc(unix_seconds: i64) -> String {
+    let days = unix_seconds.div_euclid(86_400);
+    let seconds_of_day = unix_seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+// Proleptic Gregorian date for a day count since 1970-01-01
+// (Howard Hinnant's civil_from_days).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: u32) -> String {
+        format_utc(from_1900_seconds(value))
+    }
+
+    #[test]
+    fn test_known_values() {
+        assert_eq!(utc(2_208_988_800), "1970-01-01 00:00:00 UTC");
+        assert_eq!(utc(3_155_673_600), "2000-01-01 00:00:00 UTC");
+        assert_eq!(utc(3_908_988_800), "2023-11-14 22:13:20 UTC");
+        assert_eq!(utc(3_160_857_600), "2000-03-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn test_2036_rollover() {
+        assert_eq!(utc(u32::MAX), "2036-02-07 06:28:15 UTC");
+        assert_eq!(utc(0), "2036-02-07 06:28:16 UTC");
+        assert_eq!(utc(0x7FFF_FFFF), "2104-02-26 09:42:23 UTC");
+        assert_eq!(utc(0x8000_0000), "1968-01-20 03:14:08 UTC");
+    }
+}
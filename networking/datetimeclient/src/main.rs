@@ -1,25 +1,92 @@
 use tokio::io::{AsyncReadExt, BufReader};
 use tokio::net::TcpStream;
 use std::env;
+use std::future::Future;
+use std::io;
 use std::time::Duration;
 
-#[tokio::main]
-async fn main() {
-    // Get hostname from arguments or default to time.nist.gov
-    let hostname = env::args().nth(1).unwrap_or_else(|| "time.nist.gov".to_string());
+mod epoch;
+mod ntp;
+mod time_protocol;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+const DAYTIME_SERVERS: &[&str] = &["time.nist.gov", "time-a-g.nist.gov", "time-b-g.nist.gov"];
+const NTP_SERVERS: &[&str] = &["pool.ntp.org", "time.google.com", "time.cloudflare.com"];
+const TIME_SERVERS: &[&str] = &["time.nist.gov", "time-a-g.nist.gov", "time-b-g.nist.gov"];
 
+enum Mode {
+    Daytime,
+    Ntp,
+    Time,
+}
+
+async fn daytime(host: String) -> io::Result<String> {
     // Connect to port 13 (Daytime Protocol)
-    match tokio::time::timeout(Duration::from_secs(15), TcpStream::connect((hostname.as_str(), 13))).await {
-        Ok(Ok(stream)) => {
-            let mut reader = BufReader::new(stream);
-            let mut buffer = String::new();
+    let stream = TcpStream::connect((host.as_str(), 13)).await?;
+    let mut reader = BufReader::new(stream);
+    let mut buffer = String::new();
+    reader.read_to_string(&mut buffer).await?;
+    Ok(buffer)
+}
 
-            match reader.read_to_string(&mut buffer).await {
-                Ok(_) => println!("{}", buffer),
-                Err(e) => eprintln!("Failed to read from stream: {}", e),
+// Tries each server in turn and returns the first answer, reporting failures
+async fn query_with_fallback<T, F, Fut>(servers: &[String], query: F) -> Option<T>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    for server in servers {
+        match tokio::time::timeout(TIMEOUT, query(server.clone())).await {
+            Ok(Ok(answer)) => return Some(answer),
+            Ok(Err(e)) => eprintln!("{}: {}", server, e),
+            Err(_) => eprintln!("{}: timed out after {} seconds", server, TIMEOUT.as_secs()),
+        }
+    }
+    None
+}
+
+#[tokio::main]
+async fn main() {
+    // Usage: datetimeclient [--ntp | --time] [host...]; defaults to well-known servers
+    let mut mode = Mode::Daytime;
+    let mut servers = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--ntp" => mode = Mode::Ntp,
+            "--time" => mode = Mode::Time,
+            _ => servers.push(arg),
+        }
+    }
+    if servers.is_empty() {
+        let defaults = match mode {
+            Mode::Daytime => DAYTIME_SERVERS,
+            Mode::Ntp => NTP_SERVERS,
+            Mode::Time => TIME_SERVERS,
+        };
+        servers = defaults.iter().map(|s| s.to_string()).collect();
+    }
+
+    match mode {
+        Mode::Daytime => {
+            if let Some(answer) = query_with_fallback(&servers, daytime).await {
+                println!("{}", answer);
+                return;
+            }
+        }
+        Mode::Ntp => {
+            if let Some(unix_time) = query_with_fallback(&servers, |host| async move { ntp::query(&host).await }).await {
+                let seconds = unix_time.as_secs() as i64;
+                println!("{} (Unix time {}.{:06})", epoch::format_utc(seconds), seconds, unix_time.subsec_micros());
+                return;
+            }
+        }
+        Mode::Time => {
+            if let Some(unix_time) = query_with_fallback(&servers, time_protocol::query).await {
+                println!("{}", epoch::format_utc(unix_time));
+                return;
             }
         }
-        Ok(Err(e)) => eprintln!("Connection error: {}", e),
-        Err(_) => eprintln!("Connection timed out after 15 seconds"),
     }
-}
\ No newline at end of file
+    eprintln!("No server answered");
+    std::process::exit(1);
+}
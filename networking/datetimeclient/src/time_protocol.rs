@@ -0,0 +1,30 @@
+use crate::epoch;
+use std::io;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+pub const TIME_PORT: u16 = 37;
+
+// RFC 868: the server sends the time as 4 big-endian bytes of seconds since
+// 1900-01-01 and closes the connection.
+pub fn parse_response(bytes: [u8; 4]) -> i64 {
+    epoch::from_1900_seconds(u32::from_be_bytes(bytes))
+}
+
+pub async fn query(host: String) -> io::Result<i64> {
+    let mut stream = TcpStream::connect((host.as_str(), TIME_PORT)).await?;
+    let mut bytes = [0u8; 4];
+    stream.read_exact(&mut bytes).await?;
+    Ok(parse_response(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(parse_response([0x83, 0xAA, 0x7E, 0x80]), 0);
+        assert_eq!(parse_response([0xE8, 0xFE, 0x6F, 0x80]), 1_700_000_000);
+    }
+}
@@ -0,0 +1,646 @@
+use crate::batch::DEFAULT_CONCURRENCY;
+use crate::cache::{self, DEFAULT_TTL};
+use crate::matches::DEFAULT_STRATEGY;
+use crate::retry::DEFAULT_RETRIES;
+use crate::show::Listing;
+use crate::tls::{Transport, DEFAULT_TLS_PORT};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const DEFAULT_HOST: &str = "dict.org";
+pub const DEFAULT_PORT: u16 = 2628;
+pub const DEFAULT_DATABASE: &str = "eng-lat";
+// MATCH searches every database unless told otherwise.
+pub const DEFAULT_MATCH_DATABASE: &str = "*";
+// Environment variables consulted when --user or --password is not given.
+pub const USER_VARIABLE: &str = "DICT_USER";
+pub const PASSWORD_VARIABLE: &str = "DICT_SECRET";
+
+pub const USAGE: &str = "\
+usage: dictclient [options] <word>...
+       dictclient [options] --match[=<strategy>] <word>...
+       dictclient [options] --words-file <path>
+       dictclient [options] --interactive
+       dictclient [options] --list-databases | --list-strategies
+       dictclient [options] --server-info | --status
+
+Looks up each word in turn over one connection to a DICT server. On a
+terminal, definitions are wrapped to its width and colored, and output
+longer than the screen goes through $PAGER (default: less).
+
+options:
+  --host <name>        server to connect to (default: dict.org); give it more
+                       than once for fallbacks, tried in order
+  --port <number>      TCP port of the server (default: 2628, or 2629 with --tls)
+  --tls                encrypt the connection, checking the server's certificate
+  --insecure           with --tls, accept any certificate, e.g. a self-signed one
+  --mime               ask the server for MIME headers (OPTION MIME) and decode
+                       base64 or quoted-printable definitions; carries on
+                       without them if the server refuses
+  --db <name>          database to look words up in, '*' for all
+                       (default: eng-lat, or * with --match)
+  --match[=<strategy>] list matching headwords instead of definitions,
+                       e.g. --match=exact (default strategy: prefix)
+  --auto-match         when a word has no definition, list prefix matches
+  --no-suggest         do not suggest similar words when one has no definition
+  --cache-dir <dir>    where to cache definitions (default: $XDG_CACHE_HOME/dictclient
+                       or ~/.cache/dictclient)
+  --cache-ttl <age>    how long cached definitions are used, e.g. 12h or 30d;
+                       plain numbers are seconds (default: 7d)
+  --no-cache           neither read nor write the definition cache
+  --refresh            look every word up again, updating the cache
+  --verbose            report cache hits and misses on stderr
+  --user <name>        authenticate as this user (default: $DICT_USER)
+  --password <secret>  shared secret for --user (default: $DICT_SECRET, which
+                       keeps it out of the process list)
+  --timeout <seconds>  bound on connecting and on every read and write (default: 10)
+  --retries <count>    times to retry a server that times out, refuses the
+                       connection or is busy, backing off from 0.5s (default: 2)
+  --interactive        keep the connection open and read words and /commands
+                       from stdin; type /help once connected for the list
+  --list-databases     list the server's databases and exit
+  --list-strategies    list the server's match strategies and exit
+  --server-info        print what the server says about itself (SHOW SERVER)
+  --status             print the server's STATUS line
+  --words-file <path>  look up the words in a file, one per line, over several
+                       connections at once, without the cache; results keep
+                       the file's order and a summary goes to stderr
+  --concurrency <n>    connections --words-file uses (default: 4)
+  --output <path>      write --words-file results there instead of to stdout
+  --json               print one JSON document with every lookup instead of text;
+                       with --words-file, one JSON object per word and line
+  --no-color           do not color definitions, even on a terminal
+  --help               print this message
+
+exit status: 0 when every word was found, 1 when the connection was lost
+(with --words-file, when some word could not be looked up), 2 on a usage
+error, 3 when some word had no definition or no match, 4 when the server
+rejected the credentials, 5 when no server could be reached, 6 on a timeout,
+7 when the database does not exist, 8 when the server refused a command or
+answered something unexpected.";
+
+// Everything the command line asked for, defaults filled in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    // Tried in order until one answers.
+    pub hosts: Vec<String>,
+    // None means the default for the transport.
+    pub port: Option<u16>,
+    pub tls: bool,
+    // Skip certificate checks under --tls.
+    pub insecure: bool,
+    pub mime: bool,
+    // None falls back to the environment; see `credentials`.
+    pub user: Option<String>,
+    pub password: Option<String>,
+    // None means the default for the mode.
+    pub database: Option<String>,
+    // Some(strategy) in --match mode.
+    pub strategy: Option<String>,
+    pub auto_match: bool,
+    // Suggest spellings for a word with no definition; --no-suggest turns it off.
+    pub suggest: bool,
+    // Whether definitions are cached at all; --no-cache turns it off.
+    pub cache: bool,
+    // None means cache::default_dir.
+    pub cache_dir: Option<PathBuf>,
+    pub cache_ttl: Duration,
+    // Fetch every definition even if cached.
+    pub refresh: bool,
+    pub verbose: bool,
+    // None waits up to timeout::DEFAULT_TIMEOUT.
+    pub timeout: Option<Duration>,
+    pub retries: u32,
+    pub interactive: bool,
+    pub json: bool,
+    // Color definitions on a terminal; --no-color turns it off.
+    pub color: bool,
+    // Some(listing) to print what the server offers instead of looking up words.
+    pub list: Option<Listing>,
+    // Print SHOW SERVER and STATUS before anything else.
+    pub server_info: bool,
+    pub status: bool,
+    pub words: Vec<String>,
+    // Some(path) to read the words from a file instead, as one batch.
+    pub words_file: Option<PathBuf>,
+    // None means batch::DEFAULT_CONCURRENCY connections.
+    pub concurrency: Option<usize>,
+    // None writes batch results to stdout.
+    pub output: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            hosts: vec![DEFAULT_HOST.to_string()],
+            port: None,
+            tls: false,
+            insecure: false,
+            mime: false,
+            user: None,
+            password: None,
+            database: None,
+            strategy: None,
+            auto_match: false,
+            suggest: true,
+            cache: true,
+            cache_dir: None,
+            cache_ttl: DEFAULT_TTL,
+            refresh: false,
+            verbose: false,
+            timeout: None,
+            retries: DEFAULT_RETRIES,
+            interactive: false,
+            json: false,
+            color: true,
+            list: None,
+            server_info: false,
+            status: false,
+            words: Vec::new(),
+            words_file: None,
+            concurrency: None,
+            output: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn database(&self) -> &str {
+        match (&self.database, &self.strategy) {
+            (Some(database), _) => database,
+            (None, Some(_)) => DEFAULT_MATCH_DATABASE,
+            (None, None) => DEFAULT_DATABASE,
+        }
+    }
+
+    pub fn port(&self) -> u16 {
+        match (self.port, self.tls) {
+            (Some(port), _) => port,
+            (None, true) => DEFAULT_TLS_PORT,
+            (None, false) => DEFAULT_PORT,
+        }
+    }
+
+    // The user and secret to AUTH with, if any, reading whatever the command
+    // line left out from `env` (USER_VARIABLE and PASSWORD_VARIABLE). A user
+    // without a secret is an error.
+    pub fn credentials(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<(String, String)>, String> {
+        let Some(user) = self.user.clone().or_else(|| env(USER_VARIABLE)) else {
+            return Ok(None);
+        };
+        match self.password.clone().or_else(|| env(PASSWORD_VARIABLE)) {
+            Some(password) => Ok(Some((user, password))),
+            None => Err(format!(
+                "no password for user '{}': use --password or set {}",
+                user, PASSWORD_VARIABLE
+            )),
+        }
+    }
+
+    // Connections a --words-file batch uses.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.unwrap_or(DEFAULT_CONCURRENCY)
+    }
+
+    // Whether --server-info or --status asked about the server itself.
+    pub fn describes_server(&self) -> bool {
+        self.server_info || self.status
+    }
+
+    pub fn transport(&self) -> Transport {
+        if self.tls {
+            Transport::Tls {
+                insecure: self.insecure,
+            }
+        } else {
+            Transport::Plain
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run(Box<Options>),
+    Help,
+}
+
+// Parses the arguments after the program name. Err carries a message to
+// print above the usage text.
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let mut options = Options::default();
+    let mut hosts = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(Command::Help),
+            "--interactive" => options.interactive = true,
+            "--auto-match" => options.auto_match = true,
+            "--no-suggest" => options.suggest = false,
+            "--no-cache" => options.cache = false,
+            "--refresh" => options.refresh = true,
+            "--verbose" | "-v" => options.verbose = true,
+            "--cache-dir" => options.cache_dir = Some(PathBuf::from(value(arg)?)),
+            "--cache-ttl" => {
+                let age = value(arg)?;
+                options.cache_ttl = cache::parse_ttl(&age).ok_or_else(|| {
+                    format!(
+                        "invalid cache TTL '{}': expected e.g. 90s, 30m, 12h or 7d",
+                        age
+                    )
+                })?;
+            }
+            "--json" => options.json = true,
+            "--no-color" => options.color = false,
+            "--tls" => options.tls = true,
+            "--insecure" => options.insecure = true,
+            "--mime" => options.mime = true,
+            "--list-databases" => options.list = Some(Listing::Databases),
+            "--list-strategies" => options.list = Some(Listing::Strategies),
+            "--server-info" => options.server_info = true,
+            "--status" => options.status = true,
+            "--match" => options.strategy = Some(DEFAULT_STRATEGY.to_string()),
+            "--host" => hosts.push(value(arg)?),
+            "--words-file" => options.words_file = Some(PathBuf::from(value(arg)?)),
+            "--output" => options.output = Some(PathBuf::from(value(arg)?)),
+            "--concurrency" => {
+                let count = value(arg)?;
+                options.concurrency = match count.parse::<usize>() {
+                    Ok(count) if count > 0 => Some(count),
+                    _ => {
+                        return Err(format!(
+                            "invalid concurrency '{}': expected a positive number",
+                            count
+                        ))
+                    }
+                };
+            }
+            "--db" => options.database = Some(value(arg)?),
+            "--user" => options.user = Some(value(arg)?),
+            "--password" => options.password = Some(value(arg)?),
+            "--port" => {
+                let port = value(arg)?;
+                options.port = match port.parse::<u16>() {
+                    Ok(port) if port > 0 => Some(port),
+                    _ => {
+                        return Err(format!(
+                            "invalid port '{}': expected a number from 1 to 65535",
+                            port
+                        ))
+                    }
+                };
+            }
+            "--timeout" => {
+                let seconds = value(arg)?;
+                options.timeout = match seconds.parse::<u64>() {
+                    Ok(seconds) if seconds > 0 => Some(Duration::from_secs(seconds)),
+                    _ => {
+                        return Err(format!(
+                            "invalid timeout '{}': expected a positive number of seconds",
+                            seconds
+                        ))
+                    }
+                };
+            }
+            "--retries" => {
+                let count = value(arg)?;
+                options.retries = count
+                    .parse::<u32>()
+                    .map_err(|_| format!("invalid retry count '{}': expected a number", count))?;
+            }
+            flag if flag.starts_with("--match=") => {
+                let strategy = &flag["--match=".len()..];
+                if strategy.is_empty() {
+                    return Err("--match= needs a strategy, e.g. --match=exact".to_string());
+                }
+                options.strategy = Some(strategy.to_string());
+            }
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("unknown option '{}'", flag))
+            }
+            word => options.words.push(word.to_string()),
+        }
+    }
+    if !hosts.is_empty() {
+        options.hosts = hosts;
+    }
+    if options.json && (options.interactive || options.list.is_some() || options.describes_server())
+    {
+        return Err("--json only applies to looking up words".to_string());
+    }
+    if options.words_file.is_some()
+        && (options.interactive || options.list.is_some() || options.describes_server())
+    {
+        return Err("--words-file only applies to looking up words".to_string());
+    }
+    if options.words_file.is_some() && !options.words.is_empty() {
+        return Err(
+            "give words either on the command line or with --words-file, not both".to_string(),
+        );
+    }
+    if options.words_file.is_none() && (options.concurrency.is_some() || options.output.is_some()) {
+        return Err("--concurrency and --output only apply with --words-file".to_string());
+    }
+    if options.insecure && !options.tls {
+        return Err("--insecure only applies with --tls".to_string());
+    }
+    if options.refresh && !options.cache {
+        return Err("--refresh only applies with the cache, not --no-cache".to_string());
+    }
+    let batch = options.words_file.is_some();
+    if options.words.is_empty()
+        && !batch
+        && !options.interactive
+        && options.list.is_none()
+        && !options.describes_server()
+    {
+        return Err("no word to look up".to_string());
+    }
+    Ok(Command::Run(Box::new(options)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(args: &[&str]) -> Result<Command, String> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse(&args)
+    }
+
+    #[test]
+    fn test_defaults() {
+        let expected = Options {
+            words: vec!["gold".to_string()],
+            ..Options::default()
+        };
+        assert_eq!(
+            parse_str(&["gold"]),
+            Ok(Command::Run(Box::new(expected.clone())))
+        );
+        assert_eq!(expected.database(), DEFAULT_DATABASE);
+        assert_eq!(expected.port(), DEFAULT_PORT);
+        assert_eq!(expected.transport(), Transport::Plain);
+    }
+
+    #[test]
+    fn test_match_mode() {
+        let Ok(Command::Run(options)) = parse_str(&["--match", "gol"]) else {
+            panic!("expected options");
+        };
+        assert_eq!(options.strategy.as_deref(), Some("prefix"));
+        assert_eq!(options.database(), "*");
+
+        let Ok(Command::Run(options)) = parse_str(&["--db", "wn", "--match=exact", "gold"]) else {
+            panic!("expected options");
+        };
+        assert_eq!(options.strategy.as_deref(), Some("exact"));
+        assert_eq!(options.database(), "wn");
+        assert!(parse_str(&["--match=", "gold"]).is_err());
+    }
+
+    #[test]
+    fn test_all_flags() {
+        let parsed = parse_str(&[
+            "--host",
+            "localhost",
+            "--port",
+            "2629",
+            "--db",
+            "*",
+            "--timeout",
+            "3",
+            "--retries",
+            "0",
+            "--host",
+            "dict.example.org",
+            "--auto-match",
+            "--no-suggest",
+            "--cache-dir",
+            "/tmp/dict",
+            "--cache-ttl",
+            "12h",
+            "--refresh",
+            "--verbose",
+            "--json",
+            "--no-color",
+            "--tls",
+            "--insecure",
+            "--mime",
+            "--user",
+            "jdoe",
+            "--password",
+            "s3cret",
+            "gold",
+            "silver",
+        ]);
+        let expected = Options {
+            hosts: vec!["localhost".to_string(), "dict.example.org".to_string()],
+            port: Some(2629),
+            tls: true,
+            insecure: true,
+            mime: true,
+            user: Some("jdoe".to_string()),
+            password: Some("s3cret".to_string()),
+            database: Some("*".to_string()),
+            strategy: None,
+            auto_match: true,
+            suggest: false,
+            cache: true,
+            cache_dir: Some(PathBuf::from("/tmp/dict")),
+            cache_ttl: Duration::from_secs(12 * 3600),
+            refresh: true,
+            verbose: true,
+            timeout: Some(Duration::from_secs(3)),
+            retries: 0,
+            interactive: false,
+            json: true,
+            color: false,
+            list: None,
+            server_info: false,
+            status: false,
+            words: vec!["gold".to_string(), "silver".to_string()],
+            words_file: None,
+            concurrency: None,
+            output: None,
+        };
+        assert_eq!(parsed, Ok(Command::Run(Box::new(expected))));
+    }
+
+    #[test]
+    fn test_tls() {
+        let Ok(Command::Run(options)) = parse_str(&["--tls", "gold"]) else {
+            panic!("expected options");
+        };
+        assert_eq!(options.port(), DEFAULT_TLS_PORT);
+        assert_eq!(options.transport(), Transport::Tls { insecure: false });
+
+        let Ok(Command::Run(options)) =
+            parse_str(&["--tls", "--insecure", "--port", "4000", "gold"])
+        else {
+            panic!("expected options");
+        };
+        assert_eq!(options.port(), 4000);
+        assert_eq!(options.transport(), Transport::Tls { insecure: true });
+
+        assert_eq!(
+            parse_str(&["--insecure", "gold"]),
+            Err("--insecure only applies with --tls".to_string())
+        );
+    }
+
+    #[test]
+    fn test_credentials_fall_back_to_environment() {
+        let env = |name: &str| match name {
+            USER_VARIABLE => Some("env-user".to_string()),
+            PASSWORD_VARIABLE => Some("env-secret".to_string()),
+            _ => None,
+        };
+        let none = |_: &str| None;
+        let pair = |user: &str, password: &str| Ok(Some((user.to_string(), password.to_string())));
+
+        let options = Options::default();
+        assert_eq!(options.credentials(none), Ok(None));
+        assert_eq!(options.credentials(env), pair("env-user", "env-secret"));
+
+        let options = Options {
+            user: Some("jdoe".to_string()),
+            ..Options::default()
+        };
+        assert_eq!(options.credentials(env), pair("jdoe", "env-secret"));
+        assert_eq!(
+            options.credentials(none),
+            Err("no password for user 'jdoe': use --password or set DICT_SECRET".to_string())
+        );
+
+        let options = Options {
+            password: Some("s3cret".to_string()),
+            ..Options::default()
+        };
+        assert_eq!(options.credentials(none), Ok(None));
+        assert_eq!(options.credentials(env), pair("env-user", "s3cret"));
+    }
+
+    #[test]
+    fn test_help_wins() {
+        assert_eq!(parse_str(&["--port", "2628", "--help"]), Ok(Command::Help));
+        assert_eq!(parse_str(&["-h"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn test_interactive_needs_no_word() {
+        match parse_str(&["--interactive"]) {
+            Ok(Command::Run(options)) => assert!(options.interactive && options.words.is_empty()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_listings_need_no_word() {
+        match parse_str(&["--list-databases"]) {
+            Ok(Command::Run(options)) => assert_eq!(options.list, Some(Listing::Databases)),
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_str(&["--host", "localhost", "--list-strategies"]) {
+            Ok(Command::Run(options)) => assert_eq!(options.list, Some(Listing::Strategies)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_server_info_and_status_need_no_word() {
+        match parse_str(&["--server-info", "--status"]) {
+            Ok(Command::Run(options)) => {
+                assert!(options.server_info && options.status && options.words.is_empty())
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse_str(&["--status", "gold"]) {
+            Ok(Command::Run(options)) => {
+                assert!(options.status && !options.server_info && options.words == ["gold"])
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_str(&["--status", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_json_only_for_lookups() {
+        assert_eq!(
+            parse_str(&["--json", "--interactive"]),
+            Err("--json only applies to looking up words".to_string())
+        );
+        assert!(parse_str(&["--list-databases", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_words_file() {
+        let Ok(Command::Run(options)) = parse_str(&[
+            "--words-file",
+            "vocab.txt",
+            "--concurrency",
+            "8",
+            "--output",
+            "out.txt",
+        ]) else {
+            panic!("expected options");
+        };
+        assert_eq!(options.words_file, Some(PathBuf::from("vocab.txt")));
+        assert_eq!(options.output, Some(PathBuf::from("out.txt")));
+        assert_eq!(options.concurrency(), 8);
+        let Ok(Command::Run(options)) = parse_str(&["--words-file", "vocab.txt", "--json"]) else {
+            panic!("expected options");
+        };
+        assert_eq!(options.concurrency(), DEFAULT_CONCURRENCY);
+        assert!(options.words.is_empty() && options.json);
+
+        assert_eq!(
+            parse_str(&["--words-file", "vocab.txt", "gold"]),
+            Err("give words either on the command line or with --words-file, not both".to_string())
+        );
+        assert_eq!(
+            parse_str(&["--concurrency", "2", "gold"]),
+            Err("--concurrency and --output only apply with --words-file".to_string())
+        );
+        assert!(parse_str(&["--words-file", "vocab.txt", "--concurrency", "0"]).is_err());
+        assert!(parse_str(&["--words-file", "vocab.txt", "--interactive"]).is_err());
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        assert_eq!(parse_str(&[]), Err("no word to look up".to_string()));
+        assert_eq!(
+            parse_str(&["--db", "*"]),
+            Err("no word to look up".to_string())
+        );
+        assert!(parse_str(&["--port", "http", "gold"])
+            .unwrap_err()
+            .contains("invalid port 'http'"));
+        assert!(parse_str(&["--port", "70000", "gold"]).is_err());
+        assert!(parse_str(&["--port", "0", "gold"]).is_err());
+        assert_eq!(
+            parse_str(&["gold", "--port"]),
+            Err("--port needs a value".to_string())
+        );
+        assert!(parse_str(&["--timeout", "0", "gold"]).is_err());
+        assert!(parse_str(&["--retries", "-1", "gold"]).is_err());
+        assert_eq!(
+            parse_str(&["--loud", "gold"]),
+            Err("unknown option '--loud'".to_string())
+        );
+        assert!(parse_str(&["--cache-ttl", "1w", "gold"])
+            .unwrap_err()
+            .contains("invalid cache TTL '1w'"));
+        assert_eq!(
+            parse_str(&["--no-cache", "--refresh", "gold"]),
+            Err("--refresh only applies with the cache, not --no-cache".to_string())
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::error::DictError;
+use crate::protocol;
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt};
+
+// The AUTH digest is the hex MD5 of the msg-id from the server greeting
+// (angle brackets included) followed by the shared secret (RFC 2229, 3.11).
+pub fn auth_digest(msg_id: &str, secret: &str) -> String {
+    format!("{:x}", md5::compute(format!("{}{}", msg_id, secret)))
+}
+
+pub async fn authenticate<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    user: &str,
+    secret: &str,
+    msg_id: &str,
+) -> Result<(), DictError>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let command = protocol::command_line(&format!("AUTH {} {}", user, auth_digest(msg_id, secret)));
+    writer.write_all(command.as_bytes()).await?;
+    writer.flush().await?;
+
+    let line = protocol::read_line(reader).await?;
+    let line = line.trim();
+    if line.starts_with("230") {
+        Ok(())
+    } else if line.starts_with("531") {
+        Err(DictError::AuthFailed(line.to_string()))
+    } else {
+        Err(DictError::UnexpectedResponse(line.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::greeting::parse_greeting;
+    use tokio::io::BufReader;
+
+    const MSG_ID: &str = "<1234.5678@dict.example.org>";
+
+    #[test]
+    fn test_auth_digest() {
+        assert_eq!(
+            auth_digest(MSG_ID, "s3cret"),
+            "eb32ba4fe2ddfa2782323c0ddf6db723"
+        );
+        assert_eq!(
+            auth_digest("<1493.844628@dict.example.org>", "hunter2"),
+            "aa9d22553fdbf3e4c6723c13db468a34"
+        );
+    }
+
+    #[test]
+    fn test_digest_uses_msg_id_from_greeting() {
+        let greeting = parse_greeting(
+            "220 dict.example.org dictd 1.12 <auth.mime> <1234.5678@dict.example.org>",
+        );
+        let msg_id = greeting.msg_id.expect("greeting has a msg-id");
+        assert_eq!(
+            auth_digest(&msg_id, "s3cret"),
+            auth_digest(MSG_ID, "s3cret")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_success() {
+        let mut reader = BufReader::new(&b"230 Authentication successful\r\n"[..]);
+        let mut written = Vec::new();
+        authenticate(&mut reader, &mut written, "jdoe", "s3cret", MSG_ID)
+            .await
+            .unwrap();
+        assert_eq!(written, b"AUTH jdoe eb32ba4fe2ddfa2782323c0ddf6db723\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_denied() {
+        let mut reader = BufReader::new(&b"531 Access denied\r\n"[..]);
+        let mut written = Vec::new();
+        let result = authenticate(&mut reader, &mut written, "jdoe", "wrong", MSG_ID).await;
+        assert!(matches!(result, Err(DictError::AuthFailed(line)) if line == "531 Access denied"));
+    }
+}
@@ -0,0 +1,366 @@
+use crate::args::Options;
+use crate::client::TcpDictClient;
+use crate::definition::Definition;
+use crate::error::DictError;
+use crate::matches::DEFAULT_STRATEGY;
+use crate::output::Lookup;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+// Connections a --words-file batch looks words up over unless --concurrency
+// says otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+// The words of a --words-file: one per line, surrounding whitespace trimmed
+// and blank lines skipped.
+pub fn read_words(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// How the words of a batch fared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub found: usize,
+    // The server had nothing for them (552 and the like).
+    pub missing: usize,
+    // The lookup itself failed, e.g. on a dropped connection.
+    pub failed: usize,
+}
+
+impl Summary {
+    pub fn add(&mut self, lookup: &Lookup) {
+        if lookup.found() {
+            self.found += 1;
+        } else if lookup.is_failure() {
+            self.failed += 1;
+        } else {
+            self.missing += 1;
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.found + self.missing + self.failed
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} found, {} not found, {} failed",
+            self.found, self.missing, self.failed
+        )
+    }
+}
+
+// Looks `words` up over up to `concurrency` connections opened with
+// `connect`, handing each result to `emit` in the order of `words`. A word
+// that fails, be it refused by the server or lost with its connection, is
+// emitted as a failed lookup and the batch carries on; a connection that
+// broke is replaced for the next word. Only `emit` failing stops the batch.
+pub async fn run<C, F>(
+    words: Vec<String>,
+    concurrency: usize,
+    options: Arc<Options>,
+    connect: C,
+    mut emit: impl FnMut(&Lookup) -> io::Result<()>,
+) -> io::Result<Summary>
+where
+    C: Fn() -> F + Send + Sync + 'static,
+    F: Future<Output = Result<TcpDictClient, DictError>> + Send + 'static,
+{
+    let workers = concurrency.clamp(1, words.len().max(1));
+    let queue: Arc<Mutex<VecDeque<(usize, String)>>> =
+        Arc::new(Mutex::new(words.into_iter().enumerate().collect()));
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let connect = Arc::new(connect);
+    let mut tasks = JoinSet::new();
+    for _ in 0..workers {
+        tasks.spawn(work(
+            queue.clone(),
+            sender.clone(),
+            connect.clone(),
+            options.clone(),
+        ));
+    }
+    drop(sender);
+
+    // Results arrive in whatever order the connections finish them; hold
+    // each back until every word before it has been emitted.
+    let mut summary = Summary::default();
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    while let Some((index, lookup)) = receiver.recv().await {
+        pending.insert(index, lookup);
+        while let Some(lookup) = pending.remove(&next) {
+            summary.add(&lookup);
+            emit(&lookup)?;
+            next += 1;
+        }
+    }
+    while tasks.join_next().await.is_some() {}
+    Ok(summary)
+}
+
+// One connection's share of the batch: takes words off `queue` until it is
+// empty, then sends QUIT.
+async fn work<C, F>(
+    queue: Arc<Mutex<VecDeque<(usize, String)>>>,
+    results: mpsc::UnboundedSender<(usize, Lookup)>,
+    connect: Arc<C>,
+    options: Arc<Options>,
+) where
+    C: Fn() -> F,
+    F: Future<Output = Result<TcpDictClient, DictError>>,
+{
+    let mut client = None;
+    loop {
+        let next = queue.lock().unwrap().pop_front();
+        let Some((index, word)) = next else { break };
+        let lookup = match look_up_on(&mut client, connect.as_ref(), &options, &word).await {
+            Ok(lookup) => lookup,
+            Err(e) => Lookup::failed(&word, &e),
+        };
+        if results.send((index, lookup)).is_err() {
+            break;
+        }
+    }
+    if let Some(client) = client {
+        let _ = client.quit().await;
+    }
+}
+
+// Looks `word` up on `client`, connecting first if there is no client. A
+// client an error left unusable is dropped, so the next word reconnects.
+async fn look_up_on<C, F>(
+    client: &mut Option<TcpDictClient>,
+    connect: &C,
+    options: &Options,
+    word: &str,
+) -> Result<Lookup, DictError>
+where
+    C: Fn() -> F,
+    F: Future<Output = Result<TcpDictClient, DictError>>,
+{
+    let connected = match client {
+        Some(connected) => connected,
+        None => client.insert(connect().await?),
+    };
+    let define = async |client: &mut TcpDictClient| client.define(options.database(), word).await;
+    let result = look_up(connected, options, word, define).await;
+    if matches!(&result, Err(e) if !e.leaves_session_usable()) {
+        *client = None;
+    }
+    result
+}
+
+// Looks up one word the way `dictclient <word>` does: its matches under
+// --match, otherwise its definitions as fetched by `define` (which may go
+// through the cache), with prefix matches (--auto-match) or spelling
+// suggestions when it has none.
+pub async fn look_up<D>(
+    client: &mut TcpDictClient,
+    options: &Options,
+    word: &str,
+    define: D,
+) -> Result<Lookup, DictError>
+where
+    D: AsyncFnOnce(&mut TcpDictClient) -> Result<Vec<Definition>, DictError>,
+{
+    let database = options.database();
+    if let Some(strategy) = &options.strategy {
+        return Ok(Lookup::matched(
+            word,
+            client.match_word(database, strategy, word).await?,
+        ));
+    }
+    let mut lookup = Lookup::defined(word, define(client).await?);
+    if !lookup.found() && options.auto_match {
+        lookup.matches = Some(client.match_word(database, DEFAULT_STRATEGY, word).await?);
+    } else if !lookup.found() && options.suggest {
+        lookup.suggestions = Some(client.suggest(database, word).await?);
+    }
+    Ok(lookup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{TcpListener, TcpStream};
+
+    // Answers DEFINE wn <word> with one definition of the word, except that
+    // "xyzzy" and "qwfp" have none, "boom" drops the connection and
+    // "locked" is refused. Every connection is served until QUIT.
+    async fn serve(socket: TcpStream) {
+        let (read_half, mut write_half) = socket.into_split();
+        let mut lines = BufReader::new(read_half).lines();
+        write_half
+            .write_all(b"220 mock.dict.org dictd 1.12 <> <42.7@mock.dict.org>\r\n")
+            .await
+            .unwrap();
+        while let Ok(Some(command)) = lines.next_line().await {
+            let reply = match command.split(' ').collect::<Vec<_>>()[..] {
+                ["CLIENT", ..] => "250 ok\r\n".to_string(),
+                ["QUIT"] => "221 bye\r\n".to_string(),
+                ["DEFINE", "wn", "xyzzy" | "qwfp"] => "552 no match\r\n".to_string(),
+                ["DEFINE", "wn", "boom"] => return,
+                ["DEFINE", "wn", "locked"] => "550 invalid database\r\n".to_string(),
+                ["DEFINE", "wn", word] => format!(
+                    "150 1 definitions retrieved\r\n151 \"{0}\" wn \"WordNet\"\r\n{0}\r\n  n 1: a metal\r\n.\r\n250 ok\r\n",
+                    word
+                ),
+                _ => "500 unknown command\r\n".to_string(),
+            };
+            // Slow down some words so the connections finish out of order.
+            if command.ends_with("gold") || command.ends_with("copper") {
+                tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+            }
+            write_half.write_all(reply.as_bytes()).await.unwrap();
+        }
+    }
+
+    async fn mock_server(connections: Arc<AtomicUsize>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve(socket));
+            }
+        });
+        port
+    }
+
+    fn options() -> Arc<Options> {
+        Arc::new(Options {
+            database: Some("wn".to_string()),
+            suggest: false,
+            ..Options::default()
+        })
+    }
+
+    #[test]
+    fn test_read_words() {
+        assert_eq!(
+            read_words("gold\n\n  silver \r\nfool's gold\n"),
+            ["gold", "silver", "fool's gold"]
+        );
+        assert!(read_words("\n \n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_keeps_order_and_survives_failures() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let port = mock_server(connections.clone()).await;
+        let words = read_words(
+            "gold\nsilver\nxyzzy\nboom\ncopper\niron\nlocked\nqwfp\nzinc\ntin\nlead\nnickel",
+        );
+        let mut emitted = Vec::new();
+        let summary = run(
+            words.clone(),
+            3,
+            options(),
+            move || TcpDictClient::connect("127.0.0.1", port),
+            |lookup| {
+                emitted.push(lookup.clone());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            emitted
+                .iter()
+                .map(|lookup| lookup.word.as_str())
+                .collect::<Vec<_>>(),
+            words
+        );
+        assert_eq!(
+            summary,
+            Summary {
+                found: 8,
+                missing: 2,
+                failed: 2
+            }
+        );
+        assert_eq!(summary.to_string(), "8 found, 2 not found, 2 failed");
+        assert_eq!(summary.total(), words.len());
+        for lookup in emitted.iter().filter(|lookup| lookup.found()) {
+            assert_eq!(
+                lookup.definitions.as_ref().unwrap()[0].headword,
+                lookup.word
+            );
+        }
+        assert!(emitted[2]
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with("no definition found"));
+        assert!(emitted[3].is_failure());
+        assert_eq!(
+            emitted[6].error.as_deref(),
+            Some("server has no database \"wn\"")
+        );
+        // The connection "boom" broke was replaced.
+        assert!(connections.load(Ordering::SeqCst) > 3);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_server_fails_every_word() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut failed = Vec::new();
+        let summary = run(
+            read_words("gold\nsilver\ntin"),
+            2,
+            options(),
+            move || TcpDictClient::connect("127.0.0.1", port),
+            |lookup| {
+                failed.push(lookup.is_failure());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                found: 0,
+                missing: 0,
+                failed: 3
+            }
+        );
+        assert_eq!(failed, [true, true, true]);
+    }
+
+    #[tokio::test]
+    async fn test_emit_error_stops_the_batch() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let port = mock_server(connections).await;
+        let result = run(
+            read_words("gold\nsilver"),
+            1,
+            options(),
+            move || TcpDictClient::connect("127.0.0.1", port),
+            |_| Err(io::Error::other("disk full")),
+        )
+        .await;
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+    }
+}
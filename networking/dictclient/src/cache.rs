@@ -0,0 +1,353 @@
+use crate::definition::Definition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// How long cached definitions are served before being fetched again.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+// The file holding the cache, inside the cache directory.
+pub const CACHE_FILE: &str = "definitions.json";
+
+// Where the cache lives unless --cache-dir says otherwise:
+// $XDG_CACHE_HOME/dictclient, else ~/.cache/dictclient. None when neither
+// variable is set.
+pub fn default_dir(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let base = match env("XDG_CACHE_HOME").filter(|dir| Path::new(dir).is_absolute()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env("HOME")?).join(".cache"),
+    };
+    Some(base.join("dictclient"))
+}
+
+// Definitions fetched earlier, keyed by (server, database, word) and kept
+// for `ttl`. Only words that had definitions are cached, so a word the
+// server did not know is asked about again next time.
+#[derive(Debug)]
+pub struct DefinitionCache {
+    path: PathBuf,
+    ttl: Duration,
+    entries: HashMap<(String, String, String), Entry>,
+    // Why the file on disk was ignored, if it was.
+    discarded: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    server: String,
+    database: String,
+    word: String,
+    // Seconds since the Unix epoch.
+    fetched: u64,
+    definitions: Vec<Definition>,
+}
+
+impl DefinitionCache {
+    // Loads the cache in `dir`. A missing file is an empty cache; so is one
+    // that cannot be read or parsed, which is replaced on the next `save`
+    // (see `discarded`).
+    pub fn open(dir: &Path, ttl: Duration) -> Self {
+        let path = dir.join(CACHE_FILE);
+        let mut cache = Self {
+            path,
+            ttl,
+            entries: HashMap::new(),
+            discarded: None,
+        };
+        match fs::read_to_string(&cache.path) {
+            Ok(text) => match serde_json::from_str::<Vec<Entry>>(&text) {
+                Ok(entries) => {
+                    for entry in entries {
+                        let key = (
+                            entry.server.clone(),
+                            entry.database.clone(),
+                            entry.word.clone(),
+                        );
+                        cache.entries.insert(key, entry);
+                    }
+                }
+                Err(e) => cache.discarded = Some(e.to_string()),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => cache.discarded = Some(e.to_string()),
+        }
+        cache
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn discarded(&self) -> Option<&str> {
+        self.discarded.as_deref()
+    }
+
+    // The definitions cached for `word`, with when they were fetched, if
+    // that is less than the TTL before `now`.
+    pub fn get(
+        &self,
+        server: &str,
+        database: &str,
+        word: &str,
+        now: SystemTime,
+    ) -> Option<(Vec<Definition>, SystemTime)> {
+        let entry = self.entries.get(&key(server, database, word))?;
+        if !self.is_fresh(entry, now) {
+            return None;
+        }
+        Some((entry.definitions.clone(), fetched_at(entry)?))
+    }
+
+    // Remembers what DEFINE returned for `word`. Nothing is cached for a
+    // word without definitions.
+    pub fn put(
+        &mut self,
+        server: &str,
+        database: &str,
+        word: &str,
+        definitions: &[Definition],
+        now: SystemTime,
+    ) {
+        if definitions.is_empty() {
+            return;
+        }
+        let entry = Entry {
+            server: server.to_string(),
+            database: database.to_string(),
+            word: word.to_string(),
+            fetched: now
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            definitions: definitions.to_vec(),
+        };
+        self.entries.insert(key(server, database, word), entry);
+    }
+
+    // Writes the fresh entries back, creating the directory if need be. The
+    // file is replaced in one rename, so a run cut short leaves the old one.
+    pub fn save(&self, now: SystemTime) -> io::Result<()> {
+        let mut entries: Vec<&Entry> = self
+            .entries
+            .values()
+            .filter(|entry| self.is_fresh(entry, now))
+            .collect();
+        entries.sort_by(|a, b| {
+            (&a.server, &a.database, &a.word).cmp(&(&b.server, &b.database, &b.word))
+        });
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let partial = self.path.with_extension("json.partial");
+        fs::write(
+            &partial,
+            serde_json::to_string(&entries).map_err(io::Error::other)?,
+        )?;
+        fs::rename(&partial, &self.path)
+    }
+
+    // An entry fetched after `now`, or at a time SystemTime cannot hold, was
+    // written by a clock that cannot be trusted and is stale.
+    fn is_fresh(&self, entry: &Entry, now: SystemTime) -> bool {
+        fetched_at(entry)
+            .and_then(|fetched| now.duration_since(fetched).ok())
+            .is_some_and(|age| age < self.ttl)
+    }
+}
+
+fn fetched_at(entry: &Entry) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_secs(entry.fetched))
+}
+
+fn key(server: &str, database: &str, word: &str) -> (String, String, String) {
+    (server.to_string(), database.to_string(), word.to_string())
+}
+
+// "7d", "12h", "30m", "90s" or a bare number of seconds.
+pub fn parse_ttl(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(at) => text.split_at(at),
+        None => (text, "s"),
+    };
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    number
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+}
+
+// An age as the largest whole unit parse_ttl knows: "3d", "5h", "12m", "40s".
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    match seconds {
+        86_400.. => format!("{}d", seconds / 86_400),
+        3_600.. => format!("{}h", seconds / 3_600),
+        60.. => format!("{}m", seconds / 60),
+        _ => format!("{}s", seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER: &str = "dict.org";
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("dictclient-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn gold() -> Vec<Definition> {
+        vec![Definition {
+            database: "wn".to_string(),
+            database_description: "WordNet (r) 3.0 (2006)".to_string(),
+            headword: "gold".to_string(),
+            body: "gold\n  n 1: a soft yellow metal".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_hit_and_miss() {
+        let dir = temp_dir("hit");
+        let fetched = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        assert!(cache.get(SERVER, "wn", "gold", fetched).is_none());
+        cache.put(SERVER, "wn", "gold", &gold(), fetched);
+        cache.put(SERVER, "wn", "xyzzy", &[], fetched);
+        cache.save(fetched).unwrap();
+
+        let cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        assert!(cache.discarded().is_none());
+        assert_eq!(
+            cache.get(SERVER, "wn", "gold", fetched + DAY),
+            Some((gold(), fetched))
+        );
+        // Keyed by server and database as well as word.
+        assert!(cache
+            .get("dict.example.org", "wn", "gold", fetched)
+            .is_none());
+        assert!(cache.get(SERVER, "*", "gold", fetched).is_none());
+        assert!(cache.get(SERVER, "wn", "xyzzy", fetched).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expired_entries_are_misses_and_dropped() {
+        let dir = temp_dir("expiry");
+        let fetched = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut cache = DefinitionCache::open(&dir, DAY);
+        cache.put(SERVER, "wn", "gold", &gold(), fetched);
+        assert!(cache.get(SERVER, "wn", "gold", fetched + DAY / 2).is_some());
+        assert!(cache.get(SERVER, "wn", "gold", fetched + DAY).is_none());
+
+        cache.save(fetched + DAY).unwrap();
+        assert_eq!(fs::read_to_string(cache.path()).unwrap(), "[]");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_future_and_unrepresentable_times_are_stale() {
+        let dir = temp_dir("clock");
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        cache.put(SERVER, "wn", "gold", &gold(), now + DAY);
+        assert!(cache.get(SERVER, "wn", "gold", now).is_none());
+
+        fs::create_dir_all(&dir).unwrap();
+        let entry = Entry {
+            server: SERVER.to_string(),
+            database: "wn".to_string(),
+            word: "gold".to_string(),
+            fetched: u64::MAX,
+            definitions: gold(),
+        };
+        fs::write(
+            dir.join(CACHE_FILE),
+            serde_json::to_string(&[entry]).unwrap(),
+        )
+        .unwrap();
+        let cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        assert!(cache.discarded().is_none());
+        assert!(cache.get(SERVER, "wn", "gold", now).is_none());
+        cache.save(now).unwrap();
+        assert_eq!(fs::read_to_string(cache.path()).unwrap(), "[]");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_file_is_ignored_and_rebuilt() {
+        let dir = temp_dir("corrupt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CACHE_FILE), "[{\"server\": \"dict.org\", \"datab").unwrap();
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let mut cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        assert!(cache.discarded().is_some());
+        assert!(cache.get(SERVER, "wn", "gold", now).is_none());
+        cache.put(SERVER, "wn", "gold", &gold(), now);
+        cache.save(now).unwrap();
+
+        let cache = DefinitionCache::open(&dir, DEFAULT_TTL);
+        assert!(cache.discarded().is_none());
+        assert_eq!(cache.get(SERVER, "wn", "gold", now), Some((gold(), now)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_default_dir() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            default_dir(env(&[
+                ("XDG_CACHE_HOME", "/var/cache/ana"),
+                ("HOME", "/home/ana")
+            ])),
+            Some(PathBuf::from("/var/cache/ana/dictclient"))
+        );
+        assert_eq!(
+            default_dir(env(&[
+                ("XDG_CACHE_HOME", "relative"),
+                ("HOME", "/home/ana")
+            ])),
+            Some(PathBuf::from("/home/ana/.cache/dictclient"))
+        );
+        assert_eq!(default_dir(env(&[])), None);
+    }
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("7d"), Some(DEFAULT_TTL));
+        assert_eq!(parse_ttl("12h"), Some(Duration::from_secs(12 * 3600)));
+        assert_eq!(parse_ttl("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_ttl("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_ttl("0s"), Some(Duration::ZERO));
+        assert_eq!(parse_ttl("d"), None);
+        assert_eq!(parse_ttl("1w"), None);
+        assert_eq!(parse_ttl("-1d"), None);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(40)), "40s");
+        assert_eq!(format_age(Duration::from_secs(12 * 60 + 5)), "12m");
+        assert_eq!(format_age(Duration::from_secs(5 * 3600)), "5h");
+        assert_eq!(format_age(DEFAULT_TTL), "7d");
+    }
+}
@@ -0,0 +1,489 @@
+use crate::connection::{self, DictConnection};
+use crate::definition::{self, Definition};
+use crate::error::DictError;
+use crate::greeting::Greeting;
+use crate::matches::{self, Matches, MAX_SUGGESTIONS};
+use crate::show::{self, Listing};
+use crate::timeout::Timed;
+use crate::tls::{DictStream, Transport};
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncWrite, BufReader, ReadHalf, WriteHalf};
+
+pub type TcpDictClient =
+    DictClient<BufReader<Timed<ReadHalf<DictStream>>>, Timed<WriteHalf<DictStream>>>;
+
+// The typed face of a DICT session, for programs that want definitions and
+// matches rather than raw replies. "Nothing found" answers are empty
+// results; refusals and unavailable servers are `DictError::Server`, except
+// that a lookup in a database the server does not have is
+// `DictError::InvalidDatabase`.
+pub struct DictClient<R, W> {
+    connection: DictConnection<R, W>,
+    // The server's strategy names, once SHOW STRAT has been asked for them.
+    strategies: Option<Vec<String>>,
+}
+
+impl TcpDictClient {
+    pub async fn connect(host: &str, port: u16) -> Result<Self, DictError> {
+        Ok(Self::new(connection::connect(host, port).await?))
+    }
+
+    pub async fn connect_with_timeout(
+        host: &str,
+        port: u16,
+        limit: Duration,
+    ) -> Result<Self, DictError> {
+        Ok(Self::new(
+            connection::connect_with_timeout(host, port, limit).await?,
+        ))
+    }
+
+    pub async fn connect_over(
+        host: &str,
+        port: u16,
+        limit: Duration,
+        transport: Transport,
+    ) -> Result<Self, DictError> {
+        Ok(Self::new(
+            connection::connect_over(host, port, limit, transport).await?,
+        ))
+    }
+}
+
+impl<R, W> DictClient<R, W>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    // Wraps a connection that has already read the server's greeting.
+    pub fn new(connection: DictConnection<R, W>) -> Self {
+        Self {
+            connection,
+            strategies: None,
+        }
+    }
+
+    // The full 220 line the server greeted us with.
+    pub fn banner(&self) -> &str {
+        self.connection.banner()
+    }
+
+    pub fn greeting(&self) -> &Greeting {
+        self.connection.greeting()
+    }
+
+    // Capabilities advertised in the greeting, e.g. ["auth", "mime"].
+    pub fn capabilities(&self) -> &[String] {
+        &self.connection.greeting().capabilities
+    }
+
+    pub async fn authenticate(&mut self, user: &str, secret: &str) -> Result<(), DictError> {
+        self.connection.authenticate(user, secret).await
+    }
+
+    // Turns on OPTION MIME, so bodies the server sends base64 or
+    // quoted-printable encoded come back decoded. False if the server
+    // refused and the session carries on without it.
+    pub async fn enable_mime(&mut self) -> Result<bool, DictError> {
+        self.connection.enable_mime().await
+    }
+
+    // Definitions of `word` in `database` ("*" for all, "!" for the first
+    // database that has one).
+    pub async fn define(
+        &mut self,
+        database: &str,
+        word: &str,
+    ) -> Result<Vec<Definition>, DictError> {
+        let reply = self
+            .connection
+            .define_in(database, word)
+            .await
+            .map_err(|e| in_lookup(e, database, word))?;
+        definition::parse_definitions(&reply)
+    }
+
+    // Headwords in `database` that `strategy` matches against `word`.
+    pub async fn match_word(
+        &mut self,
+        database: &str,
+        strategy: &str,
+        word: &str,
+    ) -> Result<Matches, DictError> {
+        let reply = self
+            .connection
+            .match_in(database, strategy, word)
+            .await
+            .map_err(|e| in_lookup(e, database, word))?;
+        matches::parse_matches(&reply)
+    }
+
+    // Spelling suggestions for a word `database` has no definition of: up to
+    // MAX_SUGGESTIONS headwords found with the best strategy the server
+    // offers (see `matches::SUGGESTION_STRATEGIES`). The strategy list is
+    // fetched once per session. A server that refuses SHOW STRAT or the MATCH
+    // has no suggestions rather than failing the lookup, and one that refused
+    // the list is not asked for it again.
+    pub async fn suggest(&mut self, database: &str, word: &str) -> Result<Vec<String>, DictError> {
+        let strategies = match &self.strategies {
+            Some(strategies) => strategies,
+            None => {
+                let listed = match self.show_strategies().await {
+                    Ok(listed) => listed,
+                    Err(e) if is_refusal(&e) => Vec::new(),
+                    Err(e) => return Err(e),
+                };
+                self.strategies
+                    .insert(listed.into_iter().map(|(name, _)| name).collect())
+            }
+        };
+        let Some(strategy) = matches::suggestion_strategy(strategies) else {
+            return Ok(Vec::new());
+        };
+        match self.match_word(database, strategy, word).await {
+            Ok(matches) => Ok(matches.headwords(MAX_SUGGESTIONS)),
+            Err(e) if is_refusal(&e) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Names and descriptions of the server's databases.
+    pub async fn show_databases(&mut self) -> Result<Vec<(String, String)>, DictError> {
+        self.show(Listing::Databases).await
+    }
+
+    // Names and descriptions of the server's match strategies.
+    pub async fn show_strategies(&mut self) -> Result<Vec<(String, String)>, DictError> {
+        self.show(Listing::Strategies).await
+    }
+
+    pub async fn show(&mut self, listing: Listing) -> Result<Vec<(String, String)>, DictError> {
+        let reply = self.connection.show(listing).await?;
+        show::parse_listing(listing, &reply)
+    }
+
+    // The server's description of itself (SHOW SERVER), line by line.
+    pub async fn server_info(&mut self) -> Result<Vec<String>, DictError> {
+        Ok(self.connection.server_info().await?.text)
+    }
+
+    // The server's whole STATUS line, e.g. "210 status [d/m/c = 12/3/40]".
+    pub async fn status(&mut self) -> Result<String, DictError> {
+        let reply = self.connection.status().await?;
+        Ok(format!("{} {}", reply.code, reply.message))
+    }
+
+    pub async fn quit(self) -> Result<(), DictError> {
+        self.connection.quit().await
+    }
+}
+
+// Says what a failed DEFINE or MATCH was about: a lost connection names the
+// word, and a 550 the database.
+fn in_lookup(e: DictError, database: &str, word: &str) -> DictError {
+    match e {
+        DictError::Io(e) => DictError::Io(io::Error::new(
+            e.kind(),
+            format!("looking up \"{}\": {}", word, e),
+        )),
+        DictError::Server { code: 550, .. } => DictError::InvalidDatabase(database.to_string()),
+        e => e,
+    }
+}
+
+// A 5xx refusal, e.g. 502 command not implemented or 551 invalid strategy,
+// after which the session carries on. 550 has already become
+// InvalidDatabase, which a suggestion should not hide.
+fn is_refusal(e: &DictError) -> bool {
+    matches!(
+        e,
+        DictError::Server {
+            code: 500..=599,
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // A DICT server on a local port that greets, checks that the client
+    // identifies itself first, then answers each expected command with its
+    // scripted reply, in order.
+    async fn mock_server(
+        script: &'static [(&'static str, &'static str)],
+    ) -> (u16, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read_half, mut write_half) = socket.into_split();
+            let mut lines = BufReader::new(read_half).lines();
+            write_half
+                .write_all(b"220 mock.dict.org dictd 1.12 <auth.mime> <42.7@mock.dict.org>\r\n")
+                .await
+                .unwrap();
+            let hello = lines
+                .next_line()
+                .await
+                .unwrap()
+                .expect("client hung up early");
+            assert_eq!(hello, format!("CLIENT {}", connection::CLIENT_NAME));
+            write_half.write_all(b"250 ok\r\n").await.unwrap();
+            for (expected, reply) in script {
+                let command = lines
+                    .next_line()
+                    .await
+                    .unwrap()
+                    .expect("client hung up early");
+                assert_eq!(command, *expected);
+                write_half.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+        (port, server)
+    }
+
+    const SESSION: &[(&str, &str)] = &[
+        (
+            "DEFINE wn gold",
+            "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet (r) 3.0 (2006)\"\r\ngold\r\n  n 1: a soft metal\r\n.\r\n250 ok\r\n",
+        ),
+        ("DEFINE * xyzzy", "552 no match\r\n"),
+        ("MATCH * prefix gol", "152 2 matches found\r\nwn \"gold\"\r\nwn \"golf\"\r\n.\r\n250 ok\r\n"),
+        ("SHOW DB", "110 1 databases present\r\nwn \"WordNet (r) 3.0 (2006)\"\r\n.\r\n250 ok\r\n"),
+        ("SHOW STRAT", "111 1 strategies present\r\nprefix \"Match prefixes\"\r\n.\r\n250 ok\r\n"),
+        ("SHOW SERVER", "114 server information\r\ndictd 1.12.1\r\n.\r\n250 ok\r\n"),
+        ("STATUS", "210 status [d/m/c = 2/1/1]\r\n"),
+        ("DEFINE nonesuch gold", "550 invalid database, use \"SHOW DB\" for list of databases\r\n"),
+        ("QUIT", "221 bye\r\n"),
+    ];
+
+    #[tokio::test]
+    async fn test_session_against_mock_server() {
+        let (port, server) = mock_server(SESSION).await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.banner().starts_with("220 mock.dict.org"));
+        assert_eq!(client.capabilities(), ["auth", "mime"]);
+
+        let definitions = client.define("wn", "gold").await.unwrap();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(
+            definitions[0].database_description,
+            "WordNet (r) 3.0 (2006)"
+        );
+        assert_eq!(definitions[0].body, "gold\n  n 1: a soft metal");
+        assert!(client.define("*", "xyzzy").await.unwrap().is_empty());
+
+        let matches = client.match_word("*", "prefix", "gol").await.unwrap();
+        assert_eq!(
+            matches.databases,
+            vec![(
+                "wn".to_string(),
+                vec!["gold".to_string(), "golf".to_string()]
+            )]
+        );
+
+        let databases = client.show_databases().await.unwrap();
+        assert_eq!(
+            databases,
+            vec![("wn".to_string(), "WordNet (r) 3.0 (2006)".to_string())]
+        );
+        let strategies = client.show_strategies().await.unwrap();
+        assert_eq!(
+            strategies,
+            vec![("prefix".to_string(), "Match prefixes".to_string())]
+        );
+
+        assert_eq!(client.server_info().await.unwrap(), ["dictd 1.12.1"]);
+        assert_eq!(client.status().await.unwrap(), "210 status [d/m/c = 2/1/1]");
+
+        let refused = client.define("nonesuch", "gold").await;
+        assert!(matches!(refused, Err(DictError::InvalidDatabase(name)) if name == "nonesuch"));
+
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mime_base64_body_is_decoded() {
+        let (port, server) = mock_server(&[
+            ("OPTION MIME", "250 ok - using MIME headers\r\n"),
+            (
+                "DEFINE wn gold",
+                "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\n\
+Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n\
+Z29sZA0KICBuIDE6IGEgc29mdCBtZXRhbCwgw6ls\r\nw6ltZW50IDc5DQo=\r\n.\r\n250 ok\r\n",
+            ),
+            (
+                "MATCH * prefix gol",
+                "152 1 matches found\r\n\r\nwn \"gold\"\r\n.\r\n250 ok\r\n",
+            ),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.enable_mime().await.unwrap());
+
+        let definitions = client.define("wn", "gold").await.unwrap();
+        assert_eq!(definitions[0].body, "gold\n  n 1: a soft metal, élément 79");
+        let matches = client.match_word("*", "prefix", "gol").await.unwrap();
+        assert_eq!(
+            matches.databases,
+            vec![("wn".to_string(), vec!["gold".to_string()])]
+        );
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mime_refused_falls_back_to_plain_text() {
+        let (port, server) = mock_server(&[
+            ("OPTION MIME", "500 unknown command\r\n"),
+            (
+                "DEFINE wn gold",
+                "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n\r\nmore\r\n.\r\n250 ok\r\n",
+            ),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(!client.enable_mime().await.unwrap());
+
+        let definitions = client.define("wn", "gold").await.unwrap();
+        assert_eq!(definitions[0].body, "gold\n\nmore");
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suggestions_use_lev_and_cache_strategies() {
+        let (port, server) = mock_server(&[
+            ("DEFINE wn gupy", "552 no match\r\n"),
+            (
+                "SHOW STRAT",
+                "111 3 strategies present\r\nexact \"Match headwords exactly\"\r\nprefix \"Match prefixes\"\r\n\
+lev \"Match headwords within Levenshtein distance one\"\r\n.\r\n250 ok\r\n",
+            ),
+            (
+                "MATCH wn lev gupy",
+                "152 3 matches found\r\nwn \"guppy\"\r\nwn \"gump\"\r\nwn \"guppy\"\r\n.\r\n250 ok\r\n",
+            ),
+            ("DEFINE wn xyzzyx", "552 no match\r\n"),
+            ("MATCH wn lev xyzzyx", "552 no match\r\n"),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.define("wn", "gupy").await.unwrap().is_empty());
+        assert_eq!(
+            client.suggest("wn", "gupy").await.unwrap(),
+            ["guppy", "gump"]
+        );
+        assert!(client.define("wn", "xyzzyx").await.unwrap().is_empty());
+        assert!(client.suggest("wn", "xyzzyx").await.unwrap().is_empty());
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suggestions_fall_back_to_soundex() {
+        let (port, server) = mock_server(&[
+            ("DEFINE * nite", "552 no match\r\n"),
+            (
+                "SHOW STRAT",
+                "111 2 strategies present\r\nprefix \"Match prefixes\"\r\nsoundex \"Match using SOUNDEX algorithm\"\r\n.\r\n250 ok\r\n",
+            ),
+            ("MATCH * soundex nite", "152 2 matches found\r\nwn \"night\"\r\ngcide \"knight\"\r\n.\r\n250 ok\r\n"),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.define("*", "nite").await.unwrap().is_empty());
+        assert_eq!(
+            client.suggest("*", "nite").await.unwrap(),
+            ["night", "knight"]
+        );
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_refused_suggestions_are_none() {
+        let (port, server) = mock_server(&[
+            ("SHOW STRAT", "502 command not implemented\r\n"),
+            ("DEFINE wn gold", "250 ok\r\n"),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.suggest("wn", "gupy").await.unwrap().is_empty());
+        // The refused list is not asked for again.
+        assert!(client.suggest("wn", "nite").await.unwrap().is_empty());
+        client.define("wn", "gold").await.unwrap();
+        client.quit().await.unwrap();
+        server.await.unwrap();
+
+        let (port, server) = mock_server(&[
+            (
+                "SHOW STRAT",
+                "111 1 strategies present\r\nlev \"Match within distance one\"\r\n.\r\n250 ok\r\n",
+            ),
+            ("MATCH wn lev gupy", "551 invalid strategy\r\n"),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        assert!(client.suggest("wn", "gupy").await.unwrap().is_empty());
+        client.quit().await.unwrap();
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        match DictClient::connect("127.0.0.1", port).await {
+            Err(e @ DictError::Connect { .. }) => {
+                assert!(e
+                    .to_string()
+                    .starts_with(&format!("cannot connect to 127.0.0.1:{}", port)))
+            }
+            other => panic!("expected a connect error, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_mid_definition_names_the_word() {
+        // The server hangs up partway through the definition.
+        let (port, server) = mock_server(&[(
+            "DEFINE wn gold",
+            "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n  n 1: a so",
+        )])
+        .await;
+        let mut client = DictClient::connect("127.0.0.1", port).await.unwrap();
+        let result = client.define("wn", "gold").await;
+        server.await.unwrap();
+        match result {
+            Err(DictError::Io(e)) => {
+                assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+                assert!(
+                    e.to_string()
+                        .starts_with("looking up \"gold\": connection closed"),
+                    "{}",
+                    e
+                );
+            }
+            other => panic!("expected an I/O error, got {:?}", other),
+        }
+        // The session is over; later commands say why.
+        let later = client.define("wn", "silver").await;
+        assert!(
+            matches!(later, Err(DictError::Aborted(cause)) if cause.contains("connection closed"))
+        );
+    }
+}
@@ -0,0 +1,522 @@
+use crate::auth;
+use crate::error::DictError;
+use crate::greeting::{self, Greeting};
+use crate::mime;
+use crate::protocol::{self, quote, Reply};
+use crate::show::Listing;
+use crate::timeout::{self, Timed};
+use crate::tls::{self, DictStream, Transport};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt, BufReader, ReadHalf, WriteHalf};
+
+// How the client names itself to the server with CLIENT.
+pub const CLIENT_NAME: &str = concat!("rust-dictclient/", env!("CARGO_PKG_VERSION"));
+
+pub type TcpDictConnection =
+    DictConnection<BufReader<Timed<ReadHalf<DictStream>>>, Timed<WriteHalf<DictStream>>>;
+
+// An open session with a DICT server: one command at a time, each answered
+// by a complete Reply. Failed lookups (e.g. 552 no match) are replies, not
+// errors; transport problems, malformed responses and refused commands are
+// DictErrors.
+pub struct DictConnection<R, W> {
+    reader: R,
+    writer: W,
+    banner: String,
+    greeting: Greeting,
+    // Why the session was abandoned, once an error has left the reader at an
+    // unknown point in a reply.
+    aborted: Option<String>,
+    // Set once the server has accepted OPTION MIME.
+    mime: bool,
+}
+
+pub async fn connect(host: &str, port: u16) -> Result<TcpDictConnection, DictError> {
+    connect_with_timeout(host, port, timeout::DEFAULT_TIMEOUT).await
+}
+
+pub async fn connect_with_timeout(
+    host: &str,
+    port: u16,
+    limit: Duration,
+) -> Result<TcpDictConnection, DictError> {
+    connect_over(host, port, limit, Transport::Plain).await
+}
+
+// Connects over plain TCP or TLS and identifies the client. `limit` bounds
+// connecting, the TLS handshake, and then every read and write.
+pub async fn connect_over(
+    host: &str,
+    port: u16,
+    limit: Duration,
+    transport: Transport,
+) -> Result<TcpDictConnection, DictError> {
+    let socket = timeout::connect(host, port, limit).await?;
+    let stream = match transport {
+        Transport::Plain => DictStream::Plain(socket),
+        Transport::Tls { insecure } => {
+            match tokio::time::timeout(limit, tls::handshake(socket, host, insecure)).await {
+                Ok(stream) => stream?,
+                Err(_) => return Err(DictError::Timeout(limit)),
+            }
+        }
+    };
+    let (read_half, write_half) = tokio::io::split(stream);
+    let mut connection = DictConnection::new(
+        BufReader::new(Timed::new(read_half, limit)),
+        Timed::new(write_half, limit),
+    )
+    .await?;
+    connection.identify(CLIENT_NAME).await?;
+    Ok(connection)
+}
+
+impl<R, W> DictConnection<R, W>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    // Takes over an already open stream and reads the 220 greeting from it.
+    pub async fn new(mut reader: R, writer: W) -> Result<Self, DictError> {
+        let banner = protocol::read_line(&mut reader).await?;
+        // 420 / 421 instead of a greeting: busy or shutting down, worth retrying.
+        if let Some(code @ 400..=499) = protocol::status_code(&banner) {
+            let message = banner[3..].trim().to_string();
+            return Err(DictError::Server { code, message });
+        }
+        if !banner.starts_with("220") {
+            return Err(DictError::UnexpectedResponse(banner));
+        }
+        let greeting = greeting::parse_greeting(&banner);
+        Ok(Self {
+            reader,
+            writer,
+            banner,
+            greeting,
+            aborted: None,
+            mime: false,
+        })
+    }
+
+    pub fn banner(&self) -> &str {
+        &self.banner
+    }
+
+    pub fn greeting(&self) -> &Greeting {
+        &self.greeting
+    }
+
+    pub async fn authenticate(&mut self, user: &str, secret: &str) -> Result<(), DictError> {
+        let msg_id =
+            self.greeting.msg_id.clone().ok_or_else(|| {
+                DictError::UnexpectedResponse("greeting has no msg-id".to_string())
+            })?;
+        self.check_usable()?;
+        let result =
+            auth::authenticate(&mut self.reader, &mut self.writer, user, secret, &msg_id).await;
+        self.note_failure(&result);
+        result
+    }
+
+    // Asks the server to put a MIME header before every text block (RFC 2229,
+    // 3.10). Returns false, leaving the session in plain mode, if the server
+    // refuses with a 5xx status.
+    pub async fn enable_mime(&mut self) -> Result<bool, DictError> {
+        match self.command("OPTION MIME").await {
+            Ok(_) => {
+                self.mime = true;
+                Ok(true)
+            }
+            Err(DictError::Server {
+                code: 500..=599, ..
+            }) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn is_mime(&self) -> bool {
+        self.mime
+    }
+
+    // Tells the server which client this is (CLIENT, RFC 2229 3.6). The
+    // reply carries nothing, so a server that refuses the command is fine.
+    pub async fn identify(&mut self, client: &str) -> Result<(), DictError> {
+        match self.command(&format!("CLIENT {}", client)).await {
+            Ok(_)
+            | Err(DictError::Server {
+                code: 500..=599, ..
+            }) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Sends one command line, which `protocol::command_line` ends with CRLF,
+    // and reads its reply. Once a command fails partway, e.g. by timing out
+    // mid-reply, every later one fails with DictError::Aborted without
+    // touching the connection. In MIME mode the text blocks come back with
+    // their headers stripped and their bodies decoded; a body that fails to
+    // decode does not end the session.
+    async fn command(&mut self, command: &str) -> Result<Reply, DictError> {
+        self.check_usable()?;
+        let result = self.exchange(command).await;
+        self.note_failure(&result);
+        match result {
+            Ok(reply) if self.mime => mime::decode_reply(reply),
+            result => result,
+        }
+    }
+
+    async fn exchange(&mut self, command: &str) -> Result<Reply, DictError> {
+        self.writer
+            .write_all(protocol::command_line(command).as_bytes())
+            .await?;
+        self.writer.flush().await?;
+        protocol::read_reply(&mut self.reader).await
+    }
+
+    fn check_usable(&self) -> Result<(), DictError> {
+        match &self.aborted {
+            Some(cause) => Err(DictError::Aborted(cause.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn note_failure<T>(&mut self, result: &Result<T, DictError>) {
+        if let Err(e) = result {
+            if !e.leaves_session_usable() {
+                self.aborted = Some(e.to_string());
+            }
+        }
+    }
+
+    // Looks `word` up in every database.
+    pub async fn define(&mut self, word: &str) -> Result<Reply, DictError> {
+        self.define_in("*", word).await
+    }
+
+    pub async fn define_in(&mut self, database: &str, word: &str) -> Result<Reply, DictError> {
+        self.command(&format!("DEFINE {} {}", database, quote(word)))
+            .await
+    }
+
+    // Uses the server's default strategy (".") across all databases.
+    pub async fn match_word(&mut self, word: &str) -> Result<Reply, DictError> {
+        self.match_in("*", ".", word).await
+    }
+
+    // Lists headwords in `database` ("*" for all) that `strategy` (e.g.
+    // "prefix", "exact") matches against `word`.
+    pub async fn match_in(
+        &mut self,
+        database: &str,
+        strategy: &str,
+        word: &str,
+    ) -> Result<Reply, DictError> {
+        self.command(&format!("MATCH {} {} {}", database, strategy, quote(word)))
+            .await
+    }
+
+    pub async fn databases(&mut self) -> Result<Reply, DictError> {
+        self.show(Listing::Databases).await
+    }
+
+    // SHOW DB or SHOW STRAT; see `show::parse_listing` for the reply.
+    pub async fn show(&mut self, listing: Listing) -> Result<Reply, DictError> {
+        self.command(listing.command()).await
+    }
+
+    // SHOW SERVER: the server's description of itself, as a 114 text block.
+    pub async fn server_info(&mut self) -> Result<Reply, DictError> {
+        self.command("SHOW SERVER").await
+    }
+
+    // STATUS: a single 210 line, typically the server's counters and timings.
+    pub async fn status(&mut self) -> Result<Reply, DictError> {
+        self.command("STATUS").await
+    }
+
+    pub async fn quit(mut self) -> Result<(), DictError> {
+        self.command("QUIT").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncRead};
+    use tokio::net::TcpListener;
+
+    // Reads one command line from the client, which must end in CRLF.
+    async fn read_command<R: AsyncBufRead + Unpin>(reader: &mut R) -> Option<String> {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line).await.unwrap() == 0 {
+            return None;
+        }
+        let command = line
+            .strip_suffix(b"\r\n")
+            .expect("command not terminated by CRLF");
+        Some(String::from_utf8(command.to_vec()).unwrap())
+    }
+
+    // Plays the server side of a session: sends the greeting, then expects
+    // each command in turn and answers it with the scripted reply.
+    async fn serve<S: AsyncRead + AsyncWrite + Unpin>(stream: S, script: &[(&str, &str)]) {
+        let (read_half, mut write_half) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read_half);
+        write_half
+            .write_all(b"220 dict.example.org dictd <auth.mime> <1.2@dict.example.org>\r\n")
+            .await
+            .unwrap();
+        for (expected, reply) in script {
+            let command = read_command(&mut reader)
+                .await
+                .expect("client hung up early");
+            assert_eq!(command, *expected);
+            write_half.write_all(reply.as_bytes()).await.unwrap();
+        }
+    }
+
+    const SCRIPT: &[(&str, &str)] = &[
+        (
+            "DEFINE * gold",
+            "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n  n 1: a metal\r\n.\r\n250 ok\r\n",
+        ),
+        ("DEFINE eng-lat \"fool's gold\"", "552 no match\r\n"),
+        ("MATCH * . gol", "152 2 matches found\r\nwn \"gold\"\r\nwn \"golf\"\r\n.\r\n250 ok\r\n"),
+        ("MATCH * prefix \"fool's gold\"", "552 no match\r\n"),
+        ("SHOW DB", "110 1 databases present\r\nwn \"WordNet\"\r\n.\r\n250 ok\r\n"),
+        ("SHOW STRAT", "555 No strategies available\r\n"),
+        ("QUIT", "221 bye\r\n"),
+    ];
+
+    async fn run_script<R, W>(mut connection: DictConnection<R, W>)
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        assert!(connection.greeting().supports("auth"));
+        assert_eq!(
+            connection.greeting().msg_id.as_deref(),
+            Some("<1.2@dict.example.org>")
+        );
+
+        let definition = connection.define("gold").await.unwrap();
+        assert!(definition.is_success());
+        assert_eq!(definition.text, vec!["gold", "  n 1: a metal"]);
+
+        let missing = connection
+            .define_in("eng-lat", "fool's gold")
+            .await
+            .unwrap();
+        assert_eq!((missing.code, missing.message.as_str()), (552, "no match"));
+
+        let matches = connection.match_word("gol").await.unwrap();
+        assert_eq!(matches.text, vec!["wn \"gold\"", "wn \"golf\""]);
+        let none = connection
+            .match_in("*", "prefix", "fool's gold")
+            .await
+            .unwrap();
+        assert_eq!(none.code, 552);
+
+        let databases = connection.databases().await.unwrap();
+        assert_eq!(databases.text, vec!["wn \"WordNet\""]);
+        let strategies = connection.show(Listing::Strategies).await.unwrap();
+        assert_eq!(strategies.code, 555);
+
+        connection.quit().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_command_sequence() {
+        let (client, server) = tokio::io::duplex(1024);
+        let server = tokio::spawn(async move { serve(server, SCRIPT).await });
+        let (read_half, write_half) = tokio::io::split(client);
+        let connection = DictConnection::new(BufReader::new(read_half), write_half)
+            .await
+            .unwrap();
+        run_script(connection).await;
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let hello = format!("CLIENT {}", CLIENT_NAME);
+            serve(
+                socket,
+                &[&[(hello.as_str(), "250 ok\r\n")], SCRIPT].concat(),
+            )
+            .await;
+        });
+        let connection = connect("127.0.0.1", port).await.unwrap();
+        assert!(connection.banner().starts_with("220 dict.example.org"));
+        run_script(connection).await;
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_line_precedes_first_define() {
+        assert!(CLIENT_NAME.starts_with("rust-dictclient/"));
+        assert!(CLIENT_NAME.ends_with(env!("CARGO_PKG_VERSION")));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let (read_half, mut write_half) = socket.into_split();
+            let mut reader = BufReader::new(read_half);
+            write_half
+                .write_all(b"220 dict.example.org dictd <> <1.2@dict.example.org>\r\n")
+                .await
+                .unwrap();
+            let mut received = Vec::new();
+            while let Some(command) = read_command(&mut reader).await {
+                let reply: &[u8] = match command.split(' ').next() {
+                    // A server that does not know CLIENT must not stop the session.
+                    Some("CLIENT") => b"500 unknown command\r\n",
+                    Some("DEFINE") => b"552 no match\r\n",
+                    _ => b"221 bye\r\n",
+                };
+                write_half.write_all(reply).await.unwrap();
+                received.push(command);
+            }
+            received
+        });
+        let mut connection = connect("127.0.0.1", port).await.unwrap();
+        assert_eq!(connection.define("gold").await.unwrap().code, 552);
+        connection.quit().await.unwrap();
+        let received = server.await.unwrap();
+        assert_eq!(
+            received,
+            [
+                format!("CLIENT {}", CLIENT_NAME),
+                "DEFINE * gold".into(),
+                "QUIT".into()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_info_and_status() {
+        let script: &[(&str, &str)] = &[
+            (
+                "SHOW SERVER",
+                "114 server information\r\ndictd 1.12.1 on Linux 6.1\r\nOn dict.example.org: up 2+03:04:05\r\n\
+..dotted line\r\n.\r\n250 ok\r\n",
+            ),
+            ("STATUS", "210 status [d/m/c = 12/3/40; 9.000r 0.100u 0.020s]\r\n"),
+        ];
+        let (client, server) = tokio::io::duplex(1024);
+        let server = tokio::spawn(async move { serve(server, script).await });
+        let (read_half, write_half) = tokio::io::split(client);
+        let mut connection = DictConnection::new(BufReader::new(read_half), write_half)
+            .await
+            .unwrap();
+
+        let info = connection.server_info().await.unwrap();
+        assert_eq!(info.preliminary, [(114, "server information".to_string())]);
+        assert_eq!(
+            info.text,
+            [
+                "dictd 1.12.1 on Linux 6.1",
+                "On dict.example.org: up 2+03:04:05",
+                ".dotted line"
+            ]
+        );
+        let status = connection.status().await.unwrap();
+        assert_eq!(
+            (status.code, status.message.as_str()),
+            (210, "status [d/m/c = 12/3/40; 9.000r 0.100u 0.020s]")
+        );
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_unavailable_mid_session() {
+        let script: &[(&str, &str)] = &[
+            (
+                "DEFINE * gold",
+                "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n.\r\n250 ok\r\n",
+            ),
+            ("DEFINE nonesuch gold", "550 invalid database, use \"SHOW DB\" for list of databases\r\n"),
+            ("DEFINE * silver", "420 Server temporarily unavailable\r\n"),
+        ];
+        let (client, server) = tokio::io::duplex(1024);
+        let server = tokio::spawn(async move { serve(server, script).await });
+        let (read_half, write_half) = tokio::io::split(client);
+        let mut connection = DictConnection::new(BufReader::new(read_half), write_half)
+            .await
+            .unwrap();
+
+        assert!(connection.define("gold").await.unwrap().is_success());
+        let refused = connection.define_in("nonesuch", "gold").await;
+        assert!(matches!(refused, Err(DictError::Server { code: 550, .. })));
+        let unavailable = connection.define("silver").await;
+        match unavailable {
+            Err(DictError::Server { code, message }) => {
+                assert_eq!(
+                    (code, message.as_str()),
+                    (420, "Server temporarily unavailable")
+                )
+            }
+            other => panic!("expected 420, got {:?}", other),
+        }
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timeout_mid_reply_aborts_session() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let (read_half, write_half) = tokio::io::split(client);
+        let limit = Duration::from_millis(50);
+        server
+            .write_all(b"220 dict.example.org dictd <> <1.2@dict.example.org>\r\n")
+            .await
+            .unwrap();
+        let mut connection = DictConnection::new(
+            BufReader::new(Timed::new(read_half, limit)),
+            Timed::new(write_half, limit),
+        )
+        .await
+        .unwrap();
+
+        // Half a reply, then silence.
+        server
+            .write_all(b"150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngo")
+            .await
+            .unwrap();
+        let stalled = connection.define("gold").await;
+        assert!(matches!(stalled, Err(DictError::Timeout(waited)) if waited == limit));
+
+        // The rest arriving late must not be read as the reply to the next command.
+        server.write_all(b"ld\r\n.\r\n250 ok\r\n").await.unwrap();
+        let next = connection.define("silver").await;
+        assert!(matches!(next, Err(DictError::Aborted(cause)) if cause.contains("no response")));
+        assert!(matches!(
+            connection.quit().await,
+            Err(DictError::Aborted(_))
+        ));
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_busy_greeting_is_a_server_error() {
+        let reader = BufReader::new(&b"420 Server temporarily unavailable\r\n"[..]);
+        match DictConnection::new(reader, Vec::new()).await {
+            Err(e @ DictError::Server { code: 420, .. }) => assert!(e.is_transient()),
+            other => panic!("expected 420, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_greeting() {
+        let reader = BufReader::new(&b"530 access denied\r\n"[..]);
+        let result = DictConnection::new(reader, Vec::new()).await;
+        assert!(
+            matches!(result, Err(DictError::UnexpectedResponse(line)) if line == "530 access denied")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::error::DictError;
+use crate::protocol::{check_count, unquote, Reply};
+use serde::{Deserialize, Serialize};
+
+// One definition from a DEFINE reply: the 151 header's fields and the text
+// block after it, lines joined with '\n'.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Definition {
+    pub database: String,
+    pub database_description: String,
+    pub headword: String,
+    pub body: String,
+}
+
+// Reads the definitions out of the reply to a DEFINE command. 552 (no match)
+// is an empty list; any other failure is an error. Each 151 header is paired
+// with the text block that follows it, and the count announced by the 150
+// line must agree with the definitions that arrived.
+pub fn parse_definitions(reply: &Reply) -> Result<Vec<Definition>, DictError> {
+    if reply.code == 552 {
+        return Ok(Vec::new());
+    }
+    if !reply.is_success() {
+        return Err(DictError::UnexpectedResponse(format!(
+            "{} {}",
+            reply.code, reply.message
+        )));
+    }
+    let headers: Vec<&String> = reply
+        .preliminary
+        .iter()
+        .filter(|(code, _)| *code == 151)
+        .map(|(_, m)| m)
+        .collect();
+    if headers.len() != reply.blocks.len() {
+        return Err(DictError::UnexpectedResponse(format!(
+            "{} definition headers for {} text blocks",
+            headers.len(),
+            reply.blocks.len()
+        )));
+    }
+    let mut definitions = Vec::new();
+    for (header, block) in headers.into_iter().zip(&reply.blocks) {
+        let (headword, database, database_description) = parse_header(header)
+            .ok_or_else(|| DictError::UnexpectedResponse(format!("151 {}", header)))?;
+        definitions.push(Definition {
+            database,
+            database_description,
+            headword,
+            body: block.join("\n"),
+        });
+    }
+    check_count(reply, 150, "definitions", definitions.len())?;
+    Ok(definitions)
+}
+
+// The text of a 151 line: `"headword" database "description"`. The headword
+// and description are quoted and may contain escaped quotes; the database
+// name is a bare word.
+fn parse_header(header: &str) -> Option<(String, String, String)> {
+    let (headword, rest) = unquote(header.trim())?;
+    let (database, rest) = rest.trim_start().split_once(char::is_whitespace)?;
+    let (description, _) = unquote(rest.trim_start())?;
+    Some((headword, database.to_string(), description))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::read_reply;
+    use tokio::io::BufReader;
+
+    async fn definitions(transcript: &[u8]) -> Result<Vec<Definition>, DictError> {
+        let reply = read_reply(&mut BufReader::new(transcript)).await.unwrap();
+        parse_definitions(&reply)
+    }
+
+    #[tokio::test]
+    async fn test_single_definition() {
+        let transcript = b"150 1 definitions retrieved\r\n\
+151 \"gold\" wn \"WordNet (r) 3.0 (2006)\"\r\n\
+gold\r\n    n 1: coins made of gold\r\n\
+.\r\n\
+250 ok [d/m/c = 1/0/16; 0.000r 0.000u 0.000s]\r\n";
+        let definitions = definitions(transcript).await.unwrap();
+        assert_eq!(
+            definitions,
+            vec![Definition {
+                database: "wn".to_string(),
+                database_description: "WordNet (r) 3.0 (2006)".to_string(),
+                headword: "gold".to_string(),
+                body: "gold\n    n 1: coins made of gold".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_multiple_definitions() {
+        let transcript = b"150 2 definitions retrieved\r\n\
+151 \"aurum\" eng-lat \"English-Latin Freedict dictionary\"\r\n\
+gold /gould/\r\n\
+aurum\r\n\
+.\r\n\
+151 \"Gold\" gcide \"The \\\"Collaborative\\\" International Dictionary\"\r\n\
+Gold \\Gold\\, n.\r\n\
+.\r\n\
+250 ok\r\n";
+        let definitions = definitions(transcript).await.unwrap();
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions[0].database, "eng-lat");
+        assert_eq!(definitions[0].headword, "aurum");
+        assert_eq!(definitions[0].body, "gold /gould/\naurum");
+        assert_eq!(
+            definitions[1].database_description,
+            "The \"Collaborative\" International Dictionary"
+        );
+        assert_eq!(definitions[1].body, "Gold \\Gold\\, n.");
+    }
+
+    #[tokio::test]
+    async fn test_dot_stuffed_body() {
+        let transcript = b"150 1 definitions retrieved\r\n\
+151 \"dotfile\" jargon \"The Jargon File\"\r\n\
+dotfile\r\n\
+..profile and ..login are examples\r\n\
+.\r\n\
+250 ok\r\n";
+        let definitions = definitions(transcript).await.unwrap();
+        assert_eq!(
+            definitions[0].body,
+            "dotfile\n.profile and ..login are examples"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_match() {
+        assert!(definitions(b"552 no match\r\n").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_count_must_agree() {
+        let transcript = b"150 2 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n.\r\n250 ok\r\n";
+        let result = definitions(transcript).await;
+        assert!(
+            matches!(result, Err(DictError::UnexpectedResponse(m)) if m.contains("announced 2"))
+        );
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("\"fool's gold\" wn \"WordNet\""),
+            Some((
+                "fool's gold".to_string(),
+                "wn".to_string(),
+                "WordNet".to_string()
+            ))
+        );
+        assert_eq!(parse_header("gold wn \"WordNet\""), None);
+        assert_eq!(parse_header("\"gold\" wn"), None);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::protocol::{classify, Status};
+use crate::timeout::Elapsed;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum DictError {
+    // The connection failed mid-session, e.g. the server hung up partway
+    // through a reply.
+    Io(io::Error),
+    // No connection to `host:port` could be made at all.
+    Connect {
+        host: String,
+        port: u16,
+        source: io::Error,
+    },
+    AuthFailed(String),
+    UnexpectedResponse(String),
+    Timeout(Duration),
+    // A 4xx or 5xx status other than the "nothing found" ones; see
+    // `protocol::Status`.
+    Server {
+        code: u16,
+        message: String,
+    },
+    // The server has no database by this name (550).
+    InvalidDatabase(String),
+    // A command on a connection an earlier error left in an unknown state,
+    // carrying that error's description.
+    Aborted(String),
+    // The TLS handshake with `host` failed, e.g. on an untrusted certificate.
+    Tls {
+        host: String,
+        message: String,
+    },
+}
+
+impl DictError {
+    // The network or the server let us down rather than the request being
+    // wrong, so trying again, perhaps elsewhere, may work.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            DictError::Io(_) | DictError::Connect { .. } | DictError::Timeout(_) => true,
+            DictError::Server { code, .. } => classify(*code) == Some(Status::Unavailable),
+            _ => false,
+        }
+    }
+
+    // True after a refused command or AUTH, whose whole reply has been read.
+    // Any other error may strike mid-reply, after which the connection
+    // cannot be trusted to be at a reply boundary.
+    pub fn leaves_session_usable(&self) -> bool {
+        match self {
+            DictError::AuthFailed(_) | DictError::InvalidDatabase(_) => true,
+            DictError::Server { code, .. } => classify(*code) == Some(Status::Refused),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DictError::Io(e) => write!(f, "I/O error: {}", e),
+            DictError::Connect { host, port, source } => {
+                write!(f, "cannot connect to {}:{}: {}", host, port, source)
+            }
+            DictError::AuthFailed(line) => write!(f, "authentication failed: {}", line),
+            DictError::UnexpectedResponse(line) => {
+                write!(f, "unexpected server response: {}", line)
+            }
+            DictError::Timeout(limit) => write!(f, "no response from server within {:?}", limit),
+            DictError::Server { code, message } => write!(f, "server replied {} {}", code, message),
+            DictError::InvalidDatabase(name) => write!(f, "server has no database \"{}\"", name),
+            DictError::Aborted(cause) => {
+                write!(f, "session aborted after an earlier error: {}", cause)
+            }
+            DictError::Tls { host, message } => write!(f, "TLS with {} failed: {}", host, message),
+        }
+    }
+}
+
+impl std::error::Error for DictError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DictError::Io(e) | DictError::Connect { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DictError {
+    fn from(e: io::Error) -> Self {
+        match e
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<Elapsed>())
+        {
+            Some(Elapsed(limit)) => DictError::Timeout(*limit),
+            None => DictError::Io(e),
+        }
+    }
+}
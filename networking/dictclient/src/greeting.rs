@@ -0,0 +1,77 @@
+// The 220 banner is "220 text <capabilities> <msg-id>" (RFC 2229, 3.1), where
+// capabilities are dot separated, e.g. "<auth.mime>", and the msg-id looks
+// like "<1234.5678@dict.example.org>". Either block may be missing.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Greeting {
+    pub capabilities: Vec<String>,
+    pub msg_id: Option<String>,
+}
+
+impl Greeting {
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(capability))
+    }
+}
+
+pub fn parse_greeting(line: &str) -> Greeting {
+    let mut blocks = Vec::new();
+    let mut rest = line.trim();
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>') {
+            Some(end) => {
+                blocks.push(&rest[start..=start + end]);
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+
+    let mut greeting = Greeting::default();
+    if let Some(last) = blocks.last() {
+        if last.contains('@') {
+            greeting.msg_id = Some(last.to_string());
+            blocks.pop();
+        }
+    }
+    if let Some(capabilities) = blocks.first() {
+        greeting.capabilities = capabilities[1..capabilities.len() - 1]
+            .split('.')
+            .filter(|c| !c.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    greeting
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greeting_with_capabilities() {
+        let greeting = parse_greeting(
+            "220 dict.dict.org dictd 1.12.1/rf on Linux <auth.mime> <51.7@dict.dict.org>\r\n",
+        );
+        assert_eq!(greeting.capabilities, vec!["auth", "mime"]);
+        assert_eq!(greeting.msg_id.as_deref(), Some("<51.7@dict.dict.org>"));
+        assert!(greeting.supports("AUTH"));
+    }
+
+    #[test]
+    fn test_greeting_without_capabilities() {
+        let greeting = parse_greeting("220 example dictd <1234.5678@dict.example.org>");
+        assert!(greeting.capabilities.is_empty());
+        assert_eq!(
+            greeting.msg_id.as_deref(),
+            Some("<1234.5678@dict.example.org>")
+        );
+    }
+
+    #[test]
+    fn test_greeting_without_blocks() {
+        assert_eq!(parse_greeting("220 hello"), Greeting::default());
+        assert!(parse_greeting("220 <mime>").msg_id.is_none());
+    }
+}
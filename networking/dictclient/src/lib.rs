@@ -0,0 +1,27 @@
+//! DICT protocol (RFC 2229) client: connecting, optionally over TLS, the
+//! greeting, AUTH and the DEFINE / MATCH / SHOW DB / SHOW STRAT commands.
+//! `client::DictClient` turns replies into definitions, matches and listings;
+//! `connection::DictConnection` underneath it hands back raw replies;
+//! `batch` looks a list of words up over several connections at once. The
+//! `dictclient` binary is a thin front end over this library, printing text
+//! laid out by `terminal` or, with `--json`, an `output::Report`.
+
+pub mod args;
+pub mod auth;
+pub mod batch;
+pub mod cache;
+pub mod client;
+pub mod connection;
+pub mod definition;
+pub mod error;
+pub mod greeting;
+pub mod matches;
+pub mod mime;
+pub mod output;
+pub mod protocol;
+pub mod repl;
+pub mod retry;
+pub mod show;
+pub mod terminal;
+pub mod timeout;
+pub mod tls;
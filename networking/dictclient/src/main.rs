@@ -1,47 +1,741 @@
-use tokio::net::TcpStream;
+use dictclient::args::{self, Command, Options, DEFAULT_MATCH_DATABASE};
+use dictclient::batch;
+use dictclient::cache::{self, DefinitionCache};
+use dictclient::client::TcpDictClient;
+use dictclient::definition::Definition;
+use dictclient::error::DictError;
+use dictclient::matches::Matches;
+use dictclient::output::{Lookup, Report};
+use dictclient::protocol::{classify, quote, Status};
+use dictclient::repl;
+use dictclient::retry::{self, RetryPolicy};
+use dictclient::show::{self, Listing};
+use dictclient::terminal::{self, Screen, Style};
+use dictclient::timeout::DEFAULT_TIMEOUT;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::io::{AsyncBufReadExt, BufReader};
 
-const SERVER: &str = "dict.org";
-const PORT: u16 = 2628;
+// Exit status when the session went fine but some word found nothing.
+const EXIT_NOT_FOUND: u8 = 3;
+// Exit status when the server rejected the credentials.
+const EXIT_AUTH_FAILED: u8 = 4;
+// Exit status when no server could be connected to.
+const EXIT_CONNECT_FAILED: u8 = 5;
+// Exit status when the server stopped answering within --timeout.
+const EXIT_TIMEOUT: u8 = 6;
+// Exit status when the server has no database by the --database name.
+const EXIT_INVALID_DATABASE: u8 = 7;
+// Exit status when the server refused a command or answered nonsense.
+const EXIT_SERVER_ERROR: u8 = 8;
 
-#[tokio::main]
-async fn main() {
-    match TcpStream::connect((SERVER, PORT)).await {
-        Ok(mut socket) => {
-            let (read_half, mut write_half) = socket.split();
-            let mut reader = BufReader::new(read_half);
-            let mut line = String::new();
-
-            // Read initial server greeting
-            reader.read_line(&mut line).await.unwrap();
-            println!("Server: {}", line.trim());
-            line.clear();
-
-            // Define a word
-            let word = "gold";
-            let command = format!("DEFINE eng-lat {}
-", word);
-            write_half.write_all(command.as_bytes()).await.unwrap();
-            write_half.flush().await.unwrap();
-
-            // Read response
-            while reader.read_line(&mut line).await.unwrap() != 0 {
-                if line.trim() == "." {
-                    break;
+// The definition cache of a run, and the server whose definitions it is
+// serving.
+struct Cache {
+    definitions: DefinitionCache,
+    server: String,
+}
+
+// Opens the cache for looking words up, unless --no-cache, --match (which does
+// not DEFINE) or there is no directory to keep it in.
+fn open_cache(options: &Options) -> Option<DefinitionCache> {
+    if !options.cache || options.strategy.is_some() || options.words.is_empty() {
+        return None;
+    }
+    let Some(dir) = options
+        .cache_dir
+        .clone()
+        .or_else(|| cache::default_dir(|name| std::env::var(name).ok()))
+    else {
+        if options.verbose {
+            eprintln!("No cache directory: set XDG_CACHE_HOME or HOME, or use --cache-dir");
+        }
+        return None;
+    };
+    let definitions = DefinitionCache::open(&dir, options.cache_ttl);
+    if let Some(reason) = definitions.discarded() {
+        eprintln!(
+            "Ignoring unreadable cache {}: {}",
+            definitions.path().display(),
+            reason
+        );
+    }
+    Some(definitions)
+}
+
+// Every word's definitions straight from the cache, and the server they were
+// fetched from, when all of them are cached there and something was found for
+// each, so the run need not connect at all. None if some word has to be asked
+// for, or the run does more than look words up.
+fn cached_lookups(
+    definitions: &DefinitionCache,
+    options: &Options,
+) -> Option<(String, Vec<Lookup>)> {
+    if options.refresh
+        || options.interactive
+        || options.list.is_some()
+        || options.server_info
+        || options.status
+    {
+        return None;
+    }
+    let now = SystemTime::now();
+    let database = options.database();
+    options.hosts.iter().find_map(|host| {
+        let mut hits = Vec::new();
+        for word in &options.words {
+            match definitions.get(host, database, word, now) {
+                Some((found, fetched)) if !found.is_empty() => hits.push((found, fetched)),
+                _ => return None,
+            }
+        }
+        let lookups = options
+            .words
+            .iter()
+            .zip(hits)
+            .map(|(word, (found, fetched))| {
+                report_hit(options, word, now, fetched);
+                Lookup::defined(word, found)
+            })
+            .collect();
+        Some((host.clone(), lookups))
+    })
+}
+
+fn report_hit(options: &Options, word: &str, now: SystemTime, fetched: SystemTime) {
+    if options.verbose {
+        let age = now.duration_since(fetched).unwrap_or_default();
+        eprintln!(
+            "[cache hit] {} in {}, fetched {} ago",
+            word,
+            options.database(),
+            cache::format_age(age)
+        );
+    }
+}
+
+// Shows lookups answered from the cache alone, as look_up_words or run_json
+// would have shown them.
+fn show_cached(
+    server: String,
+    lookups: Vec<Lookup>,
+    options: &Options,
+    style: Style,
+    screen: Option<Screen>,
+) -> ExitCode {
+    if options.json {
+        let report = Report {
+            server: Some(server),
+            lookups,
+            ..Report::default()
+        };
+        println!("{}", report.to_json());
+        return ExitCode::SUCCESS;
+    }
+    let mut text = Vec::new();
+    for lookup in &lookups {
+        if let Err(e) = write_lookup(&mut text, lookup, options, style) {
+            return failure_status(&e.into());
+        }
+    }
+    let shown = match screen.zip(terminal::pager_command(|name| std::env::var(name).ok())) {
+        Some((screen, pager)) => show_paged(&text, &pager, screen.height),
+        None => io::stdout().write_all(&text),
+    };
+    match shown {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            failure_status(&e.into())
+        }
+    }
+}
+
+fn save_cache(cache: Option<Cache>) {
+    if let Some(cache) = cache {
+        if let Err(e) = cache.definitions.save(SystemTime::now()) {
+            eprintln!(
+                "Could not save the cache {}: {}",
+                cache.definitions.path().display(),
+                e
+            );
+        }
+    }
+}
+
+// DEFINE by way of the cache: cached definitions still within the TTL are
+// used unless --refresh, and whatever the server sends is cached.
+async fn define(
+    client: &mut TcpDictClient,
+    cache: &mut Option<Cache>,
+    options: &Options,
+    word: &str,
+) -> Result<Vec<Definition>, DictError> {
+    let database = options.database();
+    let Some(cache) = cache else {
+        return client.define(database, word).await;
+    };
+    let now = SystemTime::now();
+    if !options.refresh {
+        if let Some((definitions, fetched)) =
+            cache.definitions.get(&cache.server, database, word, now)
+        {
+            report_hit(options, word, now, fetched);
+            return Ok(definitions);
+        }
+    }
+    if options.verbose {
+        let why = if options.refresh {
+            "refresh"
+        } else {
+            "cache miss"
+        };
+        eprintln!(
+            "[{}] {} in {}, asking {}",
+            why, word, database, cache.server
+        );
+    }
+    let definitions = client.define(database, word).await?;
+    cache
+        .definitions
+        .put(&cache.server, database, word, &definitions, now);
+    Ok(definitions)
+}
+
+// Looks up one word as `batch::look_up` does, defining it by way of the
+// cache.
+async fn look_up(
+    client: &mut TcpDictClient,
+    cache: &mut Option<Cache>,
+    options: &Options,
+    word: &str,
+) -> Result<Lookup, DictError> {
+    let define = async |client: &mut TcpDictClient| define(client, cache, options, word).await;
+    batch::look_up(client, options, word, define).await
+}
+
+// Looks up each word in turn and writes what it found to `out`, then sends
+// QUIT. Ok(false) if some word found nothing.
+async fn look_up_words(
+    mut client: TcpDictClient,
+    cache: &mut Option<Cache>,
+    options: &Options,
+    style: Style,
+    out: &mut dyn Write,
+) -> Result<bool, DictError> {
+    let mut all_found = true;
+    for word in &options.words {
+        let lookup = look_up(&mut client, cache, options, word).await?;
+        write_lookup(out, &lookup, options, style)?;
+        all_found &= lookup.found();
+    }
+    client.quit().await?;
+    Ok(all_found)
+}
+
+// Like look_up_words, but collects everything into one report for --json.
+// An error ends the run and is recorded in the report, and returned along
+// with it.
+async fn report_words(
+    mut client: TcpDictClient,
+    cache: &mut Option<Cache>,
+    options: &Options,
+) -> (Report, Option<DictError>) {
+    let mut report = Report::new(client.banner());
+    for word in &options.words {
+        match look_up(&mut client, cache, options, word).await {
+            Ok(lookup) => report.lookups.push(lookup),
+            Err(e) => {
+                report.error = Some(e.to_string());
+                return (report, Some(e));
+            }
+        }
+    }
+    match client.quit().await {
+        Ok(()) => (report, None),
+        Err(e) => {
+            report.error = Some(e.to_string());
+            (report, Some(e))
+        }
+    }
+}
+
+// Writes the definition text, or the matches. A word with no definition
+// gets its spelling suggestions, or failing those a hint to try --match
+// unless --auto-match already listed matches. A lookup that failed outright
+// (only in a --words-file batch) says why.
+fn write_lookup(
+    out: &mut dyn Write,
+    lookup: &Lookup,
+    options: &Options,
+    style: Style,
+) -> io::Result<()> {
+    if lookup.is_failure() {
+        let error = lookup.error.as_deref().unwrap_or_default();
+        writeln!(out, "Could not look up {}: {}", lookup.word, error)?;
+    }
+    if let Some(definitions) = &lookup.definitions {
+        if definitions.is_empty() {
+            writeln!(out, "No definition found for {}", lookup.word)?;
+            match &lookup.suggestions {
+                Some(suggestions) if !suggestions.is_empty() => {
+                    write_suggestions(out, suggestions)?
                 }
-                if !line.starts_with(|c: char| c.is_digit(10)) {
-                    println!("{}", line.trim());
-                } else if line.starts_with("552") {
-                    println!("No definition found for {}", word);
-                    break;
+                _ if !options.auto_match => {
+                    writeln!(out, "Try: dictclient --match {}", quote(&lookup.word))?
                 }
-                line.clear();
+                _ => {}
             }
+        }
+        write_definitions(out, definitions, style)?;
+    }
+    if let Some(matches) = &lookup.matches {
+        write_matches(out, &lookup.word, matches)?;
+    }
+    Ok(())
+}
+
+// Writes each definition under a line naming the database it came from,
+// laid out in `style`.
+fn write_definitions(
+    out: &mut dyn Write,
+    definitions: &[Definition],
+    style: Style,
+) -> io::Result<()> {
+    for (i, definition) in definitions.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        write!(out, "{}", terminal::format_definition(definition, style))?;
+    }
+    Ok(())
+}
+
+// Prints `text`, through `pager` when it would not fit on a screen `height`
+// lines high. Should the pager not start, the text is printed after all.
+fn show_paged(text: &[u8], pager: &str, height: usize) -> io::Result<()> {
+    if terminal::overflows(&String::from_utf8_lossy(text), height) {
+        match terminal::page(text, pager) {
+            Ok(()) => return Ok(()),
+            Err(e) => eprintln!("Could not run the pager '{}': {}", pager, e),
+        }
+    }
+    io::stdout().write_all(text)
+}
+
+fn write_suggestions(out: &mut dyn Write, suggestions: &[String]) -> io::Result<()> {
+    writeln!(out, "Did you mean: {}", suggestions.join(", "))
+}
+
+// Writes headwords under the database they came from.
+fn write_matches(out: &mut dyn Write, word: &str, matches: &Matches) -> io::Result<()> {
+    if matches.is_empty() {
+        writeln!(out, "No matches found for {}", word)?;
+    }
+    for (database, words) in &matches.databases {
+        writeln!(out, "{}:", database)?;
+        for word in words {
+            writeln!(out, "  {}", word)?;
+        }
+    }
+    Ok(())
+}
 
-            // Send quit
-            write_half.write_all(b"quit
-").await.unwrap();
-            write_half.flush().await.unwrap();
+// Prints databases or strategies as a table.
+fn print_listing(listing: Listing, entries: &[(String, String)]) {
+    if entries.is_empty() {
+        println!("{}", listing.empty_message());
+    }
+    for line in show::format_table(entries) {
+        println!("{}", line);
+    }
+}
+
+// Prints the server's databases or strategies, then sends QUIT.
+async fn list(mut client: TcpDictClient, listing: Listing) -> Result<(), DictError> {
+    print_listing(listing, &client.show(listing).await?);
+    client.quit().await
+}
+
+// Prints the SHOW SERVER text for --server-info and the STATUS line for
+// --status.
+async fn describe_server(client: &mut TcpDictClient, options: &Options) -> Result<(), DictError> {
+    if options.server_info {
+        for line in client.server_info().await? {
+            println!("{}", line);
+        }
+    }
+    if options.status {
+        println!("{}", client.status().await?);
+    }
+    Ok(())
+}
+
+// Carries out one interactive command, looking words up in `database`. A
+// word with no definition gets spelling suggestions if `suggest` is set.
+async fn run_action(
+    client: &mut TcpDictClient,
+    database: &mut String,
+    suggest: bool,
+    style: Style,
+    action: repl::Action,
+) -> Result<(), DictError> {
+    let out = &mut io::stdout();
+    match action {
+        repl::Action::Define(word) => {
+            let definitions = client.define(database, &word).await?;
+            if definitions.is_empty() {
+                writeln!(out, "No definition found for {}", word)?;
+                let suggestions = if suggest {
+                    client.suggest(database, &word).await?
+                } else {
+                    Vec::new()
+                };
+                if !suggestions.is_empty() {
+                    write_suggestions(out, &suggestions)?;
+                }
+            }
+            write_definitions(out, &definitions, style)?;
+        }
+        repl::Action::Match { strategy, word } => write_matches(
+            out,
+            &word,
+            &client.match_word(database, &strategy, &word).await?,
+        )?,
+        repl::Action::UseDatabase(name) => *database = name,
+        repl::Action::ShowDatabases => {
+            print_listing(Listing::Databases, &client.show_databases().await?)
+        }
+        repl::Action::Help => println!("{}", repl::HELP),
+        repl::Action::Quit => {}
+    }
+    Ok(())
+}
+
+// Reads commands from stdin until /quit or EOF (Ctrl-D), and sends QUIT
+// either way. Words are looked up in --db if given, else every database.
+async fn run_interactive(
+    mut client: TcpDictClient,
+    options: &Options,
+    style: Style,
+) -> Result<(), DictError> {
+    let mut database = options
+        .database
+        .clone()
+        .unwrap_or_else(|| DEFAULT_MATCH_DATABASE.to_string());
+    let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+    println!("{}", repl::HELP);
+    loop {
+        print!("{}", repl::prompt(&database));
+        io::stdout().flush()?;
+        let action = match stdin.next_line().await? {
+            None => {
+                println!();
+                repl::Action::Quit
+            }
+            Some(input) => match repl::parse_input(&input) {
+                Ok(Some(action)) => action,
+                Ok(None) => continue,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    continue;
+                }
+            },
+        };
+        if action == repl::Action::Quit {
+            return client.quit().await;
+        }
+        match run_action(&mut client, &mut database, options.suggest, style, action).await {
+            // A refused command leaves the session usable; anything else
+            // (a 4xx, a dropped connection) ends it.
+            Err(DictError::Server { code, message }) if classify(code) == Some(Status::Refused) => {
+                println!("{} {}", code, message);
+            }
+            Err(e @ DictError::InvalidDatabase(_)) => println!("{}", e),
+            Err(e) => {
+                eprintln!("Lost the session with the server");
+                return Err(e);
+            }
+            Ok(()) => {}
+        }
+    }
+}
+
+// Readies the session for lookups: AUTH if credentials were given, then
+// OPTION MIME for --mime.
+async fn prepare(
+    client: &mut TcpDictClient,
+    credentials: &Option<(String, String)>,
+    options: &Options,
+) -> Result<(), DictError> {
+    authenticate(client, credentials).await?;
+    if options.mime && !client.enable_mime().await? {
+        eprintln!("Server refused OPTION MIME; continuing without it");
+    }
+    Ok(())
+}
+
+// Authenticates when credentials are provided, using the msg-id from the
+// greeting
+async fn authenticate(
+    client: &mut TcpDictClient,
+    credentials: &Option<(String, String)>,
+) -> Result<(), DictError> {
+    if let Some((user, secret)) = credentials {
+        let greeting = client.greeting().clone();
+        match &greeting.msg_id {
+            Some(_) if greeting.capabilities.is_empty() || greeting.supports("auth") => {
+                client.authenticate(user, secret).await?;
+            }
+            Some(_) => eprintln!("Server does not advertise AUTH; skipping"),
+            None => eprintln!("Server greeting has no msg-id; skipping AUTH"),
+        }
+    }
+    Ok(())
+}
+
+// The exit status for a run `e` ended: one per kind of failure, and 1 for a
+// connection lost mid-session.
+fn failure_status(e: &DictError) -> ExitCode {
+    match e {
+        DictError::AuthFailed(_) => ExitCode::from(EXIT_AUTH_FAILED),
+        DictError::Connect { .. } | DictError::Tls { .. } => ExitCode::from(EXIT_CONNECT_FAILED),
+        DictError::Timeout(_) => ExitCode::from(EXIT_TIMEOUT),
+        DictError::InvalidDatabase(_) => ExitCode::from(EXIT_INVALID_DATABASE),
+        DictError::Server { .. } | DictError::UnexpectedResponse(_) => {
+            ExitCode::from(EXIT_SERVER_ERROR)
+        }
+        DictError::Io(_) | DictError::Aborted(_) => ExitCode::FAILURE,
+    }
+}
+
+// Runs the lookups for --json and prints the report.
+async fn run_json(
+    connected: Result<(String, TcpDictClient), DictError>,
+    credentials: &Option<(String, String)>,
+    options: &Options,
+    definitions: Option<DefinitionCache>,
+) -> ExitCode {
+    let (report, failure) = match connected {
+        Err(e) => (Report::failed(format!("failed to connect: {}", e)), Some(e)),
+        Ok((server, mut client)) => {
+            let (report, failure) = match prepare(&mut client, credentials, options).await {
+                Ok(()) => {
+                    let mut cache = definitions.map(|definitions| Cache {
+                        definitions,
+                        server: server.clone(),
+                    });
+                    let done = report_words(client, &mut cache, options).await;
+                    save_cache(cache);
+                    done
+                }
+                Err(e) => (
+                    Report {
+                        error: Some(e.to_string()),
+                        ..Report::new(client.banner())
+                    },
+                    Some(e),
+                ),
+            };
+            (
+                Report {
+                    server: Some(server),
+                    ..report
+                },
+                failure,
+            )
+        }
+    };
+    println!("{}", report.to_json());
+    match failure {
+        Some(e) => failure_status(&e),
+        None if report.all_found() => ExitCode::SUCCESS,
+        None => ExitCode::from(EXIT_NOT_FOUND),
+    }
+}
+
+// Looks up the words of --words-file over --concurrency connections of their
+// own, each readied like the main one, and writes every result to --output or
+// stdout in the file's order: text, in `style` on stdout, or with --json one
+// object per line. The summary goes to stderr.
+async fn run_batch(
+    path: &Path,
+    credentials: Option<(String, String)>,
+    options: Options,
+    style: Style,
+) -> ExitCode {
+    let words = match std::fs::read_to_string(path) {
+        Ok(text) => batch::read_words(&text),
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (mut out, style): (Box<dyn Write>, Style) = match &options.output {
+        None => (Box::new(io::stdout()), style),
+        Some(output) => match File::create(output) {
+            Ok(file) => (Box::new(BufWriter::new(file)), Style::PLAIN),
+            Err(e) => {
+                eprintln!("Cannot write {}: {}", output.display(), e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let options = Arc::new(options);
+    let connect = {
+        let options = options.clone();
+        move || {
+            let (options, credentials) = (options.clone(), credentials.clone());
+            async move {
+                let limit = options.timeout.unwrap_or(DEFAULT_TIMEOUT);
+                let policy = RetryPolicy::new(options.retries);
+                let transport = options.transport();
+                let (_, mut client) = retry::connect_any(
+                    &options.hosts,
+                    options.port(),
+                    limit,
+                    transport,
+                    policy,
+                    |_, _| {},
+                )
+                .await?;
+                prepare(&mut client, &credentials, &options).await?;
+                Ok(client)
+            }
+        }
+    };
+    let emit = |lookup: &Lookup| {
+        if options.json {
+            writeln!(out, "{}", lookup.to_json())
+        } else {
+            write_lookup(&mut out, lookup, &options, style)
+        }
+    };
+    let summary = batch::run(words, options.concurrency(), options.clone(), connect, emit).await;
+    let summary = match summary.and_then(|summary| out.flush().map(|()| summary)) {
+        Ok(summary) => summary,
+        Err(e) => {
+            eprintln!("Cannot write the results: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    eprintln!("Looked up {} words: {}", summary.total(), summary);
+    if summary.failed > 0 {
+        ExitCode::FAILURE
+    } else if summary.missing > 0 {
+        ExitCode::from(EXIT_NOT_FOUND)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match args::parse(&args) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("dictclient: {}\n\n{}", message, args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let credentials = match options.credentials(|name| std::env::var(name).ok()) {
+        Ok(credentials) => credentials,
+        Err(message) => {
+            eprintln!("dictclient: {}", message);
+            return ExitCode::from(2);
+        }
+    };
+
+    let screen = Screen::of_stdout();
+    let style = Style::for_screen(screen, options.color);
+    if let Some(path) = options.words_file.clone() {
+        return run_batch(&path, credentials, *options, style).await;
+    }
+
+    // Words all defined in the cache are answered without a connection.
+    let definitions = open_cache(&options);
+    if let Some((server, lookups)) = definitions
+        .as_ref()
+        .and_then(|definitions| cached_lookups(definitions, &options))
+    {
+        return show_cached(server, lookups, &options, style, screen);
+    }
+
+    let limit = options.timeout.unwrap_or(DEFAULT_TIMEOUT);
+    let policy = RetryPolicy::new(options.retries);
+    let connected = retry::connect_any(
+        &options.hosts,
+        options.port(),
+        limit,
+        options.transport(),
+        policy,
+        |host, e| {
+            eprintln!("{}: {}", host, e);
+        },
+    )
+    .await;
+    if options.json {
+        return run_json(connected, &credentials, &options, definitions).await;
+    }
+    let (server, mut client) = match connected {
+        Ok(connected) => connected,
+        Err(e) => {
+            eprintln!("Failed to connect to {}", options.hosts.join(", "));
+            return failure_status(&e);
+        }
+    };
+    println!("Server: {} ({})", client.banner(), server);
+
+    if let Err(e) = prepare(&mut client, &credentials, &options).await {
+        eprintln!("{}", e);
+        return failure_status(&e);
+    }
+
+    if let Err(e) = describe_server(&mut client, &options).await {
+        eprintln!("{}", e);
+        return failure_status(&e);
+    }
+
+    let mut cache = definitions.map(|definitions| Cache {
+        definitions,
+        server,
+    });
+    let result = match options.list {
+        _ if options.interactive => run_interactive(client, &options, style)
+            .await
+            .map(|()| true),
+        Some(listing) => list(client, listing).await.map(|()| true),
+        None if options.words.is_empty() => client.quit().await.map(|()| true),
+        // On a terminal the output is held back until it is all there, to
+        // see whether it needs the pager.
+        None => match screen.zip(terminal::pager_command(|name| std::env::var(name).ok())) {
+            Some((screen, pager)) => {
+                let mut held = Vec::new();
+                let result = look_up_words(client, &mut cache, &options, style, &mut held).await;
+                let shown = show_paged(&held, &pager, screen.height);
+                result.and_then(|found| shown.map(|()| found).map_err(DictError::from))
+            }
+            None => look_up_words(client, &mut cache, &options, style, &mut io::stdout()).await,
+        },
+    };
+    save_cache(cache);
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_NOT_FOUND),
+        Err(e) => {
+            eprintln!("{}", e);
+            failure_status(&e)
         }
-        Err(e) => eprintln!("Failed to connect: {}", e),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,161 @@
+use crate::error::DictError;
+use crate::protocol::{check_count, parse_pair, Reply};
+
+pub const DEFAULT_STRATEGY: &str = "prefix";
+
+// Strategies to find spelling suggestions with, best first: edit distance,
+// then sound, then prefix.
+pub const SUGGESTION_STRATEGIES: &[&str] = &["lev", "soundex", "prefix"];
+// Most suggestions offered for one word.
+pub const MAX_SUGGESTIONS: usize = 10;
+
+// Headwords from a MATCH reply, grouped by database in the order the server
+// first listed each database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Matches {
+    pub databases: Vec<(String, Vec<String>)>,
+}
+
+impl Matches {
+    pub fn count(&self) -> usize {
+        self.databases.iter().map(|(_, words)| words.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    // Distinct headwords across all databases, in the order first listed,
+    // at most `limit` of them.
+    pub fn headwords(&self, limit: usize) -> Vec<String> {
+        let mut headwords: Vec<String> = Vec::new();
+        for word in self.databases.iter().flat_map(|(_, words)| words) {
+            if headwords.len() == limit {
+                break;
+            }
+            if !headwords.contains(word) {
+                headwords.push(word.clone());
+            }
+        }
+        headwords
+    }
+
+    fn add(&mut self, database: String, word: String) {
+        match self
+            .databases
+            .iter_mut()
+            .find(|(name, _)| *name == database)
+        {
+            Some((_, words)) => words.push(word),
+            None => self.databases.push((database, vec![word])),
+        }
+    }
+}
+
+// Reads the headwords out of the reply to a MATCH command. 552 (no match) is
+// an empty list. The count announced by the 152 status line must agree with
+// the lines that follow it, so a reply cut short is caught.
+pub fn parse_matches(reply: &Reply) -> Result<Matches, DictError> {
+    let mut matches = Matches::default();
+    if reply.code == 552 {
+        return Ok(matches);
+    }
+    for line in &reply.text {
+        let (database, word) =
+            parse_pair(line).ok_or_else(|| DictError::UnexpectedResponse(line.clone()))?;
+        matches.add(database, word);
+    }
+    check_count(reply, 152, "matches", matches.count())?;
+    Ok(matches)
+}
+
+// The first of SUGGESTION_STRATEGIES among the `offered` strategy names, as
+// listed by SHOW STRAT.
+pub fn suggestion_strategy(offered: &[String]) -> Option<&'static str> {
+    SUGGESTION_STRATEGIES
+        .iter()
+        .find(|strategy| offered.iter().any(|name| name == *strategy))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::read_reply;
+    use tokio::io::BufReader;
+
+    async fn reply(transcript: &[u8]) -> Reply {
+        read_reply(&mut BufReader::new(transcript)).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_matches_grouped_by_database() {
+        let transcript = b"152 4 matches found\r\n\
+wn \"gold\"\r\n\
+gcide \"Gold\"\r\n\
+wn \"gold rush\"\r\n\
+wn goldfish\r\n\
+.\r\n\
+250 ok [d/m/c = 0/4/9; 0.000r 0.000u 0.000s]\r\n";
+        let matches = parse_matches(&reply(transcript).await).unwrap();
+        assert_eq!(matches.count(), 4);
+        assert_eq!(
+            matches.databases,
+            vec![
+                (
+                    "wn".to_string(),
+                    vec![
+                        "gold".to_string(),
+                        "gold rush".to_string(),
+                        "goldfish".to_string()
+                    ]
+                ),
+                ("gcide".to_string(), vec!["Gold".to_string()]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_match() {
+        let matches = parse_matches(&reply(b"552 no match\r\n").await).unwrap();
+        assert!(matches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_count_must_agree() {
+        let transcript = b"152 3 matches found\r\nwn \"gold\"\r\n.\r\n250 ok\r\n";
+        let result = parse_matches(&reply(transcript).await);
+        assert!(
+            matches!(result, Err(DictError::UnexpectedResponse(m)) if m.contains("announced 3"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_headwords_are_distinct_and_limited() {
+        let transcript =
+            b"152 4 matches found\r\nwn \"gold\"\r\ngcide \"gold\"\r\nwn \"golf\"\r\nwn \"gild\"\r\n.\r\n250 ok\r\n";
+        let matches = parse_matches(&reply(transcript).await).unwrap();
+        assert_eq!(matches.headwords(10), ["gold", "golf", "gild"]);
+        assert_eq!(matches.headwords(2), ["gold", "golf"]);
+    }
+
+    #[test]
+    fn test_suggestion_strategy_order() {
+        let offered = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            suggestion_strategy(&offered(&["exact", "prefix", "soundex", "lev"])),
+            Some("lev")
+        );
+        assert_eq!(
+            suggestion_strategy(&offered(&["exact", "prefix", "soundex"])),
+            Some("soundex")
+        );
+        assert_eq!(suggestion_strategy(&offered(&["prefix"])), Some("prefix"));
+        assert_eq!(suggestion_strategy(&offered(&["exact"])), None);
+    }
+}
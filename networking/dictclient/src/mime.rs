@@ -0,0 +1,197 @@
+use crate::error::DictError;
+use crate::protocol::Reply;
+
+// The MIME header that OPTION MIME puts before every text block (RFC 2229,
+// 3.10). Absent fields mean the defaults: text/plain, 8bit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MimeHeaders {
+    pub content_type: Option<String>,
+    pub transfer_encoding: Option<String>,
+}
+
+// Splits the header off a text block: every line up to the first blank one,
+// provided they all look like "Name: value". A block without a header is
+// all body, so a server that forgets it does not lose text.
+pub fn split_headers(block: &[String]) -> (MimeHeaders, &[String]) {
+    let mut headers = MimeHeaders::default();
+    let Some(blank) = block.iter().position(|line| line.is_empty()) else {
+        return (headers, block);
+    };
+    let mut fields = Vec::new();
+    for line in &block[..blank] {
+        match line.split_once(':') {
+            Some((name, value)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                fields.push((name, value.trim()))
+            }
+            _ => return (headers, block),
+        }
+    }
+    for (name, value) in fields {
+        if name.eq_ignore_ascii_case("Content-Type") {
+            headers.content_type = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("Content-Transfer-Encoding") {
+            headers.transfer_encoding = Some(value.to_string());
+        }
+    }
+    (headers, &block[blank + 1..])
+}
+
+// Undoes the transfer encoding of a body, returning its lines.
+// quoted-printable and base64 are decoded (as UTF-8, replacing stray bytes);
+// 7bit, 8bit, binary or no encoding leave the lines as they are.
+pub fn decode_body(headers: &MimeHeaders, body: &[String]) -> Result<Vec<String>, DictError> {
+    let encoding = headers
+        .transfer_encoding
+        .as_deref()
+        .unwrap_or("8bit")
+        .to_ascii_lowercase();
+    let bytes = match encoding.as_str() {
+        "base64" => decode_base64(&body.concat())?,
+        "quoted-printable" => decode_quoted_printable(body)?,
+        _ => return Ok(body.to_vec()),
+    };
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    Ok(text
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r').to_string())
+        .collect())
+}
+
+// Strips and decodes the MIME header of every text block in a reply.
+pub fn decode_reply(mut reply: Reply) -> Result<Reply, DictError> {
+    let mut blocks = Vec::with_capacity(reply.blocks.len());
+    for block in &reply.blocks {
+        let (headers, body) = split_headers(block);
+        blocks.push(decode_body(&headers, body)?);
+    }
+    reply.text = blocks.concat();
+    reply.blocks = blocks;
+    Ok(reply)
+}
+
+fn decode_base64(text: &str) -> Result<Vec<u8>, DictError> {
+    let invalid = || DictError::UnexpectedResponse(format!("invalid base64 body: {}", text));
+    let mut bytes = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err(invalid()),
+        };
+        buffer = (buffer << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    if bits >= 6 {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+fn decode_quoted_printable(lines: &[String]) -> Result<Vec<u8>, DictError> {
+    let mut bytes = Vec::new();
+    for line in lines {
+        let line = line.trim_end_matches([' ', '\t']);
+        // A trailing "=" is a soft line break: the next line continues this one.
+        let (line, soft_break) = match line.strip_suffix('=') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let mut rest = line.as_bytes();
+        while let Some((&c, after)) = rest.split_first() {
+            if c != b'=' {
+                bytes.push(c);
+                rest = after;
+                continue;
+            }
+            let byte = after
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| {
+                    DictError::UnexpectedResponse(format!(
+                        "invalid quoted-printable line: {}",
+                        line
+                    ))
+                })?;
+            bytes.push(byte);
+            rest = &after[2..];
+        }
+        if !soft_break {
+            bytes.push(b'\n');
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_headers() {
+        let block = lines(&[
+            "Content-Type: text/plain; charset=utf-8",
+            "Content-Transfer-Encoding: base64",
+            "",
+            "Z29sZA==",
+        ]);
+        let (headers, body) = split_headers(&block);
+        assert_eq!(
+            headers.content_type.as_deref(),
+            Some("text/plain; charset=utf-8")
+        );
+        assert_eq!(headers.transfer_encoding.as_deref(), Some("base64"));
+        assert_eq!(body, ["Z29sZA=="]);
+
+        // An empty header: just the blank line.
+        let block = lines(&["", "gold"]);
+        assert_eq!(split_headers(&block), (MimeHeaders::default(), &block[1..]));
+
+        // No header at all: the blank line belongs to the body.
+        let block = lines(&["gold", "", "  n 1: a soft metal"]);
+        assert_eq!(split_headers(&block), (MimeHeaders::default(), &block[..]));
+    }
+
+    #[test]
+    fn test_decode_base64() {
+        assert_eq!(decode_base64("Z29sZA==").unwrap(), b"gold");
+        assert_eq!(decode_base64("Z29s\r\nZGZp c2g=").unwrap(), b"goldfish");
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert!(decode_base64("Z29sZA!=").is_err());
+        assert!(decode_base64("Z").is_err());
+    }
+
+    #[test]
+    fn test_decode_quoted_printable() {
+        let body = lines(&["caf=C3=A9 au lait=", "s, ", "=3D done"]);
+        assert_eq!(
+            decode_quoted_printable(&body).unwrap(),
+            "café au laits,\n= done\n".as_bytes()
+        );
+        assert!(decode_quoted_printable(&lines(&["bad =ZZ"])).is_err());
+    }
+
+    #[test]
+    fn test_unencoded_body_is_untouched() {
+        let body = lines(&["gold", "  n 1: a soft metal"]);
+        let headers = MimeHeaders {
+            transfer_encoding: Some("8bit".to_string()),
+            ..MimeHeaders::default()
+        };
+        assert_eq!(decode_body(&headers, &body).unwrap(), body);
+    }
+}
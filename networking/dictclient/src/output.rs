@@ -0,0 +1,216 @@
+use crate::definition::Definition;
+use crate::error::DictError;
+use crate::matches::Matches;
+use serde::{Serialize, Serializer};
+
+// Everything one `dictclient --json` invocation found, printed as a single
+// JSON document. `server` is the host that answered, of those given with
+// --host. `error` is set when the run stopped early: no server could be
+// reached, AUTH was refused, or the server gave up mid-session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub server: Option<String>,
+    pub banner: Option<String>,
+    pub lookups: Vec<Lookup>,
+    pub error: Option<String>,
+}
+
+// The outcome for one word: its definitions, or its matches in --match mode
+// (and under --auto-match when it has no definition). `suggestions` are
+// similar words offered when it has none. `error` says why nothing was found,
+// or, with neither definitions nor matches, why the lookup failed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Lookup {
+    pub word: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definitions: Option<Vec<Definition>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "flat_matches"
+    )]
+    pub matches: Option<Matches>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<String>>,
+    pub error: Option<String>,
+}
+
+impl Report {
+    pub fn new(banner: &str) -> Self {
+        Self {
+            banner: Some(banner.to_string()),
+            ..Self::default()
+        }
+    }
+
+    // A run that never got as far as a greeting.
+    pub fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+
+    // True if the run completed and every word found something.
+    pub fn all_found(&self) -> bool {
+        self.error.is_none() && self.lookups.iter().all(Lookup::found)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a report always serializes")
+    }
+}
+
+impl Lookup {
+    pub fn defined(word: &str, definitions: Vec<Definition>) -> Self {
+        let error = definitions
+            .is_empty()
+            .then(|| format!("no definition found for {}", word));
+        Self {
+            word: word.to_string(),
+            definitions: Some(definitions),
+            error,
+            ..Self::default()
+        }
+    }
+
+    pub fn matched(word: &str, matches: Matches) -> Self {
+        let error = matches
+            .is_empty()
+            .then(|| format!("no matches found for {}", word));
+        Self {
+            word: word.to_string(),
+            matches: Some(matches),
+            error,
+            ..Self::default()
+        }
+    }
+
+    // A lookup that got no answer at all, e.g. because the connection dropped.
+    pub fn failed(word: &str, error: &DictError) -> Self {
+        Self {
+            word: word.to_string(),
+            error: Some(error.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn found(&self) -> bool {
+        self.error.is_none()
+    }
+
+    // True if the lookup failed, as opposed to finding nothing.
+    pub fn is_failure(&self) -> bool {
+        self.error.is_some() && self.definitions.is_none() && self.matches.is_none()
+    }
+
+    // One line of --words-file --json output.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("a lookup always serializes")
+    }
+}
+
+#[derive(Serialize)]
+struct Match<'a> {
+    database: &'a str,
+    headword: &'a str,
+}
+
+// Matches go out as a flat list of {database, headword} in server order,
+// which is easier to consume than the grouping `Matches` keeps.
+fn flat_matches<S: Serializer>(
+    matches: &Option<Matches>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let flat: Option<Vec<Match>> = matches.as_ref().map(|matches| {
+        matches
+            .databases
+            .iter()
+            .flat_map(|(database, words)| {
+                words
+                    .iter()
+                    .map(move |headword| Match { database, headword })
+            })
+            .collect()
+    });
+    flat.serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::parse_definitions;
+    use crate::matches::parse_matches;
+    use crate::protocol::read_reply;
+    use tokio::io::BufReader;
+
+    const BANNER: &str = "220 dict.org dictd 1.12 <auth.mime> <42.7@dict.org>";
+
+    #[tokio::test]
+    async fn test_definitions_json_shape() {
+        let transcript = b"150 1 definitions retrieved\r\n\
+151 \"gold\" wn \"WordNet (r) 3.0 (2006)\"\r\n\
+gold\r\n    n 1: coins made of gold\r\n\
+.\r\n\
+250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&transcript[..]))
+            .await
+            .unwrap();
+        let mut report = Report {
+            server: Some("dict.org".to_string()),
+            ..Report::new(BANNER)
+        };
+        report
+            .lookups
+            .push(Lookup::defined("gold", parse_definitions(&reply).unwrap()));
+        assert!(report.all_found());
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"server":"dict.org","banner":"220 dict.org dictd 1.12 <auth.mime> <42.7@dict.org>","#,
+                r#""lookups":[{"word":"gold","definitions":[{"database":"wn","#,
+                r#""database_description":"WordNet (r) 3.0 (2006)","headword":"gold","#,
+                r#""body":"gold\n    n 1: coins made of gold"}],"error":null}],"error":null}"#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_match_json_shape() {
+        let transcript = b"152 2 matches found\r\nwn \"gold\"\r\ngcide \"Gold\"\r\n.\r\n250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&transcript[..]))
+            .await
+            .unwrap();
+        let mut lookup = Lookup::defined("gol", Vec::new());
+        lookup.matches = Some(parse_matches(&reply).unwrap());
+        let mut report = Report::new(BANNER);
+        report.lookups.push(lookup);
+        assert!(!report.all_found());
+        let json = report.to_json();
+        assert!(json.contains(
+            r#"{"word":"gol","definitions":[],"matches":[{"database":"wn","headword":"gold"},{"database":"gcide","headword":"Gold"}],"error":"no definition found for gol"}"#
+        ));
+    }
+
+    #[test]
+    fn test_suggestions_json_shape() {
+        let lookup = Lookup {
+            suggestions: Some(vec!["guppy".to_string(), "gump".to_string()]),
+            ..Lookup::defined("gupy", Vec::new())
+        };
+        assert!(!lookup.found());
+        assert_eq!(
+            serde_json::to_string(&lookup).unwrap(),
+            r#"{"word":"gupy","definitions":[],"suggestions":["guppy","gump"],"error":"no definition found for gupy"}"#
+        );
+    }
+
+    #[test]
+    fn test_connection_failure_json_shape() {
+        let report = Report::failed("failed to connect: connection refused".to_string());
+        assert!(!report.all_found());
+        assert_eq!(
+            report.to_json(),
+            r#"{"server":null,"banner":null,"lookups":[],"error":"failed to connect: connection refused"}"#
+        );
+    }
+}
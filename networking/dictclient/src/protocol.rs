@@ -0,0 +1,424 @@
+use crate::error::DictError;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+// Final status line of a command together with any text blocks the server
+// sent before it (definitions, matches, database lists). `text` holds every
+// block's lines run together; `blocks` keeps them apart, one per definition.
+// `preliminary` holds the 1xx status lines, e.g. "152 2 matches found".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub code: u16,
+    pub message: String,
+    pub text: Vec<String>,
+    pub blocks: Vec<Vec<String>>,
+    pub preliminary: Vec<(u16, String)>,
+}
+
+impl Reply {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code)
+    }
+
+    pub fn first_block(&self) -> Option<&[String]> {
+        self.blocks.first().map(Vec::as_slice)
+    }
+}
+
+// What a status line's code (RFC 2229, 2.4) means for the rest of the reply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    // 1xx followed by a text block terminated by a lone ".".
+    TextFollows,
+    // Any other 1xx: more status lines follow.
+    Preliminary,
+    // 2xx, or 3xx asking for more input: the reply is complete.
+    Complete,
+    // 552 no match, 554 no databases, 555 no strategies: the command was
+    // fine but found nothing, and callers treat that as an empty result.
+    NoResults,
+    // 4xx: the server cannot serve the command now, e.g. 420 temporarily
+    // unavailable or 421 shutting down. It usually hangs up next.
+    Unavailable,
+    // Any other 5xx: the command was refused, e.g. 500 / 501 syntax error or
+    // 550 invalid database. The session can carry on.
+    Refused,
+}
+
+pub fn classify(code: u16) -> Option<Status> {
+    match code {
+        110..=114 | 151 | 152 => Some(Status::TextFollows),
+        100..=199 => Some(Status::Preliminary),
+        200..=399 => Some(Status::Complete),
+        552 | 554 | 555 => Some(Status::NoResults),
+        400..=499 => Some(Status::Unavailable),
+        500..=599 => Some(Status::Refused),
+        _ => None,
+    }
+}
+
+// Words containing spaces must be sent as a quoted string.
+pub fn quote(word: &str) -> String {
+    if word.contains(char::is_whitespace) {
+        format!("\"{}\"", word.replace('"', "\\\""))
+    } else {
+        word.to_string()
+    }
+}
+
+// Splits a leading quoted string off `text`, undoing `quote`: the value
+// unescaped, and whatever follows the closing quote.
+pub fn unquote(text: &str) -> Option<(String, &str)> {
+    let inner = text.strip_prefix('"')?;
+    let mut value = String::new();
+    let mut chars = inner.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?.1),
+            '"' => return Some((value, &inner[i + 1..])),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+// A line of a MATCH or SHOW listing: a bare name, then a headword or
+// description, quoted when it contains spaces and possibly holding escaped
+// quotes: `wn "fool's gold"`, `foldoc "The \"Free\" On-line Dictionary"`.
+pub fn parse_pair(line: &str) -> Option<(String, String)> {
+    let (name, rest) = line.trim().split_once(char::is_whitespace)?;
+    let rest = rest.trim();
+    let value = if rest.starts_with('"') {
+        unquote(rest)?.0
+    } else {
+        rest.to_string()
+    };
+    Some((name.to_string(), value))
+}
+
+// Checks the count announced by the `code` status line, e.g. "152 4 matches
+// found", against the `found` items that followed it, so a reply cut short is
+// caught. A reply without that line passes.
+pub fn check_count(reply: &Reply, code: u16, items: &str, found: usize) -> Result<(), DictError> {
+    let Some((_, message)) = reply.preliminary.iter().find(|(c, _)| *c == code) else {
+        return Ok(());
+    };
+    let count = message
+        .split_whitespace()
+        .next()
+        .and_then(|n| n.parse::<usize>().ok())
+        .ok_or_else(|| DictError::UnexpectedResponse(format!("{} {}", code, message)))?;
+    if count != found {
+        return Err(DictError::UnexpectedResponse(format!(
+            "{} announced {} {} but {} followed",
+            code, count, items, found
+        )));
+    }
+    Ok(())
+}
+
+// Frames a command for the wire. Every command line ends in CRLF (RFC 2229,
+// 2.3), and a line break inside `command` is sent as a space, so a word can
+// never split one command into two.
+pub fn command_line(command: &str) -> String {
+    let mut line: String = command
+        .trim_end_matches(['\r', '\n'])
+        .chars()
+        .map(|c| if c == '\r' || c == '\n' { ' ' } else { c })
+        .collect();
+    line.push_str("\r\n");
+    line
+}
+
+pub(crate) fn status_code(line: &str) -> Option<u16> {
+    let code = line.get(..3)?;
+    if code.bytes().all(|b| b.is_ascii_digit()) {
+        code.parse().ok()
+    } else {
+        None
+    }
+}
+
+// Reads one complete protocol line, without its line ending: the CRLF, or a
+// bare LF from a lax server, and nothing more, so a line's own trailing bytes
+// come through untouched. TCP may split a line across any number of reads;
+// the buffered reader keeps reading until the newline arrives. A line cut off
+// by the connection closing is an I/O error rather than a short line, and
+// stray non-UTF-8 bytes are replaced instead of failing the whole reply.
+pub async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<String, DictError> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line).await? == 0 {
+        return Err(closed("connection closed".to_string()));
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(closed(format!(
+            "connection closed mid-line: {}",
+            String::from_utf8_lossy(&line)
+        )));
+    }
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+// The server hanging up is an I/O failure like any other lost connection.
+fn closed(message: String) -> DictError {
+    DictError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, message))
+}
+
+// Reads status lines and text blocks until a final status ends the reply.
+// Complete and no-result replies are returned; a 4xx or any other 5xx
+// status becomes `DictError::Server` carrying the code and message.
+pub async fn read_reply<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Reply, DictError> {
+    let mut blocks: Vec<Vec<String>> = Vec::new();
+    let mut preliminary = Vec::new();
+    loop {
+        let line = read_line(reader).await?;
+        let status = status_code(&line).and_then(|code| Some((code, classify(code)?)));
+        let (code, status) = status.ok_or_else(|| DictError::UnexpectedResponse(line.clone()))?;
+        let message = line[3..].trim().to_string();
+        match status {
+            Status::Complete | Status::NoResults => {
+                return Ok(Reply {
+                    code,
+                    message,
+                    text: blocks.concat(),
+                    blocks,
+                    preliminary,
+                })
+            }
+            Status::Unavailable | Status::Refused => {
+                return Err(DictError::Server { code, message })
+            }
+            Status::Preliminary | Status::TextFollows => preliminary.push((code, message)),
+        }
+        if status == Status::TextFollows {
+            let mut block = Vec::new();
+            loop {
+                let line = read_line(reader).await?;
+                if line == "." {
+                    break;
+                }
+                // Text lines starting with a dot have it doubled on the wire.
+                match line.strip_prefix('.') {
+                    Some(unstuffed) => block.push(unstuffed.to_string()),
+                    None => block.push(line),
+                }
+            }
+            blocks.push(block);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    #[tokio::test]
+    async fn test_read_definition_reply() {
+        let input = b"150 1 definitions retrieved\r\n\
+151 \"gold\" wn \"WordNet\"\r\n\
+gold\r\n  n 1: coins made of gold\r\n.\r\n\
+250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&input[..])).await.unwrap();
+        assert_eq!(reply.code, 250);
+        assert!(reply.is_success());
+        assert_eq!(reply.text, vec!["gold", "  n 1: coins made of gold"]);
+        assert_eq!(
+            reply.preliminary[0],
+            (150, "1 definitions retrieved".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_definitions_kept_as_separate_blocks() {
+        let input = b"150 2 definitions retrieved\r\n\
+151 \"gold\" wn \"WordNet\"\r\ngold\r\n  n 1: a soft metal\r\n.\r\n\
+151 \"gold\" gcide \"GCIDE\"\r\nGold \\Gold\\\r\n.\r\n\
+250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&input[..])).await.unwrap();
+        assert_eq!(reply.blocks.len(), 2);
+        assert_eq!(
+            reply.first_block().unwrap(),
+            ["gold", "  n 1: a soft metal"]
+        );
+        assert_eq!(reply.text.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_dot_stuffed_lines() {
+        let input = b"151 \"dot\" jargon \"Jargon File\"\r\n..dotfile\r\n...\r\n.\r\n250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&input[..])).await.unwrap();
+        assert_eq!(reply.text, vec![".dotfile", ".."]);
+    }
+
+    #[tokio::test]
+    async fn test_dot_stuffed_body_is_reconstructed_exactly() {
+        let body = [
+            ".",
+            "..",
+            ". leading dot",
+            "...and more",
+            "trailing space ",
+            "",
+            "\tindented.",
+            ".\r",
+        ];
+        let mut transcript =
+            b"150 1 definitions retrieved\r\n151 \"dot\" jargon \"Jargon File\"\r\n".to_vec();
+        for line in body {
+            if line.starts_with('.') {
+                transcript.push(b'.');
+            }
+            transcript.extend_from_slice(line.as_bytes());
+            transcript.extend_from_slice(b"\r\n");
+        }
+        transcript.extend_from_slice(b".\r\n250 ok\r\n");
+
+        let reply = read_reply(&mut BufReader::new(&transcript[..]))
+            .await
+            .unwrap();
+        assert_eq!(reply.blocks, [body]);
+        assert_eq!(
+            reply.text.join("\r\n").into_bytes(),
+            body.join("\r\n").into_bytes()
+        );
+        assert_eq!(reply.code, 250);
+    }
+
+    #[tokio::test]
+    async fn test_lone_dot_ends_block_only_unstuffed() {
+        // "..": a text line holding a single dot, not the terminator.
+        let input = b"151 \"dot\" jargon \"Jargon File\"\r\n..\r\n.\r\n151 \"dot\" wn \"WordNet\"\r\n.\r\n250 ok\r\n";
+        let reply = read_reply(&mut BufReader::new(&input[..])).await.unwrap();
+        assert_eq!(reply.blocks, [vec!["."], vec![]]);
+    }
+
+    #[test]
+    fn test_command_line_ends_in_crlf() {
+        assert_eq!(command_line("SHOW DB"), "SHOW DB\r\n");
+        assert_eq!(command_line("QUIT\r\n"), "QUIT\r\n");
+        assert_eq!(command_line("STATUS\n"), "STATUS\r\n");
+        assert_eq!(
+            command_line("DEFINE * \"gold\nQUIT\""),
+            "DEFINE * \"gold QUIT\"\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_line_split_across_reads() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            server.write_all(b"151 \"gold\" wn \"Wor").await.unwrap();
+            server.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            server
+                .write_all(b"dNet\"\r\ngold\r\n.\r\n25")
+                .await
+                .unwrap();
+            server.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            server.write_all(b"0 ok\r\n").await.unwrap();
+        });
+
+        let reply = read_reply(&mut BufReader::new(client)).await.unwrap();
+        writer.await.unwrap();
+        assert_eq!((reply.code, reply.message.as_str()), (250, "ok"));
+        assert_eq!(reply.text, vec!["gold"]);
+    }
+
+    #[tokio::test]
+    async fn test_connection_closed_mid_line() {
+        let result = read_reply(&mut BufReader::new(&b"151 \"gold\" wn\r\ngol"[..])).await;
+        assert!(
+            matches!(result, Err(DictError::Io(e)) if e.to_string().ends_with("mid-line: gol"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_error_reply() {
+        let reply = read_reply(&mut BufReader::new(&b"552 no match\r\n"[..]))
+            .await
+            .unwrap();
+        assert_eq!((reply.code, reply.message.as_str()), (552, "no match"));
+        assert!(reply.text.is_empty());
+    }
+
+    async fn server_error(transcript: &[u8]) -> (u16, String) {
+        match read_reply(&mut BufReader::new(transcript)).await {
+            Err(DictError::Server { code, message }) => (code, message),
+            other => panic!("expected a server error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refusals_are_errors() {
+        assert_eq!(
+            server_error(b"550 invalid database, use \"SHOW DB\" for list of databases\r\n").await,
+            (
+                550,
+                "invalid database, use \"SHOW DB\" for list of databases".to_string()
+            )
+        );
+        assert_eq!(
+            server_error(b"501 syntax error, illegal parameters\r\n").await,
+            (501, "syntax error, illegal parameters".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_after_preliminary_lines() {
+        let transcript =
+            b"150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n.\r\n\
+421 server shutting down at operator request\r\n";
+        assert_eq!(server_error(transcript).await.0, 421);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_codes_are_unexpected() {
+        let result = read_reply(&mut BufReader::new(&b"999 what\r\n"[..])).await;
+        assert!(matches!(result, Err(DictError::UnexpectedResponse(line)) if line == "999 what"));
+    }
+
+    #[test]
+    fn test_parse_pair() {
+        assert_eq!(
+            parse_pair("wn \"fool's gold\""),
+            Some(("wn".to_string(), "fool's gold".to_string()))
+        );
+        assert_eq!(
+            parse_pair("  jargon   \"say \\\"hi\\\"\"  "),
+            Some(("jargon".to_string(), "say \"hi\"".to_string()))
+        );
+        assert_eq!(
+            parse_pair("wn gold"),
+            Some(("wn".to_string(), "gold".to_string()))
+        );
+        assert_eq!(parse_pair("wn \"unterminated"), None);
+        assert_eq!(parse_pair("lonely"), None);
+    }
+
+    #[test]
+    fn test_unquote_returns_the_rest() {
+        assert_eq!(
+            unquote("\"gold\" wn \"WordNet\""),
+            Some(("gold".to_string(), " wn \"WordNet\""))
+        );
+        assert_eq!(unquote("gold"), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(151), Some(Status::TextFollows));
+        assert_eq!(classify(150), Some(Status::Preliminary));
+        assert_eq!(classify(250), Some(Status::Complete));
+        assert_eq!(classify(552), Some(Status::NoResults));
+        assert_eq!(classify(420), Some(Status::Unavailable));
+        assert_eq!(classify(550), Some(Status::Refused));
+        assert_eq!(classify(600), None);
+    }
+}
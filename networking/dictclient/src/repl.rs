@@ -0,0 +1,134 @@
+// What the user types in --interactive mode: a bare word (or phrase) to look
+// up, or a command starting with '/'.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Define(String),
+    Match { strategy: String, word: String },
+    UseDatabase(String),
+    ShowDatabases,
+    Help,
+    Quit,
+}
+
+pub const HELP: &str = "\
+type a word to define it, or a command:
+  /match <strategy> <word>  list matching headwords, e.g. /match prefix gol
+  /db <name>                look words up in <name> from now on, * for all
+  /dbs                      list the server's databases
+  /help                     print this message
+  /quit                     end the session (as does Ctrl-D)";
+
+// The prompt names the database words are looked up in.
+pub fn prompt(database: &str) -> String {
+    format!("dict [{}]> ", database)
+}
+
+// Returns Ok(None) for a blank line and Err with a message for bad input.
+pub fn parse_input(line: &str) -> Result<Option<Action>, String> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return Ok(Some(Action::Define(line.to_string())));
+    };
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, argument.trim()),
+        None => (command, ""),
+    };
+    let action = match (name.to_ascii_lowercase().as_str(), argument) {
+        ("match", argument) => match argument.split_once(char::is_whitespace) {
+            Some((strategy, word)) => Action::Match {
+                strategy: strategy.to_string(),
+                word: word.trim().to_string(),
+            },
+            None => return Err("usage: /match <strategy> <word>".to_string()),
+        },
+        ("db", "") => return Err("usage: /db <name>".to_string()),
+        ("db", database) if database.contains(char::is_whitespace) => {
+            return Err(format!("'{}' is not a database name", database))
+        }
+        ("db", database) => Action::UseDatabase(database.to_string()),
+        ("dbs", "") => Action::ShowDatabases,
+        ("help", "") => Action::Help,
+        ("quit" | "exit", "") => Action::Quit,
+        _ => {
+            return Err(format!(
+                "unknown command '{}'; type /help for the list",
+                line
+            ))
+        }
+    };
+    Ok(Some(action))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> Action {
+        parse_input(line).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_bare_words_are_defined() {
+        assert_eq!(parsed("gold"), Action::Define("gold".to_string()));
+        assert_eq!(
+            parsed("  fool's gold \n"),
+            Action::Define("fool's gold".to_string())
+        );
+        assert_eq!(parse_input("   "), Ok(None));
+    }
+
+    #[test]
+    fn test_match() {
+        assert_eq!(
+            parsed("/match prefix gol"),
+            Action::Match {
+                strategy: "prefix".to_string(),
+                word: "gol".to_string()
+            }
+        );
+        assert_eq!(
+            parsed("/MATCH  exact  fool's gold"),
+            Action::Match {
+                strategy: "exact".to_string(),
+                word: "fool's gold".to_string()
+            }
+        );
+        assert_eq!(
+            parse_input("/match"),
+            Err("usage: /match <strategy> <word>".to_string())
+        );
+        assert_eq!(
+            parse_input("/match prefix"),
+            Err("usage: /match <strategy> <word>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_switch_database() {
+        assert_eq!(parsed("/db wn"), Action::UseDatabase("wn".to_string()));
+        assert_eq!(parsed("/db *"), Action::UseDatabase("*".to_string()));
+        assert_eq!(parse_input("/db"), Err("usage: /db <name>".to_string()));
+        assert!(parse_input("/db wn gcide").is_err());
+    }
+
+    #[test]
+    fn test_other_commands() {
+        assert_eq!(parsed("/dbs"), Action::ShowDatabases);
+        assert_eq!(parsed("/help"), Action::Help);
+        assert_eq!(parsed("/quit"), Action::Quit);
+        assert_eq!(parsed("/exit"), Action::Quit);
+        assert!(parse_input("/dbs all").is_err());
+        assert_eq!(
+            parse_input("/lookup gold"),
+            Err("unknown command '/lookup gold'; type /help for the list".to_string())
+        );
+    }
+
+    #[test]
+    fn test_prompt_names_database() {
+        assert_eq!(prompt("wn"), "dict [wn]> ");
+    }
+}
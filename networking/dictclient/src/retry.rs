@@ -0,0 +1,202 @@
+use crate::client::{DictClient, TcpDictClient};
+use crate::error::DictError;
+use crate::tls::Transport;
+use std::io;
+use std::time::Duration;
+
+pub const DEFAULT_RETRIES: u32 = 2;
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+// However many retries are allowed, none waits longer than this.
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+// How many times to retry a server after a transient failure, and how long
+// to wait before the first retry; the wait doubles with every retry after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_RETRIES)
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            retries,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+        }
+    }
+
+    // The wait before retry number `retry`, counting from 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(MAX_BACKOFF)
+    }
+}
+
+// Connects to the first of `hosts` that greets us, returning which one it
+// was. Each host is retried with backoff while it fails transiently (refused
+// or reset connections, timeouts, 420 / 421 greetings); any other failure
+// moves on to the next host at once. `on_failure` hears about every failed
+// attempt. When all hosts fail, the last error is returned.
+pub async fn connect_any(
+    hosts: &[String],
+    port: u16,
+    limit: Duration,
+    transport: Transport,
+    policy: RetryPolicy,
+    mut on_failure: impl FnMut(&str, &DictError),
+) -> Result<(String, TcpDictClient), DictError> {
+    let mut last_error = None;
+    for host in hosts {
+        for retry in 0..=policy.retries {
+            if retry > 0 {
+                tokio::time::sleep(policy.backoff(retry - 1)).await;
+            }
+            match DictClient::connect_over(host, port, limit, transport).await {
+                Ok(client) => return Ok((host.clone(), client)),
+                Err(e) => {
+                    on_failure(host, &e);
+                    let transient = e.is_transient();
+                    last_error = Some(e);
+                    if !transient {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        DictError::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no server to connect to",
+        ))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    const LIMIT: Duration = Duration::from_millis(100);
+
+    // Accepts every connection and then never sends a byte, holding the
+    // sockets open until the test ends.
+    async fn silent_server() -> (u16, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+        (port, server)
+    }
+
+    fn quick(retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            retries,
+            initial_backoff: Duration::from_millis(10),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_a_cap() {
+        let policy = RetryPolicy::new(10);
+        assert_eq!(policy.backoff(0), Duration::from_millis(500));
+        assert_eq!(policy.backoff(1), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(4));
+        assert_eq!(policy.backoff(9), MAX_BACKOFF);
+        assert_eq!(policy.backoff(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn test_silent_server_times_out() {
+        let (port, server) = silent_server().await;
+        let started = Instant::now();
+        let result = DictClient::connect_with_timeout("127.0.0.1", port, LIMIT).await;
+        assert!(matches!(result, Err(DictError::Timeout(limit)) if limit == LIMIT));
+        assert!(started.elapsed() < LIMIT * 10);
+        server.abort();
+    }
+
+    // Binding 127.0.0.2 needs the whole 127/8 block on loopback, as on Linux.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_retries_then_falls_back_to_next_host() {
+        let (silent_port, silent) = silent_server().await;
+        // The fallback listens on the same port of another loopback address.
+        let fallback = TcpListener::bind(("127.0.0.2", silent_port)).await.unwrap();
+        let answering = tokio::spawn(async move {
+            let (mut socket, _) = fallback.accept().await.unwrap();
+            socket
+                .write_all(b"220 fallback.dict.org dictd <> <1@fallback>\r\n")
+                .await
+                .unwrap();
+            let mut hello = String::new();
+            BufReader::new(&mut socket)
+                .read_line(&mut hello)
+                .await
+                .unwrap();
+            assert!(hello.starts_with("CLIENT "));
+            socket.write_all(b"250 ok\r\n").await.unwrap();
+            socket
+        });
+
+        let hosts = vec!["127.0.0.1".to_string(), "127.0.0.2".to_string()];
+        let mut failures = Vec::new();
+        let (host, client) = connect_any(
+            &hosts,
+            silent_port,
+            LIMIT,
+            Transport::Plain,
+            quick(2),
+            |host, e| failures.push((host.to_string(), e.to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(host, "127.0.0.2");
+        assert!(client.banner().starts_with("220 fallback.dict.org"));
+        assert_eq!(failures.len(), 3);
+        assert!(failures
+            .iter()
+            .all(|(host, e)| host == "127.0.0.1" && e.contains("no response")));
+        silent.abort();
+        drop(answering.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_permanent_failure_is_not_retried() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(b"530 access denied\r\n").await.unwrap();
+        });
+
+        let mut attempts = 0;
+        let result = connect_any(
+            &["127.0.0.1".to_string()],
+            port,
+            LIMIT,
+            Transport::Plain,
+            quick(3),
+            |_, _| attempts += 1,
+        )
+        .await;
+        assert!(
+            matches!(result, Err(DictError::UnexpectedResponse(line)) if line == "530 access denied")
+        );
+        assert_eq!(attempts, 1);
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::DictError;
+use crate::protocol::{check_count, parse_pair, Reply};
+
+// The two SHOW listings the client can ask for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Listing {
+    Databases,
+    Strategies,
+}
+
+impl Listing {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Listing::Databases => "SHOW DB",
+            Listing::Strategies => "SHOW STRAT",
+        }
+    }
+
+    // The 1xx code announcing the list, and the 5xx code meaning there is
+    // nothing to list.
+    fn codes(&self) -> (u16, u16) {
+        match self {
+            Listing::Databases => (110, 554),
+            Listing::Strategies => (111, 555),
+        }
+    }
+
+    // What to print when the server has none.
+    pub fn empty_message(&self) -> &'static str {
+        match self {
+            Listing::Databases => "The server has no databases",
+            Listing::Strategies => "The server offers no match strategies",
+        }
+    }
+}
+
+// Reads the (name, description) pairs out of the reply to SHOW DB or SHOW
+// STRAT. 554 / 555 (none present) is an empty list, and the count on the
+// 110 / 111 status line must agree with the lines that follow it.
+pub fn parse_listing(listing: Listing, reply: &Reply) -> Result<Vec<(String, String)>, DictError> {
+    let (announce, none) = listing.codes();
+    if reply.code == none {
+        return Ok(Vec::new());
+    }
+    if !reply.is_success() {
+        return Err(DictError::UnexpectedResponse(format!(
+            "{} {}",
+            reply.code, reply.message
+        )));
+    }
+    let entries = reply
+        .text
+        .iter()
+        .map(|line| parse_pair(line).ok_or_else(|| DictError::UnexpectedResponse(line.clone())))
+        .collect::<Result<Vec<_>, _>>()?;
+    check_count(reply, announce, "entries", entries.len())?;
+    Ok(entries)
+}
+
+// Lines of names and descriptions with the descriptions lined up.
+pub fn format_table(entries: &[(String, String)]) -> Vec<String> {
+    let width = entries
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    entries
+        .iter()
+        .map(|(name, description)| format!("{:<width$}  {}", name, description, width = width))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::read_reply;
+    use tokio::io::BufReader;
+
+    async fn reply(transcript: &[u8]) -> Reply {
+        read_reply(&mut BufReader::new(transcript)).await.unwrap()
+    }
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(a, b)| (a.to_string(), b.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_databases() {
+        let transcript = b"110 3 databases present\r\n\
+gcide \"The Collaborative International Dictionary of English v.0.48\"\r\n\
+wn \"WordNet (r) 3.0 (2006)\"\r\n\
+foldoc \"The \\\"Free\\\" On-line Dictionary of Computing (30 December 2018)\"\r\n\
+.\r\n\
+250 ok\r\n";
+        let entries = parse_listing(Listing::Databases, &reply(transcript).await).unwrap();
+        assert_eq!(
+            entries,
+            pairs(&[
+                (
+                    "gcide",
+                    "The Collaborative International Dictionary of English v.0.48"
+                ),
+                ("wn", "WordNet (r) 3.0 (2006)"),
+                (
+                    "foldoc",
+                    "The \"Free\" On-line Dictionary of Computing (30 December 2018)"
+                ),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strategies() {
+        let transcript = b"111 2 strategies available\r\n\
+exact \"Match headwords exactly\"\r\n\
+prefix \"Match prefixes\"\r\n\
+.\r\n\
+250 ok\r\n";
+        let entries = parse_listing(Listing::Strategies, &reply(transcript).await).unwrap();
+        assert_eq!(
+            entries,
+            pairs(&[
+                ("exact", "Match headwords exactly"),
+                ("prefix", "Match prefixes")
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_empty_lists() {
+        let none = reply(b"554 No databases present\r\n").await;
+        assert!(parse_listing(Listing::Databases, &none).unwrap().is_empty());
+        let none = reply(b"555 No strategies available\r\n").await;
+        assert!(parse_listing(Listing::Strategies, &none)
+            .unwrap()
+            .is_empty());
+        // 554 only means "none" in answer to SHOW DB.
+        assert!(parse_listing(
+            Listing::Strategies,
+            &reply(b"554 No databases present\r\n").await
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_count_must_agree() {
+        let transcript = b"110 2 databases present\r\nwn \"WordNet\"\r\n.\r\n250 ok\r\n";
+        let result = parse_listing(Listing::Databases, &reply(transcript).await);
+        assert!(
+            matches!(result, Err(DictError::UnexpectedResponse(m)) if m.contains("announced 2"))
+        );
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&pairs(&[("wn", "WordNet"), ("foldoc", "FOLDOC")]));
+        assert_eq!(table, vec!["wn      WordNet", "foldoc  FOLDOC"]);
+        assert!(format_table(&[]).is_empty());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::definition::Definition;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+// Screen size assumed on a terminal that will not say.
+pub const DEFAULT_WIDTH: usize = 80;
+pub const DEFAULT_HEIGHT: usize = 24;
+// Run when $PAGER is not set.
+pub const DEFAULT_PAGER: &str = "less";
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+// Bold cyan, for the line naming the database a definition came from.
+const HEADER: &str = "\x1b[1;36m";
+
+// The terminal stdout is attached to, in columns and lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Screen {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Screen {
+    // None when stdout is not a terminal. The size comes from the terminal
+    // itself, else $COLUMNS and $LINES, else 80x24.
+    pub fn of_stdout() -> Option<Self> {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        let from_env = |name| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0)
+        };
+        let (width, height) = window_size().unwrap_or_else(|| {
+            (
+                from_env("COLUMNS").unwrap_or(DEFAULT_WIDTH),
+                from_env("LINES").unwrap_or(DEFAULT_HEIGHT),
+            )
+        });
+        Some(Self { width, height })
+    }
+}
+
+#[cfg(unix)]
+fn window_size() -> Option<(usize, usize)> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a winsize into the struct it is given.
+    let status = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (status == 0 && size.ws_col > 0 && size.ws_row > 0)
+        .then_some((size.ws_col as usize, size.ws_row as usize))
+}
+
+#[cfg(not(unix))]
+fn window_size() -> Option<(usize, usize)> {
+    None
+}
+
+// How definitions are printed: wrapped at `width` columns, if any, and with
+// ANSI colors or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub width: Option<usize>,
+    pub color: bool,
+}
+
+impl Style {
+    // Lines as the server sent them and no escape codes, for pipes and files.
+    pub const PLAIN: Style = Style {
+        width: None,
+        color: false,
+    };
+
+    // Wrapped to the screen stdout is on, colored unless `color` is false;
+    // plain when stdout is not a terminal.
+    pub fn for_screen(screen: Option<Screen>, color: bool) -> Self {
+        match screen {
+            Some(screen) => Style {
+                width: Some(screen.width),
+                color,
+            },
+            None => Style::PLAIN,
+        }
+    }
+}
+
+// A definition as printed: a line naming the database, a blank line, then
+// the body. With color the database line is highlighted and the headword
+// starting the body is bold.
+pub fn format_definition(definition: &Definition, style: Style) -> String {
+    let header = format!(
+        "From {} [{}]:",
+        definition.database_description, definition.database
+    );
+    let mut text = if style.color {
+        format!("{}{}{}\n\n", HEADER, header, RESET)
+    } else {
+        format!("{}\n\n", header)
+    };
+    let mut lines: Vec<String> = match style.width {
+        Some(width) => definition
+            .body
+            .lines()
+            .flat_map(|line| wrap(line, width))
+            .collect(),
+        None => definition.body.lines().map(str::to_string).collect(),
+    };
+    if style.color {
+        if let Some(first) = lines.first_mut() {
+            *first = embolden(first, &definition.headword);
+        }
+    }
+    for line in lines {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    text
+}
+
+// Breaks `line` between words so no piece is wider than `width` columns,
+// where it can be. Every piece keeps the line's indentation; a word longer
+// than the room left gets a piece of its own. Lines that fit, blank lines and
+// lines indented past `width` come back unchanged.
+pub fn wrap(line: &str, width: usize) -> Vec<String> {
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let indent_width = indent.chars().count();
+    if line.chars().count() <= width || line.trim().is_empty() || indent_width >= width {
+        return vec![line.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut piece = indent.to_string();
+    let mut piece_width = indent_width;
+    for word in line.split_whitespace() {
+        let word_width = word.chars().count();
+        if piece_width > indent_width && piece_width + 1 + word_width > width {
+            pieces.push(std::mem::replace(&mut piece, indent.to_string()));
+            piece_width = indent_width;
+        }
+        if piece_width > indent_width {
+            piece.push(' ');
+            piece_width += 1;
+        }
+        piece.push_str(word);
+        piece_width += word_width;
+    }
+    pieces.push(piece);
+    pieces
+}
+
+// Makes `headword` bold where `line` starts with it, ignoring case and
+// indentation, as dictd bodies usually do.
+fn embolden(line: &str, headword: &str) -> String {
+    let start = line.len() - line.trim_start().len();
+    let rest = &line[start..];
+    match rest.get(..headword.len()) {
+        Some(found) if !headword.is_empty() && found.eq_ignore_ascii_case(headword) => {
+            format!(
+                "{}{}{}{}{}",
+                &line[..start],
+                BOLD,
+                found,
+                RESET,
+                &rest[headword.len()..]
+            )
+        }
+        _ => line.to_string(),
+    }
+}
+
+// Whether `text` runs past a screen `height` lines high, leaving the last
+// line for the shell prompt.
+pub fn overflows(text: &str, height: usize) -> bool {
+    text.lines().count() >= height
+}
+
+// The pager to show long output through: $PAGER, or less if it is unset.
+// None if $PAGER is empty or "cat", which ask for no paging.
+pub fn pager_command(env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let command = env("PAGER").unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command = command.trim();
+    (!command.is_empty() && command != "cat").then(|| command.to_string())
+}
+
+// Runs `command` through the shell and feeds it `text`, waiting until the
+// user leaves it. LESS defaults to FRX, as for git, so less passes the colors
+// through and exits at once when everything fits after all.
+pub fn page(text: &[u8], command: &str) -> io::Result<()> {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command).stdin(Stdio::piped());
+    if std::env::var_os("LESS").is_none() {
+        shell.env("LESS", "FRX");
+    }
+    let mut pager = shell.spawn()?;
+    let mut stdin = pager.stdin.take().expect("stdin is piped");
+    match stdin.write_all(text) {
+        // Quitting the pager before the end is fine.
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+        _ => drop(stdin),
+    }
+    pager.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gold() -> Definition {
+        Definition {
+            database: "wn".to_string(),
+            database_description: "WordNet (r) 3.0 (2006)".to_string(),
+            headword: "gold".to_string(),
+            body: "Gold\n  n 1: coins made of gold, a soft yellow malleable ductile metal\n\n  adj 1: made from or covered with gold".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("short line", 20), ["short line"]);
+        assert_eq!(wrap("", 20), [""]);
+        assert_eq!(
+            wrap("  n 1: coins made of gold, a soft yellow metal", 20),
+            ["  n 1: coins made of", "  gold, a soft", "  yellow metal"]
+        );
+        // A word wider than the line is not broken up.
+        assert_eq!(
+            wrap("see antidisestablishmentarianism now", 12),
+            ["see", "antidisestablishmentarianism", "now"]
+        );
+        assert_eq!(wrap("        deep", 4), ["        deep"]);
+        for piece in wrap("  ünïcödé wörds are counted by characters, not bytes", 16) {
+            assert!(piece.chars().count() <= 16, "{:?}", piece);
+        }
+    }
+
+    #[test]
+    fn test_plain_definition_is_untouched() {
+        assert_eq!(
+            format_definition(&gold(), Style::PLAIN),
+            format!("From WordNet (r) 3.0 (2006) [wn]:\n\n{}\n", gold().body)
+        );
+    }
+
+    #[test]
+    fn test_wrapped_without_color() {
+        let text = format_definition(
+            &gold(),
+            Style {
+                width: Some(40),
+                color: false,
+            },
+        );
+        assert_eq!(
+            text,
+            "From WordNet (r) 3.0 (2006) [wn]:\n\nGold\n  n 1: coins made of gold, a soft yellow\n  malleable ductile metal\n\n  adj 1: made from or covered with gold\n"
+        );
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_colored_header_and_bold_headword() {
+        let text = format_definition(
+            &gold(),
+            Style {
+                width: Some(80),
+                color: true,
+            },
+        );
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "\x1b[1;36mFrom WordNet (r) 3.0 (2006) [wn]:\x1b[0m"
+        );
+        assert_eq!(lines[2], "\x1b[1mGold\x1b[0m");
+        assert_eq!(
+            lines[3],
+            "  n 1: coins made of gold, a soft yellow malleable ductile metal"
+        );
+
+        let mut other = gold();
+        other.body = "  1. aurum".to_string();
+        assert!(format_definition(
+            &other,
+            Style {
+                width: None,
+                color: true
+            }
+        )
+        .ends_with("\n\n  1. aurum\n"));
+    }
+
+    #[test]
+    fn test_style_for_screen() {
+        let screen = Screen {
+            width: 100,
+            height: 30,
+        };
+        assert_eq!(
+            Style::for_screen(Some(screen), true),
+            Style {
+                width: Some(100),
+                color: true
+            }
+        );
+        assert_eq!(
+            Style::for_screen(Some(screen), false),
+            Style {
+                width: Some(100),
+                color: false
+            }
+        );
+        // Not a terminal: no color, whatever was asked for.
+        assert_eq!(Style::for_screen(None, true), Style::PLAIN);
+    }
+
+    #[test]
+    fn test_overflows() {
+        assert!(!overflows("one\ntwo\n", 3));
+        assert!(overflows("one\ntwo\nthree\n", 3));
+    }
+
+    #[test]
+    fn test_pager_command() {
+        let env = |pager: Option<&'static str>| {
+            move |name: &str| {
+                (name == "PAGER")
+                    .then_some(pager)
+                    .flatten()
+                    .map(str::to_string)
+            }
+        };
+        assert_eq!(pager_command(env(None)), Some("less".to_string()));
+        assert_eq!(
+            pager_command(env(Some("most -s"))),
+            Some("most -s".to_string())
+        );
+        assert_eq!(pager_command(env(Some(""))), None);
+        assert_eq!(pager_command(env(Some("cat"))), None);
+    }
+}
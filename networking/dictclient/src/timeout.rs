@@ -0,0 +1,196 @@
+use crate::error::DictError;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio::time::{sleep, Sleep};
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Carried inside the io::Error a timed out read or write returns, so the
+// conversion to DictError can report how long it waited.
+#[derive(Debug)]
+pub struct Elapsed(pub Duration);
+
+impl fmt::Display for Elapsed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for Elapsed {}
+
+// Opens a TCP connection to `host:port`, giving up after `limit`.
+pub async fn connect(host: &str, port: u16, limit: Duration) -> Result<TcpStream, DictError> {
+    match tokio::time::timeout(limit, TcpStream::connect((host, port))).await {
+        Ok(result) => result.map_err(|source| DictError::Connect {
+            host: host.to_string(),
+            port,
+            source,
+        }),
+        Err(_) => Err(DictError::Timeout(limit)),
+    }
+}
+
+// Wraps a stream so that any single read or write that makes no progress for
+// `limit` fails with Elapsed. The clock starts when an operation first has to
+// wait and is reset every time one completes, so a slow but steady server is
+// never cut off.
+pub struct Timed<S> {
+    inner: S,
+    limit: Duration,
+    read_deadline: Option<Pin<Box<Sleep>>>,
+    write_deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> Timed<S> {
+    pub fn new(inner: S, limit: Duration) -> Self {
+        Self {
+            inner,
+            limit,
+            read_deadline: None,
+            write_deadline: None,
+        }
+    }
+}
+
+// Turns Pending into a timeout error once the deadline for the waiting
+// operation has passed.
+fn poll_deadline<T>(
+    result: Poll<io::Result<T>>,
+    deadline: &mut Option<Pin<Box<Sleep>>>,
+    limit: Duration,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<T>> {
+    if result.is_ready() {
+        *deadline = None;
+        return result;
+    }
+    let timer = deadline.get_or_insert_with(|| Box::pin(sleep(limit)));
+    match timer.as_mut().poll(cx) {
+        Poll::Ready(()) => {
+            *deadline = None;
+            Poll::Ready(Err(io::Error::new(io::ErrorKind::TimedOut, Elapsed(limit))))
+        }
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Timed<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        poll_deadline(result, &mut this.read_deadline, this.limit, cx)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Timed<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_write(cx, buf);
+        poll_deadline(result, &mut this.write_deadline, this.limit, cx)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_flush(cx);
+        poll_deadline(result, &mut this.write_deadline, this.limit, cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol;
+    use std::time::Instant;
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    // A peer that accepts the connection and then never sends or accepts a
+    // byte.
+    struct Stalled;
+
+    impl AsyncRead for Stalled {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+    }
+
+    impl AsyncWrite for Stalled {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    const LIMIT: Duration = Duration::from_millis(50);
+
+    #[tokio::test]
+    async fn test_stalled_read_times_out() {
+        let mut reader = BufReader::new(Timed::new(Stalled, LIMIT));
+        let started = Instant::now();
+        let result = protocol::read_reply(&mut reader).await;
+        let waited = started.elapsed();
+        assert!(matches!(result, Err(DictError::Timeout(limit)) if limit == LIMIT));
+        assert!(
+            waited >= LIMIT && waited < LIMIT * 10,
+            "waited {:?}",
+            waited
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stalled_write_times_out() {
+        let mut writer = Timed::new(Stalled, LIMIT);
+        let result: Result<(), DictError> =
+            writer.write_all(b"QUIT\r\n").await.map_err(DictError::from);
+        assert!(matches!(result, Err(DictError::Timeout(limit)) if limit == LIMIT));
+    }
+
+    #[tokio::test]
+    async fn test_slow_but_steady_reads_succeed() {
+        let (client, mut server) = tokio::io::duplex(64);
+        let writer = tokio::spawn(async move {
+            for chunk in [&b"250 "[..], b"o", b"k\r\n"] {
+                tokio::time::sleep(LIMIT / 2).await;
+                server.write_all(chunk).await.unwrap();
+            }
+        });
+
+        let reply = protocol::read_reply(&mut BufReader::new(Timed::new(client, LIMIT)))
+            .await
+            .unwrap();
+        writer.await.unwrap();
+        assert_eq!((reply.code, reply.message.as_str()), (250, "ok"));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::error::DictError;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    self, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+
+// Where servers customarily offer DICT over TLS.
+pub const DEFAULT_TLS_PORT: u16 = 2629;
+
+// How to talk to the server once the TCP connection is up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Transport {
+    #[default]
+    Plain,
+    // TLS, checking the server's certificate against the bundled web PKI
+    // roots unless `insecure`, e.g. for a self-signed test server.
+    Tls {
+        insecure: bool,
+    },
+}
+
+// A connection to the server, plain or encrypted; everything above it reads
+// and writes the same way either way.
+pub enum DictStream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl AsyncRead for DictStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DictStream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            DictStream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for DictStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            DictStream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            DictStream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DictStream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            DictStream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            DictStream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            DictStream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
+
+// Runs the TLS handshake over an open socket to `host`. A certificate that
+// does not check out is a DictError::Tls naming `host`.
+pub async fn handshake(
+    socket: TcpStream,
+    host: &str,
+    insecure: bool,
+) -> Result<DictStream, DictError> {
+    let name = ServerName::try_from(host.to_string()).map_err(|e| DictError::Tls {
+        host: host.to_string(),
+        message: e.to_string(),
+    })?;
+    let connector = TlsConnector::from(Arc::new(client_config(insecure)));
+    match connector.connect(name, socket).await {
+        Ok(stream) => Ok(DictStream::Tls(Box::new(stream))),
+        Err(e) => Err(handshake_error(host, e)),
+    }
+}
+
+// rustls reports protocol and certificate failures inside an io::Error;
+// anything else is the network's fault.
+fn handshake_error(host: &str, e: io::Error) -> DictError {
+    match e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+    {
+        Some(cause) => DictError::Tls {
+            host: host.to_string(),
+            message: cause.to_string(),
+        },
+        None => DictError::from(e),
+    }
+}
+
+fn client_config(insecure: bool) -> ClientConfig {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions");
+    if insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth()
+    } else {
+        let roots = RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+        };
+        builder.with_root_certificates(roots).with_no_client_auth()
+    }
+}
+
+// --insecure: any certificate for any name is accepted, though the handshake
+// signatures are still checked so the session is at least encrypted.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::DictClient;
+    use std::time::Duration;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+    use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use tokio_rustls::rustls::ServerConfig;
+    use tokio_rustls::TlsAcceptor;
+
+    const LIMIT: Duration = Duration::from_secs(5);
+
+    // A DICT server on a local port speaking TLS with a fresh self-signed
+    // certificate for "localhost". It greets, then answers each expected
+    // command with its scripted reply.
+    async fn tls_mock_server(
+        script: &'static [(&'static str, &'static str)],
+    ) -> (u16, tokio::task::JoinHandle<io::Result<()>>) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let key =
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(vec![certified.cert.der().clone()], key)
+                .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await?;
+            let stream = acceptor.accept(socket).await?;
+            let (read_half, mut write_half) = tokio::io::split(stream);
+            let mut lines = BufReader::new(read_half).lines();
+            write_half
+                .write_all(b"220 tls.dict.example.org dictd <mime> <7.1@tls.dict.example.org>\r\n")
+                .await?;
+            write_half.flush().await?;
+            if let Some(hello) = lines.next_line().await? {
+                assert!(hello.starts_with("CLIENT rust-dictclient/"), "{}", hello);
+                write_half.write_all(b"250 ok\r\n").await?;
+                write_half.flush().await?;
+            }
+            for (expected, reply) in script {
+                let command = lines.next_line().await?.expect("client hung up early");
+                assert_eq!(command, *expected);
+                write_half.write_all(reply.as_bytes()).await?;
+                write_half.flush().await?;
+            }
+            Ok(())
+        });
+        (port, server)
+    }
+
+    #[tokio::test]
+    async fn test_define_over_tls() {
+        let (port, server) = tls_mock_server(&[
+            (
+                "DEFINE wn gold",
+                "150 1 definitions retrieved\r\n151 \"gold\" wn \"WordNet\"\r\ngold\r\n  n 1: a soft metal\r\n.\r\n250 ok\r\n",
+            ),
+            ("QUIT", "221 bye\r\n"),
+        ])
+        .await;
+
+        let transport = Transport::Tls { insecure: true };
+        let mut client = DictClient::connect_over("localhost", port, LIMIT, transport)
+            .await
+            .unwrap();
+        assert!(client.banner().starts_with("220 tls.dict.example.org"));
+        let definitions = client.define("wn", "gold").await.unwrap();
+        assert_eq!(definitions[0].body, "gold\n  n 1: a soft metal");
+        client.quit().await.unwrap();
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_certificate_names_the_host() {
+        let (port, server) = tls_mock_server(&[]).await;
+
+        let transport = Transport::Tls { insecure: false };
+        let result = DictClient::connect_over("localhost", port, LIMIT, transport).await;
+        match result {
+            Err(DictError::Tls { host, message }) => {
+                assert_eq!(host, "localhost");
+                assert!(message.contains("certificate"), "{}", message);
+            }
+            Err(other) => panic!("expected a TLS error, got {}", other),
+            Ok(_) => panic!("a self-signed certificate was trusted"),
+        }
+        // The server sees the client abort the handshake.
+        assert!(server.await.unwrap().is_err());
+    }
+}